@@ -0,0 +1,107 @@
+//! Benchmarks of the encoding pipeline: the whole encoder for small, medium
+//! and huge payloads, the numeric fast path, mask selection, and error
+//! correction generation.
+//!
+//! Run with `cargo bench --bench encode`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use qrcode::bits::Bits;
+use qrcode::canvas::Canvas;
+use qrcode::ec::{self, Codewords};
+use qrcode::spec::{EcLevelH, EcLevelL, EcLevelM, QrSpec, Version1, Version10, Version20, Version40};
+use qrcode::{Encoder, QrCode};
+
+/// A payload mixing digits, upper case letters and binary data, so that the
+/// optimizer has segments to merge.
+fn payload(len: usize) -> Vec<u8> {
+    b"0123456789ABCDEFGHIJ0123456789hello, world!\x00\xff".iter().copied().cycle().take(len).collect()
+}
+
+fn raw_bits<V: QrSpec>(data: &[u8]) -> heapless::Vec<u8, V::BitsSize> {
+    let mut bits = Bits::<V>::new();
+    bits.push_optimal_data(data).unwrap();
+    bits.push_terminator().unwrap();
+    bits.into_bytes()
+}
+
+fn bench_encode_version<V: QrSpec>(c: &mut Criterion, name: &str, len: usize) {
+    let data = payload(len);
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_with_input(BenchmarkId::new(name, len), &data, |b, data| {
+        b.iter(|| QrCode::<V>::new(black_box(data)).unwrap())
+    });
+    let mut encoder = Encoder::<V>::new();
+    group.bench_with_input(BenchmarkId::new(format!("{}/encoder", name), len), &data, |b, data| {
+        b.iter(|| encoder.encode(black_box(data)).unwrap().max_allowed_errors())
+    });
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    bench_encode_version::<Version1<EcLevelM>>(c, "1-M", 10);
+    bench_encode_version::<Version10<EcLevelM>>(c, "10-M", 200);
+    bench_encode_version::<Version40<EcLevelL>>(c, "40-L", 2000);
+}
+
+/// Compares `QrCode::new` with the `QrCode::new_numeric` fast path, for the
+/// bits alone and for the whole encoder.
+fn bench_numeric_version<V: QrSpec>(c: &mut Criterion, name: &str, len: usize) {
+    let digits: Vec<u8> = b"0123456789".iter().copied().cycle().take(len).collect();
+    let mut group = c.benchmark_group(format!("numeric/{}", name));
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("optimal_bits", |b| {
+        b.iter(|| {
+            let mut bits = Bits::<V>::new();
+            bits.push_optimal_data(black_box(&digits)).unwrap();
+            bits
+        })
+    });
+    group.bench_function("numeric_bits", |b| {
+        b.iter(|| {
+            let mut bits = Bits::<V>::new();
+            bits.push_numeric_data(black_box(&digits)).unwrap();
+            bits
+        })
+    });
+    group.bench_function("new", |b| b.iter(|| QrCode::<V>::new(black_box(&digits)).unwrap()));
+    group.bench_function("new_numeric", |b| b.iter(|| QrCode::<V>::new_numeric(black_box(&digits)).unwrap()));
+    group.finish();
+}
+
+fn bench_numeric(c: &mut Criterion) {
+    bench_numeric_version::<Version1<EcLevelM>>(c, "1-M", 20);
+    bench_numeric_version::<Version10<EcLevelM>>(c, "10-M", 400);
+}
+
+fn bench_mask_version<V: QrSpec>(c: &mut Criterion, name: &str) {
+    let rawbits = raw_bits::<V>(&payload(Codewords::<V>::data_len() / 2));
+    let mut canvas = Canvas::<V>::new();
+    canvas.draw_all_functional_patterns();
+    canvas.draw_codewords_iter(Codewords::new(&rawbits).unwrap());
+    c.bench_function(&format!("apply_best_mask/{}", name), |b| b.iter(|| black_box(&canvas).apply_best_mask()));
+}
+
+fn bench_mask(c: &mut Criterion) {
+    bench_mask_version::<Version1<EcLevelM>>(c, "1-M");
+    bench_mask_version::<Version10<EcLevelM>>(c, "10-M");
+    bench_mask_version::<Version40<EcLevelL>>(c, "40-L");
+}
+
+fn bench_ec_version<V: QrSpec>(c: &mut Criterion, name: &str) {
+    let rawbits = raw_bits::<V>(&payload(Codewords::<V>::data_len() / 2));
+    c.bench_function(&format!("construct_codewords/{}", name), |b| {
+        b.iter(|| ec::construct_codewords::<V>(black_box(&rawbits)).unwrap())
+    });
+}
+
+fn bench_ec(c: &mut Criterion) {
+    bench_ec_version::<Version10<EcLevelM>>(c, "10-M");
+    bench_ec_version::<Version20<EcLevelH>>(c, "20-H");
+    bench_ec_version::<Version40<EcLevelL>>(c, "40-L");
+}
+
+criterion_group!(benches, bench_encode, bench_numeric, bench_mask, bench_ec);
+criterion_main!(benches);
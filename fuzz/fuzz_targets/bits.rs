@@ -0,0 +1,48 @@
+//! Pushes arbitrary sequences of segments, which must fail cleanly if they do
+//! not fit or contain invalid characters.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use qrcode::bits::Bits;
+use qrcode::spec::*;
+use qrcode::QrCode;
+
+#[derive(Arbitrary, Debug)]
+enum Push<'a> {
+    Numeric(&'a [u8]),
+    Alphanumeric(&'a [u8]),
+    Byte(&'a [u8]),
+    Kanji(&'a [u8]),
+    Eci(u32),
+    Fnc1First,
+    Fnc1Second(u8),
+    Terminator,
+}
+
+fn push_all<V: QrSpec>(pushes: &[Push]) {
+    let mut bits = Bits::<V>::new();
+    for push in pushes {
+        let _ = match *push {
+            Push::Numeric(data) => bits.push_numeric_data(data),
+            Push::Alphanumeric(data) => bits.push_alphanumeric_data(data),
+            Push::Byte(data) => bits.push_byte_data(data),
+            Push::Kanji(data) => bits.push_kanji_data(data),
+            Push::Eci(designator) => bits.push_eci_designator(designator),
+            Push::Fnc1First => bits.push_fnc1_first_position(),
+            Push::Fnc1Second(indicator) => bits.push_fnc1_second_position(indicator),
+            Push::Terminator => bits.push_terminator(),
+        };
+    }
+    let _ = QrCode::with_bits(bits);
+}
+
+fuzz_target!(|input: (bool, Vec<Push>)| {
+    let (large, pushes) = input;
+    if large {
+        push_all::<Version40<EcLevelL>>(&pushes);
+    } else {
+        push_all::<Version1<EcLevelH>>(&pushes);
+    }
+});
@@ -0,0 +1,22 @@
+//! Decodes arbitrary module matrices, which must fail cleanly if they are not
+//! valid QR codes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qrcode::decode::{decode, decode_with_segments};
+use qrcode::types::Color;
+
+fuzz_target!(|input: (u8, u16, &[u8])| {
+    let (version, out_len, bits) = input;
+    // Mostly valid widths, but also the ones just around them.
+    let width = 17 + 4 * usize::from(version % 41) + usize::from(version / 41 % 3) / 2;
+    let modules: Vec<Color> = (0..width * width)
+        .map(|i| bits.get(i / 8).map_or(Color::Light, |byte| Color::from_bit(byte >> (7 - i % 8))))
+        .collect();
+
+    let mut out = vec![0; usize::from(out_len)];
+    let _ = decode(&modules, &mut out);
+    let _ = decode(&modules[..modules.len() / 2], &mut out);
+    let _ = decode_with_segments(width, |x, y| modules[y * width + x], &mut out, |_| {});
+});
@@ -0,0 +1,29 @@
+//! Checks that the optimized segments cover the data in order, and are never
+//! longer than the ones from the parser.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qrcode::optimize::{total_encoded_len, Parser, Segment};
+use qrcode::types::Version;
+
+fuzz_target!(|input: (u8, &[u8])| {
+    let (version, data) = input;
+    let version = match version % 41 {
+        0 => Version::Micro(i16::from(version % 4) + 1),
+        v => Version::Normal(i16::from(v)),
+    };
+
+    let parsed: Vec<Segment> = Parser::new(data).collect();
+    let optimized: Vec<Segment> = Parser::new(data).optimize(version).collect();
+    for segments in [&parsed, &optimized] {
+        let mut end = 0;
+        for segment in segments.iter() {
+            assert_eq!(segment.begin, end);
+            assert!(segment.end > segment.begin);
+            end = segment.end;
+        }
+        assert_eq!(end, data.len());
+    }
+    assert!(total_encoded_len(&optimized, version) <= total_encoded_len(&parsed, version));
+});
@@ -0,0 +1,34 @@
+//! Encodes arbitrary data with the optimal segments, and decodes it back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qrcode::bits::Bits;
+use qrcode::decode::decode;
+use qrcode::spec::*;
+use qrcode::types::Color;
+use qrcode::QrCode;
+
+fn round_trip<V: QrSpec>(data: &[u8]) {
+    let mut bits = Bits::<V>::new();
+    if bits.push_optimal_data(data).is_err() || bits.push_terminator().is_err() {
+        return;
+    }
+    let code = QrCode::with_bits(bits).unwrap();
+    let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+    let mut out = vec![0; data.len()];
+    let decoded = decode(&modules, &mut out).unwrap();
+    assert_eq!(&out[..decoded.len], data);
+}
+
+fuzz_target!(|input: (u8, &[u8])| {
+    let (spec, data) = input;
+    match spec % 6 {
+        0 => round_trip::<Version1<EcLevelL>>(data),
+        1 => round_trip::<Version2<EcLevelH>>(data),
+        2 => round_trip::<Version9<EcLevelM>>(data),
+        3 => round_trip::<Version10<EcLevelQ>>(data),
+        4 => round_trip::<Version27<EcLevelL>>(data),
+        _ => round_trip::<Version40<EcLevelH>>(data),
+    }
+});
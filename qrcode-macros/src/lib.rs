@@ -0,0 +1,142 @@
+//! Compile-time QR code encoding for the `qrcode` crate.
+//!
+//! The `qr!` macro encodes a literal while compiling and expands to a
+//! `qrcode::PackedQrCode` holding the finished modules, so fixed codes cost
+//! nothing at run time.
+//!
+//! ```
+//! use qrcode::{EcLevel, PackedQrCode, Version};
+//! use qrcode_macros::qr;
+//!
+//! static SUPPORT_URL: PackedQrCode<'static> = qr!("https://example.com/support", Q);
+//!
+//! assert_eq!(SUPPORT_URL.version(), Version::Normal(3));
+//! assert_eq!(SUPPORT_URL.ec_level(), EcLevel::Q);
+//! assert_eq!(SUPPORT_URL.colors().count(), 29 * 29);
+//! ```
+//!
+//! Both crates must be dependencies: the expansion refers to `qrcode` types.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, Lit, Token};
+
+use qrcode::spec::*;
+use qrcode::{EcLevel, QrCode, QrError, Version};
+
+/// The arguments of `qr!`: a string or byte string literal, optionally
+/// followed by an error correction level.
+struct QrInput {
+    data: Vec<u8>,
+    span: Span,
+    ec_level: EcLevel,
+}
+
+impl Parse for QrInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit: Lit = input.parse()?;
+        let data = match &lit {
+            Lit::Str(s) => s.value().into_bytes(),
+            Lit::ByteStr(s) => s.value(),
+            _ => return Err(syn::Error::new(lit.span(), "expected a string or byte string literal")),
+        };
+
+        let mut ec_level = EcLevel::M;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            ec_level = ident
+                .to_string()
+                .parse()
+                .map_err(|_| syn::Error::new(ident.span(), "expected an error correction level: L, M, Q or H"))?;
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        Ok(Self { data, span: lit.span(), ec_level })
+    }
+}
+
+/// A symbol encoded by the macro.
+struct Encoded {
+    version: i16,
+    modules: Vec<u8>,
+}
+
+fn pack<V: QrSpec>(code: QrCode<V>) -> Encoded {
+    let mut bytes = vec![0; QrCode::<V>::binary_len()];
+    code.to_bytes(&mut bytes).expect("buffer of binary_len");
+    let version = match V::VERSION {
+        Version::Normal(v) | Version::Micro(v) => v,
+    };
+    // Drop the header, the expansion stores the version and level itself.
+    Encoded { version, modules: bytes.split_off(3) }
+}
+
+fn try_encode<V: QrSpec>(data: &[u8]) -> Result<Encoded, QrError> {
+    QrCode::<V>::new(data).map(pack)
+}
+
+/// Encodes the data in the smallest version that fits.
+fn encode(data: &[u8], ec_level: EcLevel) -> Result<Encoded, QrError> {
+    type Attempt = fn(&[u8]) -> Result<Encoded, QrError>;
+    macro_rules! all_versions {
+        ($level:ident) => {
+            all_versions!($level;
+                Version1 Version2 Version3 Version4 Version5 Version6 Version7 Version8 Version9 Version10
+                Version11 Version12 Version13 Version14 Version15 Version16 Version17 Version18 Version19 Version20
+                Version21 Version22 Version23 Version24 Version25 Version26 Version27 Version28 Version29 Version30
+                Version31 Version32 Version33 Version34 Version35 Version36 Version37 Version38 Version39 Version40)
+        };
+        ($level:ident; $($version:ident)*) => {
+            [$(try_encode::<$version<$level>> as Attempt),*]
+        };
+    }
+
+    let attempts = match ec_level {
+        EcLevel::L => all_versions!(EcLevelL),
+        EcLevel::M => all_versions!(EcLevelM),
+        EcLevel::Q => all_versions!(EcLevelQ),
+        EcLevel::H => all_versions!(EcLevelH),
+    };
+    let mut result = Err(QrError::UnsupportedCharacterSet);
+    for attempt in &attempts {
+        result = attempt(data);
+        if !matches!(result, Err(QrError::DataTooLong { .. })) {
+            break;
+        }
+    }
+    result
+}
+
+/// Encodes a string or byte string literal into a QR code at compile time.
+///
+/// Expands to a `qrcode::PackedQrCode<'static>` of the smallest version that
+/// fits the data, usable in `const` and `static` items. The error correction
+/// level defaults to M, and can be given after the data:
+///
+/// ```
+/// # use qrcode::PackedQrCode;
+/// # use qrcode_macros::qr;
+/// static DEVICE_CLASS: PackedQrCode<'static> = qr!(b"CLASS-7F", H);
+/// ```
+///
+/// Data which does not fit any version is a compile error.
+#[proc_macro]
+pub fn qr(input: TokenStream) -> TokenStream {
+    let QrInput { data, span, ec_level } = parse_macro_input!(input as QrInput);
+    let encoded = match encode(&data, ec_level) {
+        Ok(encoded) => encoded,
+        Err(e) => return syn::Error::new(span, e).to_compile_error().into(),
+    };
+
+    let version = encoded.version;
+    let ec_level = Ident::new(&format!("{:?}", ec_level), Span::call_site());
+    let modules = encoded.modules;
+    let expanded = quote! {
+        ::qrcode::PackedQrCode::new(::qrcode::Version::Normal(#version), ::qrcode::EcLevel::#ec_level, &[#(#modules),*])
+    };
+    expanded.into()
+}
@@ -0,0 +1,22 @@
+use qrcode::spec::{EcLevelH, EcLevelM, Version1, Version3};
+use qrcode::{EcLevel, PackedQrCode, QrCode, Version};
+use qrcode_macros::qr;
+
+static NUMERIC: PackedQrCode<'static> = qr!("01234567");
+const URL: PackedQrCode<'static> = qr!(b"https://example.com/", H,);
+
+#[test]
+fn test_default_level() {
+    assert_eq!(NUMERIC.version(), Version::Normal(1));
+    assert_eq!(NUMERIC.ec_level(), EcLevel::M);
+    let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    assert!(NUMERIC.colors().eq(code.colors()));
+}
+
+#[test]
+fn test_explicit_level() {
+    assert_eq!(URL.version(), Version::Normal(3));
+    assert_eq!(URL.ec_level(), EcLevel::H);
+    let code = QrCode::<Version3<EcLevelH>>::new(b"https://example.com/").unwrap();
+    assert!(URL.colors().eq(code.colors()));
+}
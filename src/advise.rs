@@ -0,0 +1,341 @@
+//! The `advise` module suggests changes to a payload which keep its meaning
+//! but let it encode into fewer bits, and so maybe into a smaller version.
+//!
+//!     use qrcode::advise::{advise, Transformation};
+//!     use qrcode::{EcLevel, Version};
+//!
+//!     let advice = advise(b"https://example.com:443/", EcLevel::M);
+//!     assert_eq!(advice.current.version, Some(Version::Normal(2)));
+//!     assert_eq!(advice.combined.version, Some(Version::Normal(1)));
+//!
+//!     let best = advice.suggestions.iter().max_by_key(|s| s.saved_bits).unwrap();
+//!     assert_eq!(best.transformation, Transformation::UppercaseSchemeAndHost);
+//!
+//!     let mut url = *b"https://example.com:443/";
+//!     let len = Transformation::StripDefaultPort.apply(&mut url);
+//!     assert_eq!(&url[..len], b"https://example.com/");
+//!
+//! The transformations only apply to URLs, and only change what RFC 3986
+//! declares equivalent: the case of the scheme, the host and percent-encoded
+//! octets, the default port and an empty path. Some servers still treat the
+//! changed URL differently, so a UI should let users choose.
+
+use core::ops::Range;
+
+use heapless::consts::U4;
+use heapless::Vec;
+
+use crate::bits::DATA_LENGTHS;
+use crate::optimize::Parser;
+use crate::types::{EcLevel, Version};
+
+//------------------------------------------------------------------------------
+//{{{ Transformation
+
+/// A change to a URL which keeps its meaning.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Transformation {
+    /// Removes the port if it is the default of the scheme, 80 for `http` and
+    /// 443 for `https`.
+    StripDefaultPort,
+
+    /// Removes the path if it is a single `/`, with nothing after it.
+    StripEmptyPath,
+
+    /// Writes the scheme and the host in upper case, so they fit into the
+    /// alphanumeric mode.
+    UppercaseSchemeAndHost,
+
+    /// Writes the hexadecimal digits of percent-encoded octets in upper case.
+    UppercasePercentEscapes,
+}
+
+/// All transformations, in the order `advise` applies them.
+const TRANSFORMATIONS: [Transformation; 4] = [
+    Transformation::StripDefaultPort,
+    Transformation::StripEmptyPath,
+    Transformation::UppercaseSchemeAndHost,
+    Transformation::UppercasePercentEscapes,
+];
+
+/// The parts of a URL, as ranges of its bytes.
+struct Url {
+    scheme: Range<usize>,
+    host: Range<usize>,
+    /// The port, including the `:`, if any.
+    port: Option<Range<usize>>,
+    path: Range<usize>,
+}
+
+impl Url {
+    /// Splits `data` into the parts of a URL, if it starts with a scheme and
+    /// `://`.
+    fn parse(data: &[u8]) -> Option<Url> {
+        let scheme_end = data.iter().position(|&b| b == b':')?;
+        let scheme = &data[..scheme_end];
+        let valid_scheme = scheme.first().is_some_and(u8::is_ascii_alphabetic)
+            && scheme.iter().all(|&b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+        if !valid_scheme || !data[scheme_end..].starts_with(b"://") {
+            return None;
+        }
+
+        let authority_start = scheme_end + 3;
+        let end_of = |start: usize, delimiters: &[u8]| {
+            data[start..].iter().position(|b| delimiters.contains(b)).map_or(data.len(), |i| start + i)
+        };
+        let authority_end = end_of(authority_start, b"/?#");
+        let authority = &data[authority_start..authority_end];
+        let host_start =
+            authority.iter().rposition(|&b| b == b'@').map_or(authority_start, |i| authority_start + i + 1);
+
+        // A `:` inside the brackets of an IPv6 address is not a port.
+        let bracket =
+            data[host_start..authority_end].iter().rposition(|&b| b == b']').map_or(host_start, |i| host_start + i);
+        let colon = data[bracket..authority_end].iter().rposition(|&b| b == b':').map(|i| bracket + i);
+        let port =
+            colon.filter(|&i| data[i + 1..authority_end].iter().all(u8::is_ascii_digit)).map(|i| i..authority_end);
+        let host_end = port.as_ref().map_or(authority_end, |port| port.start);
+
+        Some(Url {
+            scheme: 0..scheme_end,
+            host: host_start..host_end,
+            port,
+            path: authority_end..end_of(authority_end, b"?#"),
+        })
+    }
+}
+
+/// Removes `range` from `data` by moving the bytes after it forward,
+/// returning the length left.
+fn remove(data: &mut [u8], range: Range<usize>) -> usize {
+    let removed = range.len();
+    data.copy_within(range.end.., range.start);
+    data.len() - removed
+}
+
+impl Transformation {
+    /// Applies the transformation to `data` in place, returning its new
+    /// length. Data which is not a URL, or which the transformation does not
+    /// change, is left as it is.
+    pub fn apply(self, data: &mut [u8]) -> usize {
+        let url = match Url::parse(data) {
+            Some(url) => url,
+            None => return data.len(),
+        };
+        match self {
+            Transformation::StripDefaultPort => {
+                let scheme = &data[url.scheme];
+                let default: &[u8] = match () {
+                    () if scheme.eq_ignore_ascii_case(b"http") => b":80",
+                    () if scheme.eq_ignore_ascii_case(b"https") => b":443",
+                    () => return data.len(),
+                };
+                match url.port {
+                    Some(port) if &data[port.clone()] == default => remove(data, port),
+                    _ => data.len(),
+                }
+            }
+            Transformation::StripEmptyPath => {
+                if &data[url.path.clone()] == b"/" && url.path.end == data.len() {
+                    remove(data, url.path)
+                } else {
+                    data.len()
+                }
+            }
+            Transformation::UppercasePercentEscapes => {
+                let mut i = url.host.start;
+                while let Some(escape) = data.get_mut(i..i + 3) {
+                    if escape[0] == b'%' && escape[1].is_ascii_hexdigit() && escape[2].is_ascii_hexdigit() {
+                        escape.make_ascii_uppercase();
+                        i += 3;
+                    } else {
+                        i += 1;
+                    }
+                }
+                data.len()
+            }
+            Transformation::UppercaseSchemeAndHost => {
+                data[url.scheme].make_ascii_uppercase();
+                data[url.host].make_ascii_uppercase();
+                data.len()
+            }
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Advice
+
+/// The size of a payload when encoded optimally.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Estimate {
+    /// The number of bits of the segments, in `version`, or in version 40 if
+    /// the payload fits into none.
+    pub bits: usize,
+
+    /// The smallest version the payload fits into, if any.
+    pub version: Option<Version>,
+}
+
+/// A transformation which makes a payload smaller.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Suggestion {
+    /// The transformation.
+    pub transformation: Transformation,
+
+    /// The size of the payload with this and the previous suggestions
+    /// applied.
+    pub estimate: Estimate,
+
+    /// The number of bits saved over the previous suggestions, counted in the
+    /// version of the current estimate.
+    pub saved_bits: usize,
+}
+
+/// The suggestions for a payload. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Advice {
+    /// The size of the payload as it is.
+    pub current: Estimate,
+
+    /// The transformations which make the payload smaller, in the order they
+    /// should be applied.
+    pub suggestions: Vec<Suggestion, U4>,
+
+    /// The size of the payload with all suggestions applied.
+    pub combined: Estimate,
+}
+
+/// The longest payload which is analyzed: 7089 digits fit into version 40-L.
+const MAX_LEN: usize = 7089;
+
+/// The number of bits of `data` encoded optimally in `version`.
+fn encoded_len(data: &[u8], version: Version) -> usize {
+    Parser::new(data).optimize(version).map(|segment| segment.encoded_len(version)).sum()
+}
+
+fn estimate(data: &[u8], ec_level: EcLevel) -> Estimate {
+    for v in 1..=40 {
+        let version = Version::Normal(v);
+        let bits = encoded_len(data, version);
+        if version.fetch(ec_level, &DATA_LENGTHS).is_ok_and(|capacity| bits <= capacity) {
+            return Estimate { bits, version: Some(version) };
+        }
+    }
+    Estimate { bits: encoded_len(data, Version::Normal(40)), version: None }
+}
+
+/// Analyzes `data`, suggesting transformations which make it encode into
+/// fewer bits at the error correction level `ec_level`.
+///
+/// Every transformation is tried on top of the ones suggested before it, as
+/// some only pay off together: upper case percent escapes save nothing in a
+/// lower case URL, as the optimizer would not switch modes for them.
+///
+/// Data longer than any QR code can hold gets no suggestions.
+pub fn advise(data: &[u8], ec_level: EcLevel) -> Advice {
+    let current = estimate(data, ec_level);
+    let mut advice = Advice { current, suggestions: Vec::new(), combined: current };
+    if data.len() > MAX_LEN || Url::parse(data).is_none() {
+        return advice;
+    }
+
+    let version = current.version.unwrap_or(Version::Normal(40));
+    let mut bits = encoded_len(data, version);
+    let mut buffer = [0; MAX_LEN];
+    for &transformation in &TRANSFORMATIONS {
+        // Apply the suggestions so far again, instead of keeping a second
+        // buffer for the transformed data.
+        let changed = &mut buffer[..data.len()];
+        changed.copy_from_slice(data);
+        let mut len = data.len();
+        for suggestion in advice.suggestions.iter() {
+            len = suggestion.transformation.apply(&mut changed[..len]);
+        }
+        len = transformation.apply(&mut changed[..len]);
+
+        let new_bits = encoded_len(&changed[..len], version);
+        if new_bits < bits {
+            let estimate = estimate(&changed[..len], ec_level);
+            // There are as many transformations as the vector holds.
+            let _ = advice.suggestions.push(Suggestion { transformation, estimate, saved_bits: bits - new_bits });
+            advice.combined = estimate;
+            bits = new_bits;
+        }
+    }
+    advice
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Tests
+
+#[cfg(test)]
+mod advise_tests {
+    use crate::advise::{advise, Estimate, Transformation};
+    use crate::types::{EcLevel, Version};
+
+    fn apply(transformation: Transformation, url: &str) -> String {
+        let mut data = url.as_bytes().to_vec();
+        let len = transformation.apply(&mut data);
+        String::from_utf8(data[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_transformations() {
+        use Transformation::*;
+        assert_eq!(apply(StripDefaultPort, "http://a.example:80/x"), "http://a.example/x");
+        assert_eq!(apply(StripDefaultPort, "HTTPS://user:pw@a.example:443"), "HTTPS://user:pw@a.example");
+        assert_eq!(apply(StripDefaultPort, "https://a.example:80/"), "https://a.example:80/");
+        assert_eq!(apply(StripDefaultPort, "http://[::80]/"), "http://[::80]/");
+        assert_eq!(apply(StripDefaultPort, "http://[::1]:80/"), "http://[::1]/");
+        assert_eq!(apply(StripEmptyPath, "https://a.example/"), "https://a.example");
+        assert_eq!(apply(StripEmptyPath, "https://a.example/?q"), "https://a.example/?q");
+        assert_eq!(
+            apply(UppercasePercentEscapes, "https://a.example/%e2%82%ac?x=%zz%4"),
+            "https://a.example/%E2%82%AC?x=%zz%4"
+        );
+        assert_eq!(apply(UppercaseSchemeAndHost, "https://me@a.example:8080/path"), "HTTPS://me@A.EXAMPLE:8080/path");
+        assert_eq!(apply(UppercaseSchemeAndHost, "not a url"), "not a url");
+        assert_eq!(apply(UppercaseSchemeAndHost, "mailto:a@example.com"), "mailto:a@example.com");
+    }
+
+    #[test]
+    fn test_advise() {
+        let advice = advise(b"https://example.com:443/", EcLevel::M);
+        assert_eq!(advice.current, Estimate { bits: 4 + 8 + 24 * 8, version: Some(Version::Normal(2)) });
+        let suggested = advice.suggestions.iter().map(|s| (s.transformation, s.saved_bits)).collect::<Vec<_>>();
+        assert_eq!(
+            suggested,
+            [
+                (Transformation::StripDefaultPort, 32),
+                (Transformation::StripEmptyPath, 8),
+                (Transformation::UppercaseSchemeAndHost, (4 + 8 + 19 * 8) - (4 + 9 + 9 * 11 + 6))
+            ]
+        );
+        // "HTTPS://EXAMPLE.COM" is alphanumeric.
+        assert_eq!(advice.combined, Estimate { bits: 4 + 9 + 9 * 11 + 6, version: Some(Version::Normal(1)) });
+
+        // Upper case escapes only pay off in an upper case URL.
+        let advice = advise(b"https://a.example/%e2%82%ac%e2%82%ac", EcLevel::L);
+        let suggested = advice.suggestions.iter().map(|s| (s.transformation, s.saved_bits)).collect::<Vec<_>>();
+        assert_eq!(
+            suggested,
+            [(Transformation::UppercaseSchemeAndHost, 34), (Transformation::UppercasePercentEscapes, 55)]
+        );
+        assert_eq!(advice.combined, Estimate { bits: 4 + 9 + 18 * 11, version: Some(Version::Normal(2)) });
+    }
+
+    #[test]
+    fn test_no_advice() {
+        for data in [&b"HTTPS://EXAMPLE.COM"[..], b"hello world", b"", &[b'1'; 8000]] {
+            let advice = advise(data, EcLevel::H);
+            assert!(advice.suggestions.is_empty());
+            assert_eq!(advice.combined, advice.current);
+        }
+        assert_eq!(advise(&[b'1'; 8000], EcLevel::L).current.version, None);
+    }
+}
+
+//}}}
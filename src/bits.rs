@@ -1,30 +1,72 @@
 //! The `bits` module encodes binary data into raw bits used in a QR code.
 
+// The tests group binary literals by field rather than by nibble.
+#![allow(clippy::unusual_byte_groupings)]
+
 use core::cmp::min;
 
 #[cfg(feature = "bench")]
 use test::{black_box, Bencher};
 
 use crate::cast::{As, Truncate};
-use crate::optimize::{Parser, Segment};
+use crate::jis;
+use crate::optimize::{Parser, Segment, StreamParser};
 use crate::spec::QrSpec;
+use crate::storage::Buffer;
 use crate::types::{EcLevel, Mode, QrError, QrResult, Version};
+use crate::QrCode;
 
 use heapless::Vec;
+use typenum::Unsigned;
 
 //------------------------------------------------------------------------------
 //{{{ Bits
 
 /// The `Bits` structure stores the encoded data for a QR code.
 pub struct Bits<V: QrSpec> {
-    data: Vec<u8, V::BitsSize>,
+    data: Buffer<V::BitsSize>,
     bit_offset: usize,
+    /// The position of the character count field of the last byte segment,
+    /// so that more bytes can be appended to it.
+    byte_segment: Option<usize>,
+}
+
+/// The largest number of `n` bits, for `n` up to 16. Unlike `(1 << n) - 1`,
+/// this does not overflow for 16 bits where `usize` has 16 bits, such as on
+/// AVR.
+pub(crate) const fn max_number(n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        (u16::MAX >> (16 - n)) as usize
+    }
 }
 
 impl<V: QrSpec> Bits<V> {
     /// Constructs a new, empty bits structure.
-    pub fn new() -> Self {
-        Self { data: Vec::new(), bit_offset: 0 }
+    pub const fn new() -> Self {
+        Self { data: Buffer::new(), bit_offset: 0, byte_segment: None }
+    }
+
+    /// Removes all bits, keeping the buffer in place, so one `Bits` can be
+    /// reused for many payloads.
+    ///
+    ///     use qrcode::Encoder;
+    ///     use qrcode::bits::Bits;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let mut encoder = Encoder::<Version1<EcLevelM>>::new();
+    ///     let mut bits = Bits::new();
+    ///     for otp in [b"123456", b"654321"] {
+    ///         bits.clear();
+    ///         bits.push_numeric_data(otp).unwrap();
+    ///         bits.push_terminator().unwrap();
+    ///         let code = encoder.encode_bits(&bits).unwrap();
+    ///     }
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit_offset = 0;
+        self.byte_segment = None;
     }
 
     /// Pushes an N-bit big-endian integer to the end of the bits.
@@ -35,49 +77,83 @@ impl<V: QrSpec> Bits<V> {
     fn push_number(&mut self, n: usize, number: u16) -> QrResult<()> {
         debug_assert!(n == 16 || n < 16 && number < (1 << n), "{} is too big as a {}-bit number", number, n);
 
+        let len = self.len();
         let b = self.bit_offset + n;
         let last_index = self.data.len().wrapping_sub(1);
         match (self.bit_offset, b) {
             (0, 0..=8) => {
-                self.data.push((number << (8 - b)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
+                self.data.push((number << (8 - b)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
             }
             (0, _) => {
-                self.data.push((number >> (b - 8)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
-                self.data.push((number << (16 - b)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
+                self.data.push((number >> (b - 8)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
+                self.data.push((number << (16 - b)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
             }
             (_, 0..=8) => {
                 self.data[last_index] |= (number << (8 - b)).truncate_as_u8();
             }
             (_, 9..=16) => {
                 self.data[last_index] |= (number >> (b - 8)).truncate_as_u8();
-                self.data.push((number << (16 - b)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
+                self.data.push((number << (16 - b)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
             }
             _ => {
                 self.data[last_index] |= (number >> (b - 8)).truncate_as_u8();
-                self.data.push((number >> (b - 16)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
-                self.data.push((number << (24 - b)).truncate_as_u8()).map_err(|_| QrError::DataTooLong)?;
+                self.data.push((number >> (b - 16)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
+                self.data.push((number << (24 - b)).truncate_as_u8()).map_err(|_| self.too_long(len + n))?;
             }
         }
         self.bit_offset = b & 7;
         Ok(())
     }
 
+    /// Reads the N-bit big-endian integer starting at bit `position`, which
+    /// must already have been pushed.
+    fn get_number(&self, position: usize, n: usize) -> usize {
+        (position..position + n)
+            .fold(0, |number, bit| number << 1 | usize::from(self.data[bit / 8] >> (7 - bit % 8) & 1))
+    }
+
+    /// Overwrites the N-bit big-endian integer starting at bit `position`,
+    /// which must already have been pushed.
+    fn set_number(&mut self, position: usize, n: usize, number: usize) {
+        for i in 0..n {
+            let bit = position + i;
+            let mask = 0x80 >> (bit % 8);
+            if number >> (n - 1 - i) & 1 == 0 {
+                self.data[bit / 8] &= !mask;
+            } else {
+                self.data[bit / 8] |= mask;
+            }
+        }
+    }
+
     /// Pushes an N-bit big-endian integer to the end of the bits, and check
     /// that the number does not overflow the bits.
     ///
-    /// Returns `Err(QrError::DataTooLong)` on overflow.
+    /// Returns `Err(QrError::DataTooLong { .. })` on overflow.
     fn push_number_checked(&mut self, n: usize, number: usize) -> QrResult<()> {
-        if n > 16 || number >= (1 << n) {
-            Err(QrError::DataTooLong)
+        if n > 16 || number > max_number(n) {
+            Err(self.too_long(self.len() + n))
         } else {
             self.push_number(n, number.as_u16())?;
             Ok(())
         }
     }
 
+    /// The error for data needing `needed` bits in total, which is more than
+    /// the QR code can hold.
+    fn too_long(&self, needed: usize) -> QrError {
+        let available = self.max_len(V::EC_LEVEL).unwrap_or(V::BitsSize::USIZE * 8);
+        QrError::DataTooLong { needed, available }
+    }
+
     /// Convert the bits into a bytes vector.
     pub fn into_bytes(self) -> Vec<u8, V::BitsSize> {
-        self.data
+        self.data.to_vec()
+    }
+
+    /// The bytes of the bits, like `into_bytes` but borrowed.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.data
     }
 
     /// Total number of bits currently pushed.
@@ -106,6 +182,11 @@ impl<V: QrSpec> Bits<V> {
     }
 }
 
+impl<V: QrSpec> Default for Bits<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[test]
 fn test_push_number() {
@@ -138,6 +219,26 @@ fn test_push_number() {
     );
 }
 
+#[test]
+fn test_clear() {
+    use crate::spec::{EcLevelL, Version1};
+    let mut bits = Bits::<Version1<EcLevelL>>::new();
+    bits.push_numeric_data(b"0123").unwrap();
+    bits.push_terminator().unwrap();
+    bits.clear();
+    assert!(bits.is_empty());
+    assert_eq!(bits.len(), 0);
+
+    // Nothing of the old bits, and not their bit offset, is left.
+    bits.push_number(3, 0b101).unwrap();
+    assert_eq!(bits.len(), 3);
+    bits.push_alphanumeric_data(b"AC-42").unwrap();
+    let mut fresh = Bits::<Version1<EcLevelL>>::new();
+    fresh.push_number(3, 0b101).unwrap();
+    fresh.push_alphanumeric_data(b"AC-42").unwrap();
+    assert_eq!(bits.into_bytes(), fresh.into_bytes());
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Mode indicator
@@ -168,7 +269,7 @@ impl<V: QrSpec> Bits<V> {
     /// If the mode is not supported in the provided version, this method
     /// returns `Err(QrError::UnsupportedCharacterSet)`.
     pub fn push_mode_indicator(&mut self, mode: ExtendedMode) -> QrResult<()> {
-        #[cfg_attr(feature = "cargo-clippy", allow(match_same_arms))]
+        #[allow(clippy::match_same_arms)]
         let number = match (V::VERSION, mode) {
             (Version::Micro(1), ExtendedMode::Data(Mode::Numeric)) => return Ok(()),
             (Version::Micro(_), ExtendedMode::Data(Mode::Numeric)) => 0,
@@ -190,6 +291,151 @@ impl<V: QrSpec> Bits<V> {
     }
 }
 
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Raw bits
+
+/// Writes fields into `Bits` exactly as given, without any of the checks of
+/// the `push_*` methods, to build malformed or edge-case bit streams for
+/// testing decoders. Obtained from `Bits::raw`.
+///
+/// Nothing is validated: mode indicators may be unassigned, character counts
+/// may have any width and disagree with the payload, and the payload may be
+/// any bits. The only error is `Err(QrError::DataTooLong { .. })` when the
+/// buffer of the version is full, in which case nothing is written. Do not
+/// use it for codes meant to be scanned.
+pub struct RawBits<'a, V: QrSpec> {
+    bits: &'a mut Bits<V>,
+}
+
+impl<V: QrSpec> Bits<V> {
+    /// Starts writing raw fields after the bits pushed so far. See `RawBits`.
+    ///
+    ///     use qrcode::bits::Bits;
+    ///     use qrcode::decode::decode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///     use qrcode::types::{Color, QrError};
+    ///
+    ///     // A byte segment claiming 200 bytes, followed by only 5.
+    ///     let mut bits = Bits::<Version1<EcLevelM>>::new();
+    ///     let mut raw = bits.raw();
+    ///     raw.mode_indicator(0b0100).unwrap();
+    ///     raw.character_count(8, 200).unwrap();
+    ///     raw.payload(b"Hello", 40).unwrap();
+    ///
+    ///     let code = bits.into_qr().unwrap();
+    ///     let modules: Vec<Color> = code.colors().collect();
+    ///     assert_eq!(decode(&modules, &mut [0; 32]), Err(QrError::InvalidSymbol));
+    pub fn raw(&mut self) -> RawBits<'_, V> {
+        // Later byte data must not be merged into a raw segment.
+        self.byte_segment = None;
+        RawBits { bits: self }
+    }
+}
+
+impl<V: QrSpec> RawBits<'_, V> {
+    /// Writes the lowest `n` bits of `value` in big-endian order. Widths above
+    /// 32 bits are padded with leading zeros.
+    pub fn number(&mut self, n: usize, value: u32) -> QrResult<()> {
+        self.reserve(n)?;
+        for i in (0..n).rev() {
+            let bit = if i < 32 { value >> i & 1 } else { 0 };
+            self.bits.push_number(1, bit.as_u16())?;
+        }
+        Ok(())
+    }
+
+    /// Writes a mode indicator of the width used by the version, which is 4
+    /// bits for normal QR codes. Extra high bits of `indicator` are dropped.
+    pub fn mode_indicator(&mut self, indicator: u8) -> QrResult<()> {
+        self.number(V::VERSION.mode_bits_count(), u32::from(indicator))
+    }
+
+    /// Writes a character count field of `width` bits, whatever the mode and
+    /// version would call for.
+    pub fn character_count(&mut self, width: usize, count: u32) -> QrResult<()> {
+        self.number(width, count)
+    }
+
+    /// Writes the first `n` bits of `data`, most significant bit first.
+    /// Widths beyond the end of `data` are padded with zeros.
+    pub fn payload(&mut self, data: &[u8], n: usize) -> QrResult<()> {
+        self.reserve(n)?;
+        for i in 0..n {
+            let bit = data.get(i / 8).map_or(0, |byte| byte >> (7 - i % 8) & 1);
+            self.bits.push_number(1, u16::from(bit))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `n` more bits fit into the buffer.
+    fn reserve(&self, n: usize) -> QrResult<()> {
+        let needed = self.bits.len() + n;
+        if needed > V::BitsSize::USIZE * 8 {
+            return Err(self.bits.too_long(needed));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod raw_tests {
+    use crate::bits::{Bits, ExtendedMode};
+    use crate::spec::{EcLevelL, EcLevelM, QrSpec, Version1};
+    use crate::types::{Mode, QrError};
+    use typenum::Unsigned;
+
+    #[test]
+    fn test_matches_push() {
+        let mut expected = Bits::<Version1<EcLevelM>>::new();
+        expected.push_numeric_data(b"01234567").unwrap();
+
+        let mut bits = Bits::<Version1<EcLevelM>>::new();
+        let mut raw = bits.raw();
+        raw.mode_indicator(0b0001).unwrap();
+        raw.character_count(10, 8).unwrap();
+        raw.number(10, 12).unwrap();
+        raw.number(10, 345).unwrap();
+        raw.number(7, 67).unwrap();
+        assert_eq!(bits.len(), expected.len());
+        assert_eq!(bits.into_bytes(), expected.into_bytes());
+    }
+
+    #[test]
+    fn test_unchecked_fields() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_mode_indicator(ExtendedMode::Data(Mode::Byte)).unwrap();
+        let mut raw = bits.raw();
+        raw.character_count(40, 0xab).unwrap();
+        raw.mode_indicator(0xff).unwrap();
+        raw.payload(&[0b1010_0000], 3).unwrap();
+        raw.payload(&[], 2).unwrap();
+        assert_eq!(bits.len(), 4 + 40 + 4 + 5);
+        assert_eq!(bits.into_bytes(), &[0b0100_0000, 0, 0, 0, 0b0000_1010, 0b1011_1111, 0b1010_0000]);
+    }
+
+    #[test]
+    fn test_no_byte_merge() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_byte_data(b"a").unwrap();
+        bits.raw().payload(b"b", 8).unwrap();
+        bits.push_byte_data(b"c").unwrap();
+        assert_eq!(bits.len(), 2 * (4 + 8 + 8) + 8);
+    }
+
+    #[test]
+    fn test_full() {
+        type V = Version1<EcLevelL>;
+        let size = <V as QrSpec>::BitsSize::USIZE * 8;
+        let mut bits = Bits::<V>::new();
+        bits.raw().number(size - 3, 0).unwrap();
+        assert_eq!(bits.raw().payload(&[0xff], 4), Err(QrError::DataTooLong { needed: size + 1, available: 152 }));
+        assert_eq!(bits.len(), size - 3);
+        bits.raw().payload(&[0xff], 3).unwrap();
+        assert_eq!(bits.len(), size);
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ ECI
@@ -230,7 +476,7 @@ impl<V: QrSpec> Bits<V> {
     /// `Err(QrError::UnsupportedCharacterSet)`.
     ///
     /// If the designator is outside of the expected range, this method will
-    /// return `Err(QrError::InvalidECIDesignator)`.
+    /// return `Err(QrError::InvalidEciDesignator(_))`.
     pub fn push_eci_designator(&mut self, eci_designator: u32) -> QrResult<()> {
         self.push_mode_indicator(ExtendedMode::Eci)?;
         match eci_designator {
@@ -246,7 +492,7 @@ impl<V: QrSpec> Bits<V> {
                 self.push_number(5, (eci_designator >> 16).as_u16())?;
                 self.push_number(16, (eci_designator & 0xffff).as_u16())?;
             }
-            _ => return Err(QrError::InvalidEciDesignator),
+            _ => return Err(QrError::InvalidEciDesignator(eci_designator)),
         }
         Ok(())
     }
@@ -283,7 +529,7 @@ mod eci_tests {
     #[test]
     fn test_invalid_designator() {
         let mut bits = Bits::<Version1<EcLevelL>>::new();
-        assert_eq!(bits.push_eci_designator(1000000), Err(QrError::InvalidEciDesignator));
+        assert_eq!(bits.push_eci_designator(1000000), Err(QrError::InvalidEciDesignator(1000000)));
     }
 
     // #[test]
@@ -298,9 +544,26 @@ mod eci_tests {
 //------------------------------------------------------------------------------
 //{{{ Mode::Numeric mode
 
+/// Checks that every byte of the data is accepted by `is_valid`, or returns
+/// the first offending one.
+fn check_characters<F: Fn(u8) -> bool>(data: &[u8], is_valid: F) -> QrResult<()> {
+    match data.iter().position(|&b| !is_valid(b)) {
+        Some(index) => Err(QrError::InvalidCharacter { index, byte: data[index] }),
+        None => Ok(()),
+    }
+}
+
 impl<V: QrSpec> Bits<V> {
+    /// Pushes the mode indicator and character count of a segment, after
+    /// checking that the whole segment fits into the QR code.
     fn push_header(&mut self, mode: Mode, raw_data_len: usize) -> QrResult<()> {
         let length_bits = mode.length_bits_count(V::VERSION);
+        let header_bits = V::VERSION.mode_bits_count() + length_bits;
+        let needed = mode.data_bits_count(raw_data_len).saturating_add(self.len() + header_bits);
+        let available = self.max_len(V::EC_LEVEL)?;
+        if needed > available {
+            return Err(QrError::DataTooLong { needed, available });
+        }
         self.push_mode_indicator(ExtendedMode::Data(mode))?;
         self.push_number_checked(length_bits, raw_data_len)?;
         Ok(())
@@ -310,6 +573,7 @@ impl<V: QrSpec> Bits<V> {
     ///
     /// The data should only contain the characters 0 to 9.
     pub fn push_numeric_data(&mut self, data: &[u8]) -> QrResult<()> {
+        check_characters(data, |b| b.is_ascii_digit())?;
         self.push_header(Mode::Numeric, data.len())?;
         for chunk in data.chunks(3) {
             let number = chunk.iter().map(|b| u16::from(*b - b'0')).fold(0, |a, b| a * 10 + b);
@@ -414,6 +678,7 @@ impl<V: QrSpec> Bits<V> {
     /// The data should only contain the characters A to Z (excluding lowercase),
     /// 0 to 9, space, `$`, `%`, `*`, `+`, `-`, `.`, `/` or `:`.
     pub fn push_alphanumeric_data(&mut self, data: &[u8]) -> QrResult<()> {
+        check_characters(data, |b| b == b'0' || alphanumeric_digit(b) != 0)?;
         self.push_header(Mode::Alphanumeric, data.len())?;
         for chunk in data.chunks(2) {
             let number = chunk.iter().map(|b| alphanumeric_digit(*b)).fold(0, |a, b| a * 45 + b);
@@ -460,8 +725,42 @@ mod alphanumeric_tests {
 
 impl<V: QrSpec> Bits<V> {
     /// Encodes 8-bit byte data to the bits.
+    ///
+    /// If nothing else was pushed since the previous call, the data is
+    /// appended to the byte segment of that call instead of starting a new
+    /// one, as long as the character count still fits. Data streamed in
+    /// chunks thus costs no more than data pushed at once. Use
+    /// `push_byte_segment` to always start a new segment.
     pub fn push_byte_data(&mut self, data: &[u8]) -> QrResult<()> {
+        let length_bits = Mode::Byte.length_bits_count(V::VERSION);
+        let len = self.len();
+        let open_segment = self.byte_segment.map(|position| (position, self.get_number(position, length_bits)));
+        match open_segment {
+            Some((position, count))
+                if position + length_bits + count * 8 == len && count + data.len() <= max_number(length_bits) =>
+            {
+                let needed = len + data.len() * 8;
+                let available = self.max_len(V::EC_LEVEL)?;
+                if needed > available {
+                    return Err(QrError::DataTooLong { needed, available });
+                }
+                self.set_number(position, length_bits, count + data.len());
+                self.push_bytes(data)
+            }
+            _ => self.push_byte_segment(data),
+        }
+    }
+
+    /// Encodes 8-bit byte data to the bits as a segment of its own, with its
+    /// own mode indicator and character count.
+    pub fn push_byte_segment(&mut self, data: &[u8]) -> QrResult<()> {
+        let position = self.len() + V::VERSION.mode_bits_count();
         self.push_header(Mode::Byte, data.len())?;
+        self.byte_segment = Some(position);
+        self.push_bytes(data)
+    }
+
+    fn push_bytes(&mut self, data: &[u8]) -> QrResult<()> {
         for b in data {
             self.push_number(8, u16::from(*b))?;
         }
@@ -469,11 +768,11 @@ impl<V: QrSpec> Bits<V> {
     }
 }
 
-
 #[cfg(test)]
 mod byte_tests {
     use crate::bits::Bits;
     use crate::spec::{Version1, EcLevelL};
+    use crate::types::QrError;
 
     #[test]
     fn test() {
@@ -496,6 +795,44 @@ mod byte_tests {
         );
     }
 
+    #[test]
+    fn test_merge_chunks() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        assert_eq!(bits.push_byte_data(b"\x12\x34\x56"), Ok(()));
+        assert_eq!(bits.push_byte_data(b""), Ok(()));
+        assert_eq!(bits.push_byte_data(b"\x78\x9a\xbc\xde\xf0"), Ok(()));
+        assert_eq!(bits.len(), 4 + 8 + 8 * 8);
+        let mut whole = Bits::<Version1<EcLevelL>>::new();
+        whole.push_byte_data(b"\x12\x34\x56\x78\x9a\xbc\xde\xf0").unwrap();
+        assert_eq!(bits.into_bytes(), whole.into_bytes());
+    }
+
+    #[test]
+    fn test_no_merge_after_other_segment() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        assert_eq!(bits.push_byte_data(b"a"), Ok(()));
+        assert_eq!(bits.push_numeric_data(b"1"), Ok(()));
+        assert_eq!(bits.push_byte_data(b"b"), Ok(()));
+        assert_eq!(bits.len(), (4 + 8 + 8) + (4 + 10 + 4) + (4 + 8 + 8));
+    }
+
+    #[test]
+    fn test_separate_segments() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        assert_eq!(bits.push_byte_data(b"a"), Ok(()));
+        assert_eq!(bits.push_byte_segment(b"b"), Ok(()));
+        assert_eq!(bits.len(), 2 * (4 + 8 + 8));
+    }
+
+    #[test]
+    fn test_merge_data_too_long() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        assert_eq!(bits.push_byte_data(&[0; 10]), Ok(()));
+        assert_eq!(bits.push_byte_data(&[0; 7]), Ok(()));
+        assert_eq!(bits.push_byte_data(&[0; 1]), Err(QrError::DataTooLong { needed: 156, available: 152 }));
+        assert_eq!(bits.len(), 148);
+    }
+
     // #[test]
     // fn test_micro_qr_unsupported() {
     //    let mut bits = Bits::new(Version::Micro(2));
@@ -514,23 +851,46 @@ mod byte_tests {
 //------------------------------------------------------------------------------
 //{{{ Mode::Kanji mode
 
+/// Checks that the data is a sequence of Shift JIS double-byte characters, or
+/// returns the first byte of the first one which is not.
+fn check_kanji(data: &[u8]) -> QrResult<()> {
+    for (i, kanji) in data.chunks(2).enumerate() {
+        let valid = match *kanji {
+            [a, b] => {
+                matches!(u16::from(a) << 8 | u16::from(b), 0x8140..=0x9ffc | 0xe040..=0xebbf)
+                    && matches!(b, 0x40..=0x7e | 0x80..=0xfc)
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(QrError::InvalidCharacter { index: i * 2, byte: kanji[0] });
+        }
+    }
+    Ok(())
+}
+
 impl<V: QrSpec> Bits<V> {
     /// Encodes Shift JIS double-byte data to the bits.
+    ///
+    /// Every character must be a pair of bytes in the ranges 0x8140–0x9ffc or
+    /// 0xe040–0xebbf, whose second byte is a valid Shift JIS trail byte in
+    /// 0x40–0x7e or 0x80–0xfc.
     pub fn push_kanji_data(&mut self, data: &[u8]) -> QrResult<()> {
+        check_kanji(data)?;
         self.push_header(Mode::Kanji, data.len() / 2)?;
         for kanji in data.chunks(2) {
-            if kanji.len() != 2 {
-                return Err(QrError::InvalidCharacter);
-            }
-            let cp = u16::from(kanji[0]) * 256 + u16::from(kanji[1]);
-            let bytes = if cp < 0xe040 { cp - 0x8140 } else { cp - 0xc140 };
-            let number = (bytes >> 8) * 0xc0 + (bytes & 0xff);
-            self.push_number(13, number)?;
+            self.push_number(13, kanji_number(u16::from(kanji[0]) * 256 + u16::from(kanji[1])))?;
         }
         Ok(())
     }
 }
 
+/// Compacts a valid Shift JIS double-byte code into the 13 bits of kanji mode.
+fn kanji_number(cp: u16) -> u16 {
+    let bytes = if cp < 0xe040 { cp - 0x8140 } else { cp - 0xc140 };
+    (bytes >> 8) * 0xc0 + (bytes & 0xff)
+}
+
 
 #[cfg(test)]
 mod kanji_tests {
@@ -613,7 +973,7 @@ impl<V: QrSpec> Bits<V> {
 //{{{ Finish
 
 // This table is copied from ISO/IEC 18004:2006 §6.4.10, Table 7.
-static DATA_LENGTHS: [[usize; 4]; 44] = [
+pub(crate) static DATA_LENGTHS: [[usize; 4]; 44] = [
     // Normal versions
     [152, 128, 104, 72],
     [272, 224, 176, 128],
@@ -673,7 +1033,7 @@ impl<V: QrSpec> Bits<V> {
         let cur_length = self.len();
         let data_length = self.max_len(V::EC_LEVEL)?;
         if cur_length > data_length {
-            return Err(QrError::DataTooLong);
+            return Err(QrError::DataTooLong { needed: cur_length, available: data_length });
         }
 
         let terminator_size = min(terminator_size, data_length - cur_length);
@@ -687,23 +1047,42 @@ impl<V: QrSpec> Bits<V> {
             self.bit_offset = 0;
             let data_bytes_length = data_length / 8;
             let padding_bytes_count = data_bytes_length - self.data.len();
-            let padding = PADDING_BYTES.iter().cloned().cycle().take(padding_bytes_count);
-            self.data.extend(padding);
+            for &byte in PADDING_BYTES.iter().cycle().take(padding_bytes_count) {
+                self.data.push(byte).map_err(|_| self.too_long(data_length))?;
+            }
         }
 
         if self.len() < data_length {
-            self.data.push(0).map_err(|_| QrError::DataTooLong)?;
+            self.data.push(0).map_err(|_| self.too_long(data_length))?;
         }
 
         Ok(())
     }
+
+    /// Pushes the terminator and the padding, and builds the QR code from
+    /// the bits. This is `push_terminator` followed by `QrCode::with_bits`,
+    /// which cannot be forgotten.
+    ///
+    ///     use qrcode::bits::Bits;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let mut bits = Bits::<Version1<EcLevelM>>::new();
+    ///     bits.push_numeric_data(b"01234567").unwrap();
+    ///     let code = bits.into_qr().unwrap();
+    ///
+    /// Bits which are already terminated are left as they are.
+    pub fn into_qr(mut self) -> QrResult<QrCode<V>> {
+        self.push_terminator()?;
+        QrCode::with_bits(self)
+    }
 }
 
 
 #[cfg(test)]
 mod finish_tests {
     use crate::bits::Bits;
-    use crate::spec::{Version1, EcLevelQ};
+    use crate::spec::{EcLevelL, EcLevelQ, Version1};
+    use crate::QrCode;
 
     #[test]
     fn test_hello_world() {
@@ -719,6 +1098,33 @@ mod finish_tests {
         );
     }
 
+    #[test]
+    fn test_into_qr() {
+        let hello = || {
+            let mut bits = Bits::<Version1<EcLevelQ>>::new();
+            bits.push_alphanumeric_data(b"HELLO WORLD").unwrap();
+            bits
+        };
+        let mut terminated = hello();
+        terminated.push_terminator().unwrap();
+        let expected = QrCode::with_bits(terminated).unwrap();
+        assert_eq!(hello().into_qr(), Ok(expected.clone()));
+
+        // Terminating twice changes nothing.
+        let mut terminated = hello();
+        terminated.push_terminator().unwrap();
+        assert_eq!(terminated.into_qr(), Ok(expected));
+    }
+
+    #[test]
+    fn test_into_qr_full() {
+        // 12 bits of header and 136 of data leave room for the terminator only.
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_byte_data(&[0; 17]).unwrap();
+        assert_eq!(bits.len(), 148);
+        assert!(bits.into_qr().is_ok());
+    }
+
     // #[test]
     // fn test_too_long() {
     //     let mut bits = Bits::new(Version::Micro(1));
@@ -770,22 +1176,143 @@ impl<V: QrSpec> Bits<V> {
     where
         I: Iterator<Item = Segment>,
     {
-        for segment in segments_iter {
+        let mut segments_iter = segments_iter;
+        while let Some(segment) = segments_iter.next() {
             let slice = &data[segment.begin..segment.end];
-            match segment.mode {
+            let result = match segment.mode {
                 Mode::Numeric => self.push_numeric_data(slice),
                 Mode::Alphanumeric => self.push_alphanumeric_data(slice),
                 Mode::Byte => self.push_byte_data(slice),
                 Mode::Kanji => self.push_kanji_data(slice),
-            }?;
+            };
+            match result {
+                // Nothing of the failed segment was pushed, report the length
+                // of the whole data.
+                Err(QrError::DataTooLong { available, .. }) => {
+                    let rest: usize = segments_iter.map(|s| s.encoded_len(V::VERSION)).sum();
+                    let needed = self.len() + segment.encoded_len(V::VERSION) + rest;
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(version = ?V::VERSION, ec_level = ?V::EC_LEVEL, needed, available, "data too long");
+                    return Err(QrError::DataTooLong { needed, available });
+                }
+                Err(QrError::InvalidCharacter { index, byte }) => {
+                    return Err(QrError::InvalidCharacter { index: segment.begin + index, byte });
+                }
+                result => result?,
+            }
         }
         Ok(())
     }
 
     /// Pushes the data the bits, using the optimal encoding.
+    ///
+    /// With the `tracing` feature, every chosen segment is emitted as a trace
+    /// event, and the total length as a debug event.
     pub fn push_optimal_data(&mut self, data: &[u8]) -> QrResult<()> {
         let segments = Parser::new(data).optimize(V::VERSION);
-        self.push_segments(data, segments)
+        #[cfg(feature = "tracing")]
+        let segments = segments.inspect(|segment| {
+            tracing::trace!(mode = ?segment.mode, begin = segment.begin, end = segment.end, "segment chosen");
+        });
+        self.push_segments(data, segments)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            version = ?V::VERSION,
+            ec_level = ?V::EC_LEVEL,
+            bits = self.len(),
+            available = self.max_len(V::EC_LEVEL).unwrap_or(0),
+            "data segmented"
+        );
+        Ok(())
+    }
+}
+
+
+/// A run of data in one character set, for `Bits::push_eci_segments`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EciSegment<'a> {
+    /// The ECI designator switching to the character set of the data, or
+    /// `None` to keep the character set of the previous segment.
+    pub designator: Option<u32>,
+
+    /// The data, in that character set.
+    pub data: &'a [u8],
+}
+
+impl EciSegment<'_> {
+    /// The number of bits needed to encode the segment with
+    /// `Bits::push_eci_segments`, including the ECI header. Compare this
+    /// rather than the length of the data alone when choosing between
+    /// alternative encodings of the same text, as the header can outweigh
+    /// what a shorter encoding saves.
+    ///
+    ///     use qrcode::bits::EciSegment;
+    ///     use qrcode::types::Version;
+    ///
+    ///     // "Grüße" in UTF-8 after ECI 26, or in ISO-8859-1, the default.
+    ///     let utf_8 = EciSegment { designator: Some(26), data: "Grüße".as_bytes() };
+    ///     let latin_1 = EciSegment { designator: None, data: b"Gr\xfc\xdfe" };
+    ///     assert_eq!(utf_8.encoded_len(Version::Normal(1)), 12 + 4 + 8 + 7 * 8);
+    ///     assert_eq!(latin_1.encoded_len(Version::Normal(1)), 4 + 8 + 5 * 8);
+    pub fn encoded_len(&self, version: Version) -> usize {
+        let designator_len = match self.designator {
+            None => 0,
+            Some(0..=127) => 8,
+            Some(128..=16383) => 16,
+            Some(_) => 24,
+        };
+        let data_len: usize = Parser::new(self.data).optimize(version).map(|s| s.encoded_len(version)).sum();
+        let mode_len = if self.designator.is_some() { version.mode_bits_count() } else { 0 };
+        mode_len + designator_len + data_len
+    }
+}
+
+impl<V: QrSpec> Bits<V> {
+    /// Pushes data switching between character sets within the symbol. Each
+    /// segment with a designator starts with an ECI designator, and its data
+    /// is encoded optimally like `push_optimal_data`.
+    ///
+    ///     use qrcode::bits::{Bits, EciSegment};
+    ///     use qrcode::spec::{Version2, EcLevelM};
+    ///
+    ///     let mut bits = Bits::<Version2<EcLevelM>>::new();
+    ///     bits.push_eci_segments(&[
+    ///         EciSegment { designator: Some(3), data: b"Caf\xe9: " }, // ISO-8859-1
+    ///         EciSegment { designator: Some(20), data: b"\x93\x5f\xe4\xaa" }, // Shift JIS
+    ///     ]).unwrap();
+    ///     let code = bits.into_qr().unwrap();
+    ///
+    /// The segments are checked before anything is pushed. This method
+    /// returns `Err(QrError::InvalidEciDesignator(_))` if a designator is
+    /// outside of 0 to 999999, `Err(QrError::EmptyEciSegment { .. })` if a
+    /// designator is not followed by any data, and
+    /// `Err(QrError::UnsupportedCharacterSet)` for Micro QR codes, which do
+    /// not support ECI. If the segments do not fit, it returns
+    /// `Err(QrError::DataTooLong { .. })` with the bits needed by all of them.
+    pub fn push_eci_segments(&mut self, segments: &[EciSegment]) -> QrResult<()> {
+        for (index, segment) in segments.iter().enumerate() {
+            match segment.designator {
+                Some(_) if V::VERSION.is_micro() => return Err(QrError::UnsupportedCharacterSet),
+                Some(designator @ 1_000_000..) => return Err(QrError::InvalidEciDesignator(designator)),
+                Some(_) if segment.data.is_empty() => return Err(QrError::EmptyEciSegment { index }),
+                _ => {}
+            }
+        }
+        for (index, segment) in segments.iter().enumerate() {
+            let start = self.len();
+            let result = match segment.designator {
+                Some(designator) => self.push_eci_designator(designator),
+                None => Ok(()),
+            };
+            match result.and_then(|()| self.push_optimal_data(segment.data)) {
+                Err(QrError::DataTooLong { available, .. }) => {
+                    let rest: usize = segments[index..].iter().map(|s| s.encoded_len(V::VERSION)).sum();
+                    return Err(QrError::DataTooLong { needed: start + rest, available });
+                }
+                result => result?,
+            }
+        }
+        Ok(())
     }
 }
 
@@ -824,9 +1351,706 @@ mod encode_tests {
     #[test]
     fn test_too_long() {
         let res = encode::<Version1<EcLevelH>>(b">>>>>>>>");
-        assert_eq!(res, Err(QrError::DataTooLong));
+        assert_eq!(res, Err(QrError::DataTooLong { needed: 76, available: 72 }));
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        let mut bits = Bits::<Version1<EcLevelQ>>::new();
+        assert_eq!(bits.push_numeric_data(b"12a4"), Err(QrError::InvalidCharacter { index: 2, byte: b'a' }));
+        assert_eq!(bits.push_alphanumeric_data(b"AB_"), Err(QrError::InvalidCharacter { index: 2, byte: b'_' }));
+        assert_eq!(bits.push_kanji_data(b"\x93\x5f\x00\x00"), Err(QrError::InvalidCharacter { index: 2, byte: 0 }));
+        assert_eq!(bits.push_kanji_data(b"\x93"), Err(QrError::InvalidCharacter { index: 0, byte: 0x93 }));
+        assert_eq!(bits.push_kanji_data(b"\x82\x20"), Err(QrError::InvalidCharacter { index: 0, byte: 0x82 }));
+        assert_eq!(bits.push_kanji_data(b"\x93\x5f\x93\x7f"), Err(QrError::InvalidCharacter { index: 2, byte: 0x93 }));
+        assert_eq!(bits.push_kanji_data(b"\xe0\xfd"), Err(QrError::InvalidCharacter { index: 0, byte: 0xe0 }));
+        assert!(bits.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod eci_segment_tests {
+    use crate::bits::{Bits, EciSegment};
+    use crate::decode::{parse_segments, SegmentKind};
+    use crate::spec::{EcLevelL, Version1, Version2, Version4};
+    use crate::types::{Mode, QrError, Version};
+
+    #[test]
+    fn test_switch_character_set() {
+        let mut bits = Bits::<Version4<EcLevelL>>::new();
+        let segments = [
+            EciSegment { designator: Some(3), data: b"Caf\xe9 " },
+            EciSegment { designator: Some(20), data: b"\x93\x5f\xe4\xaa" },
+            EciSegment { designator: None, data: b"0123456789" },
+            EciSegment { designator: Some(26), data: "Grüße".as_bytes() },
+        ];
+        bits.push_eci_segments(&segments).unwrap();
+        bits.push_terminator().unwrap();
+
+        let mut out = [0; 64];
+        let mut kinds = Vec::new();
+        let len = parse_segments(Version::Normal(4), bits.bytes(), &mut out, |s| kinds.push(s.kind)).unwrap();
+        let data: Vec<u8> = segments.iter().flat_map(|s| s.data.iter().copied()).collect();
+        assert_eq!(&out[..len], &data[..]);
+        assert_eq!(
+            kinds,
+            [
+                SegmentKind::Eci(3),
+                SegmentKind::Data(Mode::Byte),
+                SegmentKind::Eci(20),
+                SegmentKind::Data(Mode::Kanji),
+                SegmentKind::Data(Mode::Numeric),
+                SegmentKind::Eci(26),
+                SegmentKind::Data(Mode::Byte),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_segments() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        let segments = [EciSegment { designator: Some(3), data: b"A" }, EciSegment { designator: Some(20), data: b"" }];
+        assert_eq!(bits.push_eci_segments(&segments), Err(QrError::EmptyEciSegment { index: 1 }));
+        let segments = [EciSegment { designator: Some(3), data: b"A" }, EciSegment { designator: Some(1_000_000), data: b"B" }];
+        assert_eq!(bits.push_eci_segments(&segments), Err(QrError::InvalidEciDesignator(1_000_000)));
+        assert!(bits.is_empty());
+
+        assert_eq!(bits.push_eci_segments(&[EciSegment { designator: None, data: b"" }]), Ok(()));
+    }
+
+    #[test]
+    fn test_too_long() {
+        let mut bits = Bits::<Version2<EcLevelL>>::new();
+        let segments = [
+            EciSegment { designator: Some(3), data: &[b'a'; 20] },
+            EciSegment { designator: Some(899), data: &[b'b'; 20] },
+            EciSegment { designator: None, data: b"0123" },
+        ];
+        // The designators take 12 and 20 bits, the byte segments 172 bits
+        // each and the numeric segment 28 bits.
+        let needed = 12 + 172 + 20 + 172 + 28;
+        assert_eq!(bits.push_eci_segments(&segments), Err(QrError::DataTooLong { needed, available: 272 }));
+    }
+}
+
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Text
+
+/// The ECI designator of UTF-8.
+const ECI_UTF_8: u32 = 26;
+
+/// The character set which scanners assume for byte mode data without an ECI
+/// designator. The standard says ISO-8859-1, but many scanners, phones in
+/// particular, assume UTF-8 and some mishandle the ECI designator, so the
+/// encoder can target either behavior.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum ByteCharset {
+    /// ISO-8859-1, the default character set of the standard. Text outside of
+    /// ISO-8859-1 and kanji is encoded as UTF-8 after the ECI designator of
+    /// UTF-8.
+    #[default]
+    Latin1,
+
+    /// UTF-8. All text is encoded as UTF-8 in byte mode without an ECI
+    /// designator, and kanji mode is not used.
+    Utf8,
+}
+
+/// How `Bits::push_str` encodes the characters outside of ASCII.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum TextCharset {
+    /// ISO-8859-1 in byte mode, the default character set, and Shift JIS in
+    /// kanji mode. No ECI designator is needed.
+    Latin1AndKanji,
+
+    /// UTF-8 in byte mode, after an ECI designator.
+    Utf8,
+}
+
+impl TextCharset {
+    /// Chooses the character set for the text, and whether it needs the ECI
+    /// designator of UTF-8.
+    ///
+    /// Under `ByteCharset::Latin1`, text with kanji can also be encoded as
+    /// UTF-8 after the ECI designator. Kanji scattered between other
+    /// characters split the text into many segments, whose headers can cost
+    /// more than the designator and the longer UTF-8, so both are counted
+    /// in full and the shorter one wins.
+    fn choose(text: &str, byte_charset: ByteCharset, version: Version) -> QrResult<(Self, bool)> {
+        if byte_charset == ByteCharset::Utf8 {
+            return Ok((TextCharset::Utf8, false));
+        }
+        if !text.chars().all(|c| u32::from(c) <= 0xff || jis::shift_jis(c).is_some()) {
+            return Ok((TextCharset::Utf8, true));
+        }
+        // ISO-8859-1 alone is never longer than UTF-8, and Micro QR codes
+        // have no ECI designators.
+        if text.chars().all(|c| u32::from(c) <= 0xff) || matches!(version, Version::Micro(_)) {
+            return Ok((TextCharset::Latin1AndKanji, false));
+        }
+        let latin_1 = TextCharset::Latin1AndKanji.text_len(text, version, false)?;
+        let utf_8 = TextCharset::Utf8.text_len(text, version, true)?;
+        Ok(if utf_8 < latin_1 { (TextCharset::Utf8, true) } else { (TextCharset::Latin1AndKanji, false) })
+    }
+
+    /// The number of bits needed to encode the whole text, including the ECI
+    /// designator of UTF-8 if `eci` is set.
+    fn text_len(self, text: &str, version: Version, eci: bool) -> QrResult<usize> {
+        let mut len = if eci { version.mode_bits_count() + 8 } else { 0 };
+        self.segments(text, version, |mode, text| {
+            len = len.saturating_add(self.encoded_len(mode, text, version));
+            Ok(())
+        })?;
+        Ok(len)
+    }
+
+    /// Whether the character is encoded in kanji mode.
+    fn is_kanji(self, c: char) -> bool {
+        self == TextCharset::Latin1AndKanji && u32::from(c) > 0xff
+    }
+
+    /// The number of characters of the text in the given mode, as counted by
+    /// the character count indicator.
+    fn chars_count(self, mode: Mode, text: &str) -> usize {
+        match (mode, self) {
+            (Mode::Kanji, _) | (Mode::Byte, TextCharset::Latin1AndKanji) => text.chars().count(),
+            _ => text.len(),
+        }
+    }
+
+    /// The number of bits needed to encode the text in the given mode,
+    /// including the segment header.
+    fn encoded_len(self, mode: Mode, text: &str, version: Version) -> usize {
+        let data_bits_count = mode.data_bits_count(self.chars_count(mode, text));
+        data_bits_count.saturating_add(version.mode_bits_count() + mode.length_bits_count(version))
+    }
+
+    /// Splits the text into segments, calling `f` with the mode and the text
+    /// of each. Runs of kanji form segments of their own, and the rest is
+    /// segmented like `Bits::push_optimal_data`, except that the bytes of
+    /// multi-byte characters are never mistaken for Shift JIS.
+    fn segments<F>(self, text: &str, version: Version, mut f: F) -> QrResult<()>
+    where
+        F: FnMut(Mode, &str) -> QrResult<()>,
+    {
+        // Adjacent byte segments are merged, as the runs are segmented apart.
+        let mut pending: Option<(usize, usize)> = None;
+        let mut push = |mode: Mode, begin: usize, end: usize| -> QrResult<()> {
+            match pending {
+                Some((pending_begin, pending_end)) if mode == Mode::Byte && pending_end == begin => {
+                    pending = Some((pending_begin, end));
+                    return Ok(());
+                }
+                Some((pending_begin, pending_end)) => f(Mode::Byte, &text[pending_begin..pending_end])?,
+                None => {}
+            }
+            pending = None;
+            match mode {
+                Mode::Byte => pending = Some((begin, end)),
+                _ if begin < end => f(mode, &text[begin..end])?,
+                _ => {}
+            }
+            Ok(())
+        };
+
+        let mut run_begin = 0;
+        while run_begin < text.len() {
+            let run = &text[run_begin..];
+            let kanji = run.chars().next().is_some_and(|c| self.is_kanji(c));
+            let run_len = run.char_indices().find(|&(_, c)| self.is_kanji(c) != kanji).map_or(run.len(), |(i, _)| i);
+            let run = &run[..run_len];
+            if kanji {
+                push(Mode::Kanji, run_begin, run_begin + run_len)?;
+            } else {
+                // Every character outside of ASCII stands in as 0x80, which is
+                // never part of a Shift JIS double-byte character.
+                let class = |c: char| if c.is_ascii() { c as u8 } else { 0x80 };
+                let chars = StreamParser::new(run.chars().map(class));
+                let bytes = StreamParser::new(run.bytes().map(|b| class(char::from(b))));
+                let segments: &mut dyn Iterator<Item = Segment> = match self {
+                    TextCharset::Latin1AndKanji => &mut chars.optimize(version),
+                    TextCharset::Utf8 => &mut bytes.optimize(version),
+                };
+                let mut offsets = run.char_indices().map(|(i, _)| i).skip(1);
+                let mut end = 0;
+                for segment in segments {
+                    let begin = end;
+                    end = match self {
+                        TextCharset::Latin1AndKanji => {
+                            offsets.nth(segment.end - segment.begin - 1).unwrap_or(run.len())
+                        }
+                        TextCharset::Utf8 => segment.end,
+                    };
+                    let mode = if segment.mode == Mode::Kanji { Mode::Byte } else { segment.mode };
+                    push(mode, run_begin + begin, run_begin + end)?;
+                }
+            }
+            run_begin += run_len;
+        }
+        push(Mode::Numeric, text.len(), text.len())
+    }
+}
+
+impl<V: QrSpec> Bits<V> {
+    /// Pushes text, choosing the modes and the character set for it. Digits
+    /// and the characters of alphanumeric mode are segmented like
+    /// `push_optimal_data`, characters of JIS X 0208 outside of ISO-8859-1
+    /// are encoded in kanji mode, and the rest in byte mode as ISO-8859-1,
+    /// the default character set. If the text has characters in neither of
+    /// these character sets, or has kanji and takes fewer bits that way, it is
+    /// preceded by the ECI designator of UTF-8 and encoded as UTF-8, without
+    /// kanji mode. The comparison counts the bits of the designator.
+    ///
+    ///     use qrcode::bits::Bits;
+    ///     use qrcode::spec::{Version1, EcLevelL};
+    ///
+    ///     let mut latin_1 = Bits::<Version1<EcLevelL>>::new();
+    ///     latin_1.push_str("Grüße").unwrap();
+    ///     assert_eq!(latin_1.len(), 4 + 8 + 5 * 8);
+    ///
+    ///     let mut kanji = Bits::<Version1<EcLevelL>>::new();
+    ///     kanji.push_str("点茗").unwrap();
+    ///     assert_eq!(kanji.len(), 4 + 8 + 2 * 13);
+    ///
+    ///     let mut utf_8 = Bits::<Version1<EcLevelL>>::new();
+    ///     utf_8.push_str("Hi 👋").unwrap();
+    ///     assert_eq!(utf_8.len(), 4 + 8 + 4 + 8 + 7 * 8);
+    ///
+    /// Returns `Err(QrError::UnsupportedCharacterSet)` if the version does not
+    /// support a mode or the ECI designator needed, and
+    /// `Err(QrError::DataTooLong { .. })` with the bits needed by the whole
+    /// text if it does not fit.
+    pub fn push_str(&mut self, text: &str) -> QrResult<()> {
+        self.push_str_with(text, ByteCharset::Latin1)
+    }
+
+    /// Pushes text like `push_str`, for scanners which assume `byte_charset`
+    /// for byte mode data without an ECI designator. With
+    /// `ByteCharset::Utf8`, the text is encoded as UTF-8 without the ECI
+    /// designator.
+    ///
+    ///     use qrcode::bits::{Bits, ByteCharset};
+    ///     use qrcode::spec::{Version1, EcLevelL};
+    ///
+    ///     let mut bits = Bits::<Version1<EcLevelL>>::new();
+    ///     bits.push_str_with("Grüße", ByteCharset::Utf8).unwrap();
+    ///     assert_eq!(bits.len(), 4 + 8 + 7 * 8);
+    pub fn push_str_with(&mut self, text: &str, byte_charset: ByteCharset) -> QrResult<()> {
+        let (charset, eci) = TextCharset::choose(text, byte_charset, V::VERSION)?;
+        let start = self.len();
+        match self.push_text(text, charset, eci) {
+            Err(QrError::DataTooLong { available, .. }) => {
+                let needed = start.saturating_add(charset.text_len(text, V::VERSION, eci)?);
+                Err(QrError::DataTooLong { needed, available })
+            }
+            result => result,
+        }
+    }
+
+    fn push_text(&mut self, text: &str, charset: TextCharset, eci: bool) -> QrResult<()> {
+        if eci {
+            self.push_eci_designator(ECI_UTF_8)?;
+        }
+        charset.segments(text, V::VERSION, |mode, text| match (mode, charset) {
+            (Mode::Numeric, _) => self.push_numeric_data(text.as_bytes()),
+            (Mode::Alphanumeric, _) => self.push_alphanumeric_data(text.as_bytes()),
+            (Mode::Byte, TextCharset::Utf8) => self.push_byte_segment(text.as_bytes()),
+            (Mode::Byte, TextCharset::Latin1AndKanji) => {
+                // Checked up front, as the chunks are pushed one by one.
+                let needed = self.len() + charset.encoded_len(mode, text, V::VERSION);
+                let available = self.max_len(V::EC_LEVEL)?;
+                if needed > available {
+                    return Err(QrError::DataTooLong { needed, available });
+                }
+                let mut chars = text.chars();
+                let mut chunk = [0; 32];
+                let mut first = true;
+                loop {
+                    let len = chunk.iter_mut().zip(chars.by_ref()).map(|(b, c)| *b = c as u8).count();
+                    if len == 0 {
+                        return Ok(());
+                    }
+                    if first {
+                        self.push_byte_segment(&chunk[..len])?;
+                        first = false;
+                    } else {
+                        self.push_byte_data(&chunk[..len])?;
+                    }
+                }
+            }
+            (Mode::Kanji, _) => {
+                self.push_header(Mode::Kanji, text.chars().count())?;
+                for c in text.chars() {
+                    self.push_number(13, jis::shift_jis(c).map_or(0, kanji_number))?;
+                }
+                Ok(())
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod text_tests {
+    use crate::bits::{Bits, ByteCharset};
+    use crate::decode::{parse_segments, SegmentKind};
+    use crate::spec::{EcLevelL, EcLevelM, QrSpec, Version1, Version10, Version2};
+    use crate::types::{Mode, QrError, Version};
+
+    /// Pushes the text, and returns the decoded data and segment kinds.
+    fn round_trip<V: QrSpec>(text: &str) -> (Vec<u8>, Vec<SegmentKind>) {
+        let mut bits = Bits::<V>::new();
+        bits.push_str(text).unwrap();
+        bits.push_terminator().unwrap();
+        let mut out = [0; 512];
+        let mut kinds = Vec::new();
+        let len = parse_segments(V::VERSION, bits.bytes(), &mut out, |s| kinds.push(s.kind)).unwrap();
+        (out[..len].to_vec(), kinds)
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mut text = Bits::<Version2<EcLevelL>>::new();
+        text.push_str("HELLO WORLD 0123456789 hello").unwrap();
+        let mut data = Bits::<Version2<EcLevelL>>::new();
+        data.push_optimal_data(b"HELLO WORLD 0123456789 hello").unwrap();
+        assert_eq!(text.into_bytes(), data.into_bytes());
+    }
+
+    #[test]
+    fn test_latin_1() {
+        // "é" followed by "a" is 0xe9 0x61, a Shift JIS double-byte code.
+        let (data, kinds) = round_trip::<Version2<EcLevelL>>("Café au lait 0123456789");
+        assert_eq!(data, b"Caf\xe9 au lait 0123456789");
+        assert_eq!(kinds, [SegmentKind::Data(Mode::Byte), SegmentKind::Data(Mode::Numeric)]);
+        let (data, _) = round_trip::<Version1<EcLevelL>>("éa");
+        assert_eq!(data, b"\xe9a");
+    }
+
+    #[test]
+    fn test_kanji() {
+        let (data, kinds) = round_trip::<Version2<EcLevelM>>("価格: 1500円 (税込)");
+        assert_eq!(data, b"\x89\xbf\x8a\x69: 1500\x89\x7e (\x90\xc5\x8d\x9e)");
+        assert_eq!(
+            kinds,
+            [
+                SegmentKind::Data(Mode::Kanji),
+                SegmentKind::Data(Mode::Alphanumeric),
+                SegmentKind::Data(Mode::Kanji),
+                SegmentKind::Data(Mode::Byte),
+                SegmentKind::Data(Mode::Kanji),
+                SegmentKind::Data(Mode::Byte),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf_8() {
+        let text = "Grüße, мир! 👋 日本 0123456789012";
+        let (data, kinds) = round_trip::<Version10<EcLevelL>>(text);
+        assert_eq!(data, text.as_bytes());
+        assert_eq!(kinds, [SegmentKind::Eci(26), SegmentKind::Data(Mode::Byte), SegmentKind::Data(Mode::Numeric)]);
+
+        // The euro sign is in neither ISO-8859-1 nor JIS X 0208.
+        let (data, kinds) = round_trip::<Version2<EcLevelL>>("1 €");
+        assert_eq!(data, "1 €".as_bytes());
+        assert_eq!(kinds, [SegmentKind::Eci(26), SegmentKind::Data(Mode::Byte)]);
+    }
+
+    #[test]
+    fn test_eci_cost() {
+        // Alternating with letters, every kanji is a segment of its own:
+        // 4 byte segments of 20 bits and 3 kanji segments of 25 bits.
+        // UTF-8 takes the designator and one byte segment of 13 bytes.
+        let text = "a点b点c点d";
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        assert_eq!(bits.push_str(text), Ok(()));
+        assert_eq!(bits.len(), 4 + 8 + 4 + 8 + 13 * 8);
+        let (data, kinds) = round_trip::<Version1<EcLevelL>>(text);
+        assert_eq!(data, text.as_bytes());
+        assert_eq!(kinds, [SegmentKind::Eci(26), SegmentKind::Data(Mode::Byte)]);
+
+        // Kanji in one run stay in kanji mode: 36 + 38 + 36 bits against
+        // 12 + 4 + 8 + 12 * 8 bits. Without counting the designator, UTF-8
+        // would have won.
+        let (data, kinds) = round_trip::<Version1<EcLevelL>>("abc点茗def");
+        assert_eq!(data, b"abc\x93\x5f\xe4\xaadef");
+        let byte = SegmentKind::Data(Mode::Byte);
+        assert_eq!(kinds, [byte, SegmentKind::Data(Mode::Kanji), byte]);
+    }
+
+    #[test]
+    fn test_too_long() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        // 4 + 8 + 17 * 8 bits of ISO-8859-1.
+        assert_eq!(bits.push_str("ééééééééééééééééé"), Ok(()));
+        assert_eq!(bits.push_str("é"), Err(QrError::DataTooLong { needed: 148 + 20, available: 152 }));
+        assert_eq!(bits.len(), 148);
+
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        // The ECI designator and 20 bytes of UTF-8.
+        let needed = 4 + 8 + 4 + 8 + 20 * 8;
+        assert_eq!(bits.push_str("👋👋👋👋👋"), Err(QrError::DataTooLong { needed, available: 152 }));
+
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        let needed = 4 + 8 + 20 * 8;
+        let result = bits.push_str_with("👋👋👋👋👋", ByteCharset::Utf8);
+        assert_eq!(result, Err(QrError::DataTooLong { needed, available: 152 }));
+    }
+
+    #[test]
+    fn test_assume_utf_8() {
+        let text = "Grüße, мир! 👋 日本 0123456789012";
+        let mut bits = Bits::<Version10<EcLevelL>>::new();
+        bits.push_str_with(text, ByteCharset::Utf8).unwrap();
+        bits.push_terminator().unwrap();
+        let mut out = [0; 512];
+        let mut kinds = Vec::new();
+        let len = parse_segments(Version::Normal(10), bits.bytes(), &mut out, |s| kinds.push(s.kind)).unwrap();
+        assert_eq!(&out[..len], text.as_bytes());
+        assert_eq!(kinds, [SegmentKind::Data(Mode::Byte), SegmentKind::Data(Mode::Numeric)]);
+
+        // ASCII is the same in both character sets.
+        let mut latin_1 = Bits::<Version2<EcLevelL>>::new();
+        latin_1.push_str("HELLO WORLD 0123456789 hello").unwrap();
+        let mut utf_8 = Bits::<Version2<EcLevelL>>::new();
+        utf_8.push_str_with("HELLO WORLD 0123456789 hello", ByteCharset::Utf8).unwrap();
+        assert_eq!(latin_1.into_bytes(), utf_8.into_bytes());
     }
 }
 
 
-//}}}
\ No newline at end of file
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Segment diagnostics
+
+/// How one segment of a `SegmentCheck` encodes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SegmentUsage {
+    /// The segment.
+    pub segment: Segment,
+
+    /// The number of bits of the mode indicator and character count.
+    pub header_bits: usize,
+
+    /// The number of bits of the encoded characters.
+    pub data_bits: usize,
+
+    /// The bit position right after the segment, counting the bits pushed
+    /// before the check and all segments before this one.
+    pub end: usize,
+
+    /// Whether the segment ends within the data capacity.
+    pub fits: bool,
+
+    /// What is wrong with the data of the segment, if anything:
+    /// `QrError::OutOfBounds` if its range is not within the data, or
+    /// `QrError::InvalidCharacter { .. }` with the index into the whole data
+    /// if a character cannot be encoded in its mode.
+    pub error: Option<QrError>,
+}
+
+/// Diagnostics of a segment list against the capacity of a QR code, from
+/// `Bits::check_segments`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SegmentCheck<'a> {
+    data: &'a [u8],
+    segments: &'a [Segment],
+    version: Version,
+    start: usize,
+
+    /// The number of bits of all mode indicators and character counts.
+    pub header_bits: usize,
+
+    /// The number of bits of all encoded characters.
+    pub data_bits: usize,
+
+    /// The number of data bits the QR code can hold, including those pushed
+    /// before the check.
+    pub capacity: usize,
+
+    /// The index of the first segment which does not fit.
+    pub overflow: Option<usize>,
+
+    /// The index of the first segment whose data is invalid, see
+    /// `SegmentUsage::error`.
+    pub invalid: Option<usize>,
+}
+
+impl<'a> SegmentCheck<'a> {
+    /// The number of bits needed in total, including those pushed before the
+    /// check.
+    pub fn total_bits(&self) -> usize {
+        self.start + self.header_bits + self.data_bits
+    }
+
+    /// The error `Bits::push_segments` would return for the segments, if any.
+    pub fn result(&self) -> QrResult<()> {
+        if let Some(index) = self.invalid {
+            if let Some(error) = self.usage().nth(index).and_then(|usage| usage.error) {
+                return Err(error);
+            }
+        }
+        match self.overflow {
+            Some(_) => Err(QrError::DataTooLong { needed: self.total_bits(), available: self.capacity }),
+            None => Ok(()),
+        }
+    }
+
+    /// Iterates how every segment encodes, in order.
+    pub fn usage(&self) -> impl Iterator<Item = SegmentUsage> + 'a {
+        let (data, version, capacity) = (self.data, self.version, self.capacity);
+        let mut end = self.start;
+        self.segments.iter().map(move |&segment| {
+            let usage = segment_usage(data, segment, version, end, capacity);
+            end = usage.end;
+            usage
+        })
+    }
+}
+
+/// Measures a segment starting at bit position `start`.
+fn segment_usage(data: &[u8], segment: Segment, version: Version, start: usize, capacity: usize) -> SegmentUsage {
+    let header_bits = version.mode_bits_count() + segment.mode.length_bits_count(version);
+    let data_bits = segment.encoded_len(version) - header_bits;
+    let end = start + header_bits + data_bits;
+    let error = match data.get(segment.begin..segment.end) {
+        None => Some(QrError::OutOfBounds),
+        Some(slice) => match segment.mode {
+            Mode::Numeric => check_characters(slice, |b| b.is_ascii_digit()),
+            Mode::Alphanumeric => check_characters(slice, |b| b == b'0' || alphanumeric_digit(b) != 0),
+            Mode::Byte => Ok(()),
+            Mode::Kanji => check_kanji(slice),
+        }
+        .err()
+        .map(|error| match error {
+            QrError::InvalidCharacter { index, byte } => QrError::InvalidCharacter { index: segment.begin + index, byte },
+            error => error,
+        }),
+    };
+    SegmentUsage { segment, header_bits, data_bits, end, fits: end <= capacity, error }
+}
+
+impl<V: QrSpec> Bits<V> {
+    /// Checks segments against the remaining capacity before pushing them
+    /// with `push_segments`, reporting the bits taken by every segment and
+    /// which one overflows, rather than just whether they fit.
+    ///
+    ///     use qrcode::bits::Bits;
+    ///     use qrcode::optimize::Segment;
+    ///     use qrcode::spec::{Version1, EcLevelH};
+    ///     use qrcode::types::Mode;
+    ///
+    ///     let data = b"0123456789ABCDEFGHIJ";
+    ///     let segments = [
+    ///         Segment { mode: Mode::Numeric, begin: 0, end: 10 },
+    ///         Segment { mode: Mode::Alphanumeric, begin: 10, end: 20 },
+    ///     ];
+    ///     let check = Bits::<Version1<EcLevelH>>::new().check_segments(data, &segments);
+    ///     assert_eq!((check.header_bits, check.data_bits), (4 + 10 + 4 + 9, 34 + 55));
+    ///     assert_eq!((check.total_bits(), check.capacity), (116, 72));
+    ///     assert_eq!(check.overflow, Some(1));
+    ///
+    ///     let first = check.usage().next().unwrap();
+    ///     assert_eq!((first.end, first.fits), (48, true));
+    ///
+    /// Segments are measured as they are given, without merging byte segments
+    /// like `push_byte_data`, so the result is exact for `push_byte_segment`
+    /// and may overestimate `push_segments` by a header.
+    pub fn check_segments<'a>(&self, data: &'a [u8], segments: &'a [Segment]) -> SegmentCheck<'a> {
+        let capacity = self.max_len(V::EC_LEVEL).unwrap_or(0);
+        let mut check = SegmentCheck {
+            data,
+            segments,
+            version: V::VERSION,
+            start: self.len(),
+            header_bits: 0,
+            data_bits: 0,
+            capacity,
+            overflow: None,
+            invalid: None,
+        };
+        for (index, usage) in check.usage().enumerate() {
+            check.header_bits += usage.header_bits;
+            check.data_bits += usage.data_bits;
+            if !usage.fits && check.overflow.is_none() {
+                check.overflow = Some(index);
+            }
+            if usage.error.is_some() && check.invalid.is_none() {
+                check.invalid = Some(index);
+            }
+        }
+        check
+    }
+}
+
+#[cfg(test)]
+mod segment_check_tests {
+    use crate::bits::{Bits, SegmentUsage};
+    use crate::optimize::{Parser, Segment};
+    use crate::spec::{EcLevelL, EcLevelM, QrSpec, Version1, Version10};
+    use crate::types::{Mode, QrError};
+
+    #[test]
+    fn test_matches_push_segments() {
+        fn check<V: QrSpec>(data: &[u8]) {
+            let segments: Vec<Segment> = Parser::new(data).collect();
+            let check = Bits::<V>::new().check_segments(data, &segments);
+            let mut bits = Bits::<V>::new();
+            let result = bits.push_segments(data, segments.iter().copied());
+            assert_eq!(check.result(), result);
+            if result.is_ok() {
+                assert_eq!(check.total_bits(), bits.len());
+            }
+        }
+        check::<Version1<EcLevelM>>(b"0123456789ABCDEF");
+        check::<Version1<EcLevelM>>(b"0123456789ABCDEF0123456789ABCDEF");
+        check::<Version10<EcLevelL>>(b"Hello, 0123456789 WORLD");
+    }
+
+    #[test]
+    fn test_usage() {
+        let data = b"HELLO\x93\x5f01234567";
+        let segments = [
+            Segment { mode: Mode::Alphanumeric, begin: 0, end: 5 },
+            Segment { mode: Mode::Kanji, begin: 5, end: 7 },
+            Segment { mode: Mode::Numeric, begin: 7, end: 15 },
+        ];
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_eci_designator(20).unwrap();
+        let check = bits.check_segments(data, &segments);
+        assert_eq!(check.capacity, 152);
+        assert_eq!(check.total_bits(), 12 + (13 + 28) + (12 + 13) + (14 + 27));
+        assert_eq!((check.overflow, check.invalid), (None, None));
+        let usage: Vec<SegmentUsage> = check.usage().collect();
+        assert_eq!(usage[1].header_bits, 4 + 8);
+        assert_eq!(usage[1].end, 12 + 41 + 25);
+        assert!(usage.iter().all(|u| u.fits && u.error.is_none()));
+        assert_eq!(check.result(), Ok(()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let data = b"0123A5678";
+        let segments = [
+            Segment { mode: Mode::Numeric, begin: 0, end: 9 },
+            Segment { mode: Mode::Kanji, begin: 7, end: 9 },
+            Segment { mode: Mode::Byte, begin: 5, end: 12 },
+        ];
+        let check = Bits::<Version1<EcLevelL>>::new().check_segments(data, &segments);
+        let errors: Vec<_> = check.usage().map(|u| u.error).collect();
+        assert_eq!(
+            errors,
+            [
+                Some(QrError::InvalidCharacter { index: 4, byte: b'A' }),
+                Some(QrError::InvalidCharacter { index: 7, byte: b'7' }),
+                Some(QrError::OutOfBounds)
+            ]
+        );
+        assert_eq!(check.invalid, Some(0));
+        assert_eq!(check.result(), Err(QrError::InvalidCharacter { index: 4, byte: b'A' }));
+    }
+}
+
+//}}}
@@ -7,13 +7,16 @@
 //!     let mut c = Canvas::<Version1<EcLevelL>>::new();
 //!     c.draw_all_functional_patterns();
 //!     c.draw_data(b"data_here", b"ec_code_here");
-//!     c.apply_mask(MaskPattern::Checkerboard);
+//!     c.apply_mask(MaskPattern::Checkerboard).unwrap();
 
 use core::cmp::max;
+use core::ops::{BitAnd, BitOr, BitXor, Deref, Not, Range};
 
 use crate::cast::As;
+use crate::ec::Codewords;
 use crate::spec::QrSpec;
-use crate::types::{Color, EcLevel, Version};
+use crate::storage::Buffer;
+use crate::types::{Color, EcLevel, QrError, QrResult, Version};
 
 use heapless::Vec;
 
@@ -40,8 +43,6 @@ impl From<Module> for Color {
 }
 
 impl Module {
-    const EMPTY: Module = Module::Unmasked(Color::Light);
-
     /// Checks whether a module is dark.
     pub fn is_dark(self) -> bool {
         Color::from(self) == Color::Dark
@@ -52,8 +53,7 @@ impl Module {
             0 => Module::Unmasked(Color::Light),
             1 => Module::Unmasked(Color::Dark),
             2 => Module::Masked(Color::Light),
-            3 => Module::Masked(Color::Dark),
-            _ => unreachable!(),
+            _ => Module::Masked(Color::Dark),
         }
     }
 
@@ -91,7 +91,7 @@ impl Module {
             }
         }
 
-        iter.flat_map(|x| U82bitIter(x, 0)).map(|m| Module::from_bits(m)).take(len)
+        iter.flat_map(|x| U82bitIter(x, 0)).map(Module::from_bits).take(len)
     }
 
     /// Apply a mask to the unmasked modules.
@@ -122,7 +122,7 @@ impl Module {
 pub struct Canvas<V: QrSpec> {
     /// The modules of the QR code. Modules are arranged in left-to-right, then
     /// top-to-bottom order.
-    modules: Vec<u8, V::CanvasSize>,
+    modules: Buffer<V::CanvasSize>,
 }
 
 impl<V: QrSpec> Clone for Canvas<V> {
@@ -131,14 +131,38 @@ impl<V: QrSpec> Clone for Canvas<V> {
     }
 }
 
+impl<V: QrSpec> Default for Canvas<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<V: QrSpec> Canvas<V> {
     /// Constructs a new canvas big enough for a QR code of the given version.
     pub fn new() -> Self {
-        let mut modules = Vec::new();
-        modules.resize((V::WIDTH * V::WIDTH).as_usize() / 4 + 1, 0).unwrap();
+        let mut modules = Buffer::new();
+        modules.fill_zeroes((V::WIDTH * V::WIDTH).as_usize() / 4 + 1);
         Self { modules }
     }
 
+    /// Constructs a canvas without modules, to be `reset` before drawing.
+    pub(crate) const fn empty() -> Self {
+        Self { modules: Buffer::new() }
+    }
+
+    /// Clears all modules in place, like a canvas from `Canvas::new`.
+    pub(crate) fn reset(&mut self) {
+        self.modules.fill_zeroes((V::WIDTH * V::WIDTH).as_usize() / 4 + 1);
+    }
+
+    /// Copies all modules of `other` in place.
+    pub(crate) fn copy_from(&mut self, other: &Self) {
+        if self.modules.len() != other.modules.len() {
+            self.modules.fill_zeroes(other.modules.len());
+        }
+        self.modules.copy_from_slice(&other.modules);
+    }
+
     /// Converts the canvas into a human-readable string.
     #[cfg(test)]
     pub(crate) fn to_debug_str(&self) -> String {
@@ -159,14 +183,30 @@ impl<V: QrSpec> Canvas<V> {
     }
 
     fn coords_to_index(&self, x: i16, y: i16) -> (usize, u8) {
+        Self::check_coords(x, y).expect("coordinates outside of the symbol");
         let x = if x < 0 { x + V::WIDTH } else { x }.as_usize();
         let y = if y < 0 { y + V::WIDTH } else { y }.as_usize();
         let index = y * V::WIDTH.as_usize() + x;
         (index / 4, (index % 4) as u8)
     }
 
+    /// Checks that the coordinates are inside of the symbol, counting negative
+    /// coordinates from the right or bottom edge.
+    fn check_coords(x: i16, y: i16) -> QrResult<()> {
+        let range = -V::WIDTH..V::WIDTH;
+        if range.contains(&x) && range.contains(&y) {
+            Ok(())
+        } else {
+            Err(QrError::OutOfBounds)
+        }
+    }
+
     /// Obtains a module at the given coordinates. For convenience, negative
     /// coordinates will wrap around.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the symbol. See `try_get`.
     pub fn get(&self, x: i16, y: i16) -> Module {
         let (index, sub_index) = self.coords_to_index(x, y);
         Module::from_u8(self.modules[index], sub_index)
@@ -174,6 +214,10 @@ impl<V: QrSpec> Canvas<V> {
 
     /// Sets the color of a functional module at the given coordinates. For
     /// convenience, negative coordinates will wrap around.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the symbol. See `try_put`.
     pub fn put(&mut self, x: i16, y: i16, color: Color) {
         let (index, sub_index) = self.coords_to_index(x, y);
         Module::Masked(color).write(&mut self.modules[index], sub_index);
@@ -181,16 +225,45 @@ impl<V: QrSpec> Canvas<V> {
 
     /// Sets the color of a functional module at the given coordinates. For
     /// convenience, negative coordinates will wrap around.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the symbol. See
+    /// `try_put_unmasked`.
     pub fn put_unmasked(&mut self, x: i16, y: i16, color: Color) {
         let (index, sub_index) = self.coords_to_index(x, y);
         Module::Unmasked(color).write(&mut self.modules[index], sub_index);
     }
+
+    /// Obtains a module like `get`, but returns `Err(QrError::OutOfBounds)`
+    /// if the coordinates are outside of the symbol.
+    pub fn try_get(&self, x: i16, y: i16) -> QrResult<Module> {
+        Self::check_coords(x, y)?;
+        Ok(self.get(x, y))
+    }
+
+    /// Sets a module like `put`, but returns `Err(QrError::OutOfBounds)` if
+    /// the coordinates are outside of the symbol.
+    pub fn try_put(&mut self, x: i16, y: i16, color: Color) -> QrResult<()> {
+        Self::check_coords(x, y)?;
+        self.put(x, y, color);
+        Ok(())
+    }
+
+    /// Sets a module like `put_unmasked`, but returns
+    /// `Err(QrError::OutOfBounds)` if the coordinates are outside of the
+    /// symbol.
+    pub fn try_put_unmasked(&mut self, x: i16, y: i16, color: Color) -> QrResult<()> {
+        Self::check_coords(x, y)?;
+        self.put_unmasked(x, y, color);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod basic_canvas_tests {
     use crate::canvas::{Canvas, Module};
-    use crate::types::Color;
+    use crate::types::{Color, QrError};
     use crate::spec::{Version1, EcLevelL};
 
     #[test]
@@ -208,6 +281,26 @@ mod basic_canvas_tests {
         assert_eq!(c.get(-1, 21 - 7), Module::Masked(Color::Light));
     }
 
+    #[test]
+    fn test_checked_index() {
+        let mut c = Canvas::<Version1<EcLevelL>>::new();
+        assert_eq!(c.try_put(-21, 20, Color::Dark), Ok(()));
+        assert_eq!(c.try_get(0, -1), Ok(Module::Masked(Color::Dark)));
+        assert_eq!(c.try_put_unmasked(3, 3, Color::Dark), Ok(()));
+
+        assert_eq!(c.try_get(21, 0), Err(QrError::OutOfBounds));
+        assert_eq!(c.try_get(0, -22), Err(QrError::OutOfBounds));
+        assert_eq!(c.try_put(i16::MAX, 0, Color::Dark), Err(QrError::OutOfBounds));
+        assert_eq!(c.try_put_unmasked(0, i16::MIN, Color::Dark), Err(QrError::OutOfBounds));
+    }
+
+    #[test]
+    #[should_panic(expected = "coordinates outside of the symbol")]
+    fn test_get_past_width() {
+        // Without the check, this would read the first module of row 1.
+        Canvas::<Version1<EcLevelL>>::new().get(21, 0);
+    }
+
     #[test]
     fn test_debug_str() {
         let mut c = Canvas::<Version1<EcLevelL>>::new();
@@ -265,7 +358,7 @@ impl<V: QrSpec> Canvas<V> {
         for j in dy_top..=dy_bottom {
             for i in dx_left..=dx_right {
                 self.put(x + i, y + j, {
-                    #[cfg_attr(feature = "cargo-clippy", allow(match_same_arms))]
+                    #[allow(clippy::match_same_arms)]
                     match (i, j) {
                         (4, _) | (_, 4) | (-4, _) | (_, -4) => Color::Light,
                         (3, _) | (_, 3) | (-3, _) | (_, -3) => Color::Dark,
@@ -384,12 +477,13 @@ impl<V: QrSpec> Canvas<V> {
     /// to help the scanner create the square grid.
     fn draw_alignment_patterns(&mut self) {
         match V::VERSION {
-            Version::Micro(_) | Version::Normal(1) => {}
-            Version::Normal(2..=6) => self.draw_alignment_pattern_at(-7, -7),
+            Version::Micro(_) => {}
             Version::Normal(a) => {
-                let positions = ALIGNMENT_PATTERN_POSITIONS[(a - 7).as_usize()];
-                for x in positions.iter() {
-                    for y in positions.iter() {
+                let (positions, len) = alignment_pattern_positions(a);
+                // `draw_alignment_pattern_at` skips the positions next to
+                // the finder patterns, which are drawn already.
+                for x in positions[..len].iter() {
+                    for y in positions[..len].iter() {
                         self.draw_alignment_pattern_at(*x, *y);
                     }
                 }
@@ -533,45 +627,95 @@ mod alignment_pattern_tests {
 }
 
 
-/// `ALIGNMENT_PATTERN_POSITIONS` describes the x- and y-coordinates of the
-/// center of the alignment patterns. Since the QR code is symmetric, only one
-/// coordinate is needed.
-static ALIGNMENT_PATTERN_POSITIONS: [&'static [i16]; 34] = [
-    &[6, 22, 38],
-    &[6, 24, 42],
-    &[6, 26, 46],
-    &[6, 28, 50],
-    &[6, 30, 54],
-    &[6, 32, 58],
-    &[6, 34, 62],
-    &[6, 26, 46, 66],
-    &[6, 26, 48, 70],
-    &[6, 26, 50, 74],
-    &[6, 30, 54, 78],
-    &[6, 30, 56, 82],
-    &[6, 30, 58, 86],
-    &[6, 34, 62, 90],
-    &[6, 28, 50, 72, 94],
-    &[6, 26, 50, 74, 98],
-    &[6, 30, 54, 78, 102],
-    &[6, 28, 54, 80, 106],
-    &[6, 32, 58, 84, 110],
-    &[6, 30, 58, 86, 114],
-    &[6, 34, 62, 90, 118],
-    &[6, 26, 50, 74, 98, 122],
-    &[6, 30, 54, 78, 102, 126],
-    &[6, 26, 52, 78, 104, 130],
-    &[6, 30, 56, 82, 108, 134],
-    &[6, 34, 60, 86, 112, 138],
-    &[6, 30, 58, 86, 114, 142],
-    &[6, 34, 62, 90, 118, 146],
-    &[6, 30, 54, 78, 102, 126, 150],
-    &[6, 24, 50, 76, 102, 128, 154],
-    &[6, 28, 54, 80, 106, 132, 158],
-    &[6, 32, 58, 84, 110, 136, 162],
-    &[6, 26, 54, 82, 110, 138, 166],
-    &[6, 30, 58, 86, 114, 142, 170],
-];
+/// The x- and y-coordinates of the centers of the alignment patterns of a QR
+/// code version, in the first `len` entries of the returned array. Since the
+/// QR code is symmetric, only one coordinate is needed. The positions next to
+/// the finder patterns are included, but hold no alignment pattern.
+///
+/// ISO/IEC 18004:2006 lists the positions in Annex E. They start at 6 and end
+/// at 7 modules before the edge, and the rest are spread evenly from the end,
+/// with an even step rounded up, so that any surplus goes into the first gap.
+/// Version 32 is the one exception, whose step would be 28 but is 26.
+///
+/// Returns an empty list for versions outside of 2 to 40.
+pub(crate) const fn alignment_pattern_positions(version: i16) -> ([i16; 7], usize) {
+    let mut positions = [0; 7];
+    if version < 2 || version > 40 {
+        return (positions, 0);
+    }
+    let len = version / 7 + 2;
+    let last = version * 4 + 10;
+    let step = if version == 32 { 26 } else { (version * 4 + len * 2 + 1) / (len * 2 - 2) * 2 };
+    positions[0] = 6;
+    let mut i = 1;
+    while i < len {
+        positions[i as usize] = last - (len - 1 - i) * step;
+        i += 1;
+    }
+    (positions, len as usize)
+}
+
+#[cfg(test)]
+mod alignment_pattern_positions_tests {
+    use crate::canvas::alignment_pattern_positions;
+
+    /// The table of ISO/IEC 18004:2006, Annex E, for versions 7 to 40.
+    static ALIGNMENT_PATTERN_POSITIONS: [&[i16]; 34] = [
+        &[6, 22, 38],
+        &[6, 24, 42],
+        &[6, 26, 46],
+        &[6, 28, 50],
+        &[6, 30, 54],
+        &[6, 32, 58],
+        &[6, 34, 62],
+        &[6, 26, 46, 66],
+        &[6, 26, 48, 70],
+        &[6, 26, 50, 74],
+        &[6, 30, 54, 78],
+        &[6, 30, 56, 82],
+        &[6, 30, 58, 86],
+        &[6, 34, 62, 90],
+        &[6, 28, 50, 72, 94],
+        &[6, 26, 50, 74, 98],
+        &[6, 30, 54, 78, 102],
+        &[6, 28, 54, 80, 106],
+        &[6, 32, 58, 84, 110],
+        &[6, 30, 58, 86, 114],
+        &[6, 34, 62, 90, 118],
+        &[6, 26, 50, 74, 98, 122],
+        &[6, 30, 54, 78, 102, 126],
+        &[6, 26, 52, 78, 104, 130],
+        &[6, 30, 56, 82, 108, 134],
+        &[6, 34, 60, 86, 112, 138],
+        &[6, 30, 58, 86, 114, 142],
+        &[6, 34, 62, 90, 118, 146],
+        &[6, 30, 54, 78, 102, 126, 150],
+        &[6, 24, 50, 76, 102, 128, 154],
+        &[6, 28, 54, 80, 106, 132, 158],
+        &[6, 32, 58, 84, 110, 136, 162],
+        &[6, 26, 54, 82, 110, 138, 166],
+        &[6, 30, 58, 86, 114, 142, 170],
+    ];
+
+    #[test]
+    fn test_against_table() {
+        for version in 2..=6 {
+            let (positions, len) = alignment_pattern_positions(version);
+            assert_eq!(&positions[..len], &[6, version * 4 + 10]);
+        }
+        for (version, expected) in (7..=40).zip(ALIGNMENT_PATTERN_POSITIONS.iter()) {
+            let (positions, len) = alignment_pattern_positions(version);
+            assert_eq!(&positions[..len], *expected, "version {}", version);
+        }
+    }
+
+    #[test]
+    fn test_without_alignment_patterns() {
+        for version in [i16::MIN, -1, 0, 1, 41, i16::MAX] {
+            assert_eq!(alignment_pattern_positions(version).1, 0);
+        }
+    }
+}
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Timing patterns
@@ -700,17 +844,7 @@ impl<V: QrSpec> Canvas<V> {
 
     /// Draws the format info patterns for an encoded number.
     fn draw_format_info_patterns_with_number(&mut self, format_info: u16) {
-        let format_info = u32::from(format_info);
-        match V::VERSION {
-            Version::Micro(_) => {
-                self.draw_number(format_info, 15, Color::Dark, Color::Light, &FORMAT_INFO_COORDS_MICRO_QR);
-            }
-            Version::Normal(_) => {
-                self.draw_number(format_info, 15, Color::Dark, Color::Light, &FORMAT_INFO_COORDS_QR_MAIN);
-                self.draw_number(format_info, 15, Color::Dark, Color::Light, &FORMAT_INFO_COORDS_QR_SIDE);
-                self.put(8, -8, Color::Dark); // Dark module.
-            }
-        }
+        for_each_format_info_module::<V, _>(format_info, |x, y, color| self.put(x, y, color));
     }
 
     /// Reserves area to put in the format information.
@@ -721,11 +855,9 @@ impl<V: QrSpec> Canvas<V> {
     /// Draws the version information patterns.
     fn draw_version_info_patterns(&mut self) {
         match V::VERSION {
-            Version::Micro(_) | Version::Normal(1..=6) => {
-                return;
-            }
+            Version::Micro(_) | Version::Normal(1..=6) => {}
             Version::Normal(a) => {
-                let version_info = VERSION_INFOS[(a - 7).as_usize()];
+                let version_info = encode_version_info(a as u8);
                 self.draw_number(version_info, 18, Color::Dark, Color::Light, &VERSION_INFO_COORDS_BL);
                 self.draw_number(version_info, 18, Color::Dark, Color::Light, &VERSION_INFO_COORDS_TR);
             }
@@ -733,6 +865,25 @@ impl<V: QrSpec> Canvas<V> {
     }
 }
 
+/// Calls `put` with the coordinates and color of every module of the format
+/// info patterns for an encoded number, including the dark module of normal
+/// QR codes.
+fn for_each_format_info_module<V: QrSpec, F: FnMut(i16, i16, Color)>(format_info: u16, mut put: F) {
+    let copies: &[&[(i16, i16)]] = match V::VERSION {
+        Version::Micro(_) => &[&FORMAT_INFO_COORDS_MICRO_QR],
+        Version::Normal(_) => &[&FORMAT_INFO_COORDS_QR_MAIN, &FORMAT_INFO_COORDS_QR_SIDE],
+    };
+    for coords in copies {
+        for (i, &(x, y)) in coords.iter().enumerate() {
+            let color = if format_info >> (14 - i) & 1 == 0 { Color::Light } else { Color::Dark };
+            put(x, y, color);
+        }
+    }
+    if let Version::Normal(_) = V::VERSION {
+        put(8, -8, Color::Dark); // Dark module.
+    }
+}
+
 #[cfg(test)]
 mod draw_version_info_tests {
     use crate::canvas::Canvas;
@@ -900,7 +1051,7 @@ mod draw_version_info_tests {
 }
 
 
-static VERSION_INFO_COORDS_BL: [(i16, i16); 18] = [
+pub(crate) static VERSION_INFO_COORDS_BL: [(i16, i16); 18] = [
     (5, -9),
     (5, -10),
     (5, -11),
@@ -921,7 +1072,7 @@ static VERSION_INFO_COORDS_BL: [(i16, i16); 18] = [
     (0, -11),
 ];
 
-static VERSION_INFO_COORDS_TR: [(i16, i16); 18] = [
+pub(crate) static VERSION_INFO_COORDS_TR: [(i16, i16); 18] = [
     (-9, 5),
     (-10, 5),
     (-11, 5),
@@ -942,7 +1093,7 @@ static VERSION_INFO_COORDS_TR: [(i16, i16); 18] = [
     (-11, 0),
 ];
 
-static FORMAT_INFO_COORDS_QR_MAIN: [(i16, i16); 15] = [
+pub(crate) static FORMAT_INFO_COORDS_QR_MAIN: [(i16, i16); 15] = [
     (0, 8),
     (1, 8),
     (2, 8),
@@ -960,7 +1111,7 @@ static FORMAT_INFO_COORDS_QR_MAIN: [(i16, i16); 15] = [
     (8, 0),
 ];
 
-static FORMAT_INFO_COORDS_QR_SIDE: [(i16, i16); 15] = [
+pub(crate) static FORMAT_INFO_COORDS_QR_SIDE: [(i16, i16); 15] = [
     (8, -1),
     (8, -2),
     (8, -3),
@@ -996,12 +1147,6 @@ static FORMAT_INFO_COORDS_MICRO_QR: [(i16, i16); 15] = [
     (8, 1),
 ];
 
-static VERSION_INFOS: [u32; 34] = [
-    0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928, 0x10b78, 0x1145d, 0x12a17,
-    0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4, 0x191e1, 0x1afab, 0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75,
-    0x1f250, 0x209d5, 0x216f0, 0x228ba, 0x2379f, 0x24b0b, 0x2542e, 0x26a64, 0x27541, 0x28c69,
-];
-
 //}}}
 //------------------------------------------------------------------------------
 //{{{ All functional patterns before data placement
@@ -1023,8 +1168,28 @@ impl<V: QrSpec> Canvas<V> {
 }
 
 /// Gets whether the module at the given coordinates represents a functional
-/// module.
-pub fn is_functional(version: Version, width: i16, x: i16, y: i16) -> bool {
+/// module. For convenience, negative coordinates will wrap around.
+///
+/// Returns `Err(QrError::InvalidWidth)` if `width` is not the width of
+/// `version` or the version does not exist, and `Err(QrError::OutOfBounds)`
+/// if the coordinates are outside of the symbol.
+pub fn is_functional(version: Version, width: i16, x: i16, y: i16) -> QrResult<bool> {
+    let valid = match version {
+        Version::Normal(a) => (1..=40).contains(&a),
+        Version::Micro(a) => (1..=4).contains(&a),
+    };
+    if !valid || width != version.width() {
+        return Err(QrError::InvalidWidth(width.max(0).as_usize()));
+    }
+    if !(-width..width).contains(&x) || !(-width..width).contains(&y) {
+        return Err(QrError::OutOfBounds);
+    }
+    Ok(is_functional_unchecked(version, width, x, y))
+}
+
+/// Gets whether the module at the given coordinates represents a functional
+/// module, like `is_functional`, for a valid version and coordinates.
+pub(crate) fn is_functional_unchecked(version: Version, width: i16, x: i16, y: i16) -> bool {
     debug_assert!(width == version.width());
 
     let x = if x < 0 { x + width } else { x };
@@ -1039,13 +1204,10 @@ pub fn is_functional(version: Version, width: i16, x: i16, y: i16) -> bool {
                     (x >= width-8 && y < 9); // Top-right finder pattern
             if non_alignment_test {
                 true
-            } else if a == 1 {
-                false
-            } else if 2 <= a && a <= 6 {
-                (width - 7 - x).abs() <= 2 && (width - 7 - y).abs() <= 2
             } else {
-                let positions = ALIGNMENT_PATTERN_POSITIONS[(a - 7).as_usize()];
-                let last = positions.len() - 1;
+                let (positions, len) = alignment_pattern_positions(a);
+                let positions = &positions[..len];
+                let last = len.saturating_sub(1);
                 for (i, align_x) in positions.iter().enumerate() {
                     for (j, align_y) in positions.iter().enumerate() {
                         if i == 0 && (j == 0 || j == last) || (i == last && j == 0) {
@@ -1065,7 +1227,7 @@ pub fn is_functional(version: Version, width: i16, x: i16, y: i16) -> bool {
 #[cfg(test)]
 mod all_functional_patterns_tests {
     use crate::canvas::{is_functional, Canvas};
-    use crate::types::{Version};
+    use crate::types::{QrError, Version};
     use crate::spec::{Version2, EcLevelL};
 
     #[test]
@@ -1127,50 +1289,62 @@ mod all_functional_patterns_tests {
     #[test]
     fn test_is_functional_qr_1() {
         let version = Version::Normal(1);
-        assert!(is_functional(version, version.width(), 0, 0));
-        assert!(is_functional(version, version.width(), 10, 6));
-        assert!(!is_functional(version, version.width(), 10, 5));
-        assert!(!is_functional(version, version.width(), 14, 14));
-        assert!(is_functional(version, version.width(), 6, 11));
-        assert!(!is_functional(version, version.width(), 4, 11));
-        assert!(is_functional(version, version.width(), 4, 13));
-        assert!(is_functional(version, version.width(), 17, 7));
-        assert!(!is_functional(version, version.width(), 17, 17));
+        assert_eq!(is_functional(version, version.width(), 0, 0), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 10, 6), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 10, 5), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 14, 14), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 6, 11), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 4, 11), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 4, 13), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 17, 7), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 17, 17), Ok(false));
     }
 
     #[test]
     fn test_is_functional_qr_3() {
         let version = Version::Normal(3);
-        assert!(is_functional(version, version.width(), 0, 0));
-        assert!(!is_functional(version, version.width(), 25, 24));
-        assert!(is_functional(version, version.width(), 24, 24));
-        assert!(!is_functional(version, version.width(), 9, 25));
-        assert!(!is_functional(version, version.width(), 20, 0));
-        assert!(is_functional(version, version.width(), 21, 0));
+        assert_eq!(is_functional(version, version.width(), 0, 0), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 25, 24), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 24, 24), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 9, 25), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 20, 0), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 21, 0), Ok(true));
     }
 
     #[test]
     fn test_is_functional_qr_7() {
         let version = Version::Normal(7);
-        assert!(is_functional(version, version.width(), 21, 4));
-        assert!(is_functional(version, version.width(), 7, 21));
-        assert!(is_functional(version, version.width(), 22, 22));
-        assert!(is_functional(version, version.width(), 8, 8));
-        assert!(!is_functional(version, version.width(), 19, 5));
-        assert!(!is_functional(version, version.width(), 36, 3));
-        assert!(!is_functional(version, version.width(), 4, 36));
-        assert!(is_functional(version, version.width(), 38, 38));
+        assert_eq!(is_functional(version, version.width(), 21, 4), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 7, 21), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 22, 22), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 8, 8), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 19, 5), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 36, 3), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 4, 36), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 38, 38), Ok(true));
     }
 
     #[test]
     fn test_is_functional_micro() {
         let version = Version::Micro(1);
-        assert!(is_functional(version, version.width(), 8, 0));
-        assert!(is_functional(version, version.width(), 10, 0));
-        assert!(!is_functional(version, version.width(), 10, 1));
-        assert!(is_functional(version, version.width(), 8, 8));
-        assert!(is_functional(version, version.width(), 0, 9));
-        assert!(!is_functional(version, version.width(), 1, 9));
+        assert_eq!(is_functional(version, version.width(), 8, 0), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 10, 0), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 10, 1), Ok(false));
+        assert_eq!(is_functional(version, version.width(), 8, 8), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 0, 9), Ok(true));
+        assert_eq!(is_functional(version, version.width(), 1, 9), Ok(false));
+    }
+
+    #[test]
+    fn test_is_functional_invalid() {
+        let version = Version::Normal(2);
+        assert_eq!(is_functional(version, 25, -25, 24), Ok(true));
+        assert_eq!(is_functional(version, 25, 25, 0), Err(QrError::OutOfBounds));
+        assert_eq!(is_functional(version, 25, 0, -26), Err(QrError::OutOfBounds));
+        assert_eq!(is_functional(version, 25, i16::MIN, i16::MAX), Err(QrError::OutOfBounds));
+        assert_eq!(is_functional(version, 21, 0, 0), Err(QrError::InvalidWidth(21)));
+        let version = Version::Normal(41);
+        assert_eq!(is_functional(version, version.width(), 0, 0), Err(QrError::InvalidWidth(181)));
     }
 }
 
@@ -1234,8 +1408,96 @@ impl Iterator for DataModuleIter {
     }
 }
 
+/// A cursor over the modules in data placement order, the same order as
+/// `DataModuleIter`, yielding their indices into the canvas.
+///
+/// The index is stepped by whole rows and columns while walking a two-column
+/// strip, so no coordinates need to be converted per module.
+struct PlacementCursor {
+    /// The right column of the current strip. The strips are exhausted once
+    /// this is less than 1.
+    x: i16,
+    y: i16,
+    is_left: bool,
+    is_upward: bool,
+    index: usize,
+    width: i16,
+    timing_pattern_column: i16,
+}
+
+impl PlacementCursor {
+    fn new(version: Version) -> Self {
+        let width = version.width();
+        Self {
+            x: width - 1,
+            y: width - 1,
+            is_left: false,
+            is_upward: true,
+            index: (width * width - 1).as_usize(),
+            width,
+            timing_pattern_column: match version {
+                Version::Micro(_) => 0,
+                Version::Normal(_) => 6,
+            },
+        }
+    }
+
+    /// Moves to the top or bottom of the next strip to the left, skipping the
+    /// vertical timing pattern.
+    fn next_strip(&mut self) {
+        self.x -= 2;
+        if self.x == self.timing_pattern_column {
+            self.x -= 1;
+        }
+        self.is_upward = !self.is_upward;
+        if self.x >= 0 {
+            self.index = (self.y * self.width + self.x).as_usize();
+        }
+    }
+
+    /// Finds the index of the next module which is not part of a functional
+    /// pattern, i.e. not masked yet.
+    fn next_free(&mut self, modules: &[u8]) -> Option<usize> {
+        self.find(|&index| modules[index / 4] >> (index % 4 * 2) & 0b10 == 0)
+    }
+}
+
+impl Iterator for PlacementCursor {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.x < 1 {
+            return None;
+        }
+
+        let res = self.index;
+        let width = self.width.as_usize();
+        if !self.is_left {
+            self.is_left = true;
+            self.index -= 1;
+        } else {
+            self.is_left = false;
+            self.index += 1;
+            match (self.is_upward, self.y) {
+                (true, 0) => self.next_strip(),
+                (true, _) => {
+                    self.y -= 1;
+                    self.index -= width;
+                }
+                (false, y) if y == self.width - 1 => self.next_strip(),
+                (false, _) => {
+                    self.y += 1;
+                    self.index += width;
+                }
+            }
+        }
+
+        Some(res)
+    }
+}
+
 #[cfg(test)]
-#[cfg_attr(rustfmt, rustfmt_skip)] // skip to prevent file becoming too long.
+#[rustfmt::skip] // skip to prevent file becoming too long.
 mod data_iter_tests {
     use crate::canvas::DataModuleIter;
     use crate::types::Version;
@@ -1400,44 +1662,149 @@ mod data_iter_tests {
     }
 }
 
+#[cfg(test)]
+mod placement_cursor_tests {
+    use crate::canvas::{DataModuleIter, PlacementCursor};
+    use crate::types::Version;
+
+    #[test]
+    fn test_same_order_as_iter() {
+        let versions = (1..=40).map(Version::Normal).chain((1..=4).map(Version::Micro));
+        for version in versions {
+            let width = version.width();
+            let expected = DataModuleIter::new(version).map(|(x, y)| (y * width + x) as usize);
+            assert!(PlacementCursor::new(version).eq(expected), "{:?}", version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod data_coverage_tests {
+    use crate::canvas::{is_functional_unchecked, is_version_info, DataModuleIter};
+    use crate::spec::BlockLayout;
+    use crate::types::{EcLevel, Version};
+
+    // There are few enough versions to check every one of them, rather than
+    // a random sample.
+    fn all_versions() -> impl Iterator<Item = Version> {
+        (1..=40).map(Version::Normal).chain((1..=4).map(Version::Micro))
+    }
+
+    /// Counts how often data is placed in every module, skipping the
+    /// functional modules as the drawing does.
+    fn placements(version: Version) -> Vec<u8> {
+        let width = version.width();
+        let mut counts = vec![0; (width * width) as usize];
+        for (x, y) in DataModuleIter::new(version) {
+            assert!((0..width).contains(&x) && (0..width).contains(&y), "{:?}: ({}, {})", version, x, y);
+            if !is_functional_unchecked(version, width, x, y) {
+                counts[(y * width + x) as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_every_data_module_once() {
+        for version in all_versions() {
+            let width = version.width();
+            for (i, count) in placements(version).into_iter().enumerate() {
+                let (x, y) = (i as i16 % width, i as i16 / width);
+                let expected = u8::from(!is_functional_unchecked(version, width, x, y));
+                assert_eq!(count, expected, "{:?}: ({}, {})", version, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_data_modules_hold_all_codewords() {
+        for version in all_versions() {
+            // The version information is not counted by `is_functional`, and
+            // skipped separately when reading the codewords.
+            let width = version.width();
+            let data_modules = (placements(version).into_iter().enumerate())
+                .filter(|&(i, count)| {
+                    count != 0 && !is_version_info(version, width, i as i16 % width, i as i16 / width)
+                })
+                .count();
+            let (codeword_bits, remainder_bits) = match version {
+                Version::Normal(v) => {
+                    let total_len = BlockLayout::fetch(version, EcLevel::L).unwrap().total_len();
+                    let remainder_bits = match v {
+                        2..=6 => 7,
+                        14..=20 | 28..=34 => 3,
+                        21..=27 => 4,
+                        _ => 0,
+                    };
+                    (total_len * 8, remainder_bits)
+                }
+                // Micro versions 1 and 3 end with a half codeword, and have
+                // no remainder bits.
+                Version::Micro(v) => ([36, 80, 132, 192][v as usize - 1], 0),
+            };
+            assert_eq!(data_modules, codeword_bits + remainder_bits, "{:?}", version);
+        }
+    }
+}
+
 
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Data placement
 
 impl<V: QrSpec> Canvas<V> {
-    fn draw_codewords<I>(&mut self, codewords: &[u8], is_half_codeword_at_end: bool, coords: &mut I)
+    fn draw_codewords<C>(&mut self, codewords: C, is_half_codeword_at_end: bool, cursor: &mut PlacementCursor)
     where
-        I: Iterator<Item = (i16, i16)>,
+        C: ExactSizeIterator<Item = u8>,
     {
         let length = codewords.len();
         let last_word = if is_half_codeword_at_end { length - 1 } else { length };
-        for (i, b) in codewords.iter().enumerate() {
+        for (i, b) in codewords.enumerate() {
             let bits_end = if i == last_word { 4 } else { 0 };
-            'outside: for j in (bits_end..=7).rev() {
-                let color = if (*b & (1 << j)) == 0 { Color::Light } else { Color::Dark };
-                while let Some((x, y)) = coords.next() {
-                    let r = self.get(x, y);
-                    if let Module::Unmasked(_) = r {
-                        self.put_unmasked(x, y, color);
-                        continue 'outside;
-                    }
-                }
-                return;
+            for j in (bits_end..=7).rev() {
+                let Some(index) = cursor.next_free(&self.modules) else {
+                    return;
+                };
+                let shift = index % 4 * 2;
+                self.modules[index / 4] &= !(0b11 << shift);
+                self.modules[index / 4] |= (b >> j & 1) << shift;
             }
         }
     }
 
     /// Draws the encoded data and error correction codes to the empty modules.
     pub fn draw_data(&mut self, data: &[u8], ec: &[u8]) {
-        let is_half_codeword_at_end = match (V::VERSION, V::EC_LEVEL) {
-            (Version::Micro(1), EcLevel::L) | (Version::Micro(3), EcLevel::M) => true,
-            _ => false,
-        };
+        let is_half_codeword_at_end =
+            matches!((V::VERSION, V::EC_LEVEL), (Version::Micro(1), EcLevel::L) | (Version::Micro(3), EcLevel::M));
+
+        let mut cursor = PlacementCursor::new(V::VERSION);
+        self.draw_codewords(data.iter().copied(), is_half_codeword_at_end, &mut cursor);
+        self.draw_codewords(ec.iter().copied(), false, &mut cursor);
+    }
+
+    /// Draws the interleaved data and error correction codewords to the empty
+    /// modules, without buffering them.
+    pub fn draw_codewords_iter<B: Deref<Target = [u8]>>(&mut self, mut codewords: Codewords<V, B>) {
+        let is_half_codeword_at_end =
+            matches!((V::VERSION, V::EC_LEVEL), (Version::Micro(1), EcLevel::L) | (Version::Micro(3), EcLevel::M));
 
-        let mut coords = DataModuleIter::new(V::VERSION);
-        self.draw_codewords(data, is_half_codeword_at_end, &mut coords);
-        self.draw_codewords(ec, false, &mut coords);
+        let mut cursor = PlacementCursor::new(V::VERSION);
+        let data_len = Codewords::<V>::data_len();
+        self.draw_codewords(codewords.by_ref().take(data_len), is_half_codeword_at_end, &mut cursor);
+        self.draw_codewords(codewords, false, &mut cursor);
+    }
+
+    /// Computes the error correction codes of the data codewords `rawbits`,
+    /// and draws both interleaved in a single pass.
+    ///
+    /// The error correction codes are kept in `scratch` until they are drawn,
+    /// so the same matrix can then be used for `best_mask`, and no buffer of
+    /// all codewords is needed.
+    pub(crate) fn draw_rawbits(&mut self, rawbits: &[u8], scratch: &mut ColorMatrix<V>) -> QrResult<()> {
+        scratch.ensure_len();
+        let codewords = Codewords::<V, _>::new_in(rawbits, &mut scratch.bytes)?;
+        self.draw_codewords_iter(codewords);
+        Ok(())
     }
 }
 
@@ -1512,13 +1879,253 @@ mod draw_codewords_test {
     }
 }
 
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Packed rows
+
+/// The number of words in a `Row`, enough for the widest symbol (177
+/// modules).
+const ROW_WORDS: usize = 3;
+
+/// A row (or column) of modules packed one bit per module. The module at `x`
+/// is bit `x % 64` of word `x / 64`.
+///
+/// Masking and penalty scoring work on whole rows at a time, using bitwise
+/// operations on the words instead of visiting every module.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+struct Row([u64; ROW_WORDS]);
+
+impl Row {
+    /// Constructs a row with the first `width` bits set.
+    #[inline]
+    fn ones(width: usize) -> Self {
+        let mut row = Row::default();
+        for (i, word) in row.0.iter_mut().enumerate() {
+            let start = i * 64;
+            *word = match width.saturating_sub(start) {
+                0 => 0,
+                n if n >= 64 => !0,
+                n => (1 << n) - 1,
+            };
+        }
+        row
+    }
+
+    /// Checks whether the bit at `x` is set.
+    #[inline]
+    fn get(self, x: usize) -> bool {
+        self.0[x / 64] >> (x % 64) & 1 != 0
+    }
+
+    /// Counts the set bits.
+    #[inline]
+    fn count_ones(self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// Moves every bit `n` places towards the end of the row, i.e. bit `x` of
+    /// the result is bit `x - n` of `self`. `n` must be less than 64.
+    #[inline]
+    fn shl(self, n: u32) -> Self {
+        let mut row = Row::default();
+        let mut carry = 0;
+        for (dst, &src) in row.0.iter_mut().zip(&self.0) {
+            *dst = src << n | carry;
+            carry = if n == 0 { 0 } else { src >> (64 - n) };
+        }
+        row
+    }
+
+    /// Moves every bit `n` places towards the start of the row, i.e. bit `x`
+    /// of the result is bit `x + n` of `self`. `n` must be less than 64.
+    #[inline]
+    fn shr(self, n: u32) -> Self {
+        let mut row = Row::default();
+        let mut carry = 0;
+        for (dst, &src) in row.0.iter_mut().zip(&self.0).rev() {
+            *dst = src >> n | carry;
+            carry = if n == 0 { 0 } else { src << (64 - n) };
+        }
+        row
+    }
+
+    /// Obtains the 4 bits starting at `x`. Bits before the start of the row
+    /// are zero.
+    #[inline]
+    fn nibble(self, x: isize) -> u8 {
+        if x < 0 {
+            (self.0[0] << -x) as u8 & 0xf
+        } else {
+            let (i, shift) = (x as usize / 64, x as usize % 64);
+            let mut bits = self.0[i] >> shift;
+            if shift > 60 && i + 1 < ROW_WORDS {
+                bits |= self.0[i + 1] << (64 - shift);
+            }
+            bits as u8 & 0xf
+        }
+    }
+
+    /// Sets the 4 bits of `nibble` starting at `x`. Bits before the start of
+    /// the row are dropped.
+    #[inline]
+    fn or_nibble(&mut self, nibble: u8, x: isize) {
+        if x < 0 {
+            self.0[0] |= u64::from(nibble) >> -x;
+        } else {
+            let (i, shift) = (x as usize / 64, x as usize % 64);
+            self.0[i] |= u64::from(nibble) << shift;
+            if shift > 60 && i + 1 < ROW_WORDS {
+                self.0[i + 1] |= u64::from(nibble) >> (64 - shift);
+            }
+        }
+    }
+}
+
+impl BitAnd for Row {
+    type Output = Row;
+    #[inline]
+    fn bitand(mut self, other: Row) -> Row {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a &= b);
+        self
+    }
+}
+
+impl BitOr for Row {
+    type Output = Row;
+    #[inline]
+    fn bitor(mut self, other: Row) -> Row {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a |= b);
+        self
+    }
+}
+
+impl BitXor for Row {
+    type Output = Row;
+    #[inline]
+    fn bitxor(mut self, other: Row) -> Row {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a ^= b);
+        self
+    }
+}
+
+impl Not for Row {
+    type Output = Row;
+    #[inline]
+    fn not(mut self) -> Row {
+        self.0.iter_mut().for_each(|a| *a = !*a);
+        self
+    }
+}
+
+/// Interleaves the 4 bits of a nibble with zeros, so that bit `i` moves to
+/// bit `2 * i`.
+#[inline]
+fn zip_nibble(nibble: u8) -> u8 {
+    let n = (nibble | nibble << 2) & 0x33;
+    (n | n << 1) & 0x55
+}
+
+/// Collects the even bits of a byte into a nibble, the reverse of
+/// `zip_nibble`.
+#[inline]
+fn unzip_nibble(byte: u8) -> u8 {
+    let n = byte & 0x55;
+    let n = (n | n >> 1) & 0x33;
+    (n | n >> 2) & 0x0f
+}
+
+impl<V: QrSpec> Canvas<V> {
+    /// Calls `f` for every byte of the canvas overlapping row `y`, with the
+    /// column of the first module of the byte. The column is negative if the
+    /// byte starts in the row above.
+    fn for_each_row_byte<F: FnMut(&mut u8, isize)>(&mut self, y: usize, mut f: F) {
+        let width = V::WIDTH.as_usize();
+        let start = y * width;
+        for i in start / 4..=(start + width - 1) / 4 {
+            f(&mut self.modules[i], (4 * i) as isize - start as isize);
+        }
+    }
+
+    /// Reads row `y` of the canvas as two packed rows: the dark modules, and
+    /// the unmasked modules.
+    fn read_row(&self, y: usize) -> (Row, Row) {
+        let width = V::WIDTH.as_usize();
+        let start = y * width;
+        let (mut dark, mut unmasked) = (Row::default(), Row::default());
+        for i in start / 4..=(start + width - 1) / 4 {
+            let x = (4 * i) as isize - start as isize;
+            let byte = self.modules[i];
+            dark.or_nibble(unzip_nibble(byte), x);
+            unmasked.or_nibble(unzip_nibble(!byte >> 1), x);
+        }
+        let full = Row::ones(width);
+        (dark & full, unmasked & full)
+    }
+}
+
+#[cfg(test)]
+mod row_tests {
+    use crate::canvas::{unzip_nibble, zip_nibble, Canvas, Row};
+    use crate::spec::{EcLevelL, Version1};
+    use crate::types::Color;
+
+    #[test]
+    fn test_ones() {
+        assert_eq!(Row::ones(0), Row([0, 0, 0]));
+        assert_eq!(Row::ones(21), Row([0x1f_ffff, 0, 0]));
+        assert_eq!(Row::ones(177), Row([!0, !0, 0x1_ffff_ffff_ffff]));
+    }
+
+    #[test]
+    fn test_shifts() {
+        let row = Row([1 << 63 | 1, 1 << 2, 0]);
+        assert_eq!(row.shl(1), Row([2, 1 | 1 << 3, 0]));
+        assert_eq!(row.shr(1), Row([1 << 62, 1 << 1, 0]));
+        assert_eq!(row.shr(3), Row([1 << 60 | 1 << 63, 0, 0]));
+        assert_eq!(row.shl(0), row);
+        assert_eq!(row.count_ones(), 3);
+        assert!(row.get(63) && row.get(66) && !row.get(65));
+    }
+
+    #[test]
+    fn test_nibbles() {
+        let mut row = Row::default();
+        row.or_nibble(0b1011, -2);
+        row.or_nibble(0b1111, 62);
+        assert_eq!(row, Row([0b10 | 0b11 << 62, 0b11, 0]));
+        assert_eq!(row.nibble(-1), 0b100);
+        assert_eq!(row.nibble(62), 0b1111);
+        assert_eq!(row.nibble(64), 0b11);
+
+        for n in 0..16 {
+            assert_eq!(unzip_nibble(zip_nibble(n)), n);
+        }
+        assert_eq!(zip_nibble(0b1101), 0b0101_0001);
+    }
+
+    #[test]
+    fn test_read_row() {
+        let mut c = Canvas::<Version1<EcLevelL>>::new();
+        c.put(0, 1, Color::Dark);
+        c.put_unmasked(3, 1, Color::Dark);
+        c.put_unmasked(20, 1, Color::Light);
+        c.put(20, 0, Color::Dark);
+        c.put(0, 2, Color::Dark);
+
+        let (dark, unmasked) = c.read_row(1);
+        assert_eq!(dark, Row([0b1001, 0, 0]));
+        assert_eq!(unmasked, Row([0x1f_fffe, 0, 0]));
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Masking
 
 /// The mask patterns. Since QR code and Micro QR code do not use the same
 /// pattern number, we name them according to their shape instead of the number.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MaskPattern {
     /// QR code pattern 000: `(x + y) % 2 == 0`.
     Checkerboard = 0b000,
@@ -1545,34 +2152,70 @@ pub enum MaskPattern {
     Meadow = 0b111,
 }
 
+impl MaskPattern {
+    /// Obtains the mask pattern from its 3-bit QR code pattern number.
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0b000 => MaskPattern::Checkerboard,
+            0b001 => MaskPattern::HorizontalLines,
+            0b010 => MaskPattern::VerticalLines,
+            0b011 => MaskPattern::DiagonalLines,
+            0b100 => MaskPattern::LargeCheckerboard,
+            0b101 => MaskPattern::Fields,
+            0b110 => MaskPattern::Diamonds,
+            0b111 => MaskPattern::Meadow,
+            _ => return None,
+        })
+    }
+
+    /// Checks whether the pattern inverts the module at the given coordinates.
+    ///
+    ///     use qrcode::canvas::MaskPattern;
+    ///
+    ///     const INVERTED: bool = MaskPattern::VerticalLines.is_inverted(3, 1);
+    ///     assert!(INVERTED);
+    pub const fn is_inverted(self, x: i16, y: i16) -> bool {
+        match self {
+            MaskPattern::Checkerboard => mask_functions::checkerboard(x, y),
+            MaskPattern::HorizontalLines => mask_functions::horizontal_lines(x, y),
+            MaskPattern::VerticalLines => mask_functions::vertical_lines(x, y),
+            MaskPattern::DiagonalLines => mask_functions::diagonal_lines(x, y),
+            MaskPattern::LargeCheckerboard => mask_functions::large_checkerboard(x, y),
+            MaskPattern::Fields => mask_functions::fields(x, y),
+            MaskPattern::Diamonds => mask_functions::diamonds(x, y),
+            MaskPattern::Meadow => mask_functions::meadow(x, y),
+        }
+    }
+}
+
 mod mask_functions {
-    pub fn checkerboard(x: i16, y: i16) -> bool {
+    pub const fn checkerboard(x: i16, y: i16) -> bool {
         (x + y) % 2 == 0
     }
-    pub fn horizontal_lines(_: i16, y: i16) -> bool {
+    pub const fn horizontal_lines(_: i16, y: i16) -> bool {
         y % 2 == 0
     }
-    pub fn vertical_lines(x: i16, _: i16) -> bool {
+    pub const fn vertical_lines(x: i16, _: i16) -> bool {
         x % 3 == 0
     }
-    pub fn diagonal_lines(x: i16, y: i16) -> bool {
+    pub const fn diagonal_lines(x: i16, y: i16) -> bool {
         (x + y) % 3 == 0
     }
-    pub fn large_checkerboard(x: i16, y: i16) -> bool {
+    pub const fn large_checkerboard(x: i16, y: i16) -> bool {
         ((y / 2) + (x / 3)) % 2 == 0
     }
-    pub fn fields(x: i16, y: i16) -> bool {
+    pub const fn fields(x: i16, y: i16) -> bool {
         (x * y) % 2 + (x * y) % 3 == 0
     }
-    pub fn diamonds(x: i16, y: i16) -> bool {
+    pub const fn diamonds(x: i16, y: i16) -> bool {
         ((x * y) % 2 + (x * y) % 3) % 2 == 0
     }
-    pub fn meadow(x: i16, y: i16) -> bool {
+    pub const fn meadow(x: i16, y: i16) -> bool {
         ((x + y) % 2 + (x * y) % 3) % 2 == 0
     }
 }
 
-fn get_mask_function(pattern: MaskPattern) -> fn(i16, i16) -> bool {
+pub(crate) fn get_mask_function(pattern: MaskPattern) -> fn(i16, i16) -> bool {
     match pattern {
         MaskPattern::Checkerboard => mask_functions::checkerboard,
         MaskPattern::HorizontalLines => mask_functions::horizontal_lines,
@@ -1585,19 +2228,74 @@ fn get_mask_function(pattern: MaskPattern) -> fn(i16, i16) -> bool {
     }
 }
 
+/// Every bit 6 places apart set, to repeat a 6-bit pattern across a word.
+const REPEAT_6: u64 = 0x1041_0410_4104_1041;
+
+impl MaskPattern {
+    /// Computes the mask of row `y` as packed bits, a set bit meaning the
+    /// module should be inverted.
+    ///
+    /// All mask patterns repeat every 6 columns, so only the first 6 columns of
+    /// every word are evaluated, and then copied across the word with a
+    /// multiplication.
+    fn row(self, y: usize, width: usize) -> Row {
+        let mask_fn = get_mask_function(self);
+        let y = y.as_i16();
+        let mut row = Row::default();
+        for (i, word) in row.0.iter_mut().enumerate() {
+            let x = (i * 64).as_i16();
+            let seed = (0..6).fold(0, |seed, k| seed | u64::from(mask_fn(x + k, y)) << k);
+            *word = seed.wrapping_mul(REPEAT_6);
+        }
+        row & Row::ones(width)
+    }
+}
+
 impl<V: QrSpec> Canvas<V> {
     /// Applies a mask to the canvas. This method will also draw the format info
     /// patterns.
-    pub fn apply_mask(&mut self, pattern: MaskPattern) {
-        let mask_fn = get_mask_function(pattern);
-        for x in 0..V::WIDTH {
-            for y in 0..V::WIDTH {
-                let module = self.get(x, y);
-                self.put(x, y, module.mask(mask_fn(x, y)));
-            }
+    ///
+    /// Returns `Err(QrError::UnsupportedMask)` if a Micro QR code does not
+    /// support the pattern, and `Err(QrError::InvalidVersion { .. })` if `V` is
+    /// not a valid Micro QR code version. The canvas is left untouched then.
+    pub fn apply_mask(&mut self, pattern: MaskPattern) -> QrResult<()> {
+        let format_info = Self::format_info(pattern)?;
+        self.apply_mask_pattern(pattern);
+        self.draw_format_info_patterns_with_number(format_info);
+        Ok(())
+    }
+
+    /// Applies only the mask transform of `apply_mask`: the modules not
+    /// masked yet are inverted where the pattern says so, and then all modules
+    /// are marked as masked. The format info patterns are not drawn; see
+    /// `draw_format_info_patterns`.
+    ///
+    /// Unlike `apply_mask`, every pattern is accepted, also for Micro QR codes.
+    pub fn apply_mask_pattern(&mut self, pattern: MaskPattern) {
+        let width = V::WIDTH.as_usize();
+        let full = Row::ones(width);
+        for y in 0..width {
+            let mask = pattern.row(y, width);
+            self.for_each_row_byte(y, |byte, x| {
+                // Invert the unmasked modules under the mask, then mark all
+                // modules of this row in the byte as masked.
+                let unmasked = !*byte >> 1 & 0x55;
+                *byte ^= zip_nibble(mask.nibble(x)) & unmasked;
+                *byte |= zip_nibble(full.nibble(x)) << 1;
+            });
         }
+    }
 
-        self.draw_format_info_patterns(pattern);
+    /// Removes the mask pattern from the data modules, reverting
+    /// `apply_mask_pattern`. The codewords and remainder bits become unmasked
+    /// again, so that another pattern can be applied, while the function
+    /// patterns and the format info are left as they are.
+    pub fn remove_mask_pattern(&mut self, pattern: MaskPattern) {
+        let mask_fn = get_mask_function(pattern);
+        for (x, y) in data_modules(V::VERSION) {
+            let color = Color::from(self.get(x, y));
+            self.put_unmasked(x, y, if mask_fn(x, y) { !color } else { color });
+        }
     }
 
     /// Draws the format information to encode the error correction level and
@@ -1605,11 +2303,18 @@ impl<V: QrSpec> Canvas<V> {
     ///
     /// If the error correction level or mask pattern is not supported in the
     /// current QR code version, this method will fail.
-    fn draw_format_info_patterns(&mut self, pattern: MaskPattern) {
-        let format_number = match V::VERSION {
+    pub fn draw_format_info_patterns(&mut self, pattern: MaskPattern) -> QrResult<()> {
+        self.draw_format_info_patterns_with_number(Self::format_info(pattern)?);
+        Ok(())
+    }
+
+    /// Computes the encoded format information for the error correction level
+    /// and mask pattern.
+    fn format_info(pattern: MaskPattern) -> QrResult<u16> {
+        match V::VERSION {
             Version::Normal(_) => {
                 let simple_format_number = ((V::EC_LEVEL as usize) ^ 1) << 3 | (pattern as usize);
-                FORMAT_INFOS_QR[simple_format_number]
+                Ok(encode_format_info(simple_format_number as u8, FORMAT_INFO_MASK_QR))
             }
             Version::Micro(a) => {
                 let micro_pattern_number = match pattern {
@@ -1617,7 +2322,7 @@ impl<V: QrSpec> Canvas<V> {
                     MaskPattern::LargeCheckerboard => 0b01,
                     MaskPattern::Diamonds => 0b10,
                     MaskPattern::Meadow => 0b11,
-                    _ => panic!("Unsupported mask pattern in Micro QR code"),
+                    _ => return Err(QrError::UnsupportedMask),
                 };
                 let symbol_number = match (a, V::EC_LEVEL) {
                     (1, EcLevel::L) => 0b000,
@@ -1628,26 +2333,38 @@ impl<V: QrSpec> Canvas<V> {
                     (4, EcLevel::L) => 0b101,
                     (4, EcLevel::M) => 0b110,
                     (4, EcLevel::Q) => 0b111,
-                    _ => panic!("Unsupported version/ec_level combination in Micro QR code"),
+                    _ => return Err(QrError::InvalidVersion { version: V::VERSION, ec_level: V::EC_LEVEL }),
                 };
                 let simple_format_number = symbol_number << 2 | micro_pattern_number;
-                FORMAT_INFOS_MICRO_QR[simple_format_number]
+                Ok(encode_format_info(simple_format_number as u8, FORMAT_INFO_MASK_MICRO_QR))
             }
-        };
-        self.draw_format_info_patterns_with_number(format_number);
+        }
     }
 }
 
 #[cfg(test)]
 mod mask_tests {
-    use crate::canvas::{Canvas, MaskPattern};
-    use crate::spec::{Version1, EcLevelL};
+    use crate::canvas::{get_mask_function, Canvas, MaskPattern, ALL_PATTERNS_QR};
+    use crate::spec::{EcLevelL, Version1};
+
+    #[test]
+    fn test_mask_rows() {
+        for &pattern in &ALL_PATTERNS_QR {
+            let mask_fn = get_mask_function(pattern);
+            for y in 0..177 {
+                let row = pattern.row(y, 177);
+                for x in 0..192 {
+                    assert_eq!(row.get(x), x < 177 && mask_fn(x as i16, y as i16), "{:?} at ({}, {})", pattern, x, y);
+                }
+            }
+        }
+    }
 
     #[test]
     fn test_apply_mask_qr() {
         let mut c = Canvas::<Version1<EcLevelL>>::new();
         c.draw_all_functional_patterns();
-        c.apply_mask(MaskPattern::Checkerboard);
+        c.apply_mask(MaskPattern::Checkerboard).unwrap();
 
         assert_eq!(
             &*c.to_debug_str(),
@@ -1676,10 +2393,35 @@ mod mask_tests {
         );
     }
 
+    #[test]
+    fn test_apply_mask_pattern() {
+        let mut c = Canvas::<Version1<EcLevelL>>::new();
+        c.draw_all_functional_patterns();
+        c.draw_data(&[0x5a; 19], &[0xa5; 7]);
+        let unmasked = c.clone();
+
+        let mut expected = c.clone();
+        expected.apply_mask(MaskPattern::Fields).unwrap();
+        c.apply_mask_pattern(MaskPattern::Fields);
+        assert_ne!(c.to_debug_str(), expected.to_debug_str());
+        c.draw_format_info_patterns(MaskPattern::Fields).unwrap();
+        assert_eq!(c.to_debug_str(), expected.to_debug_str());
+
+        // Switching the pattern keeps the format info, so it is drawn anew.
+        c.remove_mask_pattern(MaskPattern::Fields);
+        let mut reserved = unmasked.clone();
+        reserved.draw_format_info_patterns(MaskPattern::Fields).unwrap();
+        assert_eq!(c.to_debug_str(), reserved.to_debug_str());
+        c.apply_mask(MaskPattern::Meadow).unwrap();
+        let mut expected = unmasked;
+        expected.apply_mask(MaskPattern::Meadow).unwrap();
+        assert_eq!(c.to_debug_str(), expected.to_debug_str());
+    }
+
     #[test]
     fn test_draw_format_info_patterns_qr() {
         let mut c = Canvas::<Version1<EcLevelL>>::new();
-        c.draw_format_info_patterns(MaskPattern::LargeCheckerboard);
+        c.draw_format_info_patterns(MaskPattern::LargeCheckerboard).unwrap();
         assert_eq!(
             &*c.to_debug_str(),
             "\n\
@@ -1731,53 +2473,339 @@ mod mask_tests {
     // }
 }
 
+//}}}
+//------------------------------------------------------------------------------
+//{{{ BCH codes for format info & version info
+
+/// Appends the remainder of the BCH code with the given generator polynomial
+/// to `data`. `ec_bits` is the degree of the generator polynomial.
+const fn bch_encode(data: u32, generator: u32, ec_bits: u32) -> u32 {
+    let mut remainder = data << ec_bits;
+    let mut bit = 31;
+    while bit >= ec_bits {
+        if remainder & (1 << bit) != 0 {
+            remainder ^= generator << (bit - ec_bits);
+        }
+        bit -= 1;
+    }
+    data << ec_bits | remainder
+}
 
-static FORMAT_INFOS_QR: [u16; 32] = [
-    0x5412, 0x5125, 0x5e7c, 0x5b4b, 0x45f9, 0x40ce, 0x4f97, 0x4aa0, 0x77c4, 0x72f3, 0x7daa, 0x789d, 0x662f, 0x6318,
-    0x6c41, 0x6976, 0x1689, 0x13be, 0x1ce7, 0x19d0, 0x0762, 0x0255, 0x0d0c, 0x083b, 0x355f, 0x3068, 0x3f31, 0x3a06,
-    0x24b4, 0x2183, 0x2eda, 0x2bed,
-];
+/// Finds the data in `candidates` whose BCH codeword is closest to
+/// `codeword`, if it differs by at most `max_errors` bits.
+fn bch_decode(codeword: u32, candidates: Range<u32>, generator: u32, ec_bits: u32, max_errors: u32) -> Option<u32> {
+    candidates
+        .map(|data| (data, (bch_encode(data, generator, ec_bits) ^ codeword).count_ones()))
+        .filter(|&(_, distance)| distance <= max_errors)
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(data, _)| data)
+}
 
-static FORMAT_INFOS_MICRO_QR: [u16; 32] = [
-    0x4445, 0x4172, 0x4e2b, 0x4b1c, 0x55ae, 0x5099, 0x5fc0, 0x5af7, 0x6793, 0x62a4, 0x6dfd, 0x68ca, 0x7678, 0x734f,
-    0x7c16, 0x7921, 0x06de, 0x03e9, 0x0cb0, 0x0987, 0x1735, 0x1202, 0x1d5b, 0x186c, 0x2508, 0x203f, 0x2f66, 0x2a51,
-    0x34e3, 0x31d4, 0x3e8d, 0x3bba,
-];
+/// The generator polynomial of the (15, 5) BCH code protecting the format
+/// information, x<sup>10</sup> + x<sup>8</sup> + x<sup>5</sup> +
+/// x<sup>4</sup> + x<sup>2</sup> + x + 1.
+const FORMAT_INFO_GENERATOR: u32 = 0x537;
+
+/// The mask XOR-ed with the format information of normal QR codes.
+pub const FORMAT_INFO_MASK_QR: u16 = 0x5412;
+
+/// The mask XOR-ed with the format information of Micro QR codes.
+pub const FORMAT_INFO_MASK_MICRO_QR: u16 = 0x4445;
+
+/// Computes the 15-bit format information from its 5 data bits, with the
+/// (15, 5) BCH error correction bits appended and `mask` applied.
+///
+/// For normal QR codes, the data bits are the error correction level
+/// indicator followed by the mask pattern. For Micro QR codes, they are the
+/// symbol number followed by the mask pattern.
+///
+///     use qrcode::canvas::{encode_format_info, FORMAT_INFO_MASK_QR};
+///
+///     assert_eq!(encode_format_info(0b00101, FORMAT_INFO_MASK_QR), 0x40ce);
+pub const fn encode_format_info(data: u8, mask: u16) -> u16 {
+    bch_encode(data as u32 & 0b11111, FORMAT_INFO_GENERATOR, 10) as u16 ^ mask
+}
+
+/// Recovers the 5 data bits from a 15-bit format information read from a QR
+/// code, using the same `mask` as in `encode_format_info`.
+///
+/// Up to 3 erroneous bits can be corrected. Returns `None` if the format
+/// information is too damaged.
+///
+///     use qrcode::canvas::{decode_format_info, FORMAT_INFO_MASK_QR};
+///
+///     assert_eq!(decode_format_info(0x40ce, FORMAT_INFO_MASK_QR), Some(0b00101));
+///     assert_eq!(decode_format_info(0x40ce ^ 0b101, FORMAT_INFO_MASK_QR), Some(0b00101));
+pub fn decode_format_info(format_info: u16, mask: u16) -> Option<u8> {
+    let codeword = u32::from((format_info ^ mask) & 0x7fff);
+    bch_decode(codeword, 0..32, FORMAT_INFO_GENERATOR, 10, 3).map(|data| data as u8)
+}
+
+/// The generator polynomial of the (18, 6) BCH code protecting the version
+/// information, x<sup>12</sup> + x<sup>11</sup> + x<sup>10</sup> +
+/// x<sup>9</sup> + x<sup>8</sup> + x<sup>5</sup> + x<sup>2</sup> + 1.
+const VERSION_INFO_GENERATOR: u32 = 0x1f25;
+
+/// Computes the 18-bit version information of a normal QR code, i.e. the
+/// version number with the (18, 6) BCH error correction bits appended.
+///
+/// Only versions 7 to 40 carry version information.
+///
+///     use qrcode::canvas::encode_version_info;
+///
+///     assert_eq!(encode_version_info(7), 0x07c94);
+pub const fn encode_version_info(version: u8) -> u32 {
+    bch_encode(version as u32 & 0b111111, VERSION_INFO_GENERATOR, 12)
+}
+
+/// Recovers the version number from an 18-bit version information read from
+/// a QR code.
+///
+/// Up to 3 erroneous bits can be corrected. Returns `None` if the version
+/// information is too damaged, or does not decode to a version from 7 to 40.
+///
+///     use qrcode::canvas::decode_version_info;
+///
+///     assert_eq!(decode_version_info(0x07c94), Some(7));
+///     assert_eq!(decode_version_info(0x07c94 ^ 0x10010), Some(7));
+pub fn decode_version_info(version_info: u32) -> Option<u8> {
+    bch_decode(version_info & 0x3ffff, 7..41, VERSION_INFO_GENERATOR, 12, 3).map(|version| version as u8)
+}
+
+#[cfg(test)]
+mod bch_tests {
+    use crate::canvas::{
+        decode_format_info, decode_version_info, encode_format_info, encode_version_info, FORMAT_INFO_MASK_MICRO_QR,
+        FORMAT_INFO_MASK_QR,
+    };
+
+    // ISO/IEC 18004:2006, Annex C, Table C.1.
+    static FORMAT_INFOS_QR: [u16; 32] = [
+        0x5412, 0x5125, 0x5e7c, 0x5b4b, 0x45f9, 0x40ce, 0x4f97, 0x4aa0, 0x77c4, 0x72f3, 0x7daa, 0x789d, 0x662f,
+        0x6318, 0x6c41, 0x6976, 0x1689, 0x13be, 0x1ce7, 0x19d0, 0x0762, 0x0255, 0x0d0c, 0x083b, 0x355f, 0x3068,
+        0x3f31, 0x3a06, 0x24b4, 0x2183, 0x2eda, 0x2bed,
+    ];
+
+    static FORMAT_INFOS_MICRO_QR: [u16; 32] = [
+        0x4445, 0x4172, 0x4e2b, 0x4b1c, 0x55ae, 0x5099, 0x5fc0, 0x5af7, 0x6793, 0x62a4, 0x6dfd, 0x68ca, 0x7678,
+        0x734f, 0x7c16, 0x7921, 0x06de, 0x03e9, 0x0cb0, 0x0987, 0x1735, 0x1202, 0x1d5b, 0x186c, 0x2508, 0x203f,
+        0x2f66, 0x2a51, 0x34e3, 0x31d4, 0x3e8d, 0x3bba,
+    ];
+
+    #[test]
+    fn test_encode_format_info() {
+        for data in 0..32 {
+            assert_eq!(encode_format_info(data, FORMAT_INFO_MASK_QR), FORMAT_INFOS_QR[usize::from(data)]);
+            assert_eq!(encode_format_info(data, FORMAT_INFO_MASK_MICRO_QR), FORMAT_INFOS_MICRO_QR[usize::from(data)]);
+        }
+    }
+
+    #[test]
+    fn test_decode_format_info() {
+        for data in 0..32 {
+            let format_info = FORMAT_INFOS_QR[usize::from(data)];
+            assert_eq!(decode_format_info(format_info, FORMAT_INFO_MASK_QR), Some(data));
+            assert_eq!(decode_format_info(format_info ^ 0x4201, FORMAT_INFO_MASK_QR), Some(data));
+            let format_info = FORMAT_INFOS_MICRO_QR[usize::from(data)];
+            assert_eq!(decode_format_info(format_info ^ 0x0030, FORMAT_INFO_MASK_MICRO_QR), Some(data));
+        }
+        assert_eq!(decode_format_info(0x5412 ^ 0x000f, FORMAT_INFO_MASK_QR), None);
+    }
+
+    // ISO/IEC 18004:2006, Annex D, Table D.1.
+    static VERSION_INFOS: [u32; 34] = [
+        0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928, 0x10b78, 0x1145d, 0x12a17,
+        0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4, 0x191e1, 0x1afab, 0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75,
+        0x1f250, 0x209d5, 0x216f0, 0x228ba, 0x2379f, 0x24b0b, 0x2542e, 0x26a64, 0x27541, 0x28c69,
+    ];
+
+    #[test]
+    fn test_encode_version_info() {
+        for version in 7..=40 {
+            assert_eq!(encode_version_info(version), VERSION_INFOS[usize::from(version - 7)]);
+        }
+    }
+
+    #[test]
+    fn test_decode_version_info() {
+        for version in 7..=40 {
+            let version_info = VERSION_INFOS[usize::from(version - 7)];
+            assert_eq!(decode_version_info(version_info), Some(version));
+            assert_eq!(decode_version_info(version_info ^ 0x20102), Some(version));
+        }
+        assert_eq!(decode_version_info(encode_version_info(6)), None);
+    }
+}
 
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Penalty score
 
-impl<V: QrSpec> Canvas<V> {
+/// The penalty scores of a QR code for the four rules of ISO/IEC 18004:2006,
+/// §6.8.2.1, or of a Micro QR code for its single rule. The mask pattern with
+/// the lowest total is applied.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct PenaltyScores {
+    /// Runs of five or more modules of the same color in a row or column.
+    pub adjacent: u16,
+
+    /// 2×2 blocks of modules of the same color.
+    pub blocks: u16,
+
+    /// Patterns looking like a finder pattern, apart from the finder patterns
+    /// themselves.
+    pub finder: u16,
+
+    /// Deviation from an even number of dark and light modules.
+    pub balance: u16,
+
+    /// Light modules on the right and bottom sides of a Micro QR code, the
+    /// only rule of Micro QR codes, whose other scores are zero. Zero for
+    /// normal QR codes.
+    pub light_side: u16,
+}
+
+impl PenaltyScores {
+    /// The sum of all scores.
+    pub fn total(&self) -> u16 {
+        self.adjacent + self.blocks + self.finder + self.balance + self.light_side
+    }
+
+    /// The efficiency score the standard defines for Micro QR codes `width`
+    /// modules wide, which is highest for the mask pattern with the lowest
+    /// `light_side` score.
+    pub fn micro_efficiency(&self, width: i16) -> u32 {
+        (17 * (width.max(1).as_u32() - 1)).saturating_sub(u32::from(self.light_side))
+    }
+}
+
+/// The colors of a symbol packed row by row, one bit per module with dark
+/// modules set. Rows are padded to whole bytes.
+///
+/// Penalty scores are computed on this form, so that masks can be compared
+/// without drawing them onto a canvas.
+pub(crate) struct ColorMatrix<V: QrSpec> {
+    bytes: Buffer<V::ColorSize>,
+}
+
+impl<V: QrSpec> ColorMatrix<V> {
+    const WIDTH: usize = V::WIDTH as usize;
+    const STRIDE: usize = Self::WIDTH.div_ceil(8);
+
+    #[cfg(any(test, feature = "rayon"))]
+    fn new() -> Self {
+        let mut matrix = Self::empty();
+        matrix.reset();
+        matrix
+    }
+
+    /// Constructs a matrix without modules, to be `reset` before use.
+    pub(crate) const fn empty() -> Self {
+        Self { bytes: Buffer::new() }
+    }
+
+    /// Packs the colors of a symbol, given row by row.
+    pub(crate) fn from_colors(colors: impl Iterator<Item = Color>) -> Self {
+        let mut matrix = Self::empty();
+        matrix.reset();
+        for (i, color) in colors.take(V::AREA).enumerate() {
+            matrix.put((i % Self::WIDTH) as i16, (i / Self::WIDTH) as i16, color);
+        }
+        matrix
+    }
+
+    /// The number of dark modules.
+    pub(crate) fn count_dark(&self) -> usize {
+        self.bytes.iter().map(|b| b.count_ones().as_usize()).sum()
+    }
+
+    fn reset(&mut self) {
+        self.bytes.fill_zeroes(Self::WIDTH * Self::STRIDE);
+    }
+
+    /// Makes sure the matrix has all its bytes, without clearing them again
+    /// if it has.
+    fn ensure_len(&mut self) {
+        if self.bytes.len() != Self::WIDTH * Self::STRIDE {
+            self.reset();
+        }
+    }
+
+    fn row_bytes(&self, y: usize) -> &[u8] {
+        &self.bytes[y * Self::STRIDE..(y + 1) * Self::STRIDE]
+    }
+
+    /// Obtains the row `y`.
+    fn row(&self, y: usize) -> Row {
+        let mut row = Row::default();
+        for (word, chunk) in row.0.iter_mut().zip(self.row_bytes(y).chunks(8)) {
+            let mut buf = [0; 8];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            *word = u64::from_le_bytes(buf);
+        }
+        row
+    }
+
+    /// Replaces the row `y`.
+    fn set_row(&mut self, y: usize, row: Row) {
+        let bytes = &mut self.bytes[y * Self::STRIDE..(y + 1) * Self::STRIDE];
+        for (chunk, word) in bytes.chunks_mut(8).zip(&row.0) {
+            chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    /// Sets the color of the module at the given coordinates. For convenience,
+    /// negative coordinates will wrap around.
+    fn put(&mut self, x: i16, y: i16, color: Color) {
+        let x = if x < 0 { x + V::WIDTH } else { x }.as_usize();
+        let y = if y < 0 { y + V::WIDTH } else { y }.as_usize();
+        let byte = &mut self.bytes[y * Self::STRIDE + x / 8];
+        *byte &= !(1 << (x % 8));
+        *byte |= u8::from(color == Color::Dark) << (x % 8);
+    }
+
     /// Compute the penalty score for having too many adjacent modules with the
     /// same color.
     ///
     /// Every 5+N adjacent modules in the same column/row having the same color
     /// will contribute 3+N points.
+    ///
+    /// A run of L ≥ 5 modules contains L−4 windows of 5 equal modules, and the
+    /// first of those starts the run. So the score is the number of such
+    /// windows, plus 2 for every window at the start of a run.
     fn compute_adjacent_penalty_score(&self, is_horizontal: bool) -> u16 {
-        let mut total_score = 0;
-
-        for i in 0..V::WIDTH {
-            let map_fn = |j| if is_horizontal { self.get(j, i) } else { self.get(i, j) };
-
-            let colors = (0..V::WIDTH).map(map_fn).chain(Some(Module::EMPTY).into_iter());
-            let mut last_color = Module::EMPTY;
-            let mut consecutive_len = 1_u16;
-
-            for color in colors {
-                if color == last_color {
-                    consecutive_len += 1;
-                } else {
-                    last_color = color;
-                    if consecutive_len >= 5 {
-                        total_score += consecutive_len - 2;
-                    }
-                    consecutive_len = 1;
-                }
+        let full = Row::ones(Self::WIDTH);
+        let mut windows = 0;
+        let mut run_starts = 0;
+
+        if is_horizontal {
+            let valid = full & full.shl(1);
+            for y in 0..Self::WIDTH {
+                let row = self.row(y);
+                // Bit x is set if modules x−1 and x have the same color.
+                let same = !(row ^ row.shl(1)) & valid;
+                let window = same & same.shl(1) & same.shl(2) & same.shl(3);
+                windows += window.count_ones();
+                run_starts += (window & !same.shl(4)).count_ones();
+            }
+        } else {
+            // The last 5 rows of "same color as the row above" bits, the
+            // current row being the last.
+            let mut same = [Row::default(); 5];
+            let mut previous = self.row(0);
+            for y in 1..Self::WIDTH {
+                let row = self.row(y);
+                same.rotate_left(1);
+                same[4] = !(row ^ previous) & full;
+                previous = row;
+
+                let window = same[1] & same[2] & same[3] & same[4];
+                windows += window.count_ones();
+                run_starts += (window & !same[0]).count_ones();
             }
         }
 
-        total_score
+        (windows + 2 * run_starts).as_u16()
     }
 
     /// Compute the penalty score for having too many rectangles with the same
@@ -1786,52 +2814,56 @@ impl<V: QrSpec> Canvas<V> {
     /// Every 2×2 blocks (with overlapping counted) having the same color will
     /// contribute 3 points.
     fn compute_block_penalty_score(&self) -> u16 {
-        let mut total_score = 0;
-
-        for i in 0..V::WIDTH - 1 {
-            for j in 0..V::WIDTH - 1 {
-                let this = self.get(i, j);
-                let right = self.get(i + 1, j);
-                let bottom = self.get(i, j + 1);
-                let bottom_right = self.get(i + 1, j + 1);
-                if this == right && right == bottom && bottom == bottom_right {
-                    total_score += 3;
-                }
-            }
+        let valid = Row::ones(Self::WIDTH - 1);
+        let same_as_right = |row: Row| !(row ^ row.shr(1)) & valid;
+
+        let mut blocks = 0;
+        let mut top = self.row(0);
+        for y in 1..Self::WIDTH {
+            let bottom = self.row(y);
+            blocks += (same_as_right(top) & same_as_right(bottom) & !(top ^ bottom)).count_ones();
+            top = bottom;
         }
 
-        total_score
+        (blocks * 3).as_u16()
     }
 
     /// Compute the penalty score for having a pattern similar to the finder
     /// pattern in the wrong place.
     ///
     /// Every pattern that looks like `#.###.#....` in any orientation will add
-    /// 40 points.
+    /// 40 points. The three rows or columns through the center of each of the
+    /// three finder patterns are not counted, and neither are they when a
+    /// finder pattern of a damaged symbol is missing them.
     fn compute_finder_penalty_score(&self, is_horizontal: bool) -> u16 {
-        static PATTERN: [Color; 7] =
-            [Color::Dark, Color::Light, Color::Dark, Color::Dark, Color::Dark, Color::Light, Color::Dark];
-
-        let mut total_score = 0;
-
-        for i in 0..V::WIDTH {
-            for j in 0..V::WIDTH - 6 {
-                let get_h = |k| self.get(k, i).into();
-                let get_v = |k| self.get(i, k).into();
-                let get: &dyn Fn(i16) -> Color = if is_horizontal { &get_h } else { &get_v };
-
-                if (j..(j + 7)).map(&*get).ne(PATTERN.iter().cloned()) {
-                    continue;
-                }
-
-                let check = |k| 0 <= k && k < V::WIDTH && get(k) != Color::Light;
-                if !((j - 4)..j).any(&check) || !((j + 7)..(j + 11)).any(&check) {
-                    total_score += 40;
-                }
-            }
+        // Bit x is set where `at(k)` matches the pattern at x..x+7, and there
+        // are only light modules (or the edge) in x−4..x or in x+7..x+11.
+        fn count<F: Fn(isize) -> Row>(at: F) -> u32 {
+            let pattern = at(0) & !at(1) & at(2) & at(3) & at(4) & !at(5) & at(6);
+            let before = at(-1) | at(-2) | at(-3) | at(-4);
+            let after = at(7) | at(8) | at(9) | at(10);
+            (pattern & !(before & after)).count_ones()
         }
 
-        total_score - 360
+        let width = Self::WIDTH as isize;
+        let matches: u32 = if is_horizontal {
+            (0..Self::WIDTH)
+                .map(|y| {
+                    let row = self.row(y);
+                    count(|k| if k < 0 { row.shl(-k as u32) } else { row.shr(k as u32) })
+                })
+                .sum()
+        } else {
+            (0..width - 6)
+                .map(|y| {
+                    count(|k| if (0..width).contains(&(y + k)) { self.row((y + k) as usize) } else { Row::default() })
+                })
+                .sum()
+        };
+
+        // Saturating, as a symbol read with `QrCode::from_colors` may have
+        // damaged finder patterns.
+        (matches * 40).as_u16().saturating_sub(360)
     }
 
     /// Compute the penalty score for having an unbalanced dark/light ratio.
@@ -1843,10 +2875,10 @@ impl<V: QrSpec> Canvas<V> {
     /// round the result every 5%, but the difference should be negligible and
     /// should not affect which mask is chosen.
     fn compute_balance_penalty_score(&self) -> u16 {
-        let dark_modules = Module::from_iter(self.modules.iter().copied(), V::AREA).filter(|m| m.is_dark()).count();
+        let dark_modules: u32 = self.bytes.iter().map(|b| b.count_ones()).sum();
         let total_modules = V::AREA;
-        let ratio = dark_modules * 200 / total_modules;
-        if ratio >= 100 { ratio - 100 } else { 100 - ratio }.as_u16()
+        let ratio = dark_modules.as_usize() * 200 / total_modules;
+        ratio.abs_diff(100).as_u16()
     }
 
     /// Compute the penalty score for having too many light modules on the sides.
@@ -1855,10 +2887,13 @@ impl<V: QrSpec> Canvas<V> {
     ///
     /// Note that the standard gives the formula for *efficiency* score, which
     /// has the inverse meaning of this method, but it is very easy to convert
-    /// between the two (this score is (16×width − standard-score)).
+    /// between the two (this score is (17×(width − 1) − standard-score), see
+    /// `PenaltyScores::micro_efficiency`).
     fn compute_light_side_penalty_score(&self) -> u16 {
-        let h = (1..V::WIDTH).filter(|j| !self.get(*j, -1).is_dark()).count();
-        let v = (1..V::WIDTH).filter(|j| !self.get(-1, *j).is_dark()).count();
+        let last = Self::WIDTH - 1;
+        let sides = Row::ones(Self::WIDTH) & !Row::ones(1);
+        let h = last - (self.row(last) & sides).count_ones().as_usize();
+        let v = (1..Self::WIDTH).filter(|&y| !self.row(y).get(last)).count();
 
         (h + v + 15 * max(h, v)).as_u16()
     }
@@ -1879,25 +2914,80 @@ impl<V: QrSpec> Canvas<V> {
             Version::Micro(_) => self.compute_light_side_penalty_score(),
         }
     }
+
+    /// Computes the score of every rule, which add up to
+    /// `compute_total_penalty_scores`.
+    pub(crate) fn penalty_scores(&self) -> PenaltyScores {
+        match V::VERSION {
+            Version::Normal(_) => PenaltyScores {
+                adjacent: self.compute_adjacent_penalty_score(true) + self.compute_adjacent_penalty_score(false),
+                blocks: self.compute_block_penalty_score(),
+                finder: self.compute_finder_penalty_score(true) + self.compute_finder_penalty_score(false),
+                balance: self.compute_balance_penalty_score(),
+                light_side: 0,
+            },
+            Version::Micro(_) => {
+                PenaltyScores { light_side: self.compute_light_side_penalty_score(), ..PenaltyScores::default() }
+            }
+        }
+    }
 }
 
+impl<V: QrSpec> Canvas<V> {
+    /// Packs the colors the canvas would have after applying the mask pattern,
+    /// leaving the canvas itself untouched.
+    #[cfg(any(test, feature = "rayon"))]
+    fn masked_colors(&self, pattern: MaskPattern) -> ColorMatrix<V> {
+        let mut matrix = ColorMatrix::new();
+        self.masked_colors_into(pattern, &mut matrix);
+        matrix
+    }
+
+    /// Like `masked_colors`, but overwrites `matrix`.
+    fn masked_colors_into(&self, pattern: MaskPattern, matrix: &mut ColorMatrix<V>) {
+        let width = V::WIDTH.as_usize();
+        matrix.ensure_len();
+        for y in 0..width {
+            let (dark, unmasked) = self.read_row(y);
+            matrix.set_row(y, dark ^ (unmasked & pattern.row(y, width)));
+        }
+        // An unsupported version fails later in `apply_mask`, so its format
+        // information only needs a placeholder here.
+        let format_info = Self::format_info(pattern).unwrap_or(0);
+        for_each_format_info_module::<V, _>(format_info, |x, y, color| matrix.put(x, y, color));
+    }
+}
 
 #[cfg(test)]
 mod penalty_tests {
-    use crate::canvas::{Canvas, MaskPattern};
-    use crate::spec::{Version1, EcLevelQ};
-
-    fn create_test_canvas() -> Canvas<Version1<EcLevelQ>> {
+    use crate::canvas::{
+        Canvas, ColorMatrix, MaskPattern, Module, PenaltyScores, ALL_PATTERNS_MICRO_QR, ALL_PATTERNS_QR,
+    };
+    use crate::ec::construct_codewords;
+    use crate::spec::{EcLevelH, EcLevelQ, QrSpec, Version1, Version12, Version40};
+    use crate::types::{Color, EcLevel, Version};
+    use heapless::consts::{U10, U14, U17, U24, U51, U73};
+
+    fn create_unmasked_canvas() -> Canvas<Version1<EcLevelQ>> {
         let mut c = Canvas::new();
         c.draw_all_functional_patterns();
         c.draw_data(
             b"\x20\x5b\x0b\x78\xd1\x72\xdc\x4d\x43\x40\xec\x11\x00",
             b"\xa8\x48\x16\x52\xd9\x36\x9c\x00\x2e\x0f\xb4\x7a\x10",
         );
-        c.apply_mask(MaskPattern::Checkerboard);
         c
     }
 
+    fn create_test_canvas() -> Canvas<Version1<EcLevelQ>> {
+        let mut c = create_unmasked_canvas();
+        c.apply_mask(MaskPattern::Checkerboard).unwrap();
+        c
+    }
+
+    fn create_test_matrix() -> ColorMatrix<Version1<EcLevelQ>> {
+        create_unmasked_canvas().masked_colors(MaskPattern::Checkerboard)
+    }
+
     #[test]
     fn check_penalty_canvas() {
         let c = create_test_canvas();
@@ -1930,79 +3020,221 @@ mod penalty_tests {
 
     #[test]
     fn test_penalty_score_adjacent() {
-        let c = create_test_canvas();
-        assert_eq!(c.compute_adjacent_penalty_score(true), 88);
-        assert_eq!(c.compute_adjacent_penalty_score(false), 92);
+        let m = create_test_matrix();
+        assert_eq!(m.compute_adjacent_penalty_score(true), 88);
+        assert_eq!(m.compute_adjacent_penalty_score(false), 92);
     }
 
     #[test]
     fn test_penalty_score_block() {
-        let c = create_test_canvas();
-        assert_eq!(c.compute_block_penalty_score(), 90);
+        let m = create_test_matrix();
+        assert_eq!(m.compute_block_penalty_score(), 90);
     }
 
     #[test]
     fn test_penalty_score_finder() {
-        let c = create_test_canvas();
-        assert_eq!(c.compute_finder_penalty_score(true), 0);
-        assert_eq!(c.compute_finder_penalty_score(false), 40);
+        let m = create_test_matrix();
+        assert_eq!(m.compute_finder_penalty_score(true), 0);
+        assert_eq!(m.compute_finder_penalty_score(false), 40);
     }
 
     #[test]
     fn test_penalty_score_balance() {
-        let c = create_test_canvas();
-        assert_eq!(c.compute_balance_penalty_score(), 2);
+        let m = create_test_matrix();
+        assert_eq!(m.compute_balance_penalty_score(), 2);
+    }
+
+    /// Computes the total penalty score module by module.
+    fn naive_penalty_score<V: QrSpec>(c: &Canvas<V>) -> u16 {
+        let w = V::WIDTH;
+        let dark = |x, y| c.get(x, y).is_dark();
+        let line = |i, j, horizontal| if horizontal { dark(j, i) } else { dark(i, j) };
+        let mut score = 0;
+        for horizontal in [true, false] {
+            for i in 0..w {
+                let mut run = 1;
+                for j in 1..=w {
+                    if j < w && line(i, j, horizontal) == line(i, j - 1, horizontal) {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        score += run - 2;
+                    }
+                    run = 1;
+                }
+                for j in 0..w - 6 {
+                    let pattern = [true, false, true, true, true, false, true];
+                    if (0..7).any(|k| line(i, j + k, horizontal) != pattern[k as usize]) {
+                        continue;
+                    }
+                    let light =
+                        |r: core::ops::Range<i16>| r.filter(|&k| 0 <= k && k < w).all(|k| !line(i, k, horizontal));
+                    if light(j - 4..j) || light(j + 7..j + 11) {
+                        score += 40;
+                    }
+                }
+            }
+            score -= 360;
+        }
+        for x in 0..w - 1 {
+            for y in 0..w - 1 {
+                if dark(x, y) == dark(x + 1, y) && dark(x, y) == dark(x, y + 1) && dark(x, y) == dark(x + 1, y + 1) {
+                    score += 3;
+                }
+            }
+        }
+        let dark_modules = (0..w).flat_map(|y| (0..w).map(move |x| (x, y))).filter(|&(x, y)| dark(x, y)).count();
+        score + (dark_modules * 200 / V::AREA).abs_diff(100) as u16
+    }
+
+    fn check_against_naive<V: QrSpec>(seed: u8) {
+        let data: Vec<u8> = (0..V::BLOCK_LAYOUT.data_len()).map(|i| (i as u8).wrapping_mul(seed)).collect();
+        let (codewords, data_end) = construct_codewords::<V>(&data).unwrap();
+        let mut unmasked = Canvas::<V>::new();
+        unmasked.draw_all_functional_patterns();
+        unmasked.draw_data(&codewords[..data_end], &codewords[data_end..]);
+
+        let mut best = (u16::MAX, ALL_PATTERNS_QR[0]);
+        for &pattern in &ALL_PATTERNS_QR {
+            let mut c = unmasked.clone();
+            c.apply_mask(pattern).unwrap();
+            let m = unmasked.masked_colors(pattern);
+            for y in 0..V::WIDTH {
+                for x in 0..V::WIDTH {
+                    assert_eq!(m.row(y as usize).get(x as usize), c.get(x, y) == Module::Masked(Color::Dark));
+                }
+            }
+            let score = naive_penalty_score(&c);
+            assert_eq!(m.compute_total_penalty_scores(), score, "{:?}", pattern);
+            if score < best.0 {
+                best = (score, pattern);
+            }
+        }
+        assert_eq!(unmasked.best_mask(&mut ColorMatrix::empty()), best.1);
     }
 
-    // #[test]
-    // fn test_penalty_score_light_sides() {
-    //     static HORIZONTAL_SIDE: [Color; 17] = [
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Dark,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Light,
-    //     ];
-    //     static VERTICAL_SIDE: [Color; 17] = [
-    //         Color::Dark,
-    //         Color::Dark,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //         Color::Light,
-    //         Color::Dark,
-    //         Color::Light,
-    //     ];
-
-    //     let mut c = Canvas::new(Version::Micro(4), EcLevel::Q);
-    //     for i in 0_i16..17 {
-    //         c.put(i, -1, HORIZONTAL_SIDE[i as usize]);
-    //         c.put(-1, i, VERTICAL_SIDE[i as usize]);
-    //     }
-
-    //     assert_eq!(c.compute_light_side_penalty_score(), 168);
-    // }
+    #[test]
+    fn test_penalty_score_naive() {
+        check_against_naive::<Version1<EcLevelQ>>(1);
+        check_against_naive::<Version12<EcLevelH>>(37);
+        check_against_naive::<Version40<EcLevelH>>(101);
+    }
+
+    /// Micro QR code version 4 at level Q, as there are no Micro QR code
+    /// specs to encode with yet.
+    struct MicroVersion4Q;
+
+    impl QrSpec for MicroVersion4Q {
+        type TotalSize = U24;
+        type ECGenBufferSize = U24;
+        type ECBlocksSize = U14;
+        type CanvasSize = U73;
+        type ColorSize = U51;
+        type BitsSize = U10;
+        type TextSize = U24;
+        type Width = U17;
+
+        const WIDTH: i16 = 17;
+        const BLOCK_1_SIZE: usize = 10;
+        const BLOCK_1_COUNT: usize = 1;
+        const BLOCK_2_SIZE: usize = 0;
+        const BLOCK_2_COUNT: usize = 0;
+        const EC_BYTES_PER_BLOCK: usize = 14;
+        const VERSION: Version = Version::Micro(4);
+        const EC_LEVEL: EcLevel = EcLevel::Q;
+    }
+
+    #[test]
+    fn test_penalty_score_light_sides() {
+        static HORIZONTAL_SIDE: [Color; 17] = [
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Dark,
+            Color::Dark,
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Light,
+        ];
+        static VERTICAL_SIDE: [Color; 17] = [
+            Color::Dark,
+            Color::Dark,
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Dark,
+            Color::Dark,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+            Color::Light,
+            Color::Dark,
+            Color::Light,
+        ];
+
+        let mut c = Canvas::<MicroVersion4Q>::new();
+        for i in 0_i16..17 {
+            c.put(i, -1, HORIZONTAL_SIDE[i as usize]);
+            c.put(-1, i, VERTICAL_SIDE[i as usize]);
+        }
+
+        let m = ColorMatrix::<MicroVersion4Q>::from_colors(c.colors());
+        assert_eq!(m.compute_light_side_penalty_score(), 168);
+        assert_eq!(m.compute_total_penalty_scores(), 168);
+        let scores = m.penalty_scores();
+        assert_eq!(scores, PenaltyScores { light_side: 168, ..PenaltyScores::default() });
+        // 6 dark modules at the bottom and 8 on the right: 6 × 16 + 8.
+        assert_eq!(scores.micro_efficiency(17), 104);
+    }
+
+    /// The efficiency score of a Micro QR code as the standard computes it.
+    fn micro_efficiency<V: QrSpec>(c: &Canvas<V>) -> u32 {
+        let dark = |x, y| u32::from(c.get(x, y) == Module::Masked(Color::Dark));
+        let right: u32 = (1..V::WIDTH).map(|y| dark(V::WIDTH - 1, y)).sum();
+        let bottom: u32 = (1..V::WIDTH).map(|x| dark(x, V::WIDTH - 1)).sum();
+        if right <= bottom {
+            right * 16 + bottom
+        } else {
+            bottom * 16 + right
+        }
+    }
+
+    #[test]
+    fn test_micro_mask_by_efficiency() {
+        for seed in [1_u8, 7, 37, 101] {
+            let codewords: Vec<u8> = (0..24).map(|i: u8| i.wrapping_mul(seed) ^ seed).collect();
+            let mut unmasked = Canvas::<MicroVersion4Q>::new();
+            unmasked.draw_all_functional_patterns();
+            unmasked.draw_data(&codewords[..10], &codewords[10..]);
+
+            let mut best = (0, ALL_PATTERNS_MICRO_QR[0]);
+            for &pattern in &ALL_PATTERNS_MICRO_QR {
+                let mut c = unmasked.clone();
+                c.apply_mask(pattern).unwrap();
+                let efficiency = micro_efficiency(&c);
+                let scores = unmasked.masked_colors(pattern).penalty_scores();
+                assert_eq!(scores.micro_efficiency(17), efficiency, "{:?}", pattern);
+                if efficiency > best.0 {
+                    best = (efficiency, pattern);
+                }
+            }
+            assert_eq!(unmasked.best_mask(&mut ColorMatrix::empty()), best.1);
+        }
+    }
 }
 
 //}}}
@@ -2023,21 +3255,131 @@ static ALL_PATTERNS_QR: [MaskPattern; 8] = [
 static ALL_PATTERNS_MICRO_QR: [MaskPattern; 4] =
     [MaskPattern::HorizontalLines, MaskPattern::LargeCheckerboard, MaskPattern::Diamonds, MaskPattern::Meadow];
 
+/// The smallest version whose mask patterns are scored in parallel with the
+/// `rayon` feature. Below it, spawning the jobs costs more than it saves.
+#[cfg(feature = "rayon")]
+const PARALLEL_MASK_MIN_VERSION: i16 = 20;
+
+/// How to choose the mask pattern of a symbol.
+///
+/// Any of the patterns gives a valid symbol which every scanner reads: the
+/// pattern is recorded in the format information. The standard recommends
+/// the one with the lowest penalty score, as `QrCode::new` does, because it
+/// is the least likely to confuse a scanner, but the others are still within
+/// the specification, and may look better in a design.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum MaskStrategy {
+    /// The pattern with the lowest total penalty score of ISO/IEC 18004.
+    #[default]
+    Penalty,
+
+    /// The pattern whose dark modules are closest to half of the symbol.
+    Balanced,
+
+    /// The pattern with the fewest long runs and 2×2 blocks of one color, the
+    /// lowest sum of the `adjacent` and `blocks` penalty scores, for the most
+    /// even texture.
+    Uniform,
+
+    /// Always the given pattern, which must be valid for the version.
+    Fixed(MaskPattern),
+}
+
 impl<V: QrSpec> Canvas<V> {
     /// Construct a new canvas and apply the best masking that gives the lowest
     /// penalty score.
-    pub fn apply_best_mask(&self) -> Canvas<V> {
-        match V::VERSION {
-            Version::Normal(_) => ALL_PATTERNS_QR.iter(),
-            Version::Micro(_) => ALL_PATTERNS_MICRO_QR.iter(),
+    ///
+    /// With the `rayon` feature, the patterns of version 20 and above are
+    /// scored in parallel. Ties are broken the same way in both cases.
+    ///
+    /// Fails like `apply_mask` for an invalid Micro QR code version.
+    pub fn apply_best_mask(&self) -> QrResult<Canvas<V>> {
+        let ptn = self.best_mask(&mut ColorMatrix::empty());
+        let mut c: Canvas<V> = Canvas::clone(self);
+        c.apply_mask(ptn)?;
+        Ok(c)
+    }
+
+    /// Finds the first mask pattern with the lowest penalty score, drawing the
+    /// candidates into `scratch`.
+    ///
+    /// With the `tracing` feature, every score is emitted as a trace event,
+    /// and the selected pattern as a debug event.
+    pub(crate) fn best_mask(&self, scratch: &mut ColorMatrix<V>) -> MaskPattern {
+        let (pattern, _penalty) = self.score_masks(scratch);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(version = ?V::VERSION, ec_level = ?V::EC_LEVEL, ?pattern, penalty = _penalty, "mask selected");
+        pattern
+    }
+
+    /// Scores all mask patterns, returning the one with the lowest penalty
+    /// score together with that score. Ties go to the lowest pattern number.
+    pub(crate) fn score_masks(&self, scratch: &mut ColorMatrix<V>) -> (MaskPattern, u16) {
+        let patterns: &[MaskPattern] = match V::VERSION {
+            Version::Normal(_) => &ALL_PATTERNS_QR,
+            Version::Micro(_) => &ALL_PATTERNS_MICRO_QR,
+        };
+        let scored = |pattern: MaskPattern, penalty: u16| {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(?pattern, penalty, "mask scored");
+            (pattern, penalty)
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            if let Version::Normal(v) = V::VERSION {
+                if v >= PARALLEL_MASK_MIN_VERSION {
+                    return patterns
+                        .par_iter()
+                        .map(|&ptn| scored(ptn, self.masked_colors(ptn).compute_total_penalty_scores()))
+                        .min_by_key(|&(pattern, penalty)| (penalty, pattern as u8))
+                        .expect("at least one pattern");
+                }
+            }
         }
-        .map(|ptn| {
-            let mut c: Canvas<V> = Canvas::clone(self);
-            c.apply_mask(*ptn);
-            c
-        })
-        .min_by_key(Self::compute_total_penalty_scores)
-        .expect("at least one pattern")
+
+        patterns
+            .iter()
+            .map(|&ptn| {
+                self.masked_colors_into(ptn, scratch);
+                scored(ptn, scratch.compute_total_penalty_scores())
+            })
+            .min_by_key(|&(pattern, penalty)| (penalty, pattern as u8))
+            .expect("at least one pattern")
+    }
+
+    /// Chooses the mask pattern for the unmasked canvas with the given
+    /// strategy. Ties go to the lowest penalty score, then to the lowest
+    /// pattern number. See `QrCode::with_mask_strategy`.
+    pub fn select_mask(&self, strategy: MaskStrategy) -> MaskPattern {
+        self.select_mask_in(strategy, &mut ColorMatrix::empty())
+    }
+
+    /// Like `select_mask`, drawing the candidates into `scratch`.
+    pub(crate) fn select_mask_in(&self, strategy: MaskStrategy, scratch: &mut ColorMatrix<V>) -> MaskPattern {
+        let metric = match strategy {
+            MaskStrategy::Penalty => return self.best_mask(scratch),
+            MaskStrategy::Fixed(pattern) => return pattern,
+            MaskStrategy::Balanced => |m: &ColorMatrix<V>| (m.count_dark() * 2).abs_diff(V::AREA),
+            MaskStrategy::Uniform => |m: &ColorMatrix<V>| {
+                let scores = m.penalty_scores();
+                usize::from(scores.adjacent + scores.blocks)
+            },
+        };
+        let patterns: &[MaskPattern] = match V::VERSION {
+            Version::Normal(_) => &ALL_PATTERNS_QR,
+            Version::Micro(_) => &ALL_PATTERNS_MICRO_QR,
+        };
+        patterns
+            .iter()
+            .map(|&pattern| {
+                self.masked_colors_into(pattern, scratch);
+                (metric(scratch), scratch.compute_total_penalty_scores(), pattern as u8, pattern)
+            })
+            .min_by_key(|&(metric, penalty, bits, _)| (metric, penalty, bits))
+            .map(|(_, _, _, pattern)| pattern)
+            .expect("at least one pattern")
     }
 
     /// Convert the modules into a vector of colors.
@@ -2065,27 +3407,182 @@ impl<V: QrSpec> Canvas<V> {
 
     /// Convert the modules into a vector of colors.
     pub fn color_line_bits(&self) -> Vec<u8, V::ColorSize> {
-        let mut result = Vec::new();
-        let mut buf = 0_u8;
-        let mut i = 0;
-        for color in self.colors() {
-            buf <<= 1;
-            if let Color::Dark = color {
-                buf |= 0b1
-            }
-            
-            i += 1;
-            if i % 8 == 0 {
-                result.push(buf).unwrap();
-                buf = 0;
-            }
-            if i == V::WIDTH {
-                result.push(buf).unwrap();
-                buf = 0;
-                i = 0;
-            }
+        pack_color_lines::<V>(self.colors()).to_vec()
+    }
+}
+
+/// Packs the colors of a symbol row by row, padding every row to whole bytes.
+/// The last byte of a row holds its remaining modules in the low bits.
+pub(crate) fn pack_color_lines<V: QrSpec>(colors: impl Iterator<Item = Color>) -> Buffer<V::ColorSize> {
+    let mut result = Buffer::new();
+    pack_color_lines_into::<V>(colors, &mut result);
+    result
+}
+
+/// Like `pack_color_lines`, but replaces the contents of `result`.
+pub(crate) fn pack_color_lines_into<V: QrSpec>(
+    colors: impl Iterator<Item = Color>,
+    result: &mut Buffer<V::ColorSize>,
+) {
+    result.clear();
+    let mut buf = 0_u8;
+    let mut i = 0;
+    for color in colors {
+        buf <<= 1;
+        if let Color::Dark = color {
+            buf |= 0b1
         }
-        result
+
+        i += 1;
+        if i % 8 == 0 {
+            result.push(buf).unwrap();
+            buf = 0;
+        }
+        if i == V::WIDTH {
+            result.push(buf).unwrap();
+            buf = 0;
+            i = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod mask_strategy_tests {
+    use crate::bits::Bits;
+    use crate::canvas::{Canvas, ColorMatrix, MaskPattern, MaskStrategy, PenaltyScores, ALL_PATTERNS_QR};
+    use crate::ec::Codewords;
+    use crate::spec::{EcLevelM, Version3};
+
+    #[test]
+    fn test_strategies() {
+        let mut bits = Bits::<Version3<EcLevelM>>::new();
+        bits.push_optimal_data(b"mask strategies").unwrap();
+        bits.push_terminator().unwrap();
+        let rawbits = bits.into_bytes();
+        let mut canvas = Canvas::<Version3<EcLevelM>>::new();
+        canvas.draw_all_functional_patterns();
+        canvas.draw_codewords_iter(Codewords::new(&rawbits).unwrap());
+
+        let scores: Vec<(MaskPattern, usize, PenaltyScores)> = ALL_PATTERNS_QR
+            .iter()
+            .map(|&pattern| {
+                let matrix = canvas.masked_colors(pattern);
+                (pattern, matrix.count_dark(), matrix.penalty_scores())
+            })
+            .collect();
+        let best_by = |key: &dyn Fn(usize, PenaltyScores) -> usize| {
+            scores.iter().min_by_key(|&&(p, dark, s)| (key(dark, s), s.total(), p as u8)).unwrap().0
+        };
+
+        let penalty = canvas.best_mask(&mut ColorMatrix::empty());
+        assert_eq!(canvas.select_mask(MaskStrategy::Penalty), penalty);
+        assert_eq!(canvas.select_mask(MaskStrategy::default()), penalty);
+        assert_eq!(canvas.select_mask(MaskStrategy::Balanced), best_by(&|dark, _| (dark * 2).abs_diff(29 * 29)));
+        assert_eq!(canvas.select_mask(MaskStrategy::Uniform), best_by(&|_, s| usize::from(s.adjacent + s.blocks)));
+        assert_eq!(canvas.select_mask(MaskStrategy::Fixed(MaskPattern::Fields)), MaskPattern::Fields);
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Reading modules back
+
+/// Reads a big-endian integer from the modules at the given coordinates, the
+/// reverse of `Canvas::draw_number`.
+fn read_number<F: Fn(i16, i16) -> Color>(get: &F, coords: &[(i16, i16)]) -> u32 {
+    coords.iter().fold(0, |number, &(x, y)| number << 1 | u32::from(get(x, y) == Color::Dark))
+}
+
+/// Reads the two copies of the format information of a normal QR code.
+///
+/// `get` returns the color of the module at the given coordinates, where
+/// negative coordinates count from the right or bottom edge.
+pub fn read_format_info_qr<F: Fn(i16, i16) -> Color>(get: &F) -> (u16, u16) {
+    let main = read_number(get, &FORMAT_INFO_COORDS_QR_MAIN) as u16;
+    let side = read_number(get, &FORMAT_INFO_COORDS_QR_SIDE) as u16;
+    (main, side)
+}
+
+/// Reads the two copies of the version information of a normal QR code, the
+/// bottom-left copy first.
+pub fn read_version_info<F: Fn(i16, i16) -> Color>(get: &F) -> (u32, u32) {
+    (read_number(get, &VERSION_INFO_COORDS_BL), read_number(get, &VERSION_INFO_COORDS_TR))
+}
+
+/// Checks whether the timing patterns of a normal QR code are intact.
+pub fn check_timing_patterns<F: Fn(i16, i16) -> Color>(get: &F, width: i16) -> bool {
+    (8..width - 8).all(|i| {
+        let expected = if i % 2 == 0 { Color::Dark } else { Color::Light };
+        get(i, 6) == expected && get(6, i) == expected
+    })
+}
+
+/// Checks whether the module at the given (non-negative) coordinates holds
+/// version information. `is_functional` does not include these modules.
+pub(crate) fn is_version_info(version: Version, width: i16, x: i16, y: i16) -> bool {
+    match version {
+        Version::Normal(7..=40) => {
+            (x < 6 && y >= width - 11 && y < width - 8) || (y < 6 && x >= width - 11 && x < width - 8)
+        }
+        _ => false,
+    }
+}
+
+/// Iterates the coordinates of the modules holding codewords and remainder
+/// bits, in placement order, so that the `i`-th module holds bit `7 - i % 8`
+/// of codeword `i / 8`.
+pub(crate) fn data_modules(version: Version) -> impl Iterator<Item = (i16, i16)> {
+    let width = version.width();
+    DataModuleIter::new(version)
+        .filter(move |&(x, y)| !is_functional_unchecked(version, width, x, y) && !is_version_info(version, width, x, y))
+}
+
+/// Reads the data and error correction codewords in placement order,
+/// removing the mask pattern. This is the reverse of `Canvas::draw_data`
+/// followed by `Canvas::apply_mask`.
+///
+/// `codewords` is filled completely; the remainder bits after it are ignored.
+pub fn read_codewords<F: Fn(i16, i16) -> Color>(get: &F, version: Version, pattern: MaskPattern, codewords: &mut [u8]) {
+    let mask_fn = get_mask_function(pattern);
+    let mut coords = data_modules(version);
+    for codeword in codewords {
+        *codeword = coords
+            .by_ref()
+            .take(8)
+            .fold(0, |byte, (x, y)| byte << 1 | u8::from((get(x, y) == Color::Dark) != mask_fn(x, y)));
+    }
+}
+
+#[cfg(test)]
+mod read_tests {
+    use crate::canvas::{
+        check_timing_patterns, encode_format_info, read_codewords, read_format_info_qr, read_version_info, Canvas,
+        MaskPattern, FORMAT_INFO_MASK_QR,
+    };
+    use crate::ec::construct_codewords;
+    use crate::spec::{EcLevelQ, QrSpec, Version7};
+    use crate::types::Color;
+
+    #[test]
+    fn test_read_back() {
+        type V = Version7<EcLevelQ>;
+        let data: Vec<u8> = (0..V::BLOCK_LAYOUT.data_len()).map(|i| i as u8).collect();
+        let (codewords, data_end) = construct_codewords::<V>(&data).unwrap();
+
+        let mut c = Canvas::<V>::new();
+        c.draw_all_functional_patterns();
+        c.draw_data(&codewords[..data_end], &codewords[data_end..]);
+        c.apply_mask(MaskPattern::Diamonds).unwrap();
+        let get = |x, y| Color::from(c.get(x, y));
+
+        let format_info = encode_format_info(0b11110, FORMAT_INFO_MASK_QR);
+        assert_eq!(read_format_info_qr(&get), (format_info, format_info));
+        assert_eq!(read_version_info(&get), (0x07c94, 0x07c94));
+        assert!(check_timing_patterns(&get, V::WIDTH));
+
+        let mut read = [0; 196];
+        read_codewords(&get, V::VERSION, MaskPattern::Diamonds, &mut read);
+        assert_eq!(&read[..], &codewords[..]);
     }
 }
 
@@ -1,16 +1,15 @@
-use core::fmt::Display;
-
 pub trait Truncate {
     fn truncate_as_u8(self) -> u8;
 }
 
 impl Truncate for u16 {
-    #[cfg_attr(feature = "cargo-clippy", allow(cast_possible_truncation))]
+    #[allow(clippy::cast_possible_truncation)]
     fn truncate_as_u8(self) -> u8 {
         (self & 0xff) as u8
     }
 }
 
+#[allow(clippy::wrong_self_convention, dead_code)]
 pub trait As {
     fn as_u16(self) -> u16;
     fn as_i16(self) -> i16;
@@ -19,24 +18,9 @@ pub trait As {
     fn as_isize(self) -> isize;
 }
 
-trait ExpectOrOverflow {
-    type Output;
-    fn expect_or_overflow<D: Display>(self, value: D, ty: &str) -> Self::Output;
-}
-
-impl<T> ExpectOrOverflow for Option<T> {
-    type Output = T;
-    fn expect_or_overflow<D: Display>(self, value: D, ty: &str) -> Self::Output {
-        match self {
-            Some(v) => v,
-            None => panic!("{} overflows {}", value, ty),
-        }
-    }
-}
-
 macro_rules! impl_as {
     ($ty:ty) => {
-        #[cfg_attr(feature = "cargo-clippy", allow(cast_possible_truncation))]
+        #[allow(clippy::cast_possible_truncation)]
         impl As for $ty {
             fn as_u16(self) -> u16 {
                 self as u16
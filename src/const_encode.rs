@@ -0,0 +1,471 @@
+//! A `const fn` encoder.
+//!
+//! `encode_const` runs the whole pipeline (byte mode bits, Reed-Solomon error
+//! correction, module placement and a fixed mask pattern) in a `const`
+//! context, so a fixed symbol can be stored in a `static` without the
+//! `qrcode-macros` crate or any work at run time.
+//!
+//!     use qrcode::canvas::MaskPattern;
+//!     use qrcode::const_encode::{encode_const, packed_len};
+//!     use qrcode::spec::{EcLevelM, QrSpec, Version2};
+//!     use qrcode::PackedQrCode;
+//!
+//!     type V = Version2<EcLevelM>;
+//!     static MODULES: [u8; packed_len::<V>()] =
+//!         encode_const::<V, { packed_len::<V>() }>(b"https://example.com", MaskPattern::Checkerboard);
+//!     static CODE: PackedQrCode<'static> = PackedQrCode::new(V::VERSION, V::EC_LEVEL, &MODULES);
+//!
+//!     assert_eq!(CODE.width(), 25);
+//!
+//! Unlike `QrCode::new`, the data is always encoded in byte mode, and the mask
+//! pattern is given by the caller instead of chosen by penalty score. Data
+//! which does not fit the version is a compile error in a `const` or `static`,
+//! and a panic otherwise.
+//!
+//! Large versions take a while to evaluate, and may trigger the
+//! `long_running_const_eval` lint.
+//!
+//! Data encoded at run time by `QrCode::new` can be checked at compile time
+//! with `assert_fits!`, which runs the same segmentation in a `const`:
+//!
+//!     use qrcode::spec::{EcLevelM, Version3};
+//!     use qrcode::{assert_fits, QrCode};
+//!
+//!     const URL: &[u8] = b"https://example.com/device/0042";
+//!     assert_fits!(Version3<EcLevelM>, URL);
+//!
+//!     let code = QrCode::<Version3<EcLevelM>>::new(URL).unwrap();
+//!
+//! With a longer `URL`, or a smaller version, the crate using it fails to
+//! compile instead of `QrCode::new` returning `Err(QrError::DataTooLong)` on
+//! the device. Version 1 at level H holds only 7 bytes:
+//!
+//! ```compile_fail,E0080
+//! use qrcode::assert_fits;
+//! use qrcode::spec::{EcLevelH, Version1};
+//!
+//! const URL: &[u8] = b"https://example.com/device/0042";
+//! assert_fits!(Version1<EcLevelH>, URL);
+//! ```
+
+use crate::bits::DATA_LENGTHS;
+use crate::canvas::{
+    alignment_pattern_positions, encode_format_info, encode_version_info, MaskPattern, FORMAT_INFO_COORDS_QR_MAIN,
+    FORMAT_INFO_COORDS_QR_SIDE, FORMAT_INFO_MASK_QR, VERSION_INFO_COORDS_BL, VERSION_INFO_COORDS_TR,
+};
+use crate::ec::create_error_correction_code;
+use crate::optimize::SliceOptimizer;
+use crate::spec::{BlockLayout, QrSpec};
+use crate::types::{Mode, Version};
+
+/// The number of codewords of the largest symbol (version 40).
+const MAX_CODEWORDS: usize = 3706;
+
+/// Longer than any error correction block, data and error correction
+/// codewords together.
+const MAX_BLOCK_LEN: usize = 256;
+
+/// The number of bytes of the packed modules of a symbol, the `N` parameter
+/// of `encode_const`.
+pub const fn packed_len<V: QrSpec>() -> usize {
+    V::AREA.div_ceil(8)
+}
+
+/// Encodes the data in byte mode with the given mask pattern, and returns the
+/// packed modules in the layout of `PackedQrCode`.
+///
+/// `N` must be `packed_len::<V>()`. Micro QR codes are not supported.
+pub const fn encode_const<V: QrSpec, const N: usize>(data: &[u8], pattern: MaskPattern) -> [u8; N] {
+    assert!(N == packed_len::<V>(), "N must be packed_len::<V>()");
+    let version = match V::VERSION {
+        Version::Normal(v) => v,
+        Version::Micro(_) => panic!("Micro QR codes are not supported"),
+    };
+
+    let (codewords, total_len) = codewords(data, version, V::BLOCK_LAYOUT);
+    let mut symbol = Symbol::<N>::new(V::WIDTH);
+    symbol.draw_functional_patterns(version);
+    let format_number = ((V::EC_LEVEL as u8) ^ 1) << 3 | pattern as u8;
+    symbol.draw_format_info(encode_format_info(format_number, FORMAT_INFO_MASK_QR));
+    symbol.draw_codewords(&codewords, total_len, pattern);
+    symbol.dark
+}
+
+//------------------------------------------------------------------------------
+//{{{ Codewords
+
+/// Appends bits to a buffer of codewords.
+struct BitWriter {
+    bytes: [u8; MAX_CODEWORDS],
+    len: usize,
+}
+
+impl BitWriter {
+    const fn push(&mut self, value: u32, bits: usize) {
+        let mut i = bits;
+        while i > 0 {
+            i -= 1;
+            if value >> i & 1 != 0 {
+                self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Encodes the data in byte mode, pads it to the capacity of the symbol, and
+/// appends the error correction codewords. Returns the interleaved codewords
+/// and their number.
+const fn codewords(data: &[u8], version: i16, layout: BlockLayout) -> ([u8; MAX_CODEWORDS], usize) {
+    let capacity = layout.data_len() * 8;
+    let count_bits = if version <= 9 { 8 } else { 16 };
+    assert!(4 + count_bits + data.len() * 8 <= capacity, "data too long for the version and error correction level");
+
+    let mut bits = BitWriter { bytes: [0; MAX_CODEWORDS], len: 0 };
+    bits.push(0b0100, 4);
+    bits.push(data.len() as u32, count_bits);
+    let mut i = 0;
+    while i < data.len() {
+        bits.push(data[i] as u32, 8);
+        i += 1;
+    }
+
+    // The terminator and the padding to whole bytes are zero bits, which are
+    // already in place.
+    let mut len = bits.len.div_ceil(8);
+    let mut padding = 0b1110_1100;
+    while len < layout.data_len() {
+        bits.bytes[len] = padding;
+        padding ^= 0b1111_1101;
+        len += 1;
+    }
+
+    let mut codewords = [0; MAX_CODEWORDS];
+    let mut block = 0;
+    let mut start = 0;
+    while block < layout.blocks_count() {
+        let size = if block < layout.block_1_count { layout.block_1_size } else { layout.block_2_size };
+        let mut buffer = [0; MAX_BLOCK_LEN];
+        let mut i = 0;
+        while i < size {
+            buffer[i] = bits.bytes[start + i];
+            i += 1;
+        }
+        let (buffer, _) = buffer.split_at_mut(size + layout.ec_bytes_per_block);
+        create_error_correction_code(buffer, size);
+
+        // The data bytes of the buffer are overwritten by the division, so
+        // they are taken from the bits instead.
+        let mut i = 0;
+        while i < buffer.len() {
+            let codeword = if i < size { bits.bytes[start + i] } else { buffer[i] };
+            codewords[layout.interleaved_index(block, i)] = codeword;
+            i += 1;
+        }
+        start += size;
+        block += 1;
+    }
+    (codewords, layout.total_len())
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Modules
+
+/// The modules of a symbol being drawn, packed 8 per byte.
+struct Symbol<const N: usize> {
+    width: i16,
+    dark: [u8; N],
+    /// The modules of functional patterns, which hold no data and are not
+    /// masked.
+    functional: [u8; N],
+}
+
+impl<const N: usize> Symbol<N> {
+    const fn new(width: i16) -> Self {
+        Self { width, dark: [0; N], functional: [0; N] }
+    }
+
+    /// Gets the bit of a module. For convenience, negative coordinates will
+    /// wrap around.
+    const fn bit(&self, x: i16, y: i16) -> (usize, u8) {
+        let x = if x < 0 { x + self.width } else { x };
+        let y = if y < 0 { y + self.width } else { y };
+        let i = y as usize * self.width as usize + x as usize;
+        (i / 8, 0x80 >> (i % 8))
+    }
+
+    /// Sets the color of a functional module.
+    const fn put(&mut self, x: i16, y: i16, is_dark: bool) {
+        let (byte, mask) = self.bit(x, y);
+        self.functional[byte] |= mask;
+        if is_dark {
+            self.dark[byte] |= mask;
+        } else {
+            self.dark[byte] &= !mask;
+        }
+    }
+
+    /// Draws a square pattern centered at (x, y) with the given radius. Bit `d`
+    /// of `dark_rings` is set if the ring at distance `d` from the center is
+    /// dark. Modules outside the symbol are skipped.
+    const fn draw_rings(&mut self, x: i16, y: i16, radius: i16, dark_rings: u8) {
+        let mut dy = -radius;
+        while dy <= radius {
+            let mut dx = -radius;
+            while dx <= radius {
+                let (px, py) = (x + dx, y + dy);
+                if px >= 0 && px < self.width && py >= 0 && py < self.width {
+                    let d = if dx.abs() > dy.abs() { dx.abs() } else { dy.abs() };
+                    self.put(px, py, dark_rings >> d & 1 != 0);
+                }
+                dx += 1;
+            }
+            dy += 1;
+        }
+    }
+
+    /// Draws a big-endian number onto the given coordinates.
+    const fn draw_number(&mut self, number: u32, coords: &[(i16, i16)]) {
+        let mut i = 0;
+        while i < coords.len() {
+            let (x, y) = coords[i];
+            self.put(x, y, number >> (coords.len() - 1 - i) & 1 != 0);
+            i += 1;
+        }
+    }
+
+    /// Draws the finder, timing and alignment patterns, the version
+    /// information, and reserves the format information.
+    const fn draw_functional_patterns(&mut self, version: i16) {
+        let w = self.width;
+
+        // Finder patterns with their separators: rings 0, 1 and 3 are dark.
+        self.draw_rings(3, 3, 4, 0b1011);
+        self.draw_rings(w - 4, 3, 4, 0b1011);
+        self.draw_rings(3, w - 4, 4, 0b1011);
+
+        let mut i = 8;
+        while i < w - 8 {
+            self.put(i, 6, i % 2 == 0);
+            self.put(6, i, i % 2 == 0);
+            i += 1;
+        }
+
+        // Alignment patterns: rings 0 and 2 are dark.
+        let (positions, len) = alignment_pattern_positions(version);
+        let last = len.saturating_sub(1);
+        let mut i = 0;
+        while i < len {
+            let mut j = 0;
+            while j < len {
+                let is_finder = i == 0 && (j == 0 || j == last) || (i == last && j == 0);
+                if !is_finder {
+                    self.draw_rings(positions[i], positions[j], 2, 0b101);
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+
+        if version >= 7 {
+            let version_info = encode_version_info(version as u8);
+            self.draw_number(version_info, &VERSION_INFO_COORDS_BL);
+            self.draw_number(version_info, &VERSION_INFO_COORDS_TR);
+        }
+    }
+
+    /// Draws both copies of the format information and the dark module.
+    const fn draw_format_info(&mut self, format_info: u16) {
+        self.draw_number(format_info as u32, &FORMAT_INFO_COORDS_QR_MAIN);
+        self.draw_number(format_info as u32, &FORMAT_INFO_COORDS_QR_SIDE);
+        self.put(8, -8, true);
+    }
+
+    /// Places the codewords in the zig-zag order of `Canvas::draw_data`, and
+    /// applies the mask pattern to them.
+    const fn draw_codewords(&mut self, codewords: &[u8; MAX_CODEWORDS], len: usize, pattern: MaskPattern) {
+        let w = self.width;
+        let mut bit = 0;
+        let mut x = w - 1;
+        let mut is_upward = true;
+        while x >= 1 {
+            let mut row = 0;
+            while row < w {
+                let y = if is_upward { w - 1 - row } else { row };
+                let mut dx = 0;
+                while dx < 2 {
+                    let (byte, mask) = self.bit(x - dx, y);
+                    if self.functional[byte] & mask == 0 {
+                        let is_data_dark = bit < len * 8 && codewords[bit / 8] << (bit % 8) & 0x80 != 0;
+                        if is_data_dark != pattern.is_inverted(x - dx, y) {
+                            self.dark[byte] |= mask;
+                        }
+                        bit += 1;
+                    }
+                    dx += 1;
+                }
+                row += 1;
+            }
+            is_upward = !is_upward;
+            x -= 2;
+            if x == 6 {
+                x = 5;
+            }
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Capacity checks
+
+/// Computes the number of data bits `QrCode::<V>::new(data)` encodes before
+/// the terminator, with the same segments.
+///
+/// Returns `None` if a segment uses a mode which the Micro QR version does
+/// not support, or is too long for its length field.
+pub const fn optimal_bits_len<V: QrSpec>(data: &[u8]) -> Option<usize> {
+    let mut segments = SliceOptimizer::new(data, V::VERSION);
+    let mut len = 0;
+    while let Some(segment) = segments.next() {
+        let supported = match (V::VERSION, segment.mode) {
+            (Version::Normal(_), _) | (_, Mode::Numeric) => true,
+            (Version::Micro(v), Mode::Alphanumeric) => v >= 2,
+            (Version::Micro(v), Mode::Byte | Mode::Kanji) => v >= 3,
+        };
+        let byte_size = segment.end - segment.begin;
+        let chars_count = if matches!(segment.mode, Mode::Kanji) { byte_size / 2 } else { byte_size };
+        if !supported || chars_count >> segment.mode.length_bits_count(V::VERSION) != 0 {
+            return None;
+        }
+        len += segment.encoded_len(V::VERSION);
+    }
+    Some(len)
+}
+
+/// Checks whether `QrCode::<V>::new(data)` has room for the data, in a
+/// `const` context. See `assert_fits!`.
+pub const fn fits<V: QrSpec>(data: &[u8]) -> bool {
+    let index = match V::VERSION {
+        Version::Normal(v) => v as usize - 1,
+        Version::Micro(v) => v as usize + 39,
+    };
+    let capacity = DATA_LENGTHS[index][V::EC_LEVEL as usize];
+    match optimal_bits_len::<V>(data) {
+        Some(len) => capacity > 0 && len <= capacity,
+        None => false,
+    }
+}
+
+/// Fails compilation unless `QrCode::<$spec>::new($data)` has room for the
+/// data, which must be a constant `&[u8]`, like a byte string literal or a
+/// `const`. See the `const_encode` module.
+#[macro_export]
+macro_rules! assert_fits {
+    ($spec:ty, $data:expr $(,)?) => {
+        const _: () = assert!(
+            $crate::const_encode::fits::<$spec>($data),
+            "data too long for the version and error correction level"
+        );
+    };
+}
+
+//}}}
+//------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod const_encode_tests {
+    use crate::bits::Bits;
+    use crate::canvas::{Canvas, MaskPattern};
+    use crate::const_encode::{encode_const, packed_len};
+    use crate::ec::construct_codewords;
+    use crate::packed::PackedQrCode;
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, EcLevelQ, QrSpec, Version1, Version10, Version7};
+    use crate::types::Color;
+
+    type V1 = Version1<EcLevelM>;
+    static HELLO: [u8; packed_len::<V1>()] = encode_const::<V1, { packed_len::<V1>() }>(b"hello", MaskPattern::Fields);
+
+    /// Encodes the data in byte mode with the normal pipeline.
+    fn expected<V: QrSpec>(data: &[u8], pattern: MaskPattern) -> Vec<Color> {
+        let mut bits = Bits::<V>::new();
+        bits.push_byte_data(data).unwrap();
+        bits.push_terminator().unwrap();
+        let (codewords, data_end) = construct_codewords::<V>(&bits.into_bytes()).unwrap();
+        let mut canvas = Canvas::<V>::new();
+        canvas.draw_all_functional_patterns();
+        canvas.draw_data(&codewords[..data_end], &codewords[data_end..]);
+        canvas.apply_mask(pattern).unwrap();
+        canvas.colors().collect()
+    }
+
+    fn check<V: QrSpec, const N: usize>(data: &[u8], pattern: MaskPattern) {
+        let modules = encode_const::<V, N>(data, pattern);
+        let code = PackedQrCode::new(V::VERSION, V::EC_LEVEL, &modules);
+        assert!(code.colors().eq(expected::<V>(data, pattern)), "{:?}", pattern);
+    }
+
+    #[test]
+    fn test_static() {
+        let code = PackedQrCode::new(V1::VERSION, V1::EC_LEVEL, &HELLO);
+        assert!(code.colors().eq(expected::<V1>(b"hello", MaskPattern::Fields)));
+    }
+
+    #[test]
+    fn test_matches_canvas() {
+        check::<Version1<EcLevelL>, { packed_len::<Version1<EcLevelL>>() }>(b"", MaskPattern::Checkerboard);
+        check::<Version7<EcLevelQ>, { packed_len::<Version7<EcLevelQ>>() }>(&[0xa5; 80], MaskPattern::Diamonds);
+        check::<Version10<EcLevelH>, { packed_len::<Version10<EcLevelH>>() }>(&[0x3c; 119], MaskPattern::Meadow);
+    }
+
+    #[test]
+    #[should_panic(expected = "data too long")]
+    fn test_too_long() {
+        encode_const::<V1, { packed_len::<V1>() }>(&[0; 15], MaskPattern::Fields);
+    }
+}
+
+#[cfg(test)]
+mod capacity_check_tests {
+    use crate::bits::Bits;
+    use crate::const_encode::{fits, optimal_bits_len};
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, QrSpec, Version1, Version10, Version40};
+    use crate::QrCode;
+
+    const DATA: &[u8] = b"01234567890123456789ABCDEF\x82\xa0\x81\x41 hello";
+
+    crate::assert_fits!(Version1<EcLevelL>, b"01234567890123456789ABCDEF");
+    crate::assert_fits!(Version40<EcLevelH>, DATA);
+
+    fn check<V: QrSpec>(data: &[u8]) {
+        let mut bits = Bits::<V>::new();
+        let pushed = bits.push_optimal_data(data).map(|()| bits.len());
+        assert_eq!(optimal_bits_len::<V>(data).filter(|_| pushed.is_ok()), pushed.ok(), "{:?}", data);
+        assert_eq!(fits::<V>(data), QrCode::<V>::new(data).is_ok(), "{:?}", data);
+    }
+
+    #[test]
+    fn test_matches_new() {
+        let inputs: [&[u8]; 7] = [b"", b"0", b"12345", b"HELLO WORLD", b"hello", DATA, &[0xeb, 0xc0, 0x81, 0x40]];
+        for data in inputs {
+            check::<Version1<EcLevelL>>(data);
+            check::<Version1<EcLevelH>>(data);
+            check::<Version10<EcLevelM>>(data);
+        }
+        for len in 0..40 {
+            check::<Version1<EcLevelM>>(&DATA.repeat(2)[..len]);
+            check::<Version1<EcLevelH>>(&DATA.repeat(2)[..len]);
+        }
+    }
+
+    #[test]
+    fn test_boundary() {
+        type V = Version1<EcLevelL>;
+        assert_eq!(optimal_bits_len::<V>(&[b'7'; 41]), Some(4 + 10 + 137));
+        assert!(fits::<V>(&[b'7'; 41]));
+        assert!(!fits::<V>(&[b'7'; 42]));
+        assert!(fits::<V>(&[b'x'; 17]));
+        assert!(!fits::<V>(&[b'x'; 18]));
+    }
+}
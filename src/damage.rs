@@ -0,0 +1,222 @@
+//! The `damage` module flips modules of a finished QR code on purpose, to
+//! check empirically how much damage scanners recover from.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     // A scratch across the lower right quarter.
+//!     let (damaged, report) = code.damage((11..21).map(|i| (i, i))).unwrap();
+//!     assert_eq!(report.flipped, 10);
+//!     assert!(report.codewords() <= 10);
+//!     println!("{:.0}% of the error budget used", report.budget_used() * 100.0);
+//!
+//! Only the data and error correction modules are flipped. Finder, timing
+//! and alignment patterns, the format and the version information are left
+//! intact, so that the report counts the codewords a decoder has to correct.
+//! Render the damaged code and scan it to compare scanners against the
+//! report.
+
+use crate::canvas;
+use crate::ec::{BlockDamage, ErrorTolerance, MAX_BLOCKS_COUNT};
+use crate::spec::QrSpec;
+use crate::types::{QrError, QrResult};
+use crate::QrCode;
+
+/// How flipping modules of a QR code damages it, see `QrCode::damage`.
+#[derive(Debug, Clone)]
+pub struct Damage {
+    /// The number of data and error correction modules flipped. A module
+    /// listed twice is flipped back and not counted.
+    pub flipped: usize,
+
+    /// The number of functional modules among the coordinates, which were
+    /// left intact.
+    pub skipped: usize,
+
+    /// The number of flipped remainder bits, which carry nothing.
+    pub remainder: usize,
+
+    blocks: [BlockDamage; MAX_BLOCKS_COUNT],
+    blocks_count: usize,
+}
+
+impl Damage {
+    /// The damage of every block, in block order.
+    pub fn blocks(&self) -> &[BlockDamage] {
+        &self.blocks[..self.blocks_count]
+    }
+
+    /// The number of codewords with at least one flipped module.
+    pub fn codewords(&self) -> usize {
+        self.blocks().iter().map(|b| b.codewords).sum()
+    }
+
+    /// Checks whether no block has more damaged codewords than it can
+    /// correct, so that a decoder is guaranteed to restore the data.
+    pub fn is_correctable(&self) -> bool {
+        self.blocks().iter().all(|b| b.codewords <= b.correctable)
+    }
+
+    /// The fraction of the error correction capacity used by the worst
+    /// block, above 1 if the damage is not correctable.
+    pub fn budget_used(&self) -> f32 {
+        self.blocks().iter().map(|b| b.codewords as f32 / b.correctable as f32).fold(0.0, f32::max)
+    }
+}
+
+/// Flips the data modules of `code` at `coords`. See `QrCode::damage`.
+pub(crate) fn damage<V: QrSpec, I: IntoIterator<Item = (usize, usize)>>(
+    code: &QrCode<V>,
+    coords: I,
+) -> QrResult<(QrCode<V>, Damage)> {
+    let width = V::WIDTH as usize;
+    let row_byte_width = width.div_ceil(8);
+    let mut damaged = code.clone();
+    let mut skipped = 0;
+    for (x, y) in coords {
+        if x >= width || y >= width {
+            return Err(QrError::OutOfBounds);
+        }
+        let (mx, my) = (x as i16, y as i16);
+        if canvas::is_functional_unchecked(V::VERSION, V::WIDTH, mx, my)
+            || canvas::is_version_info(V::VERSION, V::WIDTH, mx, my)
+        {
+            skipped += 1;
+            continue;
+        }
+        // The same bit layout as `QrCode::get`.
+        let bits_in_byte = (width - x / 8 * 8).min(8);
+        damaged.content[y * row_byte_width + x / 8] ^= 1 << (bits_in_byte - 1 - x % 8);
+    }
+
+    let tolerance = ErrorTolerance::new(V::VERSION, V::EC_LEVEL)?;
+    let mut damage = Damage {
+        flipped: 0,
+        skipped,
+        remainder: 0,
+        blocks: [BlockDamage::default(); MAX_BLOCKS_COUNT],
+        blocks_count: 0,
+    };
+    for (block, tolerance) in damage.blocks.iter_mut().zip(tolerance.blocks()) {
+        block.correctable = tolerance.correctable;
+        damage.blocks_count += 1;
+    }
+
+    // As in `ErrorTolerance::region_damage`, the modules of a codeword are
+    // placed one after another.
+    let total_len = V::BLOCK_LAYOUT.total_len();
+    let mut last_codeword = None;
+    for (i, (x, y)) in canvas::data_modules(V::VERSION).enumerate() {
+        let codeword = i / 8;
+        if code.get(x, y) == damaged.get(x, y) {
+            continue;
+        }
+        damage.flipped += 1;
+        if codeword >= total_len {
+            damage.remainder += 1;
+            continue;
+        }
+        let block = &mut damage.blocks[tolerance.block_of_codeword(codeword)];
+        block.modules += 1;
+        if last_codeword != Some(codeword) {
+            block.codewords += 1;
+            last_codeword = Some(codeword);
+        }
+    }
+    Ok((damaged, damage))
+}
+
+#[cfg(test)]
+mod damage_tests {
+    use crate::canvas;
+    use crate::decode::decode;
+    use crate::spec::{EcLevelM, EcLevelQ, QrSpec, Version1, Version5};
+    use crate::types::{Color, QrError};
+    use crate::QrCode;
+
+    /// The coordinates of the first module of the codeword at `position` of
+    /// the interleaved codeword sequence.
+    fn codeword_module<V: QrSpec>(position: usize) -> (usize, usize) {
+        let (x, y) = canvas::data_modules(V::VERSION).nth(position * 8).unwrap();
+        (x as usize, y as usize)
+    }
+
+    fn decode_corrected<V: QrSpec>(code: &QrCode<V>) -> Result<usize, QrError> {
+        let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+        let mut out = [0; 256];
+        decode(&modules, &mut out).map(|decoded| decoded.corrected)
+    }
+
+    #[test]
+    fn test_within_budget() {
+        type V = Version1<EcLevelM>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let coords: Vec<_> = (0..4).map(codeword_module::<V>).collect();
+        let (damaged, report) = code.damage(coords).unwrap();
+        assert_eq!(report.flipped, 4);
+        assert_eq!(report.codewords(), 4);
+        assert_eq!(report.blocks()[0].correctable, code.max_allowed_errors());
+        assert!(report.is_correctable());
+        assert_eq!(report.budget_used(), 1.0);
+
+        assert_eq!(damaged.verify(), Err(QrError::InvalidSymbol));
+        assert_eq!(decode_corrected(&damaged), Ok(4));
+    }
+
+    #[test]
+    fn test_over_budget() {
+        type V = Version1<EcLevelM>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let (_, report) = code.damage((0..6).map(codeword_module::<V>)).unwrap();
+        assert_eq!(report.codewords(), 6);
+        assert!(!report.is_correctable());
+        assert_eq!(report.budget_used(), 1.5);
+    }
+
+    #[test]
+    fn test_worst_block() {
+        // 10 codewords fit into the total budget of 36, but not into the 9 of
+        // a single block.
+        type V = Version5<EcLevelQ>;
+        let code = QrCode::<V>::new(b"Hello, world!").unwrap();
+        let layout = V::BLOCK_LAYOUT;
+        let coords = (0..10).map(|i| codeword_module::<V>(layout.interleaved_index(2, i)));
+        let (damaged, report) = code.damage(coords).unwrap();
+        assert_eq!(code.max_allowed_errors(), 36);
+        let codewords: Vec<_> = report.blocks().iter().map(|b| (b.codewords, b.correctable)).collect();
+        assert_eq!(codewords, [(0, 9), (0, 9), (10, 9), (0, 9)]);
+        assert!(!report.is_correctable());
+        assert!(decode_corrected(&damaged).is_err());
+
+        let coords = (0..9).map(|i| codeword_module::<V>(layout.interleaved_index(2, i)));
+        let (damaged, report) = code.damage(coords).unwrap();
+        assert!(report.is_correctable());
+        assert_eq!(decode_corrected(&damaged), Ok(9));
+    }
+
+    #[test]
+    fn test_same_codeword() {
+        type V = Version1<EcLevelM>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let modules = canvas::data_modules(V::VERSION).take(8).map(|(x, y)| (x as usize, y as usize));
+        let (_, report) = code.damage(modules).unwrap();
+        assert_eq!((report.flipped, report.codewords()), (8, 1));
+        assert_eq!(report.blocks()[0].modules, 8);
+    }
+
+    #[test]
+    fn test_functional_and_repeated() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let (damaged, report) = code.damage([(0, 0), (8, 0), (6, 10), (20, 20), (20, 20)]).unwrap();
+        assert_eq!(damaged, code);
+        assert_eq!((report.flipped, report.skipped, report.codewords()), (0, 3, 0));
+        assert!(report.is_correctable());
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        assert_eq!(code.damage([(21, 0)]).map(|_| ()), Err(QrError::OutOfBounds));
+    }
+}
@@ -0,0 +1,997 @@
+//! The `decode` module reads the data back from the module matrix of a QR
+//! code.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::decode::decode;
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!     use qrcode::types::Color;
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let modules: Vec<Color> = code.colors().collect();
+//!
+//!     let mut out = [0; 32];
+//!     let decoded = decode(&modules, &mut out).unwrap();
+//!     assert_eq!(&out[..decoded.len], b"01234567");
+//!
+//! Only normal QR codes are supported. The decoder works on a clean matrix;
+//! locating a symbol in an image is out of scope.
+
+use core::convert::TryFrom;
+
+use crate::canvas::{self, MaskPattern};
+use crate::ec;
+use crate::spec::BlockLayout;
+use crate::types::{Color, EcLevel, Mode, QrError, QrResult, Version};
+
+/// The largest number of codewords in a QR code (version 40).
+pub(crate) const MAX_TOTAL_LEN: usize = 3706;
+
+/// The largest number of data codewords in a QR code (version 40-L).
+const MAX_DATA_LEN: usize = 2956;
+
+/// The largest number of codewords in a single Reed-Solomon block.
+pub(crate) const MAX_BLOCK_LEN: usize = 153;
+
+//------------------------------------------------------------------------------
+//{{{ Decoder
+
+/// The result of decoding a QR code.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Decoded {
+    /// The version of the QR code.
+    pub version: Version,
+
+    /// The error correction level of the QR code.
+    pub ec_level: EcLevel,
+
+    /// The mask pattern applied to the QR code.
+    pub mask: MaskPattern,
+
+    /// The number of bytes written to the output buffer.
+    pub len: usize,
+
+    /// The number of codewords fixed by error correction.
+    pub corrected: usize,
+}
+
+/// Decodes a QR code from its modules, given in row-major order. The matrix
+/// must be square and must not include a quiet zone.
+///
+/// The decoded data is written to `out`. See `decode_with` for details.
+pub fn decode(modules: &[Color], out: &mut [u8]) -> QrResult<Decoded> {
+    let width = (0..=177).find(|w| w * w >= modules.len()).unwrap_or(0);
+    if width * width != modules.len() {
+        return Err(QrError::InvalidWidth(width));
+    }
+    decode_with(width, |x, y| modules[y * width + x], out)
+}
+
+/// Decodes a QR code of the given width. `get(x, y)` returns the color of the
+/// module in column `x` and row `y`.
+///
+/// The data of every segment is written to `out` one after another: digits
+/// and alphanumeric characters as ASCII, bytes as is, and Kanji characters in
+/// Shift JIS.
+///
+/// # Errors
+///
+/// * `QrError::InvalidWidth` if the width is not one of a normal QR code.
+/// * `QrError::InvalidSymbol` if the format information cannot be read, or
+///   the data segments are malformed.
+/// * `QrError::Uncorrectable` if a block contains too many errors.
+/// * `QrError::UnsupportedCharacterSet` if the data uses an unsupported mode.
+/// * `QrError::BufferTooSmall` if `out` cannot hold the decoded data.
+pub fn decode_with<F: Fn(usize, usize) -> Color>(width: usize, get: F, out: &mut [u8]) -> QrResult<Decoded> {
+    decode_with_segments(width, get, out, |_| {})
+}
+
+/// Decodes a QR code like `decode_with`, additionally calling `on_segment`
+/// for every segment found in the data. See `parse_segments` for details.
+pub fn decode_with_segments<F, S>(width: usize, get: F, out: &mut [u8], on_segment: S) -> QrResult<Decoded>
+where
+    F: Fn(usize, usize) -> Color,
+    S: FnMut(Segment),
+{
+    let format_info = read_format_info(width, &get)?;
+    let FormatInfo { version, ec_level, mask, .. } = format_info;
+
+    let layout = BlockLayout::fetch(version, ec_level)?;
+    let mut codewords = [0; MAX_TOTAL_LEN];
+    let total_len = read_codewords(width, &get, &format_info, &mut codewords)?;
+    let codewords = &codewords[..total_len];
+
+    let mut data = [0; MAX_DATA_LEN];
+    let data = &mut data[..layout.data_len()];
+    let corrected = correct_blocks(&layout, codewords, data)?;
+
+    let len = parse_segments(version, data, out, on_segment)?;
+    Ok(Decoded { version, ec_level, mask, len, corrected })
+}
+
+/// Adapts a module getter to the signed coordinates used by `canvas`, where
+/// negative coordinates count from the right or bottom edge.
+fn wrapping_getter<F: Fn(usize, usize) -> Color>(width: usize, get: &F) -> impl Fn(i16, i16) -> Color + '_ {
+    let w = width as i16;
+    move |x: i16, y: i16| {
+        let x = if x < 0 { x + w } else { x };
+        let y = if y < 0 { y + w } else { y };
+        get(x as usize, y as usize)
+    }
+}
+
+/// De-interleaves the codewords into blocks, corrects every block, and writes
+/// their data codewords into `data` in block order.
+///
+/// Returns the total number of corrected codewords.
+pub(crate) fn correct_blocks(layout: &BlockLayout, codewords: &[u8], data: &mut [u8]) -> QrResult<usize> {
+    let mut block = [0; MAX_BLOCK_LEN];
+    let mut corrected = 0;
+    let mut data_start = 0;
+    for (b, size) in layout.block_sizes().enumerate() {
+        let block = &mut block[..size + layout.ec_bytes_per_block];
+        for (i, codeword) in block.iter_mut().enumerate() {
+            *codeword = codewords[layout.interleaved_index(b, i)];
+        }
+        corrected += ec::correct_errors(block, size)?;
+        data[data_start..data_start + size].copy_from_slice(&block[..size]);
+        data_start += size;
+    }
+    Ok(corrected)
+}
+
+#[cfg(test)]
+mod decode_tests {
+    use crate::decode::{decode, decode_with, Decoded};
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, EcLevelQ, QrSpec, Version1, Version10, Version40, Version7};
+    use crate::types::{Color, EcLevel, QrError, Version};
+    use crate::QrCode;
+
+    fn round_trip<V: QrSpec>(data: &[u8]) -> Decoded {
+        let code = QrCode::<V>::new(data).unwrap();
+        let modules: Vec<Color> = code.colors().collect();
+        let mut out = [0; 3000];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], data);
+        decoded
+    }
+
+    #[test]
+    fn test_annex_i() {
+        let decoded = round_trip::<Version1<EcLevelM>>(b"01234567");
+        assert_eq!(decoded.version, Version::Normal(1));
+        assert_eq!(decoded.ec_level, EcLevel::M);
+        assert_eq!(decoded.corrected, 0);
+    }
+
+    #[test]
+    fn test_modes() {
+        round_trip::<Version7<EcLevelQ>>(b"Hello, world! 0123456789 HELLO WORLD");
+        round_trip::<Version10<EcLevelH>>(b"\x93\x5f\xe4\xaa\x82\xa0");
+        round_trip::<Version40<EcLevelL>>(&[0xab; 2000]);
+    }
+
+    #[test]
+    fn test_correct_damage() {
+        let code = QrCode::<Version7<EcLevelH>>::new(b"damaged but readable").unwrap();
+        let mut modules: Vec<Color> = code.colors().collect();
+        let width = 45;
+        for y in 20..26 {
+            for x in 20..26 {
+                modules[y * width + x] = !modules[y * width + x];
+            }
+        }
+        let mut out = [0; 64];
+        let decoded = decode_with(width, |x, y| modules[y * width + x], &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], b"damaged but readable");
+        assert!(decoded.corrected > 0);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut out = [0; 16];
+        assert_eq!(decode(&[Color::Light; 20 * 20], &mut out), Err(QrError::InvalidWidth(20)));
+        assert_eq!(decode(&[Color::Light; 21 * 21], &mut out), Err(QrError::InvalidSymbol));
+
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let modules: Vec<Color> = code.colors().collect();
+        assert_eq!(decode(&modules, &mut out[..4]), Err(QrError::BufferTooSmall));
+    }
+}
+
+#[cfg(test)]
+mod round_trip_tests {
+    use crate::bits::Bits;
+    use crate::canvas::{self, Canvas, MaskPattern};
+    use crate::decode::decode;
+    use crate::ec::{Codewords, ErrorTolerance};
+    use crate::spec::*;
+    use crate::types::{Color, EcLevel, QrError};
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    type Check = fn(&[u8], MaskPattern, &[(u8, u8)]) -> Result<(), TestCaseError>;
+
+    macro_rules! versions {
+        ($version:expr, $ec_level:ident) => {
+            match $version {
+                1 => round_trip::<Version1<$ec_level>>,
+                2 => round_trip::<Version2<$ec_level>>,
+                3 => round_trip::<Version3<$ec_level>>,
+                4 => round_trip::<Version4<$ec_level>>,
+                5 => round_trip::<Version5<$ec_level>>,
+                6 => round_trip::<Version6<$ec_level>>,
+                7 => round_trip::<Version7<$ec_level>>,
+                8 => round_trip::<Version8<$ec_level>>,
+                9 => round_trip::<Version9<$ec_level>>,
+                10 => round_trip::<Version10<$ec_level>>,
+                11 => round_trip::<Version11<$ec_level>>,
+                12 => round_trip::<Version12<$ec_level>>,
+                13 => round_trip::<Version13<$ec_level>>,
+                14 => round_trip::<Version14<$ec_level>>,
+                15 => round_trip::<Version15<$ec_level>>,
+                16 => round_trip::<Version16<$ec_level>>,
+                17 => round_trip::<Version17<$ec_level>>,
+                18 => round_trip::<Version18<$ec_level>>,
+                19 => round_trip::<Version19<$ec_level>>,
+                20 => round_trip::<Version20<$ec_level>>,
+                21 => round_trip::<Version21<$ec_level>>,
+                22 => round_trip::<Version22<$ec_level>>,
+                23 => round_trip::<Version23<$ec_level>>,
+                24 => round_trip::<Version24<$ec_level>>,
+                25 => round_trip::<Version25<$ec_level>>,
+                26 => round_trip::<Version26<$ec_level>>,
+                27 => round_trip::<Version27<$ec_level>>,
+                28 => round_trip::<Version28<$ec_level>>,
+                29 => round_trip::<Version29<$ec_level>>,
+                30 => round_trip::<Version30<$ec_level>>,
+                31 => round_trip::<Version31<$ec_level>>,
+                32 => round_trip::<Version32<$ec_level>>,
+                33 => round_trip::<Version33<$ec_level>>,
+                34 => round_trip::<Version34<$ec_level>>,
+                35 => round_trip::<Version35<$ec_level>>,
+                36 => round_trip::<Version36<$ec_level>>,
+                37 => round_trip::<Version37<$ec_level>>,
+                38 => round_trip::<Version38<$ec_level>>,
+                39 => round_trip::<Version39<$ec_level>>,
+                40 => round_trip::<Version40<$ec_level>>,
+                _ => unreachable!(),
+            }
+        };
+    }
+
+    fn check_for(version: u8, ec_level: EcLevel) -> Check {
+        match ec_level {
+            EcLevel::L => versions!(version, EcLevelL),
+            EcLevel::M => versions!(version, EcLevelM),
+            EcLevel::Q => versions!(version, EcLevelQ),
+            EcLevel::H => versions!(version, EcLevelH),
+        }
+    }
+
+    /// Encodes as much of `data` as fits, draws it with the given mask, flips
+    /// a correctable number of data modules, and decodes it again.
+    fn round_trip<V: QrSpec>(data: &[u8], pattern: MaskPattern, damage: &[(u8, u8)]) -> Result<(), TestCaseError> {
+        let mut data = data;
+        let bits = loop {
+            let mut bits = Bits::<V>::new();
+            match bits.push_optimal_data(data).and_then(|_| bits.push_terminator()) {
+                Ok(()) => break bits,
+                Err(QrError::DataTooLong { .. }) => data = &data[..data.len() / 2],
+                Err(e) => return Err(TestCaseError::fail(format!("{:?}", e))),
+            }
+        };
+
+        let rawbits = bits.into_bytes();
+        let mut canvas = Canvas::<V>::new();
+        canvas.draw_all_functional_patterns();
+        canvas.draw_codewords_iter(Codewords::new(&rawbits).unwrap());
+        canvas.apply_mask(pattern).unwrap();
+        let mut modules: Vec<Color> = canvas.colors().collect();
+
+        // Every flipped module damages at most one codeword, so staying within
+        // the tolerance of a single block keeps every block correctable.
+        let tolerance = ErrorTolerance::new(V::VERSION, V::EC_LEVEL).unwrap();
+        let correctable = tolerance.blocks().map(|b| b.correctable).min().unwrap();
+        let width = V::WIDTH as usize;
+        let is_version_info = |x: usize, y: usize| {
+            let (x, y) = if x < y { (x, y) } else { (y, x) };
+            V::WIDTH >= 45 && x < 6 && (width - 11..width - 8).contains(&y)
+        };
+        let flips = damage
+            .iter()
+            .map(|&(x, y)| (usize::from(x) % width, usize::from(y) % width))
+            .filter(|&(x, y)| !canvas::is_functional_unchecked(V::VERSION, V::WIDTH, x as i16, y as i16))
+            .filter(|&(x, y)| !is_version_info(x, y))
+            .take(correctable);
+        for (x, y) in flips {
+            modules[y * width + x] = !modules[y * width + x];
+        }
+
+        let mut out = [0; 7089];
+        let decoded = decode(&modules, &mut out).map_err(|e| TestCaseError::fail(format!("{:?}", e)))?;
+        prop_assert_eq!(&out[..decoded.len], data);
+        prop_assert_eq!(decoded.version, V::VERSION);
+        prop_assert_eq!(decoded.ec_level, V::EC_LEVEL);
+        prop_assert_eq!(decoded.mask, pattern);
+        Ok(())
+    }
+
+    fn data() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..3000),
+            prop::collection::vec(select(b"0123456789".to_vec()), 0..7100),
+            prop::collection::vec(select(b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:".to_vec()), 0..4300),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_round_trip(
+            version in 1..=40_u8,
+            ec_level in select(vec![EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H]),
+            pattern in (0..8_u8).prop_map(|bits| MaskPattern::from_bits(bits).unwrap()),
+            data in data(),
+            damage in prop::collection::vec(any::<(u8, u8)>(), 0..40),
+        ) {
+            check_for(version, ec_level)(&data, pattern, &damage)?;
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Format and version information
+
+/// The format and version information read from a QR code.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct FormatInfo {
+    /// The version of the QR code.
+    pub version: Version,
+
+    /// The error correction level of the QR code.
+    pub ec_level: EcLevel,
+
+    /// The mask pattern applied to the QR code.
+    pub mask: MaskPattern,
+
+    /// The number of wrong bits found in the two format information copies,
+    /// the one around the top-left finder pattern first.
+    pub format_bit_errors: [u32; 2],
+
+    /// The number of wrong bits found in the two version information copies,
+    /// the bottom-left one first. Always zero below version 7.
+    pub version_bit_errors: [u32; 2],
+}
+
+/// The number of bit errors the format and version information BCH codes can
+/// correct.
+const MAX_BCH_ERRORS: u32 = 3;
+
+/// Chooses the candidate whose codeword is the closest to both copies, as long
+/// as at least one copy is correctable. Returns the candidate and the number of
+/// wrong bits in each copy.
+fn vote<I: Iterator<Item = (u8, u32)>>(candidates: I, copies: (u32, u32)) -> Option<(u8, [u32; 2])> {
+    candidates
+        .map(|(data, codeword)| (data, [(codeword ^ copies.0).count_ones(), (codeword ^ copies.1).count_ones()]))
+        .filter(|(_, errors)| errors[0].min(errors[1]) <= MAX_BCH_ERRORS)
+        .min_by_key(|(_, errors)| errors[0] + errors[1])
+}
+
+/// Splits the 5 data bits of the format information into the error correction
+/// level and the mask pattern.
+pub(crate) fn split_format_data(format_data: u8) -> Option<(EcLevel, MaskPattern)> {
+    let ec_level = match (format_data >> 3) ^ 1 {
+        0 => EcLevel::L,
+        1 => EcLevel::M,
+        2 => EcLevel::Q,
+        _ => EcLevel::H,
+    };
+    Some((ec_level, MaskPattern::from_bits(format_data & 0b111)?))
+}
+
+/// Reads the format information, and the version information for version 7
+/// and up, of a QR code of the given width.
+///
+/// Both copies of each are read and corrected, and the value closest to the
+/// two copies combined is chosen, so a symbol stays readable even if one copy
+/// is destroyed.
+///
+///     use qrcode::QrCode;
+///     use qrcode::decode::read_format_info;
+///     use qrcode::spec::{Version1, EcLevelM};
+///     use qrcode::types::{Color, EcLevel, Version};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let modules: Vec<Color> = code.colors().collect();
+///
+///     let info = read_format_info(21, |x, y| modules[y * 21 + x]).unwrap();
+///     assert_eq!(info.version, Version::Normal(1));
+///     assert_eq!(info.ec_level, EcLevel::M);
+///     assert_eq!(info.format_bit_errors, [0, 0]);
+///
+/// Returns `Err(QrError::InvalidWidth(_))` if the width is not one of a normal
+/// QR code, and `Err(QrError::InvalidSymbol)` if the information is too
+/// damaged or the version information contradicts the width.
+pub fn read_format_info<F: Fn(usize, usize) -> Color>(width: usize, get: F) -> QrResult<FormatInfo> {
+    let version_number = match i16::try_from(width).ok().and_then(Version::from_width) {
+        Some(Version::Normal(v)) => v as u8,
+        _ => return Err(QrError::InvalidWidth(width)),
+    };
+    let get = wrapping_getter(width, &get);
+
+    let (main, side) = canvas::read_format_info_qr(&get);
+    let format_candidates =
+        (0..32).map(|data| (data, u32::from(canvas::encode_format_info(data, canvas::FORMAT_INFO_MASK_QR))));
+    let (format_data, format_bit_errors) =
+        vote(format_candidates, (u32::from(main), u32::from(side))).ok_or(QrError::InvalidSymbol)?;
+    let (ec_level, mask) = split_format_data(format_data).ok_or(QrError::InvalidSymbol)?;
+
+    let mut version_bit_errors = [0; 2];
+    if version_number >= 7 {
+        let copies = canvas::read_version_info(&get);
+        let version_candidates = (7..=40).map(|v| (v, canvas::encode_version_info(v)));
+        let (version, errors) = vote(version_candidates, copies).ok_or(QrError::InvalidSymbol)?;
+        if version != version_number {
+            return Err(QrError::InvalidSymbol);
+        }
+        version_bit_errors = errors;
+    }
+
+    Ok(FormatInfo {
+        version: Version::Normal(i16::from(version_number)),
+        ec_level,
+        mask,
+        format_bit_errors,
+        version_bit_errors,
+    })
+}
+
+#[cfg(test)]
+mod format_info_tests {
+    use crate::decode::read_format_info;
+    use crate::spec::{EcLevelQ, Version7};
+    use crate::types::{Color, EcLevel, QrError, Version};
+    use crate::QrCode;
+
+    fn modules() -> Vec<Color> {
+        QrCode::<Version7<EcLevelQ>>::new(b"Hello, world!").unwrap().colors().collect()
+    }
+
+    #[test]
+    fn test_clean() {
+        let modules = modules();
+        let info = read_format_info(45, |x, y| modules[y * 45 + x]).unwrap();
+        assert_eq!(info.version, Version::Normal(7));
+        assert_eq!(info.ec_level, EcLevel::Q);
+        assert_eq!(info.format_bit_errors, [0, 0]);
+        assert_eq!(info.version_bit_errors, [0, 0]);
+    }
+
+    #[test]
+    fn test_one_copy_destroyed() {
+        let mut modules = modules();
+        let expected = read_format_info(45, |x, y| modules[y * 45 + x]).unwrap();
+        // Wipe the format information around the top-left finder pattern and
+        // the top-right version information.
+        for i in 0..9 {
+            modules[8 * 45 + i] = Color::Light;
+            modules[i * 45 + 8] = Color::Light;
+        }
+        for y in 0..6 {
+            for x in 34..37 {
+                modules[y * 45 + x] = Color::Dark;
+            }
+        }
+        let info = read_format_info(45, |x, y| modules[y * 45 + x]).unwrap();
+        assert_eq!((info.version, info.ec_level, info.mask), (expected.version, expected.ec_level, expected.mask));
+        assert!(info.format_bit_errors[0] > 0);
+        assert_eq!(info.format_bit_errors[1], 0);
+        assert_eq!(info.version_bit_errors[0], 0);
+        assert!(info.version_bit_errors[1] > 0);
+    }
+
+    #[test]
+    fn test_wrong_width() {
+        let modules = modules();
+        assert_eq!(read_format_info(49, |x, y| modules[(y * 45 + x) % (45 * 45)]), Err(QrError::InvalidSymbol));
+        assert_eq!(read_format_info(44, |_, _| Color::Light), Err(QrError::InvalidWidth(44)));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Codeword extraction
+
+/// Reads the interleaved data and error correction codewords from a QR code,
+/// in the order they were placed, after removing the mask given in
+/// `format_info`.
+///
+/// The codewords are written to `out`; no error correction is applied. This
+/// gives the same sequence as `ec::construct_codewords` when encoding.
+///
+///     use qrcode::QrCode;
+///     use qrcode::decode::{read_codewords, read_format_info};
+///     use qrcode::spec::{Version1, EcLevelM};
+///     use qrcode::types::Color;
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let modules: Vec<Color> = code.colors().collect();
+///     let get = |x, y| modules[y * 21 + x];
+///
+///     let info = read_format_info(21, get).unwrap();
+///     let mut codewords = [0; 26];
+///     assert_eq!(read_codewords(21, get, &info, &mut codewords), Ok(26));
+///     assert_eq!(&codewords[..3], b"\x10\x20\x0c");
+///
+/// Returns the number of codewords, or `Err(QrError::BufferTooSmall)` if
+/// `out` cannot hold them all.
+pub fn read_codewords<F: Fn(usize, usize) -> Color>(
+    width: usize,
+    get: F,
+    format_info: &FormatInfo,
+    out: &mut [u8],
+) -> QrResult<usize> {
+    if format_info.version.width() as usize != width {
+        return Err(QrError::InvalidWidth(width));
+    }
+    let total_len = BlockLayout::fetch(format_info.version, format_info.ec_level)?.total_len();
+    let out = out.get_mut(..total_len).ok_or(QrError::BufferTooSmall)?;
+    canvas::read_codewords(&wrapping_getter(width, &get), format_info.version, format_info.mask, out);
+    Ok(total_len)
+}
+
+#[cfg(test)]
+mod read_codewords_tests {
+    use crate::bits::Bits;
+    use crate::canvas::{Canvas, MaskPattern};
+    use crate::decode::{read_codewords, read_format_info};
+    use crate::ec::construct_codewords;
+    use crate::spec::{EcLevelH, EcLevelL, QrSpec, Version14, Version2};
+    use crate::types::{Color, QrError};
+
+    fn check<V: QrSpec>(data: &[u8], pattern: MaskPattern) {
+        let mut bits = Bits::<V>::new();
+        bits.push_byte_data(data).unwrap();
+        bits.push_terminator().unwrap();
+        let rawbits = bits.into_bytes();
+        let (expected, data_end) = construct_codewords::<V>(&rawbits).unwrap();
+
+        let mut canvas = Canvas::<V>::new();
+        canvas.draw_all_functional_patterns();
+        canvas.draw_data(&expected[..data_end], &expected[data_end..]);
+        canvas.apply_mask(pattern).unwrap();
+        let width = V::WIDTH as usize;
+        let get = |x: usize, y: usize| Color::from(canvas.get(x as i16, y as i16));
+
+        let info = read_format_info(width, get).unwrap();
+        assert_eq!(info.mask, pattern);
+        let mut codewords = [0; 4000];
+        assert_eq!(read_codewords(width, get, &info, &mut codewords), Ok(expected.len()));
+        assert_eq!(&codewords[..expected.len()], &expected[..]);
+
+        assert_eq!(
+            read_codewords(width, get, &info, &mut codewords[..expected.len() - 1]),
+            Err(QrError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_matches_encoder() {
+        check::<Version2<EcLevelL>>(b"conformance", MaskPattern::VerticalLines);
+        check::<Version14<EcLevelH>>(&[0x42; 150], MaskPattern::Meadow);
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Bit reader
+
+/// Reads big-endian numbers of arbitrary bit widths from a byte slice.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// The number of unread bits.
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    /// Reads a number of at most 16 bits.
+    fn read(&mut self, bits: usize) -> QrResult<u16> {
+        debug_assert!(bits <= 16);
+        if bits > self.remaining() {
+            return Err(QrError::InvalidSymbol);
+        }
+        let mut number = 0;
+        for _ in 0..bits {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            number = number << 1 | u16::from(bit);
+            self.position += 1;
+        }
+        Ok(number)
+    }
+}
+
+#[cfg(test)]
+mod bit_reader_tests {
+    use crate::decode::BitReader;
+    use crate::types::QrError;
+
+    #[test]
+    fn test_read() {
+        let mut reader = BitReader::new(&[0b0001_0000, 0b0010_0000, 0b1100_1100]);
+        assert_eq!(reader.read(4), Ok(0b0001));
+        assert_eq!(reader.read(10), Ok(0b00_0000_1000));
+        assert_eq!(reader.remaining(), 10);
+        assert_eq!(reader.read(10), Ok(0b00_1100_1100));
+        assert_eq!(reader.read(1), Err(QrError::InvalidSymbol));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Segment parsing
+
+/// The characters of the alphanumeric mode, indexed by their base-45 digit.
+static ALPHANUMERIC_CHARS: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Writes decoded bytes into the caller's buffer, or only counts them if
+/// there is no buffer.
+struct Output<'a> {
+    buffer: Option<&'a mut [u8]>,
+    len: usize,
+}
+
+impl<'a> Output<'a> {
+    fn push(&mut self, byte: u8) -> QrResult<()> {
+        if let Some(buffer) = &mut self.buffer {
+            let slot = buffer.get_mut(self.len).ok_or(QrError::BufferTooSmall)?;
+            *slot = byte;
+        }
+        self.len += 1;
+        Ok(())
+    }
+}
+
+/// The kind of a segment found in the data codewords.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SegmentKind {
+    /// A segment bearing data in the given mode.
+    Data(Mode),
+
+    /// An ECI designator, specifying the character set of the data segments
+    /// that follow.
+    Eci(u32),
+
+    /// FNC-1 mode in the first position.
+    Fnc1First,
+
+    /// FNC-1 mode in the second position, with its application indicator.
+    Fnc1Second(u8),
+
+    /// Structured append header, placing this symbol at `index` of `total`
+    /// symbols. `parity` is the XOR of every byte of the complete message.
+    StructuredAppend { index: u8, total: u8, parity: u8 },
+}
+
+/// A segment found in the data codewords.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Segment {
+    /// The kind of the segment.
+    pub kind: SegmentKind,
+
+    /// The start of the segment's content in the output buffer.
+    pub start: usize,
+
+    /// The end of the segment's content in the output buffer. Segments other
+    /// than `SegmentKind::Data` have no content, so `start == end`.
+    pub end: usize,
+}
+
+/// Parses the data codewords of a QR code of the given version into segments
+/// and writes their content into `out`. Returns the number of bytes written.
+///
+/// `on_segment` is called once for every segment, in order.
+///
+///     use qrcode::bits::Bits;
+///     use qrcode::decode::{parse_segments, SegmentKind};
+///     use qrcode::spec::{Version1, EcLevelL};
+///     use qrcode::types::{Mode, Version};
+///
+///     let mut bits = Bits::<Version1<EcLevelL>>::new();
+///     bits.push_eci_designator(9).unwrap();
+///     bits.push_byte_data(b"\xa1\xa2").unwrap();
+///     bits.push_terminator().unwrap();
+///     let data = bits.into_bytes();
+///
+///     let mut out = [0; 8];
+///     let mut kinds = Vec::new();
+///     let len = parse_segments(Version::Normal(1), &data, &mut out, |s| kinds.push(s.kind)).unwrap();
+///     assert_eq!(&out[..len], b"\xa1\xa2");
+///     assert_eq!(kinds, [SegmentKind::Eci(9), SegmentKind::Data(Mode::Byte)]);
+///
+/// # Errors
+///
+/// * `QrError::InvalidSymbol` if the segments are malformed or truncated.
+/// * `QrError::UnsupportedCharacterSet` if a mode indicator is unknown.
+/// * `QrError::BufferTooSmall` if `out` cannot hold the decoded data.
+pub fn parse_segments<S: FnMut(Segment)>(
+    version: Version,
+    data: &[u8],
+    out: &mut [u8],
+    on_segment: S,
+) -> QrResult<usize> {
+    let (len, _) = parse_segments_into(version, data, Output { buffer: Some(out), len: 0 }, on_segment)?;
+    Ok(len)
+}
+
+/// Parses the data codewords like `parse_segments`, but without writing the
+/// content anywhere. Returns the length of the content and the number of bits
+/// taken by the segments, excluding the terminator and padding.
+pub(crate) fn scan_segments<S: FnMut(Segment)>(
+    version: Version,
+    data: &[u8],
+    on_segment: S,
+) -> QrResult<(usize, usize)> {
+    parse_segments_into(version, data, Output { buffer: None, len: 0 }, on_segment)
+}
+
+fn parse_segments_into<S: FnMut(Segment)>(
+    version: Version,
+    data: &[u8],
+    mut output: Output,
+    mut on_segment: S,
+) -> QrResult<(usize, usize)> {
+    let mut reader = BitReader::new(data);
+    let mut bits = 0;
+
+    // A terminator may be truncated or omitted if the data fills the symbol.
+    while reader.remaining() >= 4 {
+        let start = output.len;
+        let kind = match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => SegmentKind::Data(Mode::Numeric),
+            0b0010 => SegmentKind::Data(Mode::Alphanumeric),
+            0b0100 => SegmentKind::Data(Mode::Byte),
+            0b1000 => SegmentKind::Data(Mode::Kanji),
+            0b0111 => SegmentKind::Eci(parse_eci_designator(&mut reader)?),
+            0b0101 => SegmentKind::Fnc1First,
+            0b1001 => SegmentKind::Fnc1Second(reader.read(8)? as u8),
+            0b0011 => {
+                let index = reader.read(4)? as u8;
+                let total = reader.read(4)? as u8 + 1;
+                let parity = reader.read(8)? as u8;
+                SegmentKind::StructuredAppend { index, total, parity }
+            }
+            _ => return Err(QrError::UnsupportedCharacterSet),
+        };
+        if let SegmentKind::Data(mode) = kind {
+            let count = reader.read(mode.length_bits_count(version))?.into();
+            match mode {
+                Mode::Numeric => parse_numeric(&mut reader, count, &mut output)?,
+                Mode::Alphanumeric => parse_alphanumeric(&mut reader, count, &mut output)?,
+                Mode::Byte => parse_byte(&mut reader, count, &mut output)?,
+                Mode::Kanji => parse_kanji(&mut reader, count, &mut output)?,
+            }
+        }
+        on_segment(Segment { kind, start, end: output.len });
+        bits = reader.position;
+    }
+
+    Ok((output.len, bits))
+}
+
+/// Reads an ECI designator of 1, 2 or 3 bytes, as written by
+/// `Bits::push_eci_designator`.
+fn parse_eci_designator(reader: &mut BitReader) -> QrResult<u32> {
+    let first = u32::from(reader.read(8)?);
+    let designator = if first & 0x80 == 0 {
+        first
+    } else if first & 0xc0 == 0x80 {
+        (first & 0x3f) << 8 | u32::from(reader.read(8)?)
+    } else if first & 0xe0 == 0xc0 {
+        (first & 0x1f) << 16 | u32::from(reader.read(16)?)
+    } else {
+        return Err(QrError::InvalidSymbol);
+    };
+    if designator > 999_999 {
+        return Err(QrError::InvalidSymbol);
+    }
+    Ok(designator)
+}
+
+fn parse_numeric(reader: &mut BitReader, count: usize, output: &mut Output) -> QrResult<()> {
+    let mut remaining = count;
+    while remaining > 0 {
+        let (digits, bits, limit) = match remaining {
+            1 => (1, 4, 10),
+            2 => (2, 7, 100),
+            _ => (3, 10, 1000),
+        };
+        let number = reader.read(bits)?;
+        if number >= limit {
+            return Err(QrError::InvalidSymbol);
+        }
+        for i in (0..digits).rev() {
+            output.push(b'0' + (number / 10_u16.pow(i) % 10) as u8)?;
+        }
+        remaining -= digits as usize;
+    }
+    Ok(())
+}
+
+fn parse_alphanumeric(reader: &mut BitReader, count: usize, output: &mut Output) -> QrResult<()> {
+    for _ in 0..count / 2 {
+        let number = reader.read(11)?;
+        if number >= 45 * 45 {
+            return Err(QrError::InvalidSymbol);
+        }
+        output.push(ALPHANUMERIC_CHARS[usize::from(number / 45)])?;
+        output.push(ALPHANUMERIC_CHARS[usize::from(number % 45)])?;
+    }
+    if count % 2 == 1 {
+        let number = reader.read(6)?;
+        let character = ALPHANUMERIC_CHARS.get(usize::from(number)).ok_or(QrError::InvalidSymbol)?;
+        output.push(*character)?;
+    }
+    Ok(())
+}
+
+fn parse_byte(reader: &mut BitReader, count: usize, output: &mut Output) -> QrResult<()> {
+    for _ in 0..count {
+        output.push(reader.read(8)? as u8)?;
+    }
+    Ok(())
+}
+
+fn parse_kanji(reader: &mut BitReader, count: usize, output: &mut Output) -> QrResult<()> {
+    for _ in 0..count {
+        let number = reader.read(13)?;
+        let bytes = (number / 0xc0) << 8 | (number % 0xc0);
+        let cp = if bytes < 0x1f00 { bytes + 0x8140 } else { bytes + 0xc140 };
+        output.push((cp >> 8) as u8)?;
+        output.push((cp & 0xff) as u8)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod parse_segments_tests {
+    use crate::bits::Bits;
+    use crate::decode::{parse_segments, Segment, SegmentKind};
+    use crate::spec::{EcLevelL, QrSpec, Version1, Version10, Version27};
+    use crate::types::{Mode, QrError, Version};
+
+    fn parse<V: QrSpec>(bits: Bits<V>, out: &mut [u8]) -> (usize, Vec<Segment>) {
+        let data = bits.into_bytes();
+        let mut segments = Vec::new();
+        let len = parse_segments(V::VERSION, &data, out, |s| segments.push(s)).unwrap();
+        (len, segments)
+    }
+
+    #[test]
+    fn test_iso_18004_2006_numeric() {
+        let data = [0b0001_0000, 0b0010_0000, 0b0000_1100, 0b0101_0110, 0b0110_0001, 0b1000_0000];
+        let mut out = [0; 16];
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |_| {}), Ok(8));
+        assert_eq!(&out[..8], b"01234567");
+    }
+
+    #[test]
+    fn test_iso_18004_2006_alphanumeric() {
+        let data = [0b0010_0000, 0b0010_1001, 0b1100_1110, 0b1110_0111, 0b0010_0001, 0b0000_0000];
+        let mut out = [0; 16];
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |_| {}), Ok(5));
+        assert_eq!(&out[..5], b"AC-42");
+    }
+
+    #[test]
+    fn test_iso_18004_kanji() {
+        let data = [0b1000_0000, 0b0010_0110, 0b1100_1111, 0b1110_1010, 0b1010_1000];
+        let mut out = [0; 16];
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |_| {}), Ok(4));
+        assert_eq!(&out[..4], b"\x93\x5f\xe4\xaa");
+    }
+
+    #[test]
+    fn test_eci() {
+        for &designator in &[9, 899, 999_999] {
+            let mut bits = Bits::<Version1<EcLevelL>>::new();
+            bits.push_eci_designator(designator).unwrap();
+            bits.push_byte_data(b"\xa1").unwrap();
+            let mut out = [0; 4];
+            let (len, segments) = parse(bits, &mut out);
+            assert_eq!(&out[..len], b"\xa1");
+            assert_eq!(
+                segments,
+                [
+                    Segment { kind: SegmentKind::Eci(designator), start: 0, end: 0 },
+                    Segment { kind: SegmentKind::Data(Mode::Byte), start: 0, end: 1 },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_fnc1() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_fnc1_second_position(37).unwrap();
+        bits.push_alphanumeric_data(b"AA12").unwrap();
+        bits.push_numeric_data(b"345").unwrap();
+        let mut out = [0; 16];
+        let (len, segments) = parse(bits, &mut out);
+        assert_eq!(&out[..len], b"AA12345");
+        assert_eq!(
+            segments,
+            [
+                Segment { kind: SegmentKind::Fnc1Second(37), start: 0, end: 0 },
+                Segment { kind: SegmentKind::Data(Mode::Alphanumeric), start: 0, end: 4 },
+                Segment { kind: SegmentKind::Data(Mode::Numeric), start: 4, end: 7 },
+            ]
+        );
+
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_fnc1_first_position().unwrap();
+        bits.push_numeric_data(b"01049").unwrap();
+        let (len, segments) = parse(bits, &mut out);
+        assert_eq!(&out[..len], b"01049");
+        assert_eq!(segments[0].kind, SegmentKind::Fnc1First);
+    }
+
+    #[test]
+    fn test_structured_append() {
+        // Symbol 2 of 4, parity 0x5a, followed by a single digit "7".
+        let data = [0b0011_0010, 0b0011_0101, 0b1010_0001, 0b0000_0000, 0b0101_1100, 0b0000_0000];
+        let mut out = [0; 4];
+        let mut segments = Vec::new();
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |s| segments.push(s.kind)), Ok(1));
+        assert_eq!(&out[..1], b"7");
+        assert_eq!(
+            segments,
+            [SegmentKind::StructuredAppend { index: 2, total: 4, parity: 0x5a }, SegmentKind::Data(Mode::Numeric)]
+        );
+    }
+
+    #[test]
+    fn test_character_count_widths() {
+        let digits = [b'9'; 1000];
+
+        let mut bits = Bits::<Version10<EcLevelL>>::new();
+        bits.push_numeric_data(&digits[..600]).unwrap();
+        let mut out = [0; 1000];
+        assert_eq!(parse(bits, &mut out).0, 600);
+
+        let mut bits = Bits::<Version27<EcLevelL>>::new();
+        bits.push_numeric_data(&digits).unwrap();
+        bits.push_byte_data(b"end").unwrap();
+        let mut out = [0; 1003];
+        assert_eq!(parse(bits, &mut out).0, 1003);
+        assert_eq!(&out[1000..], b"end");
+    }
+
+    #[test]
+    fn test_invalid_eci_designator() {
+        let data = [0b0111_1110, 0b0000_0000];
+        let mut out = [0; 4];
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |_| {}), Err(QrError::InvalidSymbol));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = [0b0100_0000, 0b1000_0110];
+        let mut out = [0; 16];
+        assert_eq!(parse_segments(Version::Normal(1), &data, &mut out, |_| {}), Err(QrError::InvalidSymbol));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
@@ -1,11 +1,17 @@
 //! The `ec` module applies the Reed-Solomon error correction codes.
 
-use crate::spec::QrSpec;
-use crate::types::{EcLevel, QrResult};
+use core::marker::PhantomData;
+use core::ops::{Deref, Range};
+
+use crate::canvas;
+use crate::spec::{BlockLayout, QrSpec};
+use crate::types::{EcLevel, QrError, QrResult, Version};
 
-use heapless::ArrayLength;
 use heapless::Vec;
 
+#[cfg(feature = "bench")]
+use test::Bencher;
+
 //------------------------------------------------------------------------------
 //{{{ Error correction primitive
 
@@ -21,21 +27,33 @@ use heapless::Vec;
 ///
 /// data must contain data_len data bytes and then must be zero padded with
 /// ec_code_size zeroes.
-pub fn create_error_correction_code(data: &mut [u8], data_len: usize) {
+pub const fn create_error_correction_code(data: &mut [u8], data_len: usize) {
     let ec_code_size = data.len() - data_len;
-    let log_den = GENERATOR_POLYNOMIALS[ec_code_size];
+    let den = generator_polynomial(ec_code_size);
 
-    // rust-lang-nursery/rust-clippy#2213
-    #[cfg_attr(feature = "cargo-clippy", allow(needless_range_loop))]
-    for i in 0..data_len {
-        let lead_coeff = data[i] as usize;
+    // Plain loops rather than iterators, so that this can run in `const`.
+    let mut i = 0;
+    while i < data_len {
+        let lead_coeff = data[i];
+        i += 1;
         if lead_coeff == 0 {
             continue;
         }
 
-        let log_lead_coeff = usize::from(LOG_TABLE[lead_coeff]);
-        for (u, v) in data[i + 1..].iter_mut().zip(log_den.iter()) {
-            *u ^= EXP_TABLE[(usize::from(*v) + log_lead_coeff) % 255];
+        #[cfg(not(feature = "gf-loop"))]
+        let log_lead_coeff = LOG_TABLE[lead_coeff as usize] as usize;
+
+        let mut j = 0;
+        while j < ec_code_size {
+            #[cfg(not(feature = "gf-loop"))]
+            {
+                data[i + j] ^= EXP_TABLE[(den[j] as usize + log_lead_coeff) % 255];
+            }
+            #[cfg(feature = "gf-loop")]
+            {
+                data[i + j] ^= gf_mul(den[j], lead_coeff);
+            }
+            j += 1;
         }
     }
 }
@@ -76,71 +94,695 @@ mod ec_tests {
     }
 }
 
+//}}}
+//------------------------------------------------------------------------------
+//{{{ SIMD error correction
+
+/// The most error correction codewords per block the SIMD encoder handles,
+/// which covers all QR code versions.
+#[cfg(feature = "simd")]
+const SIMD_MAX_EC_CODE_SIZE: usize = 32;
+
+/// Creates the error correction code like `create_error_correction_code`,
+/// using SSSE3 on x86-64 when the CPU supports it, and NEON on AArch64.
+///
+/// Every data codeword multiplies the whole generator polynomial, 16
+/// coefficients at once, with the products of each nibble looked up in a
+/// 16-byte table by a byte shuffle. Elsewhere, and for more than 32 error
+/// correction codewords, this falls back to `create_error_correction_code`.
+/// Only the error correction codewords are the same: the data codewords are
+/// left as they are, where the scalar version overwrites them.
+#[cfg(feature = "simd")]
+pub fn create_error_correction_code_simd(data: &mut [u8], data_len: usize) {
+    if data.len() - data_len <= SIMD_MAX_EC_CODE_SIZE {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::is_x86_feature_detected!("ssse3") {
+                // SAFETY: The CPU supports SSSE3.
+                unsafe { create_error_correction_code_ssse3(data, data_len) };
+                return;
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            // SAFETY: NEON is part of every AArch64 target.
+            unsafe { create_error_correction_code_neon(data, data_len) };
+            return;
+        }
+    }
+    create_error_correction_code(data, data_len)
+}
+
+/// The products of every element of GF(256) with each low nibble, followed
+/// by those with each high nibble.
+#[cfg(feature = "simd")]
+static MUL_NIBBLES: [[u8; 32]; 256] = build_mul_nibbles(&EXP, &LOG);
+
+/// The generator polynomials for up to `SIMD_MAX_EC_CODE_SIZE` codewords in
+/// normal form, padded with zeros.
+#[cfg(feature = "simd")]
+static SIMD_GENERATOR_POLYNOMIALS: [[u8; SIMD_MAX_EC_CODE_SIZE]; SIMD_MAX_EC_CODE_SIZE + 1] =
+    build_simd_generator_polynomials(&build_generator_polynomials(&EXP, &LOG, false));
+
+#[cfg(feature = "simd")]
+const fn build_mul_nibbles(exp_table: &[u8; 256], log_table: &[u8; 256]) -> [[u8; 32]; 256] {
+    let mut table = [[0; 32]; 256];
+    let mut a = 1;
+    while a < 256 {
+        let mut i = 1;
+        while i < 16 {
+            let (low, high) = (i, i << 4);
+            let log_a = log_table[a] as usize;
+            table[a][i] = exp_table[(log_a + log_table[low] as usize) % 255];
+            table[a][16 + i] = exp_table[(log_a + log_table[high] as usize) % 255];
+            i += 1;
+        }
+        a += 1;
+    }
+    table
+}
+
+#[cfg(feature = "simd")]
+const fn build_simd_generator_polynomials(
+    polynomials: &[[u8; 69]; 70],
+) -> [[u8; SIMD_MAX_EC_CODE_SIZE]; SIMD_MAX_EC_CODE_SIZE + 1] {
+    let mut table = [[0; SIMD_MAX_EC_CODE_SIZE]; SIMD_MAX_EC_CODE_SIZE + 1];
+    let mut n = 0;
+    while n <= SIMD_MAX_EC_CODE_SIZE {
+        let mut i = 0;
+        while i < n {
+            table[n][i] = polynomials[n][i];
+            i += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+/// Computes the remainder in two 16-byte registers shifting one codeword to
+/// the front for every data codeword, like a linear feedback shift register.
+/// The coefficients past the generator polynomial are zero, so the registers
+/// stay zero there.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+unsafe fn create_error_correction_code_ssse3(data: &mut [u8], data_len: usize) {
+    use core::arch::x86_64::*;
+
+    let ec_code_size = data.len() - data_len;
+    let generator = &SIMD_GENERATOR_POLYNOMIALS[ec_code_size];
+    let low_mask = _mm_set1_epi8(0x0f);
+    let mul = |a: u8, b: __m128i| {
+        let table = &MUL_NIBBLES[usize::from(a)];
+        let low = _mm_loadu_si128(table.as_ptr().cast());
+        let high = _mm_loadu_si128(table[16..].as_ptr().cast());
+        let low = _mm_shuffle_epi8(low, _mm_and_si128(b, low_mask));
+        _mm_xor_si128(low, _mm_shuffle_epi8(high, _mm_and_si128(_mm_srli_epi64(b, 4), low_mask)))
+    };
+
+    let g0 = _mm_loadu_si128(generator.as_ptr().cast());
+    let g1 = _mm_loadu_si128(generator[16..].as_ptr().cast());
+    let (mut r0, mut r1) = (_mm_setzero_si128(), _mm_setzero_si128());
+    for &byte in &data[..data_len] {
+        let lead = byte ^ _mm_cvtsi128_si32(r0) as u8;
+        r0 = _mm_alignr_epi8(r1, r0, 1);
+        r1 = _mm_srli_si128(r1, 1);
+        r0 = _mm_xor_si128(r0, mul(lead, g0));
+        r1 = _mm_xor_si128(r1, mul(lead, g1));
+    }
+
+    let mut remainder = [0; SIMD_MAX_EC_CODE_SIZE];
+    _mm_storeu_si128(remainder.as_mut_ptr().cast(), r0);
+    _mm_storeu_si128(remainder[16..].as_mut_ptr().cast(), r1);
+    data[data_len..].copy_from_slice(&remainder[..ec_code_size]);
+}
+
+/// The NEON version of `create_error_correction_code_ssse3`.
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+#[target_feature(enable = "neon")]
+unsafe fn create_error_correction_code_neon(data: &mut [u8], data_len: usize) {
+    use core::arch::aarch64::*;
+
+    let ec_code_size = data.len() - data_len;
+    let generator = &SIMD_GENERATOR_POLYNOMIALS[ec_code_size];
+    let mul = |a: u8, b: uint8x16_t| {
+        let table = &MUL_NIBBLES[usize::from(a)];
+        let low = vqtbl1q_u8(vld1q_u8(table.as_ptr()), vandq_u8(b, vdupq_n_u8(0x0f)));
+        veorq_u8(low, vqtbl1q_u8(vld1q_u8(table[16..].as_ptr()), vshrq_n_u8(b, 4)))
+    };
+
+    let g0 = vld1q_u8(generator.as_ptr());
+    let g1 = vld1q_u8(generator[16..].as_ptr());
+    let (mut r0, mut r1) = (vdupq_n_u8(0), vdupq_n_u8(0));
+    for &byte in &data[..data_len] {
+        let lead = byte ^ vgetq_lane_u8(r0, 0);
+        r0 = vextq_u8(r0, r1, 1);
+        r1 = vextq_u8(r1, vdupq_n_u8(0), 1);
+        r0 = veorq_u8(r0, mul(lead, g0));
+        r1 = veorq_u8(r1, mul(lead, g1));
+    }
+
+    let mut remainder = [0; SIMD_MAX_EC_CODE_SIZE];
+    vst1q_u8(remainder.as_mut_ptr(), r0);
+    vst1q_u8(remainder[16..].as_mut_ptr(), r1);
+    data[data_len..].copy_from_slice(&remainder[..ec_code_size]);
+}
+
+#[cfg(all(test, feature = "simd"))]
+mod simd_tests {
+    use crate::ec::{create_error_correction_code, create_error_correction_code_simd};
+
+    #[test]
+    fn test_matches_scalar() {
+        let data: Vec<u8> = (0..160_u32).map(|i| (i * 37 + i / 7) as u8).collect();
+        for data_len in [0, 1, 15, 16, 17, 122, 153] {
+            for ec_code_size in [1, 2, 7, 15, 16, 17, 26, 30, 32, 33, 68] {
+                let mut expected = data[..data_len].to_vec();
+                expected.resize(data_len + ec_code_size, 0);
+                let mut actual = expected.clone();
+                create_error_correction_code(&mut expected, data_len);
+                create_error_correction_code_simd(&mut actual, data_len);
+                // The scalar version leaves the remainders of the division
+                // in place of the data.
+                assert_eq!(actual[data_len..], expected[data_len..], "{} data, {} ec codewords", data_len, ec_code_size);
+            }
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Error correction decoding
+
+/// The largest number of error correction codewords in a block supported by
+/// the decoding functions. This matches the generator polynomial table.
+const MAX_EC_CODE_SIZE: usize = 69;
+
+/// Evaluates a polynomial stored with the lowest degree coefficient first.
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, c| gf_mul(acc, x) ^ c)
+}
+
+/// Computes the syndromes of a received block.
+///
+/// The block is treated as a polynomial in the same way as
+/// `create_error_correction_code`, i.e. `block[0]` is the coefficient of the
+/// highest degree. `syndromes[j]` will be set to the value of the polynomial
+/// at 2<sup>j</sup>, for every `j` up to the length of `syndromes`, which
+/// should be the number of error correction codewords in the block.
+///
+/// Returns `true` if any syndrome is non-zero, i.e. the block is corrupted.
+pub fn compute_syndromes(block: &[u8], syndromes: &mut [u8]) -> bool {
+    let mut has_errors = false;
+    for (j, syndrome) in syndromes.iter_mut().enumerate() {
+        let x = gf_exp(j);
+        *syndrome = block.iter().fold(0, |acc, c| gf_mul(acc, x) ^ c);
+        has_errors |= *syndrome != 0;
+    }
+    has_errors
+}
+
+/// Finds the error locator polynomial Λ(x) from the syndromes using the
+/// Berlekamp–Massey algorithm.
+///
+/// The coefficients are written to `locator` (lowest degree first), and the
+/// number of errors (the degree of Λ) is returned.
+fn find_error_locator(syndromes: &[u8], locator: &mut [u8; MAX_EC_CODE_SIZE + 1]) -> usize {
+    let mut prev = [0_u8; MAX_EC_CODE_SIZE + 1];
+    let mut temp = [0_u8; MAX_EC_CODE_SIZE + 1];
+
+    *locator = [0; MAX_EC_CODE_SIZE + 1];
+    locator[0] = 1;
+    prev[0] = 1;
+
+    let mut errors_count = 0;
+    let mut shift = 1;
+    let mut prev_discrepancy = 1;
+
+    for n in 0..syndromes.len() {
+        let discrepancy = (1..=errors_count).fold(syndromes[n], |acc, i| acc ^ gf_mul(locator[i], syndromes[n - i]));
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let coeff = gf_div(discrepancy, prev_discrepancy);
+        let update_length = 2 * errors_count <= n;
+        if update_length {
+            temp = *locator;
+        }
+        for (i, p) in prev.iter().enumerate().take(MAX_EC_CODE_SIZE + 1 - shift) {
+            locator[i + shift] ^= gf_mul(coeff, *p);
+        }
+        if update_length {
+            errors_count = n + 1 - errors_count;
+            prev = temp;
+            prev_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+
+    errors_count
+}
+
+/// Corrects errors in a Reed-Solomon block in place.
+///
+/// The block must contain `data_len` data bytes followed by the error
+/// correction code, in the same layout produced by
+/// `create_error_correction_code`. Errors anywhere in the block (including
+/// the error correction code itself) are located with the Berlekamp–Massey
+/// algorithm and a Chien search, and then repaired using Forney's formula.
+///
+///     use qrcode::ec::{create_error_correction_code, correct_errors};
+///
+///     let mut block = *b"hello world\0\0\0\0\0\0";
+///     create_error_correction_code(&mut block, 11);
+///     block[..11].copy_from_slice(b"hello world");
+///     block[1] ^= 0x55;
+///     block[7] = 0;
+///     assert_eq!(correct_errors(&mut block, 11), Ok(2));
+///     assert_eq!(&block[..11], b"hello world");
+///
+/// Returns the number of corrected bytes. If the block contains more errors
+/// than the code can correct (half the number of error correction bytes),
+/// this method returns `Err(QrError::Uncorrectable)` and leaves the block
+/// untouched. The same error is returned if `data_len` exceeds the length of
+/// the block, or leaves more error correction bytes than any version uses.
+pub fn correct_errors(block: &mut [u8], data_len: usize) -> QrResult<usize> {
+    let ec_code_size = block.len().checked_sub(data_len).ok_or(QrError::Uncorrectable)?;
+    if ec_code_size > MAX_EC_CODE_SIZE {
+        return Err(QrError::Uncorrectable);
+    }
+
+    let mut syndromes = [0_u8; MAX_EC_CODE_SIZE];
+    let syndromes = &mut syndromes[..ec_code_size];
+    if !compute_syndromes(block, syndromes) {
+        return Ok(0);
+    }
+
+    let mut locator = [0_u8; MAX_EC_CODE_SIZE + 1];
+    let errors_count = find_error_locator(syndromes, &mut locator);
+    if errors_count * 2 > ec_code_size {
+        return Err(QrError::Uncorrectable);
+    }
+    let locator = &locator[..=errors_count];
+
+    // Ω(x) = S(x) Λ(x) mod x^n
+    let mut evaluator = [0_u8; MAX_EC_CODE_SIZE];
+    for (i, s) in syndromes.iter().enumerate() {
+        for (j, l) in locator.iter().enumerate().take(ec_code_size - i) {
+            evaluator[i + j] ^= gf_mul(*s, *l);
+        }
+    }
+    let evaluator = &evaluator[..ec_code_size];
+
+    // Chien search: the error at power p of the block polynomial is located at
+    // a root 2^-p of Λ(x).
+    let mut corrections = [(0_usize, 0_u8); MAX_EC_CODE_SIZE / 2];
+    let mut found = 0;
+    let last = block.len() - 1;
+    for i in 0..block.len() {
+        let power = last - i;
+        let x_inv = gf_exp(255 - power % 255);
+        if poly_eval(locator, x_inv) != 0 {
+            continue;
+        }
+        if found == errors_count {
+            return Err(QrError::Uncorrectable);
+        }
+
+        // Forney: e = X Ω(X^-1) / Λ'(X^-1), where the formal derivative only
+        // keeps the odd-degree terms in GF(2^8).
+        let derivative =
+            locator.iter().enumerate().skip(1).step_by(2).fold(0, |acc, (k, l)| acc ^ gf_mul(*l, gf_pow(x_inv, k - 1)));
+        if derivative == 0 {
+            return Err(QrError::Uncorrectable);
+        }
+        let magnitude = gf_mul(gf_exp(power), gf_div(poly_eval(evaluator, x_inv), derivative));
+        corrections[found] = (i, magnitude);
+        found += 1;
+    }
+    if found != errors_count {
+        return Err(QrError::Uncorrectable);
+    }
+
+    for &(i, magnitude) in &corrections[..found] {
+        block[i] ^= magnitude;
+    }
+    if compute_syndromes(block, syndromes) {
+        for &(i, magnitude) in &corrections[..found] {
+            block[i] ^= magnitude;
+        }
+        return Err(QrError::Uncorrectable);
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod correct_errors_tests {
+    use crate::ec::{compute_syndromes, correct_errors, create_error_correction_code};
+    use crate::types::QrError;
+
+    fn encoded_block(data: &[u8], ec_code_size: usize) -> Vec<u8> {
+        let mut block = data.to_vec();
+        block.resize(data.len() + ec_code_size, 0);
+        create_error_correction_code(&mut block, data.len());
+        block[..data.len()].copy_from_slice(data);
+        block
+    }
+
+    #[test]
+    fn test_syndromes_of_valid_block() {
+        let block = encoded_block(b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11", 10);
+        let mut syndromes = [0xff; 10];
+        assert!(!compute_syndromes(&block, &mut syndromes));
+        assert_eq!(syndromes, [0; 10]);
+    }
+
+    #[test]
+    fn test_no_errors() {
+        let mut block = encoded_block(b"CUF\x86W&U\xc2w2\x06\x12\x06g&", 18);
+        assert_eq!(correct_errors(&mut block, 15), Ok(0));
+    }
+
+    #[test]
+    fn test_correct_up_to_capacity() {
+        let data = b"CUF\x86W&U\xc2w2\x06\x12\x06g&";
+        let original = encoded_block(data, 18);
+        for errors in 1..=9 {
+            let mut block = original.clone();
+            for k in 0..errors {
+                block[(k * 7 + 3) % original.len()] ^= (k as u8).wrapping_mul(37) | 1;
+            }
+            assert_eq!(correct_errors(&mut block, data.len()), Ok(errors), "{} errors", errors);
+            assert_eq!(block, original);
+        }
+    }
+
+    #[test]
+    fn test_errors_in_ec_code() {
+        let data = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec";
+        let original = encoded_block(data, 13);
+        let mut block = original.clone();
+        block[data.len()] ^= 0xff;
+        block[original.len() - 1] ^= 0x01;
+        assert_eq!(correct_errors(&mut block, data.len()), Ok(2));
+        assert_eq!(block, original);
+    }
+
+    #[test]
+    fn test_too_many_errors() {
+        let data = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
+        let original = encoded_block(data, 10);
+        let mut block = original.clone();
+        for i in 0..6 {
+            block[i * 3] ^= 0x5a;
+        }
+        let damaged = block.clone();
+        assert_eq!(correct_errors(&mut block, data.len()), Err(QrError::Uncorrectable));
+        assert_eq!(block, damaged);
+    }
+
+    #[test]
+    fn test_invalid_data_len() {
+        let mut block = encoded_block(b"hello world", 6);
+        assert_eq!(correct_errors(&mut block, 18), Err(QrError::Uncorrectable));
+        assert_eq!(correct_errors(&mut [0; 4], usize::MAX), Err(QrError::Uncorrectable));
+        assert_eq!(correct_errors(&mut [0; 80], 0), Err(QrError::Uncorrectable));
+        assert_eq!(correct_errors(&mut block, 17), Ok(0));
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Interleave support
 
+/// Copies every `strive`-th byte of `data` starting from `offset` into `out`
+/// at `*cursor`, advancing the cursor.
 #[inline]
-fn interleave_append<N: ArrayLength<u8>>(
-    vec: &mut Vec<u8, N>,
-    data: &[u8],
-    count: usize,
-    strive: usize,
-    offset: usize,
-) {
+fn interleave_append(out: &mut [u8], cursor: &mut usize, data: &[u8], count: usize, strive: usize, offset: usize) {
     if offset >= strive {
         return;
     }
     for i in (0..count).map(|x| x * strive + offset) {
-        vec.push(data[i]).unwrap();
+        out[*cursor] = data[i];
+        *cursor += 1;
     }
 }
 
-//}}}
-//------------------------------------------------------------------------------
-//{{{ QR code error correction
+/// Computes the error correction codes of every block, concatenated in block
+/// order.
+fn construct_ec_blocks<V: QrSpec>(rawbits: &[u8]) -> Vec<u8, V::ECBlocksSize> {
+    let mut ec_blocks = Vec::new();
+    ec_blocks.resize(ec_blocks_len::<V>(), 0).unwrap();
+    construct_ec_blocks_into::<V>(rawbits, &mut ec_blocks);
+    ec_blocks
+}
 
-/// Constructs data and error correction codewords ready to be put in the QR
-/// code matrix.
-pub fn construct_codewords<V: QrSpec>(rawbits: &[u8]) -> QrResult<(Vec<u8, V::TotalSize>, usize)> {
-    let blocks_count = V::BLOCK_1_COUNT + V::BLOCK_2_COUNT;
-    let block_1_end = V::BLOCK_1_SIZE * V::BLOCK_1_COUNT;
-    let total_size = block_1_end + V::BLOCK_2_SIZE * V::BLOCK_2_COUNT;
+/// Checks that `rawbits` holds exactly the data codewords of `V`.
+fn check_data_len<V: QrSpec>(rawbits: &[u8]) -> QrResult<()> {
+    let expected = V::BLOCK_LAYOUT.data_len();
+    if rawbits.len() != expected {
+        return Err(QrError::InvalidDataLength { len: rawbits.len(), expected });
+    }
+    Ok(())
+}
 
-    debug_assert_eq!(rawbits.len(), total_size);
+/// The number of error correction codewords of all blocks.
+fn ec_blocks_len<V: QrSpec>() -> usize {
+    V::EC_BYTES_PER_BLOCK * (V::BLOCK_1_COUNT + V::BLOCK_2_COUNT)
+}
+
+/// Like `construct_ec_blocks`, but writes into `ec_blocks`, which must be
+/// exactly `ec_blocks_len` bytes long.
+fn construct_ec_blocks_into<V: QrSpec>(rawbits: &[u8], ec_blocks: &mut [u8]) {
+    let block_1_end = V::BLOCK_1_SIZE * V::BLOCK_1_COUNT;
 
-    let mut ec_blocks: Vec<u8, V::ECBlocksSize> = Vec::new();
     let mut buffer: Vec<u8, V::ECGenBufferSize> = Vec::new();
+    buffer.resize(V::BLOCK_1_SIZE.max(V::BLOCK_2_SIZE) + V::EC_BYTES_PER_BLOCK, 0).unwrap();
 
     // Generate EC codes.
-    for block in rawbits[..block_1_end]
+    let blocks = rawbits[..block_1_end]
         .chunks(V::BLOCK_1_SIZE)
-        .chain(rawbits[block_1_end..].chunks(if V::BLOCK_2_SIZE == 0 { 1 } else { V::BLOCK_2_SIZE }))
-    {
+        .chain(rawbits[block_1_end..].chunks(if V::BLOCK_2_SIZE == 0 { 1 } else { V::BLOCK_2_SIZE }));
+    for (block, ec_block) in blocks.zip(ec_blocks.chunks_mut(V::EC_BYTES_PER_BLOCK)) {
         let size = block.len() + V::EC_BYTES_PER_BLOCK;
 
-        buffer.clear();
-        buffer.extend_from_slice(block).unwrap();
-        buffer.resize(size, 0).unwrap();
+        buffer[..block.len()].copy_from_slice(block);
+        for b in &mut buffer[block.len()..size] {
+            *b = 0;
+        }
+        #[cfg(not(feature = "simd"))]
         create_error_correction_code(&mut buffer[0..size], block.len());
+        #[cfg(feature = "simd")]
+        create_error_correction_code_simd(&mut buffer[0..size], block.len());
+
+        ec_block.copy_from_slice(&buffer[block.len()..size]);
+    }
+}
+
+/// An iterator over the data and error correction codewords of a QR code, in
+/// the order they are placed in the matrix.
+///
+/// Only the error correction codes are buffered, the data codewords are read
+/// from the raw bits on the fly. This avoids allocating the whole
+/// `V::TotalSize` buffer used by `construct_codewords`. The error correction
+/// codes are either owned, or kept in a caller-provided buffer with
+/// `Codewords::new_in`.
+pub struct Codewords<'a, V: QrSpec, B = Vec<u8, <V as QrSpec>::ECBlocksSize>> {
+    rawbits: &'a [u8],
+    ec_blocks: B,
+    round: usize,
+    block: usize,
+    remaining: usize,
+    _spec: PhantomData<V>,
+}
+
+impl<'a, V: QrSpec> Codewords<'a, V> {
+    /// Computes the error correction codes for `rawbits`, which must contain
+    /// exactly the number of data codewords of the QR code, or this method
+    /// returns `Err(QrError::InvalidDataLength)`.
+    pub fn new(rawbits: &'a [u8]) -> QrResult<Self> {
+        check_data_len::<V>(rawbits)?;
 
-        ec_blocks.extend_from_slice(&buffer[block.len()..]).unwrap();
+        let ec_blocks = construct_ec_blocks::<V>(rawbits);
+        Ok(Self::with_ec_blocks(rawbits, ec_blocks))
     }
+}
+
+impl<'a, V: QrSpec> Codewords<'a, V, &'a [u8]> {
+    /// Like `Codewords::new`, but computes the error correction codes into
+    /// `buffer` instead of a buffer on the stack.
+    ///
+    /// Returns `Err(QrError::BufferTooSmall)` if `buffer` is shorter than the
+    /// number of error correction codewords of the QR code.
+    pub fn new_in(rawbits: &'a [u8], buffer: &'a mut [u8]) -> QrResult<Self> {
+        check_data_len::<V>(rawbits)?;
 
+        let ec_blocks = buffer.get_mut(..ec_blocks_len::<V>()).ok_or(QrError::BufferTooSmall)?;
+        construct_ec_blocks_into::<V>(rawbits, ec_blocks);
+        Ok(Self::with_ec_blocks(rawbits, ec_blocks))
+    }
+}
+
+impl<'a, V: QrSpec, B: Deref<Target = [u8]>> Codewords<'a, V, B> {
+    fn with_ec_blocks(rawbits: &'a [u8], ec_blocks: B) -> Self {
+        let remaining = rawbits.len() + ec_blocks.len();
+        Self { rawbits, ec_blocks, round: 0, block: 0, remaining, _spec: PhantomData }
+    }
+
+    /// The number of data codewords, which are yielded before the error
+    /// correction codewords.
+    pub fn data_len() -> usize {
+        V::BLOCK_1_SIZE * V::BLOCK_1_COUNT + V::BLOCK_2_SIZE * V::BLOCK_2_COUNT
+    }
+
+    /// Moves to the next block, wrapping around to the next round.
+    fn advance(&mut self) {
+        self.block += 1;
+        if self.block == V::BLOCK_1_COUNT + V::BLOCK_2_COUNT {
+            self.block = 0;
+            self.round += 1;
+        }
+    }
+}
+
+impl<'a, V: QrSpec, B: Deref<Target = [u8]>> Iterator for Codewords<'a, V, B> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        if self.remaining >= self.ec_blocks.len() {
+            // Data codewords. Blocks in group 1 are one codeword shorter than
+            // those in group 2, so they are skipped in the last round.
+            loop {
+                let (start, size) = if self.block < V::BLOCK_1_COUNT {
+                    (self.block * V::BLOCK_1_SIZE, V::BLOCK_1_SIZE)
+                } else {
+                    let block_1_end = V::BLOCK_1_SIZE * V::BLOCK_1_COUNT;
+                    (block_1_end + (self.block - V::BLOCK_1_COUNT) * V::BLOCK_2_SIZE, V::BLOCK_2_SIZE)
+                };
+                let round = self.round;
+                self.advance();
+                if round < size {
+                    return Some(self.rawbits[start + round]);
+                }
+            }
+        }
+
+        if self.remaining + 1 == self.ec_blocks.len() {
+            // First error correction codeword, restart the rounds.
+            self.round = 0;
+            self.block = 0;
+        }
+        let codeword = self.ec_blocks[self.block * V::EC_BYTES_PER_BLOCK + self.round];
+        self.advance();
+        Some(codeword)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V: QrSpec, B: Deref<Target = [u8]>> ExactSizeIterator for Codewords<'a, V, B> {}
+
+#[cfg(test)]
+mod codewords_tests {
+    use crate::ec::{construct_codewords, Codewords};
+    use crate::spec::{EcLevelH, EcLevelM, EcLevelQ, QrSpec, Version1, Version40, Version5};
+
+    fn check<V: QrSpec>() {
+        let data: Vec<u8> = (0..Codewords::<V>::data_len()).map(|i| (i * 31 + 7) as u8).collect();
+        let (expected, data_end) = construct_codewords::<V>(&data).unwrap();
+        let codewords = Codewords::<V>::new(&data).unwrap();
+        assert_eq!(codewords.len(), expected.len());
+        assert_eq!(Codewords::<V>::data_len(), data_end);
+        assert!(codewords.eq(expected.iter().copied()));
+
+        let mut buffer = [0; 2500];
+        let codewords = Codewords::<V, _>::new_in(&data, &mut buffer).unwrap();
+        assert!(codewords.eq(expected.iter().copied()));
+        let ec_len = expected.len() - data_end;
+        assert!(Codewords::<V, _>::new_in(&data, &mut buffer[..ec_len - 1]).is_err());
+    }
+
+    #[test]
+    fn test_single_block() {
+        check::<Version1<EcLevelM>>();
+    }
+
+    #[test]
+    fn test_two_groups() {
+        check::<Version5<EcLevelQ>>();
+        check::<Version40<EcLevelH>>();
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ QR code error correction
+
+/// Constructs data and error correction codewords ready to be put in the QR
+/// code matrix.
+///
+/// See `Codewords` for a variant which does not buffer the whole result, and
+/// `construct_codewords_into` for one writing into an existing buffer.
+pub fn construct_codewords<V: QrSpec>(rawbits: &[u8]) -> QrResult<(Vec<u8, V::TotalSize>, usize)> {
     let mut result: Vec<u8, V::TotalSize> = Vec::new();
+    result.resize(V::BLOCK_LAYOUT.total_len(), 0).unwrap();
+    let data_end = construct_codewords_into::<V>(rawbits, &mut result)?;
+    Ok((result, data_end))
+}
+
+/// Constructs data and error correction codewords ready to be put in the QR
+/// code matrix, writing them into `out`.
+///
+///     use qrcode::ec::construct_codewords_into;
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let mut out = [0; 26];
+///     let msg = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
+///     assert_eq!(construct_codewords_into::<Version1<EcLevelM>>(msg, &mut out), Ok(16));
+///     assert_eq!(&out[16..], b"\xc4#'w\xeb\xd7\xe7\xe2]\x17");
+///
+/// Returns the number of data codewords, which are followed by the error
+/// correction codewords. If `out` is shorter than the total number of
+/// codewords of the spec, this method returns `Err(QrError::BufferTooSmall)`,
+/// and if `rawbits` is not exactly as long as the data codewords, it returns
+/// `Err(QrError::InvalidDataLength)`. Any bytes after the codewords are left
+/// untouched.
+pub fn construct_codewords_into<V: QrSpec>(rawbits: &[u8], out: &mut [u8]) -> QrResult<usize> {
+    let blocks_count = V::BLOCK_1_COUNT + V::BLOCK_2_COUNT;
+    let block_1_end = V::BLOCK_1_SIZE * V::BLOCK_1_COUNT;
+
+    check_data_len::<V>(rawbits)?;
+    if out.len() < V::BLOCK_LAYOUT.total_len() {
+        return Err(QrError::BufferTooSmall);
+    }
+
+    let ec_blocks = construct_ec_blocks::<V>(rawbits);
+
+    let mut cursor = 0;
 
     for i in 0..V::BLOCK_1_SIZE.max(V::BLOCK_2_SIZE) {
-        interleave_append(&mut result, &rawbits[..block_1_end], V::BLOCK_1_COUNT, V::BLOCK_1_SIZE, i);
-        interleave_append(&mut result, &rawbits[block_1_end..], V::BLOCK_2_COUNT, V::BLOCK_2_SIZE, i);
+        interleave_append(out, &mut cursor, &rawbits[..block_1_end], V::BLOCK_1_COUNT, V::BLOCK_1_SIZE, i);
+        interleave_append(out, &mut cursor, &rawbits[block_1_end..], V::BLOCK_2_COUNT, V::BLOCK_2_SIZE, i);
     }
 
-    let data_end = result.len();
+    let data_end = cursor;
 
     for i in 0..V::EC_BYTES_PER_BLOCK {
-        interleave_append(&mut result, &ec_blocks[..], blocks_count, V::EC_BYTES_PER_BLOCK, i);
+        interleave_append(out, &mut cursor, &ec_blocks[..], blocks_count, V::EC_BYTES_PER_BLOCK, i);
     }
 
-    Ok((result, data_end))
+    Ok(data_end)
 }
 
 // hello
@@ -149,8 +791,9 @@ pub fn construct_codewords<V: QrSpec>(rawbits: &[u8]) -> QrResult<(Vec<u8, V::To
 
 #[cfg(test)]
 mod construct_codewords_test {
-    use crate::ec::construct_codewords;
+    use crate::ec::{construct_codewords, construct_codewords_into};
     use crate::spec::{EcLevelM, EcLevelQ, Version1, Version5};
+    use crate::types::QrError;
 
     #[test]
     fn test_add_ec_simple() {
@@ -177,6 +820,51 @@ mod construct_codewords_test {
         assert_eq!(&blocks_vec[..data_end], &expected_blocks[..]);
         assert_eq!(&blocks_vec[data_end..], &expected_ec[..]);
     }
+
+    #[test]
+    fn test_into_buffer() {
+        let msg = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
+        let mut out = [0xaa; 30];
+        assert_eq!(construct_codewords_into::<Version1<EcLevelM>>(msg, &mut out), Ok(16));
+        assert_eq!(&out[..16], msg);
+        assert_eq!(&out[16..26], b"\xc4#'w\xeb\xd7\xe7\xe2]\x17");
+        assert_eq!(&out[26..], [0xaa; 4]);
+        assert_eq!(construct_codewords_into::<Version1<EcLevelM>>(msg, &mut out[..25]), Err(QrError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_invalid_data_len() {
+        let msg = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11";
+        let mut out = [0; 26];
+        let err = Err(QrError::InvalidDataLength { len: 15, expected: 16 });
+        assert_eq!(construct_codewords_into::<Version1<EcLevelM>>(&msg[..15], &mut out), err);
+        let err = Err(QrError::InvalidDataLength { len: 0, expected: 16 });
+        assert_eq!(construct_codewords::<Version1<EcLevelM>>(b"").map(|_| ()), err);
+    }
+}
+
+#[cfg(feature = "bench")]
+#[bench]
+fn bench_construct_codewords_version_20(bencher: &mut Bencher) {
+    use crate::spec::{EcLevelH, QrSpec, Version20};
+
+    let data = [0x5a_u8; 1600];
+    let data_len = Version20::<EcLevelH>::BLOCK_1_SIZE * Version20::<EcLevelH>::BLOCK_1_COUNT
+        + Version20::<EcLevelH>::BLOCK_2_SIZE * Version20::<EcLevelH>::BLOCK_2_COUNT;
+    let data = &data[..data_len];
+    bencher.iter(|| construct_codewords::<Version20<EcLevelH>>(data));
+}
+
+#[cfg(feature = "bench")]
+#[bench]
+fn bench_construct_codewords_version_40(bencher: &mut Bencher) {
+    use crate::spec::{EcLevelL, QrSpec, Version40};
+
+    let data = [0x5a_u8; 3000];
+    let data_len = Version40::<EcLevelL>::BLOCK_1_SIZE * Version40::<EcLevelL>::BLOCK_1_COUNT
+        + Version40::<EcLevelL>::BLOCK_2_SIZE * Version40::<EcLevelL>::BLOCK_2_COUNT;
+    let data = &data[..data_len];
+    bencher.iter(|| construct_codewords::<Version40<EcLevelL>>(data));
 }
 
 //}}}
@@ -186,19 +874,25 @@ mod construct_codewords_test {
 /// Computes the maximum allowed number of erratic modules can be introduced to
 /// the QR code, before the data becomes truly corrupted.
 pub fn max_allowed_errors<V: QrSpec>() -> QrResult<usize> {
+    let p = misdecode_protection_codewords(V::VERSION, V::EC_LEVEL);
+    let ec_bytes = (V::BLOCK_1_COUNT + V::BLOCK_2_COUNT) * V::EC_BYTES_PER_BLOCK;
+
+    Ok((ec_bytes - p) / 2)
+}
+
+/// The number of error correction codewords reserved for misdecode
+/// protection, which cannot be used to correct errors. This is the value "p"
+/// in ISO/IEC 18004:2006, §6.5.1, Table 9.
+pub(crate) fn misdecode_protection_codewords(version: Version, ec_level: EcLevel) -> usize {
     use crate::types::Version::{Micro, Normal};
     use EcLevel::{L, M};
 
-    let p = match (V::VERSION, V::EC_LEVEL) {
+    match (version, ec_level) {
         (Micro(2), L) | (Normal(1), L) => 3,
         (Micro(_), L) | (Normal(2), L) | (Micro(2), M) | (Normal(1), M) => 2,
         (Normal(1), _) | (Normal(3), L) => 1,
         _ => 0,
-    };
-
-    let ec_bytes = (V::BLOCK_1_COUNT + V::BLOCK_2_COUNT) * V::EC_BYTES_PER_BLOCK;
-
-    Ok((ec_bytes - p) / 2)
+    }
 }
 
 #[cfg(test)]
@@ -252,128 +946,680 @@ mod max_allowed_errors_test {
 
 //}}}
 //------------------------------------------------------------------------------
-//{{{ Precomputed tables for GF(256).
+//{{{ Error tolerance detail
+
+/// The error correction capacity of a single Reed-Solomon block.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BlockTolerance {
+    /// The number of data codewords in the block.
+    pub data_len: usize,
+
+    /// The number of error correction codewords in the block.
+    pub ec_len: usize,
+
+    /// The number of erroneous codewords the block can recover from.
+    pub correctable: usize,
+}
+
+/// Describes how many errors a QR code can tolerate, block by block.
+///
+/// `max_allowed_errors` assumes errors are spread evenly over every block. In
+/// reality each block is decoded on its own, so the code is only readable if
+/// no block has more erroneous codewords than it can correct. Since codewords
+/// are interleaved, a contiguous run of damaged codewords is spread across the
+/// blocks; `max_burst_len` computes the longest such run which is always
+/// recoverable.
+///
+///     use qrcode::ec::ErrorTolerance;
+///     use qrcode::types::{EcLevel, Version};
+///
+///     let tolerance = ErrorTolerance::new(Version::Normal(5), EcLevel::Q).unwrap();
+///     assert_eq!(tolerance.blocks().count(), 4);
+///     assert!(tolerance.blocks().all(|b| b.correctable == 9));
+///     assert_eq!(tolerance.max_burst_len(), 34);
+#[derive(Debug, Copy, Clone)]
+pub struct ErrorTolerance {
+    version: Version,
+    layout: BlockLayout,
+    correctable: usize,
+}
+
+impl ErrorTolerance {
+    /// Computes the error tolerance of a version and error correction level.
+    ///
+    /// Returns `Err(QrError::InvalidVersion { .. })` for unsupported versions.
+    pub fn new(version: Version, ec_level: EcLevel) -> QrResult<Self> {
+        let layout = BlockLayout::fetch(version, ec_level)?;
+        let p = misdecode_protection_codewords(version, ec_level);
+        Ok(Self { version, layout, correctable: (layout.ec_bytes_per_block - p) / 2 })
+    }
+
+    /// Iterates the tolerance of every block, in block order.
+    pub fn blocks(&self) -> impl Iterator<Item = BlockTolerance> {
+        let ec_len = self.layout.ec_bytes_per_block;
+        let correctable = self.correctable;
+        self.layout.block_sizes().map(move |data_len| BlockTolerance { data_len, ec_len, correctable })
+    }
+
+    /// Finds the block which the codeword at `index` (in the order they are
+    /// placed in the matrix) belongs to.
+    pub fn block_of_codeword(&self, index: usize) -> usize {
+        let blocks_count = self.layout.blocks_count();
+        let data_len = self.layout.data_len();
+        let full_rounds_end = self.layout.block_1_size * blocks_count;
+        if index < full_rounds_end {
+            index % blocks_count
+        } else if index < data_len {
+            // The extra codewords of the longer blocks in group 2.
+            self.layout.block_1_count + (index - full_rounds_end)
+        } else {
+            (index - data_len) % blocks_count
+        }
+    }
+
+    /// Computes the length of the longest run of consecutive codewords (in
+    /// the order they are placed in the matrix) which can be entirely
+    /// destroyed while the QR code remains readable, wherever it starts.
+    pub fn max_burst_len(&self) -> usize {
+        let total_len = self.layout.total_len();
+        let mut counts = [0_usize; MAX_BLOCKS_COUNT];
+
+        // Two pointers: for every start, extend the window until some block
+        // exceeds its capacity. Windows reaching the end are never limiting.
+        let mut best = total_len;
+        let mut end = 0;
+        for start in 0..total_len {
+            while end < total_len {
+                let block = self.block_of_codeword(end);
+                if counts[block] == self.correctable {
+                    break;
+                }
+                counts[block] += 1;
+                end += 1;
+            }
+            if end == total_len {
+                break;
+            }
+            best = best.min(end - start);
+            counts[self.block_of_codeword(start)] -= 1;
+        }
+        best
+    }
+
+    /// Finds which blocks the modules in the columns `x` and rows `y` belong
+    /// to, and whether the symbol can be read with all of them covered. The
+    /// parts of the ranges outside of the symbol are ignored.
+    ///
+    ///     use qrcode::ec::ErrorTolerance;
+    ///     use qrcode::types::{EcLevel, Version};
+    ///
+    ///     let tolerance = ErrorTolerance::new(Version::Normal(10), EcLevel::H).unwrap();
+    ///     // A 9×9 logo between the finder patterns and the central alignment
+    ///     // pattern.
+    ///     let damage = tolerance.region_damage(12..21, 12..21);
+    ///     assert!(damage.is_correctable());
+    ///     assert_eq!(damage.modules, 81);
+    ///     assert!(!tolerance.region_damage(0..9, 0..9).is_correctable());
+    pub fn region_damage(&self, x: Range<usize>, y: Range<usize>) -> RegionDamage {
+        let width = self.version.width();
+        let clip = |r: Range<usize>| r.start.min(width as usize)..r.end.min(width as usize);
+        let (x, y) = (clip(x), clip(y));
+        let inside = |mx: i16, my: i16| x.contains(&(mx as usize)) && y.contains(&(my as usize));
+
+        let mut damage = RegionDamage {
+            modules: x.len() * y.len(),
+            functional: 0,
+            remainder: 0,
+            blocks: [BlockDamage::default(); MAX_BLOCKS_COUNT],
+            blocks_count: self.layout.blocks_count(),
+        };
+        for block in damage.blocks[..damage.blocks_count].iter_mut() {
+            block.correctable = self.correctable;
+        }
+        for my in y.clone() {
+            for mx in x.clone() {
+                let (mx, my) = (mx as i16, my as i16);
+                if canvas::is_functional_unchecked(self.version, width, mx, my)
+                    || canvas::is_version_info(self.version, width, mx, my)
+                {
+                    damage.functional += 1;
+                }
+            }
+        }
+
+        // The modules of a codeword are placed one after another, so a
+        // codeword is counted when its first module inside the region is.
+        let total_len = self.layout.total_len();
+        let mut last_codeword = None;
+        for (i, (mx, my)) in canvas::data_modules(self.version).enumerate() {
+            let codeword = i / 8;
+            if !inside(mx, my) {
+                continue;
+            }
+            if codeword >= total_len {
+                damage.remainder += 1;
+                continue;
+            }
+            let block = &mut damage.blocks[self.block_of_codeword(codeword)];
+            block.modules += 1;
+            if last_codeword != Some(codeword) {
+                block.codewords += 1;
+                last_codeword = Some(codeword);
+            }
+        }
+        damage
+    }
+}
+
+/// The maximum number of blocks in any QR code (version 40-H).
+pub(crate) const MAX_BLOCKS_COUNT: usize = 81;
+
+/// How covering a region damages a single Reed-Solomon block, see
+/// `ErrorTolerance::region_damage`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct BlockDamage {
+    /// The number of modules of the block inside the region.
+    pub modules: usize,
+
+    /// The number of codewords of the block with at least one module inside
+    /// the region.
+    pub codewords: usize,
+
+    /// The number of erroneous codewords the block can recover from.
+    pub correctable: usize,
+}
+
+/// How covering a rectangular region of a symbol, such as with a logo or a
+/// window cut into a label, damages it. Every covered module is assumed to
+/// be read wrongly.
+#[derive(Debug, Clone)]
+pub struct RegionDamage {
+    /// The number of modules of the region inside the symbol.
+    pub modules: usize,
+
+    /// The number of modules of the function patterns, format information
+    /// and version information inside the region. These carry no codewords,
+    /// but readers need them to find and sample the symbol.
+    pub functional: usize,
+
+    /// The number of remainder bits inside the region, which carry nothing.
+    pub remainder: usize,
+
+    blocks: [BlockDamage; MAX_BLOCKS_COUNT],
+    blocks_count: usize,
+}
+
+impl RegionDamage {
+    /// The damage of every block, in block order.
+    pub fn blocks(&self) -> &[BlockDamage] {
+        &self.blocks[..self.blocks_count]
+    }
+
+    /// Checks whether the symbol stays readable with the region covered: no
+    /// block loses more codewords than it can correct, and the function
+    /// patterns and format and version information are left clear.
+    pub fn is_correctable(&self) -> bool {
+        self.functional == 0 && self.blocks().iter().all(|b| b.codewords <= b.correctable)
+    }
+}
+
+#[cfg(test)]
+mod error_tolerance_tests {
+    use crate::decode::decode;
+    use crate::ec::{max_allowed_errors, Codewords, ErrorTolerance};
+    use crate::spec::{EcLevelH, QrSpec, Version10, Version40};
+    use crate::types::{Color, EcLevel, Version};
+    use crate::QrCode;
+
+    #[test]
+    fn test_single_block() {
+        let tolerance = ErrorTolerance::new(Version::Normal(1), EcLevel::L).unwrap();
+        assert_eq!(tolerance.blocks().map(|b| (b.data_len, b.ec_len, b.correctable)).collect::<Vec<_>>(), [(19, 7, 2)]);
+        assert_eq!(tolerance.max_burst_len(), 2);
+    }
+
+    #[test]
+    fn test_sum_matches_max_allowed_errors() {
+        let tolerance = ErrorTolerance::new(Version::Normal(40), EcLevel::H).unwrap();
+        let sum: usize = tolerance.blocks().map(|b| b.correctable).sum();
+        assert_eq!(Ok(sum), max_allowed_errors::<Version40<EcLevelH>>());
+    }
+
+    #[test]
+    fn test_max_burst_len() {
+        // Checks every window of every length directly.
+        fn brute_force(tolerance: &ErrorTolerance, total_len: usize, correctable: usize) -> usize {
+            (1..=total_len)
+                .take_while(|len| {
+                    (0..=total_len - len).all(|start| {
+                        let mut counts = [0; 81];
+                        (start..start + len).all(|i| {
+                            let block = tolerance.block_of_codeword(i);
+                            counts[block] += 1;
+                            counts[block] <= correctable
+                        })
+                    })
+                })
+                .last()
+                .unwrap_or(0)
+        }
+
+        for &(version, ec_level) in &[
+            (Version::Normal(3), EcLevel::H),
+            (Version::Normal(5), EcLevel::Q),
+            (Version::Normal(7), EcLevel::H),
+            (Version::Normal(10), EcLevel::M),
+        ] {
+            let tolerance = ErrorTolerance::new(version, ec_level).unwrap();
+            let total_len = tolerance.blocks().map(|b| b.data_len + b.ec_len).sum();
+            let correctable = tolerance.blocks().next().unwrap().correctable;
+            assert_eq!(tolerance.max_burst_len(), brute_force(&tolerance, total_len, correctable));
+        }
+
+        // The extra codeword of the group 2 blocks at the end of the data
+        // makes bursts across it slightly less tolerable.
+        let tolerance = ErrorTolerance::new(Version::Normal(40), EcLevel::H).unwrap();
+        assert_eq!(tolerance.max_burst_len(), 1195);
+    }
+
+    #[test]
+    fn test_region_damage_whole_symbol() {
+        for (version, ec_level) in [(Version::Normal(1), EcLevel::L), (Version::Normal(7), EcLevel::Q)] {
+            let tolerance = ErrorTolerance::new(version, ec_level).unwrap();
+            let width = version.width() as usize;
+            let damage = tolerance.region_damage(0..width + 10, 0..usize::MAX);
+            assert_eq!(damage.modules, width * width);
+            let data_modules: usize = damage.blocks().iter().map(|b| b.modules).sum();
+            assert_eq!(damage.functional + damage.remainder + data_modules, width * width);
+            for (block, tolerance) in damage.blocks().iter().zip(tolerance.blocks()) {
+                assert_eq!(block.codewords, tolerance.data_len + tolerance.ec_len);
+                assert_eq!(block.modules, block.codewords * 8);
+                assert_eq!(block.correctable, tolerance.correctable);
+            }
+            assert!(!damage.is_correctable());
+
+            let damage = tolerance.region_damage(width..width + 5, 3..3);
+            assert_eq!(damage.modules, 0);
+            assert!(damage.is_correctable());
+        }
+    }
+
+    #[test]
+    fn test_region_damage_readable() {
+        type V = Version10<EcLevelH>;
+        let code = QrCode::<V>::new(b"https://example.com/a/long/enough/path").unwrap();
+        let width = V::WIDTH as usize;
+        let tolerance = ErrorTolerance::new(V::VERSION, V::EC_LEVEL).unwrap();
+        let mut out = [0; 64];
+        for (x, y) in [(12..21, 12..21), (31..45, 10..20), (9..26, 30..35), (12..30, 12..30)] {
+            let damage = tolerance.region_damage(x.clone(), y.clone());
+            if !damage.is_correctable() {
+                continue;
+            }
+            // Invert the region, so that every module is read wrongly.
+            let mut modules: Vec<Color> = code.colors().take(V::AREA).collect();
+            for my in y {
+                for mx in x.clone() {
+                    modules[my * width + mx] = !modules[my * width + mx];
+                }
+            }
+            assert!(decode(&modules, &mut out).is_ok());
+        }
+        assert!(tolerance.region_damage(12..21, 12..21).is_correctable());
+        assert!(!tolerance.region_damage(12..30, 12..30).is_correctable());
+    }
+
+    #[test]
+    fn test_block_of_codeword() {
+        type V = Version40<EcLevelH>;
+        // Tag every data codeword with its block number and check where the
+        // interleaver puts it.
+        let tolerance = ErrorTolerance::new(V::VERSION, V::EC_LEVEL).unwrap();
+        let mut data = Vec::new();
+        for (block, size) in V::BLOCK_LAYOUT.block_sizes().enumerate() {
+            data.extend(core::iter::repeat_n(block as u8, size));
+        }
+        let codewords = Codewords::<V>::new(&data).unwrap();
+        for (i, codeword) in codewords.take(data.len()).enumerate() {
+            assert_eq!(tolerance.block_of_codeword(i), usize::from(codeword));
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ GF(256) arithmetic
+
+/// The primitive polynomial x<sup>8</sup> + x<sup>4</sup> + x<sup>3</sup> +
+/// x<sup>2</sup> + 1 used to reduce products in GF(256).
+const PRIMITIVE_POLYNOMIAL: u16 = 0x11d;
+
+#[cfg(any(not(feature = "gf-loop"), feature = "simd", test))]
+const fn build_exp_table() -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut value = 1_u16;
+    let mut i = 0;
+    while i < 256 {
+        table[i] = value as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= PRIMITIVE_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    table
+}
+
+#[cfg(any(not(feature = "gf-loop"), feature = "simd", test))]
+const fn build_log_table(exp_table: &[u8; 256]) -> [u8; 256] {
+    let mut table = [0xff; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp_table[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+/// Expands the generator polynomials. If `log_form` is set, the coefficients
+/// are stored as their logarithms, otherwise they are stored as is.
+#[cfg(any(not(feature = "gf-loop"), feature = "simd"))]
+const fn build_generator_polynomials(exp_table: &[u8; 256], log_table: &[u8; 256], log_form: bool) -> [[u8; 69]; 70] {
+    let mut table = [[0; 69]; 70];
+
+    // The expanded polynomial, highest degree first, in normal form.
+    let mut poly = [0_u8; 70];
+    poly[0] = 1;
+
+    let mut n = 1;
+    while n < 70 {
+        // Multiply by (x − 2^(n-1)).
+        let log_root = n - 1;
+        let mut j = n;
+        while j > 0 {
+            let prev = poly[j - 1];
+            if prev != 0 {
+                poly[j] ^= exp_table[(log_table[prev as usize] as usize + log_root) % 255];
+            }
+            j -= 1;
+        }
+
+        let mut i = 0;
+        while i < n {
+            table[n][i] = if log_form { log_table[poly[i + 1] as usize] } else { poly[i + 1] };
+            i += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+#[cfg(any(not(feature = "gf-loop"), feature = "simd", test))]
+const EXP: [u8; 256] = build_exp_table();
+#[cfg(any(not(feature = "gf-loop"), feature = "simd", test))]
+const LOG: [u8; 256] = build_log_table(&EXP);
 
 /// `EXP_TABLE` encodes the value of 2<sup>n</sup> in the Galois Field GF(256).
-static EXP_TABLE: &'static [u8] = b"\
-\x01\x02\x04\x08\x10\x20\x40\x80\x1d\x3a\x74\xe8\xcd\x87\x13\x26\
-\x4c\x98\x2d\x5a\xb4\x75\xea\xc9\x8f\x03\x06\x0c\x18\x30\x60\xc0\
-\x9d\x27\x4e\x9c\x25\x4a\x94\x35\x6a\xd4\xb5\x77\xee\xc1\x9f\x23\
-\x46\x8c\x05\x0a\x14\x28\x50\xa0\x5d\xba\x69\xd2\xb9\x6f\xde\xa1\
-\x5f\xbe\x61\xc2\x99\x2f\x5e\xbc\x65\xca\x89\x0f\x1e\x3c\x78\xf0\
-\xfd\xe7\xd3\xbb\x6b\xd6\xb1\x7f\xfe\xe1\xdf\xa3\x5b\xb6\x71\xe2\
-\xd9\xaf\x43\x86\x11\x22\x44\x88\x0d\x1a\x34\x68\xd0\xbd\x67\xce\
-\x81\x1f\x3e\x7c\xf8\xed\xc7\x93\x3b\x76\xec\xc5\x97\x33\x66\xcc\
-\x85\x17\x2e\x5c\xb8\x6d\xda\xa9\x4f\x9e\x21\x42\x84\x15\x2a\x54\
-\xa8\x4d\x9a\x29\x52\xa4\x55\xaa\x49\x92\x39\x72\xe4\xd5\xb7\x73\
-\xe6\xd1\xbf\x63\xc6\x91\x3f\x7e\xfc\xe5\xd7\xb3\x7b\xf6\xf1\xff\
-\xe3\xdb\xab\x4b\x96\x31\x62\xc4\x95\x37\x6e\xdc\xa5\x57\xae\x41\
-\x82\x19\x32\x64\xc8\x8d\x07\x0e\x1c\x38\x70\xe0\xdd\xa7\x53\xa6\
-\x51\xa2\x59\xb2\x79\xf2\xf9\xef\xc3\x9b\x2b\x56\xac\x45\x8a\x09\
-\x12\x24\x48\x90\x3d\x7a\xf4\xf5\xf7\xf3\xfb\xeb\xcb\x8b\x0b\x16\
-\x2c\x58\xb0\x7d\xfa\xe9\xcf\x83\x1b\x36\x6c\xd8\xad\x47\x8e\x01";
+#[cfg(not(feature = "gf-loop"))]
+static EXP_TABLE: [u8; 256] = EXP;
 
 /// `LOG_TABLE` is the inverse function of `EXP_TABLE`.
-static LOG_TABLE: &'static [u8] = b"\
-\xff\x00\x01\x19\x02\x32\x1a\xc6\x03\xdf\x33\xee\x1b\x68\xc7\x4b\
-\x04\x64\xe0\x0e\x34\x8d\xef\x81\x1c\xc1\x69\xf8\xc8\x08\x4c\x71\
-\x05\x8a\x65\x2f\xe1\x24\x0f\x21\x35\x93\x8e\xda\xf0\x12\x82\x45\
-\x1d\xb5\xc2\x7d\x6a\x27\xf9\xb9\xc9\x9a\x09\x78\x4d\xe4\x72\xa6\
-\x06\xbf\x8b\x62\x66\xdd\x30\xfd\xe2\x98\x25\xb3\x10\x91\x22\x88\
-\x36\xd0\x94\xce\x8f\x96\xdb\xbd\xf1\xd2\x13\x5c\x83\x38\x46\x40\
-\x1e\x42\xb6\xa3\xc3\x48\x7e\x6e\x6b\x3a\x28\x54\xfa\x85\xba\x3d\
-\xca\x5e\x9b\x9f\x0a\x15\x79\x2b\x4e\xd4\xe5\xac\x73\xf3\xa7\x57\
-\x07\x70\xc0\xf7\x8c\x80\x63\x0d\x67\x4a\xde\xed\x31\xc5\xfe\x18\
-\xe3\xa5\x99\x77\x26\xb8\xb4\x7c\x11\x44\x92\xd9\x23\x20\x89\x2e\
-\x37\x3f\xd1\x5b\x95\xbc\xcf\xcd\x90\x87\x97\xb2\xdc\xfc\xbe\x61\
-\xf2\x56\xd3\xab\x14\x2a\x5d\x9e\x84\x3c\x39\x53\x47\x6d\x41\xa2\
-\x1f\x2d\x43\xd8\xb7\x7b\xa4\x76\xc4\x17\x49\xec\x7f\x0c\x6f\xf6\
-\x6c\xa1\x3b\x52\x29\x9d\x55\xaa\xfb\x60\x86\xb1\xbb\xcc\x3e\x5a\
-\xcb\x59\x5f\xb0\x9c\xa9\xa0\x51\x0b\xf5\x16\xeb\x7a\x75\x2c\xd7\
-\x4f\xae\xd5\xe9\xe6\xe7\xad\xe8\x74\xd6\xf4\xea\xa8\x50\x58\xaf";
+#[cfg(not(feature = "gf-loop"))]
+static LOG_TABLE: [u8; 256] = LOG;
+
+/// The total length of the generator polynomials for 0 to 69 error correction
+/// code words.
+#[cfg(not(feature = "gf-loop"))]
+const GENERATOR_POLYNOMIALS_LEN: usize = 69 * 70 / 2;
+
+/// Concatenates the generator polynomials without padding, and records where
+/// each of them starts.
+#[cfg(not(feature = "gf-loop"))]
+const fn build_flat_generator_polynomials(
+    polynomials: &[[u8; 69]; 70],
+) -> ([u8; GENERATOR_POLYNOMIALS_LEN], [u16; 71]) {
+    let mut table = [0; GENERATOR_POLYNOMIALS_LEN];
+    let mut offsets = [0; 71];
+    let mut offset = 0;
+    let mut n = 0;
+    while n < 70 {
+        offsets[n] = offset as u16;
+        let mut i = 0;
+        while i < n {
+            table[offset + i] = polynomials[n][i];
+            i += 1;
+        }
+        offset += n;
+        n += 1;
+    }
+    offsets[70] = offset as u16;
+    (table, offsets)
+}
+
+#[cfg(not(feature = "gf-loop"))]
+const FLAT_GENERATOR_POLYNOMIALS: ([u8; GENERATOR_POLYNOMIALS_LEN], [u16; 71]) =
+    build_flat_generator_polynomials(&build_generator_polynomials(&EXP, &LOG, true));
 
 /// The generator polynomial list.
 ///
-/// `GENERATOR_POLYNOMIALS[i]` is the polynomial for `i` error correction code
-/// words. Each entry encodes the log coefficients of the expanded polynomial
-/// (x − 2<sup>0</sup>)(x − 2<sup>1</sup>)…(x − 2<sup>i-1</sup>). Each entry is
-/// used as the denominator for polynomial division to obtain the modulus which
-/// is the Reed-Solomon error correction code.
+/// The polynomial for `i` error correction code words is stored in
+/// `GENERATOR_POLYNOMIALS[GENERATOR_OFFSETS[i]..GENERATOR_OFFSETS[i + 1]]`,
+/// and is `i` bytes long. Each polynomial encodes the coefficients of the
+/// expanded polynomial (x − 2<sup>0</sup>)(x − 2<sup>1</sup>)…(x −
+/// 2<sup>i-1</sup>), without the leading coefficient. Each polynomial is used
+/// as the denominator for polynomial division to obtain the modulus which is
+/// the Reed-Solomon error correction code.
+///
+/// The coefficients are stored as logarithms. The table is computed at
+/// compile time. A partial list can be found from ISO/IEC 18004:2006 Annex A.
 ///
-/// A partial list can be found from ISO/IEC 18004:2006 Annex A.
-#[cfg_attr(rustfmt, rustfmt_skip)]
-// ^ this attribute is currently useless, see rust-lang-nursery/rustfmt#1080 and 1298
-static GENERATOR_POLYNOMIALS: [&'static [u8]; 70] = [
-    b"",
-    b"\x00",
-    b"\x19\x01",
-    b"\xc6\xc7\x03",
-    b"\x4b\xf9\x4e\x06",
-    b"\x71\xa4\xa6\x77\x0a",
-    b"\xa6\x00\x86\x05\xb0\x0f",
-    b"\x57\xe5\x92\x95\xee\x66\x15",
-    b"\xaf\xee\xd0\xf9\xd7\xfc\xc4\x1c",
-    b"\x5f\xf6\x89\xe7\xeb\x95\x0b\x7b\x24",
-    b"\xfb\x43\x2e\x3d\x76\x46\x40\x5e\x20\x2d",
-    b"\xdc\xc0\x5b\xc2\xac\xb1\xd1\x74\xe3\x0a\x37",
-    b"\x66\x2b\x62\x79\xbb\x71\xc6\x8f\x83\x57\x9d\x42",
-    b"\x4a\x98\xb0\x64\x56\x64\x6a\x68\x82\xda\xce\x8c\x4e",
-    b"\xc7\xf9\x9b\x30\xbe\x7c\xda\x89\xd8\x57\xcf\x3b\x16\x5b",
-    b"\x08\xb7\x3d\x5b\xca\x25\x33\x3a\x3a\xed\x8c\x7c\x05\x63\x69",
-    b"\x78\x68\x6b\x6d\x66\xa1\x4c\x03\x5b\xbf\x93\xa9\xb6\xc2\xe1\x78",
-    b"\x2b\x8b\xce\x4e\x2b\xef\x7b\xce\xd6\x93\x18\x63\x96\x27\xf3\xa3\x88",
-    b"\xd7\xea\x9e\x5e\xb8\x61\x76\xaa\x4f\xbb\x98\x94\xfc\xb3\x05\x62\x60\x99",
-    b"\x43\x03\x69\x99\x34\x5a\x53\x11\x96\x9f\x2c\x80\x99\x85\xfc\xde\x8a\xdc\xab",
-    b"\x11\x3c\x4f\x32\x3d\xa3\x1a\xbb\xca\xb4\xdd\xe1\x53\xef\x9c\xa4\xd4\xd4\xbc\xbe",
-    b"\xf0\xe9\x68\xf7\xb5\x8c\x43\x62\x55\xc8\xd2\x73\x94\x89\xe6\x24\x7a\xfe\x94\xaf\xd2",
-    b"\xd2\xab\xf7\xf2\x5d\xe6\x0e\x6d\xdd\x35\xc8\x4a\x08\xac\x62\x50\xdb\x86\xa0\x69\xa5\xe7",
-    b"\xab\x66\x92\x5b\x31\x67\x41\x11\xc1\x96\x0e\x19\xb7\xf8\x5e\xa4\xe0\xc0\x01\x4e\x38\x93\xfd",
-    b"\xe5\x79\x87\x30\xd3\x75\xfb\x7e\x9f\xb4\xa9\x98\xc0\xe2\xe4\xda\x6f\x00\x75\xe8\x57\x60\xe3\x15",
-    b"\xe7\xb5\x9c\x27\xaa\x1a\x0c\x3b\x0f\x94\xc9\x36\x42\xed\xd0\x63\xa7\x90\xb6\x5f\xf3\x81\xb2\xfc\x2d",
-    b"\xad\x7d\x9e\x02\x67\xb6\x76\x11\x91\xc9\x6f\x1c\xa5\x35\xa1\x15\xf5\x8e\x0d\x66\x30\xe3\x99\x91\xda\x46",
-    b"\x4f\xe4\x08\xa5\xe3\x15\xb4\x1d\x09\xed\x46\x63\x2d\x3a\x8a\x87\x49\x7e\xac\x5e\xd8\xc1\x9d\x1a\x11\x95\x60",
-    b"\xa8\xdf\xc8\x68\xe0\xea\x6c\xb4\x6e\xbe\xc3\x93\xcd\x1b\xe8\xc9\x15\x2b\xf5\x57\x2a\xc3\xd4\x77\xf2\x25\x09\x7b",
-    b"\x9c\x2d\xb7\x1d\x97\xdb\x36\x60\xf9\x18\x88\x05\xf1\xaf\xbd\x1c\x4b\xea\x96\x94\x17\x09\xca\xa2\x44\xfa\x8c\x18\x97",
-    b"\x29\xad\x91\x98\xd8\x1f\xb3\xb6\x32\x30\x6e\x56\xef\x60\xde\x7d\x2a\xad\xe2\xc1\xe0\x82\x9c\x25\xfb\xd8\xee\x28\xc0\xb4",
-    b"\x14\x25\xfc\x5d\x3f\x4b\xe1\x1f\x73\x53\x71\x27\x2c\x49\x7a\x89\x76\x77\x90\xf8\xf8\x37\x01\xe1\x69\x7b\xb7\x75\xbb\xc8\xd2",
-    b"\x0a\x06\x6a\xbe\xf9\xa7\x04\x43\xd1\x8a\x8a\x20\xf2\x7b\x59\x1b\x78\xb9\x50\x9c\x26\x45\xab\x3c\x1c\xde\x50\x34\xfe\xb9\xdc\xf1",
-    b"\xf5\xe7\x37\x18\x47\x4e\x4c\x51\xe1\xd4\xad\x25\xd7\x2e\x77\xe5\xf5\xa7\x7e\x48\xb5\x5e\xa5\xd2\x62\x7d\x9f\xb8\xa9\xe8\xb9\xe7\x12",
-    b"\x6f\x4d\x92\x5e\x1a\x15\x6c\x13\x69\x5e\x71\xc1\x56\x8c\xa3\x7d\x3a\x9e\xe5\xef\xda\x67\x38\x46\x72\x3d\xb7\x81\xa7\x0d\x62\x3e\x81\x33",
-    b"\x07\x5e\x8f\x51\xf7\x7f\xca\xca\xc2\x7d\x92\x1d\x8a\xa2\x99\x41\x69\x7a\x74\xee\x1a\x24\xd8\x70\x7d\xe4\x0f\x31\x08\xa2\x1e\x7e\x6f\x3a\x55",
-    b"\xc8\xb7\x62\x10\xac\x1f\xf6\xea\x3c\x98\x73\x00\xa7\x98\x71\xf8\xee\x6b\x12\x3f\xda\x25\x57\xd2\x69\xb1\x78\x4a\x79\xc4\x75\xfb\x71\xe9\x1e\x78",
-    b"\x9a\x4b\x8d\xb4\x3d\xa5\x68\xe8\x2e\xe3\x60\xb2\x5c\x87\x39\xa2\x78\xc2\xd4\xae\xfc\xb7\x2a\x23\x9d\x6f\x17\x85\x64\x08\x69\x25\xc0\xbd\x9f\x13\x9c",
-    b"\x9f\x22\x26\xe4\xe6\x3b\xf3\x5f\x31\xda\xb0\xa4\x14\x41\x2d\x6f\x27\x51\x31\x76\x71\xde\xc1\xfa\xf2\xa8\xd9\x29\xa4\xf7\xb1\x1e\xee\x12\x78\x99\x3c\xc1",
-    b"\x51\xd8\xae\x2f\xc8\x96\x3b\x9c\x59\x8f\x59\xa6\xb7\xaa\x98\x15\xa5\xb1\x71\x84\xea\x05\x9a\x44\x7c\xaf\xc4\x9d\xf9\xe9\x53\x18\x99\xf1\x7e\x24\x74\x13\xe7",
-    b"\x3b\x74\x4f\xa1\xfc\x62\x80\xcd\x80\xa1\xf7\x39\xa3\x38\xeb\x6a\x35\x1a\xbb\xae\xe2\x68\xaa\x07\xaf\x23\xb5\x72\x58\x29\x2f\xa3\x7d\x86\x48\x14\xe8\x35\x23\x0f",
-    b"\x84\xa7\x34\x8b\xb8\xdf\x95\x5c\xfa\x12\x53\x21\x7f\x6d\xc2\x07\xd3\xf2\x6d\x42\x56\xa9\x57\x60\xbb\x9f\x72\xac\x76\xd0\xb7\xc8\x52\xb3\x26\x27\x22\xf2\x8e\x93\x37",
-    b"\xfa\x67\xdd\xe6\x19\x12\x89\xe7\x00\x03\x3a\xf2\xdd\xbf\x6e\x54\xe6\x08\xbc\x6a\x60\x93\x0f\x83\x8b\x22\x65\xdf\x27\x65\xd5\xc7\xed\xfe\xc9\x7b\xab\xa2\xc2\x75\x32\x60",
-    b"\x60\x43\x03\xf5\xd9\xd7\x21\x41\xf0\x6d\x90\x3f\x15\x83\x26\x65\x99\x80\x37\x1f\xed\x03\x5e\xa0\x14\x57\x4d\x38\xbf\x7b\xcf\x4b\x52\x00\x7a\x84\x65\x91\xd7\x0f\x79\xc0\x8a",
-    b"\xbe\x07\x3d\x79\x47\xf6\x45\x37\xa8\xbc\x59\xf3\xbf\x19\x48\x7b\x09\x91\x0e\xf7\x01\xee\x2c\x4e\x8f\x3e\xe0\x7e\x76\x72\x44\xa3\x34\xc2\xd9\x93\xcc\xa9\x25\x82\x71\x66\x49\xb5",
-    b"\x06\xac\x48\xfa\x12\xab\xab\xa2\xe5\xbb\xef\x04\xbb\x0b\x25\xe4\x66\x48\x66\x16\x21\x49\x5f\x63\x84\x01\x0f\x59\x04\x70\x82\x5f\xd3\xeb\xe3\x3a\x23\x58\x84\x17\x2c\xa5\x36\xbb\xe1",
-    b"\x70\x5e\x58\x70\xfd\xe0\xca\x73\xbb\x63\x59\x05\x36\x71\x81\x2c\x3a\x10\x87\xd8\xa9\xd3\x24\x01\x04\x60\x3c\xf1\x49\x68\xea\x08\xf9\xf5\x77\xae\x34\x19\x9d\xe0\x2b\xca\xdf\x13\x52\x0f",
-    b"\x4c\xa4\xe5\x5c\x4f\xa8\xdb\x6e\x68\x15\xdc\x4a\x13\xc7\xc3\x64\x5d\xbf\x2b\xd5\x48\x38\x8a\xa1\x7d\xbb\x77\xfa\xbd\x89\xbe\x4c\x7e\xf7\x5d\x1e\x84\x06\x3a\xd5\xd0\xa5\xe0\x98\x85\x5b\x3d",
-    b"\xe4\x19\xc4\x82\xd3\x92\x3c\x18\xfb\x5a\x27\x66\xf0\x3d\xb2\x3f\x2e\x7b\x73\x12\xdd\x6f\x87\xa0\xb6\xcd\x6b\xce\x5f\x96\x78\xb8\x5b\x15\xf7\x9c\x8c\xee\xbf\x0b\x5e\xe3\x54\x32\xa3\x27\x22\x6c",
-    b"\xac\x79\x01\x29\xc1\xde\xed\x40\x6d\xb5\x34\x78\xd4\xe2\xef\xf5\xd0\x14\xf6\x22\xe1\xcc\x86\x65\x7d\xce\x45\x8a\xfa\x00\x4d\x3a\x8f\xb9\xdc\xfe\xd2\xbe\x70\x58\x5b\x39\x5a\x6d\x05\x0d\xb5\x19\x9c",
-    b"\xe8\x7d\x9d\xa1\xa4\x09\x76\x2e\xd1\x63\xcb\xc1\x23\x03\xd1\x6f\xc3\xf2\xcb\xe1\x2e\x0d\x20\xa0\x7e\xd1\x82\xa0\xf2\xd7\xf2\x4b\x4d\x2a\xbd\x20\x71\x41\x7c\x45\xe4\x72\xeb\xaf\x7c\xaa\xd7\xe8\x85\xcd",
-    b"\xd5\xa6\x8e\x2b\x0a\xd8\x8d\xa3\xac\xb4\x66\x46\x59\x3e\xde\x3e\x2a\xd2\x97\xa3\xda\x46\x4d\x27\xa6\xbf\x72\xca\xf5\xbc\xb7\xdd\x4b\xd4\x1b\xed\x7f\xcc\xeb\x3e\xbe\xe8\x12\x2e\xab\x0f\x62\xf7\x42\xa3\x00",
-    b"\x74\x32\x56\xba\x32\xdc\xfb\x59\xc0\x2e\x56\x7f\x7c\x13\xb8\xe9\x97\xd7\x16\x0e\x3b\x91\x25\xf2\xcb\x86\xfe\x59\xbe\x5e\x3b\x41\x7c\x71\x64\xe9\xeb\x79\x16\x4c\x56\x61\x27\xf2\xc8\xdc\x65\x21\xef\xfe\x74\x33",
-    b"\x7a\xd6\xe7\x88\xc7\x0b\x06\xcd\x7c\x48\xd5\x75\xbb\x3c\x93\xc9\x49\x4b\x21\x92\xab\xf7\x76\xd0\x9d\xb1\xcb\xeb\x53\x2d\xe2\xca\xe5\xa8\x07\x39\xed\xeb\xc8\x7c\x6a\xfe\xa5\x0e\x93\x00\x39\x2a\x1f\xb2\xd5\xad\x67",
-    b"\xb7\x1a\xc9\x57\xd2\xdd\x71\x15\x2e\x41\x2d\x32\xee\xb8\xf9\xe1\x66\x3a\xd1\xda\x6d\xa5\x1a\x5f\xb8\xc0\x34\xf5\x23\xfe\xee\xaf\xac\x4f\x7b\x19\x7a\x2b\x78\x6c\xd7\x50\x80\xc9\xeb\x08\x99\x3b\x65\x1f\xc6\x4c\x1f\x9c",
-    b"\x26\xc5\x7b\xa7\x10\x57\xb2\xee\xe3\x61\x94\xf7\x1a\x5a\xe4\xb6\xec\xc5\x2f\xf9\x24\xd5\x36\x71\xb5\x4a\xb1\xcc\x9b\x3d\x2f\x2a\x00\x84\x90\xfb\xc8\x26\x26\x8a\x36\x2c\x40\x13\x16\xce\x10\x0a\xe4\xd3\xa1\xab\x2c\xc2\xd2",
-    b"\x6a\x78\x6b\x9d\xa4\xd8\x70\x74\x02\x5b\xf8\xa3\x24\xc9\xca\xe5\x06\x90\xfe\x9b\x87\xd0\xaa\xd1\x0c\x8b\x7f\x8e\xb6\xf9\xb1\xae\xbe\x1c\x0a\x55\xef\xb8\x65\x7c\x98\xce\x60\x17\xa3\x3d\x1b\xc4\xf7\x97\x9a\xca\xcf\x14\x3d\x0a",
-    b"\x3a\x8c\xed\x5d\x6a\x3d\xc1\x02\x57\x49\xc2\xd7\x9f\xa3\x0a\x9b\x05\x79\x99\x3b\xf8\x04\x75\x16\x3c\xb1\x90\x2c\x48\xe4\x3e\x01\x13\xaa\x71\x9e\x19\xaf\xc7\x8b\x5a\x01\xd2\x07\x77\x9a\x59\x9f\x82\x7a\x2e\x93\xbe\x87\x5e\x44\x42",
-    b"\x52\x74\x1a\xf7\x42\x1b\x3e\x6b\xfc\xb6\xc8\xb9\xeb\x37\xfb\xf2\xd2\x90\x9a\xed\xb0\x8d\xc0\xf8\x98\xf9\xce\x55\xfd\x8e\x41\xa5\x7d\x17\x18\x1e\x7a\xf0\xd6\x06\x81\xda\x1d\x91\x7f\x86\xce\xf5\x75\x1d\x29\x3f\x9f\x8e\xe9\x7d\x94\x7b",
-    b"\x39\x73\xe8\x0b\xc3\xd9\x03\xce\x4d\x43\x1d\xa6\xb4\x6a\x76\xcb\x11\x45\x98\xd5\x4a\x2c\x31\x2b\x62\x3d\xfd\x7a\x0e\x2b\xd1\x8f\x09\x68\x6b\xab\xe0\x39\xfe\xfb\xe2\xe8\xdd\xc2\xf0\x75\xa1\x52\xb2\xf6\xb2\x21\x32\x56\xd7\xef\xb4\xb4\xb5",
-    b"\x6b\x8c\x1a\x0c\x09\x8d\xf3\xc5\xe2\xc5\xdb\x2d\xd3\x65\xdb\x78\x1c\xb5\x7f\x06\x64\xf7\x02\xcd\xc6\x39\x73\xdb\x65\x6d\xa0\x52\x25\x26\xee\x31\xa0\xd1\x79\x56\x0b\x7c\x1e\xb5\x54\x19\xc2\x57\x41\x66\xbe\xdc\x46\x1b\xd1\x10\x59\x07\x21\xf0",
-    b"\xa1\xf4\x69\x73\x40\x09\xdd\xec\x10\x91\x94\x22\x90\xba\x0d\x14\xfe\xf6\x26\x23\xca\x48\x04\xd4\x9f\xd3\xa5\x87\xfc\xfa\x19\x57\x1e\x78\xe2\xea\x5c\xc7\x48\x07\x9b\xda\xe7\x2c\x7d\xb2\x9c\xae\x7c\x2b\x64\x1f\x38\x65\xcc\x40\xaf\xe1\xa9\x92\x2d",
-    b"\x41\xca\x71\x62\x47\xdf\xf8\x76\xd6\x5e\x00\x7a\x25\x17\x02\xe4\x3a\x79\x07\x69\x87\x4e\xf3\x76\x46\x4c\xdf\x59\x48\x32\x46\x6f\xc2\x11\xd4\x7e\xb5\x23\xdd\x75\xeb\x0b\xe5\x95\x93\x7b\xd5\x28\x73\x06\xc8\x64\x1a\xf6\xb6\xda\x7f\xd7\x24\xba\x6e\x6a",
-    b"\x1e\x47\x24\x47\x13\xc3\xac\x6e\x3d\x02\xa9\xc2\x5a\x88\x3b\xb6\xe7\x91\x66\x27\xaa\xe7\xd6\x43\xc4\xcf\x35\x70\xf6\x5a\x5a\x79\xb7\x92\x4a\x4d\x26\x59\x16\xe7\x37\x38\xf2\x70\xd9\x6e\x7b\x3e\xc9\xd9\x80\xa5\x3c\xb5\x25\xa1\xf6\x84\xf6\x12\x73\x88\xa8",
-    b"\x2d\x33\xaf\x09\x07\x9e\x9f\x31\x44\x77\x5c\x7b\xb1\xcc\xbb\xfe\xc8\x4e\x8d\x95\x77\x1a\x7f\x35\xa0\x5d\xc7\xd4\x1d\x18\x91\x9c\xd0\x96\xda\xd1\x04\xd8\x5b\x2f\xb8\x92\x2f\x8c\xc3\xc3\x7d\xf2\xee\x3f\x63\x6c\x8c\xe6\xf2\x1f\xcc\x0b\xb2\xf3\xd9\x9c\xd5\xe7",
-    b"\x89\x9e\xf7\xf0\x25\xee\xd6\x80\x63\xda\x2e\x8a\xc6\x80\x5c\xdb\x6d\x8b\xa6\x19\x42\x43\x0e\x3a\xee\x95\xb1\xc3\xdd\x9a\xab\x30\x50\x0c\x3b\xbe\xe4\x13\x37\xd0\x5c\x70\xe5\x25\x3c\x0a\x2f\x51\x00\xc0\x25\xab\xaf\x93\x80\x49\xa6\x3d\x95\x0c\x18\x5f\x46\x71\x28",
-    b"\x05\x76\xde\xb4\x88\x88\xa2\x33\x2e\x75\x0d\xd7\x51\x11\x8b\xf7\xc5\xab\x5f\xad\x41\x89\xb2\x44\x6f\x5f\x65\x29\x48\xd6\xa9\xc5\x5f\x07\x2c\x9a\x4d\x6f\xec\x28\x79\x8f\x3f\x57\x50\xfd\xf0\x7e\xd9\x4d\x22\xe8\x6a\x32\xa8\x52\x4c\x92\x43\x6a\xab\x19\x84\x5d\x2d\x69",
-    b"\xbf\xac\x71\x56\x07\xa6\xf6\xb9\x9b\xfa\x62\x71\x59\x56\xd6\xe1\x9c\xbe\x3a\x21\x90\x43\xb3\xa3\x34\x9a\xe9\x97\x68\xfb\xa0\x7e\xaf\xd0\xe1\x46\xe3\x92\x04\x98\x8b\x67\x19\x6b\x3d\xcc\x9f\xfa\xc1\xe1\x69\xa0\x62\xa7\x02\x35\x10\xf2\x53\xd2\xc4\x67\xf8\x56\xd3\x29\xab",
-    b"\xf7\x9f\xdf\x21\xe0\x5d\x4d\x46\x5a\xa0\x20\xfe\x2b\x96\x54\x65\xbe\xcd\x85\x34\x3c\xca\xa5\xdc\xcb\x97\x5d\x54\x0f\x54\xfd\xad\xa0\x59\xe3\x34\xc7\x61\x5f\xe7\x34\xb1\x29\x7d\x89\xf1\xa6\xe1\x76\x02\x36\x20\x52\xd7\xaf\xc6\x2b\xee\xeb\x1b\x65\xb8\x7f\x03\x05\x08\xa3\xee",
-    b"\x69\x49\x44\x01\x1d\xa8\x75\x0e\x58\xd0\x37\x2e\x2a\xd9\x06\x54\xb3\x61\x06\xf0\xc0\xe7\x9e\x40\x76\xa0\xcb\x39\x3d\x6c\xc7\x7c\x41\xbb\xdd\xa7\x27\xb6\x9f\xb4\xf4\xcb\xe4\xfe\x0d\xaf\x3d\x5a\xce\x28\xc7\x5e\x43\x39\x51\xe5\x2e\x7b\x59\x25\x1f\xca\x42\xfa\x23\xaa\xf3\x58\x33",
-];
+/// With the `gf-loop` feature, the 2415 bytes of the table and the offsets
+/// are left out, and the polynomials are expanded when needed instead.
+#[cfg(not(feature = "gf-loop"))]
+static GENERATOR_POLYNOMIALS: [u8; GENERATOR_POLYNOMIALS_LEN] = FLAT_GENERATOR_POLYNOMIALS.0;
+
+/// Where each polynomial of `GENERATOR_POLYNOMIALS` starts, followed by the
+/// end of the last one.
+#[cfg(not(feature = "gf-loop"))]
+static GENERATOR_OFFSETS: [u16; 71] = FLAT_GENERATOR_POLYNOMIALS.1;
+
+/// Obtains the coefficients of the generator polynomial for `ec_code_size`
+/// error correction code words.
+#[cfg(not(feature = "gf-loop"))]
+#[inline]
+const fn generator_polynomial(ec_code_size: usize) -> &'static [u8] {
+    let (begin, end) = (GENERATOR_OFFSETS[ec_code_size] as usize, GENERATOR_OFFSETS[ec_code_size + 1] as usize);
+    GENERATOR_POLYNOMIALS.split_at(end).0.split_at(begin).1
+}
+
+/// Expands the generator polynomial for `ec_code_size` error correction code
+/// words into the first `ec_code_size` bytes, in normal form. This takes far
+/// less time than the division it is used for.
+#[cfg(feature = "gf-loop")]
+const fn generator_polynomial(ec_code_size: usize) -> [u8; 69] {
+    // The expanded polynomial, highest degree first, as in
+    // `build_generator_polynomials`.
+    let mut poly = [0_u8; 70];
+    poly[0] = 1;
+    let mut root = 1;
+    let mut n = 1;
+    while n <= ec_code_size {
+        // Multiply by (x − 2^(n-1)).
+        let mut j = n;
+        while j > 0 {
+            poly[j] ^= gf_mul(poly[j - 1], root);
+            j -= 1;
+        }
+        root = gf_mul(root, 2);
+        n += 1;
+    }
+
+    let mut coefficients = [0; 69];
+    let mut i = 0;
+    while i < ec_code_size {
+        coefficients[i] = poly[i + 1];
+        i += 1;
+    }
+    coefficients
+}
+
+#[cfg(not(feature = "gf-loop"))]
+#[inline]
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP_TABLE[(usize::from(LOG_TABLE[usize::from(a)]) + usize::from(LOG_TABLE[usize::from(b)])) % 255]
+    }
+}
+
+/// Multiplies two elements of GF(256) with shift-and-xor, which needs no
+/// lookup tables.
+#[cfg(feature = "gf-loop")]
+const fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= PRIMITIVE_POLYNOMIAL as u8;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+#[cfg(not(feature = "gf-loop"))]
+#[inline]
+fn gf_div(a: u8, b: u8) -> u8 {
+    debug_assert!(b != 0, "division by zero in GF(256)");
+    if a == 0 {
+        0
+    } else {
+        EXP_TABLE[(usize::from(LOG_TABLE[usize::from(a)]) + 255 - usize::from(LOG_TABLE[usize::from(b)])) % 255]
+    }
+}
+
+#[cfg(feature = "gf-loop")]
+fn gf_div(a: u8, b: u8) -> u8 {
+    debug_assert!(b != 0, "division by zero in GF(256)");
+    // b^254 = b^-1 since the multiplicative group has order 255.
+    gf_mul(a, gf_pow(b, 254))
+}
+
+/// Computes a<sup>n</sup> in GF(256).
+#[cfg(not(feature = "gf-loop"))]
+#[inline]
+fn gf_pow(a: u8, n: usize) -> u8 {
+    if a == 0 {
+        u8::from(n == 0)
+    } else {
+        EXP_TABLE[usize::from(LOG_TABLE[usize::from(a)]) * n % 255]
+    }
+}
+
+/// Computes a<sup>n</sup> in GF(256).
+#[cfg(feature = "gf-loop")]
+fn gf_pow(mut a: u8, mut n: usize) -> u8 {
+    let mut result = 1;
+    while n != 0 {
+        if n & 1 != 0 {
+            result = gf_mul(result, a);
+        }
+        a = gf_mul(a, a);
+        n >>= 1;
+    }
+    result
+}
+
+/// Computes 2<sup>n</sup> in GF(256) for any exponent.
+#[cfg(not(feature = "gf-loop"))]
+#[inline]
+fn gf_exp(n: usize) -> u8 {
+    EXP_TABLE[n % 255]
+}
+
+/// Computes 2<sup>n</sup> in GF(256) for any exponent.
+#[cfg(feature = "gf-loop")]
+fn gf_exp(n: usize) -> u8 {
+    gf_pow(2, n % 255)
+}
+
+#[cfg(test)]
+mod gf_tests {
+    use crate::ec::{generator_polynomial, gf_div, gf_exp, gf_mul, gf_pow, EXP, LOG};
+
+    fn reference_mul(a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            EXP[(usize::from(LOG[usize::from(a)]) + usize::from(LOG[usize::from(b)])) % 255]
+        }
+    }
+
+    #[test]
+    fn test_exp_log_inverse() {
+        assert_eq!(EXP[255], 1);
+        assert_eq!(LOG[0], 0xff);
+        for i in 0..255 {
+            assert_eq!(usize::from(LOG[usize::from(EXP[i])]), i);
+        }
+    }
+
+    #[test]
+    fn test_mul_div() {
+        for a in 0..=255 {
+            for b in 1..=255 {
+                let product = gf_mul(a, b);
+                assert_eq!(product, reference_mul(a, b));
+                assert_eq!(gf_div(product, b), a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(gf_pow(0, 0), 1);
+        assert_eq!(gf_pow(0, 3), 0);
+        assert_eq!(gf_pow(3, 0), 1);
+        for n in 0..600 {
+            assert_eq!(gf_exp(n), EXP[n % 255]);
+            assert_eq!(gf_pow(2, n), EXP[n % 255]);
+        }
+    }
+
+    #[test]
+    fn test_generator_polynomials() {
+        fn logs(ec_code_size: usize) -> Vec<u8> {
+            let poly = &generator_polynomial(ec_code_size)[..ec_code_size];
+            if cfg!(feature = "gf-loop") {
+                poly.iter().map(|c| LOG[usize::from(*c)]).collect()
+            } else {
+                poly.to_vec()
+            }
+        }
+
+        assert_eq!(logs(0), b"");
+        assert_eq!(logs(2), b"\x19\x01");
+        assert_eq!(logs(7), b"\x57\xe5\x92\x95\xee\x66\x15");
+        assert_eq!(logs(10), b"\xfb\x43\x2e\x3d\x76\x46\x40\x5e\x20\x2d");
+    }
+
+    #[test]
+    fn test_generator_polynomial_roots() {
+        for ec_code_size in 0..70 {
+            let poly = &generator_polynomial(ec_code_size)[..ec_code_size];
+            let coefficient = |c: u8| if cfg!(feature = "gf-loop") { c } else { EXP[usize::from(c)] };
+            for (i, &root) in EXP.iter().enumerate().take(ec_code_size) {
+                // Horner's method, starting with the leading coefficient 1.
+                let value = poly.iter().fold(1, |value, &c| gf_mul(value, root) ^ coefficient(c));
+                assert_eq!(value, 0, "2^{} is not a root for {} codewords", i, ec_code_size);
+            }
+        }
+    }
+}
 
 //}}}
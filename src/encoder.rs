@@ -0,0 +1,143 @@
+//! Encoding many codes in a row.
+//!
+//! An `Encoder` owns all the buffers needed to encode one version, and keeps
+//! them between codes. The finder, timing and alignment patterns and the
+//! version information are drawn once, and copied for every code instead of
+//! clearing and drawing the canvas again. This is for bulk generation, such as
+//! tickets or label runs, where the setup of every code adds up.
+//!
+//!     use qrcode::spec::{EcLevelM, Version2};
+//!     use qrcode::Encoder;
+//!
+//!     let mut encoder = Encoder::<Version2<EcLevelM>>::new();
+//!     for serial in 1000..1010 {
+//!         let code = encoder.encode(format!("TICKET-{}", serial)).unwrap();
+//!         assert!(code.verify().is_ok());
+//!     }
+
+use crate::bits::{Bits, ByteCharset};
+use crate::canvas::Canvas;
+use crate::spec::QrSpec;
+use crate::storage::QrStorage;
+use crate::{QrCode, QrResult};
+
+/// Encodes codes of the version `V` one after another, reusing its buffers.
+pub struct Encoder<V: QrSpec> {
+    /// The functional patterns, drawn once.
+    template: Canvas<V>,
+    storage: QrStorage<V>,
+    byte_charset: ByteCharset,
+}
+
+impl<V: QrSpec> Encoder<V> {
+    /// Constructs an encoder, drawing the functional patterns of `V`.
+    pub fn new() -> Self {
+        let mut template = Canvas::new();
+        template.draw_all_functional_patterns();
+        Self { template, storage: QrStorage::new(), byte_charset: ByteCharset::default() }
+    }
+
+    /// Sets the character set which the targeted scanners assume for byte
+    /// mode data without an ECI designator, `ByteCharset::Latin1` by default.
+    /// This decides how `encode_str` encodes text, and whether it emits the
+    /// ECI designator of UTF-8.
+    ///
+    ///     use qrcode::bits::ByteCharset;
+    ///     use qrcode::spec::{EcLevelM, Version2};
+    ///     use qrcode::Encoder;
+    ///
+    ///     // For phone scanners, which read byte mode as UTF-8.
+    ///     let mut encoder = Encoder::<Version2<EcLevelM>>::new().byte_charset(ByteCharset::Utf8);
+    ///     let code = encoder.encode_str("Grüße").unwrap();
+    pub fn byte_charset(mut self, byte_charset: ByteCharset) -> Self {
+        self.byte_charset = byte_charset;
+        self
+    }
+
+    /// Encodes the data like `QrCode::new`. The code is kept until the next
+    /// call.
+    pub fn encode<D: AsRef<[u8]>>(&mut self, data: D) -> QrResult<&QrCode<V>> {
+        self.storage.bits.clear();
+        self.storage.bits.push_optimal_data(data.as_ref())?;
+        self.storage.bits.push_terminator()?;
+        self.encode_storage_bits()
+    }
+
+    /// Encodes text like `QrCode::encode_str`, for the byte mode character set
+    /// set with `byte_charset`. See `Bits::push_str_with`.
+    pub fn encode_str(&mut self, text: &str) -> QrResult<&QrCode<V>> {
+        self.storage.bits.clear();
+        self.storage.bits.push_str_with(text, self.byte_charset)?;
+        self.storage.bits.push_terminator()?;
+        self.encode_storage_bits()
+    }
+
+    /// Encodes a string of digits like `QrCode::new_numeric`.
+    pub fn encode_numeric<D: AsRef<[u8]>>(&mut self, digits: D) -> QrResult<&QrCode<V>> {
+        self.storage.bits.clear();
+        self.storage.bits.push_numeric_data(digits.as_ref())?;
+        self.storage.bits.push_terminator()?;
+        self.encode_storage_bits()
+    }
+
+    /// Encodes the bits like `QrCode::with_bits`.
+    pub fn encode_bits(&mut self, bits: &Bits<V>) -> QrResult<&QrCode<V>> {
+        let QrStorage { canvas, scratch, code, .. } = &mut self.storage;
+        QrCode::encode_in(bits.bytes(), Some(&self.template), canvas, scratch, code)
+    }
+
+    fn encode_storage_bits(&mut self) -> QrResult<&QrCode<V>> {
+        let QrStorage { bits, canvas, scratch, code } = &mut self.storage;
+        QrCode::encode_in(bits.bytes(), Some(&self.template), canvas, scratch, code)
+    }
+}
+
+impl<V: QrSpec> Default for Encoder<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod encoder_tests {
+    use crate::bits::{Bits, ByteCharset};
+    use crate::spec::{EcLevelH, EcLevelL, Version1, Version7};
+    use crate::{Encoder, QrCode};
+
+    #[test]
+    fn test_same_as_new() {
+        let mut encoder = Encoder::<Version7<EcLevelH>>::new();
+        for data in [&b"first, and the longest of them all"[..], b"HELLO WORLD", b"", b"0123456789"] {
+            assert!(*encoder.encode(data).unwrap() == QrCode::new(data).unwrap());
+        }
+        assert!(*encoder.encode_numeric(b"31415926").unwrap() == QrCode::new_numeric(b"31415926").unwrap());
+        assert!(encoder.encode([0; 100]).is_err());
+        assert!(*encoder.encode(b"after an error").unwrap() == QrCode::new(b"after an error").unwrap());
+    }
+
+    #[test]
+    fn test_encode_bits() {
+        let mut bits = Bits::<Version1<EcLevelL>>::new();
+        bits.push_eci_designator(9).unwrap();
+        bits.push_byte_data(b"\xca\xfe").unwrap();
+        bits.push_terminator().unwrap();
+        let expected = QrCode::with_bits(bits).unwrap();
+
+        let mut bits = Bits::new();
+        bits.push_eci_designator(9).unwrap();
+        bits.push_byte_data(b"\xca\xfe").unwrap();
+        bits.push_terminator().unwrap();
+        assert!(*Encoder::default().encode_bits(&bits).unwrap() == expected);
+    }
+
+    #[test]
+    fn test_byte_charset() {
+        let mut encoder = Encoder::<Version7<EcLevelH>>::new();
+        assert!(*encoder.encode_str("Grüße 👋").unwrap() == QrCode::encode_str("Grüße 👋").unwrap());
+
+        let mut encoder = encoder.byte_charset(ByteCharset::Utf8);
+        let mut bits = Bits::new();
+        bits.push_byte_data("Grüße 👋".as_bytes()).unwrap();
+        assert!(*encoder.encode_str("Grüße 👋").unwrap() == bits.into_qr().unwrap());
+    }
+}
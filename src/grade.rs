@@ -0,0 +1,408 @@
+//! The `grade` module grades the print quality of a QR code from its sampled
+//! module matrix, in the manner of ISO/IEC 15415 and ISO/IEC 18004 §12, so
+//! that labels can be verified after printing.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::grade::{grade, Grade};
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!     use qrcode::types::Color;
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let modules: Vec<Color> = code.colors().collect();
+//!
+//!     let grading = grade(21, |x, y| modules[y * 21 + x]).unwrap();
+//!     assert_eq!(grading.unused_error_correction, 1.0);
+//!     assert_eq!(grading.overall(), Grade::A);
+//!
+//! With reflectance values, `grade_reflectance` also grades the symbol
+//! contrast. Parameters which need the scan profile itself, like modulation
+//! or axial nonuniformity, are out of scope; locating and sampling the symbol
+//! is left to the caller, or to the `sample` module.
+
+use crate::decode::{self, MAX_BLOCK_LEN, MAX_TOTAL_LEN};
+use crate::ec;
+use crate::spec::BlockLayout;
+use crate::types::{Color, EcLevel, QrResult, Version};
+
+//------------------------------------------------------------------------------
+//{{{ Grades
+
+/// A print quality grade. Grades are ordered from the worst, `F`, to the
+/// best, `A`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum Grade {
+    /// Grade 0, a failed symbol.
+    F,
+    /// Grade 1.
+    D,
+    /// Grade 2.
+    C,
+    /// Grade 3.
+    B,
+    /// Grade 4, the best grade.
+    A,
+}
+
+impl Grade {
+    /// The numeric grade, from 0 for `F` to 4 for `A`.
+    pub fn value(self) -> u8 {
+        self as u8
+    }
+
+    /// The letter grade.
+    pub fn letter(self) -> char {
+        match self {
+            Grade::A => 'A',
+            Grade::B => 'B',
+            Grade::C => 'C',
+            Grade::D => 'D',
+            Grade::F => 'F',
+        }
+    }
+
+    /// Grades a value against the lower bounds of grades A, B, C and D, in
+    /// this order.
+    fn at_least(value: f32, bounds: [f32; 4]) -> Self {
+        match bounds.iter().position(|&bound| value >= bound) {
+            Some(0) => Grade::A,
+            Some(1) => Grade::B,
+            Some(2) => Grade::C,
+            Some(3) => Grade::D,
+            _ => Grade::F,
+        }
+    }
+
+    /// Grades a number of wrong modules or bits: none is an A, each one costs
+    /// a grade, and four or more fail.
+    fn from_errors(errors: usize) -> Self {
+        match errors {
+            0 => Grade::A,
+            1 => Grade::B,
+            2 => Grade::C,
+            3 => Grade::D,
+            _ => Grade::F,
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Grading
+
+/// The print quality parameters of a QR code and their grades.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Grading {
+    /// The version of the QR code.
+    pub version: Version,
+
+    /// The error correction level of the QR code.
+    pub ec_level: EcLevel,
+
+    /// Whether every Reed-Solomon block could be corrected: `A` if so, `F`
+    /// otherwise.
+    pub decode: Grade,
+
+    /// The unused error correction of the worst block, between 0 and 1. A
+    /// block with `e` corrected codewords out of `d` error correction
+    /// codewords, `p` of which are reserved for misdecode protection, leaves
+    /// `1 - 2e / (d - p)` unused. Zero if a block is uncorrectable.
+    pub unused_error_correction: f32,
+
+    /// The grade of the unused error correction: A from 0.62, B from 0.50, C
+    /// from 0.37 and D from 0.25.
+    pub unused_error_correction_grade: Grade,
+
+    /// The number of wrong modules in the finder patterns with their
+    /// separators, the timing patterns and the alignment patterns.
+    pub fixed_pattern_errors: usize,
+
+    /// The grade of the most damaged fixed pattern. Each finder pattern, each
+    /// timing pattern and each alignment pattern loses a grade per wrong
+    /// module, and fails from four.
+    pub fixed_pattern_damage: Grade,
+
+    /// The grade of the format information and, from version 7, the version
+    /// information. Each loses a grade per wrong bit in its better copy, and
+    /// fails from four, where the BCH code can no longer correct it.
+    pub format_info_damage: Grade,
+
+    /// The symbol contrast, the difference between the highest and lowest
+    /// reflectance, between 0 and 1. `None` without reflectance values.
+    pub symbol_contrast: Option<f32>,
+
+    /// The grade of the symbol contrast: A from 0.70, B from 0.55, C from 0.40
+    /// and D from 0.20. `None` without reflectance values.
+    pub symbol_contrast_grade: Option<Grade>,
+}
+
+impl Grading {
+    /// The overall grade, the lowest of all graded parameters.
+    pub fn overall(&self) -> Grade {
+        let grade = self
+            .decode
+            .min(self.unused_error_correction_grade)
+            .min(self.fixed_pattern_damage)
+            .min(self.format_info_damage);
+        self.symbol_contrast_grade.map_or(grade, |contrast| grade.min(contrast))
+    }
+}
+
+/// Grades a QR code of the given width from its module colors.
+///
+/// `get` returns the color of the module at the given coordinates, from the
+/// top-left corner, without the quiet zone.
+///
+/// Returns `Err(QrError::InvalidWidth(_))` if the width is not one of a normal
+/// QR code, and `Err(QrError::InvalidSymbol)` if the format information cannot
+/// be read, in which case the symbol fails.
+pub fn grade<F: Fn(usize, usize) -> Color>(width: usize, get: F) -> QrResult<Grading> {
+    let format_info = decode::read_format_info(width, &get)?;
+    let format_errors = format_info.format_bit_errors[0].min(format_info.format_bit_errors[1]);
+    let version_errors = format_info.version_bit_errors[0].min(format_info.version_bit_errors[1]);
+    let format_info_damage = Grade::from_errors(format_errors.max(version_errors) as usize);
+
+    let layout = BlockLayout::fetch(format_info.version, format_info.ec_level)?;
+    let mut codewords = [0; MAX_TOTAL_LEN];
+    let len = decode::read_codewords(width, &get, &format_info, &mut codewords)?;
+    let unused_error_correction =
+        unused_error_correction(&layout, &codewords[..len], format_info.version, format_info.ec_level);
+
+    let (fixed_pattern_errors, fixed_pattern_damage) = fixed_pattern_damage(format_info.version, &get);
+
+    Ok(Grading {
+        version: format_info.version,
+        ec_level: format_info.ec_level,
+        decode: unused_error_correction.map_or(Grade::F, |_| Grade::A),
+        unused_error_correction: unused_error_correction.unwrap_or(0.0),
+        unused_error_correction_grade: Grade::at_least(
+            unused_error_correction.unwrap_or(0.0),
+            [0.62, 0.50, 0.37, 0.25],
+        ),
+        fixed_pattern_errors,
+        fixed_pattern_damage,
+        format_info_damage,
+        symbol_contrast: None,
+        symbol_contrast_grade: None,
+    })
+}
+
+/// Grades a QR code of the given width from the reflectance of its modules,
+/// from 0 for black to 255 for white, like `grade`, additionally grading the
+/// symbol contrast.
+///
+///     use qrcode::QrCode;
+///     use qrcode::grade::{grade_reflectance, Grade};
+///     use qrcode::spec::{Version1, EcLevelM};
+///     use qrcode::types::Color;
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let modules: Vec<Color> = code.colors().collect();
+///
+///     // A faded print on grey paper.
+///     let reflectance = |x, y| if modules[y * 21 + x] == Color::Dark { 90 } else { 200 };
+///     let grading = grade_reflectance(21, reflectance).unwrap();
+///     assert_eq!(grading.symbol_contrast_grade, Some(Grade::C));
+///     assert_eq!(grading.overall(), Grade::C);
+///
+/// Modules are dark below the global threshold, halfway between the highest
+/// and the lowest reflectance.
+pub fn grade_reflectance<F: Fn(usize, usize) -> u8>(width: usize, reflectance: F) -> QrResult<Grading> {
+    let (mut min, mut max) = (u8::MAX, u8::MIN);
+    for y in 0..width {
+        for x in 0..width {
+            let value = reflectance(x, y);
+            min = min.min(value);
+            max = max.max(value);
+        }
+    }
+    let threshold = (u16::from(min) + u16::from(max)).div_ceil(2);
+    let get = |x, y| if u16::from(reflectance(x, y)) < threshold { Color::Dark } else { Color::Light };
+
+    let mut grading = grade(width, get)?;
+    let contrast = f32::from(max.saturating_sub(min)) / 255.0;
+    grading.symbol_contrast = Some(contrast);
+    grading.symbol_contrast_grade = Some(Grade::at_least(contrast, [0.70, 0.55, 0.40, 0.20]));
+    Ok(grading)
+}
+
+/// Corrects every block and returns the unused error correction of the worst
+/// one, or `None` if a block is uncorrectable.
+fn unused_error_correction(layout: &BlockLayout, codewords: &[u8], version: Version, ec_level: EcLevel) -> Option<f32> {
+    let reserved = ec::misdecode_protection_codewords(version, ec_level);
+    let correctable = layout.ec_bytes_per_block.checked_sub(reserved).filter(|&n| n > 0)?;
+    let mut block = [0; MAX_BLOCK_LEN];
+    let mut worst = 1.0_f32;
+    for (b, size) in layout.block_sizes().enumerate() {
+        let block = &mut block[..size + layout.ec_bytes_per_block];
+        for (i, codeword) in block.iter_mut().enumerate() {
+            *codeword = codewords[layout.interleaved_index(b, i)];
+        }
+        let corrected = ec::correct_errors(block, size).ok()?;
+        let unused = 1.0 - (2 * corrected) as f32 / correctable as f32;
+        if unused < 0.0 {
+            return None;
+        }
+        worst = worst.min(unused);
+    }
+    Some(worst)
+}
+
+/// Counts the wrong modules of the fixed patterns, and grades the most damaged
+/// one.
+fn fixed_pattern_damage<F: Fn(usize, usize) -> Color>(version: Version, get: &F) -> (usize, Grade) {
+    let width = version.width();
+    let mut total = 0;
+    let mut worst = Grade::A;
+    let mut count = |errors: usize| {
+        total += errors;
+        worst = worst.min(Grade::from_errors(errors));
+    };
+    let wrong = |x: i16, y: i16, expected: Color| usize::from(get(x as usize, y as usize) != expected);
+    let ring_color = |distance: i16, dark: &[i16]| if dark.contains(&distance) { Color::Dark } else { Color::Light };
+
+    // Finder patterns with their separators, clipped to the symbol.
+    for &(cx, cy) in &[(3, 3), (width - 4, 3), (3, width - 4)] {
+        let mut errors = 0;
+        for y in (cy - 4).max(0)..=(cy + 4).min(width - 1) {
+            for x in (cx - 4).max(0)..=(cx + 4).min(width - 1) {
+                let distance = (x - cx).abs().max((y - cy).abs());
+                errors += wrong(x, y, ring_color(distance, &[0, 1, 3]));
+            }
+        }
+        count(errors);
+    }
+
+    // Timing patterns.
+    let timing_color = |i: i16| if i % 2 == 0 { Color::Dark } else { Color::Light };
+    count((8..width - 8).map(|i| wrong(i, 6, timing_color(i))).sum());
+    count((8..width - 8).map(|i| wrong(6, i, timing_color(i))).sum());
+
+    // Alignment patterns, skipping those overlapping the finder patterns.
+    if let Version::Normal(v) = version {
+        let (positions, len) = crate::canvas::alignment_pattern_positions(v);
+        let positions = &positions[..len];
+        let last = len.saturating_sub(1);
+        for (i, &ax) in positions.iter().enumerate() {
+            for (j, &ay) in positions.iter().enumerate() {
+                if i == 0 && (j == 0 || j == last) || (i == last && j == 0) {
+                    continue;
+                }
+                let mut errors = 0;
+                for y in ay - 2..=ay + 2 {
+                    for x in ax - 2..=ax + 2 {
+                        let distance = (x - ax).abs().max((y - ay).abs());
+                        errors += wrong(x, y, ring_color(distance, &[0, 2]));
+                    }
+                }
+                count(errors);
+            }
+        }
+    }
+
+    (total, worst)
+}
+
+#[cfg(test)]
+mod grade_tests {
+    use crate::grade::{grade, grade_reflectance, Grade};
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, QrSpec, Version1, Version10, Version2, Version7};
+    use crate::types::{Color, EcLevel, QrError, Version};
+    use crate::QrCode;
+
+    fn modules<V: QrSpec>(data: &[u8]) -> Vec<Color> {
+        QrCode::<V>::new(data).unwrap().colors().collect()
+    }
+
+    #[test]
+    fn test_intact() {
+        let modules = modules::<Version10<EcLevelM>>(b"Hello, world!");
+        let grading = grade(57, |x, y| modules[y * 57 + x]).unwrap();
+        assert_eq!(grading.version, Version::Normal(10));
+        assert_eq!(grading.ec_level, EcLevel::M);
+        assert_eq!(grading.decode, Grade::A);
+        assert_eq!(grading.unused_error_correction, 1.0);
+        assert_eq!(grading.fixed_pattern_errors, 0);
+        assert_eq!(grading.format_info_damage, Grade::A);
+        assert_eq!(grading.symbol_contrast, None);
+        assert_eq!(grading.overall(), Grade::A);
+    }
+
+    #[test]
+    fn test_data_damage() {
+        let mut modules = modules::<Version7<EcLevelH>>(b"damaged but readable");
+        for y in 9..40 {
+            for x in 25..35 {
+                modules[y * 45 + x] = !modules[y * 45 + x];
+            }
+        }
+        let grading = grade(45, |x, y| modules[y * 45 + x]).unwrap();
+        assert_eq!(grading.decode, Grade::A);
+        assert!(grading.unused_error_correction < 1.0);
+        assert!(grading.unused_error_correction_grade < Grade::A);
+        assert_eq!(grading.fixed_pattern_damage, Grade::A);
+        assert_eq!(grading.overall(), grading.unused_error_correction_grade);
+
+        for module in &mut modules[45 * 12..45 * 30] {
+            *module = Color::Light;
+        }
+        let grading = grade(45, |x, y| modules[y * 45 + x]).unwrap();
+        assert_eq!((grading.decode, grading.unused_error_correction), (Grade::F, 0.0));
+        assert_eq!(grading.overall(), Grade::F);
+    }
+
+    #[test]
+    fn test_fixed_pattern_damage() {
+        let mut modules = modules::<Version2<EcLevelL>>(b"fixed patterns");
+        let mut flip = |x: usize, y: usize| modules[y * 25 + x] = !modules[y * 25 + x];
+        // Two in the top-left finder pattern, one in its separator, one in
+        // the alignment pattern.
+        flip(2, 2);
+        flip(3, 3);
+        flip(7, 3);
+        flip(18, 18);
+        let grading = grade(25, |x, y| modules[y * 25 + x]).unwrap();
+        assert_eq!(grading.fixed_pattern_errors, 4);
+        assert_eq!(grading.fixed_pattern_damage, Grade::D);
+        assert_eq!(grading.format_info_damage, Grade::A);
+        assert_eq!(grading.overall(), Grade::D);
+    }
+
+    #[test]
+    fn test_format_info_damage() {
+        let mut modules = modules::<Version1<EcLevelL>>(b"format");
+        // One bit of the copy around the top-left finder pattern, two of the
+        // other copy.
+        modules[8 * 21] = !modules[8 * 21];
+        modules[20 * 21 + 8] = !modules[20 * 21 + 8];
+        modules[19 * 21 + 8] = !modules[19 * 21 + 8];
+        let grading = grade(21, |x, y| modules[y * 21 + x]).unwrap();
+        assert_eq!(grading.format_info_damage, Grade::B);
+        assert_eq!(grading.overall(), Grade::B);
+    }
+
+    #[test]
+    fn test_reflectance() {
+        let modules = &modules::<Version1<EcLevelM>>(b"01234567");
+        let reflectance =
+            |dark, light| move |x: usize, y: usize| if modules[y * 21 + x] == Color::Dark { dark } else { light };
+        let grading = grade_reflectance(21, reflectance(40, 200)).unwrap();
+        assert_eq!(grading.symbol_contrast_grade, Some(Grade::B));
+        assert_eq!(grading.overall(), Grade::B);
+        let grading = grade_reflectance(21, reflectance(0, 255)).unwrap();
+        assert_eq!(grading.symbol_contrast, Some(1.0));
+        assert_eq!(grading.overall(), Grade::A);
+        let grading = grade_reflectance(21, reflectance(100, 140)).unwrap();
+        assert_eq!(grading.symbol_contrast_grade, Some(Grade::F));
+        assert_eq!(grading.decode, Grade::A);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(grade(22, |_, _| Color::Light), Err(QrError::InvalidWidth(22)));
+        assert_eq!(grade(21, |_, _| Color::Light), Err(QrError::InvalidSymbol));
+        assert_eq!(Grade::A.value(), 4);
+        assert_eq!(Grade::F.letter(), 'F');
+    }
+}
+
+//}}}
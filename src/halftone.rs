@@ -0,0 +1,251 @@
+//! The `halftone` module embeds a grayscale image into a QR code which stays
+//! decodable, combining two known techniques:
+//!
+//! * The codewords after the terminator are ignored by decoders, so instead
+//!   of the pad codewords `0xEC` and `0x11`, `Halftone::encode` chooses them
+//!   bit by bit so that their modules match the image, and picks the mask
+//!   pattern under which most modules match (as in Russ Cox's "QArt codes").
+//! * `Halftone::pixels` draws every module as 3×3 sub-modules. The center
+//!   keeps the color of the module, where scanners sample it, and the other
+//!   eight follow the image with an ordered dither (as in "Halftone QR codes"
+//!   by Chu et al.). Functional modules are drawn solid.
+//!
+//! The image is given as a function of the sub-module coordinates:
+//!
+//!     use qrcode::halftone::Halftone;
+//!     use qrcode::spec::{Version5, EcLevelL};
+//!
+//!     // A dark disc in the middle of the symbol.
+//!     let halftone = Halftone::new(|x: usize, y: usize| {
+//!         let (dx, dy) = (x as i32 - 55, y as i32 - 55);
+//!         if dx * dx + dy * dy < 40 * 40 { 0 } else { 255 }
+//!     });
+//!     let code = halftone.encode::<Version5<EcLevelL>>(b"https://example.com").unwrap();
+//!     assert!(code.verify().is_ok());
+//!
+//!     let pixels: Vec<u8> = halftone.pixels(&code).collect();
+//!     assert_eq!(pixels.len(), (37 + 8) * 3 * (37 + 8) * 3);
+//!
+//! The more capacity is left after the data, the more of the image shows
+//! through, so choose a version well above the smallest one fitting. The
+//! error correction modules cannot follow the image. The pad codewords are
+//! not those of ISO/IEC 18004, and the halftone cells make the modules
+//! smaller for the scanner, so test the results with the scanners they are
+//! meant for.
+
+use crate::bits::Bits;
+use crate::canvas::{self, Canvas, ColorMatrix, MaskPattern};
+use crate::render::Pixel;
+use crate::spec::QrSpec;
+use crate::types::{Color, QrResult};
+use crate::QrCode;
+
+/// The number of sub-modules per module in each direction.
+const CELLS: usize = 3;
+
+/// The ordered dither thresholds of the 3×3 sub-modules, in ninths.
+const DITHER: [[u16; CELLS]; CELLS] = [[0, 7, 3], [6, 5, 2], [4, 1, 8]];
+
+/// Embeds a grayscale image into QR codes. See the module documentation.
+#[derive(Debug, Clone, Copy)]
+pub struct Halftone<F> {
+    image: F,
+
+    /// The width of the light border around the symbol in `pixels`, in
+    /// modules. Defaults to 4.
+    pub quiet_zone: u32,
+}
+
+impl<F: Fn(usize, usize) -> u8> Halftone<F> {
+    /// Creates a halftone from an image given as the luma, from 0 for black
+    /// to 255 for white, at the sub-module coordinates `(x, y)`, which range
+    /// over 3 times the width of the symbol without the quiet zone.
+    pub fn new(image: F) -> Self {
+        Self { image, quiet_zone: 4 }
+    }
+
+    /// The mean luma of the image over the module at `(x, y)`.
+    fn module_luma(&self, x: usize, y: usize) -> u16 {
+        let mut sum = 0;
+        for dy in 0..CELLS {
+            for dx in 0..CELLS {
+                sum += u16::from((self.image)(x * CELLS + dx, y * CELLS + dy));
+            }
+        }
+        sum / (CELLS * CELLS) as u16
+    }
+
+    /// Encodes the data with the optimal segments like `QrCode::new`, then
+    /// fills the capacity after the terminator and picks the mask pattern so
+    /// that the modules approximate the image.
+    ///
+    /// Returns `Err(QrError::DataTooLong { .. })` if the data does not fit.
+    pub fn encode<V: QrSpec>(&self, data: &[u8]) -> QrResult<QrCode<V>> {
+        let mut bits = Bits::<V>::new();
+        bits.push_optimal_data(data)?;
+        let free_start = bits.len() + 4;
+        bits.push_terminator()?;
+        let rawbits = bits.into_bytes();
+
+        let layout = V::BLOCK_LAYOUT;
+        let mut scratch = ColorMatrix::empty();
+        let mut best: Option<(usize, Canvas<V>)> = None;
+        for pattern in (0..8).filter_map(MaskPattern::from_bits) {
+            let mask_fn = canvas::get_mask_function(pattern);
+            let mut codewords = rawbits.clone();
+            for (k, (x, y)) in canvas::data_modules(V::VERSION).enumerate() {
+                let index = match layout.data_index(k / 8) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let bit = index * 8 + k % 8;
+                if bit < free_start {
+                    continue;
+                }
+                let dark = self.module_luma(x as usize, y as usize) < 128;
+                let mask = 0x80 >> (k % 8);
+                if dark != mask_fn(x, y) {
+                    codewords[index] |= mask;
+                } else {
+                    codewords[index] &= !mask;
+                }
+            }
+
+            let mut canvas = Canvas::<V>::new();
+            canvas.draw_all_functional_patterns();
+            canvas.draw_rawbits(&codewords, &mut scratch)?;
+            canvas.apply_mask(pattern)?;
+            let matches = canvas
+                .colors()
+                .take(V::AREA)
+                .enumerate()
+                .filter(|&(i, color)| {
+                    let (x, y) = (i % V::WIDTH as usize, i / V::WIDTH as usize);
+                    (color == Color::Dark) == (self.module_luma(x, y) < 128)
+                })
+                .count();
+            if best.as_ref().is_none_or(|(best_matches, _)| matches > *best_matches) {
+                best = Some((matches, canvas));
+            }
+        }
+
+        let (_, canvas) = best.expect("eight mask patterns");
+        Ok(QrCode { content: canvas::pack_color_lines::<V>(canvas.colors()) })
+    }
+
+    /// Iterates the pixels of the halftone image of `code` in row-major
+    /// order, 3 pixels per module in each direction, surrounded by the quiet
+    /// zone.
+    pub fn pixels<'a, V: QrSpec, P: Pixel + 'a>(
+        &'a self,
+        code: &'a QrCode<V>,
+    ) -> impl ExactSizeIterator<Item = P> + 'a {
+        let quiet_zone = (self.quiet_zone as usize).saturating_mul(CELLS);
+        let size = (V::WIDTH as usize * CELLS).saturating_add(quiet_zone.saturating_mul(2));
+        (0..size.saturating_mul(size)).map(move |i| {
+            let (x, y) = ((i % size).wrapping_sub(quiet_zone), (i / size).wrapping_sub(quiet_zone));
+            P::from_color(self.pixel(code, x, y))
+        })
+    }
+
+    /// The color of the pixel at the sub-module coordinates `(x, y)`, which
+    /// are light outside of the symbol.
+    fn pixel<V: QrSpec>(&self, code: &QrCode<V>, x: usize, y: usize) -> Color {
+        let (mx, my) = (x / CELLS, y / CELLS);
+        if mx >= V::WIDTH as usize || my >= V::WIDTH as usize {
+            return Color::Light;
+        }
+        let module = code.get(mx as i16, my as i16);
+        let (cx, cy) = (x % CELLS, y % CELLS);
+        let (mx, my) = (mx as i16, my as i16);
+        let solid = canvas::is_functional_unchecked(V::VERSION, V::WIDTH, mx, my)
+            || canvas::is_version_info(V::VERSION, V::WIDTH, mx, my);
+        if (cx, cy) == (1, 1) || solid {
+            return module;
+        }
+        let threshold = (DITHER[cy][cx] * 2 + 1) * 256 / 18;
+        if u16::from((self.image)(x, y)) < threshold {
+            Color::Dark
+        } else {
+            Color::Light
+        }
+    }
+}
+
+#[cfg(test)]
+mod halftone_tests {
+    use crate::decode::decode;
+    use crate::halftone::Halftone;
+    use crate::spec::{EcLevelH, EcLevelL, QrSpec, Version10, Version3};
+    use crate::types::Color;
+    use crate::QrCode;
+
+    /// A dark square in the lower right quarter of a symbol 57 modules wide.
+    fn square(x: usize, y: usize) -> u8 {
+        if (90..150).contains(&x) && (90..150).contains(&y) {
+            20
+        } else {
+            230
+        }
+    }
+
+    fn matches<V: QrSpec>(code: &QrCode<V>) -> usize {
+        let halftone = Halftone::new(square);
+        let width = V::WIDTH as usize;
+        code.colors()
+            .take(V::AREA)
+            .enumerate()
+            .filter(|&(i, color)| (color == Color::Dark) == (halftone.module_luma(i % width, i / width) < 128))
+            .count()
+    }
+
+    #[test]
+    fn test_encode() {
+        type V = Version10<EcLevelL>;
+        let data = b"https://example.com/halftone";
+        let code = Halftone::new(square).encode::<V>(data).unwrap();
+        assert!(code.verify().is_ok());
+
+        let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+        let mut out = [0; 64];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], data);
+        assert_eq!(decoded.corrected, 0);
+
+        let plain = QrCode::<V>::new(data).unwrap();
+        assert!(matches(&code) > matches(&plain) + 57 * 57 / 10);
+    }
+
+    #[test]
+    fn test_full() {
+        // Without capacity left, only the mask can follow the image.
+        let code = Halftone::new(square).encode::<Version3<EcLevelH>>(&[b'x'; 24]).unwrap();
+        assert!(code.verify().is_ok());
+    }
+
+    #[test]
+    fn test_pixels() {
+        type V = Version3<EcLevelH>;
+        let code = Halftone::new(|_, _| 0).encode::<V>(b"dark").unwrap();
+        let halftone = Halftone { quiet_zone: 1, ..Halftone::new(|_, _| 0) };
+        let size = (29 + 2) * 3;
+        let pixels: Vec<Color> = halftone.pixels(&code).collect();
+        assert_eq!(pixels.len(), size * size);
+        assert!(pixels[..size * 3].iter().all(|&p| p == Color::Light));
+
+        for y in 0..29 {
+            for x in 0..29 {
+                let center = pixels[(y * 3 + 4) * size + x * 3 + 4];
+                assert_eq!(center, code.get(x as i16, y as i16));
+            }
+        }
+        // The corner of a light data module follows the black image, the one
+        // of the light ring of a finder pattern does not.
+        let (x, y) = (0..29 * 29)
+            .map(|i| (i % 29, i / 29))
+            .find(|&(x, y)| x > 9 && y > 9 && code.get(x as i16, y as i16) == Color::Light)
+            .unwrap();
+        assert_eq!(pixels[(y * 3 + 3) * size + x * 3 + 3], Color::Dark);
+        assert_eq!(pixels[(3 + 3) * size + 3 + 3], Color::Light);
+    }
+}
@@ -0,0 +1,322 @@
+//! The `inspect` module reports how a finished QR code was encoded, for
+//! command line tools, tests and support tooling.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let report = code.inspect().unwrap();
+//!     assert_eq!(report.segments, 1);
+//!     assert_eq!(report.data_bits, 41);
+//!     assert_eq!(report.data_codewords, 16);
+//!     assert!(report.dark_ratio() > 0.4 && report.dark_ratio() < 0.6);
+//!
+//! The report is read back from the modules, like the decoder does, so it
+//! also describes codes restored with `QrCode::from_bytes`.
+//!
+//! `QrCode::format_bits` breaks the format information down bit by bit, for
+//! debugging scanners which reject a symbol:
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!     use qrcode::types::EcLevel;
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let bits = code.format_bits();
+//!     assert_eq!(bits.raw, [0x5e7c, 0x5e7c]);
+//!     assert_eq!(bits.decoded[0].map(|(ec_level, _)| ec_level), Some(EcLevel::M));
+//!     assert_eq!(bits.coords[0][0], (0, 8));
+
+use heapless::Vec;
+
+use crate::canvas::{self, ColorMatrix, MaskPattern, PenaltyScores};
+use crate::decode::{self, Segment};
+use crate::spec::QrSpec;
+use crate::types::{EcLevel, QrError, QrResult, Version};
+use crate::QrCode;
+
+/// A report on a finished QR code.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Report {
+    /// The version of the QR code.
+    pub version: Version,
+
+    /// The error correction level of the QR code.
+    pub ec_level: EcLevel,
+
+    /// The mask pattern applied to the QR code.
+    pub mask: MaskPattern,
+
+    /// The number of segments, including ECI and FNC1 headers.
+    pub segments: usize,
+
+    /// The number of bits taken by the segments, excluding the terminator and
+    /// padding.
+    pub data_bits: usize,
+
+    /// The number of data codewords the version and error correction level
+    /// can hold.
+    pub data_codewords: usize,
+
+    /// The number of error correction codewords.
+    pub ec_codewords: usize,
+
+    /// The number of Reed-Solomon blocks the codewords are split into.
+    pub blocks: usize,
+
+    /// The number of codewords fixed by error correction, zero for an intact
+    /// code.
+    pub corrected: usize,
+
+    /// The penalty scores of the applied mask pattern.
+    pub penalty: PenaltyScores,
+
+    /// The number of dark modules.
+    pub dark_modules: usize,
+}
+
+impl Report {
+    /// The number of modules of the symbol.
+    pub fn modules(&self) -> usize {
+        let width = self.version.width() as usize;
+        width * width
+    }
+
+    /// The fraction of dark modules, between 0 and 1.
+    pub fn dark_ratio(&self) -> f32 {
+        self.dark_modules as f32 / self.modules() as f32
+    }
+
+    /// The fraction of the data capacity taken by the segments, between 0
+    /// and 1.
+    pub fn fill_ratio(&self) -> f32 {
+        self.data_bits as f32 / (self.data_codewords * 8) as f32
+    }
+}
+
+/// Inspects `code`, calling `on_segment` for every segment found in the data.
+/// See `QrCode::inspect_with_segments`.
+pub(crate) fn inspect<V: QrSpec, S: FnMut(Segment)>(code: &QrCode<V>, on_segment: S) -> QrResult<Report> {
+    let width = V::WIDTH as usize;
+    let get = |x: usize, y: usize| code.get(x as i16, y as i16);
+
+    let format_info = decode::read_format_info(width, get)?;
+    if (format_info.version, format_info.ec_level) != (V::VERSION, V::EC_LEVEL) {
+        return Err(QrError::InvalidSymbol);
+    }
+
+    let layout = V::BLOCK_LAYOUT;
+    let mut codewords: Vec<u8, V::TotalSize> = Vec::new();
+    codewords.resize(layout.total_len(), 0).map_err(|()| QrError::InvalidSymbol)?;
+    decode::read_codewords(width, get, &format_info, &mut codewords)?;
+    let mut data: Vec<u8, V::TotalSize> = Vec::new();
+    data.resize(layout.data_len(), 0).map_err(|()| QrError::InvalidSymbol)?;
+    let corrected = decode::correct_blocks(&layout, &codewords, &mut data)?;
+
+    let mut on_segment = on_segment;
+    let mut segments = 0;
+    let (_, data_bits) = decode::scan_segments(V::VERSION, &data, |segment| {
+        segments += 1;
+        on_segment(segment);
+    })?;
+
+    let matrix = ColorMatrix::<V>::from_colors(code.colors());
+    Ok(Report {
+        version: V::VERSION,
+        ec_level: V::EC_LEVEL,
+        mask: format_info.mask,
+        segments,
+        data_bits,
+        data_codewords: layout.data_len(),
+        ec_codewords: layout.total_len() - layout.data_len(),
+        blocks: layout.block_sizes().count(),
+        corrected,
+        penalty: matrix.penalty_scores(),
+        dark_modules: matrix.count_dark(),
+    })
+}
+
+/// The format information of a QR code, bit by bit.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct FormatBits {
+    /// The raw 15-bit values of the two copies, as read from the modules and
+    /// still masked with `canvas::FORMAT_INFO_MASK_QR`, the copy around the
+    /// top-left finder pattern first.
+    pub raw: [u16; 2],
+
+    /// The error correction level and mask pattern decoded from each copy,
+    /// after correcting up to 3 wrong bits, or `None` if the copy is too
+    /// damaged.
+    pub decoded: [Option<(EcLevel, MaskPattern)>; 2],
+
+    /// The `(x, y)` coordinates of the module holding each bit of each copy,
+    /// from the most significant bit, counted from the top-left corner.
+    pub coords: [[(i16, i16); 15]; 2],
+}
+
+impl FormatBits {
+    /// The number of bits of each copy which differ from the format
+    /// information it decodes to, or `None` if the copy is too damaged.
+    pub fn bit_errors(&self) -> [Option<u32>; 2] {
+        let mut errors = [None; 2];
+        for (errors, &raw) in errors.iter_mut().zip(&self.raw) {
+            *errors = canvas::decode_format_info(raw, canvas::FORMAT_INFO_MASK_QR)
+                .map(|data| (canvas::encode_format_info(data, canvas::FORMAT_INFO_MASK_QR) ^ raw).count_ones());
+        }
+        errors
+    }
+}
+
+/// Reads the format information of `code` bit by bit. See
+/// `QrCode::format_bits`.
+pub(crate) fn format_bits<V: QrSpec>(code: &QrCode<V>) -> FormatBits {
+    let get = |x, y| code.get(x, y);
+    let (main, side) = canvas::read_format_info_qr(&get);
+    let raw = [main, side];
+
+    let mut decoded = [None; 2];
+    for (decoded, &raw) in decoded.iter_mut().zip(&raw) {
+        *decoded = canvas::decode_format_info(raw, canvas::FORMAT_INFO_MASK_QR).and_then(decode::split_format_data);
+    }
+
+    let unwrap = |c: i16| if c < 0 { c + V::WIDTH } else { c };
+    let mut coords = [[(0, 0); 15]; 2];
+    for (coords, copy) in
+        coords.iter_mut().zip(&[canvas::FORMAT_INFO_COORDS_QR_MAIN, canvas::FORMAT_INFO_COORDS_QR_SIDE])
+    {
+        for (coord, &(x, y)) in coords.iter_mut().zip(copy) {
+            *coord = (unwrap(x), unwrap(y));
+        }
+    }
+
+    FormatBits { raw, decoded, coords }
+}
+
+#[cfg(test)]
+mod inspect_tests {
+    use crate::bits::Bits;
+    use crate::canvas::{Canvas, ColorMatrix};
+    use crate::decode::SegmentKind;
+    use crate::ec::Codewords;
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, QrSpec, Version1, Version10, Version40, Version7};
+    use crate::types::{Color, EcLevel, Mode, QrError, Version};
+    use crate::QrCode;
+
+    #[test]
+    fn test_report() {
+        let code = QrCode::<Version7<EcLevelH>>::new(b"0123456789012345HELLO WORLD, hello world").unwrap();
+        let mut kinds = Vec::new();
+        let report =
+            code.inspect_with_segments(|segment| kinds.push((segment.kind, segment.end - segment.start))).unwrap();
+        assert_eq!(
+            kinds,
+            [
+                (SegmentKind::Data(Mode::Numeric), 16),
+                (SegmentKind::Data(Mode::Alphanumeric), 11),
+                (SegmentKind::Data(Mode::Byte), 13)
+            ]
+        );
+        assert_eq!(report.version, Version::Normal(7));
+        assert_eq!(report.ec_level, EcLevel::H);
+        assert_eq!(report.segments, 3);
+        assert_eq!(report.data_bits, (4 + 10 + 54) + (4 + 9 + 61) + (4 + 8 + 13 * 8));
+        assert_eq!((report.data_codewords, report.ec_codewords, report.blocks), (66, 130, 5));
+        assert_eq!(report.corrected, 0);
+        assert_eq!(report.modules(), 45 * 45);
+        assert_eq!(report, code.inspect().unwrap());
+    }
+
+    /// Checks the mask and its penalty scores against the encoder's choice.
+    fn check_mask<V: QrSpec>(data: &[u8]) {
+        let mut bits = Bits::<V>::new();
+        bits.push_optimal_data(data).unwrap();
+        bits.push_terminator().unwrap();
+        let rawbits = bits.into_bytes();
+        let mut canvas = Canvas::<V>::new();
+        canvas.draw_all_functional_patterns();
+        canvas.draw_codewords_iter(Codewords::new(&rawbits).unwrap());
+        let (expected, penalty) = canvas.score_masks(&mut ColorMatrix::empty());
+        canvas.apply_mask(expected).unwrap();
+
+        let code = QrCode::<V>::new(data).unwrap();
+        assert!(code.colors().take(V::AREA).eq(canvas.colors().take(V::AREA)));
+        let report = code.inspect().unwrap();
+        assert_eq!(report.mask, expected);
+        let matrix = ColorMatrix::<V>::from_colors(code.colors());
+        assert_eq!(report.penalty, matrix.penalty_scores());
+        assert_eq!(report.penalty.total(), penalty);
+        assert_eq!(report.dark_modules, code.colors().take(V::AREA).filter(|&c| c == Color::Dark).count());
+    }
+
+    #[test]
+    fn test_mask() {
+        check_mask::<Version1<EcLevelM>>(b"01234567");
+        check_mask::<Version10<EcLevelL>>(b"Lorem ipsum dolor sit amet");
+        check_mask::<Version40<EcLevelH>>(&[0x5a; 1000]);
+    }
+
+    #[test]
+    fn test_damaged() {
+        type V = Version7<EcLevelH>;
+        let code = QrCode::<V>::new(b"damaged but readable").unwrap();
+        let mut bytes = [0; QrCode::<V>::binary_len()];
+        code.to_bytes(&mut bytes).unwrap();
+        for y in 20..26 {
+            for x in 20..26 {
+                let i = y * 45 + x;
+                bytes[3 + i / 8] ^= 0x80 >> (i % 8);
+            }
+        }
+        let report = QrCode::<V>::from_bytes(&bytes).unwrap().inspect().unwrap();
+        assert!(report.corrected > 0);
+        assert_eq!(report.data_bits, code.inspect().unwrap().data_bits);
+
+        // A finder pattern missing a column of its center no longer matches
+        // the finder penalty pattern, which must not underflow the score.
+        let code = QrCode::<Version1<EcLevelH>>::new(b"01234567").unwrap();
+        let mut colors = [Color::Light; 21 * 21];
+        code.to_colors_into(&mut colors).unwrap();
+        for y in 2..5 {
+            colors[y * 21 + 3] = Color::Light;
+        }
+        let damaged = QrCode::<Version1<EcLevelH>>::from_colors(&colors).unwrap();
+        assert_eq!(damaged.verify(), Ok(()));
+        let report = damaged.inspect().unwrap();
+        assert_eq!(report.penalty.finder, 0);
+
+        let mut blank = [0; QrCode::<Version1<EcLevelM>>::binary_len()];
+        blank[..3].copy_from_slice(&[1, 1, 1]);
+        assert_eq!(QrCode::<Version1<EcLevelM>>::from_bytes(&blank).unwrap().inspect(), Err(QrError::InvalidSymbol));
+    }
+
+    #[test]
+    fn test_format_bits() {
+        type V = Version7<EcLevelH>;
+        let code = QrCode::<V>::new(b"format bits").unwrap();
+        let mask = code.inspect().unwrap().mask;
+        let bits = code.format_bits();
+        assert_eq!(bits.raw[0], bits.raw[1]);
+        assert_eq!(bits.decoded, [Some((EcLevel::H, mask)); 2]);
+        assert_eq!(bits.bit_errors(), [Some(0), Some(0)]);
+        assert_eq!(bits.coords[1][0], (8, 44));
+        assert_eq!(bits.coords[1][14], (44, 8));
+        for (coords, &raw) in bits.coords.iter().zip(&bits.raw) {
+            for (i, &(x, y)) in coords.iter().enumerate() {
+                assert_eq!(code.get(x, y) == Color::Dark, raw >> (14 - i) & 1 == 1);
+            }
+        }
+
+        let mut colors: Vec<Color> = code.colors().take(V::AREA).collect();
+        for &(x, y) in &bits.coords[0][..2] {
+            colors[y as usize * 45 + x as usize] = !colors[y as usize * 45 + x as usize];
+        }
+        for &(x, y) in &bits.coords[1][..5] {
+            colors[y as usize * 45 + x as usize] = !colors[y as usize * 45 + x as usize];
+        }
+        let damaged = QrCode::<V>::from_colors(&colors).unwrap().format_bits();
+        assert_eq!(damaged.raw, [bits.raw[0] ^ 0x6000, bits.raw[1] ^ 0x7c00]);
+        assert_eq!(damaged.decoded, [Some((EcLevel::H, mask)), None]);
+        assert_eq!(damaged.bit_errors(), [Some(2), None]);
+        assert_eq!(damaged.coords, bits.coords);
+    }
+}
@@ -27,26 +27,203 @@
 //!     println!("{}", string);
 //! }
 //! ```
+//!
+//! # Panics
+//!
+//! Encoding, decoding, verifying, inspecting, serializing and rendering
+//! report every failure as a `QrError` and do not panic, whatever the input,
+//! for every version and error correction level. This is checked by tests
+//! driving the public API with oversized, malformed and corrupted inputs for
+//! all specs, including symbols with damaged finder patterns, and with
+//! extreme render options. Image and text sizes, such as
+//! `render::Layout::image_size` and `render::Text::max_len`, saturate at the
+//! largest value of their type instead of overflowing.
+//!
+//! The exceptions are documented on the functions concerned:
+//!
+//! * `const_encode` and `PackedQrCode::new` are meant for constants, and
+//!   panic at compile time on invalid input. Use `PackedQrCode::try_new` at
+//!   run time.
+//! * The module accessors `PackedQrCode::get` and `Canvas::get`, `put` and
+//!   `put_unmasked` panic when the coordinates are outside of the symbol.
+//!   Their `try_` variants, like `QrCode::is_functional`, return
+//!   `Err(QrError::OutOfBounds)` instead.
+//! * The Reed-Solomon primitive `ec::create_error_correction_code` panics
+//!   when `data_len` exceeds the buffer or leaves more than 69 error
+//!   correction bytes. `ec::correct_errors` reports both as an error.
+//!
+//! Any other panic is a bug in the crate, as it means an internal invariant
+//! was broken.
+//!
+//! # Determinism
+//!
+//! The same data, version and error correction level always give the same
+//! symbol, module for module, on every platform, whichever features are
+//! enabled and whether the code is built with `QrCode`, `QrStorage` or an
+//! `Encoder`. The encoder uses no floating point, no hashing and no platform
+//! dependent integer sizes, so symbols can be part of reproducible builds or
+//! signed artifacts. The choices which the standard leaves open are pinned:
+//!
+//! * Segmentation: `QrCode::new` splits the data with `optimize::Parser` and
+//!   merges adjacent segments from left to right with `optimize::Optimizer`,
+//!   merging whenever the merged segment is not longer than the two apart.
+//! * Padding: the terminator is cut short if the data capacity runs out,
+//!   followed by zero bits up to the next byte, then the pad codewords
+//!   `0xEC` and `0x11` alternately.
+//! * Mask: the pattern with the lowest penalty score wins, and on a tie, the
+//!   one with the lowest pattern number, also when the masks are scored in
+//!   parallel with the `rayon` feature.
+//!
+//! Changing any of these is a breaking change of the crate, and the symbols
+//! of a set of inputs are checked against pinned digests in the tests.
 
 #![cfg_attr(not(test), no_std)]
+#![cfg_attr(feature = "bench", feature(test))]
 
+#[cfg(feature = "bench")]
+extern crate test;
+
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+pub mod advise;
 pub mod bits;
 pub mod canvas;
 mod cast;
+pub mod const_encode;
+pub mod damage;
+pub mod decode;
 pub mod ec;
+pub mod encoder;
+pub mod grade;
+pub mod halftone;
+pub mod inspect;
+mod jis;
 pub mod optimize;
+pub mod packed;
+pub mod payloads;
+pub mod render;
+#[cfg(feature = "std")]
+pub mod sample;
+#[cfg(feature = "serde")]
+mod serde_impl;
 pub mod spec;
+pub mod storage;
+pub mod stream;
 pub mod types;
 
+use cast::As;
+pub use encoder::Encoder;
+pub use packed::PackedQrCode;
 use spec::QrSpec;
-pub use types::{Color, EcLevel, QrResult, Version};
+pub use storage::QrStorage;
+pub use types::{Color, EcLevel, ParseError, QrError, QrResult, Version};
+
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
 
 use heapless::Vec;
 
+use crate::storage::Buffer;
+
+/// The version of the binary format written by `QrCode::to_bytes`.
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+
+/// The header of the binary format: format version, QR code version and error
+/// correction level, one byte each.
+const BINARY_HEADER_LEN: usize = 3;
+
 /// The encoded QR code symbol.
-#[derive(Clone)]
 pub struct QrCode<V: QrSpec> {
-    content: Vec<u8, V::ColorSize>,
+    content: Buffer<V::ColorSize>,
+}
+
+// Implemented by hand, as deriving would require `V` itself to implement them.
+impl<V: QrSpec> Clone for QrCode<V> {
+    fn clone(&self) -> Self {
+        Self { content: self.content.clone() }
+    }
+}
+
+impl<V: QrSpec> PartialEq for QrCode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.content == other.content
+    }
+}
+
+impl<V: QrSpec> Eq for QrCode<V> {}
+
+impl<V: QrSpec> Hash for QrCode<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content.hash(state);
+    }
+}
+
+/// Encodes the data like `QrCode::new`.
+///
+///     use core::convert::TryInto;
+///     use qrcode::{QrCode, QrResult};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     fn encode(text: &str) -> QrResult<QrCode<Version1<EcLevelM>>> {
+///         Ok(text.try_into()?)
+///     }
+///     assert!(encode("hello").is_ok());
+impl<V: QrSpec> TryFrom<&[u8]> for QrCode<V> {
+    type Error = QrError;
+
+    fn try_from(data: &[u8]) -> QrResult<Self> {
+        Self::new(data)
+    }
+}
+
+/// Encodes the UTF-8 bytes of the string like `QrCode::new`.
+impl<V: QrSpec> TryFrom<&str> for QrCode<V> {
+    type Error = QrError;
+
+    fn try_from(data: &str) -> QrResult<Self> {
+        Self::new(data)
+    }
+}
+
+/// Formats the modules row by row, `#` for dark and `.` for light modules.
+struct DebugModules<'a, V: QrSpec>(&'a QrCode<V>);
+
+impl<V: QrSpec> fmt::Debug for DebugModules<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Row<'a>(&'a [Color]);
+        impl fmt::Debug for Row<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_char('"')?;
+                for color in self.0 {
+                    f.write_char(color.select('#', '.'))?;
+                }
+                f.write_char('"')
+            }
+        }
+
+        let mut list = f.debug_list();
+        let mut row = [Color::Light; 177];
+        let mut colors = self.0.colors();
+        for _ in 0..V::WIDTH {
+            for module in &mut row[..V::WIDTH as usize] {
+                *module = colors.next().unwrap_or(Color::Light);
+            }
+            list.entry(&Row(&row[..V::WIDTH as usize]));
+        }
+        list.finish()
+    }
+}
+
+impl<V: QrSpec> fmt::Debug for QrCode<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QrCode")
+            .field("version", &V::VERSION)
+            .field("ec_level", &V::EC_LEVEL)
+            .field("modules", &DebugModules(self))
+            .finish()
+    }
 }
 
 impl<V: QrSpec> QrCode<V> {
@@ -66,6 +243,46 @@ impl<V: QrSpec> QrCode<V> {
         Self::with_bits(bits)
     }
 
+    /// Constructs a new QR code for text, choosing the modes and character
+    /// set with `Bits::push_str`: kanji mode for Japanese, no ECI designator
+    /// for text in ISO-8859-1, and UTF-8 after an ECI designator for anything
+    /// else, or where it takes fewer bits including the designator.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version2, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version2<EcLevelM>>::encode_str("価格: 1500円").unwrap();
+    ///
+    /// Unlike `QrCode::new` and `TryFrom<&str>`, which encode the UTF-8 bytes
+    /// as they are, this never mistakes the bytes of multi-byte characters
+    /// for Shift JIS, and marks UTF-8 text as such for scanners. For scanners
+    /// which assume UTF-8 without the ECI designator, see
+    /// `Encoder::byte_charset`.
+    pub fn encode_str(text: &str) -> QrResult<Self> {
+        let mut bits = bits::Bits::new();
+        bits.push_str(text)?;
+        bits.push_terminator()?;
+        Self::with_bits(bits)
+    }
+
+    /// Constructs a new QR code from a string of digits, such as a serial
+    /// number. The digits are encoded in numeric mode directly, skipping the
+    /// search for the optimal segments done by `QrCode::new`.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new_numeric(b"0123456789").unwrap();
+    ///
+    /// Returns `Err(QrError::InvalidCharacter)` if the data contains anything
+    /// but the digits 0 to 9.
+    pub fn new_numeric<D: AsRef<[u8]>>(digits: D) -> QrResult<Self> {
+        let mut bits = bits::Bits::new();
+        bits.push_numeric_data(digits.as_ref())?;
+        bits.push_terminator()?;
+        Self::with_bits(bits)
+    }
+
     /// Constructs a new QR code with encoded bits.
     ///
     /// Use this method only if there are very special need to manipulate the
@@ -89,30 +306,342 @@ impl<V: QrSpec> QrCode<V> {
     ///     bits.push_terminator();
     ///     let qrcode = QrCode::with_bits(bits);
     ///
+    /// Returns `Err(QrError::InvalidDataLength)` if the bits were not
+    /// terminated with `Bits::push_terminator`. `Bits::into_qr` terminates
+    /// the bits and calls this method in one step.
     pub fn with_bits(bits: bits::Bits<V>) -> QrResult<Self> {
+        Self::with_bits_and_mask_strategy(bits, canvas::MaskStrategy::Penalty)
+    }
+
+    /// Constructs a new QR code like `QrCode::new`, but chooses the mask
+    /// pattern with `strategy` instead of the lowest penalty score. See
+    /// `canvas::MaskStrategy`.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::canvas::MaskStrategy;
+    ///     use qrcode::spec::{Version2, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version2<EcLevelM>>::with_mask_strategy(b"Hello", MaskStrategy::Balanced).unwrap();
+    ///     let report = code.inspect().unwrap();
+    ///     assert!((report.dark_ratio() - 0.5).abs() < 0.05);
+    pub fn with_mask_strategy<D: AsRef<[u8]>>(data: D, strategy: canvas::MaskStrategy) -> QrResult<Self> {
+        let mut bits = bits::Bits::new();
+        bits.push_optimal_data(data.as_ref())?;
+        bits.push_terminator()?;
+        Self::with_bits_and_mask_strategy(bits, strategy)
+    }
+
+    /// Constructs a new QR code with encoded bits like `QrCode::with_bits`,
+    /// choosing the mask pattern with `strategy`.
+    ///
+    /// Returns `Err(QrError::UnsupportedMask)` if a fixed pattern is not valid
+    /// for the version.
+    pub fn with_bits_and_mask_strategy(bits: bits::Bits<V>, strategy: canvas::MaskStrategy) -> QrResult<Self> {
         let data = bits.into_bytes();
-        let (data_ec, data_end) = ec::construct_codewords::<V>(&*data)?;
+        let mut scratch = canvas::ColorMatrix::empty();
         let mut canvas = canvas::Canvas::<V>::new();
         canvas.draw_all_functional_patterns();
-        canvas.draw_data(&data_ec[..data_end], &data_ec[data_end..]);
-        let canvas = canvas.apply_best_mask();
-        let content = canvas.color_line_bits();
+        canvas.draw_rawbits(&data, &mut scratch)?;
+        canvas.apply_mask(canvas.select_mask_in(strategy, &mut scratch))?;
+        let content = canvas::pack_color_lines::<V>(canvas.colors());
         Ok(Self { content })
     }
 
+    /// Constructs a new QR code like `QrCode::new`, but inside `storage`
+    /// instead of on the stack.
+    ///
+    ///     use qrcode::{QrCode, QrStorage};
+    ///     use qrcode::spec::{Version1, EcLevelL};
+    ///
+    ///     let mut storage = QrStorage::<Version1<EcLevelL>>::new();
+    ///     let code = QrCode::new_in(b"Some data", &mut storage).unwrap();
+    ///
+    /// The storage can be reused for further codes, which replace the previous
+    /// one.
+    pub fn new_in<D: AsRef<[u8]>>(data: D, storage: &mut QrStorage<V>) -> QrResult<&QrCode<V>> {
+        storage.bits.clear();
+        storage.bits.push_optimal_data(data.as_ref())?;
+        storage.bits.push_terminator()?;
+        let QrStorage { bits, canvas, scratch, code } = storage;
+        Self::encode_in(bits.bytes(), None, canvas, scratch, code)
+    }
+
+    /// Constructs a new QR code with encoded bits like `QrCode::with_bits`,
+    /// but inside `storage` instead of on the stack.
+    pub fn with_bits_in<'s>(bits: &bits::Bits<V>, storage: &'s mut QrStorage<V>) -> QrResult<&'s QrCode<V>> {
+        let QrStorage { canvas, scratch, code, .. } = storage;
+        Self::encode_in(bits.bytes(), None, canvas, scratch, code)
+    }
+
+    /// Encodes the data codewords `data` into `code`, using `canvas` and
+    /// `scratch` as working memory. The functional patterns are copied from
+    /// `template` if given, or drawn otherwise.
+    pub(crate) fn encode_in<'s>(
+        data: &[u8],
+        template: Option<&canvas::Canvas<V>>,
+        canvas: &mut canvas::Canvas<V>,
+        scratch: &mut canvas::ColorMatrix<V>,
+        code: &'s mut QrCode<V>,
+    ) -> QrResult<&'s QrCode<V>> {
+        match template {
+            Some(template) => canvas.copy_from(template),
+            None => {
+                canvas.reset();
+                canvas.draw_all_functional_patterns();
+            }
+        }
+        canvas.draw_rawbits(data, scratch)?;
+        canvas.apply_mask(canvas.best_mask(scratch))?;
+        canvas::pack_color_lines_into::<V>(canvas.colors(), &mut code.content);
+        Ok(code)
+    }
+
     /// Gets the maximum number of allowed erratic modules can be introduced
     /// before the data becomes corrupted. Note that errors should not be
     /// introduced to functional modules.
     pub fn max_allowed_errors(&self) -> usize {
-        ec::max_allowed_errors::<V>().expect("invalid version or ec_level")
+        // Never fails, as `V` is a valid spec.
+        ec::max_allowed_errors::<V>().unwrap_or(0)
+    }
+
+    /// Returns a copy of the symbol with the data modules at the coordinates
+    /// `(x, y)` flipped, and reports how much of the error correction
+    /// capacity the damage uses. Functional modules are left intact. See the
+    /// `damage` module.
+    ///
+    /// Returns `Err(QrError::OutOfBounds)` if any coordinates are outside of
+    /// the symbol.
+    pub fn damage<I: IntoIterator<Item = (usize, usize)>>(&self, coords: I) -> QrResult<(Self, damage::Damage)> {
+        damage::damage(self, coords)
     }
 
     /// Checks whether a module at coordinate (x, y) is a functional module or
     /// not.
-    pub fn is_functional(&self, x: usize, y: usize) -> bool {
-        let x = x as i16;
-        let y = y as i16;
-        canvas::is_functional(V::VERSION, V::WIDTH, x, y)
+    ///
+    /// Returns `Err(QrError::OutOfBounds)` if the coordinates are outside of
+    /// the symbol.
+    pub fn is_functional(&self, x: usize, y: usize) -> QrResult<bool> {
+        if x >= V::WIDTH.as_usize() || y >= V::WIDTH.as_usize() {
+            return Err(QrError::OutOfBounds);
+        }
+        canvas::is_functional(V::VERSION, V::WIDTH, x.as_i16(), y.as_i16())
+    }
+
+    /// Gets the color of the module at coordinate (x, y). Negative coordinates
+    /// count from the right or bottom edge.
+    pub(crate) fn get(&self, x: i16, y: i16) -> Color {
+        let x = if x < 0 { x + V::WIDTH } else { x }.as_usize();
+        let y = if y < 0 { y + V::WIDTH } else { y }.as_usize();
+        let width = V::WIDTH.as_usize();
+        let row_byte_width = width.div_ceil(8);
+        let bits_in_byte = (width - x / 8 * 8).min(8);
+        let byte = self.content[y * row_byte_width + x / 8];
+        Color::from_bit(byte >> (bits_in_byte - 1 - x % 8))
+    }
+
+    /// Re-reads the finished symbol and checks that it is internally
+    /// consistent: both copies of the format information match the error
+    /// correction level, the version information (for version 7 and up)
+    /// matches the version, the timing patterns are intact, and every
+    /// Reed-Solomon block read back from the data modules is free of errors.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     assert!(code.verify().is_ok());
+    ///
+    /// This is useful after post-processing the symbol. Returns
+    /// `Err(QrError::InvalidSymbol)` if any check fails.
+    pub fn verify(&self) -> QrResult<()> {
+        let get = |x, y| self.get(x, y);
+        let pattern = self.verify_format_info()?;
+
+        if !canvas::check_timing_patterns(&get, V::WIDTH) || get(8, -8) != Color::Dark {
+            return Err(QrError::InvalidSymbol);
+        }
+
+        let layout = V::BLOCK_LAYOUT;
+        let mut codewords: Vec<u8, V::TotalSize> = Vec::new();
+        codewords.resize(layout.total_len(), 0).map_err(|()| QrError::InvalidSymbol)?;
+        canvas::read_codewords(&get, V::VERSION, pattern, &mut codewords);
+
+        let mut block: Vec<u8, V::ECGenBufferSize> = Vec::new();
+        block
+            .resize(layout.block_1_size.max(layout.block_2_size) + layout.ec_bytes_per_block, 0)
+            .map_err(|()| QrError::InvalidSymbol)?;
+        let mut syndromes = [0; 30];
+        for (b, size) in layout.block_sizes().enumerate() {
+            let block_len = size + layout.ec_bytes_per_block;
+            for (i, codeword) in block[..block_len].iter_mut().enumerate() {
+                *codeword = codewords[layout.interleaved_index(b, i)];
+            }
+            if ec::compute_syndromes(&block[..block_len], &mut syndromes[..layout.ec_bytes_per_block]) {
+                return Err(QrError::InvalidSymbol);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the format information and, for version 7 and up, the version
+    /// information: both copies must be intact and agree with `V`. Returns
+    /// the mask pattern of the symbol.
+    ///
+    /// This is the part of `verify` which holds for any content, such as for
+    /// symbols wrapped with `from_colors`. Returns `Err(QrError::InvalidSymbol)`
+    /// if any check fails.
+    pub fn verify_format_info(&self) -> QrResult<canvas::MaskPattern> {
+        let get = |x, y| self.get(x, y);
+
+        let (main, side) = canvas::read_format_info_qr(&get);
+        let format_data =
+            canvas::decode_format_info(main, canvas::FORMAT_INFO_MASK_QR).ok_or(QrError::InvalidSymbol)?;
+        let expected_format_info = canvas::encode_format_info(format_data, canvas::FORMAT_INFO_MASK_QR);
+        if main != expected_format_info || side != expected_format_info || format_data >> 3 != (V::EC_LEVEL as u8 ^ 1) {
+            return Err(QrError::InvalidSymbol);
+        }
+        let pattern = canvas::MaskPattern::from_bits(format_data & 0b111).ok_or(QrError::InvalidSymbol)?;
+
+        if let Version::Normal(v @ 7..=40) = V::VERSION {
+            let expected_version_info = canvas::encode_version_info(v as u8);
+            if canvas::read_version_info(&get) != (expected_version_info, expected_version_info) {
+                return Err(QrError::InvalidSymbol);
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// The number of bytes written by `to_bytes`.
+    pub const fn binary_len() -> usize {
+        BINARY_HEADER_LEN + V::AREA.div_ceil(8)
+    }
+
+    /// Writes the finished symbol into `out` in a compact binary format, and
+    /// returns the number of bytes written.
+    ///
+    /// The format starts with a 3-byte header (`BINARY_FORMAT_VERSION`, the
+    /// version number, with the high bit set for Micro QR codes, and the error
+    /// correction level), followed by the modules
+    /// in row-major order, 8 per byte, most significant bit first.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let mut bytes = [0; QrCode::<Version1<EcLevelM>>::binary_len()];
+    ///     assert_eq!(code.to_bytes(&mut bytes), Ok(59));
+    ///
+    ///     let restored = QrCode::<Version1<EcLevelM>>::from_bytes(&bytes).unwrap();
+    ///     assert!(restored.colors().eq(code.colors()));
+    ///
+    /// Returns `Err(QrError::BufferTooSmall)` if `out` is shorter than
+    /// `binary_len()`.
+    pub fn to_bytes(&self, out: &mut [u8]) -> QrResult<usize> {
+        let out = out.get_mut(..Self::binary_len()).ok_or(QrError::BufferTooSmall)?;
+        let (header, modules) = out.split_at_mut(BINARY_HEADER_LEN);
+        let version = match V::VERSION {
+            Version::Normal(v) => v as u8,
+            Version::Micro(v) => 0x80 | v as u8,
+        };
+        header.copy_from_slice(&[BINARY_FORMAT_VERSION, version, V::EC_LEVEL as u8]);
+        modules.iter_mut().for_each(|b| *b = 0);
+        for (i, color) in self.colors().enumerate() {
+            if color == Color::Dark {
+                modules[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        Ok(Self::binary_len())
+    }
+
+    /// Reads a symbol written by `to_bytes`.
+    ///
+    /// Returns `Err(QrError::InvalidVersion { .. })` with the stored version
+    /// and error correction level if they differ from `V`, and
+    /// `Err(QrError::InvalidSymbol)` if the data is malformed. The modules
+    /// themselves are not checked, see `verify` for that.
+    pub fn from_bytes(bytes: &[u8]) -> QrResult<Self> {
+        if bytes.len() != Self::binary_len() || bytes[0] != BINARY_FORMAT_VERSION {
+            return Err(QrError::InvalidSymbol);
+        }
+        let version = match bytes[1] {
+            v @ 0x80..=0xff => Version::Micro(i16::from(v & 0x7f)),
+            v => Version::Normal(i16::from(v)),
+        };
+        let ec_level = match bytes[2] {
+            0 => EcLevel::L,
+            1 => EcLevel::M,
+            2 => EcLevel::Q,
+            3 => EcLevel::H,
+            _ => return Err(QrError::InvalidSymbol),
+        };
+        if version != V::VERSION || ec_level != V::EC_LEVEL {
+            return Err(QrError::InvalidVersion { version, ec_level });
+        }
+
+        let modules = &bytes[BINARY_HEADER_LEN..];
+        let colors = (0..V::AREA).map(|i| Color::from_bit(modules[i / 8] >> (7 - i % 8)));
+        Ok(Self { content: canvas::pack_color_lines::<V>(colors) })
+    }
+
+    /// Wraps a matrix of modules, given in row-major order without a quiet
+    /// zone, such as a symbol loaded from storage or read by a scanner, so it
+    /// can be rendered like an encoded one.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let colors: Vec<_> = code.colors().take(21 * 21).collect();
+    ///
+    ///     let loaded = QrCode::<Version1<EcLevelM>>::from_colors(&colors).unwrap();
+    ///     assert!(loaded.verify_format_info().is_ok());
+    ///     assert!(loaded == code);
+    ///
+    /// Returns `Err(QrError::InvalidWidth(_))` if the matrix does not have
+    /// the width of `V`. The modules themselves are not checked; see
+    /// `verify_format_info` and `verify` for that.
+    pub fn from_colors(colors: &[Color]) -> QrResult<Self> {
+        if colors.len() != V::AREA {
+            let width = (0..=177).find(|w| w * w >= colors.len()).unwrap_or(0);
+            return Err(QrError::InvalidWidth(width));
+        }
+        Ok(Self { content: canvas::pack_color_lines::<V>(colors.iter().copied()) })
+    }
+
+    /// Reports how the symbol was encoded: its mask pattern, segments,
+    /// codeword counts, penalty scores and dark modules. See
+    /// `inspect::Report`.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version2, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version2<EcLevelM>>::new(b"Why is my code version 2?").unwrap();
+    ///     let report = code.inspect().unwrap();
+    ///     println!("{} of {} data bits used", report.data_bits, report.data_codewords * 8);
+    ///
+    /// Like the decoder, this returns `Err(QrError::InvalidSymbol)` or
+    /// `Err(QrError::Uncorrectable)` if the modules were damaged beyond
+    /// repair.
+    pub fn inspect(&self) -> QrResult<inspect::Report> {
+        inspect::inspect(self, |_| {})
+    }
+
+    /// Reports on the symbol like `inspect`, additionally calling `on_segment`
+    /// for every segment found in the data. The segment bounds count the
+    /// decoded bytes, as in `decode::parse_segments`.
+    pub fn inspect_with_segments<S: FnMut(decode::Segment)>(&self, on_segment: S) -> QrResult<inspect::Report> {
+        inspect::inspect(self, on_segment)
+    }
+
+    /// Reads the format information bit by bit: the raw value of both copies,
+    /// the error correction level and mask pattern they decode to, and the
+    /// coordinates of every bit. See `inspect::FormatBits`.
+    ///
+    /// Unlike `verify_format_info`, this never fails, so that damaged or
+    /// tampered symbols can be examined too.
+    pub fn format_bits(&self) -> inspect::FormatBits {
+        inspect::format_bits(self)
     }
 
     /// Converts the QR code into a human-readable string. This is mainly for
@@ -132,6 +661,38 @@ impl<V: QrSpec> QrCode<V> {
         buffer
     }
 
+    /// Iterates the modules in row-major order, mapped to the pixel type `P`.
+    pub fn pixels<'a, P: render::Pixel + 'a>(&'a self) -> impl Iterator<Item = P> + 'a {
+        self.colors().map(P::from_color)
+    }
+
+    /// Iterates the pixels of the image of the code in row-major order, every
+    /// module `scale` pixels wide and high, surrounded by a light quiet zone
+    /// `quiet_zone` modules wide, mapped to the pixel type `P`.
+    ///
+    /// Nothing is buffered, so a display driver can feed the pixels straight
+    /// into an SPI or DMA transfer:
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let pixels = code.scaled_pixels::<u8>(3, 4);
+    ///     assert_eq!(pixels.len(), (29 * 3) * (29 * 3));
+    ///
+    ///     let row: Vec<u8> = code.scaled_pixels(3, 4).skip(12 * 87).take(87).collect();
+    ///     assert_eq!(&row[10..16], &[255, 255, 0, 0, 0, 0]);
+    ///
+    /// The image is `(V::WIDTH + 2 * quiet_zone) * scale` pixels wide, and
+    /// empty for a scale of 0. See also `render::Layout::pixels`.
+    pub fn scaled_pixels<'a, P: render::Pixel + 'a>(
+        &'a self,
+        scale: u32,
+        quiet_zone: u32,
+    ) -> impl ExactSizeIterator<Item = P> + 'a {
+        render::Layout { quiet_zone, module_size: scale }.pixels(self)
+    }
+
     /// Converts the QR code to a vector of colors.
     pub fn colors(&self) -> impl Iterator<Item = Color> + '_ {
         struct BitIter(u8, u8);
@@ -147,7 +708,7 @@ impl<V: QrSpec> QrCode<V> {
                 }
             }
         }
-        let row_byte_width = (V::WIDTH as usize + 7) / 8;
+        let row_byte_width = (V::WIDTH as usize).div_ceil(8);
         let last_byte_empty_bits = row_byte_width * 8 - V::WIDTH as usize;
 
         self.content
@@ -163,6 +724,87 @@ impl<V: QrSpec> QrCode<V> {
             .map(Color::from_bit)
     }
 
+    /// Writes the modules in row-major order into `out`, without the
+    /// iterator overhead of `colors`, for redrawing a display every frame.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::types::Color;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let mut frame = [Color::Light; 21 * 21];
+    ///     assert_eq!(code.to_colors_into(&mut frame), Ok(21 * 21));
+    ///     assert_eq!(frame[0], Color::Dark);
+    ///
+    /// Returns the number of modules written, or
+    /// `Err(QrError::BufferTooSmall)` if `out` is shorter than `V::AREA`.
+    /// The rest of `out` is left as is.
+    pub fn to_colors_into(&self, out: &mut [Color]) -> QrResult<usize> {
+        self.to_pixels_into(out)
+    }
+
+    /// Writes the modules like `to_colors_into`, `true` for dark modules.
+    pub fn to_bools_into(&self, out: &mut [bool]) -> QrResult<usize> {
+        self.to_pixels_into(out)
+    }
+
+    /// Copies the modules into a matrix indexed by `[y, x]`, `true` for dark
+    /// modules, for use in data analysis and computer vision pipelines.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let matrix = code.to_ndarray();
+    ///     assert_eq!(matrix.dim(), (21, 21));
+    ///     assert!(matrix[[0, 0]] && !matrix[[7, 0]]);
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<bool> {
+        let width = V::WIDTH.as_usize();
+        ndarray::Array2::from_shape_fn((width, width), |(y, x)| self.get(x.as_i16(), y.as_i16()).into())
+    }
+
+    fn to_pixels_into<P: render::Pixel>(&self, out: &mut [P]) -> QrResult<usize> {
+        let out = out.get_mut(..V::AREA).ok_or(QrError::BufferTooSmall)?;
+        let width = V::WIDTH.as_usize();
+        for (y, row) in out.chunks_exact_mut(width).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = P::from_color(self.get(x.as_i16(), y.as_i16()));
+            }
+        }
+        Ok(V::AREA)
+    }
+
+    /// Iterates the modules in row-major order like `colors`, surrounded by a
+    /// light quiet zone `quiet_zone` modules wide, so that the output can be
+    /// copied into an image as is. `V::VERSION.quiet_zone()` is the width the
+    /// standard asks for.
+    ///
+    ///     use qrcode::QrCode;
+    ///     use qrcode::spec::{QrSpec, Version1, EcLevelM};
+    ///
+    ///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+    ///     let quiet_zone = Version1::<EcLevelM>::VERSION.quiet_zone();
+    ///     assert_eq!(code.colors_with_quiet_zone(quiet_zone).count(), 29 * 29);
+    pub fn colors_with_quiet_zone(&self, quiet_zone: u32) -> impl Iterator<Item = Color> + '_ {
+        self.rows_with_quiet_zone(quiet_zone).flatten()
+    }
+
+    /// Iterates the rows of modules from top to bottom, each from left to
+    /// right.
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Color> + '_> + '_ {
+        self.rows_with_quiet_zone(0)
+    }
+
+    /// Iterates the rows of modules like `rows`, surrounded by a light quiet
+    /// zone `quiet_zone` modules wide. The rows of the quiet zone above and
+    /// below the symbol are included.
+    pub fn rows_with_quiet_zone(&self, quiet_zone: u32) -> impl Iterator<Item = impl Iterator<Item = Color> + '_> + '_ {
+        let layout = render::Layout { quiet_zone, module_size: 1 };
+        let size = layout.modules(V::WIDTH);
+        (0..size).map(move |y| (0..size).map(move |x| layout.color(self, x, y)))
+    }
+
     // /// Converts the QR code to a vector of colors.
     // pub fn colors(self) -> Vec<Color, V::ColorSize> {
     //     self.content
@@ -171,8 +813,10 @@ impl<V: QrSpec> QrCode<V> {
 
 #[cfg(test)]
 mod tests {
-    use crate::spec::{EcLevelM, Version1};
-    use crate::QrCode;
+    use crate::bits::Bits;
+    use crate::spec::{EcLevelH, EcLevelM, EcLevelQ, Version1, Version7};
+    use crate::types::{Color, Version};
+    use crate::{QrCode, QrError, QrStorage};
 
     #[test]
     fn test_annex_i_qr() {
@@ -205,6 +849,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_new_numeric() {
+        let code = QrCode::<Version1<EcLevelM>>::new_numeric(b"01234567").unwrap();
+        assert!(code == QrCode::new(b"01234567").unwrap());
+        let digits = [b'9'; 34];
+        assert!(QrCode::<Version1<EcLevelM>>::new_numeric(digits).unwrap() == QrCode::new(digits).unwrap());
+
+        assert_eq!(
+            QrCode::<Version1<EcLevelM>>::new_numeric(b"0123-4567").err(),
+            Some(QrError::InvalidCharacter { index: 4, byte: b'-' })
+        );
+        assert!(matches!(QrCode::<Version1<EcLevelM>>::new_numeric([b'1'; 35]), Err(QrError::DataTooLong { .. })));
+    }
+
+    #[test]
+    fn test_encode_str() {
+        type V = Version7<EcLevelQ>;
+        let code = QrCode::<V>::encode_str("Hello, world! 0123456789").unwrap();
+        assert!(code == QrCode::new("Hello, world! 0123456789").unwrap());
+
+        let text = "日本語のテキスト 👍";
+        let code = QrCode::<V>::encode_str(text).unwrap();
+        let modules: std::vec::Vec<Color> = code.colors().take(45 * 45).collect();
+        let mut out = [0; 64];
+        let decoded = crate::decode::decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], text.as_bytes());
+    }
+
+    #[test]
+    fn test_unterminated_bits() {
+        let mut bits = Bits::<Version1<EcLevelM>>::new();
+        bits.push_numeric_data(b"01234567").unwrap();
+        let err = QrError::InvalidDataLength { len: 6, expected: 16 };
+        assert_eq!(QrCode::with_bits_in(&bits, &mut QrStorage::new()).err(), Some(err));
+        assert_eq!(QrCode::with_bits(bits).err(), Some(err));
+    }
+
+    #[test]
+    fn test_rows_with_quiet_zone() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let rows: Vec<Vec<Color>> = code.rows().map(Iterator::collect).collect();
+        assert_eq!(rows.len(), 21);
+        assert!(rows.iter().flatten().copied().eq(code.colors().take(21 * 21)));
+
+        let framed: Vec<Vec<Color>> = code.rows_with_quiet_zone(4).map(Iterator::collect).collect();
+        assert_eq!(framed.len(), 29);
+        for (y, row) in framed.iter().enumerate() {
+            assert_eq!(row.len(), 29);
+            for (x, &color) in row.iter().enumerate() {
+                let inside = (4..25).contains(&x) && (4..25).contains(&y);
+                assert_eq!(color, if inside { rows[y - 4][x - 4] } else { Color::Light });
+            }
+        }
+        assert!(code.colors_with_quiet_zone(4).eq(framed.into_iter().flatten()));
+        assert_eq!(Version::Normal(1).quiet_zone(), 4);
+        assert_eq!(Version::Micro(2).quiet_zone(), 2);
+    }
+
+    #[test]
+    fn test_to_colors_into() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut colors = [Color::Dark; 21 * 21 + 1];
+        assert_eq!(code.to_colors_into(&mut colors), Ok(21 * 21));
+        assert!(colors[..21 * 21].iter().copied().eq(code.colors().take(21 * 21)));
+        assert_eq!(colors[21 * 21], Color::Dark);
+
+        let mut bools = [false; 21 * 21];
+        assert_eq!(code.to_bools_into(&mut bools), Ok(21 * 21));
+        assert!(bools.iter().zip(&colors).all(|(&dark, &color)| dark == (color == Color::Dark)));
+
+        assert_eq!(code.to_colors_into(&mut colors[..21 * 21 - 1]), Err(QrError::BufferTooSmall));
+        assert_eq!(code.to_bools_into(&mut []), Err(QrError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_from_colors() {
+        let code = QrCode::<Version7<EcLevelQ>>::new(b"from colors").unwrap();
+        let mut colors: Vec<Color> = code.colors().take(45 * 45).collect();
+        let loaded = QrCode::<Version7<EcLevelQ>>::from_colors(&colors).unwrap();
+        assert!(loaded == code);
+        assert_eq!(loaded.verify_format_info(), code.inspect().map(|report| report.mask));
+        assert!(QrCode::<Version7<EcLevelH>>::from_colors(&colors).unwrap().verify_format_info().is_err());
+
+        // The version information next to the top right finder pattern.
+        colors[34] = !colors[34];
+        let damaged = QrCode::<Version7<EcLevelQ>>::from_colors(&colors).unwrap();
+        assert_eq!(damaged.verify_format_info(), Err(QrError::InvalidSymbol));
+
+        assert_eq!(QrCode::<Version7<EcLevelQ>>::from_colors(&colors[1..]).err(), Some(QrError::InvalidWidth(45)));
+        assert_eq!(QrCode::<Version7<EcLevelQ>>::from_colors(&colors[..41 * 41]).err(), Some(QrError::InvalidWidth(41)));
+    }
+
     // #[test]
     // fn test_annex_i_micro_qr() {
     //     let code = QrCode::with_version(b"01234567", Version::Micro(2), EcLevel::L).unwrap();
@@ -227,3 +963,510 @@ mod tests {
     //     );
     // }
 }
+
+#[cfg(test)]
+mod trait_tests {
+    use crate::spec::{EcLevelL, EcLevelM, Version1};
+    use crate::{QrCode, QrError};
+    use core::convert::TryFrom;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_eq_and_hash() {
+        let a = QrCode::<Version1<EcLevelM>>::new(b"a").unwrap();
+        let b = QrCode::<Version1<EcLevelM>>::new(b"b").unwrap();
+        assert_eq!(a, a.clone());
+        assert_ne!(a, b);
+
+        let set: HashSet<_> = vec![a.clone(), b, a].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_try_from() {
+        use core::convert::TryInto;
+        let from_str: QrCode<Version1<EcLevelM>> = "a".try_into().unwrap();
+        let from_bytes: QrCode<Version1<EcLevelM>> = (&b"a"[..]).try_into().unwrap();
+        assert_eq!(from_str, from_bytes);
+        assert_eq!(
+            QrCode::<Version1<EcLevelM>>::try_from("this is too long to fit a version 1 symbol"),
+            Err(QrError::DataTooLong { needed: 348, available: 128 })
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let code = QrCode::<Version1<EcLevelL>>::new(b"01234567").unwrap();
+        let debug = format!("{:?}", code);
+        assert!(debug.starts_with("QrCode { version: Normal(1), ec_level: L, modules: [\"#######."));
+        let pretty = format!("{:#?}", code);
+        assert_eq!(pretty.lines().filter(|line| line.trim_start().starts_with('"')).count(), 21);
+    }
+}
+
+#[cfg(test)]
+mod binary_tests {
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, Version1, Version40};
+    use crate::types::{EcLevel, QrError, Version};
+    use crate::{QrCode, BINARY_FORMAT_VERSION};
+
+    #[test]
+    fn test_round_trip() {
+        type Code = QrCode<Version40<EcLevelL>>;
+        let code = Code::new(&[0x5a; 1000][..]).unwrap();
+        let mut bytes = vec![0; Code::binary_len()];
+        assert_eq!(code.to_bytes(&mut bytes), Ok(3 + 3917));
+        assert_eq!(&bytes[..3], &[BINARY_FORMAT_VERSION, 40, 0]);
+        let restored = Code::from_bytes(&bytes).unwrap();
+        assert!(restored.colors().eq(code.colors()));
+        assert_eq!(restored.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid() {
+        type Code = QrCode<Version1<EcLevelH>>;
+        let code = Code::new(b"abc").unwrap();
+        let mut bytes = [0; 59];
+        assert_eq!(code.to_bytes(&mut bytes[..58]), Err(QrError::BufferTooSmall));
+        code.to_bytes(&mut bytes).unwrap();
+
+        assert_eq!(Code::from_bytes(&bytes[..58]).err(), Some(QrError::InvalidSymbol));
+        let mut other_format = bytes;
+        other_format[0] = BINARY_FORMAT_VERSION + 1;
+        assert_eq!(Code::from_bytes(&other_format).err(), Some(QrError::InvalidSymbol));
+        assert_eq!(
+            QrCode::<Version1<EcLevelM>>::from_bytes(&bytes).err(),
+            Some(QrError::InvalidVersion { version: Version::Normal(1), ec_level: EcLevel::H })
+        );
+    }
+}
+
+#[cfg(test)]
+mod verify_tests {
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, EcLevelQ, Version1, Version10, Version40, Version7};
+    use crate::types::QrError;
+    use crate::QrCode;
+
+    #[test]
+    fn test_valid_symbols() {
+        assert_eq!(QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap().verify(), Ok(()));
+        assert_eq!(QrCode::<Version7<EcLevelQ>>::new(b"Hello, world!").unwrap().verify(), Ok(()));
+        assert_eq!(QrCode::<Version10<EcLevelH>>::new(b"ABCDEFGHIJ0123456789").unwrap().verify(), Ok(()));
+        assert_eq!(QrCode::<Version40<EcLevelL>>::new(b"\x00\xff\x80").unwrap().verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_damaged_data() {
+        let mut code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        // Module (20, 20), the first data module.
+        code.content[20 * 3 + 2] ^= 0b1;
+        assert_eq!(code.verify(), Err(QrError::InvalidSymbol));
+    }
+
+    #[test]
+    fn test_damaged_format_info() {
+        let mut code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        // Module (0, 8), part of the main format information copy.
+        code.content[8 * 3] ^= 0b1000_0000;
+        assert_eq!(code.verify(), Err(QrError::InvalidSymbol));
+    }
+
+    #[test]
+    fn test_damaged_version_info() {
+        let mut code = QrCode::<Version7<EcLevelQ>>::new(b"Hello, world!").unwrap();
+        // Module (0, 34), part of the bottom-left version information copy.
+        code.content[34 * 6] ^= 0b1000_0000;
+        assert_eq!(code.verify(), Err(QrError::InvalidSymbol));
+    }
+}
+
+#[cfg(test)]
+mod panic_free_tests {
+    //! Drives the public API with adversarial inputs for every spec. All
+    //! failures must be reported as a `QrError`, never as a panic.
+
+    use crate::bits::Bits;
+    use crate::canvas::is_functional;
+    use crate::decode::{decode, decode_with};
+    use crate::ec::{compute_syndromes, correct_errors};
+    use crate::halftone::Halftone;
+    use crate::render::{Caption, Dense, Glyphs, Layout, Padding, Svg, Text};
+    use crate::spec::{for_each_spec, BlockLayout, EcLevelH, QrSpec, SpecVisitor, Version1};
+    use crate::types::{Color, EcLevel, QrError, Version};
+    use crate::{Encoder, PackedQrCode, QrCode, QrStorage};
+    use heapless::consts::U64;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    const DIGITS: &[u8] = b"0123456789";
+    const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+    const KANJI: &[u8] = b"\x81\x40\x9f\xfc\xe0\xeb\xbf";
+
+    /// Pseudo-random bytes drawn from `alphabet`.
+    fn random(seed: usize, len: usize, alphabet: &[u8]) -> Vec<u8> {
+        let mut state = (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..len).map(|_| alphabet[(next() % alphabet.len() as u64) as usize]).collect()
+    }
+
+    /// Finds the longest data from `alphabet` which fits into `V`.
+    fn capacity<V: QrSpec>(alphabet: &[u8]) -> usize {
+        let fits = |len| {
+            let mut bits = Bits::<V>::new();
+            bits.push_optimal_data(&random(len, len, alphabet)).and_then(|()| bits.push_terminator()).is_ok()
+        };
+        let (mut fitting, mut too_long) = (0, V::BLOCK_LAYOUT.data_len() * 8 / 3 + 1);
+        while too_long - fitting > 1 {
+            let len = (fitting + too_long) / 2;
+            if fits(len) {
+                fitting = len;
+            } else {
+                too_long = len;
+            }
+        }
+        fitting
+    }
+
+    /// The data to encode: no data at all, and data just filling the symbol
+    /// and one character longer, both from a single mode picked by `index`,
+    /// and mixing modes so the optimizer splits it into many segments.
+    ///
+    /// Encoding dominates the run time, so every spec only gets one of the
+    /// single mode alphabets.
+    fn payloads<V: QrSpec>(index: usize) -> Vec<Vec<u8>> {
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        let alphabets = [DIGITS, ALPHANUMERIC, KANJI, &all_bytes];
+        let mixed = [DIGITS, b"AB $", KANJI, b"xyz"].concat();
+        let mut payloads = vec![Vec::new()];
+        for alphabet in [alphabets[index % alphabets.len()], &mixed] {
+            let capacity = capacity::<V>(alphabet);
+            payloads.extend((capacity..=capacity + 1).map(|len| random(len, len, alphabet)));
+        }
+        payloads
+    }
+
+    fn check_data<V: QrSpec>(data: &[u8], encoder: &mut Encoder<V>) {
+        let code = match encoder.encode(data) {
+            Ok(code) => code.clone(),
+            Err(err) => {
+                assert!(matches!(err, QrError::DataTooLong { .. }), "{:?}", err);
+                assert!(QrCode::<V>::new(data).err() == Some(err));
+                assert!(QrCode::new_in(data, &mut QrStorage::<V>::new()).err() == Some(err));
+                return;
+            }
+        };
+        if data.is_empty() {
+            // An empty numeric segment, rather than no segment at all.
+            encoder.encode_numeric(data).unwrap();
+        } else if data.iter().all(u8::is_ascii_digit) {
+            assert!(QrCode::<V>::new_numeric(data).unwrap() == code);
+        } else {
+            assert!(matches!(encoder.encode_numeric(data), Err(QrError::InvalidCharacter { .. })));
+        }
+
+        let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+        let mut out = vec![0; data.len()];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], data);
+    }
+
+    fn check_bits<V: QrSpec>(data: &[u8], storage: &mut QrStorage<V>) {
+        type Push<V> = fn(&mut Bits<V>, &[u8]) -> Result<(), QrError>;
+        let pushes: [Push<V>; 10] = [
+            |bits, data| bits.push_numeric_data(data),
+            |bits, data| bits.push_alphanumeric_data(data),
+            |bits, data| bits.push_byte_data(data),
+            |bits, data| bits.push_kanji_data(data),
+            |bits, data| bits.push_optimal_data(data),
+            |bits, data| bits.push_str(&String::from_utf8_lossy(data)),
+            |bits, data| bits.push_eci_designator(data.len() as u32 * 4099),
+            |bits, _| bits.push_eci_designator(u32::MAX),
+            |bits, _| bits.push_fnc1_first_position(),
+            |bits, data| bits.push_fnc1_second_position(data.len() as u8),
+        ];
+        let mut all = Bits::<V>::new();
+        for push in &pushes {
+            let _ = push(&mut all, data);
+            let mut bits = Bits::<V>::new();
+            let _ = push(&mut bits, data);
+            let _ = push(&mut bits, data);
+            if bits.len().div_ceil(8) != V::BLOCK_LAYOUT.data_len() {
+                assert!(matches!(QrCode::with_bits_in(&bits, storage), Err(QrError::InvalidDataLength { .. })));
+            }
+            let _ = bits.push_terminator();
+        }
+        let _ = all.push_terminator();
+        let _ = all.push_terminator();
+        let _ = QrCode::with_bits(all);
+    }
+
+    /// Checks symbols with arbitrary modules, as restored by `from_bytes`.
+    fn check_damaged<V: QrSpec>() {
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        assert_eq!(code.verify(), Ok(()));
+        let width = V::WIDTH as usize;
+        assert_eq!(code.is_functional(width - 1, width - 1), Ok(false));
+        for (x, y) in [(width, 0), (0, width), (usize::MAX, usize::MAX), (1 << 16, 1 << 16)] {
+            assert_eq!(code.is_functional(x, y), Err(QrError::OutOfBounds));
+        }
+        Svg::default().write(&code, &mut String::new()).unwrap();
+        let mut out = [0; 64];
+        assert_eq!(
+            decode(&code.colors().take(V::AREA).collect::<Vec<_>>(), &mut out[..7]),
+            Err(QrError::BufferTooSmall)
+        );
+
+        let mut bytes = vec![0; QrCode::<V>::binary_len()];
+        assert_eq!(code.to_bytes(&mut bytes[1..]), Err(QrError::BufferTooSmall));
+        code.to_bytes(&mut bytes).unwrap();
+
+        for header in [[0, 1, 0], [1, 0x81, 0], [1, 41, 3], [1, 1, 4]] {
+            let mut bytes = bytes.clone();
+            bytes[..3].copy_from_slice(&header);
+            assert!(QrCode::<V>::from_bytes(&bytes).is_err());
+        }
+        assert!(QrCode::<V>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        for seed in 0..8 {
+            let mut bytes = bytes.clone();
+            let len = bytes.len() - 3;
+            match seed {
+                0 => bytes[3..].fill(0),
+                1 => bytes[3..].fill(0xff),
+                2 => bytes[3..].iter_mut().for_each(|b| *b = !*b),
+                _ => bytes[3..].copy_from_slice(&random(seed, len, &[0, 1, 0x55, 0xa5, 0xff])),
+            }
+            let damaged = QrCode::<V>::from_bytes(&bytes).unwrap();
+            let _ = damaged.verify();
+            let _ = damaged.inspect();
+            let modules: Vec<Color> = damaged.colors().take(V::AREA).collect();
+            let _ = decode(&modules, &mut out);
+            let _ = decode(&modules, &mut []);
+        }
+
+        // A column through the center of the top left finder pattern, which
+        // is not part of the format information checked by `verify`.
+        let mut modules: Vec<Color> = code.colors().take(V::AREA).collect();
+        for y in 2..5 {
+            modules[y * width + 3] = Color::Light;
+        }
+        let damaged = QrCode::<V>::from_colors(&modules).unwrap();
+        let _ = damaged.verify();
+        let _ = damaged.inspect();
+        assert!(QrCode::<V>::from_colors(&modules[1..]).is_err());
+    }
+
+    /// Checks every spec, collecting the ones which panicked.
+    struct PanicFree {
+        index: usize,
+        panicked: Vec<(Version, EcLevel)>,
+    }
+
+    impl SpecVisitor for PanicFree {
+        fn visit<V: QrSpec>(&mut self) {
+            let index = self.index;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                let mut encoder = Encoder::<V>::new();
+                let mut storage = QrStorage::<V>::new();
+                for data in payloads::<V>(index) {
+                    check_data::<V>(&data, &mut encoder);
+                    check_bits::<V>(&data, &mut storage);
+                }
+                for data in [&b"\x81"[..], b"\x81\x40\xeb", b"0A\x81\x40a"] {
+                    check_bits::<V>(data, &mut storage);
+                }
+                check_damaged::<V>();
+            }));
+            if result.is_err() {
+                self.panicked.push((V::VERSION, V::EC_LEVEL));
+            }
+            self.index += 1;
+        }
+    }
+
+    #[test]
+    fn test_all_specs() {
+        let mut visitor = PanicFree { index: 0, panicked: Vec::new() };
+        for_each_spec(&mut visitor);
+        assert_eq!(visitor.index, 160);
+        assert_eq!(visitor.panicked, []);
+    }
+
+    #[test]
+    fn test_extreme_render_options() {
+        type V = Version1<EcLevelH>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let glyphs = [Glyphs::HalfBlock, Glyphs::Quadrant, Glyphs::Braille, Glyphs::FullBlock, Glyphs::WideBlock];
+        for quiet_zone in [0, 1 << 12, u32::MAX / 2, u32::MAX / 2 + 1, u32::MAX] {
+            for module_size in [0, 1, 1 << 20, u32::MAX] {
+                let layout = Layout { quiet_zone, module_size };
+                let _ = (layout.modules(V::WIDTH), layout.image_size(V::WIDTH));
+                let _ = layout.pixels::<_, Color>(&code).len();
+                let _ = code.scaled_pixels::<Color>(module_size, quiet_zone).len();
+                let caption = Caption { scale: module_size, ..Caption::new("SN 0042") };
+                let _ = (caption.height(), caption.width());
+                let _ = layout.pixels_with_caption::<_, Color>(&code, &caption).len();
+            }
+
+            let padding = Padding { left: u32::MAX, right: u32::MAX, fill: '\u{2588}' };
+            let text = Text { quiet_zone, padding, ..Text::default() };
+            let _ = text.max_len::<V>();
+            assert_eq!(text.to_heapless::<_, U64>(&code), Err(QrError::BufferTooSmall));
+            for &glyphs in &glyphs {
+                let dense = Dense { quiet_zone, glyphs, ..Dense::default() };
+                let _ = dense.size::<V>();
+                let _ = dense.fit::<V>(80, 24);
+            }
+            let mut halftone = Halftone::new(|_, _| 0);
+            halftone.quiet_zone = quiet_zone;
+            let _ = halftone.pixels::<_, Color>(&code).len();
+        }
+    }
+
+    #[test]
+    fn test_error_correction() {
+        let mut syndromes = [0; 80];
+        for len in [0, 1, 26, 70, 255, 300] {
+            for seed in 0..4 {
+                let mut block = random(seed, len, &[0, 1, 0x55, 0xa5, 0xff]);
+                for data_len in [0, 1, len / 2, len.saturating_sub(69), len, len + 1, usize::MAX] {
+                    let _ = correct_errors(&mut block, data_len);
+                }
+                let _ = compute_syndromes(&block, &mut syndromes[..len.min(80)]);
+            }
+        }
+        assert_eq!(correct_errors(&mut [0; 4], 5), Err(QrError::Uncorrectable));
+    }
+
+    #[test]
+    fn test_unchecked_versions() {
+        let modules = [Color::Dark; 177 * 177 + 1];
+        let mut out = [0; 16];
+        for len in [0, 1, 20 * 20, 21 * 21, 25 * 25 - 1, 177 * 177, 177 * 177 + 1] {
+            assert!(decode(&modules[..len], &mut out).is_err());
+        }
+        for width in [0, 1, 21, 22, 177, 181, usize::MAX] {
+            let _ = decode_with(width, |_, _| Color::Light, &mut out);
+        }
+        let versions =
+            [Version::Normal(0), Version::Normal(41), Version::Normal(i16::MIN), Version::Micro(5), Version::Micro(1)];
+        for version in versions {
+            assert!(BlockLayout::fetch(version, EcLevel::H).is_err());
+            assert!(PackedQrCode::try_new(version, EcLevel::H, &[]).is_err());
+            for (width, x, y) in [(0, 0, 0), (11, i16::MIN, i16::MAX), (i16::MAX, i16::MAX, 0)] {
+                assert!(is_functional(version, width, x, y).is_err());
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod ndarray_tests {
+    use crate::spec::{EcLevelL, Version1, Version40};
+    use crate::QrCode;
+
+    #[test]
+    fn test_to_ndarray() {
+        let code = QrCode::<Version1<EcLevelL>>::new(b"01234567").unwrap();
+        let matrix = code.to_ndarray();
+        assert_eq!(matrix.dim(), (21, 21));
+        let mut bools = [false; 21 * 21];
+        code.to_bools_into(&mut bools).unwrap();
+        assert_eq!(matrix.as_slice(), Some(&bools[..]));
+
+        let code = QrCode::<Version40<EcLevelL>>::new(b"01234567").unwrap();
+        let matrix = code.to_ndarray();
+        assert_eq!(matrix.dim(), (177, 177));
+        assert!(matrix.indexed_iter().all(|((y, x), &dark)| dark == bool::from(code.get(x as i16, y as i16))));
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tracing_tests {
+    use crate::spec::{EcLevelM, Version1};
+    use crate::QrCode;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::Field;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// The fields of an event, formatted with `Debug`.
+    type Fields = Vec<(&'static str, String)>;
+
+    /// Records the fields of every event.
+    struct Recorder(Arc<Mutex<Vec<Fields>>>);
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::new();
+            event.record(&mut |field: &Field, value: &dyn fmt::Debug| {
+                fields.push((field.name(), format!("{:?}", value)))
+            });
+            self.0.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    /// Runs `f`, returning the events it emitted.
+    fn events(f: impl FnOnce()) -> Vec<Fields> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Recorder(events.clone()), f);
+        let events = events.lock().unwrap();
+        events.clone()
+    }
+
+    /// The value of `name` in the events with the message `message`.
+    fn values(events: &[Fields], message: &str, name: &str) -> Vec<String> {
+        events
+            .iter()
+            .filter(|fields| fields.contains(&("message", message.to_owned())))
+            .filter_map(|fields| fields.iter().find(|(field, _)| *field == name).map(|(_, value)| value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_milestones() {
+        let events = events(|| {
+            QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        });
+        assert_eq!(values(&events, "segment chosen", "mode"), ["Numeric"]);
+        assert_eq!(values(&events, "data segmented", "bits"), ["41"]);
+        assert_eq!(values(&events, "data segmented", "available"), ["128"]);
+
+        let penalties: Vec<u16> =
+            values(&events, "mask scored", "penalty").iter().map(|penalty| penalty.parse().unwrap()).collect();
+        assert_eq!(penalties.len(), 8);
+        let selected = values(&events, "mask selected", "penalty");
+        assert_eq!(selected, [penalties.iter().min().unwrap().to_string()]);
+    }
+
+    #[test]
+    fn test_data_too_long() {
+        let events = events(|| {
+            QrCode::<Version1<EcLevelM>>::new(b"THIS DOES NOT FIT INTO VERSION 1").unwrap_err();
+        });
+        assert_eq!(values(&events, "data too long", "needed"), ["189"]);
+        assert_eq!(values(&events, "data too long", "available"), ["128"]);
+        assert!(values(&events, "mask selected", "penalty").is_empty());
+    }
+}
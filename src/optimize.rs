@@ -1,5 +1,6 @@
 //! Find the optimal data mode sequence to encode a piece of data.
 use crate::types::{Mode, Version};
+use core::iter::Copied;
 use core::slice::Iter;
 
 #[cfg(feature = "bench")]
@@ -24,15 +25,15 @@ pub struct Segment {
 impl Segment {
     /// Compute the number of bits (including the size of the mode indicator and
     /// length bits) when this segment is encoded.
-    pub fn encoded_len(&self, version: Version) -> usize {
+    pub const fn encoded_len(&self, version: Version) -> usize {
         let byte_size = self.end - self.begin;
-        let chars_count = if self.mode == Mode::Kanji { byte_size / 2 } else { byte_size };
+        let chars_count = if matches!(self.mode, Mode::Kanji) { byte_size / 2 } else { byte_size };
 
         let mode_bits_count = version.mode_bits_count();
         let length_bits_count = self.mode.length_bits_count(version);
         let data_bits_count = self.mode.data_bits_count(chars_count);
 
-        mode_bits_count + length_bits_count + data_bits_count
+        data_bits_count.saturating_add(mode_bits_count + length_bits_count)
     }
 }
 
@@ -43,7 +44,7 @@ impl Segment {
 /// This iterator is basically equivalent to
 ///
 /// ```ignore
-/// data.map(|c| ExclCharSet::from_u8(*c))
+/// data.map(ExclCharSet::from_u8)
 ///     .chain(Some(ExclCharSet::End).move_iter())
 ///     .enumerate()
 /// ```
@@ -56,7 +57,7 @@ struct EcsIter<I> {
     ended: bool,
 }
 
-impl<'a, I: Iterator<Item = &'a u8>> Iterator for EcsIter<I> {
+impl<I: Iterator<Item = u8>> Iterator for EcsIter<I> {
     type Item = (usize, ExclCharSet);
 
     fn next(&mut self) -> Option<(usize, ExclCharSet)> {
@@ -72,7 +73,7 @@ impl<'a, I: Iterator<Item = &'a u8>> Iterator for EcsIter<I> {
             Some(c) => {
                 let old_index = self.index;
                 self.index += 1;
-                Some((old_index, ExclCharSet::from_u8(*c)))
+                Some((old_index, ExclCharSet::from_u8(c)))
             }
         }
     }
@@ -80,10 +81,7 @@ impl<'a, I: Iterator<Item = &'a u8>> Iterator for EcsIter<I> {
 
 /// QR code data parser to classify the input into distinct segments.
 pub struct Parser<'a> {
-    ecs_iter: EcsIter<Iter<'a, u8>>,
-    state: State,
-    begin: usize,
-    pending_single_byte: bool,
+    inner: StreamParser<Copied<Iter<'a, u8>>>,
 }
 
 impl<'a> Parser<'a> {
@@ -98,13 +96,13 @@ impl<'a> Parser<'a> {
     ///                                Segment { mode: Numeric, begin: 3, end: 6 },
     ///                                Segment { mode: Byte, begin: 6, end: 10 }]);
     ///
-    pub fn new(data: &[u8]) -> Parser {
-        Parser {
-            ecs_iter: EcsIter { base: data.iter(), index: 0, ended: false },
-            state: State::Init,
-            begin: 0,
-            pending_single_byte: false,
-        }
+    /// Pairs of bytes forming a Shift JIS double-byte character which kanji
+    /// mode can encode (0x8140–0x9ffc and 0xe040–0xebbf) are found anywhere
+    /// in the data, also between characters of the other modes, and become
+    /// `Kanji` segments. `Optimizer` then keeps those which are shorter than
+    /// their bytes merged into a neighbouring byte segment.
+    pub fn new(data: &[u8]) -> Parser<'_> {
+        Parser { inner: StreamParser::new(data.iter().copied()) }
     }
 }
 
@@ -112,41 +110,111 @@ impl<'a> Iterator for Parser<'a> {
     type Item = Segment;
 
     fn next(&mut self) -> Option<Segment> {
-        if self.pending_single_byte {
-            self.pending_single_byte = false;
-            self.begin += 1;
-            return Some(Segment { mode: Mode::Byte, begin: self.begin - 1, end: self.begin });
+        self.inner.next()
+    }
+}
+
+/// Like `Parser`, but reads the data from any iterator of bytes, looking at
+/// most one byte ahead. Together with `Optimizer`, which only holds the
+/// segment being merged, input of any length is segmented in constant
+/// memory, for example while it is read from a file or a radio link:
+///
+///     use qrcode::optimize::StreamParser;
+///     use qrcode::types::{Mode, Version};
+///
+///     let data = b"0123456789".iter().copied().cycle().take(1_000_000);
+///     let mut segments = StreamParser::new(data).optimize(Version::Normal(40));
+///     let segment = segments.next().unwrap();
+///     assert_eq!((segment.mode, segment.begin, segment.end), (Mode::Numeric, 0, 1_000_000));
+///
+/// The segments index into the whole stream, so the bytes of each segment
+/// can be pushed as they are read again.
+pub struct StreamParser<I> {
+    ecs_iter: EcsIter<I>,
+    segmenter: Segmenter,
+}
+
+/// The state machine of `StreamParser`, fed one character set at a time. It
+/// is separate from the iterator so that `SliceOptimizer` can run it in a
+/// `const` context.
+struct Segmenter {
+    state: State,
+    begin: usize,
+    pending_single_byte: bool,
+}
+
+impl Segmenter {
+    const fn new() -> Self {
+        Segmenter { state: State::Init, begin: 0, pending_single_byte: false }
+    }
+
+    /// Returns the single byte segment left over by the last
+    /// `Action::KanjiAndSingleByte`, if any.
+    const fn take_pending(&mut self) -> Option<Segment> {
+        if !self.pending_single_byte {
+            return None;
         }
+        self.pending_single_byte = false;
+        self.begin += 1;
+        Some(Segment { mode: Mode::Byte, begin: self.begin - 1, end: self.begin })
+    }
 
-        loop {
-            let (i, ecs) = match self.ecs_iter.next() {
-                None => return None,
-                Some(a) => a,
-            };
-            let (next_state, action) = STATE_TRANSITION[self.state as usize + ecs as usize];
-            self.state = next_state;
-
-            let old_begin = self.begin;
-            let push_mode = match action {
-                Action::Idle => continue,
-                Action::Numeric => Mode::Numeric,
-                Action::Alpha => Mode::Alphanumeric,
-                Action::Byte => Mode::Byte,
-                Action::Kanji => Mode::Kanji,
-                Action::KanjiAndSingleByte => {
-                    let next_begin = i - 1;
-                    if self.begin == next_begin {
-                        Mode::Byte
-                    } else {
-                        self.pending_single_byte = true;
-                        self.begin = next_begin;
-                        return Some(Segment { mode: Mode::Kanji, begin: old_begin, end: next_begin });
-                    }
+    /// Feeds the character set of the byte at index `i`, or `ExclCharSet::End`
+    /// at the end of the data, and returns the segment it completes, if any.
+    const fn push(&mut self, i: usize, ecs: ExclCharSet) -> Option<Segment> {
+        let (next_state, action) = STATE_TRANSITION[self.state as usize + ecs as usize];
+        self.state = next_state;
+
+        let old_begin = self.begin;
+        let push_mode = match action {
+            Action::Idle => return None,
+            Action::Numeric => Mode::Numeric,
+            Action::Alpha => Mode::Alphanumeric,
+            Action::Byte => Mode::Byte,
+            Action::Kanji => Mode::Kanji,
+            Action::KanjiAndSingleByte => {
+                let next_begin = i - 1;
+                if self.begin == next_begin {
+                    Mode::Byte
+                } else {
+                    self.pending_single_byte = true;
+                    self.begin = next_begin;
+                    return Some(Segment { mode: Mode::Kanji, begin: old_begin, end: next_begin });
                 }
-            };
+            }
+        };
 
-            self.begin = i;
-            return Some(Segment { mode: push_mode, begin: old_begin, end: i });
+        self.begin = i;
+        Some(Segment { mode: push_mode, begin: old_begin, end: i })
+    }
+}
+
+impl<I: Iterator<Item = u8>> StreamParser<I> {
+    /// Creates a new iterator which parse the bytes into segments, like
+    /// `Parser::new`.
+    pub fn new(data: I) -> Self {
+        StreamParser { ecs_iter: EcsIter { base: data, index: 0, ended: false }, segmenter: Segmenter::new() }
+    }
+
+    /// Merges the segments with an `Optimizer`.
+    pub fn optimize(self, version: Version) -> Optimizer<Self> {
+        Optimizer::new(self, version)
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for StreamParser<I> {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Segment> {
+        if let Some(segment) = self.segmenter.take_pending() {
+            return Some(segment);
+        }
+
+        loop {
+            let (i, ecs) = self.ecs_iter.next()?;
+            if let Some(segment) = self.segmenter.push(i, ecs) {
+                return Some(segment);
+            }
         }
     }
 }
@@ -190,6 +258,22 @@ mod parse_tests {
         );
     }
 
+    #[test]
+    fn test_parse_all_kanji() {
+        // Every double-byte code of kanji mode, alone and between characters
+        // of the other modes.
+        let codes =
+            (0x8140..=0x9ffc).chain(0xe040..=0xebbf).filter(|&c: &u16| matches!(c & 0xff, 0x40..=0x7e | 0x80..=0xfc));
+        for code in codes {
+            let [hi, lo] = code.to_be_bytes();
+            assert_eq!(parse(&[hi, lo]), vec![Segment { mode: Mode::Kanji, begin: 0, end: 2 }], "{:#x}", code);
+            let segs = parse(&[b'a', hi, lo, b'1', hi, lo, hi, lo, b'A']);
+            let kanji: Vec<(usize, usize)> =
+                segs.iter().filter(|s| s.mode == Mode::Kanji).map(|s| (s.begin, s.end)).collect();
+            assert_eq!(kanji, [(1, 3), (4, 8)], "{:#x}", code);
+        }
+    }
+
     #[test]
     fn test_parse_utf_8() {
         // Mojibake?
@@ -251,13 +335,50 @@ mod parse_tests {
 //------------------------------------------------------------------------------
 //{{{ Optimizer
 
-#[cfg_attr(feature = "cargo-clippy", allow(stutter))] // rust-lang-nursery/rust-clippy#2212 ಠ_ಠ
+#[allow(clippy::module_name_repetitions)] // rust-lang-nursery/rust-clippy#2212 ಠ_ಠ
 pub struct Optimizer<I> {
     parser: I,
+    merger: Merger,
+    ended: bool,
+}
+
+/// The greedy merging of `Optimizer`, fed one segment at a time. It is
+/// separate from the iterator so that `SliceOptimizer` can run it in a `const`
+/// context.
+struct Merger {
     last_segment: Segment,
     last_segment_size: usize,
     version: Version,
-    ended: bool,
+}
+
+impl Merger {
+    const fn new(segment: Segment, version: Version) -> Self {
+        Merger { last_segment: segment, last_segment_size: segment.encoded_len(version), version }
+    }
+
+    /// Merges the segment into the last one if that is no longer, otherwise
+    /// returns the last segment and keeps the new one.
+    const fn push(&mut self, segment: Segment) -> Option<Segment> {
+        let seg_size = segment.encoded_len(self.version);
+
+        let new_segment = Segment {
+            mode: self.last_segment.mode.max(segment.mode),
+            begin: self.last_segment.begin,
+            end: segment.end,
+        };
+        let new_size = new_segment.encoded_len(self.version);
+
+        if self.last_segment_size + seg_size >= new_size {
+            self.last_segment = new_segment;
+            self.last_segment_size = new_size;
+            None
+        } else {
+            let old_segment = self.last_segment;
+            self.last_segment = segment;
+            self.last_segment_size = seg_size;
+            Some(old_segment)
+        }
+    }
 }
 
 impl<I: Iterator<Item = Segment>> Optimizer<I> {
@@ -271,18 +392,10 @@ impl<I: Iterator<Item = Segment>> Optimizer<I> {
         match segments.next() {
             None => Self {
                 parser: segments,
-                last_segment: Segment { mode: Mode::Numeric, begin: 0, end: 0 },
-                last_segment_size: 0,
-                version,
+                merger: Merger::new(Segment { mode: Mode::Numeric, begin: 0, end: 0 }, version),
                 ended: true,
             },
-            Some(segment) => Self {
-                parser: segments,
-                last_segment: segment,
-                last_segment_size: segment.encoded_len(version),
-                version,
-                ended: false,
-            },
+            Some(segment) => Self { parser: segments, merger: Merger::new(segment, version), ended: false },
         }
     }
 }
@@ -305,26 +418,64 @@ impl<I: Iterator<Item = Segment>> Iterator for Optimizer<I> {
             match self.parser.next() {
                 None => {
                     self.ended = true;
-                    return Some(self.last_segment);
+                    return Some(self.merger.last_segment);
                 }
                 Some(segment) => {
-                    let seg_size = segment.encoded_len(self.version);
-
-                    let new_segment = Segment {
-                        mode: self.last_segment.mode.max(segment.mode),
-                        begin: self.last_segment.begin,
-                        end: segment.end,
-                    };
-                    let new_size = new_segment.encoded_len(self.version);
-
-                    if self.last_segment_size + seg_size >= new_size {
-                        self.last_segment = new_segment;
-                        self.last_segment_size = new_size;
-                    } else {
-                        let old_segment = self.last_segment;
-                        self.last_segment = segment;
-                        self.last_segment_size = seg_size;
-                        return Some(old_segment);
+                    if let Some(segment) = self.merger.push(segment) {
+                        return Some(segment);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The segments of `Parser::new(data).optimize(version)`, computed by `const
+/// fn`s for the compile time checks of `const_encode`.
+pub(crate) struct SliceOptimizer<'a> {
+    data: &'a [u8],
+    index: usize,
+    segmenter: Segmenter,
+    merger: Option<Merger>,
+    version: Version,
+}
+
+impl<'a> SliceOptimizer<'a> {
+    pub(crate) const fn new(data: &'a [u8], version: Version) -> Self {
+        SliceOptimizer { data, index: 0, segmenter: Segmenter::new(), merger: None, version }
+    }
+
+    /// The next parsed segment, before merging.
+    const fn next_parsed(&mut self) -> Option<Segment> {
+        if let Some(segment) = self.segmenter.take_pending() {
+            return Some(segment);
+        }
+        while self.index <= self.data.len() {
+            let i = self.index;
+            let ecs = if i < self.data.len() { ExclCharSet::from_u8(self.data[i]) } else { ExclCharSet::End };
+            self.index += 1;
+            if let Some(segment) = self.segmenter.push(i, ecs) {
+                return Some(segment);
+            }
+        }
+        None
+    }
+
+    /// The next optimized segment, like `Optimizer::next`.
+    pub(crate) const fn next(&mut self) -> Option<Segment> {
+        loop {
+            let parsed = self.next_parsed();
+            match (&mut self.merger, parsed) {
+                (None, None) => return None,
+                (None, Some(segment)) => self.merger = Some(Merger::new(segment, self.version)),
+                (Some(merger), None) => {
+                    let segment = merger.last_segment;
+                    self.merger = None;
+                    return Some(segment);
+                }
+                (Some(merger), Some(segment)) => {
+                    if let Some(segment) = merger.push(segment) {
+                        return Some(segment);
                     }
                 }
             }
@@ -339,13 +490,13 @@ pub fn total_encoded_len(segments: &[Segment], version: Version) -> usize {
 
 #[cfg(test)]
 mod optimize_tests {
-    use crate::optimize::{total_encoded_len, Optimizer, Segment};
+    use crate::optimize::{total_encoded_len, Optimizer, Parser, Segment, StreamParser};
     use crate::types::{Mode, Version};
 
     fn test_optimization_result(given: Vec<Segment>, expected: Vec<Segment>, version: Version) {
-        let prev_len = total_encoded_len(&*given, version);
-        let opt_segs = Optimizer::new(given.iter().map(|seg| *seg), version).collect::<Vec<_>>();
-        let new_len = total_encoded_len(&*opt_segs, version);
+        let prev_len = total_encoded_len(&given, version);
+        let opt_segs = Optimizer::new(given.iter().copied(), version).collect::<Vec<_>>();
+        let new_len = total_encoded_len(&opt_segs, version);
         if given != opt_segs {
             assert!(prev_len > new_len, "{} > {}", prev_len, new_len);
         }
@@ -353,7 +504,7 @@ mod optimize_tests {
             opt_segs == expected,
             "Optimization gave something better: {} < {} ({:?})",
             new_len,
-            total_encoded_len(&*expected, version),
+            total_encoded_len(&expected, version),
             opt_segs
         );
     }
@@ -415,6 +566,34 @@ mod optimize_tests {
         );
     }
 
+    #[test]
+    fn test_shift_jis_text() {
+        // "東京都千代田区丸の内1-9-1" in Shift JIS.
+        let data = b"\x93\x8c\x8b\x9e\x93\x73\x90\xe7\x91\xe3\x93\x63\x8b\xe6\x8a\xdb\x82\xcc\x93\xe01-9-1";
+        let segs: Vec<Segment> = Parser::new(data).optimize(Version::Normal(2)).collect();
+        assert_eq!(
+            segs,
+            vec![
+                Segment { mode: Mode::Kanji, begin: 0, end: 20 },
+                Segment { mode: Mode::Alphanumeric, begin: 20, end: 25 },
+            ]
+        );
+
+        // A few kanji among other characters are cheaper in the byte
+        // segment than in segments of their own.
+        // "価格: 1500円 (税込)" in Shift JIS.
+        let data = b"\x89\xbf\x8a\x69: 1500\x89\x7e (\x90\xc5\x8d\x9e)";
+        let segs: Vec<Segment> = Parser::new(data).optimize(Version::Normal(2)).collect();
+        assert_eq!(
+            segs,
+            vec![
+                Segment { mode: Mode::Byte, begin: 0, end: 6 },
+                Segment { mode: Mode::Numeric, begin: 6, end: 10 },
+                Segment { mode: Mode::Byte, begin: 10, end: 19 },
+            ]
+        );
+    }
+
     #[test]
     fn test_annex_j_guideline_1a() {
         test_optimization_result(
@@ -453,13 +632,30 @@ mod optimize_tests {
             Version::Micro(3),
         );
     }
+
+    #[test]
+    fn test_stream() {
+        let data = b"\x82\xa0\x81\x41\x41\xb1\x81\xf0 HELLO 0123456789 world \xeb\xc0";
+        let version = Version::Normal(5);
+        let expected: Vec<_> = Parser::new(data).optimize(version).collect();
+        let streamed: Vec<_> = StreamParser::new(data.iter().copied()).optimize(version).collect();
+        assert_eq!(streamed, expected);
+
+        // A gigabyte would pass as well, in the same memory.
+        let len = 3_000_000;
+        let stream = data.iter().copied().cycle().take(len);
+        let (count, end) = StreamParser::new(stream).optimize(version).fold((0, 0), |(count, end), segment| {
+            assert_eq!(segment.begin, end);
+            (count + 1, segment.end)
+        });
+        assert_eq!(end, len);
+        assert!(count > len / data.len());
+    }
 }
 
 #[cfg(feature = "bench")]
 #[bench]
 fn bench_optimize(bencher: &mut Bencher) {
-    use types::Version;
-
     let data = b"QR\x83R\x81[\x83h\x81i\x83L\x83\x85\x81[\x83A\x81[\x83\x8b\x83R\x81[\x83h\x81j\
                  \x82\xc6\x82\xcd\x81A1994\x94N\x82\xc9\x83f\x83\x93\x83\\\x81[\x82\xcc\x8aJ\
                  \x94\xad\x95\x94\x96\xe5\x81i\x8c\xbb\x8d\xdd\x82\xcd\x95\xaa\x97\xa3\x82\xb5\x83f\
@@ -532,7 +728,7 @@ enum ExclCharSet {
 
 impl ExclCharSet {
     /// Determines which character set a byte is in.
-    fn from_u8(c: u8) -> Self {
+    const fn from_u8(c: u8) -> Self {
         match c {
             0x20 | 0x24 | 0x25 | 0x2a | 0x2b | 0x2d..=0x2f | 0x3a => ExclCharSet::Symbol,
             0x30..=0x39 => ExclCharSet::Numeric,
@@ -0,0 +1,176 @@
+//! Finished symbols stored as packed modules in static memory.
+//!
+//! The `qr!` macro of the companion `qrcode-macros` crate encodes a literal at
+//! compile time and expands to a `PackedQrCode`, so fixed codes can be
+//! embedded in firmware without running the encoder.
+//!
+//!     use qrcode::{EcLevel, PackedQrCode, QrCode, Version};
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let mut bytes = [0; QrCode::<Version1<EcLevelM>>::binary_len()];
+//!     code.to_bytes(&mut bytes).unwrap();
+//!
+//!     let packed = PackedQrCode::new(Version::Normal(1), EcLevel::M, &bytes[3..]);
+//!     assert_eq!(packed.width(), 21);
+//!     assert!(packed.colors().eq(code.colors()));
+
+use crate::render::Pixel;
+use crate::types::{Color, EcLevel, QrError, QrResult, Version};
+
+/// A finished symbol borrowing its modules from a byte slice.
+///
+/// The modules are stored in row-major order, 8 per byte, most significant
+/// bit first, the same layout as the modules written by `QrCode::to_bytes`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PackedQrCode<'a> {
+    version: Version,
+    ec_level: EcLevel,
+    modules: &'a [u8],
+}
+
+impl<'a> PackedQrCode<'a> {
+    /// Wraps the packed modules of a symbol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version does not exist, or if `modules` does not hold
+    /// exactly `width²` bits, rounded up to whole bytes. In a `const` or
+    /// `static` initializer, this is a compile error instead. See `try_new`
+    /// for a variant returning an error.
+    pub const fn new(version: Version, ec_level: EcLevel, modules: &'a [u8]) -> Self {
+        match Self::try_new(version, ec_level, modules) {
+            Ok(code) => code,
+            Err(QrError::InvalidVersion { .. }) => panic!("invalid version"),
+            Err(_) => panic!("wrong number of packed modules"),
+        }
+    }
+
+    /// Wraps the packed modules of a symbol, like `new`.
+    ///
+    /// Returns `Err(QrError::InvalidVersion { .. })` if the version does not
+    /// exist, and `Err(QrError::InvalidSymbol)` if `modules` has the wrong
+    /// length.
+    pub const fn try_new(version: Version, ec_level: EcLevel, modules: &'a [u8]) -> QrResult<Self> {
+        let width = match version {
+            Version::Normal(1..=40) | Version::Micro(1..=4) => version.width() as usize,
+            _ => return Err(QrError::InvalidVersion { version, ec_level }),
+        };
+        if modules.len() != (width * width).div_ceil(8) {
+            return Err(QrError::InvalidSymbol);
+        }
+        Ok(Self { version, ec_level, modules })
+    }
+
+    /// Gets the version of the symbol.
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Gets the error correction level of the symbol.
+    pub const fn ec_level(&self) -> EcLevel {
+        self.ec_level
+    }
+
+    /// Gets the number of modules on each side of the symbol.
+    pub const fn width(&self) -> usize {
+        self.version.width() as usize
+    }
+
+    /// Gets the packed modules.
+    pub const fn modules(&self) -> &'a [u8] {
+        self.modules
+    }
+
+    /// Gets the color of the module at coordinate (x, y).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the symbol. See `try_get`.
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.try_get(x, y).expect("coordinates outside of the symbol")
+    }
+
+    /// Gets the color of the module at coordinate (x, y), or
+    /// `Err(QrError::OutOfBounds)` if the coordinates are outside of the
+    /// symbol.
+    pub fn try_get(&self, x: usize, y: usize) -> QrResult<Color> {
+        if x >= self.width() || y >= self.width() {
+            return Err(QrError::OutOfBounds);
+        }
+        let i = y * self.width() + x;
+        Ok(Color::from_bit(self.modules[i / 8] >> (7 - i % 8)))
+    }
+
+    /// Iterates the modules in row-major order.
+    pub fn colors(&self) -> impl Iterator<Item = Color> + 'a {
+        let modules = self.modules;
+        let area = self.width() * self.width();
+        (0..area).map(move |i| Color::from_bit(modules[i / 8] >> (7 - i % 8)))
+    }
+
+    /// Iterates the modules in row-major order, mapped to the pixel type `P`.
+    pub fn pixels<P: Pixel + 'a>(&self) -> impl Iterator<Item = P> + 'a {
+        self.colors().map(P::from_color)
+    }
+}
+
+#[cfg(test)]
+mod packed_tests {
+    use crate::packed::PackedQrCode;
+    use crate::spec::{EcLevelQ, QrSpec, Version3};
+    use crate::types::{Color, EcLevel, QrError, Version};
+    use crate::QrCode;
+
+    static MODULES: [u8; 106] = [0x55; 106];
+    static CHECKERED: PackedQrCode<'static> = PackedQrCode::new(Version::Normal(3), EcLevel::Q, &MODULES);
+
+    #[test]
+    fn test_static() {
+        assert_eq!(CHECKERED.width(), 29);
+        assert_eq!(CHECKERED.version(), Version::Normal(3));
+        assert_eq!(CHECKERED.ec_level(), EcLevel::Q);
+        assert_eq!(CHECKERED.get(0, 0), Color::Light);
+        assert_eq!(CHECKERED.get(1, 0), Color::Dark);
+        assert_eq!(CHECKERED.get(0, 1), Color::Dark);
+        assert_eq!(CHECKERED.colors().count(), 29 * 29);
+        assert_eq!(CHECKERED.try_get(28, 28), Ok(Color::Light));
+        assert_eq!(CHECKERED.try_get(29, 0), Err(QrError::OutOfBounds));
+        assert_eq!(CHECKERED.try_get(0, usize::MAX), Err(QrError::OutOfBounds));
+    }
+
+    #[test]
+    fn test_matches_code() {
+        type V = Version3<EcLevelQ>;
+        let code = QrCode::<V>::new(b"packed modules").unwrap();
+        let mut bytes = [0; QrCode::<V>::binary_len()];
+        code.to_bytes(&mut bytes).unwrap();
+
+        let packed = PackedQrCode::new(V::VERSION, V::EC_LEVEL, &bytes[3..]);
+        assert!(packed.colors().eq(code.colors()));
+        assert!(packed.pixels::<bool>().eq(code.pixels::<bool>()));
+    }
+
+    #[test]
+    #[should_panic(expected = "coordinates outside of the symbol")]
+    fn test_get_past_width() {
+        // Without the check, this would read the first module of row 1.
+        CHECKERED.get(29, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong number of packed modules")]
+    fn test_wrong_len() {
+        PackedQrCode::new(Version::Normal(1), EcLevel::L, &[0; 55]);
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert!(PackedQrCode::try_new(Version::Normal(3), EcLevel::Q, &MODULES) == Ok(CHECKERED));
+        assert_eq!(PackedQrCode::try_new(Version::Normal(1), EcLevel::L, &[0; 55]), Err(QrError::InvalidSymbol));
+        for version in [Version::Normal(0), Version::Normal(41), Version::Micro(5), Version::Normal(-9000)] {
+            let err = QrError::InvalidVersion { version, ec_level: EcLevel::H };
+            assert_eq!(PackedQrCode::try_new(version, EcLevel::H, &[]), Err(err));
+        }
+    }
+}
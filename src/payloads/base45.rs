@@ -0,0 +1,250 @@
+//! Base45 (RFC 9285), which writes binary data with the 45 characters of the
+//! alphanumeric mode, for payloads which must survive scanners returning
+//! text only.
+//!
+//!     use qrcode::payloads::base45::Base45;
+//!
+//!     assert_eq!(Base45::new(b"Hello!!").to_string(), "%69 VD92EX0");
+//!
+//! Two bytes take three characters, 16.5 bits in the alphanumeric mode, so
+//! the payload is only about 3% larger than in the byte mode.
+//!
+//! With the `zlib` feature, `Compressed` compresses the data with zlib before
+//! writing it in Base45 after a prefix, as EU Digital COVID Certificates
+//! (`HC1:`) and similar schemes do.
+
+use core::fmt::{self, Write};
+
+#[cfg(feature = "zlib")]
+use crate::spec::QrSpec;
+#[cfg(feature = "zlib")]
+use crate::QrCode;
+
+#[cfg(feature = "std")]
+use crate::types::{QrError, QrResult};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+const BASE45: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Data written in Base45. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Base45<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Base45<'a> {
+    /// The Base45 text of `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Base45 { data }
+    }
+}
+
+impl fmt::Display for Base45<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.data.chunks(2).try_for_each(|pair| write_base45(f, pair))
+    }
+}
+
+/// Decodes Base45 `text`. This requires the `std` feature.
+///
+///     use qrcode::payloads::base45;
+///
+///     assert_eq!(base45::decode("%69 VD92EX0")?, b"Hello!!");
+///     # Ok::<(), qrcode::types::QrError>(())
+///
+/// Returns `Err(QrError::InvalidField("data"))` if the text holds characters
+/// outside of the Base45 alphabet, ends with a single character, or a group
+/// of characters is out of range.
+#[cfg(feature = "std")]
+pub fn decode(text: &str) -> QrResult<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 3 * 2 + 1);
+    read_base45(text.as_bytes(), &mut out)?;
+    Ok(out)
+}
+
+/// Writes one or two bytes in Base45: three characters for two bytes, two
+/// for one.
+pub(crate) fn write_base45(f: &mut impl Write, bytes: &[u8]) -> fmt::Result {
+    let (mut n, digits) = match *bytes {
+        [a, b] => (usize::from(a) << 8 | usize::from(b), 3),
+        [a] => (usize::from(a), 2),
+        _ => return Ok(()),
+    };
+    for _ in 0..digits {
+        f.write_char(char::from(BASE45[n % 45]))?;
+        n /= 45;
+    }
+    Ok(())
+}
+
+/// Decodes Base45 `text`, appending the bytes to `out`.
+#[cfg(feature = "std")]
+pub(crate) fn read_base45(text: &[u8], out: &mut Vec<u8>) -> QrResult<()> {
+    let invalid = QrError::InvalidField("data");
+    for chunk in text.chunks(3) {
+        // Three characters reach 45^3 - 1 = 91124, past a 16-bit `usize`.
+        let mut n = 0_u32;
+        for &c in chunk.iter().rev() {
+            let digit = BASE45.iter().position(|&d| d == c).ok_or(invalid)?;
+            n = n * 45 + digit as u32;
+        }
+        match chunk.len() {
+            3 if n <= 0xffff => out.extend_from_slice(&[(n >> 8) as u8, n as u8]),
+            2 if n <= 0xff => out.push(n as u8),
+            _ => return Err(invalid),
+        }
+    }
+    Ok(())
+}
+
+/// Data compressed with zlib and written in Base45 after a prefix. This
+/// requires the `zlib` feature.
+///
+///     use qrcode::QrCode;
+///     use qrcode::payloads::{self, base45::Compressed};
+///     use qrcode::spec::{Version10, EcLevelQ};
+///
+///     // A signed certificate, which compresses well.
+///     let certificate = b"name: Erika Mustermann; dose: 1/2; dose: 2/2; ".repeat(8);
+///     let hc1 = Compressed::new("HC1:", &certificate)?;
+///     assert!(hc1.to_string().len() < certificate.len());
+///
+///     let code: QrCode<Version10<EcLevelQ>> = payloads::encode(&hc1)?;
+///     assert_eq!(Compressed::decompress("HC1:", &hc1.to_string(), 4096)?, certificate);
+///     # Ok::<(), qrcode::types::QrError>(())
+///
+/// The data is compressed at the highest level, and always to the same bytes.
+/// The whole text is in the character set of the alphanumeric mode.
+#[cfg(feature = "zlib")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Compressed<'a> {
+    prefix: &'a str,
+    compressed: Vec<u8>,
+}
+
+#[cfg(feature = "zlib")]
+impl<'a> Compressed<'a> {
+    /// Compresses `data`, to be written after `prefix`.
+    ///
+    /// Returns `Err(QrError::InvalidField("prefix"))` if the prefix holds
+    /// characters outside of the alphanumeric mode, such as lowercase letters.
+    pub fn new(prefix: &'a str, data: &[u8]) -> QrResult<Self> {
+        if !prefix.bytes().all(|b| BASE45.contains(&b)) {
+            return Err(QrError::InvalidField("prefix"));
+        }
+        Ok(Compressed { prefix, compressed: miniz_oxide::deflate::compress_to_vec_zlib(data, 9) })
+    }
+
+    /// The data compressed with zlib, before it is written in Base45.
+    pub fn compressed(&self) -> &[u8] {
+        &self.compressed
+    }
+
+    /// Restores the data from the `text` of a scanned code, which starts with
+    /// `prefix`. The data must decompress to at most `max_len` bytes, which
+    /// protects against payloads crafted to decompress to huge sizes.
+    ///
+    /// Returns `Err(QrError::InvalidField("prefix"))` if the text does not
+    /// start with the prefix, and `Err(QrError::InvalidField("data"))` if the
+    /// rest is not Base45, not zlib data, or decompresses to more than
+    /// `max_len` bytes.
+    pub fn decompress(prefix: &str, text: &str, max_len: usize) -> QrResult<Vec<u8>> {
+        let payload = text.strip_prefix(prefix).ok_or(QrError::InvalidField("prefix"))?;
+        let compressed = decode(payload)?;
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, max_len)
+            .map_err(|_| QrError::InvalidField("data"))
+    }
+}
+
+#[cfg(feature = "zlib")]
+impl fmt::Display for Compressed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, Base45::new(&self.compressed))
+    }
+}
+
+/// Compresses `data`, writes it in Base45 after `prefix` and encodes the text
+/// in one step, like `payloads::encode(&Compressed::new(prefix, data)?)`.
+/// This requires the `zlib` feature.
+///
+///     use qrcode::QrCode;
+///     use qrcode::payloads::base45;
+///     use qrcode::spec::{Version5, EcLevelQ};
+///
+///     let code = base45::encode_compressed::<Version5<EcLevelQ>>("HC1:", b"Hello, world!")?;
+///     # Ok::<(), qrcode::types::QrError>(())
+///
+/// Returns the errors of `Compressed::new` and `payloads::encode`.
+#[cfg(feature = "zlib")]
+pub fn encode_compressed<V: QrSpec>(prefix: &str, data: &[u8]) -> QrResult<QrCode<V>> {
+    super::encode(&Compressed::new(prefix, data)?)
+}
+
+#[cfg(test)]
+mod base45_tests {
+    use crate::payloads::base45::Base45;
+
+    #[test]
+    fn test_base45() {
+        // The examples of RFC 9285.
+        assert_eq!(Base45::new(b"AB").to_string(), "BB8");
+        assert_eq!(Base45::new(b"Hello!!").to_string(), "%69 VD92EX0");
+        assert_eq!(Base45::new(b"base-45").to_string(), "UJCLQE7W581");
+        assert_eq!(Base45::new(b"ietf!").to_string(), "QED8WEX0");
+        assert_eq!(Base45::new(b"").to_string(), "");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode() {
+        use crate::payloads::base45::decode;
+        use crate::types::QrError;
+
+        assert_eq!(decode("QED8WEX0"), Ok(b"ietf!".to_vec()));
+        assert_eq!(decode("QED8WE"), Ok(b"ietf".to_vec()));
+        assert_eq!(decode(""), Ok(Vec::new()));
+        let invalid = Err(QrError::InvalidField("data"));
+        assert_eq!(decode("GGW"), invalid, "65536 does not fit into two bytes");
+        assert_eq!(decode(":::"), invalid, "91124 does not fit into two bytes");
+        assert_eq!(decode("ZZ"), invalid, "1610 does not fit into one byte");
+        assert_eq!(decode("BB8A"), invalid);
+        assert_eq!(decode("bb8"), invalid);
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_compressed() {
+        use crate::payloads::base45::{encode_compressed, Compressed};
+        use crate::spec::{EcLevelL, EcLevelQ, Version1, Version5};
+        use crate::types::QrError;
+        use crate::QrCode;
+
+        let data = b"Hello, world! Hello, world! Hello, world!";
+        let hc1 = Compressed::new("HC1:", data).unwrap();
+        assert_eq!(&hc1.compressed()[..2], [0x78, 0xda]);
+        let text = hc1.to_string();
+        assert!(text.starts_with("HC1:"));
+        assert!(text.bytes().all(|b| super::BASE45.contains(&b)));
+        assert_eq!(Compressed::decompress("HC1:", &text, data.len()), Ok(data.to_vec()));
+
+        let code = encode_compressed::<Version5<EcLevelQ>>("HC1:", data).unwrap();
+        assert!(code == QrCode::new(text.as_bytes()).unwrap());
+        assert!(matches!(encode_compressed::<Version1<EcLevelL>>("HC1:", data), Err(QrError::DataTooLong { .. })));
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_compressed_errors() {
+        use crate::payloads::base45::{Base45, Compressed};
+        use crate::types::QrError;
+
+        assert_eq!(Compressed::new("hc1:", b"").err(), Some(QrError::InvalidField("prefix")));
+
+        let text = Compressed::new("HC1:", &[0; 1000]).unwrap().to_string();
+        assert_eq!(Compressed::decompress("HC2:", &text, 1000), Err(QrError::InvalidField("prefix")));
+        assert_eq!(Compressed::decompress("HC1:", &text, 999), Err(QrError::InvalidField("data")));
+        let uncompressed = format!("HC1:{}", Base45::new(b"not zlib"));
+        assert_eq!(Compressed::decompress("HC1:", &uncompressed, 1000), Err(QrError::InvalidField("data")));
+    }
+}
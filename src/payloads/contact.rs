@@ -0,0 +1,536 @@
+//! Contact cards, as vCard 3.0 or 4.0 (RFC 2426, RFC 6350), or as the more
+//! compact MeCard.
+//!
+//!     use qrcode::payloads::contact::{Contact, Phone, VCardVersion};
+//!
+//!     let phones = [Phone::cell("+1 555 0100")];
+//!     let contact = Contact::new("Ada", "Lovelace").phones(&phones).emails(&["ada@example.com"]);
+//!
+//!     assert_eq!(
+//!         contact.vcard(VCardVersion::V3).to_string(),
+//!         "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Lovelace;Ada;;;\r\nFN:Ada Lovelace\r\n\
+//!          TEL;TYPE=CELL:+1 555 0100\r\nEMAIL:ada@example.com\r\nEND:VCARD\r\n",
+//!     );
+//!     assert_eq!(
+//!         contact.mecard().to_string(),
+//!         "MECARD:N:Lovelace,Ada;TEL:+1 555 0100;EMAIL:ada@example.com;;",
+//!     );
+//!
+//! Text is escaped as each format requires. vCard lines longer than 75 bytes
+//! are folded, never inside a UTF-8 character or an escape sequence. Use
+//! `payloads::encode` to get a symbol.
+
+use core::fmt::{self, Write};
+
+use crate::payloads::Escaped;
+
+//------------------------------------------------------------------------------
+//{{{ Contact
+
+/// The kind of a phone number.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PhoneKind {
+    /// A mobile phone.
+    Cell,
+
+    /// A phone at work.
+    Work,
+
+    /// A phone at home.
+    Home,
+
+    /// A fax machine.
+    Fax,
+
+    /// A number without a kind.
+    Other,
+}
+
+/// A phone number.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Phone<'a> {
+    /// The number, as it should be dialed.
+    pub number: &'a str,
+
+    /// The kind of the number.
+    pub kind: PhoneKind,
+}
+
+impl<'a> Phone<'a> {
+    /// A mobile number.
+    pub const fn cell(number: &'a str) -> Self {
+        Phone { number, kind: PhoneKind::Cell }
+    }
+
+    /// A work number.
+    pub const fn work(number: &'a str) -> Self {
+        Phone { number, kind: PhoneKind::Work }
+    }
+
+    /// A home number.
+    pub const fn home(number: &'a str) -> Self {
+        Phone { number, kind: PhoneKind::Home }
+    }
+}
+
+/// A postal address. Empty fields are left out.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Address<'a> {
+    /// The street and house number.
+    pub street: &'a str,
+
+    /// The city.
+    pub city: &'a str,
+
+    /// The state, province or region.
+    pub region: &'a str,
+
+    /// The postal code.
+    pub postal_code: &'a str,
+
+    /// The country.
+    pub country: &'a str,
+}
+
+/// The details of a person. Empty fields are left out.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Contact<'a> {
+    /// The given name.
+    pub given_name: &'a str,
+
+    /// The family name.
+    pub family_name: &'a str,
+
+    /// The name as displayed, by default the given and family names separated
+    /// by a space.
+    pub formatted_name: Option<&'a str>,
+
+    /// The organization.
+    pub organization: &'a str,
+
+    /// The job title. Not part of MeCards.
+    pub title: &'a str,
+
+    /// The phone numbers.
+    pub phones: &'a [Phone<'a>],
+
+    /// The e-mail addresses.
+    pub emails: &'a [&'a str],
+
+    /// The web site.
+    pub url: &'a str,
+
+    /// The postal address.
+    pub address: Option<Address<'a>>,
+
+    /// A note, which may span several lines.
+    pub note: &'a str,
+}
+
+impl<'a> Contact<'a> {
+    /// A contact with the given name and nothing else.
+    pub const fn new(given_name: &'a str, family_name: &'a str) -> Self {
+        Contact {
+            given_name,
+            family_name,
+            formatted_name: None,
+            organization: "",
+            title: "",
+            phones: &[],
+            emails: &[],
+            url: "",
+            address: None,
+            note: "",
+        }
+    }
+
+    /// Sets the name as displayed.
+    pub const fn formatted_name(mut self, formatted_name: &'a str) -> Self {
+        self.formatted_name = Some(formatted_name);
+        self
+    }
+
+    /// Sets the organization.
+    pub const fn organization(mut self, organization: &'a str) -> Self {
+        self.organization = organization;
+        self
+    }
+
+    /// Sets the job title.
+    pub const fn title(mut self, title: &'a str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Sets the phone numbers.
+    pub const fn phones(mut self, phones: &'a [Phone<'a>]) -> Self {
+        self.phones = phones;
+        self
+    }
+
+    /// Sets the e-mail addresses.
+    pub const fn emails(mut self, emails: &'a [&'a str]) -> Self {
+        self.emails = emails;
+        self
+    }
+
+    /// Sets the web site.
+    pub const fn url(mut self, url: &'a str) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Sets the postal address.
+    pub const fn address(mut self, address: Address<'a>) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Sets the note.
+    pub const fn note(mut self, note: &'a str) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// Formats the contact as a vCard.
+    pub const fn vcard(self, version: VCardVersion) -> VCard<'a> {
+        VCard { contact: self, version }
+    }
+
+    /// Formats the contact as a MeCard.
+    pub const fn mecard(self) -> MeCard<'a> {
+        MeCard { contact: self }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ vCard
+
+/// The version of a vCard.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum VCardVersion {
+    /// vCard 3.0, RFC 2426, which most scanners understand.
+    V3,
+
+    /// vCard 4.0, RFC 6350.
+    V4,
+}
+
+/// A contact formatted as a vCard. See `Contact::vcard`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct VCard<'a> {
+    /// The contact to format.
+    pub contact: Contact<'a>,
+
+    /// The version of the vCard format.
+    pub version: VCardVersion,
+}
+
+/// The longest line of a vCard, in bytes, not counting the line break.
+const MAX_LINE_LEN: usize = 75;
+
+/// Writes the content lines of a vCard, folding them before they grow longer
+/// than `MAX_LINE_LEN` bytes.
+struct Lines<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    len: usize,
+}
+
+impl Lines<'_, '_> {
+    /// Writes `unit` on the current line, or on a continuation line if it
+    /// does not fit.
+    fn unit(&mut self, unit: &str) -> fmt::Result {
+        if self.len + unit.len() > MAX_LINE_LEN {
+            // The space starting a continuation line counts as well.
+            self.f.write_str("\r\n ")?;
+            self.len = 1;
+        }
+        self.len += unit.len();
+        self.f.write_str(unit)
+    }
+
+    /// Writes `s` as is, such as a property name or a URI.
+    fn raw(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| self.unit(c.encode_utf8(&mut [0; 4])))
+    }
+
+    /// Writes `text` as a text value, escaping backslashes, commas,
+    /// semicolons and line breaks.
+    fn text(&mut self, text: &str) -> fmt::Result {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => self.unit("\\\\")?,
+                ',' => self.unit("\\,")?,
+                ';' => self.unit("\\;")?,
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\r' | '\n' => self.unit("\\n")?,
+                c => self.unit(c.encode_utf8(&mut [0; 4]))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a whole property with a single text value.
+    fn property(&mut self, name: &str, text: &str) -> fmt::Result {
+        self.raw(name)?;
+        self.raw(":")?;
+        self.text(text)?;
+        self.end()
+    }
+
+    fn end(&mut self) -> fmt::Result {
+        self.len = 0;
+        self.f.write_str("\r\n")
+    }
+}
+
+impl fmt::Display for VCard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let contact = &self.contact;
+        let v4 = self.version == VCardVersion::V4;
+        let mut lines = Lines { f, len: 0 };
+
+        lines.raw("BEGIN:VCARD")?;
+        lines.end()?;
+        lines.raw(if v4 { "VERSION:4.0" } else { "VERSION:3.0" })?;
+        lines.end()?;
+
+        lines.raw("N:")?;
+        lines.text(contact.family_name)?;
+        lines.raw(";")?;
+        lines.text(contact.given_name)?;
+        lines.raw(";;;")?;
+        lines.end()?;
+
+        lines.raw("FN:")?;
+        match contact.formatted_name {
+            Some(formatted_name) => lines.text(formatted_name)?,
+            None => {
+                lines.text(contact.given_name)?;
+                if !contact.given_name.is_empty() && !contact.family_name.is_empty() {
+                    lines.raw(" ")?;
+                }
+                lines.text(contact.family_name)?;
+            }
+        }
+        lines.end()?;
+
+        if !contact.organization.is_empty() {
+            lines.property("ORG", contact.organization)?;
+        }
+        if !contact.title.is_empty() {
+            lines.property("TITLE", contact.title)?;
+        }
+        for phone in contact.phones {
+            let kind = match (phone.kind, v4) {
+                (PhoneKind::Cell, false) => ";TYPE=CELL",
+                (PhoneKind::Work, false) => ";TYPE=WORK",
+                (PhoneKind::Home, false) => ";TYPE=HOME",
+                (PhoneKind::Fax, false) => ";TYPE=FAX",
+                (PhoneKind::Cell, true) => ";TYPE=cell",
+                (PhoneKind::Work, true) => ";TYPE=work",
+                (PhoneKind::Home, true) => ";TYPE=home",
+                (PhoneKind::Fax, true) => ";TYPE=fax",
+                (PhoneKind::Other, _) => "",
+            };
+            lines.raw("TEL")?;
+            lines.raw(kind)?;
+            if v4 {
+                // vCard 4.0 numbers are `tel:` URIs, where spaces are not
+                // allowed as visual separators.
+                lines.raw(";VALUE=uri:tel:")?;
+                for (i, part) in phone.number.split(' ').enumerate() {
+                    if i > 0 {
+                        lines.raw("-")?;
+                    }
+                    lines.raw(part)?;
+                }
+            } else {
+                lines.raw(":")?;
+                lines.text(phone.number)?;
+            }
+            lines.end()?;
+        }
+        for email in contact.emails {
+            lines.property("EMAIL", email)?;
+        }
+        if !contact.url.is_empty() {
+            lines.raw("URL:")?;
+            lines.raw(contact.url)?;
+            lines.end()?;
+        }
+        if let Some(address) = &contact.address {
+            lines.raw("ADR:;;")?;
+            let fields = [address.street, address.city, address.region, address.postal_code, address.country];
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    lines.raw(";")?;
+                }
+                lines.text(field)?;
+            }
+            lines.end()?;
+        }
+        if !contact.note.is_empty() {
+            lines.property("NOTE", contact.note)?;
+        }
+        lines.raw("END:VCARD")?;
+        lines.end()
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ MeCard
+
+/// A contact formatted as a MeCard. See `Contact::mecard`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct MeCard<'a> {
+    /// The contact to format.
+    pub contact: Contact<'a>,
+}
+
+/// The characters which must be escaped in MeCard fields.
+const MECARD_SPECIAL: &[char] = &['\\', ';', ',', ':', '"'];
+
+fn escaped(text: &str) -> Escaped<'_> {
+    Escaped { text, special: MECARD_SPECIAL }
+}
+
+impl fmt::Display for MeCard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let contact = &self.contact;
+        write!(f, "MECARD:N:{}", escaped(contact.family_name))?;
+        if !contact.given_name.is_empty() {
+            write!(f, ",{}", escaped(contact.given_name))?;
+        }
+        f.write_char(';')?;
+        if !contact.organization.is_empty() {
+            write!(f, "ORG:{};", escaped(contact.organization))?;
+        }
+        for phone in contact.phones {
+            write!(f, "TEL:{};", escaped(phone.number))?;
+        }
+        for email in contact.emails {
+            write!(f, "EMAIL:{};", escaped(email))?;
+        }
+        if !contact.url.is_empty() {
+            write!(f, "URL:{};", escaped(contact.url))?;
+        }
+        if let Some(address) = &contact.address {
+            // The post office box and the extended address come first.
+            f.write_str("ADR:,")?;
+            for field in [address.street, address.city, address.region, address.postal_code, address.country] {
+                write!(f, ",{}", escaped(field))?;
+            }
+            f.write_char(';')?;
+        }
+        if !contact.note.is_empty() {
+            write!(f, "NOTE:{};", escaped(contact.note))?;
+        }
+        f.write_char(';')
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod contact_tests {
+    use crate::decode::decode;
+    use crate::payloads::contact::{Address, Contact, Phone, PhoneKind, VCardVersion};
+    use crate::payloads::encode;
+    use crate::spec::{EcLevelM, QrSpec, Version15};
+    use crate::types::Color;
+
+    const PHONES: &[Phone] =
+        &[Phone::cell("+49 30 1234"), Phone::work("+49 30 5678"), Phone { number: "110", kind: PhoneKind::Other }];
+
+    fn full() -> Contact<'static> {
+        let address = Address {
+            street: "Unter den Linden 1",
+            city: "Berlin",
+            region: "",
+            postal_code: "10117",
+            country: "Germany",
+        };
+        Contact::new("Jürgen", "Müller")
+            .organization("Müller, Schmidt & Co.")
+            .title("CTO")
+            .phones(PHONES)
+            .emails(&["jm@example.com", "juergen@example.org"])
+            .url("https://example.com/~jm")
+            .address(address)
+            .note("Line one;\r\nline two")
+    }
+
+    #[test]
+    fn test_vcard3() {
+        assert_eq!(
+            full().vcard(VCardVersion::V3).to_string(),
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:Müller;Jürgen;;;\r\n\
+             FN:Jürgen Müller\r\n\
+             ORG:Müller\\, Schmidt & Co.\r\n\
+             TITLE:CTO\r\n\
+             TEL;TYPE=CELL:+49 30 1234\r\n\
+             TEL;TYPE=WORK:+49 30 5678\r\n\
+             TEL:110\r\n\
+             EMAIL:jm@example.com\r\n\
+             EMAIL:juergen@example.org\r\n\
+             URL:https://example.com/~jm\r\n\
+             ADR:;;Unter den Linden 1;Berlin;;10117;Germany\r\n\
+             NOTE:Line one\\;\\nline two\r\n\
+             END:VCARD\r\n"
+        );
+    }
+
+    #[test]
+    fn test_vcard4() {
+        let vcard = full().formatted_name("Dr. J. Müller").vcard(VCardVersion::V4).to_string();
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\nN:Müller;Jürgen;;;\r\nFN:Dr. J. Müller\r\n"));
+        assert!(vcard.contains("\r\nTEL;TYPE=cell;VALUE=uri:tel:+49-30-1234\r\n"));
+        assert!(vcard.contains("\r\nTEL;VALUE=uri:tel:110\r\n"));
+    }
+
+    #[test]
+    fn test_folding() {
+        let note = "Ünïcödé ".repeat(12);
+        let vcard = Contact::new("A", "B").note(&note).vcard(VCardVersion::V3).to_string();
+        for line in vcard.split("\r\n") {
+            assert!(line.len() <= 75, "{:?}", line);
+        }
+        let unfolded = vcard.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("\r\nNOTE:{}\r\n", note)));
+
+        let note = ";".repeat(50);
+        let vcard = Contact::new("A", "B").note(&note).vcard(VCardVersion::V3).to_string();
+        let lines: Vec<&str> = vcard.split("\r\n").collect();
+        assert_eq!(lines[4], format!("NOTE:{}", "\\;".repeat(35)));
+        assert_eq!(lines[5], format!(" {}", "\\;".repeat(15)));
+    }
+
+    #[test]
+    fn test_mecard() {
+        assert_eq!(
+            full().mecard().to_string(),
+            "MECARD:N:Müller,Jürgen;ORG:Müller\\, Schmidt & Co.;TEL:+49 30 1234;TEL:+49 30 5678;TEL:110;\
+             EMAIL:jm@example.com;EMAIL:juergen@example.org;URL:https\\://example.com/~jm;\
+             ADR:,,Unter den Linden 1,Berlin,,10117,Germany;NOTE:Line one\\;\r\nline two;;"
+        );
+        assert_eq!(Contact::new("", "Acme").mecard().to_string(), "MECARD:N:Acme;;");
+    }
+
+    #[test]
+    fn test_encode() {
+        type V = Version15<EcLevelM>;
+        let vcard = full().vcard(VCardVersion::V3);
+        let code = encode::<V>(&vcard).unwrap();
+        let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+        let mut out = [0; 1024];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], vcard.to_string().as_bytes());
+    }
+}
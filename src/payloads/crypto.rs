@@ -0,0 +1,197 @@
+//! Cryptocurrency payment requests, as BIP-21 `bitcoin:` URIs or the same
+//! `scheme:address?amount=` form used by other currencies.
+//!
+//!     use qrcode::payloads::crypto::PaymentUri;
+//!
+//!     let donation = PaymentUri::bitcoin("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")?
+//!         .amount(50_000, 8)?
+//!         .label("Open Source Fund")
+//!         .message("Thanks & good luck!");
+//!     assert_eq!(
+//!         donation.to_string(),
+//!         "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.0005\
+//!          &label=Open%20Source%20Fund&message=Thanks%20%26%20good%20luck%21",
+//!     );
+//!
+//!     let litecoin = PaymentUri::new("litecoin", "LQTpS3VaYTjCr4s9Y1t5zbeY26zevf7Fb3")?.amount(1, 0)?;
+//!     assert_eq!(litecoin.to_string(), "litecoin:LQTpS3VaYTjCr4s9Y1t5zbeY26zevf7Fb3?amount=1");
+//!     # Ok::<(), qrcode::types::QrError>(())
+//!
+//! Amounts are given in the smallest unit of the currency, such as satoshis,
+//! with the number of decimals of the main unit, so they are written exactly.
+
+use core::fmt;
+
+use crate::payloads::PercentEncoded;
+use crate::types::{QrError, QrResult};
+
+/// The most decimals an amount may have, so ten to their power fits into a
+/// `u64`. Ether, with 18, has the most among the common currencies.
+const MAX_DECIMALS: u32 = 18;
+
+/// A payment request. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct PaymentUri<'a> {
+    scheme: &'a str,
+    address: &'a str,
+    amount: Option<(u64, u32)>,
+    label: Option<&'a str>,
+    message: Option<&'a str>,
+    params: &'a [(&'a str, &'a str)],
+}
+
+impl<'a> PaymentUri<'a> {
+    /// A payment to `address` with the URI scheme `scheme`, such as
+    /// `litecoin`.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the scheme is not a valid URI
+    /// scheme, or the address is empty or holds characters other than letters,
+    /// digits and `-._~@:`.
+    pub fn new(scheme: &'a str, address: &'a str) -> QrResult<Self> {
+        let mut bytes = scheme.bytes();
+        let valid_scheme = bytes.next().is_some_and(|b| b.is_ascii_alphabetic())
+            && bytes.all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+        if !valid_scheme {
+            return Err(QrError::InvalidField("scheme"));
+        }
+        if address.is_empty() || !address.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~@:".contains(&b)) {
+            return Err(QrError::InvalidField("address"));
+        }
+        Ok(PaymentUri { scheme, address, amount: None, label: None, message: None, params: &[] })
+    }
+
+    /// A Bitcoin payment to `address`, as specified by BIP-21.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the address is invalid, as for
+    /// `PaymentUri::new`.
+    pub fn bitcoin(address: &'a str) -> QrResult<Self> {
+        PaymentUri::new("bitcoin", address)
+    }
+
+    /// Sets the amount to `value` smallest units of a currency whose main
+    /// unit has `decimals` decimals, such as 8 for bitcoin.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if `decimals` is more than 18.
+    pub fn amount(mut self, value: u64, decimals: u32) -> QrResult<Self> {
+        if decimals > MAX_DECIMALS {
+            return Err(QrError::InvalidField("amount"));
+        }
+        self.amount = Some((value, decimals));
+        Ok(self)
+    }
+
+    /// Sets the name of the recipient.
+    pub const fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Sets a note shown to the payer.
+    pub const fn message(mut self, message: &'a str) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Sets further parameters as key-value pairs, written after the others
+    /// in the given order. BIP-21 requires wallets to reject a URI with a
+    /// `req-` key they do not know.
+    pub const fn params(mut self, params: &'a [(&'a str, &'a str)]) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+/// Writes an amount of `.0` smallest units with `.1` decimals, without
+/// trailing zeros.
+struct Amount(u64, u32);
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = 10_u64.pow(self.1);
+        let (whole, mut fraction) = (self.0 / unit, self.0 % unit);
+        write!(f, "{}", whole)?;
+        if fraction > 0 {
+            let mut width = self.1 as usize;
+            while fraction % 10 == 0 {
+                fraction /= 10;
+                width -= 1;
+            }
+            write!(f, ".{:0width$}", fraction, width = width)?;
+        }
+        Ok(())
+    }
+}
+
+fn encoded(text: &str) -> PercentEncoded<'_> {
+    PercentEncoded { text, keep: b"" }
+}
+
+impl fmt::Display for PaymentUri<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.address)?;
+        let mut separator = '?';
+        let mut param = |f: &mut fmt::Formatter, key: &dyn fmt::Display, value: &dyn fmt::Display| {
+            let result = write!(f, "{}{}={}", separator, key, value);
+            separator = '&';
+            result
+        };
+        if let Some((value, decimals)) = self.amount {
+            param(f, &"amount", &Amount(value, decimals))?;
+        }
+        if let Some(label) = self.label {
+            param(f, &"label", &encoded(label))?;
+        }
+        if let Some(message) = self.message {
+            param(f, &"message", &encoded(message))?;
+        }
+        for &(key, value) in self.params {
+            param(f, &encoded(key), &encoded(value))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod crypto_tests {
+    use crate::payloads::crypto::{Amount, PaymentUri};
+    use crate::types::QrError;
+
+    #[test]
+    fn test_amount() {
+        let amount = |value, decimals| Amount(value, decimals).to_string();
+        assert_eq!(amount(0, 8), "0");
+        assert_eq!(amount(100_000_000, 8), "1");
+        assert_eq!(amount(123_456_789, 8), "1.23456789");
+        assert_eq!(amount(120_000_000, 8), "1.2");
+        assert_eq!(amount(1, 8), "0.00000001");
+        assert_eq!(amount(u64::MAX, 18), "18.446744073709551615");
+        assert_eq!(amount(42, 0), "42");
+    }
+
+    #[test]
+    fn test_uri() {
+        let address = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+        assert_eq!(PaymentUri::bitcoin(address).unwrap().to_string(), "bitcoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2");
+        let params = [("req-note", "a=b"), ("lightning", "LNBC10U1P3PJ257")];
+        let uri = PaymentUri::bitcoin(address).unwrap().message("Café").params(&params);
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2?message=Caf%C3%A9&req-note=a%3Db&lightning=LNBC10U1P3PJ257"
+        );
+        let ether = PaymentUri::new("ethereum", "0xfb6916095ca1df60bb79Ce92ce3ea74c37c5d359@1").unwrap();
+        assert_eq!(
+            ether.amount(2_014_000_000_000_000_000, 18).unwrap().to_string(),
+            "ethereum:0xfb6916095ca1df60bb79Ce92ce3ea74c37c5d359@1?amount=2.014"
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(PaymentUri::new("", "abc"), Err(QrError::InvalidField("scheme")));
+        assert_eq!(PaymentUri::new("1coin", "abc"), Err(QrError::InvalidField("scheme")));
+        assert_eq!(PaymentUri::new("web+coin", "abc").map(|uri| uri.to_string()), Ok("web+coin:abc".into()));
+        assert_eq!(PaymentUri::bitcoin(""), Err(QrError::InvalidField("address")));
+        assert_eq!(PaymentUri::bitcoin("abc?amount=1"), Err(QrError::InvalidField("address")));
+        assert_eq!(PaymentUri::bitcoin("abc").unwrap().amount(1, 19), Err(QrError::InvalidField("amount")));
+    }
+}
@@ -0,0 +1,325 @@
+//! SEPA credit transfers, as the EPC QR code of the European Payments Council
+//! (EPC069-12), also known as Girocode, which banking apps read to prefill a
+//! transfer.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::payloads::{self, epc::Epc};
+//!     use qrcode::spec::{Version6, EcLevelM};
+//!
+//!     let transfer = Epc::new("Red Cross", "DE68 2105 0170 0012 3456 78")?
+//!         .bic("BFSWDE33BER")?
+//!         .amount(12_50)?
+//!         .text("Donation, invoice 2024-117")?;
+//!     assert_eq!(
+//!         transfer.to_string(),
+//!         "BCD\n001\n1\nSCT\nBFSWDE33BER\nRed Cross\nDE68210501700012345678\nEUR12.5\n\n\n\
+//!          Donation, invoice 2024-117",
+//!     );
+//!
+//!     // The EPC QR code is specified for error correction level M.
+//!     let code: QrCode<Version6<EcLevelM>> = payloads::encode(&transfer)?;
+//!     # Ok::<(), qrcode::types::QrError>(())
+//!
+//! Every field is checked when it is set, against the lengths and formats of
+//! the specification, so a built payload is always valid. The payload is
+//! written in UTF-8.
+
+use core::fmt::{self, Write};
+
+use crate::types::{QrError, QrResult};
+
+/// The most bytes an EPC QR code payload may take.
+const MAX_LEN: usize = 331;
+
+/// The largest amount, in cents: 999 999 999.99 €.
+const MAX_AMOUNT: u64 = 99_999_999_999;
+
+/// A SEPA credit transfer. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Epc<'a> {
+    name: &'a str,
+    iban: &'a str,
+    bic: Option<&'a str>,
+    amount: Option<u64>,
+    purpose: Option<&'a str>,
+    reference: Option<&'a str>,
+    text: Option<&'a str>,
+    information: Option<&'a str>,
+}
+
+impl<'a> Epc<'a> {
+    /// A transfer to the account `iban` of the beneficiary `name`, without an
+    /// amount, so the payer has to enter it. The IBAN may be grouped with
+    /// spaces, which are left out of the payload.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the name is empty or longer
+    /// than 70 characters, or the IBAN is malformed or its check digits are
+    /// wrong.
+    pub fn new(name: &'a str, iban: &'a str) -> QrResult<Self> {
+        check_text(name, 70, "name")?;
+        if name.is_empty() {
+            return Err(QrError::InvalidField("name"));
+        }
+        if !is_valid_iban(iban) {
+            return Err(QrError::InvalidField("iban"));
+        }
+        Ok(Epc { name, iban, bic: None, amount: None, purpose: None, reference: None, text: None, information: None })
+    }
+
+    /// Sets the BIC of the beneficiary's bank. It is optional within the
+    /// European Economic Area, but older apps require it.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is not 8 or 11 letters and
+    /// digits.
+    pub fn bic(mut self, bic: &'a str) -> QrResult<Self> {
+        if !matches!(bic.len(), 8 | 11) || !bic.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(QrError::InvalidField("bic"));
+        }
+        self.bic = Some(bic);
+        self.check_len("bic")
+    }
+
+    /// Sets the amount in euro cents.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is not between 0.01 € and
+    /// 999 999 999.99 €.
+    pub fn amount(mut self, cents: u64) -> QrResult<Self> {
+        if !(1..=MAX_AMOUNT).contains(&cents) {
+            return Err(QrError::InvalidField("amount"));
+        }
+        self.amount = Some(cents);
+        self.check_len("amount")
+    }
+
+    /// Sets the purpose of the transfer, as a four letter ISO 20022 code such
+    /// as `CHAR` for charity.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is not four letters and
+    /// digits.
+    pub fn purpose(mut self, purpose: &'a str) -> QrResult<Self> {
+        if purpose.len() != 4 || !purpose.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(QrError::InvalidField("purpose"));
+        }
+        self.purpose = Some(purpose);
+        self.check_len("purpose")
+    }
+
+    /// Sets a structured creditor reference, such as an ISO 11649 `RF`
+    /// reference.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is longer than 35
+    /// characters, or the unstructured text is set too.
+    pub fn reference(mut self, reference: &'a str) -> QrResult<Self> {
+        check_text(reference, 35, "reference")?;
+        if self.text.is_some() {
+            return Err(QrError::InvalidField("reference"));
+        }
+        self.reference = Some(reference);
+        self.check_len("reference")
+    }
+
+    /// Sets the unstructured remittance text shown to the beneficiary.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is longer than 140
+    /// characters, or the structured reference is set too.
+    pub fn text(mut self, text: &'a str) -> QrResult<Self> {
+        check_text(text, 140, "text")?;
+        if self.reference.is_some() {
+            return Err(QrError::InvalidField("text"));
+        }
+        self.text = Some(text);
+        self.check_len("text")
+    }
+
+    /// Sets a note from the beneficiary to the payer, which apps show but do
+    /// not transfer.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if it is longer than 70
+    /// characters.
+    pub fn information(mut self, information: &'a str) -> QrResult<Self> {
+        check_text(information, 70, "information")?;
+        self.information = Some(information);
+        self.check_len("information")
+    }
+
+    /// Checks that the payload still fits into `MAX_LEN` bytes, which only
+    /// the longest fields with many multi-byte characters exceed.
+    fn check_len(self, field: &'static str) -> QrResult<Self> {
+        let mut counter = Counter(0);
+        write!(counter, "{}", self).map_err(|_| QrError::InvalidField(field))?;
+        if counter.0 > MAX_LEN {
+            return Err(QrError::InvalidField(field));
+        }
+        Ok(self)
+    }
+}
+
+/// Checks that `text` fits on one line of at most `max` characters.
+fn check_text(text: &str, max: usize, field: &'static str) -> QrResult<()> {
+    if text.chars().count() > max || text.contains(char::is_control) {
+        return Err(QrError::InvalidField(field));
+    }
+    Ok(())
+}
+
+/// Checks the format and the check digits of an IBAN, ignoring spaces.
+fn is_valid_iban(iban: &str) -> bool {
+    let compact = || iban.bytes().filter(|&b| b != b' ');
+    let len = compact().count();
+    let mut head = compact();
+    let country = head.by_ref().take(2).all(|b| b.is_ascii_alphabetic());
+    let check = head.take(2).all(|b| b.is_ascii_digit());
+    if !(15..=34).contains(&len) || !country || !check || !compact().all(|b| b.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // ISO 7064 MOD 97-10: move the first four characters to the end, replace
+    // letters by 10 to 35, and the number must leave a remainder of 1.
+    let remainder = compact().skip(4).chain(compact().take(4)).fold(0_u32, |remainder, b| {
+        let value = (b as char).to_digit(36).unwrap_or(0);
+        let shift = if value < 10 { 10 } else { 100 };
+        (remainder * shift + value) % 97
+    });
+    remainder == 1
+}
+
+/// Counts the bytes written.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Writes an IBAN or BIC in upper case, without spaces.
+struct Compact<'a>(&'a str);
+
+impl fmt::Display for Compact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0.bytes().filter(|&b| b != b' ') {
+            f.write_char(b.to_ascii_uppercase() as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes an amount in euro cents, without trailing zeros.
+struct Amount(u64);
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (euros, cents) = (self.0 / 100, self.0 % 100);
+        match cents {
+            0 => write!(f, "EUR{}", euros),
+            _ if cents % 10 == 0 => write!(f, "EUR{}.{}", euros, cents / 10),
+            _ => write!(f, "EUR{}.{:02}", euros, cents),
+        }
+    }
+}
+
+impl fmt::Display for Epc<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Version 001 requires the BIC, version 002 makes it optional.
+        let version = if self.bic.is_some() { "001" } else { "002" };
+        let bic = self.bic.map(Compact);
+        let iban = Compact(self.iban);
+        let amount = self.amount.map(Amount);
+        let fields: [Option<&dyn fmt::Display>; 12] = [
+            Some(&"BCD"),
+            Some(&version),
+            Some(&"1"),
+            Some(&"SCT"),
+            bic.as_ref().map(|bic| bic as _),
+            Some(&self.name),
+            Some(&iban),
+            amount.as_ref().map(|amount| amount as _),
+            self.purpose.as_ref().map(|purpose| purpose as _),
+            self.reference.as_ref().map(|reference| reference as _),
+            self.text.as_ref().map(|text| text as _),
+            self.information.as_ref().map(|information| information as _),
+        ];
+        // Trailing empty fields are left out, with their line breaks.
+        let len = fields.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+        for (i, field) in fields.iter().take(len).enumerate() {
+            if i > 0 {
+                f.write_char('\n')?;
+            }
+            if let Some(field) = field {
+                field.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod epc_tests {
+    use crate::payloads::epc::{is_valid_iban, Epc};
+    use crate::types::QrError;
+
+    #[test]
+    fn test_iban() {
+        assert!(is_valid_iban("DE89370400440532013000"));
+        assert!(is_valid_iban("GB82 WEST 1234 5698 7654 32"));
+        assert!(is_valid_iban("at611904300234573201"));
+        assert!(!is_valid_iban("DE89370400440532013001"));
+        assert!(!is_valid_iban("DE8937040044"));
+        assert!(!is_valid_iban("D189370400440532013000"));
+        assert!(!is_valid_iban("DE89-3704-0044-0532-0130-00"));
+        assert!(!is_valid_iban(""));
+    }
+
+    #[test]
+    fn test_format() {
+        let minimal = Epc::new("Jane Doe", "at61 1904 3002 3457 3201").unwrap();
+        assert_eq!(minimal.to_string(), "BCD\n002\n1\nSCT\n\nJane Doe\nAT611904300234573201");
+
+        let full = Epc::new("Bäckerei Müller", "DE89370400440532013000")
+            .and_then(|epc| epc.amount(1_000_000))
+            .and_then(|epc| epc.purpose("GDDS"))
+            .and_then(|epc| epc.reference("RF18539007547034"))
+            .and_then(|epc| epc.information("Danke!"))
+            .unwrap();
+        assert_eq!(
+            full.to_string(),
+            "BCD\n002\n1\nSCT\n\nBäckerei Müller\nDE89370400440532013000\nEUR10000\nGDDS\nRF18539007547034\n\nDanke!"
+        );
+
+        let amounts = [(1, "EUR0.01"), (10, "EUR0.1"), (1205, "EUR12.05"), (99_999_999_999, "EUR999999999.99")];
+        for &(cents, expected) in &amounts {
+            let epc = minimal.amount(cents).unwrap();
+            assert!(epc.to_string().ends_with(expected));
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let iban = "DE89370400440532013000";
+        assert_eq!(Epc::new("", iban), Err(QrError::InvalidField("name")));
+        assert_eq!(Epc::new(&"x".repeat(71), iban), Err(QrError::InvalidField("name")));
+        assert_eq!(Epc::new("Line\nbreak", iban), Err(QrError::InvalidField("name")));
+        assert_eq!(Epc::new("Jane", "DE00370400440532013000"), Err(QrError::InvalidField("iban")));
+
+        let (name, text) = ("ü".repeat(70), "a".repeat(140));
+        let epc = Epc::new(&name, iban).unwrap();
+        assert!(epc.bic("COBADEFF").is_ok() && epc.bic("cobadeffxxx").is_ok());
+        assert_eq!(epc.bic("COBADEF"), Err(QrError::InvalidField("bic")));
+        assert_eq!(epc.amount(0), Err(QrError::InvalidField("amount")));
+        assert_eq!(epc.amount(100_000_000_000), Err(QrError::InvalidField("amount")));
+        assert_eq!(epc.purpose("GDD"), Err(QrError::InvalidField("purpose")));
+        assert_eq!(epc.reference(&"1".repeat(36)), Err(QrError::InvalidField("reference")));
+        assert_eq!(epc.text(&"a".repeat(141)), Err(QrError::InvalidField("text")));
+        let with_text = epc.text("Invoice").unwrap();
+        assert_eq!(with_text.reference("RF18539007547034"), Err(QrError::InvalidField("reference")));
+        let with_reference = epc.reference("RF18539007547034").unwrap();
+        assert_eq!(with_reference.text("Invoice"), Err(QrError::InvalidField("text")));
+
+        // Each field fits on its own, but with two-byte characters the
+        // payload grows past 331 bytes.
+        let long = epc.text(&text).unwrap();
+        assert_eq!(long.to_string().len(), 322);
+        assert!(long.information("üüüü").is_ok());
+        assert_eq!(long.information("üüüüü"), Err(QrError::InvalidField("information")));
+    }
+}
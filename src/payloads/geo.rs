@@ -0,0 +1,134 @@
+//! Geographic locations, as `geo:` URIs (RFC 5870) which map apps open at the
+//! given point.
+//!
+//!     use qrcode::payloads::geo::Geo;
+//!
+//!     let office = Geo::new(52.5163, 13.3777).unwrap();
+//!     assert_eq!(office.to_string(), "geo:52.5163,13.3777");
+//!
+//!     let summit = Geo::with_altitude(45.8326, 6.8652, 4805.59).unwrap().query("Mont Blanc");
+//!     assert_eq!(summit.to_string(), "geo:45.8326,6.8652,4805.59?q=Mont%20Blanc");
+//!
+//! The coordinates are written in the shortest decimal form which reads back
+//! as the same number, with a `.` separator and never with an exponent,
+//! whatever the locale. Round them before building the location to get fewer
+//! digits, and a smaller code.
+
+use core::fmt;
+
+use crate::payloads::PercentEncoded;
+use crate::types::{QrError, QrResult};
+
+/// A location in WGS 84 coordinates. See the module documentation.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Geo<'a> {
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+    query: Option<&'a str>,
+}
+
+impl<'a> Geo<'a> {
+    /// The location at `latitude` and `longitude`, in degrees.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the latitude is not between -90
+    /// and 90, or the longitude not between -180 and 180.
+    pub fn new(latitude: f64, longitude: f64) -> QrResult<Self> {
+        // Written as negated comparisons, so NaN is rejected too.
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(QrError::InvalidField("latitude"));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(QrError::InvalidField("longitude"));
+        }
+        Ok(Geo { latitude, longitude, altitude: None, query: None })
+    }
+
+    /// The location at `latitude` and `longitude`, in degrees, `altitude`
+    /// meters above sea level.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if a coordinate is out of range,
+    /// or the altitude is not finite.
+    pub fn with_altitude(latitude: f64, longitude: f64, altitude: f64) -> QrResult<Self> {
+        if !altitude.is_finite() {
+            return Err(QrError::InvalidField("altitude"));
+        }
+        Ok(Geo { altitude: Some(altitude), ..Geo::new(latitude, longitude)? })
+    }
+
+    /// Sets a search query, such as the name of the place, which most map
+    /// apps show or look up near the location.
+    pub const fn query(mut self, query: &'a str) -> Self {
+        self.query = Some(query);
+        self
+    }
+
+    /// The latitude, in degrees.
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    /// The longitude, in degrees.
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    /// The altitude, in meters, if any.
+    pub fn altitude(&self) -> Option<f64> {
+        self.altitude
+    }
+}
+
+/// Writes a finite number in decimal.
+struct Decimal(f64);
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // RFC 5870 treats "-0" as 0, but some readers do not.
+        if self.0 == 0.0 {
+            f.write_str("0")
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl fmt::Display for Geo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "geo:{},{}", Decimal(self.latitude), Decimal(self.longitude))?;
+        if let Some(altitude) = self.altitude {
+            write!(f, ",{}", Decimal(altitude))?;
+        }
+        if let Some(query) = self.query {
+            write!(f, "?q={}", PercentEncoded { text: query, keep: b"" })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod geo_tests {
+    use crate::payloads::geo::Geo;
+    use crate::types::QrError;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Geo::new(0.0, -0.0).unwrap().to_string(), "geo:0,0");
+        assert_eq!(Geo::new(-33.8568, 151.2153).unwrap().to_string(), "geo:-33.8568,151.2153");
+        assert_eq!(Geo::new(90.0, -180.0).unwrap().to_string(), "geo:90,-180");
+        assert_eq!(Geo::new(1e-7, 0.1 + 0.2).unwrap().to_string(), "geo:0.0000001,0.30000000000000004");
+        assert_eq!(Geo::with_altitude(-0.5, 10.0, -413.0).unwrap().to_string(), "geo:-0.5,10,-413");
+        let geo = Geo::new(48.8584, 2.2945).unwrap().query("Tour Eiffel, Paris");
+        assert_eq!(geo.to_string(), "geo:48.8584,2.2945?q=Tour%20Eiffel%2C%20Paris");
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(Geo::new(90.5, 0.0), Err(QrError::InvalidField("latitude")));
+        assert_eq!(Geo::new(f64::NAN, 0.0), Err(QrError::InvalidField("latitude")));
+        assert_eq!(Geo::new(0.0, -180.1), Err(QrError::InvalidField("longitude")));
+        assert_eq!(Geo::new(0.0, f64::INFINITY), Err(QrError::InvalidField("longitude")));
+        assert_eq!(Geo::with_altitude(0.0, 0.0, f64::NAN), Err(QrError::InvalidField("altitude")));
+        assert_eq!(Geo::with_altitude(91.0, 0.0, 1.0), Err(QrError::InvalidField("latitude")));
+    }
+}
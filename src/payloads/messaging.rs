@@ -0,0 +1,178 @@
+//! Phone calls, text messages and e-mails, as `TEL:`, `SMSTO:` and `MAILTO:`
+//! payloads.
+//!
+//!     use qrcode::payloads::messaging::{Mail, Sms, Tel};
+//!
+//!     assert_eq!(Tel::new("+1 555 0100").to_string(), "TEL:+1-555-0100");
+//!     assert_eq!(Sms::new("+15550100").message("STOP").to_string(), "SMSTO:+15550100:STOP");
+//!
+//!     let mail = Mail::new("support@example.com").subject("Order #42").body("Hello,\nit broke.");
+//!     assert_eq!(
+//!         mail.to_string(),
+//!         "MAILTO:support@example.com?subject=Order%20%2342&body=Hello%2C%0D%0Ait%20broke.",
+//!     );
+//!
+//! The schemes are written in upper case, which readers accept as URI schemes
+//! are case-insensitive, so a phone number payload like `TEL:+1-555-0100`
+//! only holds characters of the alphanumeric mode and takes less space.
+
+use core::fmt;
+
+use crate::payloads::PercentEncoded;
+
+/// A phone number to call.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Tel<'a> {
+    /// The number, as it should be dialed. Spaces are written as `-`, the
+    /// visual separator of RFC 3966.
+    pub number: &'a str,
+}
+
+impl<'a> Tel<'a> {
+    /// A call to `number`.
+    pub const fn new(number: &'a str) -> Self {
+        Tel { number }
+    }
+}
+
+impl fmt::Display for Tel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TEL:")?;
+        for (i, part) in self.number.split(' ').filter(|part| !part.is_empty()).enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            f.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// A text message to send.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Sms<'a> {
+    /// The number to send the message to.
+    pub number: &'a str,
+
+    /// The text to prefill, if any.
+    pub message: Option<&'a str>,
+}
+
+impl<'a> Sms<'a> {
+    /// A message to `number`, without text.
+    pub const fn new(number: &'a str) -> Self {
+        Sms { number, message: None }
+    }
+
+    /// Sets the text to prefill. It needs no escaping, as readers split the
+    /// payload at the first two colons only.
+    pub const fn message(mut self, message: &'a str) -> Self {
+        self.message = Some(message);
+        self
+    }
+}
+
+impl fmt::Display for Sms<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SMSTO:{}", self.number)?;
+        if let Some(message) = self.message {
+            write!(f, ":{}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// An e-mail to write, as a `mailto:` URI (RFC 6068).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Mail<'a> {
+    /// The address to send the e-mail to.
+    pub to: &'a str,
+
+    /// The subject to prefill, if any.
+    pub subject: Option<&'a str>,
+
+    /// The body to prefill, if any. Line breaks are written as CRLF, as
+    /// RFC 6068 requires.
+    pub body: Option<&'a str>,
+}
+
+impl<'a> Mail<'a> {
+    /// An e-mail to `to`, without subject or body.
+    pub const fn new(to: &'a str) -> Self {
+        Mail { to, subject: None, body: None }
+    }
+
+    /// Sets the subject.
+    pub const fn subject(mut self, subject: &'a str) -> Self {
+        self.subject = Some(subject);
+        self
+    }
+
+    /// Sets the body.
+    pub const fn body(mut self, body: &'a str) -> Self {
+        self.body = Some(body);
+        self
+    }
+}
+
+/// The characters of an address which are left as they are, besides the
+/// unreserved ones.
+const ADDRESS: &[u8] = b"@!$'()*+,;:=";
+
+fn encoded(text: &str) -> PercentEncoded<'_> {
+    PercentEncoded { text, keep: b"" }
+}
+
+impl fmt::Display for Mail<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MAILTO:{}", PercentEncoded { text: self.to, keep: ADDRESS })?;
+        let mut separator = '?';
+        if let Some(subject) = self.subject {
+            write!(f, "{}subject={}", separator, encoded(subject))?;
+            separator = '&';
+        }
+        if let Some(body) = self.body {
+            write!(f, "{}body=", separator)?;
+            for (i, line) in body.split('\n').enumerate() {
+                if i > 0 {
+                    f.write_str("%0D%0A")?;
+                }
+                write!(f, "{}", encoded(line.strip_suffix('\r').unwrap_or(line)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod messaging_tests {
+    use crate::payloads::encode;
+    use crate::payloads::messaging::{Mail, Sms, Tel};
+    use crate::spec::{EcLevelM, Version1};
+
+    #[test]
+    fn test_tel() {
+        assert_eq!(Tel::new("+15550100").to_string(), "TEL:+15550100");
+        assert_eq!(Tel::new(" +49  30 1234 ").to_string(), "TEL:+49-30-1234");
+
+        // 15 characters in alphanumeric mode take 4 + 9 + 83 bits, which fit
+        // into the 128 bits of version 1-M, unlike the 4 + 8 + 120 of bytes.
+        assert!(encode::<Version1<EcLevelM>>(&Tel::new("+1 555 0100")).is_ok());
+    }
+
+    #[test]
+    fn test_sms() {
+        assert_eq!(Sms::new("+15550100").to_string(), "SMSTO:+15550100");
+        assert_eq!(Sms::new("12345").message("Time: 10:30; ok?").to_string(), "SMSTO:12345:Time: 10:30; ok?");
+    }
+
+    #[test]
+    fn test_mail() {
+        assert_eq!(Mail::new("a@example.com").to_string(), "MAILTO:a@example.com");
+        assert_eq!(Mail::new("a@example.com").body("x").to_string(), "MAILTO:a@example.com?body=x");
+        assert_eq!(
+            Mail::new("\"odd one\"@example.com").subject("Grüße & more").body("1\r\n2\n").to_string(),
+            "MAILTO:%22odd%20one%22@example.com?subject=Gr%C3%BC%C3%9Fe%20%26%20more&body=1%0D%0A2%0D%0A"
+        );
+    }
+}
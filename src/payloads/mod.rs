@@ -0,0 +1,167 @@
+//! The `payloads` module builds the text of well-known QR code payloads, such
+//! as Wi-Fi network credentials, contact cards, locations, payments and
+//! messages, with the escaping and validation their formats require.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::payloads::{self, wifi::Wifi};
+//!     use qrcode::spec::{Version4, EcLevelM};
+//!
+//!     let wifi = Wifi::wpa("Home; 5GHz", "correct horse").hidden(true);
+//!     assert_eq!(wifi.to_string(), r"WIFI:T:WPA;S:Home\; 5GHz;P:correct horse;H:true;;");
+//!
+//!     let code: QrCode<Version4<EcLevelM>> = payloads::encode(&wifi).unwrap();
+//!
+//! Every payload implements `Display`, so it can be written into any
+//! `fmt::Write`, such as a `heapless::String`, without allocating.
+
+use core::fmt::{self, Write};
+
+use heapless::{ArrayLength, Vec};
+
+use crate::bits::Bits;
+use crate::spec::QrSpec;
+use crate::types::{Mode, QrError, QrResult};
+use crate::QrCode;
+
+pub mod base45;
+pub mod contact;
+pub mod crypto;
+pub mod epc;
+pub mod geo;
+pub mod messaging;
+pub mod shc;
+pub mod wifi;
+
+/// Collects the text of a payload, counting the bytes which do not fit.
+struct Buffer<N: ArrayLength<u8>> {
+    bytes: Vec<u8, N>,
+    len: usize,
+}
+
+impl<N: ArrayLength<u8>> Write for Buffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Once a piece did not fit, the text is too long anyway.
+        if self.len == self.bytes.len() {
+            let _ = self.bytes.extend_from_slice(s.as_bytes());
+        }
+        self.len = self.len.saturating_add(s.len());
+        Ok(())
+    }
+}
+
+/// Encodes the text of a payload with `QrCode::new`, using a buffer on the
+/// stack instead of allocating a string.
+///
+/// The buffer holds `V::TextSize` bytes, the most characters `V` can encode:
+/// 45 for version 1-L, up to 7094 for version 40-L.
+///
+/// Returns `Err(QrError::DataTooLong)` if the text does not fit into `V`, and
+/// `Err(QrError::InvalidSymbol)` if formatting the payload fails, which the
+/// payloads of this module never do.
+pub fn encode<V: QrSpec>(payload: &impl fmt::Display) -> QrResult<QrCode<V>> {
+    let mut buffer = Buffer::<V::TextSize> { bytes: Vec::new(), len: 0 };
+    // Writing into the buffer never fails, but a `Display` implementation
+    // may.
+    write!(buffer, "{}", payload).map_err(|_| QrError::InvalidSymbol)?;
+    if buffer.len > buffer.bytes.len() {
+        // Too long for `V`. Report the bits needed as if all of it were
+        // digits, the densest encoding there is.
+        let header = V::VERSION.mode_bits_count() + Mode::Numeric.length_bits_count(V::VERSION);
+        let needed = Mode::Numeric.data_bits_count(buffer.len).saturating_add(header);
+        return Err(QrError::DataTooLong { needed, available: Bits::<V>::new().max_len(V::EC_LEVEL)? });
+    }
+    QrCode::new(&buffer.bytes)
+}
+
+/// Writes `text`, escaping every character of `special` with a backslash.
+struct Escaped<'a> {
+    text: &'a str,
+    special: &'static [char],
+}
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for part in self.text.split_inclusive(self.special) {
+            match part.strip_suffix(self.special) {
+                Some(head) => {
+                    f.write_str(head)?;
+                    f.write_char('\\')?;
+                    f.write_str(&part[head.len()..])?;
+                }
+                None => f.write_str(part)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `text` percent-encoded, keeping only the unreserved characters of
+/// RFC 3986 and the ASCII characters of `keep` as they are.
+struct PercentEncoded<'a> {
+    text: &'a str,
+    keep: &'static [u8],
+}
+
+impl fmt::Display for PercentEncoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut start = 0;
+        for (i, b) in self.text.bytes().enumerate() {
+            if !(b.is_ascii_alphanumeric() || b"-._~".contains(&b) || self.keep.contains(&b)) {
+                // Only unreserved ASCII lies between `start` and `i`, so both
+                // are character boundaries.
+                if start < i {
+                    f.write_str(&self.text[start..i])?;
+                }
+                write!(f, "%{:02X}", b)?;
+                start = i + 1;
+            }
+        }
+        f.write_str(&self.text[start..])
+    }
+}
+
+#[cfg(test)]
+mod payloads_tests {
+    use crate::payloads::{encode, Escaped, PercentEncoded};
+    use crate::spec::{EcLevelL, EcLevelM, QrSpec, Version1, Version40};
+    use crate::types::QrError;
+    use crate::QrCode;
+    use typenum::Unsigned;
+
+    #[test]
+    fn test_escaped() {
+        let escaped = |text| Escaped { text, special: &[';', '\\'] }.to_string();
+        assert_eq!(escaped("plain"), "plain");
+        assert_eq!(escaped(r"a;b\c;;"), r"a\;b\\c\;\;");
+        assert_eq!(escaped("Grüße;"), r"Grüße\;");
+    }
+
+    #[test]
+    fn test_percent_encoded() {
+        let encoded = |text| PercentEncoded { text, keep: b"" }.to_string();
+        assert_eq!(encoded("Az09-._~"), "Az09-._~");
+        assert_eq!(encoded("a b&c=d?"), "a%20b%26c%3Dd%3F");
+        assert_eq!(encoded("Grüße!"), "Gr%C3%BC%C3%9Fe%21");
+        assert_eq!(encoded("ü"), "%C3%BC");
+        assert_eq!(PercentEncoded { text: "a@b c", keep: b"@" }.to_string(), "a@b%20c");
+    }
+
+    #[test]
+    fn test_encode() {
+        let code = encode::<Version1<EcLevelM>>(&"01234567").unwrap();
+        assert!(code == QrCode::new(b"01234567").unwrap());
+
+        let digits = "1".repeat(7089);
+        assert!(encode::<Version40<EcLevelL>>(&digits).is_ok());
+        let err = encode::<Version40<EcLevelL>>(&(digits + "1")).err();
+        assert_eq!(err, Some(QrError::DataTooLong { needed: 23_652, available: 23_648 }));
+
+        // Version 1 only buffers 45 bytes, so longer text is rejected before
+        // encoding.
+        assert_eq!(<Version1<EcLevelL> as QrSpec>::TextSize::USIZE, 45);
+        let err = encode::<Version1<EcLevelL>>(&"1".repeat(42)).err();
+        assert_eq!(err, Some(QrError::DataTooLong { needed: 154, available: 152 }));
+        let err = encode::<Version1<EcLevelL>>(&"1".repeat(46)).err();
+        assert_eq!(err, Some(QrError::DataTooLong { needed: 168, available: 152 }));
+    }
+}
@@ -0,0 +1,178 @@
+//! SMART Health Cards, as `shc:/` payloads holding the signed card (a JWS) in
+//! the numeric encoding of the specification: every character becomes two
+//! digits, its code minus 45, so the bulk of the payload is encoded in the
+//! dense numeric mode.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::payloads::{self, shc::Shc};
+//!     use qrcode::spec::{Version22, EcLevelL};
+//!
+//!     let card = Shc::new("eyJ6aXAiOiJERUYifQ.x.y")?;
+//!     assert_eq!(card.to_string(), "shc:/56762909524320603460292437404460573601750176");
+//!
+//!     // The specification allows up to version 22, with error correction
+//!     // level L.
+//!     let code: QrCode<Version22<EcLevelL>> = payloads::encode(&card)?;
+//!     # Ok::<(), qrcode::types::QrError>(())
+//!
+//! A JWS longer than `MAX_CHUNK_LEN` characters does not fit into one code.
+//! `Shc::chunks` splits it over several, which the specification deprecates
+//! but older readers still need.
+
+use core::fmt;
+
+use crate::types::{QrError, QrResult};
+
+/// The most JWS characters the specification puts into one code.
+pub const MAX_CHUNK_LEN: usize = 1191;
+
+/// A SMART Health Card. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Shc<'a> {
+    jws: &'a str,
+}
+
+impl<'a> Shc<'a> {
+    /// The card signed as `jws`, in compact serialization.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the JWS holds characters other
+    /// than the base64url alphabet and `.`.
+    pub fn new(jws: &'a str) -> QrResult<Self> {
+        if !jws.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b)) {
+            return Err(QrError::InvalidField("jws"));
+        }
+        Ok(Shc { jws })
+    }
+
+    /// Splits the card into chunks of at most `max_len` JWS characters each,
+    /// or one if `max_len` is 0. The chunks are of about equal length, as the
+    /// specification asks.
+    pub fn chunks(&self, max_len: usize) -> ShcChunks<'a> {
+        let total = match max_len {
+            0 => 1,
+            _ => self.jws.len().div_ceil(max_len).max(1),
+        };
+        ShcChunks { rest: self.jws, index: 0, total }
+    }
+}
+
+/// Writes the numeric encoding of a JWS.
+struct Numeric<'a>(&'a str);
+
+impl fmt::Display for Numeric<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0.bytes() {
+            write!(f, "{:02}", b - b'-')?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Shc<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "shc:/{}", Numeric(self.jws))
+    }
+}
+
+/// One of the chunks of a card, written as `shc:/index/total/` and the
+/// numeric encoding of its part of the JWS.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ShcChunk<'a> {
+    /// The part of the JWS in this chunk.
+    pub jws: &'a str,
+
+    /// The position of the chunk, from 1.
+    pub index: usize,
+
+    /// The number of chunks.
+    pub total: usize,
+}
+
+impl fmt::Display for ShcChunk<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "shc:/{}/{}/{}", self.index, self.total, Numeric(self.jws))
+    }
+}
+
+/// An iterator over the chunks of a card. See `Shc::chunks`.
+#[derive(Debug, Clone)]
+pub struct ShcChunks<'a> {
+    rest: &'a str,
+    index: usize,
+    total: usize,
+}
+
+impl<'a> Iterator for ShcChunks<'a> {
+    type Item = ShcChunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.total {
+            return None;
+        }
+        // Spread the characters left over the chunks left, so the lengths
+        // differ by one at most. The JWS is ASCII, so any split is valid.
+        let len = self.rest.len().div_ceil(self.total - self.index);
+        let (jws, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        self.index += 1;
+        Some(ShcChunk { jws, index: self.index, total: self.total })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.total - self.index;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ShcChunks<'_> {}
+
+#[cfg(test)]
+mod shc_tests {
+    use crate::decode::decode;
+    use crate::payloads::encode;
+    use crate::payloads::shc::{Shc, MAX_CHUNK_LEN};
+    use crate::spec::{EcLevelL, QrSpec, Version22};
+    use crate::types::{Color, QrError};
+
+    #[test]
+    fn test_numeric() {
+        assert_eq!(Shc::new("").unwrap().to_string(), "shc:/");
+        assert_eq!(Shc::new("-.09AZ_az").unwrap().to_string(), "shc:/000103122045505277");
+        assert_eq!(Shc::new("a b"), Err(QrError::InvalidField("jws")));
+        assert_eq!(Shc::new("a=b"), Err(QrError::InvalidField("jws")));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let jws = "abcdefghij".repeat(250);
+        let card = Shc::new(&jws).unwrap();
+        let chunks = card.chunks(MAX_CHUNK_LEN).collect::<Vec<_>>();
+        assert_eq!(chunks.iter().map(|chunk| chunk.jws.len()).collect::<Vec<_>>(), [834, 833, 833]);
+        assert_eq!(chunks.iter().map(|chunk| chunk.jws).collect::<String>(), jws);
+        assert_eq!((chunks[2].index, chunks[2].total), (3, 3));
+        assert!(chunks[0].to_string().starts_with("shc:/1/3/5253545556"));
+
+        assert_eq!(card.chunks(2500).len(), 1);
+        assert_eq!(card.chunks(0).len(), 1);
+        assert_eq!(
+            Shc::new("ab").unwrap().chunks(1).map(|chunk| chunk.to_string()).collect::<Vec<_>>(),
+            ["shc:/1/2/52", "shc:/2/2/53"]
+        );
+        assert_eq!(
+            Shc::new("").unwrap().chunks(MAX_CHUNK_LEN).map(|chunk| chunk.to_string()).collect::<Vec<_>>(),
+            ["shc:/1/1/"]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        type V = Version22<EcLevelL>;
+        let jws = "eyJhbGciOiJFUzI1NiJ9.".repeat(56) + &"x".repeat(MAX_CHUNK_LEN - 56 * 21);
+        let card = Shc::new(&jws).unwrap();
+        let code = encode::<V>(&card).unwrap();
+        let modules = code.colors().take(V::AREA).collect::<Vec<Color>>();
+        let mut out = [0; 4096];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], card.to_string().as_bytes());
+    }
+}
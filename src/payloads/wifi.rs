@@ -0,0 +1,238 @@
+//! Wi-Fi network credentials, in the `WIFI:` format understood by the camera
+//! apps of Android and iOS.
+//!
+//!     use qrcode::payloads::wifi::{Eap, EapMethod, Phase2, Wifi};
+//!
+//!     let home = Wifi::wpa("Home", "correct horse");
+//!     assert_eq!(home.to_string(), "WIFI:T:WPA;S:Home;P:correct horse;;");
+//!
+//!     let eap = Eap::new(EapMethod::Peap, "alice", "s3cret").phase2(Phase2::MsChapV2);
+//!     let office = Wifi::eap("Office", eap).hidden(true);
+//!     assert_eq!(
+//!         office.to_string(),
+//!         "WIFI:T:WPA2-EAP;S:Office;E:PEAP;PH2:MSCHAPV2;I:alice;P:s3cret;H:true;;",
+//!     );
+//!
+//! The SSID, password and EAP fields are escaped, so they may contain any of
+//! the special characters `\`, `;`, `,`, `:` and `"`.
+
+use core::fmt;
+
+use crate::payloads::Escaped;
+
+/// The characters which must be escaped in the fields.
+const SPECIAL: &[char] = &['\\', ';', ',', ':', '"'];
+
+fn escaped(text: &str) -> Escaped<'_> {
+    Escaped { text, special: SPECIAL }
+}
+
+/// The authentication of a network.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Auth<'a> {
+    /// An open network, without a password.
+    Open,
+
+    /// WEP, with the given key.
+    Wep(&'a str),
+
+    /// WPA, WPA2 or WPA3 personal, with the given passphrase.
+    Wpa(&'a str),
+
+    /// WPA2 enterprise, authenticating with EAP.
+    Eap(Eap<'a>),
+}
+
+/// The EAP method of an enterprise network.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EapMethod {
+    /// Protected EAP, tunnelling an inner authentication.
+    Peap,
+
+    /// Tunnelled TLS, tunnelling an inner authentication.
+    Ttls,
+
+    /// TLS with a client certificate.
+    Tls,
+
+    /// Password authentication without a tunnel.
+    Pwd,
+}
+
+impl EapMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+            EapMethod::Tls => "TLS",
+            EapMethod::Pwd => "PWD",
+        }
+    }
+}
+
+/// The inner authentication of PEAP and TTLS.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Phase2 {
+    /// Plain password.
+    Pap,
+
+    /// Microsoft CHAP.
+    MsChap,
+
+    /// Microsoft CHAP version 2.
+    MsChapV2,
+
+    /// Generic token card.
+    Gtc,
+}
+
+impl Phase2 {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase2::Pap => "PAP",
+            Phase2::MsChap => "MSCHAP",
+            Phase2::MsChapV2 => "MSCHAPV2",
+            Phase2::Gtc => "GTC",
+        }
+    }
+}
+
+/// The credentials of a WPA2 enterprise network.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Eap<'a> {
+    /// The EAP method.
+    pub method: EapMethod,
+
+    /// The inner authentication, if the method has one.
+    pub phase2: Option<Phase2>,
+
+    /// The identity to authenticate as.
+    pub identity: &'a str,
+
+    /// The identity sent unencrypted before the tunnel is set up, if it
+    /// differs from `identity`.
+    pub anonymous_identity: Option<&'a str>,
+
+    /// The password.
+    pub password: &'a str,
+}
+
+impl<'a> Eap<'a> {
+    /// Credentials for the given method, without inner authentication or
+    /// anonymous identity.
+    pub const fn new(method: EapMethod, identity: &'a str, password: &'a str) -> Self {
+        Eap { method, phase2: None, identity, anonymous_identity: None, password }
+    }
+
+    /// Sets the inner authentication.
+    pub const fn phase2(mut self, phase2: Phase2) -> Self {
+        self.phase2 = Some(phase2);
+        self
+    }
+
+    /// Sets the anonymous identity.
+    pub const fn anonymous_identity(mut self, anonymous_identity: &'a str) -> Self {
+        self.anonymous_identity = Some(anonymous_identity);
+        self
+    }
+}
+
+/// The credentials of a Wi-Fi network. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Wifi<'a> {
+    /// The name of the network.
+    pub ssid: &'a str,
+
+    /// How to authenticate.
+    pub auth: Auth<'a>,
+
+    /// Whether the network hides its SSID, so devices must probe for it.
+    pub hidden: bool,
+}
+
+impl<'a> Wifi<'a> {
+    /// An open network.
+    pub const fn open(ssid: &'a str) -> Self {
+        Wifi { ssid, auth: Auth::Open, hidden: false }
+    }
+
+    /// A WEP network.
+    pub const fn wep(ssid: &'a str, key: &'a str) -> Self {
+        Wifi { ssid, auth: Auth::Wep(key), hidden: false }
+    }
+
+    /// A WPA, WPA2 or WPA3 personal network.
+    pub const fn wpa(ssid: &'a str, passphrase: &'a str) -> Self {
+        Wifi { ssid, auth: Auth::Wpa(passphrase), hidden: false }
+    }
+
+    /// A WPA2 enterprise network.
+    pub const fn eap(ssid: &'a str, eap: Eap<'a>) -> Self {
+        Wifi { ssid, auth: Auth::Eap(eap), hidden: false }
+    }
+
+    /// Sets whether the network hides its SSID.
+    pub const fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+}
+
+impl fmt::Display for Wifi<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.auth {
+            Auth::Open => "nopass",
+            Auth::Wep(_) => "WEP",
+            Auth::Wpa(_) => "WPA",
+            Auth::Eap(_) => "WPA2-EAP",
+        };
+        write!(f, "WIFI:T:{};S:{};", kind, escaped(self.ssid))?;
+        match self.auth {
+            Auth::Open => {}
+            Auth::Wep(password) | Auth::Wpa(password) => write!(f, "P:{};", escaped(password))?,
+            Auth::Eap(eap) => {
+                write!(f, "E:{};", eap.method.as_str())?;
+                if let Some(phase2) = eap.phase2 {
+                    write!(f, "PH2:{};", phase2.as_str())?;
+                }
+                if let Some(anonymous_identity) = eap.anonymous_identity {
+                    write!(f, "A:{};", escaped(anonymous_identity))?;
+                }
+                write!(f, "I:{};P:{};", escaped(eap.identity), escaped(eap.password))?;
+            }
+        }
+        if self.hidden {
+            f.write_str("H:true;")?;
+        }
+        f.write_str(";")
+    }
+}
+
+#[cfg(test)]
+mod wifi_tests {
+    use crate::payloads::wifi::{Eap, EapMethod, Phase2, Wifi};
+
+    #[test]
+    fn test_auth() {
+        assert_eq!(Wifi::open("Cafe").to_string(), "WIFI:T:nopass;S:Cafe;;");
+        assert_eq!(Wifi::wep("Legacy", "0123456789").to_string(), "WIFI:T:WEP;S:Legacy;P:0123456789;;");
+        assert_eq!(Wifi::wpa("Home", "pass").hidden(true).to_string(), "WIFI:T:WPA;S:Home;P:pass;H:true;;");
+        let eap = Eap::new(EapMethod::Ttls, "bob", "pw").phase2(Phase2::Pap).anonymous_identity("anonymous");
+        assert_eq!(
+            Wifi::eap("Campus", eap).to_string(),
+            "WIFI:T:WPA2-EAP;S:Campus;E:TTLS;PH2:PAP;A:anonymous;I:bob;P:pw;;"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let wifi = Wifi::wpa(r#""Bob's" net: 2,4\5"#, "a;b");
+        assert_eq!(wifi.to_string(), r#"WIFI:T:WPA;S:\"Bob's\" net\: 2\,4\\5;P:a\;b;;"#);
+        let eap = Eap::new(EapMethod::Peap, r"DOMAIN\alice", "p:w").anonymous_identity("anon;");
+        assert_eq!(
+            Wifi::eap("Corp", eap).to_string(),
+            r"WIFI:T:WPA2-EAP;S:Corp;E:PEAP;A:anon\;;I:DOMAIN\\alice;P:p\:w;;"
+        );
+        assert_eq!(Wifi::open("Café ☕").to_string(), "WIFI:T:nopass;S:Café ☕;;");
+    }
+}
@@ -0,0 +1,2769 @@
+//! The `render` module maps the modules of a QR code into output pixels, and
+//! renders whole images.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::spec::{Version1, EcLevelM};
+//!
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+//!     let luma: Vec<u8> = code.pixels().collect();
+//!     assert_eq!(&luma[..8], &[0, 0, 0, 0, 0, 0, 0, 255]);
+//!
+//! `Text` renders a code as lines of characters, into any `fmt::Write` or a
+//! `heapless::String` sized at compile time, and `Dense` packs several
+//! modules into every character to fit into a terminal, or draws them as
+//! full blocks for terminals whose fonts leave gaps between the others.
+//!
+//! The image renderers write their output piece by piece. `Svg` writes into
+//! any `fmt::Write`, and with the `std` feature, `Svg` and `Png` also stream
+//! into any `io::Write`, such as a file or a socket, without building the
+//! whole image in memory first. `Gif` animates several codes in one image.
+//! `Eps` writes Encapsulated PostScript and `Pdf` a one-page PDF document for
+//! print, with the colors in CMYK.
+//! `Pbm` writes portable bitmaps and `EscPos` the raster command of receipt
+//! printers, through a callback which receives a few bytes at a time.
+//!
+//! With the `embedded-io` feature, the text and image renderers other than
+//! `Png` and `Gif` also stream into any `embedded_io::Write` with
+//! `write_embedded`, such as the UART or USB CDC port of a microcontroller.
+//!
+//! `Caption` prints a short line of text, such as a serial number, below the
+//! symbol in `Png` images and in `Layout::pixels_with_caption` for frame
+//! buffers, with a built-in bitmap font.
+
+#[cfg(feature = "std")]
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "embedded-io")]
+use embedded_io::WriteFmtError;
+
+use heapless::consts::{U1, U2, U32};
+use heapless::{ArrayLength, String};
+use typenum::{Prod, Sum};
+
+use crate::spec::QrSpec;
+use crate::types::{Color, QrError, QrResult};
+use crate::QrCode;
+
+//------------------------------------------------------------------------------
+//{{{ Pixel
+
+/// A pixel type which renderers produce for dark and light modules.
+///
+/// Implement this trait to render into a custom pixel type:
+///
+///     use qrcode::render::Pixel;
+///     use qrcode::types::Color;
+///
+///     #[derive(Copy, Clone)]
+///     struct Rgb565(u16);
+///
+///     impl Pixel for Rgb565 {
+///         fn from_color(color: Color) -> Self {
+///             Rgb565(color.select(0x0000, 0xffff))
+///         }
+///     }
+pub trait Pixel: Copy {
+    /// The pixel for a module of the given color.
+    fn from_color(color: Color) -> Self;
+}
+
+impl Pixel for Color {
+    fn from_color(color: Color) -> Self {
+        color
+    }
+}
+
+/// `true` for dark modules.
+impl Pixel for bool {
+    fn from_color(color: Color) -> Self {
+        color.into()
+    }
+}
+
+/// 8-bit luma, 0 for dark and 255 for light modules.
+impl Pixel for u8 {
+    fn from_color(color: Color) -> Self {
+        color.into()
+    }
+}
+
+/// 8-bit RGB, black for dark and white for light modules.
+impl Pixel for [u8; 3] {
+    fn from_color(color: Color) -> Self {
+        [color.into(); 3]
+    }
+}
+
+/// 8-bit RGBA, opaque black for dark and opaque white for light modules.
+impl Pixel for [u8; 4] {
+    fn from_color(color: Color) -> Self {
+        let luma = color.into();
+        [luma, luma, luma, 255]
+    }
+}
+
+/// A full block for dark and a space for light modules.
+impl Pixel for char {
+    fn from_color(color: Color) -> Self {
+        color.select('\u{2588}', ' ')
+    }
+}
+
+#[cfg(test)]
+mod pixel_tests {
+    use crate::render::Pixel;
+    use crate::types::Color;
+
+    #[test]
+    fn test_builtin_pixels() {
+        assert!(bool::from_color(Color::Dark));
+        assert_eq!(u8::from_color(Color::Light), 255);
+        assert_eq!(<[u8; 3]>::from_color(Color::Dark), [0, 0, 0]);
+        assert_eq!(<[u8; 4]>::from_color(Color::Light), [255, 255, 255, 255]);
+        assert_eq!(char::from_color(Color::Dark), '█');
+        assert_eq!(Color::from_color(Color::Light), Color::Light);
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Layout
+
+/// The geometry of a rendered image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// The width of the light border around the symbol, in modules. The
+    /// standard asks for at least 4.
+    pub quiet_zone: u32,
+    /// The width and height of a module, in pixels.
+    pub module_size: u32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self { quiet_zone: 4, module_size: 8 }
+    }
+}
+
+impl Layout {
+    /// The width and height of the image of a symbol `width` modules wide, in
+    /// modules, saturating at `u32::MAX`.
+    pub const fn modules(&self, width: i16) -> u32 {
+        (width as u32).saturating_add(self.quiet_zone.saturating_mul(2))
+    }
+
+    /// The width and height of the image of a symbol `width` modules wide, in
+    /// pixels, saturating at `u32::MAX`.
+    pub const fn image_size(&self, width: i16) -> u32 {
+        self.modules(width).saturating_mul(self.module_size)
+    }
+
+    /// Iterates the pixels of the image of `code` in row-major order, each
+    /// module repeated `module_size` times in both directions, surrounded by
+    /// the quiet zone. See `QrCode::scaled_pixels`.
+    pub fn pixels<'a, V: QrSpec, P: Pixel + 'a>(&self, code: &'a QrCode<V>) -> impl ExactSizeIterator<Item = P> + 'a {
+        let layout = *self;
+        let size = layout.image_size(V::WIDTH) as usize;
+        let module_size = layout.module_size as usize;
+        (0..size.saturating_mul(size)).map(move |i| {
+            let (x, y) = (i % size / module_size, i / size / module_size);
+            P::from_color(layout.color(code, x as u32, y as u32))
+        })
+    }
+
+    /// The color of the module at the image coordinates (x, y), in modules,
+    /// where the quiet zone is light.
+    pub(crate) fn color<V: QrSpec>(&self, code: &QrCode<V>, x: u32, y: u32) -> Color {
+        let (x, y) = (x.wrapping_sub(self.quiet_zone), y.wrapping_sub(self.quiet_zone));
+        if x < V::WIDTH as u32 && y < V::WIDTH as u32 {
+            code.get(x as i16, y as i16)
+        } else {
+            Color::Light
+        }
+    }
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use crate::render::Layout;
+    use crate::spec::{EcLevelL, Version2};
+    use crate::types::Color;
+    use crate::QrCode;
+
+    #[test]
+    fn test_pixels() {
+        let code = QrCode::<Version2<EcLevelL>>::new(b"scaled pixels").unwrap();
+        let layout = Layout { quiet_zone: 2, module_size: 3 };
+        let size = 29 * 3;
+        let pixels: Vec<Color> = layout.pixels(&code).collect();
+        assert_eq!(pixels.len(), size * size);
+        for (i, &pixel) in pixels.iter().enumerate() {
+            assert_eq!(pixel, layout.color(&code, (i % size / 3) as u32, (i / size / 3) as u32));
+        }
+        assert!(code.scaled_pixels::<Color>(1, 0).eq(code.pixels::<Color>()));
+        assert_eq!(code.scaled_pixels::<bool>(0, 4).len(), 0);
+    }
+
+    #[test]
+    fn test_saturating_size() {
+        let layout = Layout { quiet_zone: u32::MAX / 2 + 1, module_size: 1 };
+        assert_eq!((layout.modules(21), layout.image_size(21)), (u32::MAX, u32::MAX));
+        let layout = Layout { quiet_zone: 1 << 12, module_size: 1 << 20 };
+        assert_eq!(layout.image_size(21), u32::MAX);
+
+        let code = QrCode::<Version2<EcLevelL>>::new(b"scaled pixels").unwrap();
+        let size = u32::MAX as usize;
+        assert_eq!(code.scaled_pixels::<Color>(1 << 20, 1 << 12).len(), size.saturating_mul(size));
+    }
+}
+
+/// Forwards a `fmt::Write` into an `io::Write`, keeping the first error.
+#[cfg(feature = "std")]
+struct IoAdapter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// Forwards a `fmt::Write` into an `embedded_io::Write`, keeping the first
+/// error.
+#[cfg(feature = "embedded-io")]
+struct EmbeddedAdapter<W: embedded_io::Write> {
+    inner: W,
+    error: Option<W::Error>,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<W: embedded_io::Write> fmt::Write for EmbeddedAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// Runs a renderer writing into a `fmt::Write` on an `embedded_io::Write`.
+#[cfg(feature = "embedded-io")]
+fn write_embedded_fmt<W, F>(out: W, write: F) -> Result<(), WriteFmtError<W::Error>>
+where
+    W: embedded_io::Write,
+    F: FnOnce(&mut EmbeddedAdapter<W>) -> fmt::Result,
+{
+    let mut adapter = EmbeddedAdapter { inner: out, error: None };
+    write(&mut adapter).map_err(|fmt::Error| adapter.error.take().map_or(WriteFmtError::FmtError, WriteFmtError::Other))
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Caption
+
+/// A line of text printed beneath the symbol in raster images, such as a
+/// serial number or "SCAN ME", in a built-in 5×7 pixel font.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Caption, Layout};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"SN 4711").unwrap();
+///     let layout = Layout::default();
+///     let caption = Caption { scale: 4, ..Caption::new("SN 4711") };
+///     let pixels: Vec<u8> = layout.pixels_with_caption(&code, &caption).collect();
+///     assert_eq!(pixels.len(), 232 * (232 + 32));
+///
+/// The caption goes below the quiet zone, centered, in a band of
+/// `Caption::height` pixels. Text wider than the image is cut off on both
+/// sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caption<'a> {
+    /// The text. Characters outside of printable ASCII are drawn as `?`.
+    pub text: &'a str,
+    /// The width and height of a pixel of the font, in image pixels.
+    pub scale: u32,
+}
+
+/// The width of a glyph of `FONT`, in font pixels.
+const GLYPH_WIDTH: u32 = 5;
+
+/// The height of a glyph of `FONT`, in font pixels.
+const GLYPH_HEIGHT: u32 = 7;
+
+/// The glyphs of the printable ASCII characters from `' '` to `'~'`, as
+/// columns from left to right, with the top row in the lowest bit.
+#[rustfmt::skip]
+static FONT: [[u8; GLYPH_WIDTH as usize]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], // ' ' !
+    [0x00, 0x07, 0x00, 0x07, 0x00], [0x14, 0x7f, 0x14, 0x7f, 0x14], // " #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], // $ %
+    [0x36, 0x49, 0x56, 0x20, 0x50], [0x00, 0x00, 0x07, 0x00, 0x00], // & '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], [0x00, 0x41, 0x22, 0x1c, 0x00], // ( )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], [0x08, 0x08, 0x3e, 0x08, 0x08], // * +
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], // , -
+    [0x00, 0x60, 0x60, 0x00, 0x00], [0x20, 0x10, 0x08, 0x04, 0x02], // . /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00], // 0 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], // 2 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], [0x27, 0x45, 0x45, 0x45, 0x39], // 4 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03], // 6 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], // 8 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], [0x00, 0x56, 0x36, 0x00, 0x00], // : ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], // < =
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], // > ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], [0x7e, 0x11, 0x11, 0x11, 0x7e], // @ A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22], // B C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], // D E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], [0x3e, 0x41, 0x49, 0x49, 0x7a], // F G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00], // H I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], // J K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], [0x7f, 0x02, 0x0c, 0x02, 0x7f], // L M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e], // N O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], // P Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], [0x46, 0x49, 0x49, 0x49, 0x31], // R S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f], // T U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x3f, 0x40, 0x38, 0x40, 0x3f], // V W
+    [0x63, 0x14, 0x08, 0x14, 0x63], [0x07, 0x08, 0x70, 0x08, 0x07], // X Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00], // Z [
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], // \\ ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], [0x40, 0x40, 0x40, 0x40, 0x40], // ^ _
+    [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], // ` a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], // b c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], [0x38, 0x54, 0x54, 0x54, 0x18], // d e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], [0x0c, 0x52, 0x52, 0x52, 0x3e], // f g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], // h i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], [0x7f, 0x10, 0x28, 0x44, 0x00], // j k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78], // l m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], // n o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], [0x08, 0x14, 0x14, 0x18, 0x7c], // p q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20], // r s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], // t u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], [0x3c, 0x40, 0x30, 0x40, 0x3c], // v w
+    [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c], // x y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], // z {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], [0x00, 0x41, 0x36, 0x08, 0x00], // | }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+impl<'a> Caption<'a> {
+    /// Creates a caption of the text at a scale of 1.
+    pub const fn new(text: &'a str) -> Self {
+        Self { text, scale: 1 }
+    }
+
+    /// The height of the band holding the caption, in pixels: the height of
+    /// the font and a margin of one font pixel below it.
+    pub const fn height(&self) -> u32 {
+        (GLYPH_HEIGHT + 1).saturating_mul(self.scale)
+    }
+
+    /// The width of the text, in pixels, with one font pixel between the
+    /// characters.
+    pub fn width(&self) -> u32 {
+        let count = self.text.chars().count() as u32;
+        (GLYPH_WIDTH + 1).saturating_mul(count).saturating_sub(1).saturating_mul(self.scale)
+    }
+
+    /// The color of the pixel at (x, y) of the band of an image
+    /// `image_width` pixels wide.
+    pub(crate) fn color(&self, x: u32, y: u32, image_width: u32) -> Color {
+        if self.scale == 0 {
+            return Color::Light;
+        }
+        let left = (i64::from(image_width) - i64::from(self.width())) / 2;
+        let (x, row) = (i64::from(x) - left, y / self.scale);
+        if x < 0 || row >= GLYPH_HEIGHT {
+            return Color::Light;
+        }
+        let x = x as u64 / u64::from(self.scale);
+        let advance = u64::from(GLYPH_WIDTH + 1);
+        let column = (x % advance) as usize;
+        let glyph = match self.text.chars().nth((x / advance) as usize) {
+            Some(_) if column == GLYPH_WIDTH as usize => return Color::Light,
+            Some(c @ ' '..='~') => FONT[c as usize - 0x20],
+            Some(_) => FONT[usize::from(b'?' - 0x20)],
+            None => return Color::Light,
+        };
+        Color::from(glyph[column] >> row & 1 != 0)
+    }
+}
+
+impl Layout {
+    /// Iterates the pixels of the image of `code` like `Layout::pixels`, with
+    /// the caption in a band below it. The image is `Caption::height` pixels
+    /// taller than wide.
+    pub fn pixels_with_caption<'a, V: QrSpec, P: Pixel + 'a>(
+        &self,
+        code: &'a QrCode<V>,
+        caption: &Caption<'a>,
+    ) -> impl ExactSizeIterator<Item = P> + 'a {
+        let (layout, caption) = (*self, *caption);
+        let size = layout.image_size(V::WIDTH);
+        let height = size.saturating_add(caption.height());
+        (0..(size as usize).saturating_mul(height as usize)).map(move |i| {
+            let (x, y) = ((i % size as usize) as u32, (i / size as usize) as u32);
+            P::from_color(layout.caption_color(code, &caption, x, y))
+        })
+    }
+
+    /// The color of the pixel at (x, y) of the image of `code` with the
+    /// caption below it.
+    pub(crate) fn caption_color<V: QrSpec>(&self, code: &QrCode<V>, caption: &Caption, x: u32, y: u32) -> Color {
+        let size = self.image_size(V::WIDTH);
+        if y < size {
+            self.color(code, x / self.module_size, y / self.module_size)
+        } else {
+            caption.color(x, y - size, size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod caption_tests {
+    use crate::render::{Caption, Layout, FONT};
+    use crate::spec::{EcLevelL, Version1};
+    use crate::types::Color;
+    use crate::QrCode;
+
+    /// Draws the band of the caption as lines of `#` and `.`.
+    fn draw(caption: &Caption, width: u32) -> Vec<String> {
+        (0..caption.height())
+            .map(|y| (0..width).map(|x| caption.color(x, y, width).select('#', '.')).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_font() {
+        assert_eq!(FONT[usize::from(b'~' - b' ')], [0x10, 0x08, 0x08, 0x10, 0x08]);
+        assert!(FONT.iter().flatten().all(|column| column & 0x80 == 0));
+    }
+
+    #[test]
+    fn test_glyphs() {
+        let caption = Caption::new("H1");
+        assert_eq!((caption.width(), caption.height()), (11, 8));
+        assert_eq!(
+            draw(&caption, 13),
+            [
+                ".#...#...#...",
+                ".#...#..##...",
+                ".#...#...#...",
+                ".#####...#...",
+                ".#...#...#...",
+                ".#...#...#...",
+                ".#...#..###..",
+                ".............",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scale_and_clipping() {
+        let caption = Caption { scale: 2, ..Caption::new("é") };
+        assert_eq!(draw(&caption, 12)[..4], ["...######...", "...######...", ".##......##.", ".##......##."]);
+
+        // The bars are 6 pixels apart, and only the middle one fits into 3.
+        let caption = Caption::new("|||");
+        assert_eq!(draw(&caption, 21)[0], "....#.....#.....#....");
+        assert_eq!(draw(&caption, 3)[0], ".#.");
+        assert!(draw(&Caption { scale: 0, ..caption }, 9).is_empty());
+    }
+
+    #[test]
+    fn test_pixels_with_caption() {
+        let code = QrCode::<Version1<EcLevelL>>::new(b"caption").unwrap();
+        let layout = Layout { quiet_zone: 2, module_size: 2 };
+        let caption = Caption { scale: 3, ..Caption::new("SCAN ME") };
+        let size = 25 * 2;
+        let pixels: Vec<Color> = layout.pixels_with_caption(&code, &caption).collect();
+        assert_eq!(pixels.len(), size * (size + 24));
+        assert!(pixels[..size * size].iter().copied().eq(layout.pixels::<_, Color>(&code)));
+        assert!(pixels[size * size..].contains(&Color::Dark));
+        assert!(pixels[size * (size + 21)..].iter().all(|&p| p == Color::Light));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Text
+
+/// Renders a code as text, one character or string per module and one line
+/// per row of modules.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Text, TextCapacity};
+///     use qrcode::spec::{Version1, EcLevelM};
+///     use heapless::consts::{U1, U2, U32};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let text = Text { quiet_zone: 2, dark: '#', light: '.', ..Text::default() };
+///     let string = text.to_heapless::<_, TextCapacity<Version1<EcLevelM>, U2, U1>>(&code).unwrap();
+///     assert_eq!(string.lines().nth(2), Some("..#######..#.##.#######.."));
+///
+/// The text can be written into any `fmt::Write`, or, without an allocator,
+/// into a `heapless::String` whose capacity `TextCapacity` computes from the
+/// spec at compile time.
+///
+/// Serial displays and e-mail bodies differ in what they expect around the
+/// symbol, so the line ending, the padding of each line and whether the quiet
+/// zone rows above and below the symbol are written can be chosen.
+///
+/// The modules can also be written as strings, such as HTML entities or two
+/// characters per module, with `with_modules`:
+///
+///     # use qrcode::QrCode;
+///     # use qrcode::render::Text;
+///     # use qrcode::spec::{Version1, EcLevelM};
+///     # let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let text = Text { quiet_zone: 0, ..Text::default() }.with_modules("&#9608;&#9608;", "&nbsp;&nbsp;");
+///     let mut html = String::new();
+///     text.write(&code, &mut html).unwrap();
+///     assert!(html.starts_with("&#9608;&#9608;&#9608;"));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<M = char> {
+    /// The width of the light border around the symbol, in modules.
+    pub quiet_zone: u32,
+    /// The text of the dark modules.
+    pub dark: M,
+    /// The text of the light modules and the quiet zone.
+    pub light: M,
+    /// The end of every line, including the last.
+    pub line_ending: LineEnding,
+    /// The characters before and after every line.
+    pub padding: Padding,
+    /// Whether the rows of the quiet zone above and below the symbol are
+    /// written. The quiet zone on the left and right is always written.
+    pub quiet_rows: bool,
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self {
+            quiet_zone: 4,
+            dark: char::from_color(Color::Dark),
+            light: char::from_color(Color::Light),
+            line_ending: LineEnding::Lf,
+            padding: Padding::default(),
+            quiet_rows: true,
+        }
+    }
+}
+
+/// The end of a line of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`, as on Unix.
+    Lf,
+    /// `\r\n`, as in e-mail, HTTP and on many serial terminals.
+    CrLf,
+}
+
+impl LineEnding {
+    /// The characters ending a line.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Characters written before and after every line of text, such as spaces
+/// to indent the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Padding {
+    /// The number of characters before every line.
+    pub left: u32,
+    /// The number of characters after every line.
+    pub right: u32,
+    /// The character to pad with.
+    pub fill: char,
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Self { left: 0, right: 0, fill: ' ' }
+    }
+}
+
+/// The text of a module, a `char` or a `&str`.
+pub trait TextModule: Copy {
+    /// Writes the text into `out`.
+    fn write_to<W: fmt::Write>(self, out: &mut W) -> fmt::Result;
+    /// The number of bytes of the text in UTF-8.
+    fn len_utf8(self) -> usize;
+}
+
+impl TextModule for char {
+    fn write_to<W: fmt::Write>(self, out: &mut W) -> fmt::Result {
+        out.write_char(self)
+    }
+
+    fn len_utf8(self) -> usize {
+        char::len_utf8(self)
+    }
+}
+
+impl TextModule for &str {
+    fn write_to<W: fmt::Write>(self, out: &mut W) -> fmt::Result {
+        out.write_str(self)
+    }
+
+    fn len_utf8(self) -> usize {
+        self.len()
+    }
+}
+
+/// The capacity in bytes of the text of a `V` symbol, with a quiet zone of
+/// `Q` modules and modules of at most `B` bytes in UTF-8, as a type for
+/// `heapless::String`. This holds the text with the default line ending,
+/// padding and quiet rows, and anything shorter.
+pub type TextCapacity<V, Q, B> =
+    Prod<Sum<<V as QrSpec>::Width, Prod<Q, U2>>, Sum<Prod<Sum<<V as QrSpec>::Width, Prod<Q, U2>>, B>, U1>>;
+
+impl<M: TextModule> Text<M> {
+    /// Replaces the text of the dark and light modules, keeping the other
+    /// options.
+    pub fn with_modules<N: TextModule>(self, dark: N, light: N) -> Text<N> {
+        let Text { quiet_zone, line_ending, padding, quiet_rows, .. } = self;
+        Text { quiet_zone, dark, light, line_ending, padding, quiet_rows }
+    }
+
+    fn layout(&self) -> Layout {
+        Layout { quiet_zone: self.quiet_zone, module_size: 1 }
+    }
+
+    /// The rows of modules written, skipping the quiet zone above and below
+    /// the symbol unless `quiet_rows` is set.
+    fn rows<V: QrSpec>(&self) -> Range<u32> {
+        let modules = self.layout().modules(V::WIDTH);
+        if self.quiet_rows {
+            0..modules
+        } else {
+            self.quiet_zone..modules - self.quiet_zone
+        }
+    }
+
+    /// The most bytes the text of a `V` symbol takes, saturating at
+    /// `usize::MAX`. With the default options, this is the length
+    /// `TextCapacity` computes.
+    pub fn max_len<V: QrSpec>(&self) -> usize {
+        let modules = self.layout().modules(V::WIDTH) as usize;
+        let module_len = self.dark.len_utf8().max(self.light.len_utf8());
+        let padding = (self.padding.left as usize).saturating_add(self.padding.right as usize);
+        let line_len = modules
+            .saturating_mul(module_len)
+            .saturating_add(padding.saturating_mul(self.padding.fill.len_utf8()))
+            .saturating_add(self.line_ending.as_str().len());
+        self.rows::<V>().len().saturating_mul(line_len)
+    }
+
+    /// Writes the text into `out`.
+    pub fn write<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        let layout = self.layout();
+        let modules = layout.modules(V::WIDTH);
+        for y in self.rows::<V>() {
+            for _ in 0..self.padding.left {
+                out.write_char(self.padding.fill)?;
+            }
+            for x in 0..modules {
+                layout.color(code, x, y).select(self.dark, self.light).write_to(out)?;
+            }
+            for _ in 0..self.padding.right {
+                out.write_char(self.padding.fill)?;
+            }
+            out.write_str(self.line_ending.as_str())?;
+        }
+        Ok(())
+    }
+
+    /// Streams the text into `out`, such as a UART, without buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        out: W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        write_embedded_fmt(out, |out| self.write(code, out))
+    }
+
+    /// Renders the text into a `heapless::String`, without allocating.
+    ///
+    /// Returns `Err(QrError::BufferTooSmall)` if the capacity `N` is less
+    /// than `max_len`, which the matching `TextCapacity` never is with the
+    /// default options.
+    pub fn to_heapless<V: QrSpec, N: ArrayLength<u8>>(&self, code: &QrCode<V>) -> QrResult<String<N>> {
+        if self.max_len::<V>() > N::USIZE {
+            return Err(QrError::BufferTooSmall);
+        }
+        let mut string = String::new();
+        self.write(code, &mut string).map_err(|fmt::Error| QrError::BufferTooSmall)?;
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod text_tests {
+    use crate::render::{LineEnding, Padding, Text, TextCapacity};
+    use crate::spec::{EcLevelL, EcLevelM, Version1, Version40};
+    use crate::types::QrError;
+    use crate::QrCode;
+    use heapless::consts::{U0, U1, U3, U4};
+    use typenum::Unsigned;
+
+    #[test]
+    fn test_text() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let text = Text { quiet_zone: 0, dark: '#', light: '.', ..Text::default() };
+        let mut string = String::new();
+        text.write(&code, &mut string).unwrap();
+        assert_eq!(format!("{}\n", code.to_debug_str('#', '.').trim_start()), string);
+        assert_eq!(string.len(), text.max_len::<Version1<EcLevelM>>());
+
+        let heapless = text.to_heapless::<_, TextCapacity<Version1<EcLevelM>, U0, U1>>(&code).unwrap();
+        assert_eq!(heapless.as_str(), string);
+    }
+
+    #[test]
+    fn test_capacity() {
+        type V = Version40<EcLevelL>;
+        let text = Text::default();
+        assert_eq!(TextCapacity::<V, U4, U3>::USIZE, text.max_len::<V>());
+        assert_eq!(TextCapacity::<V, U4, U3>::USIZE, 185 * (185 * 3 + 1));
+
+        let code = QrCode::<V>::new(b"").unwrap();
+        let heapless = text.to_heapless::<_, TextCapacity<V, U4, U3>>(&code).unwrap();
+        assert_eq!(heapless.lines().count(), 185);
+        assert!(heapless.lines().all(|line| line.chars().count() == 185));
+        assert_eq!(text.to_heapless::<_, TextCapacity<V, U4, U1>>(&code), Err(QrError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_options() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let text = Text {
+            quiet_zone: 1,
+            dark: '#',
+            light: '.',
+            line_ending: LineEnding::CrLf,
+            padding: Padding { left: 2, right: 1, fill: '|' },
+            quiet_rows: false,
+        };
+        let mut string = String::new();
+        text.write(&code, &mut string).unwrap();
+        assert_eq!(string.len(), text.max_len::<Version1<EcLevelM>>());
+        let lines: Vec<_> = string.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 21);
+        assert_eq!(lines[0], "||.#######..#.##.#######.|");
+        assert_eq!(lines[20], "||.#######.####.#..#.#...|");
+
+        let with_rows = Text { quiet_rows: true, ..text };
+        let mut string = String::new();
+        with_rows.write(&code, &mut string).unwrap();
+        assert_eq!(string.len(), with_rows.max_len::<Version1<EcLevelM>>());
+        assert!(string.starts_with("||.......................|\r\n||.#######"));
+        assert!(string.ends_with("|\r\n||.......................|\r\n"));
+
+        // Longer than the default options, and so than `TextCapacity`.
+        let result = with_rows.to_heapless::<_, TextCapacity<Version1<EcLevelM>, U1, U1>>(&code);
+        assert_eq!(result, Err(QrError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_strings() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let text = Text { quiet_zone: 1, line_ending: LineEnding::CrLf, ..Text::default() };
+        let strings = text.with_modules("[]", "");
+        assert_eq!((strings.quiet_zone, strings.line_ending), (1, LineEnding::CrLf));
+
+        let mut string = String::new();
+        strings.write(&code, &mut string).unwrap();
+        let mut expected = String::new();
+        Text { dark: '#', light: '.', ..text }.write(&code, &mut expected).unwrap();
+        assert_eq!(string, expected.replace('#', "[]").replace('.', ""));
+        assert_eq!(strings.max_len::<Version1<EcLevelM>>(), 23 * (23 * 2 + 2));
+
+        // `CrLf` is longer than `TextCapacity` counts, so leave room with a
+        // larger `B`.
+        let heapless = strings.to_heapless::<_, TextCapacity<Version1<EcLevelM>, U1, U3>>(&code).unwrap();
+        assert_eq!(heapless.as_str(), string);
+        let back = strings.with_modules('#', '.');
+        assert_eq!(back, Text { dark: '#', light: '.', ..text });
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Dense text
+
+/// Block and Braille characters packing several modules into one, so that a
+/// code fits into a terminal. The denser the glyphs, the smaller the code,
+/// but the harder it is for fonts to draw them without gaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    /// Half blocks such as `▀`, 1 module wide and 2 high. These look square
+    /// in most terminals.
+    HalfBlock,
+    /// Quadrant blocks such as `▚`, 2 modules wide and 2 high.
+    Quadrant,
+    /// Braille patterns such as `⣿`, 2 modules wide and 4 high.
+    Braille,
+    /// Two full blocks `██` or two spaces per module. This takes the most
+    /// room, but draws square modules without gaps in any terminal and
+    /// font.
+    FullBlock,
+    /// One full block `█` or ideographic space per module, for terminals
+    /// which draw the block twice as wide, as those set up for CJK text do.
+    WideBlock,
+}
+
+/// The quadrant blocks, by their top left (1), top right (2), bottom left (4)
+/// and bottom right (8) dark quadrants.
+const QUADRANTS: [char; 16] = [' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'];
+
+/// The Braille dots, by row and column of the module in the cell.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Glyphs {
+    /// The glyphs packing several modules into one character, from the most
+    /// to the least legible, in the order `Dense::fit` tries them. This leaves
+    /// out `FullBlock` and `WideBlock`, which take more room than `HalfBlock`
+    /// and are only used when asked for.
+    pub const FIT_ORDER: [Glyphs; 3] = [Glyphs::HalfBlock, Glyphs::Quadrant, Glyphs::Braille];
+
+    /// The width and height of a character, in modules.
+    pub const fn cell(self) -> (u32, u32) {
+        match self {
+            Glyphs::HalfBlock => (1, 2),
+            Glyphs::Quadrant => (2, 2),
+            Glyphs::Braille => (2, 4),
+            Glyphs::FullBlock | Glyphs::WideBlock => (1, 1),
+        }
+    }
+
+    /// The columns of the terminal a character takes, or for `FullBlock`, the
+    /// two characters of a module.
+    pub const fn columns(self) -> u32 {
+        match self {
+            Glyphs::FullBlock | Glyphs::WideBlock => 2,
+            _ => 1,
+        }
+    }
+
+    /// The character of a cell, given whether each of its modules is dark,
+    /// by row and column.
+    fn glyph(self, dark: impl Fn(u32, u32) -> bool) -> char {
+        match self {
+            Glyphs::HalfBlock => QUADRANTS[usize::from(dark(0, 0)) * 3 + usize::from(dark(1, 0)) * 12],
+            Glyphs::Quadrant => {
+                let bits = (0..4).filter(|&i| dark(i / 2, i % 2)).fold(0, |bits, i| bits | 1 << i);
+                QUADRANTS[bits]
+            }
+            Glyphs::Braille => {
+                let mut bits = 0;
+                for (row, dots) in (0..).zip(&BRAILLE_DOTS) {
+                    for (column, dot) in (0..).zip(dots) {
+                        if dark(row, column) {
+                            bits |= dot;
+                        }
+                    }
+                }
+                char::from_u32(0x2800 + u32::from(bits)).unwrap_or(' ')
+            }
+            Glyphs::FullBlock => QUADRANTS[usize::from(dark(0, 0)) * 15],
+            Glyphs::WideBlock => {
+                if dark(0, 0) {
+                    '█'
+                } else {
+                    '\u{3000}'
+                }
+            }
+        }
+    }
+}
+
+/// Renders a code as text packing several modules into every character, see
+/// `Glyphs`.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Dense, Glyphs};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let dense = Dense { glyphs: Glyphs::Quadrant, ..Dense::default() };
+///     assert_eq!(dense.size::<Version1<EcLevelM>>(), (15, 15));
+///     let mut string = String::new();
+///     dense.write(&code, &mut string).unwrap();
+///
+/// `fit` picks the most legible glyphs for a given number of columns and
+/// rows, and with the `std` feature, `fit_terminal` for the size of the
+/// terminal, so that command line tools never print a truncated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dense {
+    /// The width of the light border around the symbol, in modules.
+    pub quiet_zone: u32,
+    /// The characters the modules are drawn with. Defaults to
+    /// `Glyphs::HalfBlock`.
+    pub glyphs: Glyphs,
+    /// Whether the light modules are drawn instead of the dark ones, for
+    /// terminals with light text on a dark background.
+    pub inverted: bool,
+    /// The end of every line, including the last.
+    pub line_ending: LineEnding,
+}
+
+impl Default for Dense {
+    fn default() -> Self {
+        Self { quiet_zone: 4, glyphs: Glyphs::HalfBlock, inverted: false, line_ending: LineEnding::Lf }
+    }
+}
+
+impl Dense {
+    /// The columns and rows of the terminal which the text of a `V` symbol
+    /// takes, saturating at `u32::MAX`. These count characters, except that a
+    /// `WideBlock` character takes two columns.
+    pub fn size<V: QrSpec>(&self) -> (u32, u32) {
+        let (cells, rows) = self.cells::<V>();
+        (cells.saturating_mul(self.glyphs.columns()), rows)
+    }
+
+    /// The cells of glyphs in a row, and the rows of the text.
+    fn cells<V: QrSpec>(&self) -> (u32, u32) {
+        let modules = Layout { quiet_zone: self.quiet_zone, module_size: 1 }.modules(V::WIDTH);
+        let (width, height) = self.glyphs.cell();
+        (modules.div_ceil(width), modules.div_ceil(height))
+    }
+
+    /// Picks the most legible glyphs with which the text of a `V` symbol fits
+    /// into `columns` and `rows` characters, keeping the other options.
+    ///
+    /// Returns `Err(QrError::BufferTooSmall)` if not even the Braille
+    /// patterns fit.
+    pub fn fit<V: QrSpec>(&self, columns: u32, rows: u32) -> QrResult<Self> {
+        Glyphs::FIT_ORDER
+            .iter()
+            .map(|&glyphs| Self { glyphs, ..*self })
+            .find(|dense| {
+                let (width, height) = dense.size::<V>();
+                width <= columns && height <= rows
+            })
+            .ok_or(QrError::BufferTooSmall)
+    }
+
+    /// Picks the glyphs like `fit`, for the size of the terminal on the
+    /// standard output, leaving one row for the prompt. If the size cannot be
+    /// read, such as when the output is redirected into a file, or on systems
+    /// other than Unix, the `COLUMNS` and `LINES` environment variables are
+    /// used, and failing those, the options are kept as they are.
+    #[cfg(feature = "std")]
+    pub fn fit_terminal<V: QrSpec>(&self) -> QrResult<Self> {
+        match terminal_size() {
+            Some((columns, rows)) => self.fit::<V>(columns, rows.saturating_sub(1)),
+            None => Ok(*self),
+        }
+    }
+
+    /// Writes the text into `out`.
+    pub fn write<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        let layout = Layout { quiet_zone: self.quiet_zone, module_size: 1 };
+        let (width, height) = self.glyphs.cell();
+        let (cells, rows) = self.cells::<V>();
+        let repeat = if self.glyphs == Glyphs::FullBlock { 2 } else { 1 };
+        for row in 0..rows {
+            for column in 0..cells {
+                let glyph = self.glyphs.glyph(|dy, dx| {
+                    // Saturating past the last module of a saturated layout,
+                    // which is light.
+                    let (x, y) = ((column * width).saturating_add(dx), (row * height).saturating_add(dy));
+                    let color = layout.color(code, x, y);
+                    (color == Color::Dark) != self.inverted
+                });
+                for _ in 0..repeat {
+                    out.write_char(glyph)?;
+                }
+            }
+            out.write_str(self.line_ending.as_str())?;
+        }
+        Ok(())
+    }
+
+    /// Streams the text into `out`, such as a UART, without buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        out: W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        write_embedded_fmt(out, |out| self.write(code, out))
+    }
+}
+
+/// The columns and rows of the terminal on the standard output.
+#[cfg(feature = "std")]
+fn terminal_size() -> Option<(u32, u32)> {
+    #[cfg(unix)]
+    {
+        if let Ok(size) = rustix::termios::tcgetwinsize(std::io::stdout()) {
+            if size.ws_col > 0 && size.ws_row > 0 {
+                return Some((u32::from(size.ws_col), u32::from(size.ws_row)));
+            }
+        }
+    }
+    let var = |name| std::env::var(name).ok()?.parse().ok();
+    Some((var("COLUMNS")?, var("LINES")?))
+}
+
+#[cfg(test)]
+mod dense_tests {
+    use crate::render::{Dense, Glyphs, LineEnding, Text, BRAILLE_DOTS, QUADRANTS};
+    use crate::spec::{EcLevelL, EcLevelM, QrSpec, Version1, Version40};
+    use crate::types::QrError;
+    use crate::QrCode;
+
+    /// Reads the modules back from the text, as a row of `Text` would show
+    /// them.
+    fn modules(string: &str, glyphs: Glyphs, width: usize) -> Vec<String> {
+        let (cell_width, cell_height) = glyphs.cell();
+        let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+        let mut rows = vec![String::new(); string.lines().count() * cell_height];
+        for (row, line) in string.lines().enumerate() {
+            for glyph in line.chars() {
+                for dy in 0..cell_height {
+                    for (dx, &dot) in BRAILLE_DOTS[dy].iter().enumerate().take(cell_width) {
+                        let dark = match glyphs {
+                            Glyphs::HalfBlock | Glyphs::Quadrant => {
+                                let index = QUADRANTS.iter().position(|&c| c == glyph).unwrap();
+                                let quadrant = if glyphs == Glyphs::HalfBlock { dy * 2 } else { dy * 2 + dx };
+                                (index >> quadrant) & 1 == 1
+                            }
+                            Glyphs::Braille => (u32::from(glyph) - 0x2800) & u32::from(dot) != 0,
+                            Glyphs::FullBlock | Glyphs::WideBlock => glyph == '█',
+                        };
+                        rows[row * cell_height + dy].push(if dark { '#' } else { '.' });
+                    }
+                }
+            }
+        }
+        rows.truncate(width);
+        rows.iter_mut().for_each(|row| row.truncate(width));
+        rows
+    }
+
+    #[test]
+    fn test_glyphs() {
+        type V = Version1<EcLevelM>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let mut expected = String::new();
+        Text { quiet_zone: 3, dark: '#', light: '.', ..Text::default() }.write(&code, &mut expected).unwrap();
+        let expected: Vec<_> = expected.lines().collect();
+
+        for glyphs in Glyphs::FIT_ORDER {
+            let dense = Dense { quiet_zone: 3, glyphs, ..Dense::default() };
+            let mut string = String::new();
+            dense.write(&code, &mut string).unwrap();
+            let (columns, rows) = dense.size::<V>();
+            assert_eq!(string.lines().count(), rows as usize);
+            assert!(string.lines().all(|line| line.chars().count() == columns as usize));
+            assert_eq!(modules(&string, glyphs, 27), expected, "{:?}", glyphs);
+        }
+    }
+
+    #[test]
+    fn test_inverted() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let dense = Dense { inverted: true, line_ending: LineEnding::CrLf, ..Dense::default() };
+        let mut string = String::new();
+        dense.write(&code, &mut string).unwrap();
+        assert!(string.starts_with("█████████████████████████████\r\n"));
+        assert_eq!(string.matches("\r\n").count(), 15);
+    }
+
+    #[test]
+    fn test_full_blocks() {
+        type V = Version1<EcLevelM>;
+        let code = QrCode::<V>::new(b"01234567").unwrap();
+        let mut expected = String::new();
+        Text { quiet_zone: 1, dark: '#', light: '.', ..Text::default() }.write(&code, &mut expected).unwrap();
+
+        let full = Dense { quiet_zone: 1, glyphs: Glyphs::FullBlock, ..Dense::default() };
+        assert_eq!(full.size::<V>(), (46, 23));
+        let mut string = String::new();
+        full.write(&code, &mut string).unwrap();
+        assert_eq!(string, expected.replace('#', "██").replace('.', "  "));
+
+        let wide = Dense { glyphs: Glyphs::WideBlock, inverted: true, ..full };
+        assert_eq!(wide.size::<V>(), (46, 23));
+        let mut string = String::new();
+        wide.write(&code, &mut string).unwrap();
+        assert_eq!(string, expected.replace('#', "\u{3000}").replace('.', "█"));
+    }
+
+    #[test]
+    fn test_fit() {
+        type V = Version40<EcLevelL>;
+        let dense = Dense::default();
+        assert_eq!(V::WIDTH, 177);
+        assert_eq!(dense.fit::<V>(185, 93).map(|d| d.glyphs), Ok(Glyphs::HalfBlock));
+        assert_eq!(dense.fit::<V>(184, 93).map(|d| d.glyphs), Ok(Glyphs::Quadrant));
+        assert_eq!(dense.fit::<V>(93, 92).map(|d| d.glyphs), Ok(Glyphs::Braille));
+        assert_eq!(dense.fit::<V>(93, 47).map(|d| d.glyphs), Ok(Glyphs::Braille));
+        assert_eq!(dense.fit::<V>(93, 46), Err(QrError::BufferTooSmall));
+        assert_eq!(dense.fit::<V>(92, 1000), Err(QrError::BufferTooSmall));
+
+        let small = Dense { quiet_zone: 1, inverted: true, ..Dense::default() };
+        assert_eq!(small.fit::<Version1<EcLevelM>>(80, 24), Ok(small));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ SVG
+
+/// Renders a code as an SVG image.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::Svg;
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut image = String::new();
+///     Svg { dark_color: "#800000", ..Svg::default() }.write(&code, &mut image).unwrap();
+///     assert!(image.starts_with("<?xml"));
+///
+/// Every horizontal run of dark modules is one subpath of a single `<path>`.
+///
+/// For print, `marks` adds crop marks, bleed and registration targets around
+/// the image, which is then sized in millimetres:
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{PrintMarks, Svg};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut image = String::new();
+///     let marks = PrintMarks { module_mm: 1.0, ..PrintMarks::default() };
+///     Svg { marks: Some(marks), ..Svg::default() }.write(&code, &mut image).unwrap();
+///     assert!(image.contains(r#"width="45mm" height="45mm""#));
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Svg<'a> {
+    pub layout: Layout,
+    /// The fill of the dark modules, in any SVG color syntax.
+    pub dark_color: &'a str,
+    /// The fill of the light modules and the quiet zone.
+    pub light_color: &'a str,
+    /// The print marks around the image, if any. `layout.module_size` is
+    /// ignored then, as `PrintMarks::module_mm` sets the size.
+    pub marks: Option<PrintMarks>,
+}
+
+impl Default for Svg<'_> {
+    fn default() -> Self {
+        Self { layout: Layout::default(), dark_color: "#000", light_color: "#fff", marks: None }
+    }
+}
+
+/// Crop marks, bleed and registration targets, as print shops ask for. All
+/// lengths are in millimetres, and the offsets count from the trim edge,
+/// which is the edge of the quiet zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintMarks {
+    /// The width and height of a module.
+    pub module_mm: f32,
+    /// How far the light background extends past the trim edge, so that no
+    /// unprinted edge shows if the cut is off.
+    pub bleed: f32,
+    /// The gap between the trim edge and the crop marks.
+    pub offset: f32,
+    /// The length of the crop marks. The registration targets are as wide.
+    pub length: f32,
+    /// The stroke width of the marks.
+    pub stroke: f32,
+    /// Whether to draw a registration target outside the middle of every
+    /// side.
+    pub registration: bool,
+}
+
+impl Default for PrintMarks {
+    fn default() -> Self {
+        Self { module_mm: 0.5, bleed: 3.0, offset: 3.0, length: 5.0, stroke: 0.25, registration: true }
+    }
+}
+
+impl PrintMarks {
+    /// The distance from the trim edge to the outer edge of the marks.
+    fn margin(&self) -> f32 {
+        self.offset + self.length
+    }
+
+    /// The crop marks around a trim box `trim` millimetres wide, as the ends
+    /// `[x1, y1, x2, y2]` of every line, with y counting down from the top
+    /// left corner of the trim box. The horizontal line of each corner comes
+    /// before the vertical one.
+    fn crop_marks(&self, trim: f32) -> impl Iterator<Item = [f32; 4]> {
+        let (near, far) = (self.offset, self.offset + self.length);
+        // `IntoIterator::into_iter`, as arrays iterate by reference in this
+        // edition.
+        IntoIterator::into_iter([(0.0, -1.0), (trim, 1.0)]).flat_map(move |(x, dx)| {
+            IntoIterator::into_iter([(0.0, -1.0), (trim, 1.0)]).flat_map(move |(y, dy)| {
+                IntoIterator::into_iter([[x + dx * near, y, x + dx * far, y], [x, y + dy * near, x, y + dy * far]])
+            })
+        })
+    }
+
+    /// The centers of the registration targets, outside the middle of every
+    /// side, in the coordinates of `crop_marks`. Each target is a cross as
+    /// wide as the crop marks are long, with a circle of a quarter of that
+    /// radius.
+    fn registration_targets(&self, trim: f32) -> impl Iterator<Item = (f32, f32)> {
+        let (middle, center) = (trim / 2.0, self.offset + self.length / 2.0);
+        let targets = [(middle, -center), (middle, trim + center), (-center, middle), (trim + center, middle)];
+        IntoIterator::into_iter(targets).take(if self.registration { 4 } else { 0 })
+    }
+
+    /// Writes the crop marks and registration targets around a trim box
+    /// `trim` millimetres wide as an SVG path.
+    fn write<W: fmt::Write>(&self, trim: f32, color: &str, out: &mut W) -> fmt::Result {
+        write!(out, r#"<path fill="none" stroke="{}" stroke-width="{}" d=""#, color, self.stroke)?;
+        for [x1, y1, x2, y2] in self.crop_marks(trim) {
+            if y1 == y2 {
+                write!(out, "M{} {}H{}", x1, y1, x2)?;
+            } else {
+                write!(out, "M{} {}V{}", x1, y1, y2)?;
+            }
+        }
+        let (half, radius) = (self.length / 2.0, self.length / 4.0);
+        for (x, y) in self.registration_targets(trim) {
+            write!(out, "M{} {}h{}M{} {}v{}", x - half, y, self.length, x, y - half, self.length)?;
+            write!(out, "M{} {}a{2} {2} 0 1 0 {3} 0a{2} {2} 0 1 0 -{3} 0", x - radius, y, radius, 2.0 * radius)?;
+        }
+        out.write_str(r#""/>"#)
+    }
+
+    /// Writes the crop marks and registration targets in PostScript, in the
+    /// current color. The origin must be at the bottom left corner of the
+    /// trim box, with millimetres as the unit.
+    fn write_ps<W: fmt::Write>(&self, trim: f32, out: &mut W) -> fmt::Result {
+        // Flip the y axis to draw in the coordinates of `crop_marks`.
+        writeln!(
+            out,
+            "{} setlinewidth\n0 {} translate 1 -1 scale\n/l {{ moveto lineto stroke }} bind def",
+            self.stroke, trim
+        )?;
+        for [x1, y1, x2, y2] in self.crop_marks(trim) {
+            writeln!(out, "{} {} {} {} l", x2, y2, x1, y1)?;
+        }
+        let (half, radius) = (self.length / 2.0, self.length / 4.0);
+        for (x, y) in self.registration_targets(trim) {
+            writeln!(out, "{} {} {} {} l", x + half, y, x - half, y)?;
+            writeln!(out, "{} {} {} {} l", x, y + half, x, y - half)?;
+            writeln!(out, "{} {} {} 0 360 arc stroke", x, y, radius)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the crop marks and registration targets as PDF path operators,
+    /// stroked in the current stroke color. The origin must be at the bottom
+    /// left corner of the trim box, with millimetres as the unit.
+    fn write_pdf<W: fmt::Write>(&self, trim: f32, out: &mut W) -> fmt::Result {
+        // Flip the y axis to draw in the coordinates of `crop_marks`.
+        writeln!(out, "{} w\n1 0 0 -1 0 {} cm", self.stroke, trim)?;
+        for [x1, y1, x2, y2] in self.crop_marks(trim) {
+            writeln!(out, "{} {} m {} {} l", x1, y1, x2, y2)?;
+        }
+        let (half, radius) = (self.length / 2.0, self.length / 4.0);
+        // PDF has no arcs, so every circle is four Bézier curves, one per
+        // quadrant, between the axes `u` and `v`.
+        let control = 0.552_284_8 * radius;
+        let axes = [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0), (1.0, 0.0)];
+        for (x, y) in self.registration_targets(trim) {
+            writeln!(out, "{} {} m {} {} l", x - half, y, x + half, y)?;
+            writeln!(out, "{} {} m {} {} l", x, y - half, x, y + half)?;
+            writeln!(out, "{} {} m", x + radius, y)?;
+            for pair in axes.windows(2) {
+                let ((ux, uy), (vx, vy)) = (pair[0], pair[1]);
+                writeln!(
+                    out,
+                    "{} {} {} {} {} {} c",
+                    x + radius * ux + control * vx,
+                    y + radius * uy + control * vy,
+                    x + control * ux + radius * vx,
+                    y + control * uy + radius * vy,
+                    x + radius * vx,
+                    y + radius * vy
+                )?;
+            }
+        }
+        out.write_str("S\n")
+    }
+}
+
+impl Svg<'_> {
+    /// Writes the image into `out`.
+    pub fn write<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        let modules = self.layout.modules(V::WIDTH);
+        match self.marks {
+            None => {
+                let size = self.layout.image_size(V::WIDTH);
+                write!(
+                    out,
+                    concat!(
+                        r#"<?xml version="1.0" standalone="yes"?>"#,
+                        r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{0}" height="{0}""#,
+                        r#" viewBox="0 0 {1} {1}" shape-rendering="crispEdges">"#,
+                        r#"<rect width="{1}" height="{1}" fill="{2}"/><path fill="{3}" d=""#
+                    ),
+                    size, modules, self.light_color, self.dark_color
+                )?;
+            }
+            Some(marks) => {
+                let trim = modules as f32 * marks.module_mm;
+                let margin = marks.margin();
+                write!(
+                    out,
+                    concat!(
+                        r#"<?xml version="1.0" standalone="yes"?>"#,
+                        r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{0}mm" height="{0}mm""#,
+                        r#" viewBox="-{1} -{1} {0} {0}" shape-rendering="crispEdges">"#,
+                        r#"<rect x="-{2}" y="-{2}" width="{3}" height="{3}" fill="{4}"/>"#,
+                        r#"<g transform="scale({5})"><path fill="{6}" d=""#
+                    ),
+                    trim + 2.0 * margin,
+                    margin,
+                    marks.bleed,
+                    trim + 2.0 * marks.bleed,
+                    self.light_color,
+                    marks.module_mm,
+                    self.dark_color
+                )?;
+            }
+        }
+        for y in 0..modules {
+            let mut x = 0;
+            while x < modules {
+                if self.layout.color(code, x, y) == Color::Light {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < modules && self.layout.color(code, x, y) == Color::Dark {
+                    x += 1;
+                }
+                write!(out, "M{} {}h{}v1h-{}z", start, y, x - start, x - start)?;
+            }
+        }
+        out.write_str(r#""/>"#)?;
+        if let Some(marks) = self.marks {
+            out.write_str("</g>")?;
+            marks.write(modules as f32 * marks.module_mm, self.dark_color, out)?;
+        }
+        out.write_str("</svg>")
+    }
+
+    /// Streams the image into `out`, such as a UART, without buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        out: W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        write_embedded_fmt(out, |out| self.write(code, out))
+    }
+
+    /// Streams the image into `out`.
+    #[cfg(feature = "std")]
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, out: W) -> io::Result<()> {
+        let mut adapter = IoAdapter { inner: out, error: None };
+        self.write(code, &mut adapter)
+            .map_err(|fmt::Error| adapter.error.take().unwrap_or_else(|| io::Error::other("formatter error")))
+    }
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use crate::render::{Layout, PrintMarks, Svg};
+    use crate::spec::{EcLevelM, Version1};
+    use crate::QrCode;
+
+    #[test]
+    fn test_svg() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut image = String::new();
+        let svg = Svg { layout: Layout { quiet_zone: 1, module_size: 4 }, ..Svg::default() };
+        svg.write(&code, &mut image).unwrap();
+        assert!(image.contains(r#"width="92" height="92" viewBox="0 0 23 23""#));
+        // The top row of the symbol, between the top edges of the finder
+        // patterns.
+        assert!(image.contains(r#"d="M1 1h7v1h-7zM10 1h1v1h-1zM12 1h2v1h-2zM15 1h7v1h-7zM1 2h1v1h-1z"#));
+        assert!(image.ends_with(r#"z"/></svg>"#));
+    }
+
+    #[test]
+    fn test_svg_print_marks() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut plain = String::new();
+        let layout = Layout { quiet_zone: 2, module_size: 4 };
+        Svg { layout, ..Svg::default() }.write(&code, &mut plain).unwrap();
+
+        let mut image = String::new();
+        let marks = PrintMarks { module_mm: 0.5, bleed: 2.0, offset: 3.0, length: 4.0, stroke: 0.25, registration: true };
+        Svg { layout, marks: Some(marks), ..Svg::default() }.write(&code, &mut image).unwrap();
+        // 25 modules of 0.5 mm, and 7 mm of marks on every side.
+        assert!(image.contains(r#"width="26.5mm" height="26.5mm" viewBox="-7 -7 26.5 26.5""#));
+        assert!(image.contains(r##"<rect x="-2" y="-2" width="16.5" height="16.5" fill="#fff"/>"##));
+        // The modules are drawn exactly like without marks.
+        let path = |svg: &str| svg.split(r##"<path fill="#000" d=""##).nth(1).unwrap().split('"').next().unwrap().to_owned();
+        assert_eq!(path(&image), path(&plain));
+        // The top left crop marks, and the top registration target.
+        assert!(image.contains(r#"stroke-width="0.25" d="M-3 0H-7M0 -3V-7M-3 12.5H-7M0 15.5V19.5"#));
+        assert!(image.contains("M4.25 -5h4M6.25 -7v4M5.25 -5a1 1 0 1 0 2 0a1 1 0 1 0 -2 0"));
+        assert_eq!(image.matches("a1 1 0 1 0").count(), 8);
+        assert!(image.ends_with(r#"0"/></svg>"#));
+
+        let mut image = String::new();
+        let marks = PrintMarks { registration: false, ..marks };
+        Svg { layout, marks: Some(marks), ..Svg::default() }.write(&code, &mut image).unwrap();
+        assert_eq!(image.matches("a1 1 0 1 0").count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_svg_io() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut expected = String::new();
+        Svg::default().write(&code, &mut expected).unwrap();
+        let mut bytes = Vec::new();
+        Svg::default().write_io(&code, &mut bytes).unwrap();
+        assert_eq!(bytes, expected.as_bytes());
+
+        let mut full = [0; 100];
+        let err = Svg::default().write_io(&code, &mut full[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ EPS
+
+/// A print color as ink coverages of cyan, magenta, yellow and black, in
+/// percent from 0 to 100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cmyk {
+    pub c: u8,
+    pub m: u8,
+    pub y: u8,
+    pub k: u8,
+}
+
+impl Cmyk {
+    /// 100% black ink alone, which print shops ask for barcodes instead of a
+    /// black composed of several inks, as those never register exactly.
+    pub const BLACK: Cmyk = Cmyk { c: 0, m: 0, y: 0, k: 100 };
+
+    /// No ink at all.
+    pub const WHITE: Cmyk = Cmyk { c: 0, m: 0, y: 0, k: 0 };
+
+    /// Every ink at full coverage, so that print marks show on every plate
+    /// and the registration of the plates can be checked against them.
+    pub const REGISTRATION: Cmyk = Cmyk { c: 100, m: 100, y: 100, k: 100 };
+}
+
+impl fmt::Display for Cmyk {
+    /// Writes the operands of the PostScript `setcmykcolor` and the PDF `k`
+    /// operators, with coverages above 100% clamped.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fraction = |percent: u8| f32::from(percent.min(100)) / 100.0;
+        write!(f, "{} {} {} {}", fraction(self.c), fraction(self.m), fraction(self.y), fraction(self.k))
+    }
+}
+
+/// Renders a code as an Encapsulated PostScript image for print, with the
+/// colors in CMYK.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Cmyk, Eps};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut image = String::new();
+///     Eps { dark: Cmyk { c: 100, m: 60, y: 0, k: 20 }, ..Eps::default() }.write(&code, &mut image).unwrap();
+///     assert!(image.starts_with("%!PS-Adobe-3.0 EPSF-3.0"));
+///     assert!(image.contains("1 0.6 0 0.2 setcmykcolor"));
+///
+/// `layout.module_size` is in points of 1/72 inch. Every horizontal run of
+/// dark modules is one filled rectangle.
+///
+/// With `marks`, the image is sized in millimetres instead, and the crop
+/// marks and registration targets are drawn in `Cmyk::REGISTRATION`. The
+/// light color, if any, extends into the bleed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eps {
+    pub layout: Layout,
+    /// The color of the dark modules. Defaults to `Cmyk::BLACK`.
+    pub dark: Cmyk,
+    /// The color of the light modules and the quiet zone, or `None` to leave
+    /// them unprinted. Defaults to `None`.
+    pub light: Option<Cmyk>,
+    /// The print marks around the image, if any. `layout.module_size` is
+    /// ignored then, as `PrintMarks::module_mm` sets the size.
+    pub marks: Option<PrintMarks>,
+}
+
+impl Default for Eps {
+    fn default() -> Self {
+        Self { layout: Layout::default(), dark: Cmyk::BLACK, light: None, marks: None }
+    }
+}
+
+/// The points of 1/72 inch in a millimetre.
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+
+impl Eps {
+    /// Writes the image into `out`.
+    pub fn write<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        let modules = self.layout.modules(V::WIDTH);
+        out.write_str("%!PS-Adobe-3.0 EPSF-3.0\n")?;
+        match self.marks {
+            None => {
+                write!(
+                    out,
+                    concat!(
+                        "%%BoundingBox: 0 0 {0} {0}\n",
+                        "%%LanguageLevel: 2\n",
+                        "%%EndComments\n",
+                        "gsave\n",
+                        "{1} dup scale\n",
+                    ),
+                    self.layout.image_size(V::WIDTH),
+                    self.layout.module_size
+                )?;
+                if let Some(light) = self.light {
+                    writeln!(out, "{} setcmykcolor 0 0 {1} {1} rectfill", light, modules)?;
+                }
+            }
+            Some(marks) => {
+                // Origin at the bottom left corner of the trim box, in
+                // millimetres.
+                let trim = modules as f32 * marks.module_mm;
+                let size = (trim + 2.0 * marks.margin()) * POINTS_PER_MM;
+                // Rounded up to whole points, without `f32::ceil` in `core`.
+                let bounding_size = size as u32 + u32::from((size as u32 as f32) < size);
+                write!(
+                    out,
+                    concat!(
+                        "%%BoundingBox: 0 0 {0} {0}\n",
+                        "%%HiResBoundingBox: 0 0 {1} {1}\n",
+                        "%%LanguageLevel: 2\n",
+                        "%%EndComments\n",
+                        "gsave\n",
+                        "{2} dup scale\n",
+                        "{3} dup translate\n",
+                    ),
+                    bounding_size,
+                    size,
+                    POINTS_PER_MM,
+                    marks.margin()
+                )?;
+                if let Some(light) = self.light {
+                    let bleed = marks.bleed;
+                    writeln!(out, "{} setcmykcolor -{1} -{1} {2} {2} rectfill", light, bleed, trim + 2.0 * bleed)?;
+                }
+                writeln!(out, "gsave\n{} dup scale", marks.module_mm)?;
+            }
+        }
+        // PostScript counts y upwards, so the top row is at `modules - 1`.
+        writeln!(out, "{} setcmykcolor\n/r {{ 1 rectfill }} bind def", self.dark)?;
+        for y in 0..modules {
+            let mut x = 0;
+            while x < modules {
+                if self.layout.color(code, x, y) == Color::Light {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < modules && self.layout.color(code, x, y) == Color::Dark {
+                    x += 1;
+                }
+                writeln!(out, "{} {} {} r", start, modules - 1 - y, x - start)?;
+            }
+        }
+        if let Some(marks) = self.marks {
+            writeln!(out, "grestore\n{} setcmykcolor", Cmyk::REGISTRATION)?;
+            marks.write_ps(modules as f32 * marks.module_mm, out)?;
+        }
+        out.write_str("grestore\n%%EOF\n")
+    }
+
+    /// Streams the image into `out`, such as a UART, without buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        out: W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        write_embedded_fmt(out, |out| self.write(code, out))
+    }
+
+    /// Streams the image into `out`.
+    #[cfg(feature = "std")]
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, out: W) -> io::Result<()> {
+        let mut adapter = IoAdapter { inner: out, error: None };
+        self.write(code, &mut adapter)
+            .map_err(|fmt::Error| adapter.error.take().unwrap_or_else(|| io::Error::other("formatter error")))
+    }
+}
+
+#[cfg(test)]
+mod eps_tests {
+    use crate::render::{Cmyk, Eps, Layout, PrintMarks};
+    use crate::spec::{EcLevelM, Version1};
+    use crate::QrCode;
+
+    #[test]
+    fn test_cmyk() {
+        assert_eq!(Cmyk::BLACK.to_string(), "0 0 0 1");
+        assert_eq!(Cmyk { c: 7, m: 50, y: 255, k: 0 }.to_string(), "0.07 0.5 1 0");
+    }
+
+    #[test]
+    fn test_eps() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut image = String::new();
+        let eps = Eps { layout: Layout { quiet_zone: 1, module_size: 3 }, ..Eps::default() };
+        eps.write(&code, &mut image).unwrap();
+        assert!(image.contains("%%BoundingBox: 0 0 69 69\n"));
+        assert!(image.contains("3 dup scale\n0 0 0 1 setcmykcolor\n"));
+        assert!(!image.contains("rectfill\n0"));
+        // The top row of the symbol, between the top edges of the finder
+        // patterns.
+        assert!(image.contains("} bind def\n1 21 7 r\n10 21 1 r\n12 21 2 r\n15 21 7 r\n1 20 1 r\n"));
+        assert!(image.contains("\n1 1 7 r\n"));
+        assert!(image.ends_with(" r\ngrestore\n%%EOF\n"));
+
+        let mut image = String::new();
+        Eps { light: Some(Cmyk::WHITE), ..eps }.write(&code, &mut image).unwrap();
+        assert!(image.contains("0 0 0 0 setcmykcolor 0 0 23 23 rectfill\n0 0 0 1 setcmykcolor\n"));
+    }
+
+    #[test]
+    fn test_eps_print_marks() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let layout = Layout { quiet_zone: 2, module_size: 3 };
+        let mut plain = String::new();
+        Eps { layout, ..Eps::default() }.write(&code, &mut plain).unwrap();
+
+        let mut image = String::new();
+        let marks = PrintMarks { module_mm: 0.5, bleed: 2.0, offset: 3.0, length: 4.0, stroke: 0.25, registration: true };
+        let eps = Eps { layout, light: Some(Cmyk::WHITE), marks: Some(marks), ..Eps::default() };
+        eps.write(&code, &mut image).unwrap();
+        // 25 modules of 0.5 mm, and 7 mm of marks on every side: 26.5 mm or
+        // 75.1 points.
+        assert!(image.contains("%%BoundingBox: 0 0 76 76\n%%HiResBoundingBox: 0 0 75.11811 75.11811\n"));
+        assert!(image.contains("gsave\n2.8346457 dup scale\n7 dup translate\n"));
+        assert!(image.contains("0 0 0 0 setcmykcolor -2 -2 16.5 16.5 rectfill\ngsave\n0.5 dup scale\n"));
+        // The modules are drawn exactly like without marks.
+        let modules = |eps: &str| eps.split("bind def\n").nth(1).unwrap().split("grestore").next().unwrap().to_owned();
+        assert_eq!(modules(&image), modules(&plain));
+        // The top left crop marks, and the top registration target.
+        assert!(image.contains("grestore\n1 1 1 1 setcmykcolor\n0.25 setlinewidth\n0 12.5 translate 1 -1 scale\n"));
+        assert!(image.contains("} bind def\n-7 0 -3 0 l\n0 -7 0 -3 l\n-7 12.5 -3 12.5 l\n0 19.5 0 15.5 l\n"));
+        assert!(image.contains("\n8.25 -5 4.25 -5 l\n6.25 -3 6.25 -7 l\n6.25 -5 1 0 360 arc stroke\n"));
+        assert_eq!(image.matches(" arc stroke\n").count(), 4);
+        assert!(image.ends_with(" arc stroke\ngrestore\n%%EOF\n"));
+
+        let mut image = String::new();
+        Eps { layout, marks: Some(PrintMarks { registration: false, ..marks }), ..Eps::default() }
+            .write(&code, &mut image)
+            .unwrap();
+        assert!(!image.contains("arc"));
+        assert!(!image.contains("rectfill\ngsave"));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ PDF
+
+/// Renders a code as a one-page PDF document for print, with the colors in
+/// CMYK.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Cmyk, Pdf};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut document = String::new();
+///     Pdf { dark: Cmyk { c: 100, m: 60, y: 0, k: 20 }, ..Pdf::default() }.write(&code, &mut document).unwrap();
+///     assert!(document.starts_with("%PDF-1.4\n"));
+///     assert!(document.contains("1 0.6 0 0.2 k\n"));
+///
+/// `layout.module_size` is in points of 1/72 inch, and the dark modules are
+/// filled as one path of a rectangle for every horizontal run.
+///
+/// With `marks`, the page is sized in millimetres instead, with a `TrimBox`
+/// and a `BleedBox` for imposition, and the crop marks and registration
+/// targets are stroked in `Cmyk::REGISTRATION`. The light color, if any,
+/// extends into the bleed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pdf {
+    pub layout: Layout,
+    /// The color of the dark modules. Defaults to `Cmyk::BLACK`.
+    pub dark: Cmyk,
+    /// The color of the light modules and the quiet zone, or `None` to leave
+    /// them unprinted. Defaults to `None`.
+    pub light: Option<Cmyk>,
+    /// The print marks around the image, if any. `layout.module_size` is
+    /// ignored then, as `PrintMarks::module_mm` sets the size.
+    pub marks: Option<PrintMarks>,
+}
+
+impl Default for Pdf {
+    fn default() -> Self {
+        Self { layout: Layout::default(), dark: Cmyk::BLACK, light: None, marks: None }
+    }
+}
+
+/// Counts the bytes written through it, for the offsets in the
+/// cross-reference table of a PDF document.
+struct Counted<W> {
+    inner: W,
+    len: usize,
+}
+
+impl<W: fmt::Write> fmt::Write for Counted<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.len += s.len();
+        self.inner.write_str(s)
+    }
+}
+
+/// Discards everything written into it.
+struct Discard;
+
+impl fmt::Write for Discard {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl Pdf {
+    /// Writes the document into `out`.
+    ///
+    /// The content stream is written twice, first only to count its length,
+    /// so that nothing has to be buffered.
+    pub fn write<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        use fmt::Write as _;
+
+        let mut content = Counted { inner: Discard, len: 0 };
+        self.write_content(code, &mut content)?;
+
+        let mut out = Counted { inner: out, len: 0 };
+        let mut offsets = [0; 4];
+        out.write_str("%PDF-1.4\n")?;
+        offsets[0] = out.len;
+        out.write_str("1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n")?;
+        offsets[1] = out.len;
+        out.write_str("2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n")?;
+        offsets[2] = out.len;
+        out.write_str("3 0 obj\n<< /Type /Page /Parent 2 0 R")?;
+        match self.marks {
+            None => write!(out, " /MediaBox [0 0 {0} {0}]", self.layout.image_size(V::WIDTH))?,
+            Some(marks) => {
+                let trim = self.layout.modules(V::WIDTH) as f32 * marks.module_mm;
+                let (margin, bleed) = (marks.margin(), marks.bleed);
+                let points = |mm: f32| mm * POINTS_PER_MM;
+                write!(
+                    out,
+                    " /MediaBox [0 0 {0} {0}] /BleedBox [{1} {1} {2} {2}] /TrimBox [{3} {3} {4} {4}]",
+                    points(trim + 2.0 * margin),
+                    points(margin - bleed),
+                    points(margin + trim + bleed),
+                    points(margin),
+                    points(margin + trim)
+                )?;
+            }
+        }
+        out.write_str(" /Resources << >> /Contents 4 0 R >>\nendobj\n")?;
+        offsets[3] = out.len;
+        write!(out, "4 0 obj\n<< /Length {} >>\nstream\n", content.len)?;
+        self.write_content(code, &mut out)?;
+        out.write_str("\nendstream\nendobj\n")?;
+
+        // Every entry of the cross-reference table is exactly 20 bytes long.
+        let xref = out.len;
+        out.write_str("xref\n0 5\n0000000000 65535 f \n")?;
+        for offset in &offsets {
+            writeln!(out, "{:010} 00000 n ", offset)?;
+        }
+        write!(out, "trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", xref)
+    }
+
+    /// Writes the content stream of the page.
+    fn write_content<V: QrSpec, W: fmt::Write>(&self, code: &QrCode<V>, out: &mut W) -> fmt::Result {
+        let modules = self.layout.modules(V::WIDTH);
+        out.write_str("q\n")?;
+        match self.marks {
+            None => {
+                writeln!(out, "{0} 0 0 {0} 0 0 cm", self.layout.module_size)?;
+                if let Some(light) = self.light {
+                    writeln!(out, "{} k\n0 0 {1} {1} re f", light, modules)?;
+                }
+            }
+            Some(marks) => {
+                // Origin at the bottom left corner of the trim box, in
+                // millimetres.
+                let trim = modules as f32 * marks.module_mm;
+                writeln!(out, "{0} 0 0 {0} 0 0 cm\n1 0 0 1 {1} {1} cm", POINTS_PER_MM, marks.margin())?;
+                if let Some(light) = self.light {
+                    let bleed = marks.bleed;
+                    writeln!(out, "{} k\n-{1} -{1} {2} {2} re f", light, bleed, trim + 2.0 * bleed)?;
+                }
+                writeln!(out, "q\n{0} 0 0 {0} 0 0 cm", marks.module_mm)?;
+            }
+        }
+        // PDF counts y upwards, so the top row is at `modules - 1`.
+        writeln!(out, "{} k", self.dark)?;
+        for y in 0..modules {
+            let mut x = 0;
+            while x < modules {
+                if self.layout.color(code, x, y) == Color::Light {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < modules && self.layout.color(code, x, y) == Color::Dark {
+                    x += 1;
+                }
+                writeln!(out, "{} {} {} 1 re", start, modules - 1 - y, x - start)?;
+            }
+        }
+        out.write_str("f\n")?;
+        if let Some(marks) = self.marks {
+            writeln!(out, "Q\n{} K", Cmyk::REGISTRATION)?;
+            marks.write_pdf(modules as f32 * marks.module_mm, out)?;
+        }
+        out.write_str("Q")
+    }
+
+    /// Streams the document into `out`, such as a UART, without buffering
+    /// it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        out: W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        write_embedded_fmt(out, |out| self.write(code, out))
+    }
+
+    /// Streams the document into `out`.
+    #[cfg(feature = "std")]
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, out: W) -> io::Result<()> {
+        let mut adapter = IoAdapter { inner: out, error: None };
+        self.write(code, &mut adapter)
+            .map_err(|fmt::Error| adapter.error.take().unwrap_or_else(|| io::Error::other("formatter error")))
+    }
+}
+
+#[cfg(test)]
+mod pdf_tests {
+    use crate::render::{Cmyk, Layout, Pdf, PrintMarks};
+    use crate::spec::{EcLevelM, Version1};
+    use crate::QrCode;
+
+    /// Checks the cross-reference table and the stream length of `document`,
+    /// and returns the content stream.
+    fn check_structure(document: &str) -> &str {
+        let xref: usize = document.rsplit("startxref\n").next().unwrap().trim_end_matches("\n%%EOF\n").parse().unwrap();
+        assert!(document[xref..].starts_with("xref\n0 5\n0000000000 65535 f \n"));
+        let entries = &document[xref + 29..];
+        for object in 1..5 {
+            let entry = &entries[(object - 1) * 20..object * 20];
+            assert!(entry.ends_with(" 00000 n \n"));
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(document[offset..].starts_with(&format!("{} 0 obj\n", object)));
+        }
+        assert!(entries[80..].starts_with("trailer\n"));
+
+        let length: usize = document.split("/Length ").nth(1).unwrap().split(' ').next().unwrap().parse().unwrap();
+        let stream = &document[document.find(">>\nstream\n").unwrap() + 10..];
+        assert_eq!(&stream[length..length + 10], "\nendstream");
+        &stream[..length]
+    }
+
+    #[test]
+    fn test_pdf() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut document = String::new();
+        let pdf = Pdf { layout: Layout { quiet_zone: 1, module_size: 3 }, ..Pdf::default() };
+        pdf.write(&code, &mut document).unwrap();
+        assert!(document.starts_with("%PDF-1.4\n1 0 obj\n"));
+        assert!(document.contains(" /MediaBox [0 0 69 69] /Resources"));
+        assert!(!document.contains("TrimBox"));
+        assert!(document.ends_with("%%EOF\n"));
+
+        let content = check_structure(&document);
+        assert!(content.starts_with("q\n3 0 0 3 0 0 cm\n0 0 0 1 k\n"));
+        // The top row of the symbol, between the top edges of the finder
+        // patterns.
+        assert!(content.contains(" k\n1 21 7 1 re\n10 21 1 1 re\n12 21 2 1 re\n15 21 7 1 re\n1 20 1 1 re\n"));
+        assert!(content.contains("\n1 1 7 1 re\n"));
+        assert!(content.ends_with(" re\nf\nQ"));
+
+        let mut document = String::new();
+        Pdf { light: Some(Cmyk::WHITE), ..pdf }.write(&code, &mut document).unwrap();
+        let content = check_structure(&document);
+        assert!(content.starts_with("q\n3 0 0 3 0 0 cm\n0 0 0 0 k\n0 0 23 23 re f\n0 0 0 1 k\n"));
+    }
+
+    #[test]
+    fn test_pdf_print_marks() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let layout = Layout { quiet_zone: 2, module_size: 3 };
+        let mut plain = String::new();
+        Pdf { layout, ..Pdf::default() }.write(&code, &mut plain).unwrap();
+
+        let mut document = String::new();
+        let marks =
+            PrintMarks { module_mm: 0.5, bleed: 2.0, offset: 3.0, length: 4.0, stroke: 0.25, registration: true };
+        let pdf = Pdf { layout, light: Some(Cmyk::WHITE), marks: Some(marks), ..Pdf::default() };
+        pdf.write(&code, &mut document).unwrap();
+        // 25 modules of 0.5 mm, and 7 mm of marks on every side: 26.5 mm or
+        // 75.1 points, of which the bleed box leaves 5 mm and the trim box
+        // 7 mm.
+        assert!(document.contains(concat!(
+            " /MediaBox [0 0 75.11811 75.11811] /BleedBox [14.173229 14.173229 60.944885 60.944885]",
+            " /TrimBox [19.84252 19.84252 55.275593 55.275593] /Resources",
+        )));
+
+        let content = check_structure(&document);
+        assert!(content.starts_with("q\n2.8346457 0 0 2.8346457 0 0 cm\n1 0 0 1 7 7 cm\n"));
+        assert!(content.contains("\n0 0 0 0 k\n-2 -2 16.5 16.5 re f\nq\n0.5 0 0 0.5 0 0 cm\n0 0 0 1 k\n"));
+        // The modules are drawn exactly like without marks.
+        let modules = |content: &str| content.split(" k\n").last().unwrap().split("f\n").next().unwrap().to_owned();
+        assert_eq!(modules(content), modules(check_structure(&plain)));
+        // The top left crop marks, and the top registration target.
+        assert!(content.contains("f\nQ\n1 1 1 1 K\n0.25 w\n1 0 0 -1 0 12.5 cm\n"));
+        assert!(content.contains(" cm\n-3 0 m -7 0 l\n0 -3 m 0 -7 l\n-3 12.5 m -7 12.5 l\n0 15.5 m 0 19.5 l\n"));
+        assert!(content.contains("\n4.25 -5 m 8.25 -5 l\n6.25 -7 m 6.25 -3 l\n"));
+        assert!(content.contains("\n7.25 -5 m\n7.25 -4.4477153 6.8022847 -4 6.25 -4 c\n"));
+        assert_eq!(content.matches(" c\n").count(), 16);
+        assert!(content.ends_with(" c\nS\nQ"));
+
+        let mut document = String::new();
+        Pdf { layout, marks: Some(PrintMarks { registration: false, ..marks }), ..Pdf::default() }
+            .write(&code, &mut document)
+            .unwrap();
+        let content = check_structure(&document);
+        assert!(!content.contains(" c\n"));
+        assert!(!content.contains("re f\nq"));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Bitmaps
+
+/// Writes the `width` × `height` pixels at the top left of the image of
+/// `code` as rows of packed bits, the leftmost pixel in the highest bit and 1
+/// for dark pixels, with every row padded to whole bytes.
+fn write_packed_rows<V: QrSpec, E>(
+    layout: &Layout,
+    code: &QrCode<V>,
+    (width, height): (u32, u32),
+    mut write: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut buffer = [0; 64];
+    let mut len = 0;
+    for y in 0..height {
+        for x in (0..width).step_by(8) {
+            let mut byte = 0;
+            for bit in 0..(width - x).min(8) {
+                let color = layout.color(code, (x + bit) / layout.module_size, y / layout.module_size);
+                if color == Color::Dark {
+                    byte |= 0x80 >> bit;
+                }
+            }
+            buffer[len] = byte;
+            len += 1;
+            if len == buffer.len() {
+                write(&buffer)?;
+                len = 0;
+            }
+        }
+    }
+    if len > 0 {
+        write(&buffer[..len])?;
+    }
+    Ok(())
+}
+
+/// Renders a code as a binary PBM (portable bitmap) image, with 1 bit per
+/// pixel, which image tools and many label printers read.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{Layout, Pbm};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut image = Vec::new();
+///     let pbm = Pbm { layout: Layout { quiet_zone: 4, module_size: 1 } };
+///     pbm.write_bytes(&code, |bytes| {
+///         image.extend_from_slice(bytes);
+///         Ok::<_, ()>(())
+///     })
+///     .unwrap();
+///     assert!(image.starts_with(b"P4\n29 29\n"));
+///     assert_eq!(image.len(), 9 + 4 * 29);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pbm {
+    pub layout: Layout,
+}
+
+impl Pbm {
+    /// Writes the image piece by piece into `write`, buffering at most 64
+    /// bytes.
+    pub fn write_bytes<V: QrSpec, E>(
+        &self,
+        code: &QrCode<V>,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        use fmt::Write as _;
+
+        let size = self.layout.image_size(V::WIDTH);
+        let mut header = String::<U32>::new();
+        // "P4\n4294967295 4294967295\n" is 25 bytes long, so this cannot fail.
+        let _ = write!(header, "P4\n{0} {0}\n", size);
+        write(header.as_bytes())?;
+        write_packed_rows(&self.layout, code, (size, size), write)
+    }
+
+    /// Streams the image into `out`.
+    #[cfg(feature = "std")]
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, mut out: W) -> io::Result<()> {
+        self.write_bytes(code, |bytes| out.write_all(bytes))
+    }
+
+    /// Streams the image into `out`, such as a UART, without buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        mut out: W,
+    ) -> Result<(), W::Error> {
+        self.write_bytes(code, |bytes| out.write_all(bytes))
+    }
+}
+
+/// Renders a code as the ESC/POS command to print a raster bit image
+/// (`GS v 0`), which receipt printers print as it arrives.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::{EscPos, Layout};
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut command = Vec::new();
+///     let escpos = EscPos { layout: Layout { quiet_zone: 2, module_size: 8 } };
+///     escpos.write_bytes(&code, |bytes| {
+///         command.extend_from_slice(bytes);
+///         Ok::<_, ()>(())
+///     })
+///     .unwrap();
+///     assert_eq!(&command[..8], &[0x1d, 0x76, 0x30, 0, 25, 0, 200, 0]);
+///
+/// Only the command itself is written, so initialize the printer, align and
+/// cut the paper around it as needed. The image must fit the print width,
+/// which is often 384 or 576 dots. The command cannot describe more than
+/// 65535 rows, or 65535 bytes per row; larger images are cut off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EscPos {
+    pub layout: Layout,
+}
+
+impl EscPos {
+    /// Writes the command piece by piece into `write`, buffering at most 64
+    /// bytes.
+    pub fn write_bytes<V: QrSpec, E>(
+        &self,
+        code: &QrCode<V>,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let size = self.layout.image_size(V::WIDTH);
+        let row_bytes = size.div_ceil(8).min(0xffff);
+        let height = size.min(0xffff);
+        let [x_lo, x_hi, ..] = row_bytes.to_le_bytes();
+        let [y_lo, y_hi, ..] = height.to_le_bytes();
+        // Normal density, 1 bit per dot.
+        write(&[0x1d, 0x76, 0x30, 0, x_lo, x_hi, y_lo, y_hi])?;
+        write_packed_rows(&self.layout, code, (size.min(row_bytes * 8), height), write)
+    }
+
+    /// Streams the command into `out`.
+    #[cfg(feature = "std")]
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, mut out: W) -> io::Result<()> {
+        self.write_bytes(code, |bytes| out.write_all(bytes))
+    }
+
+    /// Streams the command into `out`, such as the UART of a printer, without
+    /// buffering it.
+    #[cfg(feature = "embedded-io")]
+    pub fn write_embedded<V: QrSpec, W: embedded_io::Write>(
+        &self,
+        code: &QrCode<V>,
+        mut out: W,
+    ) -> Result<(), W::Error> {
+        self.write_bytes(code, |bytes| out.write_all(bytes))
+    }
+}
+
+#[cfg(test)]
+mod bitmap_tests {
+    use crate::render::{EscPos, Layout, Pbm};
+    use crate::spec::{EcLevelL, Version1};
+    use crate::types::Color;
+    use crate::QrCode;
+
+    fn collect(write: impl FnOnce(&mut dyn FnMut(&[u8]) -> Result<(), ()>) -> Result<(), ()>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write(&mut |chunk| {
+            bytes.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+        bytes
+    }
+
+    fn unpack(rows: &[u8], width: usize) -> Vec<Color> {
+        let row_len = width.div_ceil(8);
+        (0..rows.len() / row_len * width)
+            .map(|i| Color::from(rows[i / width * row_len + i % width / 8] & 0x80 >> (i % width % 8) != 0))
+            .collect()
+    }
+
+    #[test]
+    fn test_pbm() {
+        let code = QrCode::<Version1<EcLevelL>>::new(b"PBM").unwrap();
+        // 3 × 31 = 93 pixels, in 12 bytes per row.
+        let layout = Layout { quiet_zone: 5, module_size: 3 };
+        let image = collect(|write| Pbm { layout }.write_bytes(&code, write));
+        assert!(image.starts_with(b"P4\n93 93\n"));
+        assert_eq!(image.len(), 9 + 12 * 93);
+        assert!(unpack(&image[9..], 93).into_iter().eq(layout.pixels::<_, Color>(&code)));
+    }
+
+    #[test]
+    fn test_escpos() {
+        let code = QrCode::<Version1<EcLevelL>>::new(b"ESC/POS").unwrap();
+        let layout = Layout { quiet_zone: 4, module_size: 12 };
+        let command = collect(|write| EscPos { layout }.write_bytes(&code, write));
+        assert_eq!(&command[..8], &[0x1d, 0x76, 0x30, 0, 44, 0, 0x5c, 1]);
+        assert_eq!(command.len(), 8 + 44 * 348);
+        assert!(unpack(&command[8..], 348).into_iter().eq(layout.pixels::<_, Color>(&code)));
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-io")]
+    fn test_embedded() {
+        use crate::render::{Svg, Text};
+        use embedded_io::{SliceWriteError, WriteFmtError};
+
+        let code = QrCode::<Version1<EcLevelL>>::new(b"UART").unwrap();
+        let mut buffer = [0; 8192];
+        let mut rest = &mut buffer[..];
+        Pbm::default().write_embedded(&code, &mut rest).unwrap();
+        let len = 8192 - rest.len();
+        assert_eq!(&buffer[..len], &collect(|write| Pbm::default().write_bytes(&code, write))[..]);
+
+        let mut expected = String::new();
+        Text::default().write(&code, &mut expected).unwrap();
+        let mut rest = &mut buffer[..];
+        Text::default().write_embedded(&code, &mut rest).unwrap();
+        let len = 8192 - rest.len();
+        assert_eq!(&buffer[..len], expected.as_bytes());
+
+        let result = Svg::default().write_embedded(&code, &mut buffer[..100]);
+        assert_eq!(result, Err(WriteFmtError::Other(SliceWriteError::Full)));
+        assert_eq!(EscPos::default().write_embedded(&code, &mut buffer[..100]), Err(SliceWriteError::Full));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ PNG
+
+/// Renders a code as a black and white PNG image, with 1 bit per pixel.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::Png;
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+///     let mut image = Vec::new();
+///     Png::default().write_io(&code, &mut image).unwrap();
+///     assert!(image.starts_with(b"\x89PNG"));
+///
+/// The pixels are stored uncompressed, so that they can be streamed without
+/// a deflate encoder. Only one row of pixels is buffered.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Png<'a> {
+    pub layout: Layout,
+    /// A line of text below the symbol, which makes the image taller.
+    pub caption: Option<Caption<'a>>,
+}
+
+/// The largest block of a stored deflate stream.
+#[cfg(feature = "std")]
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+#[cfg(feature = "std")]
+impl Png<'_> {
+    /// Streams the image into `out`.
+    pub fn write_io<V: QrSpec, W: io::Write>(&self, code: &QrCode<V>, mut out: W) -> io::Result<()> {
+        let size = self.layout.image_size(V::WIDTH);
+        let caption = self.caption.unwrap_or(Caption { text: "", scale: 0 });
+        let height = size.saturating_add(caption.height());
+        let row_len = 1 + (size as usize).div_ceil(8);
+        // Saturating, so that images too large for any PNG fail the check
+        // below.
+        let raw_len = row_len.saturating_mul(height as usize);
+        let blocks = raw_len.div_ceil(MAX_STORED_BLOCK);
+        let idat_len = raw_len.saturating_add(2 + 5 * blocks + 4);
+        let idat_len = u32::try_from(idat_len)
+            .ok()
+            .filter(|len| *len <= i32::MAX as u32)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "image too large for a PNG"))?;
+
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        let mut header = [0; 13];
+        header[..4].copy_from_slice(&size.to_be_bytes());
+        header[4..8].copy_from_slice(&height.to_be_bytes());
+        // Bit depth 1, grayscale, deflate, no filtering, no interlacing.
+        header[8..].copy_from_slice(&[1, 0, 0, 0, 0]);
+        Chunk::start(&mut out, b"IHDR", 13)?.write(&header)?.finish()?;
+
+        let mut idat = Chunk::start(&mut out, b"IDAT", idat_len)?;
+        // Deflate with a 32 KiB window and no preset dictionary.
+        idat.write(&[0x78, 0x01])?;
+        let mut deflate = StoredDeflate { chunk: idat, remaining: raw_len, block_remaining: 0, adler: (1, 0) };
+
+        // Every row starts with the filter type 0, and light pixels are 1.
+        let mut row = std::vec![0; row_len];
+        for y in 0..height {
+            // The rows of a module are all the same, those of the caption are
+            // not.
+            if y >= size || y % self.layout.module_size == 0 {
+                for byte in &mut row[1..] {
+                    *byte = 0;
+                }
+                for x in 0..size {
+                    if self.layout.caption_color(code, &caption, x, y) == Color::Light {
+                        row[1 + x as usize / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+            deflate.write(&row)?;
+        }
+        deflate.finish()?;
+
+        Chunk::start(&mut out, b"IEND", 0)?.finish()
+    }
+}
+
+/// Writes a PNG chunk of a known length, computing its CRC on the way.
+#[cfg(feature = "std")]
+struct Chunk<W> {
+    out: W,
+    crc: u32,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Chunk<W> {
+    fn start(mut out: W, kind: &[u8; 4], len: u32) -> io::Result<Self> {
+        out.write_all(&len.to_be_bytes())?;
+        out.write_all(kind)?;
+        Ok(Self { out, crc: crc32(!0, kind) })
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<&mut Self> {
+        self.out.write_all(data)?;
+        self.crc = crc32(self.crc, data);
+        Ok(self)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.write_all(&(!self.crc).to_be_bytes())
+    }
+}
+
+/// Writes a zlib stream of stored deflate blocks into a chunk, computing its
+/// Adler-32 checksum on the way.
+#[cfg(feature = "std")]
+struct StoredDeflate<W> {
+    chunk: Chunk<W>,
+    /// The number of bytes still to come, which must be known to mark the
+    /// last block.
+    remaining: usize,
+    block_remaining: usize,
+    adler: (u32, u32),
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> StoredDeflate<W> {
+    fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            if self.block_remaining == 0 {
+                let len = self.remaining.min(MAX_STORED_BLOCK);
+                let is_final = u8::from(len == self.remaining);
+                let [lo, hi] = (len as u16).to_le_bytes();
+                self.chunk.write(&[is_final, lo, hi, !lo, !hi])?;
+                self.block_remaining = len;
+            }
+            let (block, rest) = data.split_at(data.len().min(self.block_remaining));
+            self.chunk.write(block)?;
+            for &byte in block {
+                self.adler.0 = (self.adler.0 + u32::from(byte)) % 65521;
+                self.adler.1 = (self.adler.1 + self.adler.0) % 65521;
+            }
+            self.remaining -= block.len();
+            self.block_remaining -= block.len();
+            data = rest;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        debug_assert_eq!(self.remaining, 0);
+        let adler = self.adler.1 << 16 | self.adler.0;
+        self.chunk.write(&adler.to_be_bytes())?.finish()
+    }
+}
+
+/// Builds the lookup table of the CRC-32 used by PNG.
+#[cfg(feature = "std")]
+const fn build_crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(feature = "std")]
+static CRC_TABLE: [u32; 256] = build_crc_table();
+
+/// Updates a CRC-32 register, which starts as `!0` and is inverted at the
+/// end.
+#[cfg(feature = "std")]
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod png_tests {
+    use crate::render::{crc32, Caption, Layout, Png};
+    use crate::spec::{EcLevelH, EcLevelM, Version1, Version40};
+    use crate::types::Color;
+    use crate::QrCode;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(!crc32(!0, b"IEND"), 0xae42_6082);
+    }
+
+    fn decode(bytes: &[u8]) -> (u32, u32, std::vec::Vec<u8>) {
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        (info.width, info.height, pixels)
+    }
+
+    #[test]
+    fn test_png() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut bytes = std::vec::Vec::new();
+        let layout = Layout { quiet_zone: 2, module_size: 3 };
+        Png { layout, caption: None }.write_io(&code, &mut bytes).unwrap();
+
+        let (size, height, pixels) = decode(&bytes);
+        assert_eq!((size, height), (75, 75));
+        for (i, color) in code.colors().enumerate() {
+            let (x, y) = (i % 21, i / 21);
+            for (dx, dy) in [(0, 0), (2, 2)] {
+                let pixel = pixels[((y + 2) * 3 + dy) * 75 + (x + 2) * 3 + dx];
+                assert_eq!(pixel, color.select(0, 255));
+            }
+        }
+        assert!(pixels[..75 * 6].iter().all(|p| *p == 255));
+        assert_eq!(pixels.len(), 75 * 75);
+    }
+
+    #[test]
+    fn test_png_several_blocks() {
+        let code = QrCode::<Version40<EcLevelH>>::new(b"a stream over 64 KiB").unwrap();
+        let mut bytes = std::vec::Vec::new();
+        Png::default().write_io(&code, &mut bytes).unwrap();
+        let (size, _, pixels) = decode(&bytes);
+        assert_eq!(size, 1480);
+        assert_eq!(pixels[32 * 1480 + 32], 0);
+    }
+
+    #[test]
+    fn test_png_caption() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"4711").unwrap();
+        let layout = Layout { quiet_zone: 1, module_size: 3 };
+        let caption = Caption { scale: 2, ..Caption::new("No 4711") };
+        let mut bytes = std::vec::Vec::new();
+        Png { layout, caption: Some(caption) }.write_io(&code, &mut bytes).unwrap();
+
+        let (size, height, pixels) = decode(&bytes);
+        assert_eq!((size, height), (69, 69 + 16));
+        let expected = layout.pixels_with_caption::<_, Color>(&code, &caption);
+        assert!(pixels.iter().map(|&p| Color::from(p == 0)).eq(expected));
+    }
+
+    #[test]
+    fn test_png_too_large() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"4711").unwrap();
+        let mut bytes = std::vec::Vec::new();
+        let layout = Layout { quiet_zone: u32::MAX / 2, module_size: u32::MAX };
+        let error = Png { layout, caption: None }.write_io(&code, &mut bytes).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(bytes.is_empty());
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ GIF
+
+/// Renders a sequence of codes, such as the symbols of a structured append or
+/// the frames of a `stream::Sender`, as an animated GIF image, so they can be
+/// shared as a single file.
+///
+///     use qrcode::QrCode;
+///     use qrcode::render::Gif;
+///     use qrcode::spec::{Version1, EcLevelM};
+///
+///     let codes = [b"0123", b"4567"].iter().map(|part| QrCode::<Version1<EcLevelM>>::new(*part).unwrap());
+///     let mut image = Vec::new();
+///     Gif { delay: 100, ..Gif::default() }.write_io(codes, &mut image).unwrap();
+///     assert!(image.starts_with(b"GIF89a"));
+///
+/// The animation loops forever. Each frame is LZW compressed while it is
+/// written, so only the string table of the compressor is kept in memory.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gif {
+    pub layout: Layout,
+    /// How long each frame is shown, in hundredths of a second.
+    pub delay: u16,
+}
+
+#[cfg(feature = "std")]
+impl Default for Gif {
+    fn default() -> Self {
+        Self { layout: Layout::default(), delay: 50 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Gif {
+    /// Streams the image into `out`, with one frame per code.
+    ///
+    /// Returns an error of kind `InvalidInput` if there are no codes, or the
+    /// image is wider than the 65535 pixels a GIF can hold.
+    pub fn write_io<V, I, W>(&self, codes: I, mut out: W) -> io::Result<()>
+    where
+        V: QrSpec,
+        I: IntoIterator,
+        I::Item: Borrow<QrCode<V>>,
+        W: io::Write,
+    {
+        let size = u16::try_from(self.layout.image_size(V::WIDTH))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "image too large for a GIF"))?;
+        let mut codes = codes.into_iter().peekable();
+        if codes.peek().is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no frames to write"));
+        }
+
+        let [lo, hi] = size.to_le_bytes();
+        out.write_all(b"GIF89a")?;
+        // The screen, with a global table of two colors: dark is 0, and light
+        // is 1 and the background.
+        out.write_all(&[lo, hi, lo, hi, 0x80, 1, 0])?;
+        out.write_all(&[0, 0, 0, 0xff, 0xff, 0xff])?;
+        // The NETSCAPE2.0 application extension, looping forever.
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+        let [delay_lo, delay_hi] = self.delay.to_le_bytes();
+        let mut lzw = Lzw::new();
+        for code in codes {
+            let code = code.borrow();
+            // A graphic control extension with the delay, and an image which
+            // covers the whole screen.
+            out.write_all(&[0x21, 0xf9, 4, 0, delay_lo, delay_hi, 0, 0])?;
+            out.write_all(&[0x2c, 0, 0, 0, 0, lo, hi, lo, hi, 0])?;
+            let mut blocks = SubBlocks { out: &mut out, block: [0; 255], len: 0 };
+            blocks.out.write_all(&[LZW_MIN_CODE_SIZE])?;
+            lzw.start(&mut blocks)?;
+            for y in 0..u32::from(size) {
+                for x in 0..u32::from(size) {
+                    let color = self.layout.color(code, x / self.layout.module_size, y / self.layout.module_size);
+                    lzw.push(&mut blocks, color.select(0, 1))?;
+                }
+            }
+            lzw.finish(&mut blocks)?;
+            blocks.finish()?;
+        }
+        out.write_all(&[0x3b])
+    }
+}
+
+/// The number of bits of a pixel of the image data, at least 2 in a GIF.
+#[cfg(feature = "std")]
+const LZW_MIN_CODE_SIZE: u8 = 2;
+
+#[cfg(feature = "std")]
+const LZW_CLEAR: u16 = 1 << LZW_MIN_CODE_SIZE;
+
+#[cfg(feature = "std")]
+const LZW_END: u16 = LZW_CLEAR + 1;
+
+/// The number of codes of 12 bits, the widest a GIF allows.
+#[cfg(feature = "std")]
+const LZW_MAX_CODES: u16 = 1 << 12;
+
+/// Splits the image data of a GIF into sub-blocks of at most 255 bytes.
+#[cfg(feature = "std")]
+struct SubBlocks<W> {
+    out: W,
+    block: [u8; 255],
+    len: usize,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> SubBlocks<W> {
+    fn write(&mut self, byte: u8) -> io::Result<()> {
+        self.block[self.len] = byte;
+        self.len += 1;
+        if self.len == self.block.len() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.len > 0 {
+            self.out.write_all(&[self.len as u8])?;
+            self.out.write_all(&self.block[..self.len])?;
+            self.len = 0;
+        }
+        Ok(())
+    }
+
+    /// Writes the last sub-block and the empty one which ends the data.
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.out.write_all(&[0])
+    }
+}
+
+/// The LZW compressor of GIF, whose codes grow from 3 to 12 bits as the
+/// string table fills, and which starts over with a clear code once it is
+/// full.
+#[cfg(feature = "std")]
+struct Lzw {
+    /// The code of each string in the table, by the code of the string
+    /// without its last pixel and that pixel.
+    table: HashMap<(u16, u8), u16>,
+    /// The code of the pixels read but not yet written.
+    prefix: Option<u16>,
+    next: u16,
+    width: u32,
+    bits: u32,
+    bit_count: u32,
+}
+
+#[cfg(feature = "std")]
+impl Lzw {
+    fn new() -> Self {
+        Lzw { table: HashMap::new(), prefix: None, next: 0, width: 0, bits: 0, bit_count: 0 }
+    }
+
+    fn reset(&mut self) {
+        self.table.clear();
+        self.next = LZW_END + 1;
+        self.width = u32::from(LZW_MIN_CODE_SIZE) + 1;
+    }
+
+    fn start<W: io::Write>(&mut self, out: &mut SubBlocks<W>) -> io::Result<()> {
+        self.prefix = None;
+        self.reset();
+        self.write(out, LZW_CLEAR)
+    }
+
+    fn write<W: io::Write>(&mut self, out: &mut SubBlocks<W>, code: u16) -> io::Result<()> {
+        self.bits |= u32::from(code) << self.bit_count;
+        self.bit_count += self.width;
+        while self.bit_count >= 8 {
+            out.write(self.bits as u8)?;
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+        Ok(())
+    }
+
+    /// Counts the entry the decoder adds after reading a code, and widens
+    /// the codes once it does not fit. The decoder adds it one code later,
+    /// hence the `>` instead of `>=`.
+    fn grow(&mut self) {
+        self.next += 1;
+        if self.next > 1 << self.width {
+            self.width += 1;
+        }
+    }
+
+    fn push<W: io::Write>(&mut self, out: &mut SubBlocks<W>, pixel: u8) -> io::Result<()> {
+        let prefix = match self.prefix {
+            Some(prefix) => prefix,
+            None => {
+                self.prefix = Some(u16::from(pixel));
+                return Ok(());
+            }
+        };
+        if let Some(&code) = self.table.get(&(prefix, pixel)) {
+            self.prefix = Some(code);
+            return Ok(());
+        }
+        self.write(out, prefix)?;
+        if self.next < LZW_MAX_CODES {
+            self.table.insert((prefix, pixel), self.next);
+            self.grow();
+        } else {
+            self.write(out, LZW_CLEAR)?;
+            self.reset();
+        }
+        self.prefix = Some(u16::from(pixel));
+        Ok(())
+    }
+
+    fn finish<W: io::Write>(&mut self, out: &mut SubBlocks<W>) -> io::Result<()> {
+        if let Some(prefix) = self.prefix {
+            self.write(out, prefix)?;
+            if self.next < LZW_MAX_CODES {
+                self.grow();
+            }
+        }
+        self.write(out, LZW_END)?;
+        if self.bit_count > 0 {
+            out.write(self.bits as u8)?;
+        }
+        self.bits = 0;
+        self.bit_count = 0;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod gif_tests {
+    use crate::render::{Gif, Layout};
+    use crate::spec::{EcLevelH, EcLevelM, QrSpec, Version1, Version40};
+    use crate::QrCode;
+
+    /// Decodes the frames of an image, with their delays and pixels.
+    fn decode(bytes: &[u8]) -> std::vec::Vec<(u16, std::vec::Vec<u8>)> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(bytes).unwrap();
+        assert_eq!(decoder.repeat(), gif::Repeat::Infinite);
+        let size = (decoder.width(), decoder.height());
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), size);
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+        frames
+    }
+
+    fn check<V: QrSpec>(code: &QrCode<V>, layout: Layout, pixels: &[u8]) {
+        let size = layout.image_size(V::WIDTH) as usize;
+        assert_eq!(pixels.len(), size * size);
+        for (i, pixel) in pixels.iter().enumerate() {
+            let (x, y) = ((i % size) as u32, (i / size) as u32);
+            let color = layout.color(code, x / layout.module_size, y / layout.module_size);
+            assert_eq!(*pixel, color.select(0, 1), "at {}, {}", x, y);
+        }
+    }
+
+    #[test]
+    fn test_gif() {
+        let codes = [b"01234567", b"76543210"].map(|data| QrCode::<Version1<EcLevelM>>::new(data).unwrap());
+        let layout = Layout { quiet_zone: 2, module_size: 3 };
+        let mut bytes = vec![];
+        Gif { layout, delay: 25 }.write_io(&codes, &mut bytes).unwrap();
+
+        let frames = decode(&bytes);
+        assert_eq!(frames.len(), 2);
+        for (code, (delay, pixels)) in codes.iter().zip(&frames) {
+            assert_eq!(*delay, 25);
+            check(code, layout, pixels);
+        }
+    }
+
+    #[test]
+    fn test_gif_full_table() {
+        // Enough pixels to fill the string table several times.
+        let code = QrCode::<Version40<EcLevelH>>::new(b"a frame over 4096 codes").unwrap();
+        let layout = Layout { quiet_zone: 4, module_size: 2 };
+        let mut bytes = vec![];
+        Gif { layout, ..Gif::default() }.write_io([&code, &code, &code], &mut bytes).unwrap();
+
+        let frames = decode(&bytes);
+        assert_eq!(frames.len(), 3);
+        for (_, pixels) in &frames {
+            check(&code, layout, pixels);
+        }
+    }
+
+    #[test]
+    fn test_gif_errors() {
+        let codes: [QrCode<Version1<EcLevelM>>; 0] = [];
+        let err = Gif::default().write_io(&codes, vec![]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let code = QrCode::<Version40<EcLevelH>>::new(b"").unwrap();
+        let gif = Gif { layout: Layout { quiet_zone: 4, module_size: 400 }, ..Gif::default() };
+        let mut bytes = vec![];
+        let err = gif.write_io([&code], &mut bytes).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(bytes.is_empty());
+    }
+}
+
+//}}}
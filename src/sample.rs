@@ -0,0 +1,578 @@
+//! The `sample` module locates a QR code in a grayscale image and samples its
+//! modules, producing the matrix consumed by `decode::decode`.
+//!
+//!     use qrcode::decode::decode;
+//!     use qrcode::sample::{detect, GrayImage};
+//!     use qrcode::spec::{EcLevelM, Version1};
+//!     use qrcode::types::Color;
+//!     use qrcode::QrCode;
+//!
+//!     // Draw a code with its quiet zone, 4 pixels per module.
+//!     let code = QrCode::<Version1<EcLevelM>>::new(b"01234567")?;
+//!     let modules: Vec<Color> = code.colors_with_quiet_zone(4).collect();
+//!     let size = 29 * 4;
+//!     let module = |i: usize| modules[i / size / 4 * 29 + i % size / 4];
+//!     let pixels: Vec<u8> = (0..size * size).map(|i| if module(i) == Color::Dark { 20 } else { 230 }).collect();
+//!
+//!     let image = GrayImage::new(size, size, &pixels)?;
+//!     let modules = detect(&image)?;
+//!     let mut out = [0; 256];
+//!     let decoded = decode(&modules, &mut out)?;
+//!     assert_eq!(&out[..decoded.len], b"01234567");
+//!     # Ok::<(), qrcode::types::QrError>(())
+//!
+//! The detector aims at simple camera pipelines: the image is binarized with a
+//! single global threshold, and the symbol must show all three finder
+//! patterns. This module requires the `std` feature.
+
+use std::vec::Vec;
+
+use crate::types::{Color, QrError, QrResult};
+
+//------------------------------------------------------------------------------
+//{{{ Image
+
+/// A point in image coordinates. The pixel at column `x` and row `y` covers
+/// the area from `(x, y)` to `(x + 1, y + 1)`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn distance(self, other: Self) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// An 8-bit grayscale image, stored in row-major order. Lower values are
+/// darker.
+#[derive(Debug, Copy, Clone)]
+pub struct GrayImage<'a> {
+    width: usize,
+    height: usize,
+    pixels: &'a [u8],
+    threshold: u8,
+}
+
+impl<'a> GrayImage<'a> {
+    /// Wraps a pixel buffer of the given dimensions.
+    ///
+    /// Returns `Err(QrError::BufferTooSmall)` if `pixels` holds fewer than
+    /// `width * height` bytes, which includes dimensions whose product
+    /// overflows `usize`.
+    pub fn new(width: usize, height: usize, pixels: &'a [u8]) -> QrResult<Self> {
+        let len = width.checked_mul(height).ok_or(QrError::BufferTooSmall)?;
+        let pixels = pixels.get(..len).ok_or(QrError::BufferTooSmall)?;
+        let min = pixels.iter().copied().min().unwrap_or(0);
+        let max = pixels.iter().copied().max().unwrap_or(0);
+        let threshold = (u16::from(min) + u16::from(max)).div_ceil(2) as u8;
+        Ok(Self { width, height, pixels, threshold })
+    }
+
+    /// Gets whether the pixel at column `x` and row `y` is dark, or `None` if
+    /// the coordinates are outside the image.
+    fn is_dark(&self, x: isize, y: isize) -> Option<bool> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(self.pixels[y as usize * self.width + x as usize] < self.threshold)
+    }
+
+    /// Gets whether the pixel containing the point is dark.
+    fn is_dark_at(&self, p: Point) -> Option<bool> {
+        if p.x < 0.0 || p.y < 0.0 {
+            return None;
+        }
+        self.is_dark(p.x as isize, p.y as isize)
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Finder patterns
+
+/// A finder pattern located in an image.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct FinderPattern {
+    /// The center of the pattern.
+    pub center: Point,
+
+    /// The estimated size of a module, in pixels.
+    pub module_size: f64,
+
+    /// The number of scan lines which confirmed the pattern.
+    pub count: usize,
+}
+
+/// Checks whether the run lengths follow the 1:1:3:1:1 ratio of a finder
+/// pattern.
+fn is_finder_ratio(runs: &[usize; 5]) -> bool {
+    let total: usize = runs.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let tolerance = module / 2.0;
+    runs.iter()
+        .zip(&[1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&run, &ratio)| (run as f64 - module * ratio).abs() < tolerance * ratio)
+}
+
+/// Measures the five runs of a finder pattern crossing the pixel `(x, y)`
+/// along the direction `(dx, dy)`. The pixel must be in the center square.
+///
+/// Returns the run lengths and the center of the pattern along the line, as
+/// the offset from the pixel's corner.
+fn cross_check(image: &GrayImage, x: isize, y: isize, dx: isize, dy: isize) -> Option<([usize; 5], f64)> {
+    let color = |t: isize| image.is_dark(x + t * dx, y + t * dy);
+    let mut runs = [0; 5];
+
+    let mut t = 0;
+    for &(run, dark) in &[(2, true), (1, false), (0, true)] {
+        while color(t) == Some(dark) {
+            runs[run] += 1;
+            t -= 1;
+        }
+    }
+    let backward = runs[2];
+    let start_inside = color(t).is_some();
+
+    let mut t = 1;
+    for &(run, dark) in &[(2, true), (3, false), (4, true)] {
+        while color(t) == Some(dark) {
+            runs[run] += 1;
+            t += 1;
+        }
+    }
+    let forward = runs[2] - backward;
+
+    // Reject patterns cut short by the image border.
+    if !start_inside || color(t).is_none() || !is_finder_ratio(&runs) {
+        return None;
+    }
+
+    // The center run covers the pixels from `1 - backward` to `forward`.
+    Some((runs, (forward as f64 - backward as f64 + 2.0) / 2.0))
+}
+
+/// Confirms a candidate found on row `y`, centered at column `cx`, by
+/// crossing it vertically and horizontally again.
+fn confirm_candidate(image: &GrayImage, cx: f64, y: usize) -> Option<FinderPattern> {
+    let x = cx as isize;
+    let (vertical, cy) = cross_check(image, x, y as isize, 0, 1)?;
+    let cy = y as f64 + cy;
+    let (horizontal, cx) = cross_check(image, x, cy as isize, 1, 0)?;
+    let cx = x as f64 + cx;
+
+    let total: usize = vertical.iter().chain(&horizontal).sum();
+    let module_size = total as f64 / 14.0;
+    Some(FinderPattern { center: Point::new(cx, cy), module_size, count: 1 })
+}
+
+/// Finds all finder patterns in the image, ordered by decreasing confidence.
+pub fn find_finder_patterns(image: &GrayImage) -> Vec<FinderPattern> {
+    let mut patterns: Vec<FinderPattern> = Vec::new();
+    let mut runs = Vec::new();
+
+    for y in 0..image.height {
+        // Collect the runs of the row as (start, length), starting with a dark
+        // run.
+        runs.clear();
+        let mut x = 0;
+        while x < image.width {
+            let dark = image.is_dark(x as isize, y as isize);
+            let start = x;
+            while x < image.width && image.is_dark(x as isize, y as isize) == dark {
+                x += 1;
+            }
+            if dark == Some(true) || !runs.is_empty() {
+                runs.push((start, x - start));
+            }
+        }
+
+        for window in runs.windows(5).step_by(2) {
+            let lengths = [window[0].1, window[1].1, window[2].1, window[3].1, window[4].1];
+            if !is_finder_ratio(&lengths) {
+                continue;
+            }
+            let cx = window[2].0 as f64 + window[2].1 as f64 / 2.0;
+            let candidate = match confirm_candidate(image, cx, y) {
+                Some(candidate) => candidate,
+                None => continue,
+            };
+
+            let existing = patterns.iter_mut().find(|p| {
+                p.center.distance(candidate.center) <= p.module_size
+                    && (p.module_size - candidate.module_size).abs() <= p.module_size / 2.0
+            });
+            match existing {
+                Some(p) => {
+                    let n = p.count as f64;
+                    p.center.x = (p.center.x * n + candidate.center.x) / (n + 1.0);
+                    p.center.y = (p.center.y * n + candidate.center.y) / (n + 1.0);
+                    p.module_size = (p.module_size * n + candidate.module_size) / (n + 1.0);
+                    p.count += 1;
+                }
+                None => patterns.push(candidate),
+            }
+        }
+    }
+
+    patterns.sort_by_key(|p| core::cmp::Reverse(p.count));
+    patterns
+}
+
+/// Orders three finder patterns as top-left, top-right and bottom-left.
+fn order_finder_patterns(patterns: [Point; 3]) -> [Point; 3] {
+    let [a, b, c] = patterns;
+    let (ab, bc, ca) = (a.distance(b), b.distance(c), c.distance(a));
+
+    // The top-left pattern is opposite to the longest side.
+    let (mut a, b, mut c) = if bc >= ab && bc >= ca {
+        (b, a, c)
+    } else if ca >= ab && ca >= bc {
+        (a, b, c)
+    } else {
+        (a, c, b)
+    };
+
+    if (c.x - b.x) * (a.y - b.y) - (c.y - b.y) * (a.x - b.x) < 0.0 {
+        core::mem::swap(&mut a, &mut c);
+    }
+    [b, c, a]
+}
+
+#[cfg(test)]
+mod finder_tests {
+    use crate::sample::{is_finder_ratio, order_finder_patterns, Point};
+
+    #[test]
+    fn test_ratio() {
+        assert!(is_finder_ratio(&[1, 1, 3, 1, 1]));
+        assert!(is_finder_ratio(&[4, 3, 13, 4, 5]));
+        assert!(!is_finder_ratio(&[1, 1, 1, 1, 1]));
+        assert!(!is_finder_ratio(&[4, 4, 4, 12, 4]));
+    }
+
+    #[test]
+    fn test_order() {
+        let tl = Point::new(10.0, 10.0);
+        let tr = Point::new(50.0, 12.0);
+        let bl = Point::new(8.0, 50.0);
+        assert_eq!(order_finder_patterns([tr, bl, tl]), [tl, tr, bl]);
+        assert_eq!(order_finder_patterns([bl, tl, tr]), [tl, tr, bl]);
+
+        // Rotated by 180 degrees.
+        let (tl, tr, bl) = (Point::new(50.0, 50.0), Point::new(10.0, 48.0), Point::new(52.0, 10.0));
+        assert_eq!(order_finder_patterns([tl, bl, tr]), [tl, tr, bl]);
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Perspective transform
+
+/// A projective transform between two planes, mapping `(x, y)` to
+/// `(x', y')` with `[x'w, y'w, w] = [x, y, 1] × M`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PerspectiveTransform {
+    m: [[f64; 3]; 3],
+}
+
+impl PerspectiveTransform {
+    /// Computes the transform mapping the unit square onto a quadrilateral.
+    /// The corners are given in the order (0, 0), (1, 0), (1, 1), (0, 1).
+    fn square_to_quad(quad: [Point; 4]) -> Self {
+        let [p0, p1, p2, p3] = quad;
+        let dx3 = p0.x - p1.x + p2.x - p3.x;
+        let dy3 = p0.y - p1.y + p2.y - p3.y;
+        if dx3 == 0.0 && dy3 == 0.0 {
+            // A parallelogram, the transform is affine.
+            return Self { m: [[p1.x - p0.x, p1.y - p0.y, 0.0], [p2.x - p1.x, p2.y - p1.y, 0.0], [p0.x, p0.y, 1.0]] };
+        }
+        let (dx1, dx2) = (p1.x - p2.x, p3.x - p2.x);
+        let (dy1, dy2) = (p1.y - p2.y, p3.y - p2.y);
+        let denominator = dx1 * dy2 - dx2 * dy1;
+        let a13 = (dx3 * dy2 - dx2 * dy3) / denominator;
+        let a23 = (dx1 * dy3 - dx3 * dy1) / denominator;
+        Self {
+            m: [
+                [p1.x - p0.x + a13 * p1.x, p1.y - p0.y + a13 * p1.y, a13],
+                [p3.x - p0.x + a23 * p3.x, p3.y - p0.y + a23 * p3.y, a23],
+                [p0.x, p0.y, 1.0],
+            ],
+        }
+    }
+
+    /// The inverse transform, up to a scale factor which does not matter for
+    /// projective coordinates.
+    fn adjugate(&self) -> Self {
+        let m = &self.m;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+        Self {
+            m: [
+                [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+                [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+                [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+            ],
+        }
+    }
+
+    /// The transform applying `self` first, then `other`.
+    fn then(&self, other: &Self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Self { m }
+    }
+
+    /// Computes the transform mapping each corner of `from` onto the
+    /// corresponding corner of `to`.
+    pub fn quad_to_quad(from: [Point; 4], to: [Point; 4]) -> Self {
+        Self::square_to_quad(from).adjugate().then(&Self::square_to_quad(to))
+    }
+
+    /// Maps a point through the transform.
+    pub fn transform(&self, p: Point) -> Point {
+        let m = &self.m;
+        let w = p.x * m[0][2] + p.y * m[1][2] + m[2][2];
+        Point::new((p.x * m[0][0] + p.y * m[1][0] + m[2][0]) / w, (p.x * m[0][1] + p.y * m[1][1] + m[2][1]) / w)
+    }
+}
+
+#[cfg(test)]
+mod perspective_tests {
+    use crate::sample::{PerspectiveTransform, Point};
+
+    fn assert_close(a: Point, b: Point) {
+        assert!(a.distance(b) < 1e-6, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_quad_to_quad() {
+        let from = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)];
+        let to = [Point::new(3.0, 4.0), Point::new(40.0, 8.0), Point::new(45.0, 50.0), Point::new(1.0, 35.0)];
+        let transform = PerspectiveTransform::quad_to_quad(from, to);
+        for (&f, &t) in from.iter().zip(&to) {
+            assert_close(transform.transform(f), t);
+        }
+
+        let inverse = PerspectiveTransform::quad_to_quad(to, from);
+        let p = Point::new(2.5, 7.5);
+        assert_close(inverse.transform(transform.transform(p)), p);
+    }
+
+    #[test]
+    fn test_affine() {
+        let from = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)];
+        let to = [Point::new(5.0, 5.0), Point::new(5.0, 25.0), Point::new(-15.0, 25.0), Point::new(-15.0, 5.0)];
+        let transform = PerspectiveTransform::quad_to_quad(from, to);
+        assert_close(transform.transform(Point::new(0.5, 0.5)), Point::new(-5.0, 15.0));
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Grid sampling
+
+/// Searches for the dark-light-dark rings of an alignment pattern around
+/// `estimate`. `right` and `down` are the image vectors of one module along
+/// the rows and columns of the symbol.
+fn find_alignment_pattern(image: &GrayImage, estimate: Point, right: Point, down: Point) -> Option<Point> {
+    let module_size = right.distance(Point::new(0.0, 0.0)).max(down.distance(Point::new(0.0, 0.0)));
+    let radius = (module_size * 4.0) as isize;
+    let score = |center: Point| {
+        let mut score = 0;
+        for i in -2..=2_i32 {
+            for j in -2..=2_i32 {
+                let (i, j) = (f64::from(i), f64::from(j));
+                let p = Point::new(center.x + right.x * i + down.x * j, center.y + right.y * i + down.y * j);
+                let expected = i.abs().max(j.abs()) != 1.0;
+                if image.is_dark_at(p) == Some(expected) {
+                    score += 1;
+                }
+            }
+        }
+        score
+    };
+
+    // Average all positions achieving the best score, which form a plateau
+    // about the size of a module around the true center.
+    let (mut best, mut sum, mut n) = (0, Point::new(0.0, 0.0), 0.0);
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let p = Point::new(estimate.x.floor() + dx as f64 + 0.5, estimate.y.floor() + dy as f64 + 0.5);
+            let s = score(p);
+            if s > best {
+                best = s;
+                sum = Point::new(0.0, 0.0);
+                n = 0.0;
+            }
+            if s == best {
+                sum.x += p.x;
+                sum.y += p.y;
+                n += 1.0;
+            }
+        }
+    }
+    if best < 23 {
+        return None;
+    }
+    Some(Point::new(sum.x / n, sum.y / n))
+}
+
+/// Samples the modules of a symbol of the given width. `transform` maps the
+/// symbol's module coordinates, where module `(x, y)` covers the area from
+/// `(x, y)` to `(x + 1, y + 1)`, into the image.
+///
+/// Returns `Err(QrError::InvalidSymbol)` if a module lies outside the image.
+pub fn sample_grid(image: &GrayImage, transform: &PerspectiveTransform, width: usize) -> QrResult<Vec<Color>> {
+    let mut modules = Vec::with_capacity(width * width);
+    for y in 0..width {
+        for x in 0..width {
+            let p = transform.transform(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+            let dark = image.is_dark_at(p).ok_or(QrError::InvalidSymbol)?;
+            modules.push(if dark { Color::Dark } else { Color::Light });
+        }
+    }
+    Ok(modules)
+}
+
+/// Locates a QR code in the image and samples its modules in row-major
+/// order, ready for `decode::decode`.
+///
+/// Returns `Err(QrError::InvalidSymbol)` if no symbol can be located.
+pub fn detect(image: &GrayImage) -> QrResult<Vec<Color>> {
+    let patterns = find_finder_patterns(image);
+    if patterns.len() < 3 {
+        return Err(QrError::InvalidSymbol);
+    }
+    let module_size = (patterns[0].module_size + patterns[1].module_size + patterns[2].module_size) / 3.0;
+    let [top_left, top_right, bottom_left] =
+        order_finder_patterns([patterns[0].center, patterns[1].center, patterns[2].center]);
+
+    // The finder pattern centers are 7 modules in from the edges.
+    let span = (top_left.distance(top_right) + top_left.distance(bottom_left)) / (2.0 * module_size);
+    let version = ((span - 10.0) / 4.0).round().clamp(1.0, 40.0);
+    let width = version * 4.0 + 17.0;
+
+    // Without an alignment pattern, assume the symbol is a parallelogram.
+    let span = width - 7.0;
+    let right = Point::new((top_right.x - top_left.x) / span, (top_right.y - top_left.y) / span);
+    let down = Point::new((bottom_left.x - top_left.x) / span, (bottom_left.y - top_left.y) / span);
+    let mut reference = Point::new(top_right.x + bottom_left.x - top_left.x, top_right.y + bottom_left.y - top_left.y);
+    let mut reference_module = width - 3.5;
+
+    // The bottom-right alignment pattern sits 3 modules in from the finder
+    // pattern centers.
+    if version >= 2.0 {
+        let estimate = Point::new(reference.x - 3.0 * (right.x + down.x), reference.y - 3.0 * (right.y + down.y));
+        if let Some(alignment) = find_alignment_pattern(image, estimate, right, down) {
+            reference = alignment;
+            reference_module = width - 6.5;
+        }
+    }
+
+    let transform = PerspectiveTransform::quad_to_quad(
+        [
+            Point::new(3.5, 3.5),
+            Point::new(width - 3.5, 3.5),
+            Point::new(reference_module, reference_module),
+            Point::new(3.5, width - 3.5),
+        ],
+        [top_left, top_right, reference, bottom_left],
+    );
+    sample_grid(image, &transform, width as usize)
+}
+
+#[cfg(test)]
+mod detect_tests {
+    use crate::decode::decode;
+    use crate::sample::{detect, GrayImage, PerspectiveTransform, Point};
+    use crate::spec::{EcLevelM, EcLevelQ, QrSpec, Version1, Version5, Version7};
+    use crate::types::{Color, QrError};
+    use crate::QrCode;
+
+    /// Renders the symbol into an image of the given size, where `corners`
+    /// are the image positions of the corners of the symbol (without quiet
+    /// zone) in the order top-left, top-right, bottom-right, bottom-left.
+    fn render<V: QrSpec>(code: &QrCode<V>, size: usize, corners: [Point; 4]) -> Vec<u8> {
+        let modules: Vec<Color> = code.colors().collect();
+        let width = V::WIDTH as f64;
+        let square = [Point::new(0.0, 0.0), Point::new(width, 0.0), Point::new(width, width), Point::new(0.0, width)];
+        let transform = PerspectiveTransform::quad_to_quad(corners, square);
+
+        let mut pixels = vec![230; size * size];
+        for y in 0..size {
+            for x in 0..size {
+                let p = transform.transform(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+                if p.x >= 0.0 && p.y >= 0.0 && p.x < width && p.y < width {
+                    let index = p.y as usize * V::WIDTH as usize + p.x as usize;
+                    if modules[index] == Color::Dark {
+                        pixels[y * size + x] = 20;
+                    }
+                }
+            }
+        }
+        pixels
+    }
+
+    fn round_trip<V: QrSpec>(data: &[u8], size: usize, corners: [Point; 4]) {
+        let code = QrCode::<V>::new(data).unwrap();
+        let pixels = render(&code, size, corners);
+        let image = GrayImage::new(size, size, &pixels).unwrap();
+        let modules = detect(&image).unwrap();
+        let mut out = [0; 128];
+        let decoded = decode(&modules, &mut out).unwrap();
+        assert_eq!(&out[..decoded.len], data);
+    }
+
+    fn square(left: f64, top: f64, size: f64) -> [Point; 4] {
+        [
+            Point::new(left, top),
+            Point::new(left + size, top),
+            Point::new(left + size, top + size),
+            Point::new(left, top + size),
+        ]
+    }
+
+    #[test]
+    fn test_upright() {
+        round_trip::<Version1<EcLevelM>>(b"01234567", 120, square(18.0, 18.0, 84.0));
+        round_trip::<Version7<EcLevelQ>>(b"sampled from pixels", 260, square(20.0, 20.0, 225.0));
+    }
+
+    #[test]
+    fn test_rotated() {
+        let [a, b, c, d] = square(20.0, 20.0, 185.0);
+        round_trip::<Version5<EcLevelM>>(b"rotated by a quarter turn", 230, [b, c, d, a]);
+        round_trip::<Version5<EcLevelM>>(b"rotated by a half turn", 230, [c, d, a, b]);
+    }
+
+    #[test]
+    fn test_perspective() {
+        let corners =
+            [Point::new(30.0, 25.0), Point::new(200.0, 40.0), Point::new(215.0, 215.0), Point::new(20.0, 200.0)];
+        round_trip::<Version5<EcLevelM>>(b"seen from an angle", 240, corners);
+    }
+
+    #[test]
+    fn test_not_found() {
+        let pixels = [200; 64 * 64];
+        let image = GrayImage::new(64, 64, &pixels).unwrap();
+        assert_eq!(detect(&image), Err(QrError::InvalidSymbol));
+        assert_eq!(GrayImage::new(64, 65, &pixels).err(), Some(QrError::BufferTooSmall));
+        assert_eq!(GrayImage::new(usize::MAX, 2, &pixels).err(), Some(QrError::BufferTooSmall));
+    }
+}
+
+//}}}
@@ -0,0 +1,216 @@
+//! `Serialize` and `Deserialize` implementations for `QrCode`.
+//!
+//! A code is serialized as a struct of its version, error correction level,
+//! and modules. The modules are stored as bytes, row by row, with every row
+//! padded to whole bytes. The first module of a row is the most significant
+//! bit of its first byte; the last byte of a row holds the remaining modules
+//! in its low bits.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use typenum::Unsigned;
+
+use crate::spec::QrSpec;
+use crate::storage::Buffer;
+use crate::types::{EcLevel, Version};
+use crate::QrCode;
+
+const FIELDS: &[&str] = &["version", "ec_level", "modules"];
+
+/// Serializes the packed modules as a byte string.
+struct Modules<'a>(&'a [u8]);
+
+impl Serialize for Modules<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<V: QrSpec> Serialize for QrCode<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("QrCode", 3)?;
+        state.serialize_field("version", &V::VERSION)?;
+        state.serialize_field("ec_level", &V::EC_LEVEL)?;
+        state.serialize_field("modules", &Modules(&self.content))?;
+        state.end()
+    }
+}
+
+/// Deserializes the packed modules, accepting both byte strings and
+/// sequences of bytes, and checks their length and padding.
+struct PackedModules<V: QrSpec>(Buffer<V::ColorSize>);
+
+impl<'de, V: QrSpec> Deserialize<'de> for PackedModules<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(PackedModulesVisitor(PhantomData))
+    }
+}
+
+struct PackedModulesVisitor<V>(PhantomData<V>);
+
+impl<V: QrSpec> PackedModulesVisitor<V> {
+    fn expected_len() -> usize {
+        V::ColorSize::USIZE
+    }
+
+    /// Checks that the padding bits in the last byte of every row are clear,
+    /// so that equal symbols compare and hash equal.
+    fn check_padding<E: de::Error>(content: &[u8]) -> Result<(), E> {
+        let width = V::WIDTH as usize;
+        let row_byte_width = width.div_ceil(8);
+        let padding = !(0xff_u8 >> (row_byte_width * 8 - width));
+        if content.chunks(row_byte_width).any(|row| row[row_byte_width - 1] & padding != 0) {
+            return Err(E::custom("padding bits of the modules are not zero"));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, V: QrSpec> Visitor<'de> for PackedModulesVisitor<V> {
+    type Value = PackedModules<V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes of packed modules", Self::expected_len())
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        if bytes.len() != Self::expected_len() {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        let mut content = Buffer::new();
+        content.extend_from_slice(bytes).map_err(|()| E::invalid_length(bytes.len(), &self))?;
+        Self::check_padding(&content)?;
+        Ok(PackedModules(content))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut content = Buffer::new();
+        while let Some(byte) = seq.next_element()? {
+            content.push(byte).map_err(|_| de::Error::invalid_length(Self::expected_len() + 1, &self))?;
+        }
+        if content.len() != Self::expected_len() {
+            return Err(de::Error::invalid_length(content.len(), &self));
+        }
+        Self::check_padding(&content)?;
+        Ok(PackedModules(content))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    Version,
+    EcLevel,
+    Modules,
+}
+
+struct QrCodeVisitor<V>(PhantomData<V>);
+
+impl<V: QrSpec> QrCodeVisitor<V> {
+    /// Checks that the serialized version and error correction level are the
+    /// ones of `V`.
+    fn check<E: de::Error>(version: Version, ec_level: EcLevel) -> Result<(), E> {
+        if version != V::VERSION {
+            return Err(E::custom(format_args!("expected version {:?}, found {:?}", V::VERSION, version)));
+        }
+        if ec_level != V::EC_LEVEL {
+            return Err(E::custom(format_args!("expected EC level {:?}, found {:?}", V::EC_LEVEL, ec_level)));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, V: QrSpec> Visitor<'de> for QrCodeVisitor<V> {
+    type Value = QrCode<V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct QrCode")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let version = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let ec_level = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Self::check(version, ec_level)?;
+        let PackedModules::<V>(content) = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok(QrCode { content })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut version = None;
+        let mut ec_level = None;
+        let mut content = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Version if version.is_none() => version = Some(map.next_value()?),
+                Field::EcLevel if ec_level.is_none() => ec_level = Some(map.next_value()?),
+                Field::Modules if content.is_none() => content = Some(map.next_value::<PackedModules<V>>()?.0),
+                Field::Version => return Err(de::Error::duplicate_field("version")),
+                Field::EcLevel => return Err(de::Error::duplicate_field("ec_level")),
+                Field::Modules => return Err(de::Error::duplicate_field("modules")),
+            }
+        }
+        let version = version.ok_or_else(|| de::Error::missing_field("version"))?;
+        let ec_level = ec_level.ok_or_else(|| de::Error::missing_field("ec_level"))?;
+        Self::check(version, ec_level)?;
+        let content = content.ok_or_else(|| de::Error::missing_field("modules"))?;
+        Ok(QrCode { content })
+    }
+}
+
+impl<'de, V: QrSpec> Deserialize<'de> for QrCode<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("QrCode", FIELDS, QrCodeVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use crate::spec::{EcLevelH, EcLevelM, Version1, Version2};
+    use crate::types::{Color, EcLevel, Version};
+    use crate::QrCode;
+
+    #[test]
+    fn test_round_trip() {
+        let code = QrCode::<Version2<EcLevelH>>::new(b"serialized").unwrap();
+        let json = serde_json::to_string(&code).unwrap();
+        assert!(json.starts_with(r#"{"version":{"Normal":2},"ec_level":"H","modules":["#));
+        let decoded: QrCode<Version2<EcLevelH>> = serde_json::from_str(&json).unwrap();
+        assert!(decoded.colors().eq(code.colors()));
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let json = serde_json::to_string(&code).unwrap();
+        let err = serde_json::from_str::<QrCode<Version1<EcLevelH>>>(&json).err().unwrap();
+        assert!(err.to_string().starts_with("expected EC level H, found M"));
+
+        let too_long = json.replace("]}", ",0]}");
+        assert!(serde_json::from_str::<QrCode<Version1<EcLevelM>>>(&too_long).is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        // A row of version 1 takes 3 bytes, with the last 5 modules in the
+        // low bits of the third byte.
+        let code = QrCode::<Version1<EcLevelM>>::new(b"01234567").unwrap();
+        let mut value = serde_json::to_value(&code).unwrap();
+        let modules = value["modules"].as_array_mut().unwrap();
+        assert!(modules.iter().skip(2).step_by(3).all(|b| b.as_u64().unwrap() < 0x20));
+
+        modules[5] = (modules[5].as_u64().unwrap() | 0x20).into();
+        let err = serde_json::from_value::<QrCode<Version1<EcLevelM>>>(value).err().unwrap();
+        assert!(err.to_string().starts_with("padding bits of the modules are not zero"));
+    }
+
+    #[test]
+    fn test_core_types() {
+        assert_eq!(serde_json::to_string(&Version::Micro(2)).unwrap(), r#"{"Micro":2}"#);
+        assert_eq!(serde_json::from_str::<EcLevel>(r#""Q""#).unwrap(), EcLevel::Q);
+        assert_eq!(serde_json::to_string(&[Color::Light, Color::Dark]).unwrap(), r#"["Light","Dark"]"#);
+    }
+}
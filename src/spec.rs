@@ -77,11 +77,12 @@ fn main() {
         let total_size = ec * (c1 + c2) + s1 * c1 + s2 * c2;
         let width = i * 4 + 21;
         println!(
-            "  Version{}, {}, {}, {}, {} => [",
+            "  Version{}, {}, {}, {}, {}, {} => [",
             i + 1,
             TypeNum(total_size),
             TypeNum((width + 7) / 8 * width),
             TypeNum(width * width / 4 + 1),
+            TypeNum(width),
             i + 1
         );
         for l in 0..4 {
@@ -143,11 +144,12 @@ impl Display for TypeNum {
 ```
 */
 
-use crate::types::{EcLevel, Version};
+use crate::bits::{max_number, DATA_LENGTHS};
+use crate::types::{EcLevel, Mode, QrError, QrResult, Version};
 use core::marker::PhantomData;
 use heapless::consts::*;
 use heapless::ArrayLength;
-use typenum::{UInt, UTerm, B0, B1};
+use typenum::{Prod, Quot, UInt, UTerm, Unsigned, B0, B1};
 
 pub trait QrSpec {
     /// EC_BYTES_PER_BLOCK * (BLOCK_1_COUNT + BLOCK_2_COUNT) + BLOCK_1_COUNT *
@@ -163,6 +165,11 @@ pub trait QrSpec {
     type ColorSize: ArrayLength<u8>;
     /// BLOCK_1_COUNT * BLOCK_1_SIZE + BLOCK_2_COUNT * BLOCK_2_SIZE
     type BitsSize: ArrayLength<u8>;
+    /// BitsSize * 8 * 3 / 10, at least the most characters the data bits can
+    /// hold, as digits take 10 bits per 3
+    type TextSize: ArrayLength<u8>;
+    /// WIDTH, for capacities computed at the type level
+    type Width: Unsigned;
 
     const WIDTH: i16;
     const BLOCK_1_SIZE: usize;
@@ -173,8 +180,405 @@ pub trait QrSpec {
     const VERSION: Version;
     const EC_LEVEL: EcLevel;
     const AREA: usize = (Self::WIDTH * Self::WIDTH) as usize;
+    const BLOCK_LAYOUT: BlockLayout = BlockLayout::new(
+        Self::BLOCK_1_SIZE,
+        Self::BLOCK_1_COUNT,
+        Self::BLOCK_2_SIZE,
+        Self::BLOCK_2_COUNT,
+        Self::EC_BYTES_PER_BLOCK,
+    );
+
+    /// The bytes of the data bits, `Bits`.
+    const BITS_BYTES: usize = Self::BitsSize::USIZE;
+    /// The bytes of all interleaved codewords, as returned by
+    /// `construct_codewords`. The encoder itself does not buffer them.
+    const CODEWORDS_BYTES: usize = Self::TotalSize::USIZE;
+    /// The bytes of a `Canvas`, which stores 2 bits per module.
+    const CANVAS_BYTES: usize = Self::CanvasSize::USIZE;
+    /// The bytes of the packed modules of a finished `QrCode`.
+    const MODULES_BYTES: usize = Self::ColorSize::USIZE;
+    /// The bytes of the buffers `QrCode::new` keeps on the stack at once: the
+    /// data bits, the canvas, the matrix to score the masks, one error
+    /// correction block and the resulting code.
+    ///
+    /// The compiler may add copies of the buffers when moving them, especially
+    /// in debug builds, so leave a margin when sizing the stack from this, or
+    /// use `QrStorage`, whose `SIZE` is exact.
+    ///
+    ///     use qrcode::spec::{EcLevelM, QrSpec, Version10};
+    ///
+    ///     const _: () = assert!(Version10::<EcLevelM>::ENCODE_STACK_BYTES <= 8 * 1024);
+    const ENCODE_STACK_BYTES: usize =
+        Self::BITS_BYTES + Self::CANVAS_BYTES + 2 * Self::MODULES_BYTES + Self::ECGenBufferSize::USIZE;
 }
 
+//------------------------------------------------------------------------------
+//{{{ Block layout
+
+/// How the data of a QR code is split into Reed-Solomon blocks.
+///
+/// The first `block_1_count` blocks contain `block_1_size` data codewords
+/// each, followed by `block_2_count` blocks of `block_2_size` data codewords.
+/// Every block is followed by `ec_bytes_per_block` error correction
+/// codewords.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct BlockLayout {
+    pub block_1_size: usize,
+    pub block_1_count: usize,
+    pub block_2_size: usize,
+    pub block_2_count: usize,
+    pub ec_bytes_per_block: usize,
+}
+
+impl BlockLayout {
+    const EMPTY: Self = Self::new(0, 0, 0, 0, 0);
+
+    const fn new(
+        block_1_size: usize,
+        block_1_count: usize,
+        block_2_size: usize,
+        block_2_count: usize,
+        ec_bytes_per_block: usize,
+    ) -> Self {
+        Self { block_1_size, block_1_count, block_2_size, block_2_count, ec_bytes_per_block }
+    }
+
+    /// Obtains the block layout of a version and error correction level at
+    /// runtime.
+    ///
+    ///     use qrcode::spec::BlockLayout;
+    ///     use qrcode::types::{EcLevel, Version};
+    ///
+    ///     let layout = BlockLayout::fetch(Version::Normal(5), EcLevel::Q).unwrap();
+    ///     assert_eq!(layout.blocks_count(), 4);
+    ///     assert_eq!(layout.data_len(), 62);
+    ///     assert_eq!(layout.ec_len(), 72);
+    ///
+    /// Returns `Err(QrError::InvalidVersion { .. })` for unsupported versions,
+    /// including all Micro QR codes.
+    pub fn fetch(version: Version, ec_level: EcLevel) -> QrResult<Self> {
+        version.fetch(ec_level, &BLOCK_LAYOUTS)
+    }
+
+    /// The total number of blocks.
+    pub const fn blocks_count(&self) -> usize {
+        self.block_1_count + self.block_2_count
+    }
+
+    /// The total number of data codewords.
+    pub const fn data_len(&self) -> usize {
+        self.block_1_size * self.block_1_count + self.block_2_size * self.block_2_count
+    }
+
+    /// The total number of error correction codewords.
+    pub const fn ec_len(&self) -> usize {
+        self.ec_bytes_per_block * self.blocks_count()
+    }
+
+    /// The total number of codewords.
+    pub const fn total_len(&self) -> usize {
+        self.data_len() + self.ec_len()
+    }
+
+    /// Computes where the `index`-th codeword of a block is placed in the
+    /// interleaved codeword sequence. Indices past the data codewords of the
+    /// block refer to its error correction codewords.
+    pub const fn interleaved_index(&self, block: usize, index: usize) -> usize {
+        let blocks_count = self.blocks_count();
+        let block_len = if block < self.block_1_count { self.block_1_size } else { self.block_2_size };
+        if index < self.block_1_size {
+            index * blocks_count + block
+        } else if index < block_len {
+            // The extra codeword of the longer blocks in group 2.
+            self.block_1_size * blocks_count + block - self.block_1_count
+        } else {
+            self.data_len() + (index - block_len) * blocks_count + block
+        }
+    }
+
+    /// Finds the data codeword placed at `position` of the interleaved
+    /// codeword sequence, counting the data codewords of all blocks in block
+    /// order, the reverse of `interleaved_index`. Returns `None` for the
+    /// positions of error correction codewords.
+    pub const fn data_index(&self, position: usize) -> Option<usize> {
+        let blocks_count = self.blocks_count();
+        if position >= self.data_len() {
+            return None;
+        }
+        let (block, index) = if position < self.block_1_size * blocks_count {
+            (position % blocks_count, position / blocks_count)
+        } else {
+            (self.block_1_count + position - self.block_1_size * blocks_count, self.block_1_size)
+        };
+        if block < self.block_1_count {
+            Some(block * self.block_1_size + index)
+        } else {
+            Some(self.block_1_count * self.block_1_size + (block - self.block_1_count) * self.block_2_size + index)
+        }
+    }
+
+    /// Iterates the number of data codewords of each block, in order.
+    pub fn block_sizes(&self) -> impl Iterator<Item = usize> {
+        let block_1 = core::iter::repeat_n(self.block_1_size, self.block_1_count);
+        block_1.chain(core::iter::repeat_n(self.block_2_size, self.block_2_count))
+    }
+}
+
+#[cfg(test)]
+mod block_layout_tests {
+    use crate::spec::{BlockLayout, EcLevelH, EcLevelL, QrSpec, Version1, Version40, Version7};
+    use crate::types::{EcLevel, QrError, Version};
+
+    fn check<V: QrSpec>() {
+        let layout = BlockLayout::fetch(V::VERSION, V::EC_LEVEL).unwrap();
+        assert_eq!(layout, V::BLOCK_LAYOUT);
+        assert_eq!(layout.block_1_size, V::BLOCK_1_SIZE);
+        assert_eq!(layout.block_1_count, V::BLOCK_1_COUNT);
+        assert_eq!(layout.block_2_size, V::BLOCK_2_SIZE);
+        assert_eq!(layout.block_2_count, V::BLOCK_2_COUNT);
+        assert_eq!(layout.ec_bytes_per_block, V::EC_BYTES_PER_BLOCK);
+    }
+
+    #[test]
+    fn test_matches_spec() {
+        check::<Version1<EcLevelL>>();
+        check::<Version7<EcLevelH>>();
+        check::<Version40<EcLevelL>>();
+        check::<Version40<EcLevelH>>();
+    }
+
+    #[test]
+    fn test_block_sizes() {
+        let layout = BlockLayout::fetch(Version::Normal(7), EcLevel::H).unwrap();
+        let sizes: Vec<usize> = layout.block_sizes().collect();
+        assert_eq!(sizes, [13, 13, 13, 13, 14]);
+        assert_eq!(layout.total_len(), 196);
+    }
+
+    #[test]
+    fn test_interleaved_index() {
+        let layout = BlockLayout::fetch(Version::Normal(5), EcLevel::Q).unwrap();
+        let mut seen = [false; 134];
+        for (block, size) in layout.block_sizes().enumerate() {
+            for index in 0..size + layout.ec_bytes_per_block {
+                let i = layout.interleaved_index(block, index);
+                assert!(!seen[i]);
+                seen[i] = true;
+            }
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(layout.interleaved_index(0, 1), 4);
+        assert_eq!(layout.interleaved_index(3, 15), 61);
+        assert_eq!(layout.interleaved_index(2, 16), 64);
+    }
+
+    #[test]
+    fn test_data_index() {
+        for v in 1..=40 {
+            for ec_level in &[EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+                let layout = BlockLayout::fetch(Version::Normal(v), *ec_level).unwrap();
+                let mut data_index = 0;
+                for (block, size) in layout.block_sizes().enumerate() {
+                    for index in 0..size {
+                        assert_eq!(layout.data_index(layout.interleaved_index(block, index)), Some(data_index));
+                        data_index += 1;
+                    }
+                    assert_eq!(layout.data_index(layout.interleaved_index(block, size)), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_versions_total() {
+        for v in 1..=40 {
+            let version = Version::Normal(v);
+            let total = BlockLayout::fetch(version, EcLevel::L).unwrap().total_len();
+            for ec_level in &[EcLevel::M, EcLevel::Q, EcLevel::H] {
+                assert_eq!(BlockLayout::fetch(version, *ec_level).unwrap().total_len(), total);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        assert_eq!(
+            BlockLayout::fetch(Version::Micro(2), EcLevel::L),
+            Err(QrError::InvalidVersion { version: Version::Micro(2), ec_level: EcLevel::L })
+        );
+        assert_eq!(
+            BlockLayout::fetch(Version::Normal(41), EcLevel::L),
+            Err(QrError::InvalidVersion { version: Version::Normal(41), ec_level: EcLevel::L })
+        );
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Runtime lookup
+
+/// The parameters of a version and error correction level, which a `QrSpec`
+/// provides as constants, for code that only knows them at runtime.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SpecParams {
+    pub version: Version,
+    pub ec_level: EcLevel,
+    /// The width and height of the symbol in modules.
+    pub width: i16,
+    /// How the codewords are split into Reed-Solomon blocks.
+    pub block_layout: BlockLayout,
+    /// The number of bits available for the data, including the mode
+    /// indicators and character counts.
+    pub data_bits: usize,
+}
+
+/// Obtains the parameters of a version and error correction level.
+///
+///     use qrcode::spec::spec_for;
+///     use qrcode::types::{EcLevel, Mode, Version};
+///
+///     let params = spec_for(Version::Normal(10), EcLevel::M).unwrap();
+///     assert_eq!(params.width, 57);
+///     assert_eq!(params.block_layout.ec_len(), 130);
+///     assert_eq!(params.data_bits, 1728);
+///     assert_eq!(params.capacity(Mode::Alphanumeric), 311);
+///
+/// Returns `Err(QrError::InvalidVersion { .. })` for unsupported versions,
+/// including all Micro QR codes, like `BlockLayout::fetch`.
+pub fn spec_for(version: Version, ec_level: EcLevel) -> QrResult<SpecParams> {
+    let block_layout = BlockLayout::fetch(version, ec_level)?;
+    let data_bits = version.fetch(ec_level, &DATA_LENGTHS)?;
+    Ok(SpecParams { version, ec_level, width: version.width(), block_layout, data_bits })
+}
+
+impl SpecParams {
+    /// The number of modules of the symbol.
+    pub const fn area(&self) -> usize {
+        self.width as usize * self.width as usize
+    }
+
+    /// The most characters which fit into the symbol as a single segment of
+    /// the mode, counting a Kanji character as one. See `capacity`.
+    pub fn capacity(&self, mode: Mode) -> usize {
+        chars_capacity(self.version, self.data_bits, mode)
+    }
+}
+
+/// The most characters of the mode which fit into a version and error
+/// correction level as a single segment, counting a Kanji character as one,
+/// as listed in ISO/IEC 18004 Table 7. Unlike `spec_for`, this supports Micro
+/// QR codes.
+///
+///     use qrcode::spec::capacity;
+///     use qrcode::types::{EcLevel, Mode, Version};
+///
+///     let limit = capacity(Version::Normal(4), EcLevel::M, Mode::Alphanumeric)?;
+///     assert_eq!(limit, 90);
+///     let typed = "HELLO WORLD";
+///     println!("{} characters remaining", limit - typed.len());
+///
+///     assert_eq!(capacity(Version::Micro(2), EcLevel::L, Mode::Numeric)?, 10);
+///     # Ok::<(), qrcode::types::QrError>(())
+///
+/// Returns `Err(QrError::InvalidVersion { .. })` if the version does not
+/// exist or does not support the error correction level, and
+/// `Err(QrError::UnsupportedCharacterSet)` if it does not support the mode,
+/// such as byte mode in Micro QR codes M1 and M2.
+pub fn capacity(version: Version, ec_level: EcLevel, mode: Mode) -> QrResult<usize> {
+    let data_bits = version.fetch(ec_level, &DATA_LENGTHS)?;
+    if let Version::Micro(_) = version {
+        // The same condition as `Bits::push_mode_indicator`: M1 has no mode
+        // indicator, M2 one bit of it, and so on.
+        let indicator = match mode {
+            Mode::Numeric => 0,
+            Mode::Alphanumeric => 1,
+            Mode::Byte => 2,
+            Mode::Kanji => 3,
+        };
+        if indicator > max_number(version.mode_bits_count()) {
+            return Err(QrError::UnsupportedCharacterSet);
+        }
+    }
+    Ok(chars_capacity(version, data_bits, mode))
+}
+
+fn chars_capacity(version: Version, data_bits: usize, mode: Mode) -> usize {
+    let length_bits = mode.length_bits_count(version);
+    let bits = data_bits.saturating_sub(version.mode_bits_count() + length_bits);
+    let chars = match mode {
+        Mode::Numeric => bits / 10 * 3 + [0, 0, 0, 0, 1, 1, 1, 2, 2, 2][bits % 10],
+        Mode::Alphanumeric => bits / 11 * 2 + usize::from(bits % 11 >= 6),
+        Mode::Byte => bits / 8,
+        Mode::Kanji => bits / 13,
+    };
+    chars.min(max_number(length_bits))
+}
+
+#[cfg(test)]
+mod spec_params_tests {
+    use crate::spec::{capacity, spec_for};
+    use crate::types::{EcLevel, Mode, QrError, Version};
+
+    #[test]
+    fn test_capacity() {
+        // ISO/IEC 18004:2015, Table 7.
+        for &(version, ec_level, capacities) in &[
+            (1, EcLevel::L, [41, 25, 17, 10]),
+            (1, EcLevel::H, [17, 10, 7, 4]),
+            (9, EcLevel::Q, [312, 189, 130, 80]),
+            (10, EcLevel::M, [513, 311, 213, 131]),
+            (27, EcLevel::L, [3517, 2132, 1465, 902]),
+            (40, EcLevel::L, [7089, 4296, 2953, 1817]),
+            (40, EcLevel::H, [3057, 1852, 1273, 784]),
+        ] {
+            let params = spec_for(Version::Normal(version), ec_level).unwrap();
+            let modes = [Mode::Numeric, Mode::Alphanumeric, Mode::Byte, Mode::Kanji];
+            let actual: Vec<usize> = modes.iter().map(|&mode| params.capacity(mode)).collect();
+            assert_eq!(actual, capacities, "version {}-{:?}", version, ec_level);
+        }
+    }
+
+    #[test]
+    fn test_micro_capacity() {
+        // ISO/IEC 18004:2015, Table 7, with 0 for unsupported modes.
+        for &(version, ec_level, capacities) in &[
+            (1, EcLevel::L, [5, 0, 0, 0]),
+            (2, EcLevel::L, [10, 6, 0, 0]),
+            (2, EcLevel::M, [8, 5, 0, 0]),
+            (3, EcLevel::L, [23, 14, 9, 6]),
+            (3, EcLevel::M, [18, 11, 7, 4]),
+            (4, EcLevel::L, [35, 21, 15, 9]),
+            (4, EcLevel::M, [30, 18, 13, 8]),
+            (4, EcLevel::Q, [21, 13, 9, 5]),
+        ] {
+            let modes = [Mode::Numeric, Mode::Alphanumeric, Mode::Byte, Mode::Kanji];
+            let actual: Vec<usize> = modes
+                .iter()
+                .map(|&mode| match capacity(Version::Micro(version), ec_level, mode) {
+                    Err(QrError::UnsupportedCharacterSet) => 0,
+                    result => result.unwrap(),
+                })
+                .collect();
+            assert_eq!(actual, capacities, "version M{}-{:?}", version, ec_level);
+        }
+        assert!(capacity(Version::Micro(1), EcLevel::M, Mode::Numeric).is_err());
+        assert!(capacity(Version::Micro(3), EcLevel::Q, Mode::Numeric).is_err());
+        assert!(capacity(Version::Micro(5), EcLevel::L, Mode::Numeric).is_err());
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        assert_eq!(
+            spec_for(Version::Micro(4), EcLevel::L),
+            Err(QrError::InvalidVersion { version: Version::Micro(4), ec_level: EcLevel::L })
+        );
+        assert!(spec_for(Version::Normal(0), EcLevel::M).is_err());
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+
 pub trait EcLvl {
     const EC_LEVEL: EcLevel;
 }
@@ -197,7 +601,7 @@ impl EcLvl for EcLevelH {
 }
 
 macro_rules! spec_normal_level {
-    ($name:ident, $level:ty, $ec_level:expr, $total_size:ty, $ec_gen_buffer_size:ty, $ec_blocks_size:ty, $color_size:ty, $canvas_size:ty, $bits_size:ty, $version_num:expr, $block_1_size:expr, $block_1_count:expr, $block_2_size:expr, $block_2_count:expr, $ec_bytes_per_block:expr) => {
+    ($name:ident, $level:ty, $ec_level:expr, $total_size:ty, $ec_gen_buffer_size:ty, $ec_blocks_size:ty, $color_size:ty, $canvas_size:ty, $bits_size:ty, $width:ty, $version_num:expr, $block_1_size:expr, $block_1_count:expr, $block_2_size:expr, $block_2_count:expr, $ec_bytes_per_block:expr) => {
         impl QrSpec for $name<$level> {
             type TotalSize = $total_size;
             type ECGenBufferSize = $ec_gen_buffer_size;
@@ -205,6 +609,8 @@ macro_rules! spec_normal_level {
             type CanvasSize = $canvas_size;
             type ColorSize = $color_size;
             type BitsSize = $bits_size;
+            type TextSize = Quot<Prod<$bits_size, U12>, U5>;
+            type Width = $width;
 
             const WIDTH: i16 = $version_num * 4 + 17;
             const BLOCK_1_SIZE: usize = $block_1_size;
@@ -219,18 +625,118 @@ macro_rules! spec_normal_level {
 }
 
 macro_rules! spec_normal {
-   {$($name:ident, $total_size:ty, $color_size:ty, $canvas_size:ty, $version_num:expr => [
+   {$($name:ident, $total_size:ty, $color_size:ty, $canvas_size:ty, $width:ty, $version_num:expr => [
        { $ec_gen_buffer_size_l:ty, $ec_blocks_size_l:ty, $bits_size_l:ty, $block_1_size_l:expr, $block_1_count_l:expr, $block_2_size_l:expr, $block_2_count_l:expr, $ec_bytes_per_block_l:expr },
        { $ec_gen_buffer_size_m:ty, $ec_blocks_size_m:ty, $bits_size_m:ty, $block_1_size_m:expr, $block_1_count_m:expr, $block_2_size_m:expr, $block_2_count_m:expr, $ec_bytes_per_block_m:expr },
        { $ec_gen_buffer_size_q:ty, $ec_blocks_size_q:ty, $bits_size_q:ty, $block_1_size_q:expr, $block_1_count_q:expr, $block_2_size_q:expr, $block_2_count_q:expr, $ec_bytes_per_block_q:expr },
        { $ec_gen_buffer_size_h:ty, $ec_blocks_size_h:ty, $bits_size_h:ty, $block_1_size_h:expr, $block_1_count_h:expr, $block_2_size_h:expr, $block_2_count_h:expr, $ec_bytes_per_block_h:expr },
    ]),*} => {$(
        pub struct $name<L: EcLvl>(PhantomData<L>);
-       spec_normal_level!($name, EcLevelL, EcLevel::L, $total_size, $ec_gen_buffer_size_l, $ec_blocks_size_l, $color_size, $canvas_size, $bits_size_l, $version_num, $block_1_size_l, $block_1_count_l, $block_2_size_l, $block_2_count_l, $ec_bytes_per_block_l);
-       spec_normal_level!($name, EcLevelM, EcLevel::M, $total_size, $ec_gen_buffer_size_m, $ec_blocks_size_m, $color_size, $canvas_size, $bits_size_m, $version_num, $block_1_size_m, $block_1_count_m, $block_2_size_m, $block_2_count_m, $ec_bytes_per_block_m);
-       spec_normal_level!($name, EcLevelQ, EcLevel::Q, $total_size, $ec_gen_buffer_size_q, $ec_blocks_size_q, $color_size, $canvas_size, $bits_size_q, $version_num, $block_1_size_q, $block_1_count_q, $block_2_size_q, $block_2_count_q, $ec_bytes_per_block_q);
-       spec_normal_level!($name, EcLevelH, EcLevel::H, $total_size, $ec_gen_buffer_size_h, $ec_blocks_size_h, $color_size, $canvas_size, $bits_size_h, $version_num, $block_1_size_h, $block_1_count_h, $block_2_size_h, $block_2_count_h, $ec_bytes_per_block_h);
-   )*};
+       spec_normal_level!($name, EcLevelL, EcLevel::L, $total_size, $ec_gen_buffer_size_l, $ec_blocks_size_l, $color_size, $canvas_size, $bits_size_l, $width, $version_num, $block_1_size_l, $block_1_count_l, $block_2_size_l, $block_2_count_l, $ec_bytes_per_block_l);
+       spec_normal_level!($name, EcLevelM, EcLevel::M, $total_size, $ec_gen_buffer_size_m, $ec_blocks_size_m, $color_size, $canvas_size, $bits_size_m, $width, $version_num, $block_1_size_m, $block_1_count_m, $block_2_size_m, $block_2_count_m, $ec_bytes_per_block_m);
+       spec_normal_level!($name, EcLevelQ, EcLevel::Q, $total_size, $ec_gen_buffer_size_q, $ec_blocks_size_q, $color_size, $canvas_size, $bits_size_q, $width, $version_num, $block_1_size_q, $block_1_count_q, $block_2_size_q, $block_2_count_q, $ec_bytes_per_block_q);
+       spec_normal_level!($name, EcLevelH, EcLevel::H, $total_size, $ec_gen_buffer_size_h, $ec_blocks_size_h, $color_size, $canvas_size, $bits_size_h, $width, $version_num, $block_1_size_h, $block_1_count_h, $block_2_size_h, $block_2_count_h, $ec_bytes_per_block_h);
+   )*
+
+       /// The block layouts of every spec, indexed in the same way as the
+       /// tables passed to `Version::fetch`. Micro QR codes are not supported
+       /// and are left empty.
+       static BLOCK_LAYOUTS: [[BlockLayout; 4]; 44] = [
+           $([
+               BlockLayout::new($block_1_size_l, $block_1_count_l, $block_2_size_l, $block_2_count_l, $ec_bytes_per_block_l),
+               BlockLayout::new($block_1_size_m, $block_1_count_m, $block_2_size_m, $block_2_count_m, $ec_bytes_per_block_m),
+               BlockLayout::new($block_1_size_q, $block_1_count_q, $block_2_size_q, $block_2_count_q, $ec_bytes_per_block_q),
+               BlockLayout::new($block_1_size_h, $block_1_count_h, $block_2_size_h, $block_2_count_h, $ec_bytes_per_block_h),
+           ],)*
+           [BlockLayout::EMPTY; 4],
+           [BlockLayout::EMPTY; 4],
+           [BlockLayout::EMPTY; 4],
+           [BlockLayout::EMPTY; 4],
+       ];
+
+       /// Calls `visitor` with every spec, for the tests which must cover all
+       /// of them.
+       #[cfg(test)]
+       pub(crate) fn for_each_spec<S: SpecVisitor>(visitor: &mut S) {
+           $(
+               visitor.visit::<$name<EcLevelL>>();
+               visitor.visit::<$name<EcLevelM>>();
+               visitor.visit::<$name<EcLevelQ>>();
+               visitor.visit::<$name<EcLevelH>>();
+           )*
+       }
+   };
+}
+
+/// A test over specs, see `for_each_spec`.
+#[cfg(test)]
+pub(crate) trait SpecVisitor {
+    fn visit<V: QrSpec>(&mut self);
+}
+
+#[cfg(test)]
+mod sizes_tests {
+    use crate::bits::Bits;
+    use crate::canvas::Canvas;
+    use crate::spec::{for_each_spec, spec_for, EcLevelH, EcLevelL, EcLevelM, QrSpec, SpecVisitor};
+    use crate::spec::{Version1, Version10, Version40};
+    use crate::storage::QrStorage;
+    use crate::types::Mode;
+    use crate::QrCode;
+    use core::mem::size_of;
+    use typenum::Unsigned;
+
+    fn check<V: QrSpec>() {
+        let width = V::WIDTH as usize;
+        let layout = V::BLOCK_LAYOUT;
+        let params = spec_for(V::VERSION, V::EC_LEVEL).unwrap();
+        assert_eq!((params.width, params.block_layout, params.area()), (V::WIDTH, layout, V::AREA));
+        assert_eq!(params.data_bits, Bits::<V>::new().max_len(V::EC_LEVEL).unwrap());
+        assert_eq!(V::BITS_BYTES, layout.data_len());
+        assert_eq!(V::CODEWORDS_BYTES, layout.total_len());
+        assert_eq!(V::CANVAS_BYTES, V::AREA / 4 + 1);
+        assert_eq!(V::MODULES_BYTES, width.div_ceil(8) * width);
+        assert_eq!(V::Width::USIZE, width);
+        assert_eq!(V::ECBlocksSize::USIZE, layout.ec_len());
+        assert_eq!(V::ECGenBufferSize::USIZE, layout.block_1_size.max(layout.block_2_size) + layout.ec_bytes_per_block);
+        // `Canvas::draw_rawbits` keeps the error correction codes in the
+        // matrix used to score the masks.
+        assert!(V::ECBlocksSize::USIZE <= V::MODULES_BYTES);
+        // `payloads::encode` buffers the text of a payload in `TextSize`.
+        assert!(V::TextSize::USIZE >= params.capacity(Mode::Numeric));
+    }
+
+    struct Sizes(usize);
+
+    impl SpecVisitor for Sizes {
+        fn visit<V: QrSpec>(&mut self) {
+            check::<V>();
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_all_specs() {
+        let mut sizes = Sizes(0);
+        for_each_spec(&mut sizes);
+        assert_eq!(sizes.0, 160);
+    }
+
+    /// Whether the buffers of an encoding take no more than the constants
+    /// promise, allowing for the lengths and other fields next to the byte
+    /// arrays, and their padding.
+    const fn fits_encode_stack<V: QrSpec>() -> bool {
+        let word = size_of::<usize>();
+        size_of::<QrCode<V>>() <= V::MODULES_BYTES + 2 * word
+            && size_of::<Bits<V>>() <= V::BITS_BYTES + 8 * word
+            && size_of::<Canvas<V>>() <= V::CANVAS_BYTES + 2 * word
+            && QrStorage::<V>::SIZE <= V::ENCODE_STACK_BYTES + 16 * word
+    }
+
+    const _: () = assert!(fits_encode_stack::<Version1<EcLevelH>>());
+    const _: () = assert!(fits_encode_stack::<Version10<EcLevelM>>());
+    const _: () = assert!(fits_encode_stack::<Version40<EcLevelL>>());
+    const _: () = assert!(Version40::<EcLevelL>::ENCODE_STACK_BYTES == 19080);
 }
 
 // --------------------------------------------------------
@@ -238,241 +744,241 @@ macro_rules! spec_normal {
 // --------------------------------------------------------
 
 spec_normal! {
-  Version1, U26, U63, U111, 1 => [
+  Version1, U26, U63, U111, U21, 1 => [
     { U26, U7, U19, 19, 1, 0, 0, 7 },
     { U26, U10, U16, 16, 1, 0, 0, 10 },
     { U26, U13, U13, 13, 1, 0, 0, 13 },
     { U26, U17, U9, 9, 1, 0, 0, 17 },
   ],
-  Version2, U44, U100, U157, 2 => [
+  Version2, U44, U100, U157, U25, 2 => [
     { U44, U10, U34, 34, 1, 0, 0, 10 },
     { U44, U16, U28, 28, 1, 0, 0, 16 },
     { U44, U22, U22, 22, 1, 0, 0, 22 },
     { U44, U28, U16, 16, 1, 0, 0, 28 },
   ],
-  Version3, U70, U116, U211, 3 => [
+  Version3, U70, U116, U211, U29, 3 => [
     { U70, U15, U55, 55, 1, 0, 0, 15 },
     { U70, U26, U44, 44, 1, 0, 0, 26 },
     { U35, U36, U34, 17, 2, 0, 0, 18 },
     { U35, U44, U26, 13, 2, 0, 0, 22 },
   ],
-  Version4, U100, U165, U273, 4 => [
+  Version4, U100, U165, U273, U33, 4 => [
     { U100, U20, U80, 80, 1, 0, 0, 20 },
     { U50, U36, U64, 32, 2, 0, 0, 18 },
     { U50, U52, U48, 24, 2, 0, 0, 26 },
     { U25, U64, U36, 9, 4, 0, 0, 16 },
   ],
-  Version5, U134, U185, U343, 5 => [
+  Version5, U134, U185, U343, U37, 5 => [
     { U134, U26, U108, 108, 1, 0, 0, 26 },
     { U67, U48, U86, 43, 2, 0, 0, 24 },
     { U34, U72, U62, 15, 2, 16, 2, 18 },
     { U34, U88, U46, 11, 2, 12, 2, 22 },
   ],
-  Version6, U172, U246, U421, 6 => [
+  Version6, U172, U246, U421, U41, 6 => [
     { U86, U36, U136, 68, 2, 0, 0, 18 },
     { U43, U64, U108, 27, 4, 0, 0, 16 },
     { U43, U96, U76, 19, 4, 0, 0, 24 },
     { U43, U112, U60, 15, 4, 0, 0, 28 },
   ],
-  Version7, U196, U270, U507, 7 => [
+  Version7, U196, U270, U507, U45, 7 => [
     { U98, U40, U156, 78, 2, 0, 0, 20 },
     { U49, U72, U124, 31, 4, 0, 0, 18 },
     { U33, U108, U88, 14, 2, 15, 4, 18 },
     { U40, U130, U66, 13, 4, 14, 1, 26 },
   ],
-  Version8, U242, U343, U601, 8 => [
+  Version8, U242, U343, U601, U49, 8 => [
     { U121, U48, U194, 97, 2, 0, 0, 24 },
     { U61, U88, U154, 38, 2, 39, 2, 22 },
     { U41, U132, U110, 18, 4, 19, 2, 22 },
     { U41, U156, U86, 14, 4, 15, 2, 26 },
   ],
-  Version9, U292, U371, U703, 9 => [
+  Version9, U292, U371, U703, U53, 9 => [
     { U146, U60, U232, 116, 2, 0, 0, 30 },
     { U59, U110, U182, 36, 3, 37, 2, 22 },
     { U37, U160, U132, 16, 4, 17, 4, 20 },
     { U37, U192, U100, 12, 4, 13, 4, 24 },
   ],
-  Version10, U346, U456, U813, 10 => [
+  Version10, U346, U456, U813, U57, 10 => [
     { U87, U72, U274, 68, 2, 69, 2, 18 },
     { U70, U130, U216, 43, 4, 44, 1, 26 },
     { U44, U192, U154, 19, 6, 20, 2, 24 },
     { U44, U224, U122, 15, 6, 16, 2, 28 },
   ],
-  Version11, U404, U488, U931, 11 => [
+  Version11, U404, U488, U931, U61, 11 => [
     { U101, U80, U324, 81, 4, 0, 0, 20 },
     { U81, U150, U254, 50, 1, 51, 4, 30 },
     { U51, U224, U180, 22, 4, 23, 4, 28 },
     { U37, U264, U140, 12, 3, 13, 8, 24 },
   ],
-  Version12, U466, U585, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, 12 => [
+  Version12, U466, U585, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, U65, 12 => [
     { U117, U96, U370, 92, 2, 93, 2, 24 },
     { U59, U176, U290, 36, 6, 37, 2, 22 },
     { U47, U260, U206, 20, 4, 21, 6, 26 },
     { U43, U308, U158, 14, 7, 15, 4, 28 },
   ],
-  Version13, U532, U621, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, 13 => [
+  Version13, U532, U621, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, U69, 13 => [
     { U133, U104, U428, 107, 4, 0, 0, 26 },
     { U60, U198, U334, 37, 8, 38, 1, 22 },
     { U45, U288, U244, 20, 8, 21, 4, 24 },
     { U34, U352, U180, 11, 12, 12, 4, 22 },
   ],
-  Version14, U581, U730, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, 14 => [
+  Version14, U581, U730, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, U73, 14 => [
     { U146, U120, U461, 115, 3, 116, 1, 30 },
     { U65, U216, U365, 40, 4, 41, 5, 24 },
     { U37, U320, U261, 16, 11, 17, 5, 20 },
     { U37, U384, U197, 12, 11, 13, 5, 24 },
   ],
-  Version15, U655, U770, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, 15 => [
+  Version15, U655, U770, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, U77, 15 => [
     { U110, U132, U523, 87, 5, 88, 1, 22 },
     { U66, U240, U415, 41, 5, 42, 5, 24 },
     { U55, U360, U295, 24, 5, 25, 7, 30 },
     { U37, U432, U223, 12, 11, 13, 7, 24 },
   ],
-  Version16, U733, U891, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, 16 => [
+  Version16, U733, U891, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, U81, 16 => [
     { U123, U144, U589, 98, 5, 99, 1, 24 },
     { U74, U280, U453, 45, 7, 46, 3, 28 },
     { U44, U408, U325, 19, 15, 20, 2, 24 },
     { U46, U480, U253, 15, 3, 16, 13, 30 },
   ],
-  Version17, U815, U935, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, 17 => [
+  Version17, U815, U935, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, U85, 17 => [
     { U136, U168, U647, 107, 1, 108, 5, 28 },
     { U75, U308, U507, 46, 10, 47, 1, 28 },
     { U51, U448, U367, 22, 1, 23, 15, 28 },
     { U43, U532, U283, 14, 2, 15, 17, 28 },
   ],
-  Version18, U901, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, 18 => [
+  Version18, U901, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, U89, 18 => [
     { U151, U180, U721, 120, 5, 121, 1, 30 },
     { U70, U338, U563, 43, 9, 44, 4, 26 },
     { U51, U504, U397, 22, 17, 23, 1, 28 },
     { U43, U588, U313, 14, 2, 15, 19, 28 },
   ],
-  Version19, U991, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, 19 => [
+  Version19, U991, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, U93, 19 => [
     { U142, U196, U795, 113, 3, 114, 4, 28 },
     { U71, U364, U627, 44, 3, 45, 11, 26 },
     { U48, U546, U445, 21, 17, 22, 4, 26 },
     { U40, U650, U341, 13, 9, 14, 16, 26 },
   ],
-  Version20, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, 20 => [
+  Version20, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, U97, 20 => [
     { U136, U224, U861, 107, 3, 108, 5, 28 },
     { U68, U416, U669, 41, 3, 42, 13, 26 },
     { U55, U600, U485, 24, 15, 25, 5, 30 },
     { U44, U700, U385, 15, 15, 16, 10, 28 },
   ],
-  Version21, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, 21 => [
+  Version21, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, U101, 21 => [
     { U145, U224, U932, 116, 4, 117, 4, 28 },
     { U68, U442, U714, 42, 17, 0, 0, 26 },
     { U51, U644, U512, 22, 17, 23, 6, 28 },
     { U47, U750, U406, 16, 19, 17, 6, 30 },
   ],
-  Version22, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B1>, 22 => [
+  Version22, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B1>, U105, 22 => [
     { U140, U252, U1006, 111, 2, 112, 7, 28 },
     { U74, U476, U782, 46, 17, 0, 0, 28 },
     { U55, U690, U568, 24, 7, 25, 16, 30 },
     { U37, U816, U442, 13, 34, 0, 0, 24 },
   ],
-  Version23, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, 23 => [
+  Version23, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, U109, 23 => [
     { U152, U270, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, 121, 4, 122, 5, 30 },
     { U76, U504, U860, 47, 4, 48, 14, 28 },
     { U55, U750, U614, 24, 11, 25, 14, 30 },
     { U46, U900, U464, 15, 16, 16, 14, 30 },
   ],
-  Version24, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, 24 => [
+  Version24, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, U113, 24 => [
     { U148, U300, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, 117, 6, 118, 4, 30 },
     { U74, U560, U914, 45, 6, 46, 14, 28 },
     { U55, U810, U664, 24, 11, 25, 16, 30 },
     { U47, U960, U514, 16, 30, 17, 2, 30 },
   ],
-  Version25, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, 25 => [
+  Version25, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, U117, 25 => [
     { U133, U312, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, 106, 8, 107, 4, 26 },
     { U76, U588, U1000, 47, 8, 48, 13, 28 },
     { U55, U870, U718, 24, 7, 25, 22, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, U538, 15, 22, 16, 13, 30 },
   ],
-  Version26, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, 26 => [
+  Version26, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, U121, 26 => [
     { U143, U336, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, 114, 10, 115, 2, 28 },
     { U75, U644, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, 46, 19, 47, 4, 28 },
     { U51, U952, U754, 22, 28, 23, 6, 28 },
     { U47, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B0>, U596, 16, 33, 17, 4, 30 },
   ],
-  Version27, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, 27 => [
+  Version27, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, U125, 27 => [
     { U153, U360, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, 122, 8, 123, 4, 30 },
     { U74, U700, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, 45, 22, 46, 3, 28 },
     { U54, U1020, U808, 23, 8, 24, 26, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, U628, 15, 12, 16, 28, 30 },
   ],
-  Version28, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, 28 => [
+  Version28, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, U129, 28 => [
     { U148, U390, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, 117, 3, 118, 10, 30 },
     { U74, U728, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, 45, 3, 46, 23, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, U871, 24, 4, 25, 31, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B0>, U661, 15, 11, 16, 31, 30 },
   ],
-  Version29, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, 29 => [
+  Version29, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, U133, 29 => [
     { U147, U420, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, 116, 7, 117, 7, 30 },
     { U74, U784, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, 45, 21, 46, 7, 28 },
     { U54, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, U911, 23, 1, 24, 37, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, U701, 15, 19, 16, 26, 30 },
   ],
-  Version30, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, 30 => [
+  Version30, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, U137, 30 => [
     { U146, U450, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, 115, 5, 116, 10, 30 },
     { U76, U812, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, B1>, 47, 19, 48, 10, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, U985, 24, 15, 25, 25, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, U745, 15, 23, 16, 25, 30 },
   ],
-  Version31, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B1>, 31 => [
+  Version31, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B1>, U141, 31 => [
     { U146, U480, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, 115, 13, 116, 3, 30 },
     { U75, U868, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, 46, 2, 47, 29, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, 24, 42, 25, 1, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, U793, 15, 23, 16, 28, 30 },
   ],
-  Version32, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, 32 => [
+  Version32, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, U145, 32 => [
     { U145, U510, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, 115, 17, 0, 0, 30 },
     { U75, U924, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, 46, 10, 47, 23, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, 24, 10, 25, 35, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, U845, 15, 19, 16, 35, 30 },
   ],
-  Version33, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, 33 => [
+  Version33, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, U149, 33 => [
     { U146, U540, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, 115, 17, 116, 1, 30 },
     { U75, U980, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, 46, 14, 47, 21, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, 24, 29, 25, 19, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, U901, 15, 11, 16, 46, 30 },
   ],
-  Version34, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B0>, B1>, 34 => [
+  Version34, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B0>, B1>, U153, 34 => [
     { U146, U570, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, 115, 13, 116, 6, 30 },
     { U75, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, 46, 14, 47, 23, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, 24, 44, 25, 7, 30 },
     { U47, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, U961, 16, 59, 17, 1, 30 },
   ],
-  Version35, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, 35 => [
+  Version35, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B1>, U157, 35 => [
     { U152, U570, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, 121, 12, 122, 7, 30 },
     { U76, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B0>, 47, 12, 48, 26, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, 24, 39, 25, 14, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, U986, 15, 22, 16, 41, 30 },
   ],
-  Version36, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, 36 => [
+  Version36, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, U161, 36 => [
     { U152, U600, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, 121, 6, 122, 14, 30 },
     { U76, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, 47, 6, 48, 34, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B0>, 24, 46, 25, 10, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, 15, 2, 16, 64, 30 },
   ],
-  Version37, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, 37 => [
+  Version37, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, U165, 37 => [
     { U153, U630, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, 122, 17, 123, 4, 30 },
     { U75, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, 46, 29, 47, 14, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B1>, B0>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, 24, 49, 25, 10, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, 15, 24, 16, 46, 30 },
   ],
-  Version38, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B1>, 38 => [
+  Version38, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B0>, B1>, U169, 38 => [
     { U153, U660, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B0>, 122, 4, 123, 18, 30 },
     { U75, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, B1>, B1>, B0>, 46, 13, 47, 32, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, 24, 48, 25, 14, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, B0>, 15, 42, 16, 32, 30 },
   ],
-  Version39, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, 39 => [
+  Version39, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B1>, B0>, B1>, B1>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B1>, B0>, B1>, B1>, U173, 39 => [
     { U148, U720, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, 117, 20, 118, 4, 30 },
     { U76, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B0>, B1>, B0>, B0>, B0>, 47, 40, 48, 7, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B0>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, 24, 43, 25, 22, 30 },
     { U46, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, 15, 10, 16, 67, 30 },
   ],
-  Version40, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, 40 => [
+  Version40, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, B1>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B1>, B1>, B1>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B0>, B1>, B0>, B0>, B1>, B1>, B0>, B0>, B1>, U177, 40 => [
     { U149, U750, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B1>, B1>, B0>, B0>, B0>, B1>, B1>, B0>, B0>, 118, 19, 119, 6, 30 },
     { U76, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B1>, B0>, B1>, B0>, B1>, B1>, B1>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B0>, B0>, B1>, B0>, B0>, B0>, B1>, B1>, B1>, B1>, B0>, 47, 18, 48, 31, 28 },
     { U55, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B1>, B1>, B1>, B1>, B1>, B1>, B0>, B0>, B0>, UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UInt<UTerm, B1>, B1>, B0>, B1>, B0>, B0>, B0>, B0>, B0>, B1>, B0>, 24, 34, 25, 34, 30 },
@@ -0,0 +1,280 @@
+//! Caller-provided storage for encoding.
+//!
+//! The buffers used while encoding a Version 40 code add up to about 20 KiB,
+//! which `QrCode::new` keeps on the stack. `QrStorage` holds all of them, so
+//! that small-RAM targets can decide where they live, e.g. in a `static`:
+//!
+//!     use core::cell::RefCell;
+//!     use qrcode::spec::{EcLevelM, Version10};
+//!     use qrcode::{QrCode, QrStorage};
+//!
+//!     // A `Mutex<RefCell<_>>` on embedded targets.
+//!     thread_local! {
+//!         static STORAGE: RefCell<QrStorage<Version10<EcLevelM>>> = RefCell::new(QrStorage::new());
+//!     }
+//!
+//!     STORAGE.with(|storage| {
+//!         let mut storage = storage.borrow_mut();
+//!         let code = QrCode::new_in(b"https://example.com", &mut storage).unwrap();
+//!         assert!(code.verify().is_ok());
+//!     });
+//!
+//! `QrStorage::new` is a `const fn` and does not touch the buffers, so it can
+//! also initialize a `MaybeUninit` in place with `MaybeUninit::write`.
+//!
+//! # Memory budget
+//!
+//! The mask patterns are scored one after another in a single scratch matrix
+//! inside the storage. Before that, the same matrix holds the error correction
+//! codes while the codewords are drawn. The best pattern is applied to the
+//! canvas in place, so
+//! `QrCode::new_in` needs no other buffers on the stack besides one error
+//! correction block of at most 153 bytes. `QrStorage::SIZE` gives the exact
+//! size for a version, which firmware can check at compile time:
+//!
+//!     use qrcode::spec::{EcLevelM, Version10};
+//!     use qrcode::QrStorage;
+//!
+//!     const _: () = assert!(QrStorage::<Version10<EcLevelM>>::SIZE <= 4096);
+//!
+//! The worst case over all error correction levels, on 64-bit targets (32-bit
+//! targets need 28 bytes less):
+//!
+//! | Version | Bytes  |
+//! |---------|--------|
+//! | 1       | 320    |
+//! | 5       | 896    |
+//! | 10      | 2064   |
+//! | 15      | 3624   |
+//! | 20      | 5808   |
+//! | 25      | 8280   |
+//! | 30      | 11432  |
+//! | 35      | 14824  |
+//! | 40      | 19000  |
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::slice;
+
+use heapless::{ArrayLength, Vec};
+
+use crate::bits::Bits;
+use crate::canvas::{Canvas, ColorMatrix};
+use crate::spec::QrSpec;
+use crate::QrCode;
+
+/// The buffers used by `QrCode::new_in` and `QrCode::with_bits_in`, and the
+/// resulting code.
+pub struct QrStorage<V: QrSpec> {
+    pub(crate) bits: Bits<V>,
+    pub(crate) canvas: Canvas<V>,
+    pub(crate) scratch: ColorMatrix<V>,
+    pub(crate) code: QrCode<V>,
+}
+
+impl<V: QrSpec> QrStorage<V> {
+    /// The number of bytes of the storage.
+    pub const SIZE: usize = core::mem::size_of::<Self>();
+
+    /// Constructs the storage without initializing the buffers.
+    pub const fn new() -> Self {
+        Self {
+            bits: Bits::new(),
+            canvas: Canvas::empty(),
+            scratch: ColorMatrix::empty(),
+            code: QrCode { content: Buffer::new() },
+        }
+    }
+}
+
+impl<V: QrSpec> Default for QrStorage<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A byte vector with a capacity of `N`, like `heapless::Vec<u8, N>`, but
+/// which can be built in `const` contexts, so that `QrStorage::new` can be
+/// used for a `static`. heapless 0.5 has no public `const` constructor.
+pub(crate) struct Buffer<N: ArrayLength<u8>> {
+    /// The bytes, of which the first `len` are initialized.
+    bytes: MaybeUninit<N::ArrayType>,
+    len: usize,
+}
+
+impl<N: ArrayLength<u8>> Buffer<N> {
+    /// Constructs an empty buffer without touching the bytes.
+    pub(crate) const fn new() -> Self {
+        Self { bytes: MaybeUninit::uninit(), len: 0 }
+    }
+
+    /// Appends `byte`, or returns it back if the buffer is full.
+    pub(crate) fn push(&mut self, byte: u8) -> Result<(), u8> {
+        if self.len == N::USIZE {
+            return Err(byte);
+        }
+        // SAFETY: `len` is less than the `N` bytes of the array.
+        unsafe { self.bytes.as_mut_ptr().cast::<u8>().add(self.len).write(byte) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends all of `bytes`, or none of them if they do not fit.
+    pub(crate) fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        if bytes.len() > N::USIZE - self.len {
+            return Err(());
+        }
+        // SAFETY: The bytes fit behind the first `len` of the array, and do
+        // not overlap it, as `self` is borrowed mutably.
+        unsafe {
+            let end = self.bytes.as_mut_ptr().cast::<u8>().add(self.len);
+            end.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+        }
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Removes all bytes.
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Replaces the contents with `len` zeroes in place.
+    pub(crate) fn fill_zeroes(&mut self, len: usize) {
+        let len = len.min(N::USIZE);
+        // SAFETY: `len` bytes fit into the array.
+        unsafe { self.bytes.as_mut_ptr().cast::<u8>().write_bytes(0, len) };
+        self.len = len;
+    }
+
+    /// Copies the bytes into a `heapless::Vec` of the same capacity.
+    pub(crate) fn to_vec(&self) -> Vec<u8, N> {
+        let mut vec = Vec::new();
+        // Both have a capacity of `N` bytes.
+        let _ = vec.extend_from_slice(self);
+        vec
+    }
+}
+
+// SAFETY: The buffer only holds bytes, like a `[u8; N]`. Unlike there, the
+// compiler does not know that of `N::ArrayType`.
+unsafe impl<N: ArrayLength<u8>> Send for Buffer<N> {}
+unsafe impl<N: ArrayLength<u8>> Sync for Buffer<N> {}
+
+impl<N: ArrayLength<u8>> Deref for Buffer<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: The first `len` bytes are initialized.
+        unsafe { slice::from_raw_parts(self.bytes.as_ptr().cast::<u8>(), self.len) }
+    }
+}
+
+impl<N: ArrayLength<u8>> DerefMut for Buffer<N> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: The first `len` bytes are initialized.
+        unsafe { slice::from_raw_parts_mut(self.bytes.as_mut_ptr().cast::<u8>(), self.len) }
+    }
+}
+
+impl<N: ArrayLength<u8>> Clone for Buffer<N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        // Both have a capacity of `N` bytes.
+        let _ = clone.extend_from_slice(self);
+        clone
+    }
+}
+
+impl<N: ArrayLength<u8>> PartialEq for Buffer<N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<N: ArrayLength<u8>> Eq for Buffer<N> {}
+
+impl<N: ArrayLength<u8>> Hash for Buffer<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<N: ArrayLength<u8>> fmt::Debug for Buffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+
+#[cfg(test)]
+mod storage_tests {
+    use crate::spec::{EcLevelH, EcLevelL, EcLevelM, Version1, Version40};
+    use crate::storage::Buffer;
+    use crate::{QrCode, QrStorage};
+    use heapless::consts::U4;
+
+    #[test]
+    fn test_new_in() {
+        let mut storage = QrStorage::<Version1<EcLevelM>>::new();
+        for data in [&b"first payload"[..], b"01234567", b""] {
+            let expected = QrCode::<Version1<EcLevelM>>::new(data).unwrap();
+            let code = QrCode::new_in(data, &mut storage).unwrap();
+            assert_eq!(*code, expected);
+        }
+        assert!(QrCode::new_in([b'x'; 20], &mut storage).is_err());
+    }
+
+    #[test]
+    fn test_large_version() {
+        let data: std::vec::Vec<u8> = (0..=255).cycle().take(1200).collect();
+        let mut storage = std::boxed::Box::new(QrStorage::<Version40<EcLevelL>>::new());
+        let code = QrCode::new_in(&data, &mut storage).unwrap();
+        assert!(*code == QrCode::new(&data).unwrap());
+
+        let mut storage = QrStorage::<Version1<EcLevelH>>::default();
+        let code = QrCode::new_in(b"HELLO", &mut storage).unwrap();
+        assert!(code.verify().is_ok());
+    }
+
+    #[test]
+    fn test_static() {
+        static mut STORAGE: QrStorage<Version1<EcLevelM>> = QrStorage::new();
+        // SAFETY: Only this test uses the storage.
+        let storage = unsafe { &mut *core::ptr::addr_of_mut!(STORAGE) };
+        let code = QrCode::new_in(b"01234567", storage).unwrap();
+        assert!(*code == QrCode::new(b"01234567").unwrap());
+    }
+
+    #[test]
+    fn test_buffer() {
+        let mut buffer = Buffer::<U4>::new();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.extend_from_slice(&[1, 2, 3]), Ok(()));
+        assert_eq!(buffer.extend_from_slice(&[4, 5]), Err(()));
+        assert_eq!(buffer.push(4), Ok(()));
+        assert_eq!(buffer.push(5), Err(5));
+        assert_eq!(*buffer, [1, 2, 3, 4]);
+        assert_eq!(buffer.to_vec(), [1, 2, 3, 4]);
+
+        let copy = buffer.clone();
+        buffer[0] = 0;
+        assert!(buffer != copy);
+
+        buffer.fill_zeroes(2);
+        assert_eq!(*buffer, [0, 0]);
+        buffer.fill_zeroes(9);
+        assert_eq!(*buffer, [0; 4]);
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_documented_sizes() {
+        assert_eq!(QrStorage::<Version1<EcLevelL>>::SIZE, 320);
+        assert_eq!(QrStorage::<Version40<EcLevelL>>::SIZE, 19000);
+    }
+}
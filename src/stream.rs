@@ -0,0 +1,508 @@
+//! The `stream` module splits data too large for one QR code into an endless
+//! sequence of frames, to be shown one after another as an animation, in the
+//! style of the Blockchain Commons UR format used by air-gapped wallets.
+//!
+//!     use qrcode::QrCode;
+//!     use qrcode::payloads;
+//!     use qrcode::spec::{Version10, EcLevelL};
+//!     use qrcode::stream::Sender;
+//!
+//!     let data = [0x5a; 1000];
+//!     let sender = Sender::new(&data, 200)?;
+//!     assert_eq!(sender.count(), 5);
+//!
+//!     for frame in sender.frames().take(8) {
+//!         let code: QrCode<Version10<EcLevelL>> = payloads::encode(&frame)?;
+//!         // Show the code for a moment.
+//!     }
+//!     # Ok::<(), qrcode::types::QrError>(())
+//!
+//! The first `count` frames carry one fragment each. Every later frame is
+//! fountain coded: it carries the XOR of a pseudo-random set of fragments,
+//! derived from its sequence number, so a receiver which missed some frames
+//! can still reassemble the data from whichever frames it catches next.
+//! `Sender::cycle` repeats the plain fragments instead, for receivers which do
+//! not implement the fountain code.
+//!
+//! A frame is written as text, `QRS:seq-count/len/checksum/` followed by the
+//! fragment in Base45 (RFC 9285). All of it fits into the alphanumeric mode,
+//! and it survives scanners which only return text. The checksum is the
+//! CRC-32 of the whole data. With the `std` feature, `Receiver` reassembles
+//! the frames.
+
+use core::convert::TryFrom;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::payloads::base45::read_base45;
+use crate::payloads::base45::write_base45;
+use crate::types::{QrError, QrResult};
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+//------------------------------------------------------------------------------
+//{{{ Sender
+
+/// Splits data into frames. See the module documentation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Sender<'a> {
+    data: &'a [u8],
+    fragment_len: usize,
+    count: usize,
+    checksum: u32,
+    cycle: bool,
+}
+
+impl<'a> Sender<'a> {
+    /// Splits `data` into fragments of at most `max_fragment_len` bytes. The
+    /// fragments are of about equal length, so the last one needs little
+    /// padding.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if `max_fragment_len` is 0, or
+    /// the data needs more than `u32::MAX` fragments.
+    pub fn new(data: &'a [u8], max_fragment_len: usize) -> QrResult<Self> {
+        if max_fragment_len == 0 {
+            return Err(QrError::InvalidField("max_fragment_len"));
+        }
+        let count = data.len().div_ceil(max_fragment_len).max(1);
+        if u32::try_from(count).is_err() {
+            return Err(QrError::InvalidField("max_fragment_len"));
+        }
+        let fragment_len = data.len().div_ceil(count).max(1);
+        Ok(Sender { data, fragment_len, count, checksum: crc32(data), cycle: false })
+    }
+
+    /// Repeats the plain fragments instead of fountain coding the frames
+    /// after the first `count`.
+    pub const fn cycle(mut self) -> Self {
+        self.cycle = true;
+        self
+    }
+
+    /// The number of fragments, and so the fewest frames a receiver needs.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The length of every fragment, in bytes.
+    pub fn fragment_len(&self) -> usize {
+        self.fragment_len
+    }
+
+    /// The frame with the sequence number `seq`, counted from 1.
+    pub fn frame(&self, seq: u32) -> Frame<'a> {
+        let seq = match self.cycle {
+            true => (seq.max(1) - 1) % self.count as u32 + 1,
+            false => seq.max(1),
+        };
+        Frame { sender: *self, seq }
+    }
+
+    /// All frames, from sequence number 1, without end.
+    pub fn frames(&self) -> impl Iterator<Item = Frame<'a>> {
+        let sender = *self;
+        (1..=u32::MAX).map(move |seq| sender.frame(seq))
+    }
+}
+
+/// A frame of the sequence, written by its `Display` implementation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Frame<'a> {
+    sender: Sender<'a>,
+    seq: u32,
+}
+
+impl Frame<'_> {
+    /// The sequence number, counted from 1.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// The indices of the fragments combined in this frame.
+    pub fn fragments(&self) -> Fragments {
+        Fragments::new(self.seq, self.sender.count, self.sender.checksum)
+    }
+
+    /// The byte at `index` of fragment `fragment`, zero past the end of the
+    /// data.
+    fn fragment_byte(&self, fragment: usize, index: usize) -> u8 {
+        let sender = &self.sender;
+        sender.data.get(fragment * sender.fragment_len + index).copied().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sender = &self.sender;
+        write!(f, "QRS:{}-{}/{}/{:08X}/", self.seq, sender.count, sender.data.len(), sender.checksum)?;
+        // Mix the fragments two bytes at a time, as Base45 encodes pairs.
+        for index in (0..sender.fragment_len).step_by(2) {
+            let mut pair = [0; 2];
+            for fragment in self.fragments() {
+                pair[0] ^= self.fragment_byte(fragment, index);
+                pair[1] ^= self.fragment_byte(fragment, index + 1);
+            }
+            let len = (sender.fragment_len - index).min(2);
+            write_base45(f, &pair[..len])?;
+        }
+        Ok(())
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Fountain code
+
+/// The SplitMix64 generator, which is tiny and has no weak seeds.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `bound`, which must be at most `u32::MAX + 1`.
+    fn below(&mut self, bound: usize) -> usize {
+        (((self.next() >> 32) * bound as u64) >> 32) as usize
+    }
+}
+
+/// The indices of the fragments combined in a frame, in increasing order.
+#[derive(Debug, Clone)]
+pub struct Fragments {
+    rng: SplitMix64,
+    index: usize,
+    count: usize,
+    needed: usize,
+    plain: bool,
+}
+
+impl Fragments {
+    fn new(seq: u32, count: usize, checksum: u32) -> Self {
+        let seq_index = seq as usize - 1;
+        if seq_index < count {
+            return Fragments { rng: SplitMix64(0), index: seq_index, count, needed: 1, plain: true };
+        }
+        let mut rng = SplitMix64((u64::from(checksum) << 32) | u64::from(seq));
+        // The ideal soliton distribution: degree d with probability
+        // 1/(d(d-1)), and 1 with probability 1/count, drawn as 2^32/x.
+        let x = (rng.next() >> 32) + 1;
+        let degree = (1_u64 << 32).div_ceil(x);
+        let needed = if degree > count as u64 { 1 } else { degree as usize };
+        Fragments { rng, index: 0, count, needed, plain: false }
+    }
+}
+
+impl Iterator for Fragments {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.needed == 0 {
+            return None;
+        }
+        if self.plain {
+            self.needed = 0;
+            return Some(self.index);
+        }
+        // Selection sampling picks each of the fragments left with the chance
+        // that it is one of the `needed` ones, without storing any.
+        while self.index < self.count {
+            let index = self.index;
+            self.index += 1;
+            if self.rng.below(self.count - index) < self.needed {
+                self.needed -= 1;
+                return Some(index);
+            }
+        }
+        None
+    }
+}
+
+/// The CRC-32 of `data`, as used by zlib and PNG.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg()))
+    })
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Receiver
+
+/// The parameters of a sequence, which every frame repeats.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct Header {
+    count: usize,
+    len: usize,
+    checksum: u32,
+}
+
+/// Reassembles the data from the frames of a `Sender`, in any order and with
+/// any frames missing, as long as enough arrive. This requires the `std`
+/// feature.
+///
+///     use qrcode::stream::{Receiver, Sender};
+///
+///     let data: Vec<u8> = (0..=255).collect();
+///     let sender = Sender::new(&data, 40)?;
+///     let mut receiver = Receiver::new();
+///
+///     // Drop every third frame, as if the camera missed it.
+///     for frame in sender.frames().filter(|frame| frame.seq() % 3 != 0) {
+///         if receiver.receive(&frame.to_string())? {
+///             break;
+///         }
+///     }
+///     assert_eq!(receiver.data(), Some(&data[..]));
+///     # Ok::<(), qrcode::types::QrError>(())
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Receiver {
+    max_len: usize,
+    header: Option<Header>,
+    fragment_len: usize,
+    /// The fragments known, by index.
+    fragments: BTreeMap<usize, Vec<u8>>,
+    /// Mixed frames which still combine several unknown fragments.
+    mixed: Vec<(Vec<usize>, Vec<u8>)>,
+    data: Option<Vec<u8>>,
+}
+
+/// The longest data a `Receiver::new` accepts: 16 MiB.
+#[cfg(feature = "std")]
+const DEFAULT_MAX_LEN: usize = 1 << 24;
+
+#[cfg(feature = "std")]
+impl Receiver {
+    /// A receiver waiting for the first frame, accepting up to 16 MiB of
+    /// data.
+    pub fn new() -> Self {
+        Self::with_max_len(DEFAULT_MAX_LEN)
+    }
+
+    /// A receiver accepting up to `max_len` bytes of data. Frames announcing
+    /// more are rejected, so a forged frame cannot make the receiver allocate
+    /// or compute without bound.
+    pub fn with_max_len(max_len: usize) -> Self {
+        Receiver { max_len, header: None, fragment_len: 0, fragments: BTreeMap::new(), mixed: Vec::new(), data: None }
+    }
+
+    /// Takes the text of a scanned frame, returning whether the data is
+    /// complete.
+    ///
+    /// Returns `Err(QrError::InvalidField)` if the frame is malformed, belongs
+    /// to another sequence, announces more data than the receiver accepts,
+    /// or the reassembled data does not match the checksum.
+    pub fn receive(&mut self, frame: &str) -> QrResult<bool> {
+        let (header, seq, payload) = parse_frame(frame)?;
+        if self.header.is_some_and(|known| known != header) || header.len > self.max_len {
+            return Err(QrError::InvalidField("header"));
+        }
+        let mut fragment = Vec::new();
+        read_base45(payload, &mut fragment)?;
+        if self.header.is_none() {
+            // The sender makes as few fragments as the length allows.
+            if fragment.is_empty() || header.count != header.len.div_ceil(fragment.len()).max(1) {
+                return Err(QrError::InvalidField("header"));
+            }
+            self.header = Some(header);
+            self.fragment_len = fragment.len();
+        } else if fragment.len() != self.fragment_len {
+            return Err(QrError::InvalidField("data"));
+        }
+        if self.data.is_some() {
+            return Ok(true);
+        }
+
+        let indices = Fragments::new(seq, header.count, header.checksum).collect();
+        self.add(indices, fragment);
+        if self.fragments.len() < header.count {
+            return Ok(false);
+        }
+
+        let mut data: Vec<u8> = self.fragments.values().flatten().copied().collect();
+        data.truncate(header.len);
+        if crc32(&data) != header.checksum {
+            *self = Receiver::with_max_len(self.max_len);
+            return Err(QrError::InvalidField("checksum"));
+        }
+        self.data = Some(data);
+        Ok(true)
+    }
+
+    /// Reduces a frame by the known fragments, and peels every frame which is
+    /// left with a single unknown one.
+    fn add(&mut self, indices: Vec<usize>, fragment: Vec<u8>) {
+        let mut queue = std::vec![(indices, fragment)];
+        while let Some((mut indices, mut fragment)) = queue.pop() {
+            indices.retain(|i| match self.fragments.get(i) {
+                Some(known) => {
+                    fragment.iter_mut().zip(known).for_each(|(byte, known)| *byte ^= known);
+                    false
+                }
+                None => true,
+            });
+            match indices[..] {
+                [] => {}
+                [index] => {
+                    self.fragments.insert(index, fragment);
+                    // Every mixed frame holding the new fragment may peel now.
+                    let (ready, waiting): (Vec<_>, Vec<_>) =
+                        self.mixed.drain(..).partition(|(mixed, _)| mixed.contains(&index));
+                    self.mixed = waiting;
+                    queue.extend(ready);
+                }
+                _ => self.mixed.push((indices, fragment)),
+            }
+        }
+    }
+
+    /// The number of fragments known, and the number needed.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.fragments.len(), self.header.map_or(0, |header| header.count))
+    }
+
+    /// The reassembled data, once complete.
+    pub fn data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Receiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits a frame into its header, sequence number and Base45 payload.
+#[cfg(feature = "std")]
+fn parse_frame(frame: &str) -> QrResult<(Header, u32, &[u8])> {
+    let invalid = QrError::InvalidField("header");
+    let rest = frame.strip_prefix("QRS:").ok_or(invalid)?;
+    let mut parts = rest.splitn(4, '/');
+    let mut next = || parts.next().ok_or(invalid);
+    let (seq, count) = next()?.split_once('-').ok_or(invalid)?;
+    let seq: u32 = seq.parse().map_err(|_| invalid)?;
+    let count: usize = count.parse().map_err(|_| invalid)?;
+    let len: usize = next()?.parse().map_err(|_| invalid)?;
+    let checksum = u32::from_str_radix(next()?, 16).map_err(|_| invalid)?;
+    let payload = next()?.as_bytes();
+    if seq == 0 || count == 0 || u32::try_from(count).is_err() {
+        return Err(invalid);
+    }
+    Ok((Header { count, len, checksum }, seq, payload))
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Tests
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::stream::{crc32, Sender};
+    use crate::types::QrError;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_sender() {
+        let data: Vec<u8> = (0..10).collect();
+        let sender = Sender::new(&data, 4).unwrap();
+        assert_eq!((sender.count(), sender.fragment_len()), (3, 4));
+        let frames: Vec<String> = sender.frames().take(3).map(|frame| frame.to_string()).collect();
+        assert_eq!(frames[0], "QRS:1-3/10/456CD746/100KB0");
+        assert_eq!(frames[2], "QRS:3-3/10/456CD746/W01000");
+
+        assert!(sender.frames().skip(3).take(100).all(|frame| frame.fragments().count() >= 1));
+        let degrees: Vec<usize> = sender.frames().skip(3).take(1000).map(|frame| frame.fragments().count()).collect();
+        assert!(degrees.contains(&3) && degrees.iter().all(|&degree| degree <= 3));
+        assert_eq!(sender.frame(7), sender.frame(7));
+
+        let cycle = sender.cycle();
+        assert_eq!(cycle.frames().nth(4).unwrap().to_string(), frames[1]);
+
+        assert_eq!(Sender::new(&data, 0), Err(QrError::InvalidField("max_fragment_len")));
+        assert_eq!(Sender::new(b"", 10).unwrap().frame(1).to_string(), "QRS:1-1/0/00000000/00");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_receiver() {
+        use crate::stream::Receiver;
+
+        let data: Vec<u8> = (0..5000).map(|i| (i * 7 % 251) as u8).collect();
+        let sender = Sender::new(&data, 300).unwrap();
+
+        // Only fountain coded frames.
+        let mut receiver = Receiver::new();
+        let needed =
+            sender.frames().skip(sender.count()).position(|frame| receiver.receive(&frame.to_string()).unwrap());
+        assert!(needed.unwrap() < 3 * sender.count());
+        assert_eq!(receiver.data(), Some(&data[..]));
+
+        // Frames in reverse, with duplicates.
+        let mut receiver = Receiver::new();
+        for seq in (1..=sender.count() as u32).rev().flat_map(|seq| [seq, seq]) {
+            assert_eq!(receiver.receive(&sender.frame(seq).to_string()), Ok(seq == 1));
+        }
+        assert_eq!(receiver.progress(), (17, 17));
+
+        let mut small = Receiver::with_max_len(4999);
+        assert_eq!(small.receive(&sender.frame(1).to_string()), Err(QrError::InvalidField("header")));
+        let forged = "QRS:9-4000000000/4000000000/00000000/00";
+        assert_eq!(Receiver::new().receive(forged), Err(QrError::InvalidField("header")));
+
+        let other = Sender::new(b"other", 300).unwrap();
+        let mut receiver = Receiver::new();
+        receiver.receive(&sender.frame(1).to_string()).unwrap();
+        assert_eq!(receiver.receive(&other.frame(1).to_string()), Err(QrError::InvalidField("header")));
+        assert_eq!(receiver.receive("QRS:1-17/5000/"), Err(QrError::InvalidField("header")));
+        assert_eq!(receiver.receive("hello"), Err(QrError::InvalidField("header")));
+        let frame = sender.frame(2).to_string();
+        assert_eq!(receiver.receive(&frame.replace('/', "/a")), Err(QrError::InvalidField("header")));
+        assert_eq!(receiver.receive(&(frame[..frame.len() - 1].to_owned() + "a")), Err(QrError::InvalidField("data")));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_qr_round_trip() {
+        use crate::decode::decode;
+        use crate::payloads::encode;
+        use crate::spec::{EcLevelL, QrSpec, Version10};
+        use crate::stream::Receiver;
+        use crate::types::Color;
+
+        type V = Version10<EcLevelL>;
+        let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor".repeat(8);
+        let sender = Sender::new(&data, 150).unwrap();
+        let mut receiver = Receiver::new();
+        for frame in sender.frames().step_by(2) {
+            let code = encode::<V>(&frame).unwrap();
+            // Denser than the byte mode, as the frame is alphanumeric.
+            assert!(code.inspect().unwrap().data_bits < 8 * frame.to_string().len());
+            let modules: Vec<Color> = code.colors().take(V::AREA).collect();
+            let mut out = [0; 512];
+            let decoded = decode(&modules, &mut out).unwrap();
+            if receiver.receive(core::str::from_utf8(&out[..decoded.len]).unwrap()).unwrap() {
+                break;
+            }
+        }
+        assert_eq!(receiver.data(), Some(&data[..]));
+    }
+}
+
+//}}}
@@ -3,18 +3,28 @@ use core::cmp::{Ordering, PartialOrd};
 use core::default::Default;
 use core::fmt::{Display, Error, Formatter};
 use core::ops::Not;
+use core::str::FromStr;
 
 //------------------------------------------------------------------------------
 //{{{ QrResult
 
 /// `QrError` encodes the error encountered when generating a QR code.
+///
+/// New variants may be added in minor releases. Every variant has a numeric
+/// code, see `code`, which never changes, for FFI and logging.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum QrError {
     /// The data is too long to encode into a QR code for the given version.
-    DataTooLong,
+    /// Both lengths are counted in bits.
+    DataTooLong { needed: usize, available: usize },
 
     /// The provided version / error correction level combination is invalid.
-    InvalidVersion,
+    InvalidVersion { version: Version, ec_level: EcLevel },
+
+    /// The width of a symbol is not the one of any QR code version.
+    InvalidWidth(usize),
 
     /// Some characters in the data cannot be supported by the provided QR code
     /// version.
@@ -22,34 +32,173 @@ pub enum QrError {
 
     /// The provided ECI designator is invalid. A valid designator should be
     /// between 0 and 999999.
-    InvalidEciDesignator,
+    InvalidEciDesignator(u32),
+
+    /// The ECI designator of the segment at `index` is not followed by any
+    /// data, so it would not apply to anything.
+    EmptyEciSegment { index: usize },
+
+    /// A character not belonging to the character set is found at `index` of
+    /// the data.
+    InvalidCharacter { index: usize, byte: u8 },
+
+    /// The data contains more errors than the error correction codes are able
+    /// to fix.
+    Uncorrectable,
+
+    /// The modules of a symbol are not consistent with its version, error
+    /// correction level or content.
+    InvalidSymbol,
+
+    /// The provided output buffer is too small for the result.
+    BufferTooSmall,
+
+    /// The encoded data does not fill the data codewords of the QR code
+    /// exactly, usually because `Bits::push_terminator` was not called. Use
+    /// `Bits::into_qr` to terminate the bits for you. Both lengths are
+    /// counted in bytes.
+    InvalidDataLength { len: usize, expected: usize },
+
+    /// A field of a payload is empty, too long or out of range. Holds the name
+    /// of the field.
+    InvalidField(&'static str),
 
-    /// A character not belonging to the character set is found.
-    InvalidCharacter,
+    /// The coordinates of a module lie outside of the symbol.
+    OutOfBounds,
+
+    /// The mask pattern cannot be used for the version, as Micro QR codes only
+    /// support four of the eight patterns.
+    UnsupportedMask,
 }
 
 impl Display for QrError {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
-        let msg = match *self {
-            QrError::DataTooLong => "data too long",
-            QrError::InvalidVersion => "invalid version",
-            QrError::UnsupportedCharacterSet => "unsupported character set",
-            QrError::InvalidEciDesignator => "invalid ECI designator",
-            QrError::InvalidCharacter => "invalid character",
-        };
-        fmt.write_str(msg)
+        match *self {
+            QrError::DataTooLong { needed, available } => {
+                write!(fmt, "data too long: needs {} bits, but only {} are available", needed, available)
+            }
+            QrError::InvalidVersion { version: Version::Normal(v), ec_level } => {
+                write!(fmt, "invalid version: {}-{:?}", v, ec_level)
+            }
+            QrError::InvalidVersion { version: Version::Micro(v), ec_level } => {
+                write!(fmt, "invalid version: M{}-{:?}", v, ec_level)
+            }
+            QrError::InvalidWidth(width) => write!(fmt, "invalid width: {} modules", width),
+            QrError::UnsupportedCharacterSet => fmt.write_str("unsupported character set"),
+            QrError::InvalidEciDesignator(designator) => write!(fmt, "invalid ECI designator: {}", designator),
+            QrError::EmptyEciSegment { index } => write!(fmt, "ECI designator without data in segment {}", index),
+            QrError::InvalidCharacter { index, byte } => {
+                write!(fmt, "invalid character: byte 0x{:02x} at index {}", byte, index)
+            }
+            QrError::Uncorrectable => fmt.write_str("too many errors to correct"),
+            QrError::InvalidSymbol => fmt.write_str("invalid symbol"),
+            QrError::BufferTooSmall => fmt.write_str("buffer too small"),
+            QrError::InvalidDataLength { len, expected } => {
+                write!(fmt, "invalid data length: {} bytes, expected {}", len, expected)
+            }
+            QrError::InvalidField(field) => write!(fmt, "invalid payload field: {}", field),
+            QrError::OutOfBounds => fmt.write_str("coordinates outside of the symbol"),
+            QrError::UnsupportedMask => fmt.write_str("mask pattern not supported by the version"),
+        }
+    }
+}
+
+impl QrError {
+    /// The numeric code of the variant, stable across releases, for mapping
+    /// errors across a C FFI or into logs. Codes start at 1, leaving 0 for
+    /// success, and are never reused.
+    ///
+    ///     use qrcode::types::QrError;
+    ///
+    ///     assert_eq!(QrError::DataTooLong { needed: 160, available: 152 }.code(), 1);
+    ///     assert_eq!(QrError::BufferTooSmall.code(), 10);
+    pub const fn code(&self) -> u32 {
+        match *self {
+            QrError::DataTooLong { .. } => 1,
+            QrError::InvalidVersion { .. } => 2,
+            QrError::InvalidWidth(_) => 3,
+            QrError::UnsupportedCharacterSet => 4,
+            QrError::InvalidEciDesignator(_) => 5,
+            QrError::EmptyEciSegment { .. } => 6,
+            QrError::InvalidCharacter { .. } => 7,
+            QrError::Uncorrectable => 8,
+            QrError::InvalidSymbol => 9,
+            QrError::BufferTooSmall => 10,
+            QrError::InvalidDataLength { .. } => 11,
+            QrError::InvalidField(_) => 12,
+            QrError::OutOfBounds => 13,
+            QrError::UnsupportedMask => 14,
+        }
     }
 }
 
+impl core::error::Error for QrError {}
+
 /// `QrResult` is a convenient alias for a QR code generation result.
 pub type QrResult<T> = Result<T, QrError>;
 
+#[cfg(test)]
+mod qr_error_tests {
+    use crate::types::{EcLevel, QrError, Version};
+
+    #[test]
+    fn test_display() {
+        let err = QrError::DataTooLong { needed: 160, available: 152 };
+        assert_eq!(err.to_string(), "data too long: needs 160 bits, but only 152 are available");
+        let err = QrError::InvalidVersion { version: Version::Micro(1), ec_level: EcLevel::M };
+        assert_eq!(err.to_string(), "invalid version: M1-M");
+        let err = QrError::InvalidCharacter { index: 3, byte: b'a' };
+        assert_eq!(err.to_string(), "invalid character: byte 0x61 at index 3");
+        let err = QrError::InvalidDataLength { len: 3, expected: 16 };
+        assert_eq!(err.to_string(), "invalid data length: 3 bytes, expected 16");
+        assert_eq!(QrError::InvalidField("latitude").to_string(), "invalid payload field: latitude");
+        assert_eq!(QrError::OutOfBounds.to_string(), "coordinates outside of the symbol");
+        let err = QrError::EmptyEciSegment { index: 2 };
+        assert_eq!(err.to_string(), "ECI designator without data in segment 2");
+    }
+
+    #[test]
+    fn test_codes() {
+        // Pinned: changing a code breaks the users mapping them.
+        let errors = [
+            (QrError::DataTooLong { needed: 1, available: 0 }, 1),
+            (QrError::InvalidVersion { version: Version::Normal(41), ec_level: EcLevel::L }, 2),
+            (QrError::InvalidWidth(20), 3),
+            (QrError::UnsupportedCharacterSet, 4),
+            (QrError::InvalidEciDesignator(1_000_000), 5),
+            (QrError::EmptyEciSegment { index: 0 }, 6),
+            (QrError::InvalidCharacter { index: 0, byte: 0 }, 7),
+            (QrError::Uncorrectable, 8),
+            (QrError::InvalidSymbol, 9),
+            (QrError::BufferTooSmall, 10),
+            (QrError::InvalidDataLength { len: 0, expected: 1 }, 11),
+            (QrError::InvalidField("name"), 12),
+            (QrError::OutOfBounds, 13),
+            (QrError::UnsupportedMask, 14),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.code(), code, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_error_trait() {
+        fn check() -> Result<(), Box<dyn std::error::Error>> {
+            Err(QrError::InvalidSymbol)?;
+            Ok(())
+        }
+        assert_eq!(check().unwrap_err().to_string(), "invalid symbol");
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Color
 
 /// The color of a module.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     /// The module is light colored.
     Light,
@@ -94,13 +243,64 @@ impl Not for Color {
     }
 }
 
+/// Converts `true` into `Color::Dark` and `false` into `Color::Light`.
+impl From<bool> for Color {
+    fn from(dark: bool) -> Self {
+        if dark {
+            Color::Dark
+        } else {
+            Color::Light
+        }
+    }
+}
+
+/// Converts `Color::Dark` into `true` and `Color::Light` into `false`.
+impl From<Color> for bool {
+    fn from(color: Color) -> Self {
+        color == Color::Dark
+    }
+}
+
+/// Converts the color into an 8-bit luma value, 0 for `Color::Dark` and 255
+/// for `Color::Light`.
+impl From<Color> for u8 {
+    fn from(color: Color) -> Self {
+        color.select(0, 255)
+    }
+}
+
+#[cfg(test)]
+mod color_tests {
+    use crate::types::Color;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Color::from(true), Color::Dark);
+        assert_eq!(Color::from(false), Color::Light);
+        assert!(bool::from(Color::Dark));
+        assert!(!bool::from(Color::Light));
+        assert_eq!(u8::from(Color::Dark), 0);
+        assert_eq!(u8::from(Color::Light), 255);
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Error correction level
 
 /// The error correction level. It allows the original information be recovered
 /// even if parts of the code is damaged.
+///
+/// Levels are ordered by robustness, `L < M < Q < H`: a greater level recovers
+/// more damage, at the cost of less room for data.
+///
+///     use qrcode::types::EcLevel;
+///
+///     assert!(EcLevel::L < EcLevel::H);
+///     assert_eq!(EcLevel::M.max(EcLevel::Q), EcLevel::Q);
 #[derive(Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EcLevel {
     /// Low error correction. Allows up to 7% of wrong blocks.
     L = 0,
@@ -115,6 +315,37 @@ pub enum EcLevel {
     H = 3,
 }
 
+impl EcLevel {
+    /// The approximate share of codewords, in percent, which can be restored
+    /// at this level.
+    ///
+    ///     use qrcode::types::EcLevel;
+    ///
+    ///     assert_eq!(EcLevel::Q.recovery_percent(), 25);
+    pub const fn recovery_percent(self) -> u8 {
+        match self {
+            EcLevel::L => 7,
+            EcLevel::M => 15,
+            EcLevel::Q => 25,
+            EcLevel::H => 30,
+        }
+    }
+}
+
+#[cfg(test)]
+mod ec_level_tests {
+    use crate::types::EcLevel;
+
+    #[test]
+    fn test_order_matches_recovery() {
+        let levels = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+        for pair in levels.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].recovery_percent() < pair[1].recovery_percent());
+        }
+    }
+}
+
 //}}}
 //------------------------------------------------------------------------------
 //{{{ Version
@@ -126,6 +357,8 @@ pub enum EcLevel {
 /// The smallest version is `Version::Normal(1)` of size 21×21, and the largest
 /// is `Version::Normal(40)` of size 177×177.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Version {
     /// A normal QR code version. The parameter should be between 1 and 40.
     Normal(i16),
@@ -137,13 +370,47 @@ pub enum Version {
 impl Version {
     /// Get the number of "modules" on each size of the QR code, i.e. the width
     /// and height of the code.
-    pub fn width(self) -> i16 {
+    pub const fn width(self) -> i16 {
         match self {
             Version::Normal(v) => v * 4 + 17,
             Version::Micro(v) => v * 2 + 9,
         }
     }
 
+    /// Finds the version of a symbol `width` modules wide: a normal QR code
+    /// for the widths 21 to 177 in steps of 4, or a Micro QR code for 11 to
+    /// 17 in steps of 2.
+    ///
+    ///     use qrcode::types::Version;
+    ///
+    ///     assert_eq!(Version::from_width(45), Some(Version::Normal(7)));
+    ///     assert_eq!(Version::from_width(13), Some(Version::Micro(2)));
+    ///     assert_eq!(Version::from_width(22), None);
+    pub const fn from_width(width: i16) -> Option<Version> {
+        match width {
+            21..=177 if width % 4 == 1 => Some(Version::Normal((width - 17) / 4)),
+            11..=17 if width % 2 == 1 => Some(Version::Micro((width - 9) / 2)),
+            _ => None,
+        }
+    }
+
+    /// The number of alignment patterns of the symbol: none for version 1
+    /// and Micro QR codes, and up to 46 for version 40.
+    ///
+    ///     use qrcode::types::Version;
+    ///
+    ///     assert_eq!(Version::Normal(1).alignment_pattern_count(), 0);
+    ///     assert_eq!(Version::Normal(7).alignment_pattern_count(), 6);
+    pub const fn alignment_pattern_count(self) -> usize {
+        match self {
+            Version::Normal(v) => {
+                let (_, len) = crate::canvas::alignment_pattern_positions(v);
+                (len * len).saturating_sub(3)
+            }
+            Version::Micro(_) => 0,
+        }
+    }
+
     /// Obtains an object from a hard-coded table.
     ///
     /// The table must be a 44×4 array. The outer array represents the content
@@ -153,7 +420,7 @@ impl Version {
     /// the order [L, M, Q, H].
     ///
     /// If the entry compares equal to the default value of T, this method
-    /// returns `Err(QrError::InvalidVersion)`.
+    /// returns `Err(QrError::InvalidVersion { .. })`.
     pub fn fetch<T>(self, ec_level: EcLevel, table: &[[T; 4]]) -> QrResult<T>
     where
         T: PartialEq + Default + Copy,
@@ -170,24 +437,164 @@ impl Version {
             }
             _ => {}
         }
-        Err(QrError::InvalidVersion)
+        Err(QrError::InvalidVersion { version: self, ec_level })
     }
 
     /// The number of bits needed to encode the mode indicator.
-    pub fn mode_bits_count(self) -> usize {
+    pub const fn mode_bits_count(self) -> usize {
         match self {
-            Version::Micro(a) => (a - 1).as_usize(),
+            Version::Micro(a) => (a - 1) as usize,
             _ => 4,
         }
     }
 
     /// Checks whether is version refers to a Micro QR code.
-    pub fn is_micro(self) -> bool {
+    pub const fn is_micro(self) -> bool {
         match self {
             Version::Normal(_) => false,
             Version::Micro(_) => true,
         }
     }
+
+    /// The width of the light border the standard asks for around the
+    /// symbol, in modules: 4 for QR codes and 2 for Micro QR codes.
+    pub const fn quiet_zone(self) -> u32 {
+        match self {
+            Version::Normal(_) => 4,
+            Version::Micro(_) => 2,
+        }
+    }
+}
+
+//}}}
+//------------------------------------------------------------------------------
+//{{{ Parsing
+
+/// The error returned when parsing a `Version` or `EcLevel` from a string.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ParseError {
+    /// The string is not a version number, optionally prefixed by `M` for
+    /// Micro QR code.
+    InvalidVersion,
+
+    /// The version number is outside of 1 to 40, or 1 to 4 for Micro QR code.
+    VersionOutOfRange(u32),
+
+    /// The string is not one of `L`, `M`, `Q` or `H`.
+    InvalidEcLevel,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            ParseError::InvalidVersion => fmt.write_str("invalid version, expected a number or M followed by a number"),
+            ParseError::VersionOutOfRange(v) => write!(fmt, "version {} out of range", v),
+            ParseError::InvalidEcLevel => fmt.write_str("invalid error correction level, expected L, M, Q or H"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+impl FromStr for Version {
+    type Err = ParseError;
+
+    /// Parses a version, either a number from 1 to 40 such as `"7"`, or a
+    /// Micro QR code version from `"M1"` to `"M4"`. The prefix is case
+    /// insensitive.
+    ///
+    ///     use qrcode::types::Version;
+    ///
+    ///     assert_eq!("7".parse(), Ok(Version::Normal(7)));
+    ///     assert_eq!("M2".parse(), Ok(Version::Micro(2)));
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let (micro, digits) = match s.strip_prefix(['M', 'm']) {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseError::InvalidVersion);
+        }
+        let number: u32 = digits.parse().map_err(|_| ParseError::VersionOutOfRange(u32::MAX))?;
+        match (micro, number) {
+            (false, 1..=40) => Ok(Version::Normal(number as i16)),
+            (true, 1..=4) => Ok(Version::Micro(number as i16)),
+            _ => Err(ParseError::VersionOutOfRange(number)),
+        }
+    }
+}
+
+impl FromStr for EcLevel {
+    type Err = ParseError;
+
+    /// Parses an error correction level from `"L"`, `"M"`, `"Q"` or `"H"`,
+    /// case insensitive.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        match s {
+            "L" | "l" => Ok(EcLevel::L),
+            "M" | "m" => Ok(EcLevel::M),
+            "Q" | "q" => Ok(EcLevel::Q),
+            "H" | "h" => Ok(EcLevel::H),
+            _ => Err(ParseError::InvalidEcLevel),
+        }
+    }
+}
+
+#[cfg(test)]
+mod version_tests {
+    use crate::types::Version;
+
+    #[test]
+    fn test_from_width() {
+        for v in 1..=40 {
+            assert_eq!(Version::from_width(Version::Normal(v).width()), Some(Version::Normal(v)));
+        }
+        for v in 1..=4 {
+            assert_eq!(Version::from_width(Version::Micro(v).width()), Some(Version::Micro(v)));
+        }
+        for width in [-21, 0, 9, 12, 19, 23, 178, 181, i16::MAX] {
+            assert_eq!(Version::from_width(width), None);
+        }
+    }
+
+    #[test]
+    fn test_alignment_pattern_count() {
+        // The 2 to 7 positions per row and column of ISO/IEC 18004:2006, Annex
+        // E, less the three corners taken by the finder patterns.
+        let counts = [(1, 0), (2, 1), (6, 1), (7, 6), (13, 6), (14, 13), (20, 13), (21, 22), (28, 33), (35, 46)];
+        for &(v, count) in &counts {
+            assert_eq!(Version::Normal(v).alignment_pattern_count(), count, "version {}", v);
+        }
+        assert_eq!(Version::Normal(40).alignment_pattern_count(), 46);
+        assert_eq!(Version::Micro(4).alignment_pattern_count(), 0);
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use crate::types::{EcLevel, ParseError, Version};
+
+    #[test]
+    fn test_version() {
+        assert_eq!("1".parse(), Ok(Version::Normal(1)));
+        assert_eq!("40".parse(), Ok(Version::Normal(40)));
+        assert_eq!("m4".parse(), Ok(Version::Micro(4)));
+        assert_eq!("0".parse::<Version>(), Err(ParseError::VersionOutOfRange(0)));
+        assert_eq!("M5".parse::<Version>(), Err(ParseError::VersionOutOfRange(5)));
+        assert_eq!("99999999999".parse::<Version>(), Err(ParseError::VersionOutOfRange(u32::MAX)));
+        for s in &["", "M", "+7", " 7", "7a", "V7"] {
+            assert_eq!(s.parse::<Version>(), Err(ParseError::InvalidVersion), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_ec_level() {
+        assert_eq!("L".parse(), Ok(EcLevel::L));
+        assert_eq!("q".parse(), Ok(EcLevel::Q));
+        assert_eq!("X".parse::<EcLevel>(), Err(ParseError::InvalidEcLevel));
+        assert_eq!("".parse::<EcLevel>(), Err(ParseError::InvalidEcLevel));
+        assert_eq!(ParseError::VersionOutOfRange(41).to_string(), "version 41 out of range");
+    }
 }
 
 //}}}
@@ -220,10 +627,10 @@ impl Mode {
     ///
     /// This method will return `Err(QrError::UnsupportedCharacterSet)` if the
     /// mode is not supported in the given version.
-    pub fn length_bits_count(self, version: Version) -> usize {
+    pub const fn length_bits_count(self, version: Version) -> usize {
         match version {
             Version::Micro(a) => {
-                let a = a.as_usize();
+                let a = a as usize;
                 match self {
                     Mode::Numeric => 2 + a,
                     Mode::Alphanumeric | Mode::Byte => 1 + a,
@@ -258,13 +665,17 @@ impl Mode {
     ///
     /// Note that in Kanji mode, the `raw_data_len` is the number of Kanjis,
     /// i.e. half the total size of bytes.
-    pub fn data_bits_count(self, raw_data_len: usize) -> usize {
-        match self {
-            Mode::Numeric => (raw_data_len * 10 + 2) / 3,
-            Mode::Alphanumeric => (raw_data_len * 11 + 1) / 2,
-            Mode::Byte => raw_data_len * 8,
-            Mode::Kanji => raw_data_len * 13,
-        }
+    pub const fn data_bits_count(self, raw_data_len: usize) -> usize {
+        // Divided first and saturating, so that lengths which cannot fit into
+        // any version do not overflow where `usize` has 16 bits, such as on
+        // AVR.
+        let (groups, rest) = match self {
+            Mode::Numeric => ((raw_data_len / 3).saturating_mul(10), [0, 4, 7][raw_data_len % 3]),
+            Mode::Alphanumeric => ((raw_data_len / 2).saturating_mul(11), raw_data_len % 2 * 6),
+            Mode::Byte => (raw_data_len.saturating_mul(8), 0),
+            Mode::Kanji => (raw_data_len.saturating_mul(13), 0),
+        };
+        groups.saturating_add(rest)
     }
 
     /// Find the lowest common mode which both modes are compatible with.
@@ -277,11 +688,13 @@ impl Mode {
     ///     assert!(a <= c);
     ///     assert!(b <= c);
     ///
-    pub fn max(self, other: Self) -> Self {
-        match self.partial_cmp(&other) {
-            Some(Ordering::Less) | Some(Ordering::Equal) => other,
-            Some(Ordering::Greater) => self,
-            None => Mode::Byte,
+    pub const fn max(self, other: Self) -> Self {
+        // Spelled out instead of using `partial_cmp`, which is not `const`.
+        match (self, other) {
+            (Mode::Numeric, Mode::Numeric) => Mode::Numeric,
+            (Mode::Numeric | Mode::Alphanumeric, Mode::Numeric | Mode::Alphanumeric) => Mode::Alphanumeric,
+            (Mode::Kanji, Mode::Kanji) => Mode::Kanji,
+            _ => Mode::Byte,
         }
     }
 }
@@ -310,6 +723,7 @@ mod mode_tests {
     use crate::types::Mode::{Alphanumeric, Byte, Kanji, Numeric};
 
     #[test]
+    #[allow(clippy::neg_cmp_op_on_partial_ord)]
     fn test_mode_order() {
         assert!(Numeric < Alphanumeric);
         assert!(Byte > Kanji);
@@ -327,6 +741,19 @@ mod mode_tests {
         assert_eq!(Alphanumeric.max(Numeric), Alphanumeric);
         assert_eq!(Kanji.max(Kanji), Kanji);
     }
+
+    #[test]
+    fn test_data_bits_count() {
+        for len in 0..10_000 {
+            assert_eq!(Numeric.data_bits_count(len), (len * 10).div_ceil(3));
+            assert_eq!(Alphanumeric.data_bits_count(len), (len * 11).div_ceil(2));
+            assert_eq!(Byte.data_bits_count(len), len * 8);
+            assert_eq!(Kanji.data_bits_count(len), len * 13);
+        }
+        for mode in [Numeric, Alphanumeric, Byte, Kanji] {
+            assert_eq!(mode.data_bits_count(usize::MAX), usize::MAX);
+        }
+    }
 }
 
 //}}}
@@ -0,0 +1,51 @@
+//! Checks `spec::capacity` against another encoder for every version, error
+//! correction level and mode: the capacity must fit into the version, and
+//! one more character must not.
+//!
+//! The reference is Nayuki's
+//! [qrcodegen](https://www.nayuki.io/page/qr-code-generator-library), which
+//! computes the capacity from its own tables, so together with the samples
+//! of ISO/IEC 18004 Table 7 in the unit tests, this covers the whole table.
+
+mod common;
+
+use qrcodegen::{Mask, QrCodeEcc, QrSegment, QrSegmentMode};
+
+use qrcode::spec::capacity;
+use qrcode::types::{EcLevel, Mode, Version};
+
+use crate::common::reference_ecc;
+
+/// A segment of `len` characters of the mode.
+fn segment(mode: Mode, len: usize) -> QrSegment {
+    match mode {
+        Mode::Numeric => QrSegment::make_numeric(&"7".repeat(len)),
+        Mode::Alphanumeric => QrSegment::make_alphanumeric(&"Q".repeat(len)),
+        Mode::Byte => QrSegment::make_bytes(&vec![0xa5; len]),
+        Mode::Kanji => QrSegment::new(QrSegmentMode::Kanji, len, vec![true; len * 13]),
+    }
+}
+
+/// Whether qrcodegen fits the segment into exactly the version.
+fn fits(version: u8, ecl: QrCodeEcc, segment: QrSegment) -> bool {
+    let version = qrcodegen::Version::new(version);
+    let mask = Some(Mask::new(0));
+    qrcodegen::QrCode::encode_segments_advanced(&[segment], ecl, version, version, mask, false).is_ok()
+}
+
+#[test]
+fn test_matches_reference() {
+    let mut failures = Vec::new();
+    for version in 1..=40 {
+        for ec_level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            let ecl = reference_ecc(ec_level);
+            for mode in [Mode::Numeric, Mode::Alphanumeric, Mode::Byte, Mode::Kanji] {
+                let len = capacity(Version::Normal(version), ec_level, mode).unwrap();
+                if !fits(version as u8, ecl, segment(mode, len)) || fits(version as u8, ecl, segment(mode, len + 1)) {
+                    failures.push(format!("{}-{:?} {:?}: {}", version, ec_level, mode, len));
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
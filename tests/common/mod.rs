@@ -0,0 +1,166 @@
+//! Helpers shared by the integration tests which compare our symbols against
+//! Nayuki's [qrcodegen](https://www.nayuki.io/page/qr-code-generator-library):
+//! the segments and the encoder both sides are given, the mapping of versions
+//! and error correction levels to qrcodegen, and the PBM fixtures.
+
+// Every test crate includes this module, and none of them uses all of it.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use qrcode::bits::Bits;
+use qrcode::canvas::{MaskPattern, MaskStrategy};
+use qrcode::spec::QrSpec;
+use qrcode::types::{Color, EcLevel, Version};
+use qrcode::QrCode;
+
+/// A segment of data, encoded the same way by both encoders.
+pub enum Segment<'a> {
+    Numeric(&'a str),
+    Alphanumeric(&'a str),
+    Byte(&'a [u8]),
+    Eci(u32),
+}
+
+/// A version and error correction level, with our encoder for them.
+#[derive(Clone, Copy)]
+pub struct Spec {
+    pub version: Version,
+    pub ec_level: EcLevel,
+    /// Encodes the segments with the given mask, returning the modules row by
+    /// row, dark being `true`.
+    pub encode: fn(&[Segment], MaskPattern) -> Vec<bool>,
+}
+
+impl Spec {
+    pub const fn of<V: QrSpec>() -> Self {
+        Self { version: V::VERSION, ec_level: V::EC_LEVEL, encode: encode::<V> }
+    }
+
+    pub fn width(&self) -> usize {
+        self.version.width() as usize
+    }
+}
+
+fn encode<V: QrSpec>(segments: &[Segment], mask: MaskPattern) -> Vec<bool> {
+    let mut bits = Bits::<V>::new();
+    for segment in segments {
+        match *segment {
+            Segment::Numeric(digits) => bits.push_numeric_data(digits.as_bytes()),
+            Segment::Alphanumeric(text) => bits.push_alphanumeric_data(text.as_bytes()),
+            Segment::Byte(data) => bits.push_byte_segment(data),
+            Segment::Eci(designator) => bits.push_eci_designator(designator),
+        }
+        .unwrap();
+    }
+    bits.push_terminator().unwrap();
+    let code = QrCode::<V>::with_bits_and_mask_strategy(bits, MaskStrategy::Fixed(mask)).unwrap();
+    code.colors().take(V::AREA).map(|c| c == Color::Dark).collect()
+}
+
+/// The qrcodegen error correction level of `ec_level`.
+pub fn reference_ecc(ec_level: EcLevel) -> qrcodegen::QrCodeEcc {
+    use qrcodegen::QrCodeEcc;
+
+    match ec_level {
+        EcLevel::L => QrCodeEcc::Low,
+        EcLevel::M => QrCodeEcc::Medium,
+        EcLevel::Q => QrCodeEcc::Quartile,
+        EcLevel::H => QrCodeEcc::High,
+    }
+}
+
+/// Encodes the segments with qrcodegen in exactly the version of `spec`,
+/// with the given mask or letting it choose one, and returns the mask and
+/// the modules.
+pub fn reference(spec: Spec, segments: &[Segment], mask: Option<MaskPattern>) -> (MaskPattern, Vec<bool>) {
+    use qrcodegen::{Mask, QrSegment};
+
+    let segments: Vec<QrSegment> = segments
+        .iter()
+        .map(|segment| match *segment {
+            Segment::Numeric(digits) => QrSegment::make_numeric(digits),
+            Segment::Alphanumeric(text) => QrSegment::make_alphanumeric(text),
+            Segment::Byte(data) => QrSegment::make_bytes(data),
+            Segment::Eci(designator) => QrSegment::make_eci(designator),
+        })
+        .collect();
+    let version = match spec.version {
+        Version::Normal(v) => qrcodegen::Version::new(v as u8),
+        Version::Micro(_) => panic!("qrcodegen has no Micro QR codes"),
+    };
+    let ecl = reference_ecc(spec.ec_level);
+    let mask = mask.map(|mask| Mask::new(mask as u8));
+    let code = qrcodegen::QrCode::encode_segments_advanced(&segments, ecl, version, version, mask, false).unwrap();
+    let size = code.size();
+    let modules = (0..size).flat_map(|y| (0..size).map(move |x| (x, y))).map(|(x, y)| code.get_module(x, y));
+    (MaskPattern::from_bits(code.mask().value()).unwrap(), modules.collect())
+}
+
+/// The path of the fixture `name` in `tests/<dir>`.
+pub fn fixture_path(dir: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir).join(format!("{}.pbm", name))
+}
+
+/// A fixture: a PBM image of a bare symbol, one module per pixel, without a
+/// quiet zone, with a comment noting how it was made.
+pub struct Pbm {
+    pub comment: String,
+    pub width: usize,
+    /// The modules row by row, dark being `true`.
+    pub modules: Vec<bool>,
+}
+
+impl Pbm {
+    /// Parses a plain (`P1`) or binary (`P4`) PBM image with one comment line
+    /// after the magic number.
+    pub fn parse(name: &str, pbm: &[u8]) -> Self {
+        let mut lines = pbm.splitn(4, |&b| b == b'\n');
+        let magic = lines.next().unwrap();
+        let comment = std::str::from_utf8(lines.next().unwrap()).unwrap();
+        let comment = comment.strip_prefix("# ").unwrap_or_else(|| panic!("{}: no comment", name)).to_owned();
+        let size = std::str::from_utf8(lines.next().unwrap()).unwrap();
+        let size: Vec<usize> = size.split(' ').map(|n| n.parse().unwrap()).collect();
+        assert_eq!(size[0], size[1], "{}: not square", name);
+        let width = size[0];
+        let raster = lines.next().unwrap_or_default();
+        let modules: Vec<bool> = match magic {
+            b"P1" => raster.iter().filter(|b| !b.is_ascii_whitespace()).map(|&b| b == b'1').collect(),
+            b"P4" => {
+                let row_len = width.div_ceil(8);
+                assert_eq!(raster.len(), row_len * width, "{}: truncated", name);
+                let modules = (0..width * width).map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    raster[y * row_len + x / 8] & (0x80 >> (x % 8)) != 0
+                });
+                modules.collect()
+            }
+            _ => panic!("{}: not a PBM image", name),
+        };
+        assert_eq!(modules.len(), width * width, "{}: truncated", name);
+        Self { comment, width, modules }
+    }
+
+    /// The image as a plain PBM, one row of modules per line.
+    pub fn to_plain(&self) -> Vec<u8> {
+        let mut pbm = format!("P1\n# {}\n{} {}\n", self.comment, self.width, self.width).into_bytes();
+        for row in self.modules.chunks(self.width) {
+            pbm.extend(row.iter().map(|&dark| if dark { b'1' } else { b'0' }));
+            pbm.push(b'\n');
+        }
+        pbm
+    }
+
+    /// The image as a binary PBM, eight modules per byte.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut pbm = format!("P4\n# {}\n{} {}\n", self.comment, self.width, self.width).into_bytes();
+        for row in self.modules.chunks(self.width) {
+            let mut packed = vec![0; self.width.div_ceil(8)];
+            for (x, _) in row.iter().enumerate().filter(|(_, &dark)| dark) {
+                packed[x / 8] |= 0x80 >> (x % 8);
+            }
+            pbm.extend(packed);
+        }
+        pbm
+    }
+}
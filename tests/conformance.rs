@@ -0,0 +1,118 @@
+//! Compares the generated symbols, module for module, against golden symbols
+//! made by another encoder.
+//!
+//! The fixtures in `tests/conformance` were generated with Nayuki's
+//! [qrcodegen](https://www.nayuki.io/page/qr-code-generator-library) 1.8.0,
+//! given exactly the same segments, version and error correction level, and
+//! left to choose the mask itself. They are plain PBM images of the bare
+//! symbol, one module per pixel, without a quiet zone, with the mask noted in
+//! a comment. To regenerate them, run
+//!
+//!     cargo test --test conformance -- --ignored
+//!
+//! Our symbols are compared under the mask of the fixture. Which mask is best
+//! is a heuristic that encoders weigh differently (qrcodegen, for one, treats
+//! the quiet zone as part of finder-like patterns), so the choice of mask is
+//! not part of the corpus, but everything else in the symbol is.
+
+mod common;
+
+use std::fs;
+
+use qrcode::canvas::MaskPattern;
+use qrcode::spec::*;
+
+use crate::common::{fixture_path, reference, Pbm, Segment, Spec};
+
+struct Case {
+    name: &'static str,
+    segments: &'static [Segment<'static>],
+    spec: Spec,
+}
+
+macro_rules! corpus {
+    ($($name:ident: $spec:ty => [$($segment:expr),+ $(,)?];)+) => {
+        const CORPUS: &[Case] = &[$(
+            Case { name: stringify!($name), segments: &[$($segment),+], spec: Spec::of::<$spec>() },
+        )+];
+    };
+}
+
+const LOREM: &[u8] = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor \
+    incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation \
+    ullamco laboris nisi ut aliquip ex ea commodo consequat.";
+
+corpus! {
+    numeric_v1_m: Version1<EcLevelM> => [Segment::Numeric("01234567")];
+    alphanumeric_v1_q: Version1<EcLevelQ> => [Segment::Alphanumeric("HELLO WORLD")];
+    byte_v1_l: Version1<EcLevelL> => [Segment::Byte(b"Hello, world!")];
+    byte_v2_h: Version2<EcLevelH> => [Segment::Byte(b"qrcode-rust")];
+    mixed_v3_m: Version3<EcLevelM> => [
+        Segment::Numeric("0123456789012345"),
+        Segment::Alphanumeric("ABC-DEF $%*+./:"),
+        Segment::Byte(b"xyz"),
+    ];
+    eci_utf8_v2_l: Version2<EcLevelL> => [Segment::Eci(26), Segment::Byte("Grüße, 世界".as_bytes())];
+    eci_two_bytes_v4_q: Version4<EcLevelQ> => [Segment::Eci(899), Segment::Byte(b"\x01\x02\xfe\xff")];
+    eci_three_bytes_v6_h: Version6<EcLevelH> => [
+        Segment::Eci(123_456),
+        Segment::Alphanumeric("THREE BYTE DESIGNATOR"),
+    ];
+    numeric_v7_l: Version7<EcLevelL> => [Segment::Numeric(
+        "31415926535897932384626433832795028841971693993751058209749445923078164062862089986280",
+    )];
+    byte_v10_l: Version10<EcLevelL> => [Segment::Byte(LOREM)];
+    alphanumeric_v14_q: Version14<EcLevelQ> => [Segment::Alphanumeric(
+        "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG 0123456789 $%*+-./:",
+    )];
+    mixed_v20_m: Version20<EcLevelM> => [
+        Segment::Byte(LOREM),
+        Segment::Numeric("271828182845904523536028747135266249775724709369995"),
+        Segment::Eci(3),
+        Segment::Byte(b"caf\xe9"),
+    ];
+    byte_v27_h: Version27<EcLevelH> => [Segment::Byte(LOREM), Segment::Byte(LOREM)];
+    numeric_v40_h: Version40<EcLevelH> => [Segment::Numeric("0")];
+    byte_v40_l: Version40<EcLevelL> => [Segment::Byte(LOREM), Segment::Byte(LOREM), Segment::Byte(LOREM)];
+}
+
+/// The mask of a fixture, noted in its comment.
+fn mask(name: &str, pbm: &Pbm) -> MaskPattern {
+    pbm.comment
+        .rsplit("mask ")
+        .next()
+        .and_then(|mask| mask.parse().ok())
+        .and_then(MaskPattern::from_bits)
+        .unwrap_or_else(|| panic!("{}: no mask", name))
+}
+
+#[test]
+fn test_matches_reference() {
+    for case in CORPUS {
+        let pbm = Pbm::parse(case.name, &fs::read(fixture_path("conformance", case.name)).unwrap());
+        let ours = (case.spec.encode)(case.segments, mask(case.name, &pbm));
+        assert_eq!(pbm.width, case.spec.width(), "{}: wrong version", case.name);
+        let width = pbm.width;
+        let differences: Vec<(usize, usize)> =
+            (0..pbm.modules.len()).filter(|&i| pbm.modules[i] != ours[i]).map(|i| (i % width, i / width)).collect();
+        assert!(
+            differences.is_empty(),
+            "{}: {} modules differ, first at {:?}",
+            case.name,
+            differences.len(),
+            differences[0]
+        );
+    }
+}
+
+#[test]
+#[ignore]
+fn regenerate_fixtures() {
+    for case in CORPUS {
+        let (mask, modules) = reference(case.spec, case.segments, None);
+        let comment =
+            format!("qrcodegen 1.8.0, {:?}, EC level {:?}, mask {}", case.spec.version, case.spec.ec_level, mask as u8);
+        let pbm = Pbm { comment, width: case.spec.width(), modules };
+        fs::write(fixture_path("conformance", case.name), pbm.to_plain()).unwrap();
+    }
+}
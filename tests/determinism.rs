@@ -0,0 +1,54 @@
+//! Checks that the same input always gives the same symbol, against digests
+//! pinned when the encoding choices were fixed. See the "Determinism" section
+//! of the crate documentation for what is pinned.
+//!
+//! A failure here means that the symbols differ from those of earlier
+//! releases, which breaks reproducible builds relying on them. If the change
+//! is intended, it is a breaking change, and the digests printed by the
+//! failing test replace those below.
+
+use qrcode::spec::*;
+use qrcode::{Encoder, QrCode, QrStorage};
+
+/// The 64-bit FNV-1a hash of the bytes, stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Encodes the data in all three ways, checks that they agree, and returns
+/// the digest of the symbol in the binary format.
+fn digest<V: QrSpec>(data: &[u8]) -> u64 {
+    let code = QrCode::<V>::new(data).unwrap();
+    let mut storage = QrStorage::<V>::new();
+    assert!(*QrCode::new_in(data, &mut storage).unwrap() == code);
+    let mut encoder = Encoder::<V>::new();
+    encoder.encode(b"something else first").ok();
+    assert!(*encoder.encode(data).unwrap() == code);
+
+    let mut bytes = vec![0; QrCode::<V>::binary_len()];
+    code.to_bytes(&mut bytes).unwrap();
+    fnv1a(&bytes)
+}
+
+#[test]
+fn test_pinned_symbols() {
+    let long: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+    let cases: [(&str, u64, u64); 10] = [
+        ("v1-l numeric", digest::<Version1<EcLevelL>>(b"01234567"), 0x61b2_3783_05f9_4903),
+        ("v1-m numeric", digest::<Version1<EcLevelM>>(b"01234567"), 0xc0d7_ba71_551b_09df),
+        ("v1-h empty", digest::<Version1<EcLevelH>>(b""), 0xec79_551d_c44a_092d),
+        ("v2-q alphanumeric", digest::<Version2<EcLevelQ>>(b"HELLO WORLD 123"), 0xc602_172a_8db8_0107),
+        ("v3-m mixed", digest::<Version3<EcLevelM>>(b"ABC-0123456789-abc"), 0x5e05_d4da_e43b_2a7a),
+        ("v5-l url", digest::<Version5<EcLevelL>>(b"https://example.com/path?query=1#frag"), 0x01c2_a60b_2688_8d40),
+        ("v7-h utf-8", digest::<Version7<EcLevelH>>("Grüße, 世界!".as_bytes()), 0x9e00_a698_7590_5f34),
+        ("v10-q shift-jis", digest::<Version10<EcLevelQ>>(b"\x93\x5f\xe4\xaa\x82\xa0 ABC 123"), 0x37d5_38cd_80be_8138),
+        ("v25-m binary", digest::<Version25<EcLevelM>>(&long[..500]), 0x6171_ecaa_c405_78f5),
+        ("v40-l binary", digest::<Version40<EcLevelL>>(&long), 0x2204_f550_e030_cee5),
+    ];
+
+    let mismatches: Vec<_> = cases.iter().filter(|(_, actual, pinned)| actual != pinned).collect();
+    for (name, actual, pinned) in &mismatches {
+        println!("{}: {:#018x}, pinned {:#018x}", name, actual, pinned);
+    }
+    assert!(mismatches.is_empty(), "{} symbols differ from the pinned ones", mismatches.len());
+}
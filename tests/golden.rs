@@ -0,0 +1,109 @@
+//! Compares a symbol of every version and error correction level, module for
+//! module, against golden symbols made by another encoder, protecting the
+//! spec tables and the module placement from silent regressions.
+//!
+//! Unlike the hand-picked cases of `tests/conformance.rs`, every symbol here
+//! holds the same kind of payload: a byte segment filling about half of the
+//! data capacity, followed by the terminator and pad codewords. The mask is
+//! fixed, cycling through all eight patterns, so that the choice of mask is
+//! left out and every symbol is compared in full.
+//!
+//! The fixtures in `tests/golden` were generated with Nayuki's
+//! [qrcodegen](https://www.nayuki.io/page/qr-code-generator-library) 1.8.0.
+//! They are binary PBM images of the bare symbol, one module per pixel,
+//! without a quiet zone. To regenerate them, run
+//!
+//!     cargo test --test golden -- --ignored
+
+mod common;
+
+use std::fs;
+
+use qrcode::canvas::MaskPattern;
+use qrcode::spec::*;
+use qrcode::types::{Mode, Version};
+
+use crate::common::{fixture_path, reference, Pbm, Segment, Spec};
+
+struct Case(Spec);
+
+impl Case {
+    fn name(&self) -> String {
+        let v = match self.0.version {
+            Version::Normal(v) => v,
+            Version::Micro(_) => unreachable!(),
+        };
+        format!("v{}_{}", v, format!("{:?}", self.0.ec_level).to_lowercase())
+    }
+
+    /// The payload, about half of the byte mode capacity of the symbol.
+    fn payload(&self) -> Vec<u8> {
+        let Spec { version, ec_level, .. } = self.0;
+        let layout = BlockLayout::fetch(version, ec_level).unwrap();
+        let header_bits = version.mode_bits_count() + Mode::Byte.length_bits_count(version);
+        let len = (layout.data_len() * 8 - header_bits) / 8 / 2;
+        let seed = version.width() as usize * 4 + ec_level as usize;
+        (0..len).map(|i| (i * 37 + seed * 11) as u8).collect()
+    }
+
+    /// The mask, cycling through all patterns over the versions and error
+    /// correction levels.
+    fn mask(&self) -> MaskPattern {
+        let seed = self.0.version.width() as usize / 4 + self.0.ec_level as usize;
+        MaskPattern::from_bits(seed as u8 % 8).unwrap()
+    }
+}
+
+macro_rules! golden {
+    ($($spec:ident),+ $(,)?) => {
+        const GOLDEN: &[Case] = &[$(
+            Case(Spec::of::<$spec<EcLevelL>>()),
+            Case(Spec::of::<$spec<EcLevelM>>()),
+            Case(Spec::of::<$spec<EcLevelQ>>()),
+            Case(Spec::of::<$spec<EcLevelH>>()),
+        )+];
+    };
+}
+
+golden! {
+    Version1, Version2, Version3, Version4, Version5, Version6, Version7, Version8, Version9, Version10,
+    Version11, Version12, Version13, Version14, Version15, Version16, Version17, Version18, Version19, Version20,
+    Version21, Version22, Version23, Version24, Version25, Version26, Version27, Version28, Version29, Version30,
+    Version31, Version32, Version33, Version34, Version35, Version36, Version37, Version38, Version39, Version40,
+}
+
+#[test]
+fn test_matches_golden() {
+    assert_eq!(GOLDEN.len(), 160);
+    let mut failures = Vec::new();
+    for case in GOLDEN {
+        let name = case.name();
+        let pbm = Pbm::parse(&name, &fs::read(fixture_path("golden", &name)).unwrap());
+        assert_eq!(pbm.width, case.0.width(), "{}: wrong version", name);
+        let ours = (case.0.encode)(&[Segment::Byte(&case.payload())], case.mask());
+        let differences = (0..pbm.modules.len()).filter(|&i| pbm.modules[i] != ours[i]).count();
+        if differences > 0 {
+            failures.push(format!("{}: {} modules differ", name, differences));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore]
+fn regenerate_fixtures() {
+    fs::create_dir_all(fixture_path("golden", "").parent().unwrap()).unwrap();
+    for case in GOLDEN {
+        let payload = case.payload();
+        let (mask, modules) = reference(case.0, &[Segment::Byte(&payload)], Some(case.mask()));
+        let comment = format!(
+            "qrcodegen 1.8.0, {:?}, EC level {:?}, mask {}, {} bytes",
+            case.0.version,
+            case.0.ec_level,
+            mask as u8,
+            payload.len()
+        );
+        let pbm = Pbm { comment, width: case.0.width(), modules };
+        fs::write(fixture_path("golden", &case.name()), pbm.to_binary()).unwrap();
+    }
+}